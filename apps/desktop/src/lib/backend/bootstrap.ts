@@ -0,0 +1,12 @@
+import { invoke } from '$lib/backend/ipc';
+
+// Everything shown when opening a project, loaded with a single command instead of one per part.
+// The parts are plain payloads, to be turned into instances by the services using them.
+export interface ProjectBootstrap {
+	virtualBranches: any | null;
+	baseBranch: any | null;
+}
+
+export async function loadProjectBootstrap(projectId: string): Promise<ProjectBootstrap> {
+	return await invoke<ProjectBootstrap>('project_bootstrap', { projectId });
+}
@@ -36,4 +36,7 @@ export class GbConfig {
 	signingFormat?: string | undefined;
 	gpgProgram?: string | undefined;
 	gpgSshProgram?: string | undefined;
+	mirrorAttachmentsToNotes?: boolean | undefined;
+	signWorkspaceCommits?: boolean | undefined;
+	workspaceMergeChain?: boolean | undefined;
 }
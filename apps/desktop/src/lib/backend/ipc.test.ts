@@ -0,0 +1,80 @@
+import { LATE_CHUNKS_TIMEOUT_MS, invokeChunked } from './ipc';
+import { invoke as invokeTauri } from '@tauri-apps/api/core';
+import { afterEach, beforeEach, describe, expect, test, vi } from 'vitest';
+
+vi.mock('@tauri-apps/api/core', () => ({
+	Channel: class {
+		onmessage: (message: unknown) => void = () => {};
+	},
+	invoke: vi.fn()
+}));
+
+type Chunk = { type: 'items'; subject: number[] } | { type: 'done'; subject: { total: number } };
+
+/**
+ * Let the invoked command send `chunks` over its channel before returning.
+ */
+function sendChunks(...chunks: Chunk[]) {
+	vi.mocked(invokeTauri).mockImplementation(async (_command, params: any) => {
+		for (const chunk of chunks) params.channel.onmessage(chunk);
+	});
+}
+
+describe('invokeChunked', () => {
+	beforeEach(() => {
+		vi.useFakeTimers();
+		vi.spyOn(console, 'error').mockImplementation(() => {});
+	});
+
+	afterEach(() => {
+		vi.restoreAllMocks();
+		vi.clearAllTimers();
+	});
+
+	test('should transform each chunk as it arrives', async () => {
+		sendChunks(
+			{ type: 'items', subject: [1, 2] },
+			{ type: 'items', subject: [3] },
+			{ type: 'done', subject: { total: 3 } }
+		);
+		const transform = vi.fn((items: number[]) => items.map((item) => `${item}`));
+
+		expect(await invokeChunked('list', {}, transform)).toEqual(['1', '2', '3']);
+		expect(transform.mock.calls).toEqual([[[1, 2]], [[3]]]);
+	});
+
+	test('should reject if the command fails', async () => {
+		vi.mocked(invokeTauri).mockRejectedValue({ message: 'no such project' });
+
+		await expect(invokeChunked('list')).rejects.toThrow('No such project');
+	});
+
+	test('should reject if items are missing', async () => {
+		sendChunks({ type: 'items', subject: [1] }, { type: 'done', subject: { total: 2 } });
+
+		await expect(invokeChunked('list')).rejects.toThrow('list sent 1 of 2 items');
+	});
+
+	test('should reject if the command returns without finishing', async () => {
+		sendChunks({ type: 'items', subject: [1] });
+
+		const result = expect(invokeChunked('list')).rejects.toThrow(
+			'list returned without sending all items'
+		);
+		await vi.advanceTimersByTimeAsync(LATE_CHUNKS_TIMEOUT_MS);
+		await result;
+	});
+
+	test('should wait for chunks arriving after the command returned', async () => {
+		let channel: any;
+		vi.mocked(invokeTauri).mockImplementation(async (_command, params: any) => {
+			channel = params.channel;
+		});
+
+		const result = invokeChunked('list');
+		await vi.advanceTimersByTimeAsync(LATE_CHUNKS_TIMEOUT_MS / 2);
+		channel.onmessage({ type: 'items', subject: [1] });
+		channel.onmessage({ type: 'done', subject: { total: 1 } });
+		expect(await result).toEqual([1]);
+	});
+});
@@ -1,4 +1,4 @@
-import { invoke as invokeTauri } from '@tauri-apps/api/core';
+import { Channel, invoke as invokeTauri } from '@tauri-apps/api/core';
 import { listen as listenTauri } from '@tauri-apps/api/event';
 import type { EventCallback, EventName } from '@tauri-apps/api/event';
 
@@ -72,6 +72,68 @@ export async function invoke<T>(command: string, params: Record<string, unknown>
 	}
 }
 
+type Chunk<T> = { type: 'items'; subject: T[] } | { type: 'done'; subject: { total: number } };
+
+/**
+ * How long chunks may still arrive after their command returned, as they are delivered separately.
+ */
+export const LATE_CHUNKS_TIMEOUT_MS = 5000;
+
+/**
+ * Invoke a command which sends its result over a channel in chunks, so large results don't have
+ * to be parsed at once. Each chunk is passed through `transform` as it arrives, and the transformed
+ * items are resolved with once the last chunk arrived.
+ *
+ * Rejects if the command fails, or if it returns without all of its chunks arriving.
+ */
+export async function invokeChunked<T, U = T>(
+	command: string,
+	params: Record<string, unknown> = {},
+	transform: (items: T[]) => U[] = (items) => items as unknown as U[]
+): Promise<U[]> {
+	const channel = new Channel<Chunk<T>>();
+	return await new Promise<U[]>((resolve, reject) => {
+		const items: U[] = [];
+		let received = 0;
+		let settled = false;
+		let timeout: ReturnType<typeof setTimeout> | undefined;
+
+		function settle(outcome: () => void) {
+			if (settled) return;
+			settled = true;
+			clearTimeout(timeout);
+			outcome();
+		}
+		const fail = (error: unknown) => settle(() => reject(error));
+
+		channel.onmessage = (chunk) => {
+			if (settled) return;
+			try {
+				if (chunk.type === 'items') {
+					received += chunk.subject.length;
+					items.push(...transform(chunk.subject));
+				} else if (received === chunk.subject.total) {
+					settle(() => resolve(items));
+				} else {
+					fail(new Error(`${command} sent ${received} of ${chunk.subject.total} items`));
+				}
+			} catch (error: unknown) {
+				fail(error);
+			}
+		};
+
+		invoke<void>(command, { ...params, channel }).then(
+			() => {
+				timeout = setTimeout(
+					() => fail(new Error(`${command} returned without sending all items`)),
+					LATE_CHUNKS_TIMEOUT_MS
+				);
+			},
+			fail
+		);
+	});
+}
+
 export function listen<T>(event: EventName, handle: EventCallback<T>) {
 	const unlisten = listenTauri(event, handle);
 	return async () => await unlisten.then((unlistenFn) => unlistenFn());
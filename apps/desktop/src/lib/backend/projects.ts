@@ -28,6 +28,8 @@ export class Project {
 	use_diff_context: boolean | undefined;
 	snapshot_lines_threshold!: number | undefined;
 	use_experimental_locking!: boolean;
+	skip_commit_hooks!: boolean;
+	auto_resolve_whitespace!: boolean;
 	// Produced just for the frontend to determine if the project is open in any window.
 	is_open!: boolean;
 
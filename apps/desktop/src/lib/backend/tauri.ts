@@ -1,10 +1,12 @@
 import { invoke as invokeIpc, listen as listenIpc } from './ipc';
 import { getVersion } from '@tauri-apps/api/app';
-import { check } from '@tauri-apps/plugin-updater';
+import type { UpdateCheck } from './updater';
 
 export class Tauri {
 	invoke = invokeIpc;
 	listen = listenIpc;
-	checkUpdate = check;
+	checkUpdate = async (manual: boolean) =>
+		await invokeIpc<UpdateCheck>('check_for_update', { manual });
+	installUpdate = async () => await invokeIpc<string | null>('install_update');
 	currentVersion = getVersion;
 }
@@ -1,8 +1,7 @@
 import { Tauri } from './tauri';
-import { UPDATE_INTERVAL_MS, UpdaterService } from './updater';
+import { UPDATE_INTERVAL_MS, UpdaterService, type UpdateCheck } from './updater';
 import { get } from 'svelte/store';
 import { expect, test, describe, vi, beforeEach, afterEach } from 'vitest';
-import type { Update } from '@tauri-apps/plugin-updater';
 
 /**
  * It is important to understand the sync `get` method performs a store subscription
@@ -99,6 +98,44 @@ describe('Updater', () => {
 		expect(update2).toMatchObject({});
 	});
 
+	test('should pass on whether the check is manual', async () => {
+		const mock = vi.spyOn(tauri, 'checkUpdate').mockReturnValue(
+			mockUpdate({
+				available: false
+			})
+		);
+		await updater.checkForUpdate();
+		expect(mock).toHaveBeenLastCalledWith(false);
+		await updater.checkForUpdate(true);
+		expect(mock).toHaveBeenLastCalledWith(true);
+		expect(get(updater.channel)).toBe('stable');
+	});
+
+	test('should report install progress from the backend', async () => {
+		vi.spyOn(tauri, 'checkUpdate').mockReturnValue(mockUpdate({ available: false }));
+		let report: ((status: string) => void) | undefined;
+		const unlisten = vi.fn();
+		vi.spyOn(tauri, 'listen').mockImplementation((event, handle) => {
+			if (event === 'updater://progress') {
+				report = (status) => handle({ payload: status } as any);
+			}
+			return async () => unlisten();
+		});
+		const statuses: (string | undefined)[] = [];
+		vi.spyOn(tauri, 'installUpdate').mockImplementation(async () => {
+			report?.('Downloaded');
+			statuses.push(get(updater.update).status);
+			report?.('Installing');
+			statuses.push(get(updater.update).status);
+			return '2';
+		});
+
+		await updater.downloadAndInstall();
+		expect(statuses).toEqual(['Downloaded', 'Installing']);
+		expect(get(updater.update)).toHaveProperty('status', 'Done');
+		expect(unlisten).toHaveBeenCalledOnce();
+	});
+
 	test('should check for updates continously', async () => {
 		const mock = vi.spyOn(tauri, 'checkUpdate').mockReturnValue(
 			mockUpdate({
@@ -117,10 +154,16 @@ describe('Updater', () => {
 	});
 });
 
-async function mockUpdate(update: Partial<Update>): Promise<Update> {
+async function mockUpdate(update: {
+	available?: boolean;
+	version?: string;
+	body?: string;
+}): Promise<UpdateCheck> {
 	return await Promise.resolve({
-		download: () => {},
-		install: () => {},
-		...update
-	} as Update);
+		channel: 'stable',
+		currentVersion: '0.5.0',
+		available: update.available
+			? { version: update.version ?? '', date: null, changelog: update.body ?? null }
+			: null
+	});
 }
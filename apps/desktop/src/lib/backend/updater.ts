@@ -1,10 +1,21 @@
 import { Tauri } from './tauri';
 import { showToast } from '$lib/notifications/toasts';
 import { relaunch } from '@tauri-apps/plugin-process';
-import { type DownloadEvent, Update } from '@tauri-apps/plugin-updater';
 import posthog from 'posthog-js';
 import { writable } from 'svelte/store';
 
+export type UpdateChannel = 'stable' | 'nightly';
+
+/**
+ * The result of checking for an update on the channel selected by the user, or the channel of
+ * the build if there is no selection.
+ */
+export type UpdateCheck = {
+	channel: UpdateChannel;
+	currentVersion: string;
+	available: { version: string; date: string | null; changelog: string | null } | null;
+};
+
 type UpdateStatus = {
 	version?: string;
 	releaseNotes?: string;
@@ -20,12 +31,6 @@ export type InstallStatus =
 	| 'Up-to-date'
 	| 'Error';
 
-const downloadStatusMap: { [K in DownloadEvent['event']]: InstallStatus } = {
-	Started: 'Downloading',
-	Progress: 'Downloading',
-	Finished: 'Downloaded'
-};
-
 export const UPDATE_INTERVAL_MS = 3600000; // Hourly
 
 /**
@@ -38,6 +43,7 @@ export const UPDATE_INTERVAL_MS = 3600000; // Hourly
  */
 export class UpdaterService {
 	readonly loading = writable(false);
+	readonly channel = writable<UpdateChannel | undefined>();
 	readonly update = writable<UpdateStatus>({}, () => {
 		this.start();
 		return () => {
@@ -47,8 +53,6 @@ export class UpdaterService {
 
 	private intervalId: any;
 	private seenVersion: string | undefined;
-	private tauriDownload: Update['download'] | undefined;
-	private tauriInstall: Update['install'] | undefined;
 
 	unlistenStatus?: () => void;
 	unlistenMenu?: () => void;
@@ -75,7 +79,9 @@ export class UpdaterService {
 	async checkForUpdate(manual = false) {
 		this.loading.set(true);
 		try {
-			this.handleUpdate(await this.tauri.checkUpdate(), manual); // In DEV mode this never returns.
+			const check = await this.tauri.checkUpdate(manual); // In DEV mode this never returns.
+			this.channel.set(check.channel);
+			this.handleUpdate(check, manual);
 		} catch (err: unknown) {
 			handleError(err, manual);
 		} finally {
@@ -83,25 +89,17 @@ export class UpdaterService {
 		}
 	}
 
-	private handleUpdate(update: Update | null, manual: boolean) {
-		if (update === null) {
-			this.update.set({});
-			return;
-		}
-		if (!update.available && manual) {
-			this.setStatus('Up-to-date');
+	private handleUpdate({ available, currentVersion }: UpdateCheck, manual: boolean) {
+		if (!available) {
+			if (manual) this.setStatus('Up-to-date');
 		} else if (
-			update.available &&
-			update.version !== this.seenVersion &&
-			update.currentVersion !== '0.0.0' // DEV mode.
+			available.version !== this.seenVersion &&
+			currentVersion !== '0.0.0' // DEV mode.
 		) {
-			const { version, body, download, install } = update;
-			this.tauriDownload = download.bind(update);
-			this.tauriInstall = install.bind(update);
-			this.seenVersion = version;
+			this.seenVersion = available.version;
 			this.update.set({
-				version,
-				releaseNotes: body,
+				version: available.version,
+				releaseNotes: available.changelog ?? undefined,
 				status: undefined
 			});
 		}
@@ -109,40 +107,24 @@ export class UpdaterService {
 
 	async downloadAndInstall() {
 		this.loading.set(true);
+		const unlistenProgress = this.tauri.listen<InstallStatus>('updater://progress', (event) => {
+			this.setStatus(event.payload);
+		});
 		try {
-			await this.download();
-			await this.install();
+			this.setStatus('Downloading');
+			const installed = await this.tauri.installUpdate();
+			this.setStatus(installed ? 'Done' : 'Up-to-date');
 			posthog.capture('App Update Successful');
 		} catch (error: any) {
-			// We expect toast to be shown by error handling in `onUpdaterEvent`
 			handleError(error, true);
 			this.update.set({ status: 'Error' });
 			posthog.capture('App Update Install Error', { error });
 		} finally {
+			unlistenProgress();
 			this.loading.set(false);
 		}
 	}
 
-	private async download() {
-		if (!this.tauriDownload) {
-			throw new Error('Download function not available.');
-		}
-		this.setStatus('Downloading');
-		await this.tauriDownload((progress: DownloadEvent) => {
-			this.setStatus(downloadStatusMap[progress.event]);
-		});
-		this.setStatus('Downloaded');
-	}
-
-	private async install() {
-		if (!this.tauriInstall) {
-			throw new Error('Install function not available.');
-		}
-		this.setStatus('Installing');
-		await this.tauriInstall();
-		this.setStatus('Done');
-	}
-
 	private setStatus(status: InstallStatus) {
 		this.update.update((update) => {
 			return { ...update, status };
@@ -163,9 +145,10 @@ export class UpdaterService {
 }
 
 function isOffline(err: any): boolean {
+	const message = err instanceof Error ? err.message : err;
 	return (
-		typeof err === 'string' &&
-		(err.includes('Could not fetch a valid release') || err.includes('Network Error'))
+		typeof message === 'string' &&
+		(message.includes('Could not fetch a valid release') || message.includes('Network Error'))
 	);
 }
 
@@ -3,6 +3,7 @@ import { Code, invoke } from '$lib/backend/ipc';
 import { showError } from '$lib/notifications/toasts';
 import { plainToInstance } from 'class-transformer';
 import { writable } from 'svelte/store';
+import type { ProjectBootstrap } from '$lib/backend/bootstrap';
 
 export interface RemoteBranchInfo {
 	name: string;
@@ -15,14 +16,22 @@ export class BaseBranchService {
 	readonly loading = writable(false);
 	readonly error = writable();
 
-	constructor(private readonly projectId: string) {}
+	constructor(
+		private readonly projectId: string,
+		// Used instead of asking for the base branch on its own when it's first needed.
+		private bootstrap?: Promise<ProjectBootstrap>
+	) {}
 
 	async refresh(): Promise<void> {
 		this.loading.set(true);
+		const bootstrap = this.bootstrap;
+		this.bootstrap = undefined;
 		try {
+			const load = async () =>
+				await invoke<any>('get_base_branch_data', { projectId: this.projectId });
 			const baseBranch = plainToInstance(
 				BaseBranch,
-				await invoke<any>('get_base_branch_data', { projectId: this.projectId })
+				bootstrap ? await bootstrap.then((b) => b.baseBranch, load) : await load()
 			);
 			if (!baseBranch) this.error.set(new NoDefaultTarget());
 			this.base.set(baseBranch);
@@ -1,7 +1,7 @@
 // Class transformers will bust a gut if this isn't imported first
 import 'reflect-metadata';
 
-import { Code, invoke } from '$lib/backend/ipc';
+import { Code, invoke, invokeChunked } from '$lib/backend/ipc';
 import {
 	getEntryName,
 	getEntryUpdatedDate,
@@ -34,8 +34,11 @@ export class BranchListingService {
 
 	private async list(filter: BranchListingFilter | undefined = undefined) {
 		try {
-			const entries = await invoke<any[]>('list_branches', { projectId: this.projectId, filter });
-			return plainToInstance(BranchListing, entries);
+			return await invokeChunked<any, BranchListing>(
+				'list_branches_chunked',
+				{ projectId: this.projectId, filter },
+				(entries) => plainToInstance(BranchListing, entries)
+			);
 		} catch (error: any) {
 			if (error.code === Code.DefaultTargetNotFound) {
 				// Swallow this error since user should be taken to project setup page
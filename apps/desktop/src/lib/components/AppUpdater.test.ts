@@ -1,9 +1,8 @@
 import AppUpdater from './AppUpdater.svelte';
 import { Tauri } from '$lib/backend/tauri';
-import { UpdaterService } from '$lib/backend/updater';
+import { UpdaterService, type UpdateCheck } from '$lib/backend/updater';
 import { render, screen } from '@testing-library/svelte';
 import { expect, test, describe, vi, beforeEach, afterEach } from 'vitest';
-import type { Update } from '@tauri-apps/plugin-updater';
 
 describe('AppUpdater', () => {
 	let tauri: Tauri;
@@ -75,6 +74,7 @@ describe('AppUpdater', () => {
 				body: 'release notes'
 			})
 		);
+		vi.spyOn(tauri, 'installUpdate').mockResolvedValue('2');
 
 		render(AppUpdater, { context });
 		await updater.checkForUpdate(true);
@@ -87,10 +87,16 @@ describe('AppUpdater', () => {
 	});
 });
 
-async function mockUpdate(update: Partial<Update>): Promise<Update> {
+async function mockUpdate(update: {
+	available?: boolean;
+	version?: string;
+	body?: string;
+}): Promise<UpdateCheck> {
 	return await Promise.resolve({
-		download: () => {},
-		install: () => {},
-		...update
-	} as Update);
+		channel: 'stable',
+		currentVersion: '0.5.0',
+		available: update.available
+			? { version: update.version ?? '', date: null, changelog: update.body ?? null }
+			: null
+	});
 }
@@ -6,8 +6,15 @@
  */
 
 import { Store } from '@tauri-apps/plugin-store';
+import type { UpdateChannel } from '$lib/backend/updater';
 import { writable, type Writable } from 'svelte/store';
 
+/** Whether the backend shows clock times with a 12-hour or a 24-hour clock. */
+export type TimeFormat = '12h' | '24h';
+
+/** Whether the backend shows sizes in powers of 1024 or of 1000. */
+export type SizeUnits = 'binary' | 'decimal';
+
 export async function loadAppSettings() {
 	const diskStore = await Store.load('settings.json', { autoSave: true });
 	return new AppSettings(diskStore);
@@ -42,6 +49,28 @@ export class AppSettings {
 	 */
 	readonly appNonAnonMetricsEnabled = this.persisted(false, 'appNonAnonMetricsEnabled');
 
+	/**
+	 * Whether the backend validates hunk ranges after each change, so reports of wrongly locked
+	 * hunks include the changes that led to it. Takes effect after a restart.
+	 */
+	readonly validateHunkRanges = this.persisted(false, 'validateHunkRanges');
+
+	/**
+	 * The channel the app is updated from. If unset, the backend follows the channel of the
+	 * running build.
+	 */
+	readonly updateChannel = this.persisted<UpdateChannel | undefined>(undefined, 'updateChannel');
+
+	/**
+	 * The BCP 47 language tag like `de-CH` the backend formats numbers for. If unset, the
+	 * conventions of English are used.
+	 */
+	readonly locale = this.persisted<string | undefined>(undefined, 'locale');
+
+	readonly timeFormat = this.persisted<TimeFormat>('24h', 'timeFormat');
+
+	readonly sizeUnits = this.persisted<SizeUnits>('binary', 'sizeUnits');
+
 	private persisted<T>(initial: T, key: string): Writable<T> & { onDisk: () => Promise<T> } {
 		const diskStore = this.diskStore;
 		const storeValueWithDefault = this.storeValueWithDefault.bind(this);
@@ -53,7 +82,7 @@ export class AppSettings {
 		const subscribe = keySpecificStore.subscribe;
 
 		async function setAndPersist(value: T, set: (value: T) => void) {
-			diskStore?.set(key, value);
+			await diskStore?.set(key, value);
 			set(value);
 		}
 
@@ -63,7 +92,7 @@ export class AppSettings {
 		}
 
 		async function set(value: T) {
-			setAndPersist(value, keySpecificStore.set);
+			await setAndPersist(value, keySpecificStore.set);
 		}
 
 		async function onDisk() {
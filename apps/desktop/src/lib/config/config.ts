@@ -31,11 +31,6 @@ export function projectAiGenEnabled(projectId: string): Persisted<boolean> {
 	return persisted(false, key + projectId);
 }
 
-export function projectRunCommitHooks(projectId: string): Persisted<boolean> {
-	const key = 'projectRunCommitHooks_';
-	return persisted(false, key + projectId);
-}
-
 export function projectLaneCollapsed(projectId: string, laneId: string): Persisted<boolean> {
 	const key = 'projectLaneCollapsed_';
 	return persisted(false, key + projectId + '_' + laneId);
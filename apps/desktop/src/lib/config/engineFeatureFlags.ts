@@ -0,0 +1,48 @@
+/**
+ * Feature flags of the backend, which turn risky changes to the engine on or off per project.
+ *
+ * Unlike the flags in `uiFeatureFlags.ts`, these are stored by the backend, which consults them.
+ *
+ * @module engineFeatureFlags
+ */
+import { invoke } from '$lib/backend/ipc';
+import type { HttpClient } from '@gitbutler/shared/httpClient';
+
+export type FeatureFlag =
+	| 'experimentalLocking'
+	| 'autoStash'
+	| 'renameTracking'
+	| 'scopeLocking'
+	| 'columnLocking';
+
+export type FlagState = {
+	flag: FeatureFlag;
+	enabled: boolean;
+	source: 'default' | 'remote' | 'project' | 'legacy';
+};
+
+export async function listFeatureFlags(projectId: string): Promise<FlagState[]> {
+	return await invoke<FlagState[]>('list_feature_flags', { projectId });
+}
+
+/** Turn `flag` on or off for the project, or undo that with `undefined`. */
+export async function setFeatureFlag(
+	projectId: string,
+	flag: FeatureFlag,
+	enabled: boolean | undefined
+): Promise<FlagState[]> {
+	return await invoke<FlagState[]>('set_feature_flag', { projectId, flag, enabled });
+}
+
+/**
+ * Hand the remote configuration of the flags to the backend. Without it, the defaults of the
+ * backend apply, so failing to fetch it is fine.
+ */
+export async function loadRemoteFeatureFlags(httpClient: HttpClient) {
+	try {
+		const flags = await httpClient.get<Partial<Record<FeatureFlag, boolean>>>('feature_flags');
+		await invoke('set_remote_feature_flags', { flags });
+	} catch (err: unknown) {
+		console.warn('Failed to load remote feature flags', err);
+	}
+}
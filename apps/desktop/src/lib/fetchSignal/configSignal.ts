@@ -0,0 +1,24 @@
+import { listen } from '$lib/backend/ipc';
+import { readable } from 'svelte/store';
+
+export type ConfigFile = 'gitConfig' | 'excludes' | 'attributes' | 'gitButler';
+
+export class ConfigSignal {
+	// Stores only emit unique values so we use a counter to ensure
+	// derived stores are updated.
+	private counter = 0;
+
+	// Emits a new value when the back end detected that `.git/config` was changed
+	// outside of the app, which is where the remotes are configured.
+	readonly gitConfig = readable<number>(undefined, (set) => {
+		const unsubscribe = listen<{ files: ConfigFile[] }>(
+			`project://${this.projectId}/config`,
+			(event) => {
+				if (event.payload.files.includes('gitConfig')) set(this.counter++);
+			}
+		);
+		return async () => await unsubscribe();
+	});
+
+	constructor(private projectId: string) {}
+}
@@ -0,0 +1,28 @@
+import { listen } from '$lib/backend/ipc';
+import type { RepoInfo } from '$lib/url/gitUrl';
+
+/**
+ * A change at the forge, received as a webhook by the backend and forwarded as a
+ * `forge://webhook` event.
+ */
+export type ForgeEvent =
+	| {
+			type: 'pullRequest';
+			subject: { repository: string; number: number; action: string; merged: boolean };
+	  }
+	| { type: 'review'; subject: { repository: string; number: number; state: string } }
+	| {
+			type: 'checks';
+			subject: { repository: string; sha: string; status: string; conclusion?: string };
+	  }
+	| { type: 'mergeGroup'; subject: { repository: string; action: string; headSha: string } };
+
+/**
+ * Call `handle` for every forge event about `repo`, until the returned function is called.
+ */
+export function listenForForgeEvents(repo: RepoInfo, handle: (event: ForgeEvent) => void) {
+	const fullName = `${repo.owner}/${repo.name}`.toLowerCase();
+	return listen<ForgeEvent>('forge://webhook', (event) => {
+		if (event.payload.subject.repository.toLowerCase() === fullName) handle(event.payload);
+	});
+}
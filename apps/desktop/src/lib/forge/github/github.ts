@@ -3,6 +3,7 @@ import { GitHubChecksMonitor } from './githubChecksMonitor';
 import { GitHubListingService } from './githubListingService';
 import { GitHubPrService } from './githubPrService';
 import { GitHubIssueService } from '$lib/forge/github/issueService';
+import { anonymousOctokit } from '$lib/forge/github/octokit';
 import { Octokit } from '@octokit/rest';
 import type { ProjectMetrics } from '$lib/metrics/projectMetrics';
 import type { RepoInfo } from '$lib/url/gitUrl';
@@ -18,6 +19,8 @@ export class GitHub implements Forge {
 	private baseBranch: string;
 	private forkStr?: string;
 	private octokit?: Octokit;
+	// Reading public repositories doesn't need an access token, only writing does.
+	private readOctokit: Octokit;
 	private projectMetrics?: ProjectMetrics;
 
 	constructor({
@@ -35,14 +38,12 @@ export class GitHub implements Forge {
 		this.baseBranch = baseBranch;
 		this.forkStr = forkStr;
 		this.octokit = octokit;
+		this.readOctokit = octokit ?? anonymousOctokit();
 		this.projectMetrics = projectMetrics;
 	}
 
 	listService() {
-		if (!this.octokit) {
-			return;
-		}
-		return new GitHubListingService(this.octokit, this.repo, this.projectMetrics);
+		return new GitHubListingService(this.readOctokit, this.repo, this.projectMetrics);
 	}
 
 	prService() {
@@ -60,10 +61,7 @@ export class GitHub implements Forge {
 	}
 
 	checksMonitor(sourceBranch: string) {
-		if (!this.octokit) {
-			return;
-		}
-		return new GitHubChecksMonitor(this.octokit, this.repo, sourceBranch);
+		return new GitHubChecksMonitor(this.readOctokit, this.repo, sourceBranch);
 	}
 
 	branch(name: string) {
@@ -8,6 +8,10 @@ import type { ForgeChecksMonitor } from '../interface/forgeChecksMonitor';
 type ChecksResponse = RestEndpointMethodTypes['checks']['listForRef']['response'];
 type CheckRuns = RestEndpointMethodTypes['checks']['listForRef']['response']['data']['check_runs'];
 
+vi.mock('$lib/forge/forgeEvents', () => ({
+	listenForForgeEvents: () => async () => {}
+}));
+
 type SuitesResponse = RestEndpointMethodTypes['checks']['listSuitesForRef']['response'];
 type CheckSuites =
 	RestEndpointMethodTypes['checks']['listSuitesForRef']['response']['data']['check_suites'];
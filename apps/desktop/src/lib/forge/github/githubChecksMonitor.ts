@@ -1,4 +1,5 @@
 import { scurveBackoff } from '$lib/backoff/scurve';
+import { listenForForgeEvents } from '$lib/forge/forgeEvents';
 import { DEFAULT_HEADERS } from '$lib/forge/github/headers';
 import { parseGitHubCheckSuites } from '$lib/forge/github/types';
 import { sleep } from '$lib/utils/sleep';
@@ -26,6 +27,8 @@ export class GitHubChecksMonitor implements ForgeChecksMonitor {
 
 	private timeout: any;
 	private hasCheckSuites: boolean | undefined;
+	private headSha: string | undefined;
+	private unlisten: (() => Promise<void>) | undefined;
 
 	constructor(
 		private octokit: Octokit,
@@ -35,19 +38,30 @@ export class GitHubChecksMonitor implements ForgeChecksMonitor {
 
 	async start() {
 		this.update();
+		// Webhooks tell us about finished checks right away, polling remains the fallback.
+		this.unlisten ??= listenForForgeEvents(this.repo, (event) => {
+			if (event.type !== 'checks') return;
+			if (this.headSha && event.subject.sha !== this.headSha) return;
+			this.update();
+		});
 	}
 
 	stop() {
 		if (this.timeout) clearTimeout(this.timeout);
 		delete this.timeout;
+		this.unlisten?.();
+		delete this.unlisten;
 	}
 
 	async update() {
+		if (this.timeout) clearTimeout(this.timeout);
+		delete this.timeout;
 		this.error.set(undefined);
 		this.loading.set(true);
 
 		try {
 			const checks = await this.fetchChecksWithRetries(this.sourceBranch, 5, 2000);
+			this.headSha = checks.check_runs[0]?.head_sha;
 			const status = parseChecks(checks);
 			this.status.set(status);
 			this._status = status;
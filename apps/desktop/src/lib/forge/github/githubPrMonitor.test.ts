@@ -3,6 +3,23 @@ import { Octokit, type RestEndpointMethodTypes } from '@octokit/rest';
 import { expect, test, describe, vi, beforeEach, afterEach } from 'vitest';
 import type { ForgePrMonitor } from '../interface/forgePrMonitor';
 import type { ForgePrService } from '../interface/forgePrService';
+import type { ForgeEvent } from '$lib/forge/forgeEvents';
+import type { EventCallback } from '@tauri-apps/api/event';
+
+const listeners = vi.hoisted(() => new Set<EventCallback<ForgeEvent>>());
+vi.mock('$lib/backend/ipc', async (importOriginal) => ({
+	...(await importOriginal<typeof import('$lib/backend/ipc')>()),
+	listen: (_event: string, handle: EventCallback<ForgeEvent>) => {
+		listeners.add(handle);
+		return async () => {
+			listeners.delete(handle);
+		};
+	}
+}));
+
+function emit(payload: ForgeEvent) {
+	for (const handle of listeners) handle({ event: 'forge://webhook', id: 0, payload });
+}
 
 // TODO: Rewrite this proof-of-concept into something valuable.
 describe.concurrent('GitHubPrMonitor', () => {
@@ -50,4 +67,26 @@ describe.concurrent('GitHubPrMonitor', () => {
 		vi.advanceTimersToNextTimer();
 		expect(get).toHaveBeenCalledTimes(2);
 	});
+
+	test('should refresh on webhooks about its pull request', async () => {
+		const get = vi.spyOn(octokit.pulls, 'get').mockReturnValue(
+			Promise.resolve({
+				data: { title: 'PR Title' }
+			} as RestEndpointMethodTypes['pulls']['get']['response'])
+		);
+		const unsubscribe = monitor?.pr.subscribe(() => {});
+		expect(get).toHaveBeenCalledOnce();
+
+		const subject = { repository: 'test-owner/test-repo', number: 123, state: 'approved' };
+		emit({ type: 'review', subject: { ...subject, number: 124 } });
+		emit({ type: 'review', subject: { ...subject, repository: 'test-owner/other-repo' } });
+		expect(get).toHaveBeenCalledOnce();
+		emit({ type: 'review', subject });
+		expect(get).toHaveBeenCalledTimes(2);
+
+		// Unsubscribing should stop listening.
+		unsubscribe?.();
+		emit({ type: 'review', subject });
+		expect(get).toHaveBeenCalledTimes(2);
+	});
 });
@@ -1,3 +1,4 @@
+import { listenForForgeEvents } from '$lib/forge/forgeEvents';
 import { type DetailedPullRequest } from '$lib/forge/interface/types';
 import { sleep } from '$lib/utils/sleep';
 import { derived, writable } from 'svelte/store';
@@ -21,6 +22,7 @@ export class GitHubPrMonitor implements ForgePrMonitor {
 	readonly lastFetch = writable<Date | undefined>();
 
 	private intervalId: any;
+	private unlisten: (() => Promise<void>) | undefined;
 
 	constructor(
 		private prService: GitHubPrService,
@@ -32,10 +34,16 @@ export class GitHubPrMonitor implements ForgePrMonitor {
 		this.intervalId = setInterval(() => {
 			this.fetch();
 		}, PR_SERVICE_INTERVAL);
+		this.unlisten = listenForForgeEvents(this.prService.repo, (event) => {
+			if (event.type !== 'pullRequest' && event.type !== 'review') return;
+			if (event.subject.number === this.prNumber) this.fetch();
+		});
 	}
 
 	private stop() {
 		if (this.intervalId) clearInterval(this.intervalId);
+		this.unlisten?.();
+		this.unlisten = undefined;
 	}
 
 	async refresh(): Promise<void> {
@@ -33,4 +33,28 @@ describe.concurrent('GitHubPrService', () => {
 		const pr = await service?.get(123);
 		expect(pr?.title).equal(title);
 	});
+
+	test('test merge queue status', async () => {
+		const entry = (mergeQueueEntry: unknown) =>
+			Promise.resolve({ repository: { pullRequest: { mergeQueueEntry } } });
+		const graphql = vi
+			.spyOn(octokit, 'graphql')
+			.mockReturnValueOnce(entry({ position: 2, state: 'QUEUED' }))
+			.mockReturnValueOnce(entry({ position: 0, state: 'AWAITING_CHECKS' }))
+			.mockReturnValueOnce(entry({ position: 0, state: 'UNMERGEABLE' }))
+			.mockReturnValueOnce(entry(null));
+
+		expect(await service?.mergeQueueStatus?.(123)).toEqual({
+			type: 'queued',
+			subject: { position: 2 }
+		});
+		expect(graphql.mock.calls[0]?.[1]).toEqual({
+			owner: 'test-owner',
+			repo: 'test-repo',
+			number: 123
+		});
+		expect(await service?.mergeQueueStatus?.(123)).toEqual({ type: 'merging' });
+		expect(await service?.mergeQueueStatus?.(123)).toEqual({ type: 'failed' });
+		expect(await service?.mergeQueueStatus?.(123)).toBeUndefined();
+	});
 });
@@ -1,6 +1,11 @@
 import { GitHubPrMonitor } from './githubPrMonitor';
 import { DEFAULT_HEADERS } from './headers';
-import { ghResponseToInstance, parseGitHubDetailedPullRequest } from './types';
+import {
+	ghResponseToInstance,
+	parseGitHubDetailedPullRequest,
+	parseGitHubMergeQueueEntry,
+	type GitHubMergeQueueEntry
+} from './types';
 import { sleep } from '$lib/utils/sleep';
 import posthog from 'posthog-js';
 import { writable } from 'svelte/store';
@@ -10,6 +15,7 @@ import type {
 	CreatePullRequestArgs,
 	DetailedPullRequest,
 	MergeMethod,
+	MergeQueueStatus,
 	PullRequest
 } from '../interface/types';
 import type { Octokit } from '@octokit/rest';
@@ -19,7 +25,7 @@ export class GitHubPrService implements ForgePrService {
 
 	constructor(
 		private octokit: Octokit,
-		private repo: RepoInfo
+		readonly repo: RepoInfo
 	) {}
 
 	async createPr({
@@ -93,6 +99,26 @@ export class GitHubPrService implements ForgePrService {
 		});
 	}
 
+	async mergeQueueStatus(prNumber: number): Promise<MergeQueueStatus | undefined> {
+		// Merge queues are only available through the GraphQL API.
+		const resp = await this.octokit.graphql<{
+			repository: { pullRequest: { mergeQueueEntry: GitHubMergeQueueEntry | null } | null };
+		}>(
+			`query ($owner: String!, $repo: String!, $number: Int!) {
+				repository(owner: $owner, name: $repo) {
+					pullRequest(number: $number) {
+						mergeQueueEntry {
+							position
+							state
+						}
+					}
+				}
+			}`,
+			{ owner: this.repo.owner, repo: this.repo.name, number: prNumber }
+		);
+		return parseGitHubMergeQueueEntry(resp.repository.pullRequest?.mergeQueueEntry);
+	}
+
 	prMonitor(prNumber: number): GitHubPrMonitor {
 		return new GitHubPrMonitor(this, prNumber);
 	}
@@ -1,9 +1,52 @@
+import { invoke } from '$lib/backend/ipc';
 import { Octokit } from '@octokit/rest';
 
+/** The media type Octokit requests unless told otherwise. */
+const DEFAULT_ACCEPT = 'application/vnd.github.v3+json';
+
+interface ForgeResponse {
+	status: number;
+	body: string;
+	link?: string;
+	fromCache: boolean;
+}
+
+/**
+ * A `fetch` which sends plain `GET` requests through the rate-limited and caching forge client
+ * of the backend, authenticated with `token`. Other requests are sent directly.
+ */
+export function forgeFetch(token?: string): typeof fetch {
+	return async (input, init) => {
+		const request = new Request(input, init);
+		const accept = request.headers.get('accept');
+		if (request.method !== 'GET' || (accept && accept !== DEFAULT_ACCEPT)) {
+			return await fetch(request);
+		}
+		const response = await invoke<ForgeResponse>('forge_get', { url: request.url, token });
+		const headers = new Headers({ 'content-type': 'application/json; charset=utf-8' });
+		if (response.link) headers.set('link', response.link);
+		// Responses like `204 No Content` must not have a body.
+		const body = [204, 205, 304].includes(response.status) ? null : response.body;
+		return new Response(body, { status: response.status, headers });
+	};
+}
+
 export function octokitFromAccessToken(accessToken: string) {
 	return new Octokit({
 		auth: accessToken,
 		userAgent: 'GitButler Client',
-		baseUrl: 'https://api.github.com'
+		baseUrl: 'https://api.github.com',
+		request: { fetch: forgeFetch(accessToken) }
+	});
+}
+
+/**
+ * An unauthenticated client, which can only read public repositories and is rate limited more strictly.
+ */
+export function anonymousOctokit() {
+	return new Octokit({
+		userAgent: 'GitButler Client',
+		baseUrl: 'https://api.github.com',
+		request: { fetch: forgeFetch() }
 	});
 }
@@ -1,4 +1,10 @@
-import type { CheckSuite, DetailedPullRequest, Label, PullRequest } from '../interface/types';
+import type {
+	CheckSuite,
+	DetailedPullRequest,
+	Label,
+	MergeQueueStatus,
+	PullRequest
+} from '../interface/types';
 import type { RestEndpointMethodTypes } from '@octokit/rest';
 
 export type DetailedGitHubPullRequest = RestEndpointMethodTypes['pulls']['get']['response']['data'];
@@ -77,3 +83,24 @@ export function parseGitHubCheckSuites(data: GitHubListCheckSuitesResp): CheckSu
 	}));
 	return result;
 }
+
+/** The merge queue entry of a pull request, as returned by the GraphQL API. */
+export type GitHubMergeQueueEntry = {
+	position: number;
+	state: 'AWAITING_CHECKS' | 'LOCKED' | 'MERGEABLE' | 'QUEUED' | 'UNMERGEABLE';
+};
+
+export function parseGitHubMergeQueueEntry(
+	entry: GitHubMergeQueueEntry | null | undefined
+): MergeQueueStatus | undefined {
+	if (!entry) return undefined;
+	switch (entry.state) {
+		case 'QUEUED':
+			return { type: 'queued', subject: { position: entry.position } };
+		case 'UNMERGEABLE':
+			return { type: 'failed' };
+		default:
+			// Checks are running on the merge group, or it's about to be merged.
+			return { type: 'merging' };
+	}
+}
@@ -1,6 +1,12 @@
 import { buildContextStore } from '@gitbutler/shared/context';
 import type { ForgePrMonitor } from './forgePrMonitor';
-import type { CreatePullRequestArgs, DetailedPullRequest, MergeMethod, PullRequest } from './types';
+import type {
+	CreatePullRequestArgs,
+	DetailedPullRequest,
+	MergeMethod,
+	MergeQueueStatus,
+	PullRequest
+} from './types';
 import type { Writable } from 'svelte/store';
 
 export const [getForgePrService, createForgePrServiceStore] = buildContextStore<
@@ -19,5 +25,7 @@ export interface ForgePrService {
 	}: CreatePullRequestArgs): Promise<PullRequest>;
 	merge(method: MergeMethod, prNumber: number): Promise<void>;
 	reopen(prNumber: number): Promise<void>;
+	/** Only implemented by forges with merge queues, `undefined` if the PR isn't queued. */
+	mergeQueueStatus?(prNumber: number): Promise<MergeQueueStatus | undefined>;
 	prMonitor(prNumber: number): ForgePrMonitor;
 }
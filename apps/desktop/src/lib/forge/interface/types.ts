@@ -45,6 +45,15 @@ export interface DetailedPullRequest {
 	state: 'open' | 'closed';
 }
 
+/**
+ * The state of a pull request in the merge queue of a forge, as stored for a series.
+ * `position` is `0` for the next entry to be merged.
+ */
+export type MergeQueueStatus =
+	| { type: 'queued'; subject: { position?: number } }
+	| { type: 'merging' }
+	| { type: 'failed' };
+
 export type ChecksStatus = {
 	startedAt: Date;
 	completed: boolean;
@@ -30,7 +30,13 @@ export type Operation =
 	| 'InsertBlankCommit'
 	| 'MoveCommitFile'
 	| 'FileChanges'
-	| 'EnterEditMode';
+	| 'EnterEditMode'
+	| 'AutoStash'
+	| 'SplitCommit'
+	| 'RevertCommit'
+	| 'ShelveChanges'
+	| 'UnshelveChanges'
+	| 'ApplyPatch';
 
 export class Trailer {
 	key!: string;
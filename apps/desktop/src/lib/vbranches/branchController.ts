@@ -3,8 +3,16 @@ import { showError, showToast } from '$lib/notifications/toasts';
 import * as toasts from '$lib/utils/toasts';
 import posthog from 'posthog-js';
 import type { BaseBranchService } from '$lib/baseBranch/baseBranchService';
+import type { MergeQueueStatus } from '$lib/forge/interface/types';
 import type { RemoteBranchService } from '$lib/stores/remoteBranches';
-import type { BranchPushResult, ForgeIdentifier, Hunk, LocalFile, StackOrder } from './types';
+import type {
+	BranchPushResult,
+	CoAuthor,
+	ForgeIdentifier,
+	Hunk,
+	LocalFile,
+	StackOrder
+} from './types';
 import type { VirtualBranchService } from './virtualBranch';
 
 export type CommitIdOrChangeId = { CommitId: string } | { ChangeId: string };
@@ -54,7 +62,8 @@ export class BranchController {
 		branch: string,
 		message: string,
 		ownership: string | undefined = undefined,
-		runHooks = false
+		runHooks = true,
+		coAuthors: CoAuthor[] = []
 	) {
 		try {
 			await invoke<void>('commit_virtual_branch', {
@@ -62,7 +71,8 @@ export class BranchController {
 				branch,
 				message,
 				ownership,
-				runHooks: runHooks
+				runHooks: runHooks,
+				coAuthors
 			});
 			posthog.capture('Commit Successful');
 		} catch (err: any) {
@@ -181,6 +191,29 @@ export class BranchController {
 		}
 	}
 
+	/**
+	 * Updates the merge queue status of a branch/series, which defers its cleanup until merged.
+	 * @param stackId The stack ID to update.
+	 * @param headName The branch name to update.
+	 * @param status The status reported by the forge, or undefined if it isn't queued.
+	 */
+	async updateSeriesMergeQueueStatus(
+		stackId: string,
+		headName: string,
+		status: MergeQueueStatus | undefined
+	) {
+		try {
+			await invoke<void>('update_series_merge_queue_status', {
+				projectId: this.projectId,
+				stackId,
+				headName,
+				status
+			});
+		} catch (err) {
+			showError('Failed to update the merge queue status', err);
+		}
+	}
+
 	/**
 	 * Updates the series description.
 	 * @param stackId The stack Id (vbranch.id) which contains the series.
@@ -291,6 +324,17 @@ export class BranchController {
 		}
 	}
 
+	async updateBranchDraft(branchId: string, draft: boolean) {
+		try {
+			await invoke<void>('update_virtual_branch', {
+				projectId: this.projectId,
+				branch: { id: branchId, draft }
+			});
+		} catch (err) {
+			showError('Failed to update branch draft state', err);
+		}
+	}
+
 	async updateBranchNotes(branchId: string, notes: string) {
 		try {
 			await invoke<void>('update_virtual_branch', {
@@ -511,19 +555,35 @@ export class BranchController {
 		}
 	}
 
-	async amendBranch(branchId: string, commitOid: string, ownership: string) {
+	async amendBranch(
+		branchId: string,
+		commitOid: string,
+		ownership: string,
+		coAuthors: CoAuthor[] = []
+	) {
 		try {
 			await invoke<void>('amend_virtual_branch', {
 				projectId: this.projectId,
 				branchId,
 				commitOid,
-				ownership
+				ownership,
+				coAuthors
 			});
 		} catch (err: any) {
 			showError('Failed to amend commit', err);
 		}
 	}
 
+	/** The co-authors used most often in the project, to offer when committing. */
+	async frequentCoAuthors(): Promise<CoAuthor[]> {
+		try {
+			return await invoke<CoAuthor[]>('list_frequent_co_authors', { projectId: this.projectId });
+		} catch (err: any) {
+			showError('Failed to list co-authors', err);
+			return [];
+		}
+	}
+
 	async moveCommitFile(
 		branchId: string,
 		fromCommitOid: string,
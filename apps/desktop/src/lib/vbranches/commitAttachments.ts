@@ -0,0 +1,44 @@
+import { invoke } from '$lib/backend/ipc';
+
+export type AttachmentKind = 'ciRun' | 'review' | 'issue' | 'document' | 'other';
+
+/** A link attached to a commit, like the CI run that tested it or the review that approved it. */
+export interface CommitAttachment {
+	kind: AttachmentKind;
+	url: string;
+	title?: string;
+	/** When it was attached, in milliseconds since the Unix epoch. */
+	createdAt: number;
+}
+
+export const ATTACHMENT_KINDS: { value: AttachmentKind; label: string }[] = [
+	{ value: 'ciRun', label: 'CI run' },
+	{ value: 'review', label: 'Review' },
+	{ value: 'issue', label: 'Issue' },
+	{ value: 'document', label: 'Document' },
+	{ value: 'other', label: 'Other' }
+];
+
+export async function listCommitAttachments(projectId: string, commitOid: string) {
+	return await invoke<CommitAttachment[]>('list_commit_attachments', { projectId, commitOid });
+}
+
+export async function addCommitAttachment(
+	projectId: string,
+	commitOid: string,
+	kind: AttachmentKind,
+	url: string,
+	title?: string
+) {
+	return await invoke<CommitAttachment>('add_commit_attachment', {
+		projectId,
+		commitOid,
+		kind,
+		url,
+		title
+	});
+}
+
+export async function removeCommitAttachment(projectId: string, commitOid: string, url: string) {
+	await invoke<void>('remove_commit_attachment', { projectId, commitOid, url });
+}
@@ -0,0 +1,57 @@
+import { invoke } from '$lib/backend/ipc';
+import type { SignatureVerification } from './types';
+
+interface CommitSignature {
+	commitId: string;
+	verification?: SignatureVerification;
+}
+
+type PendingVerification = {
+	resolve: (verification: SignatureVerification | undefined) => void;
+	reject: (reason: unknown) => void;
+};
+
+/** The commits whose signatures are still to be verified, by project. */
+const pending = new Map<string, Map<string, PendingVerification[]>>();
+
+/**
+ * Verify the signature of a commit, which is batched with all other verifications that are
+ * requested until the current task is done.
+ */
+export async function verifyCommitSignature(
+	projectId: string,
+	commitId: string
+): Promise<SignatureVerification | undefined> {
+	return await new Promise((resolve, reject) => {
+		let commits = pending.get(projectId);
+		if (!commits) {
+			commits = new Map();
+			pending.set(projectId, commits);
+			queueMicrotask(() => flush(projectId));
+		}
+		const waiting = commits.get(commitId) ?? [];
+		waiting.push({ resolve, reject });
+		commits.set(commitId, waiting);
+	});
+}
+
+async function flush(projectId: string) {
+	const commits = pending.get(projectId);
+	pending.delete(projectId);
+	if (!commits) return;
+
+	try {
+		const signatures = await invoke<CommitSignature[]>('verify_commit_signatures', {
+			projectId,
+			commitOids: Array.from(commits.keys())
+		});
+		const verifications = new Map(signatures.map((s) => [s.commitId, s.verification]));
+		for (const [commitId, waiting] of commits) {
+			waiting.forEach((w) => w.resolve(verifications.get(commitId)));
+		}
+	} catch (err: unknown) {
+		for (const waiting of commits.values()) {
+			waiting.forEach((w) => w.reject(err));
+		}
+	}
+}
@@ -4,7 +4,7 @@ import { splitMessage } from '$lib/utils/commitMessage';
 import { hashCode } from '@gitbutler/ui/utils/string';
 import { isDefined, notNull } from '@gitbutler/ui/utils/typeguards';
 import { Type, Transform } from 'class-transformer';
-import type { PullRequest } from '$lib/forge/interface/types';
+import type { MergeQueueStatus, PullRequest } from '$lib/forge/interface/types';
 
 export type ChangeType =
 	/// Entry does not exist in old version
@@ -136,6 +136,8 @@ export class VirtualBranch {
 	/// The fork point between the target branch and the virtual branch
 	forkPoint!: string;
 	allowRebasing!: boolean;
+	/** If set, the branch can't be pushed or have a pull request created for it. */
+	draft!: boolean;
 	pr?: PullRequest;
 	refname!: string;
 	tree!: string;
@@ -175,6 +177,28 @@ export class VirtualBranch {
 export const BRANCH = Symbol('branch');
 export type CommitStatus = 'local' | 'localAndRemote' | 'localAndShadow' | 'integrated' | 'remote';
 
+export type SignatureStatus = 'verified' | 'unverified' | 'unknownKey';
+
+export interface SignatureVerification {
+	status: SignatureStatus;
+	/** The principal or GPG user ID of the signer, or the fingerprint of the key if it isn't known. */
+	signer?: string;
+}
+
+export function signatureDescription(signature: SignatureVerification | undefined): string {
+	const signer = signature?.signer ? ` by ${signature.signer}` : '';
+	switch (signature?.status) {
+		case 'verified':
+			return `Verified signature${signer}`;
+		case 'unverified':
+			return `Unverified signature${signer}`;
+		case 'unknownKey':
+			return `Signed with unknown key${signer}`;
+		default:
+			return 'Signed';
+	}
+}
+
 export class ConflictEntries {
 	public entries: Map<string, ConflictEntryPresence> = new Map();
 	constructor(ancestorEntries: string[], ourEntries: string[], theirEntries: string[]) {
@@ -199,6 +223,8 @@ export class ConflictEntries {
 export class DetailedCommit {
 	id!: string;
 	author!: Author;
+	/** The co-authors named in the `Co-authored-by` trailers of the message. */
+	coAuthors!: Author[];
 	description!: string;
 	@Transform((obj) => new Date(obj.value))
 	createdAt!: Date;
@@ -239,6 +265,10 @@ export class DetailedCommit {
 			new ConflictEntries(obj.value.ancestorEntries, obj.value.ourEntries, obj.value.theirEntries)
 	)
 	conflictedFiles!: ConflictEntries;
+	/** Files whose conflicts were resolved automatically when rebasing or merging this commit. */
+	autoResolved!: string[];
+	/** The id of the commit this one was copied from to its branch. */
+	copiedFromId?: string;
 
 	get status(): CommitStatus {
 		if (this.isIntegrated) return 'integrated';
@@ -371,6 +401,11 @@ export interface Author {
 	isBot?: boolean;
 }
 
+export interface CoAuthor {
+	name: string;
+	email: string;
+}
+
 export class Branch {
 	sha!: string;
 	name!: string;
@@ -445,6 +480,8 @@ export class PatchSeries {
 	 * This would occur when the branch has been merged at the remote and the workspace has been updated with that change.
 	 */
 	archived!: boolean;
+	/** The state of the review unit in the merge queue of the forge, if it was queued. */
+	mergeQueue?: MergeQueueStatus;
 
 	get localCommits() {
 		return this.patches.filter((c) => c.status === 'local');
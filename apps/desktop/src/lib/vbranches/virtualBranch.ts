@@ -3,6 +3,7 @@ import { invoke, listen } from '$lib/backend/ipc';
 import { RemoteBranchService } from '$lib/stores/remoteBranches';
 import { plainToInstance } from 'class-transformer';
 import { writable } from 'svelte/store';
+import type { ProjectBootstrap } from '$lib/backend/bootstrap';
 import type { BranchListingService } from '$lib/branches/branchListing';
 import type { ProjectMetrics } from '$lib/metrics/projectMetrics';
 
@@ -23,13 +24,25 @@ export class VirtualBranchService {
 		private projectId: string,
 		private projectMetrics: ProjectMetrics,
 		private remoteBranchService: RemoteBranchService,
-		private branchListingService: BranchListingService
+		private branchListingService: BranchListingService,
+		// Used instead of listing the branches on their own when they are first needed.
+		private bootstrap?: Promise<ProjectBootstrap>
 	) {}
 
 	async refresh() {
 		this.loading.set(true);
+		const bootstrap = this.bootstrap;
+		this.bootstrap = undefined;
 		try {
-			this.handlePayload(await this.listVirtualBranches());
+			const virtualBranches = await bootstrap?.then(
+				(b) => b.virtualBranches,
+				() => undefined
+			);
+			this.handlePayload(
+				virtualBranches
+					? plainToInstance(VirtualBranches, virtualBranches).branches
+					: await this.listVirtualBranches()
+			);
 		} catch (err: any) {
 			console.error(err);
 			this.error.set(err);
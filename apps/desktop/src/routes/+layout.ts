@@ -9,6 +9,7 @@ import { PromptService } from '$lib/backend/prompt';
 import { Tauri } from '$lib/backend/tauri';
 import { UpdaterService } from '$lib/backend/updater';
 import { loadAppSettings } from '$lib/config/appSettings';
+import { loadRemoteFeatureFlags } from '$lib/config/engineFeatureFlags';
 import { RemotesService } from '$lib/remotes/service';
 import { RustSecretService } from '$lib/secrets/secretsService';
 import { TokenMemoryService } from '$lib/stores/tokenMemoryService';
@@ -42,6 +43,8 @@ export const load: LayoutLoad = async () => {
 
 	const tokenMemoryService = new TokenMemoryService();
 	const httpClient = new HttpClient(window.fetch, PUBLIC_API_BASE_URL, tokenMemoryService.token);
+	// Not awaited, as the defaults of the backend apply until it arrives.
+	loadRemoteFeatureFlags(httpClient);
 	const authService = new AuthService();
 	const updaterService = new UpdaterService(new Tauri());
 	const promptService = new PromptService();
@@ -1,3 +1,4 @@
+import { loadProjectBootstrap } from '$lib/backend/bootstrap';
 import { getUserErrorCode, invoke } from '$lib/backend/ipc';
 import { ProjectService, type Project } from '$lib/backend/projects';
 import { TemplateService } from '$lib/backend/templateService';
@@ -8,6 +9,7 @@ import { BranchDragActionsFactory } from '$lib/branches/dragActions.js';
 import { CommitDragActionsFactory } from '$lib/commits/dragActions.js';
 import { CommitService } from '$lib/commits/service';
 import { StackingReorderDropzoneManagerFactory } from '$lib/dragging/stackingReorderDropzoneManager';
+import { ConfigSignal } from '$lib/fetchSignal/configSignal';
 import { FetchSignal } from '$lib/fetchSignal/fetchSignal.js';
 import { HistoryService } from '$lib/history/history';
 import { SyncedSnapshotService } from '$lib/history/syncedSnapshotService';
@@ -55,9 +57,13 @@ export const load: LayoutLoad = async ({ params, parent }) => {
 
 	const modeService = new ModeService(projectId);
 	const fetchSignal = new FetchSignal(projectId);
+	const configSignal = new ConfigSignal(projectId);
+
+	// Started right away but not awaited, so it loads while the page is drawn.
+	const bootstrap = loadProjectBootstrap(projectId);
 
 	const historyService = new HistoryService(projectId);
-	const baseBranchService = new BaseBranchService(projectId);
+	const baseBranchService = new BaseBranchService(projectId, bootstrap);
 	const commitService = new CommitService(projectId);
 	const templateService = new TemplateService(projectId);
 
@@ -72,7 +78,8 @@ export const load: LayoutLoad = async ({ params, parent }) => {
 		projectId,
 		projectMetrics,
 		remoteBranchService,
-		branchListingService
+		branchListingService,
+		bootstrap
 	);
 
 	const branchController = new BranchController(
@@ -120,6 +127,7 @@ export const load: LayoutLoad = async ({ params, parent }) => {
 		projectMetrics,
 		modeService,
 		fetchSignal,
+		configSignal,
 		upstreamIntegrationService,
 
 		// These observables are provided for convenience
@@ -0,0 +1,147 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use anyhow::{Context as _, Result};
+use gitbutler_command_context::CommandContext;
+use gitbutler_diff::Hunk;
+use gitbutler_project::access::WorktreeWritePermission;
+use gitbutler_repo::{LogUntil, RepositoryExt as _};
+use gitbutler_stack::{BranchOwnershipClaims, OwnershipClaim, StackId};
+use itertools::Itertools as _;
+use serde::Serialize;
+
+use crate::{status::get_applied_status, VirtualBranchesExt as _};
+
+/// What [`absorb()`] did with the uncommitted hunks of a stack.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AbsorbReport {
+    pub absorbed: Vec<AbsorbedHunk>,
+    pub skipped: Vec<SkippedHunk>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AbsorbedHunk {
+    pub path: PathBuf,
+    pub start: u32,
+    pub end: u32,
+    /// The commit the hunk was amended into, with its ID from before absorbing.
+    #[serde(with = "gitbutler_serde::oid")]
+    pub commit_id: git2::Oid,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedHunk {
+    pub path: PathBuf,
+    pub start: u32,
+    pub end: u32,
+    pub reason: SkipReason,
+}
+
+/// Why an uncommitted hunk wasn't absorbed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SkipReason {
+    /// The hunk doesn't depend on any commit.
+    Independent,
+    /// The hunk depends on more than one commit.
+    Ambiguous,
+    /// The hunk depends on a commit of another stack.
+    OtherStack,
+}
+
+/// Amend each uncommitted hunk of the stack with `stack_id` into the one commit of the stack it
+/// depends on, like `git absorb`, and report which hunks were absorbed and which weren't.
+///
+/// Hunks that depend on no commit, on more than one, or on a commit of another stack are skipped
+/// and stay uncommitted.
+pub(crate) fn absorb(
+    ctx: &CommandContext,
+    stack_id: StackId,
+    perm: &mut WorktreeWritePermission,
+) -> Result<AbsorbReport> {
+    ctx.assure_resolved()?;
+    let vb_state = ctx.project().virtual_branches();
+    let default_target = vb_state.get_default_target()?;
+
+    let (stack, files) = get_applied_status(ctx, None)?
+        .branches
+        .into_iter()
+        .find(|(stack, _)| stack.id == stack_id)
+        .with_context(|| format!("could not find applied branch with id {stack_id}"))?;
+    let stack_commit_ids =
+        ctx.repository()
+            .l(stack.head(), LogUntil::Commit(default_target.sha), false)?;
+
+    let mut report = AbsorbReport::default();
+    let mut to_absorb: HashMap<git2::Oid, BTreeMap<PathBuf, Vec<Hunk>>> = HashMap::new();
+    for file in files {
+        for hunk in file.hunks {
+            let locks = hunk.locked_to.as_deref().unwrap_or_default();
+            let commit_ids = locks
+                .iter()
+                .map(|lock| lock.commit_id)
+                .unique()
+                .collect_vec();
+            let reason = match commit_ids.as_slice() {
+                [] => Some(SkipReason::Independent),
+                _ if locks.iter().any(|lock| lock.branch_id != stack_id)
+                    || commit_ids.iter().any(|id| !stack_commit_ids.contains(id)) =>
+                {
+                    Some(SkipReason::OtherStack)
+                }
+                [_] => None,
+                _ => Some(SkipReason::Ambiguous),
+            };
+            match reason {
+                Some(reason) => report.skipped.push(SkippedHunk {
+                    path: file.path.clone(),
+                    start: hunk.start,
+                    end: hunk.end,
+                    reason,
+                }),
+                None => {
+                    let commit_id = commit_ids[0];
+                    to_absorb
+                        .entry(commit_id)
+                        .or_default()
+                        .entry(file.path.clone())
+                        .or_default()
+                        .push(Hunk {
+                            hash: None,
+                            start: hunk.start,
+                            end: hunk.end,
+                        });
+                    report.absorbed.push(AbsorbedHunk {
+                        path: file.path.clone(),
+                        start: hunk.start,
+                        end: hunk.end,
+                        commit_id,
+                    });
+                }
+            }
+        }
+    }
+
+    // Amend the commits from the top of the stack down, so the commits that are still to be
+    // amended aren't rebased, and keep their IDs.
+    for commit_id in &stack_commit_ids {
+        let Some(paths) = to_absorb.remove(commit_id) else {
+            continue;
+        };
+        let ownership = BranchOwnershipClaims {
+            claims: paths
+                .into_iter()
+                .map(|(file_path, hunks)| OwnershipClaim { file_path, hunks })
+                .collect(),
+        };
+        crate::r#virtual::amend(ctx, stack_id, *commit_id, &ownership, &[], perm)
+            .with_context(|| format!("failed to absorb hunks into commit {commit_id}"))?;
+    }
+
+    Ok(report)
+}
@@ -1,7 +1,23 @@
 use super::r#virtual as vbranch;
+use crate::absorb::{self, AbsorbReport};
+use crate::activity::{self, ActivityPage};
+use crate::apply_patch::{self, ApplyPatchReport};
+use crate::attachments::{self, AttachmentKind, CommitAttachment};
+use crate::autostash::{self, AutoStashConflicts};
 use crate::branch_upstream_integration;
+use crate::co_authors::{self, CoAuthor};
+use crate::commit::CommitSignature;
+use crate::dependencies::{self, DependencyTimings};
+use crate::format_patch::{self, PatchFormat};
+use crate::insights::{self, Contributors};
 use crate::move_commits;
+use crate::policy::{self, CommitMessageViolation, PolicyReport};
 use crate::reorder::{self, StackOrder};
+use crate::revert_commit::{self, RevertOutcome};
+use crate::shelves::{self, Shelf};
+use crate::split_commit::{self, SplitAssignment};
+use crate::summary;
+use crate::touches::{self, CommitTouch};
 use crate::upstream_integration::{
     self, BaseBranchResolution, BaseBranchResolutionApproach, BranchStatuses, Resolution,
     UpstreamIntegrationContext,
@@ -19,9 +35,14 @@ use anyhow::{Context, Result};
 use gitbutler_branch::{BranchCreateRequest, BranchUpdateRequest};
 use gitbutler_command_context::CommandContext;
 use gitbutler_diff::DiffByPathMap;
+use gitbutler_forge::event_log::ReceivedEvent;
+use gitbutler_hunk_dependency::{
+    CrossStackConflict, DependencyGraph, LineOwner, Operation, OperationPrediction, PathError,
+    RemovalPreview, TransitiveDependencies,
+};
 use gitbutler_operating_modes::assure_open_workspace_mode;
 use gitbutler_oplog::{
-    entry::{OperationKind, SnapshotDetails},
+    entry::{OperationKind, SnapshotDetails, Trailer},
     OplogExt, SnapshotExt,
 };
 use gitbutler_project::{FetchResult, Project};
@@ -30,7 +51,7 @@ use gitbutler_repo::RepositoryExt;
 use gitbutler_repo_actions::RepoActionsExt;
 use gitbutler_stack::ForgeIdentifier;
 use gitbutler_stack::{BranchOwnershipClaims, StackId};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::instrument;
 
 pub fn create_commit(
@@ -39,13 +60,48 @@ pub fn create_commit(
     message: &str,
     ownership: Option<&BranchOwnershipClaims>,
     run_hooks: bool,
+    co_authors: &[CoAuthor],
 ) -> Result<git2::Oid> {
     let ctx = open_with_verify(project)?;
     assure_open_workspace_mode(&ctx).context("Creating a commit requires open workspace mode")?;
     let mut guard = project.exclusive_worktree_access();
     let snapshot_tree = ctx.project().prepare_snapshot(guard.read_permission());
-    let result =
-        vbranch::commit(&ctx, branch_id, message, ownership, run_hooks).map_err(Into::into);
+    let result = vbranch::commit(&ctx, branch_id, message, ownership, run_hooks, co_authors)
+        .map_err(Into::into);
+    if result.is_ok() {
+        remember_co_authors(project, co_authors);
+    }
+    let _ = snapshot_tree.and_then(|snapshot_tree| {
+        ctx.project().snapshot_commit_creation(
+            snapshot_tree,
+            result.as_ref().err(),
+            message.to_owned(),
+            None,
+            guard.write_permission(),
+        )
+    });
+    result
+}
+
+/// Commit only the hunks claimed by `claims` to the branch with `branch_id`, and fail if any of
+/// them isn't an uncommitted hunk of the branch.
+pub fn create_commit_from_hunks(
+    project: &Project,
+    branch_id: StackId,
+    message: &str,
+    claims: &BranchOwnershipClaims,
+    run_hooks: bool,
+    co_authors: &[CoAuthor],
+) -> Result<git2::Oid> {
+    let ctx = open_with_verify(project)?;
+    assure_open_workspace_mode(&ctx).context("Creating a commit requires open workspace mode")?;
+    let mut guard = project.exclusive_worktree_access();
+    let snapshot_tree = ctx.project().prepare_snapshot(guard.read_permission());
+    let result = vbranch::commit_hunks(&ctx, branch_id, message, claims, run_hooks, co_authors)
+        .map_err(Into::into);
+    if result.is_ok() {
+        remember_co_authors(project, co_authors);
+    }
     let _ = snapshot_tree.and_then(|snapshot_tree| {
         ctx.project().snapshot_commit_creation(
             snapshot_tree,
@@ -151,6 +207,19 @@ pub fn list_commit_files(
     crate::file::list_commit_files(ctx.repository(), commit_oid).map_err(Into::into)
 }
 
+/// Verify the signatures of the commits with `commit_ids`, which is too slow to do while listing
+/// them.
+pub fn verify_commit_signatures(
+    project: &Project,
+    commit_ids: &[git2::Oid],
+) -> Result<Vec<CommitSignature>> {
+    let ctx = CommandContext::open(project)?;
+    Ok(crate::commit::verify_signatures(
+        ctx.repository(),
+        commit_ids,
+    ))
+}
+
 pub fn set_base_branch(project: &Project, target_branch: &RemoteRefname) -> Result<BaseBranch> {
     let ctx = CommandContext::open(project)?;
     let mut guard = project.exclusive_worktree_access();
@@ -285,6 +354,7 @@ pub fn amend(
     branch_id: StackId,
     commit_oid: git2::Oid,
     ownership: &BranchOwnershipClaims,
+    co_authors: &[CoAuthor],
 ) -> Result<git2::Oid> {
     let ctx = open_with_verify(project)?;
     assure_open_workspace_mode(&ctx).context("Amending a commit requires open workspace mode")?;
@@ -293,13 +363,49 @@ pub fn amend(
         SnapshotDetails::new(OperationKind::AmendCommit),
         guard.write_permission(),
     );
-    vbranch::amend(
+    let oid = vbranch::amend(
         &ctx,
         branch_id,
         commit_oid,
         ownership,
+        co_authors,
         guard.write_permission(),
-    )
+    )?;
+    remember_co_authors(project, co_authors);
+    Ok(oid)
+}
+
+/// Remember the co-authors of a commit that was made, which isn't worth failing the commit for.
+fn remember_co_authors(project: &Project, co_authors: &[CoAuthor]) {
+    if let Err(err) = co_authors::remember(project, co_authors) {
+        tracing::warn!(?err, "failed to remember co-authors");
+    }
+}
+
+/// Amend the uncommitted hunks of the stack with `stack_id` into the commits they depend on, and
+/// report which were absorbed.
+pub fn absorb(project: &Project, stack_id: StackId) -> Result<AbsorbReport> {
+    let ctx = open_with_verify(project)?;
+    assure_open_workspace_mode(&ctx).context("Absorbing changes requires open workspace mode")?;
+    let mut guard = project.exclusive_worktree_access();
+    let _ = ctx.project().create_snapshot(
+        SnapshotDetails::new(OperationKind::AmendCommit),
+        guard.write_permission(),
+    );
+    absorb::absorb(&ctx, stack_id, guard.write_permission())
+}
+
+/// Apply the unified diff or `git format-patch` mbox in `patch` as new commits on top of the stack
+/// with `stack_id`, and report the commits and the hunks that didn't apply.
+pub fn apply_patch(project: &Project, stack_id: StackId, patch: &[u8]) -> Result<ApplyPatchReport> {
+    let ctx = open_with_verify(project)?;
+    assure_open_workspace_mode(&ctx).context("Applying a patch requires open workspace mode")?;
+    let mut guard = project.exclusive_worktree_access();
+    let _ = ctx.project().create_snapshot(
+        SnapshotDetails::new(OperationKind::ApplyPatch),
+        guard.write_permission(),
+    );
+    apply_patch::apply_patch(&ctx, stack_id, patch, guard.write_permission())
 }
 
 pub fn move_commit_file(
@@ -320,6 +426,31 @@ pub fn move_commit_file(
         .map_err(Into::into)
 }
 
+/// Move the hunks in `ownership` of the commit with `from_commit_oid` into the commit with
+/// `to_commit_oid` of the same stack, and return the new ID of the latter.
+pub fn move_commit_hunks(
+    project: &Project,
+    stack_id: StackId,
+    from_commit_oid: git2::Oid,
+    to_commit_oid: git2::Oid,
+    ownership: &BranchOwnershipClaims,
+) -> Result<git2::Oid> {
+    let ctx = open_with_verify(project)?;
+    assure_open_workspace_mode(&ctx).context("Moving hunks requires open workspace mode")?;
+    let mut guard = project.exclusive_worktree_access();
+    let _ = ctx.project().create_snapshot(
+        SnapshotDetails::new(OperationKind::MoveCommitFile),
+        guard.write_permission(),
+    );
+    crate::move_commit_hunks::move_commit_hunks(
+        &ctx,
+        stack_id,
+        from_commit_oid,
+        to_commit_oid,
+        ownership,
+    )
+}
+
 pub fn undo_commit(project: &Project, branch_id: StackId, commit_oid: git2::Oid) -> Result<()> {
     let ctx = open_with_verify(project)?;
     assure_open_workspace_mode(&ctx).context("Undoing a commit requires open workspace mode")?;
@@ -339,6 +470,98 @@ pub fn undo_commit(project: &Project, branch_id: StackId, commit_oid: git2::Oid)
     result
 }
 
+pub fn split_commit(
+    project: &Project,
+    stack_id: StackId,
+    commit_oid: git2::Oid,
+    assignments: &[SplitAssignment],
+) -> Result<Vec<git2::Oid>> {
+    let ctx = open_with_verify(project)?;
+    assure_open_workspace_mode(&ctx).context("Splitting a commit requires open workspace mode")?;
+    let mut guard = project.exclusive_worktree_access();
+    let _ = ctx.project().create_snapshot(
+        SnapshotDetails::new(OperationKind::SplitCommit),
+        guard.write_permission(),
+    );
+    split_commit::split_commit(&ctx, stack_id, commit_oid, assignments)
+}
+
+pub fn revert_commit(
+    project: &Project,
+    stack_id: StackId,
+    commit_oid: git2::Oid,
+    drop_if_independent: bool,
+) -> Result<RevertOutcome> {
+    let ctx = open_with_verify(project)?;
+    assure_open_workspace_mode(&ctx).context("Reverting a commit requires open workspace mode")?;
+    let mut guard = project.exclusive_worktree_access();
+    let _ = ctx.project().create_snapshot(
+        SnapshotDetails::new(OperationKind::RevertCommit),
+        guard.write_permission(),
+    );
+    revert_commit::revert_commit(
+        &ctx,
+        stack_id,
+        commit_oid,
+        drop_if_independent,
+        guard.write_permission(),
+    )
+}
+
+/// Shelve the uncommitted changes of the stack with `stack_id` as `name`, which removes them from
+/// the worktree until they are unshelved.
+pub fn shelve_changes(project: &Project, stack_id: StackId, name: &str) -> Result<Shelf> {
+    let ctx = open_with_verify(project)?;
+    assure_open_workspace_mode(&ctx).context("Shelving changes requires open workspace mode")?;
+    let mut guard = project.exclusive_worktree_access();
+    let _ = ctx.project().create_snapshot(
+        shelf_snapshot_details(OperationKind::ShelveChanges, name),
+        guard.write_permission(),
+    );
+    shelves::shelve(&ctx, stack_id, name, guard.write_permission())
+}
+
+/// Apply the changes shelved as `name` to the stack with `stack_id` again, unless they conflict.
+pub fn unshelve_changes(project: &Project, stack_id: StackId, name: &str) -> Result<()> {
+    let ctx = open_with_verify(project)?;
+    assure_open_workspace_mode(&ctx).context("Unshelving changes requires open workspace mode")?;
+    let mut guard = project.exclusive_worktree_access();
+    let _ = ctx.project().create_snapshot(
+        shelf_snapshot_details(OperationKind::UnshelveChanges, name),
+        guard.write_permission(),
+    );
+    shelves::unshelve(&ctx, stack_id, name, guard.write_permission())
+}
+
+fn shelf_snapshot_details(operation: OperationKind, name: &str) -> SnapshotDetails {
+    SnapshotDetails::new(operation).with_trailers(vec![Trailer {
+        key: "name".to_string(),
+        value: name.to_string(),
+    }])
+}
+
+pub fn list_shelves(project: &Project, stack_id: StackId) -> Result<Vec<Shelf>> {
+    let ctx = CommandContext::open(project)?;
+    shelves::list(&ctx, stack_id)
+}
+
+pub fn delete_shelf(project: &Project, stack_id: StackId, name: &str) -> Result<()> {
+    let ctx = CommandContext::open(project)?;
+    shelves::delete(&ctx, stack_id, name)
+}
+
+/// Write the commits of the stack with `stack_id` as a patch series with a cover letter into
+/// `dir`, and return the paths of the written files.
+pub fn format_patch(
+    project: &Project,
+    stack_id: StackId,
+    dir: &Path,
+    format: PatchFormat,
+) -> Result<Vec<PathBuf>> {
+    let ctx = CommandContext::open(project)?;
+    format_patch::format_patch(&ctx, stack_id, dir, format)
+}
+
 pub fn insert_blank_commit(
     project: &Project,
     branch_id: StackId,
@@ -409,11 +632,28 @@ pub fn push_virtual_branch(
     project: &Project,
     branch_id: StackId,
     with_force: bool,
+    allow_draft: bool,
     askpass: Option<Option<StackId>>,
 ) -> Result<vbranch::PushResult> {
     let ctx = open_with_verify(project)?;
     assure_open_workspace_mode(&ctx).context("Pushing a branch requires open workspace mode")?;
-    vbranch::push(&ctx, branch_id, with_force, askpass)
+    vbranch::push(&ctx, branch_id, with_force, allow_draft, askpass)
+}
+
+pub fn policy_report(project: &Project) -> Result<PolicyReport> {
+    let ctx = open_with_verify(project)?;
+    assure_open_workspace_mode(&ctx).context("Checking the policy requires open workspace mode")?;
+    policy::report(&ctx)
+}
+
+/// Check `message` against the commit message rules of the policy, so a message can be corrected
+/// before committing, which fails with the same violations.
+pub fn validate_commit_message(
+    project: &Project,
+    message: &str,
+) -> Result<Vec<CommitMessageViolation>> {
+    let ctx = CommandContext::open(project)?;
+    policy::validate_commit_message(&ctx, message)
 }
 
 pub fn list_local_branches(project: Project) -> Result<Vec<RemoteBranch>> {
@@ -437,6 +677,44 @@ pub fn squash(project: &Project, branch_id: StackId, commit_oid: git2::Oid) -> R
     vbranch::squash(&ctx, branch_id, commit_oid).map_err(Into::into)
 }
 
+/// Squash the commits with `commit_oids` into the one with `target_commit_oid`, wherever they are
+/// in the stack, and return the ID of the squashed commit.
+pub fn squash_commits(
+    project: &Project,
+    stack_id: StackId,
+    target_commit_oid: git2::Oid,
+    commit_oids: &[git2::Oid],
+    message_template: Option<&str>,
+) -> Result<git2::Oid> {
+    let ctx = open_with_verify(project)?;
+    assure_open_workspace_mode(&ctx).context("Squashing commits requires open workspace mode")?;
+    let mut guard = project.exclusive_worktree_access();
+    let _ = ctx.project().create_snapshot(
+        SnapshotDetails::new(OperationKind::SquashCommit),
+        guard.write_permission(),
+    );
+    crate::squash_commits::squash_commits(
+        &ctx,
+        stack_id,
+        target_commit_oid,
+        commit_oids,
+        message_template,
+    )
+}
+
+/// Fold the `fixup!`, `squash!` and `amend!` commits of the stack with `stack_id` into the commits
+/// they target, and return how many were folded.
+pub fn autosquash(project: &Project, stack_id: StackId) -> Result<usize> {
+    let ctx = open_with_verify(project)?;
+    assure_open_workspace_mode(&ctx).context("Squashing commits requires open workspace mode")?;
+    let mut guard = project.exclusive_worktree_access();
+    let _ = ctx.project().create_snapshot(
+        SnapshotDetails::new(OperationKind::SquashCommit),
+        guard.write_permission(),
+    );
+    crate::autosquash::autosquash(&ctx, stack_id)
+}
+
 pub fn update_commit_message(
     project: &Project,
     branch_id: StackId,
@@ -459,6 +737,70 @@ pub fn find_commit(project: &Project, commit_oid: git2::Oid) -> Result<Option<Re
     remote::get_commit_data(&ctx, commit_oid)
 }
 
+pub fn branch_summary(project: &Project, branch_id: StackId) -> Result<String> {
+    let (branches, _) = list_virtual_branches(project)?;
+    let branch = branches
+        .iter()
+        .find(|branch| branch.id == branch_id)
+        .with_context(|| format!("Branch {branch_id} is not in the workspace"))?;
+    Ok(summary::branch_summary(branch))
+}
+
+pub fn commit_summary(project: &Project, commit_oid: git2::Oid) -> Result<String> {
+    let ctx = CommandContext::open(project)?;
+    let commit = ctx.repository().find_commit(commit_oid)?;
+    let files = crate::file::list_commit_files(ctx.repository(), commit_oid)?;
+    Ok(summary::commit_summary(
+        &String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()),
+        &files,
+    ))
+}
+
+pub fn contributors(project: &Project, since: Option<i64>) -> Result<Contributors> {
+    let ctx = CommandContext::open(project)?;
+    insights::contributors(&ctx, since)
+}
+
+pub fn stack_activity(
+    project: &Project,
+    stack_id: StackId,
+    forge_events: &[ReceivedEvent],
+    offset: usize,
+    limit: usize,
+) -> Result<ActivityPage> {
+    let ctx = CommandContext::open(project)?;
+    activity::stack_activity(&ctx, stack_id, forge_events, offset, limit)
+}
+
+pub fn list_commits_touching(project: &Project, path: &Path) -> Result<Vec<CommitTouch>> {
+    let ctx = CommandContext::open(project)?;
+    touches::commits_touching(&ctx, path)
+}
+
+pub fn list_commit_attachments(
+    project: &Project,
+    commit_oid: git2::Oid,
+) -> Result<Vec<CommitAttachment>> {
+    let ctx = CommandContext::open(project)?;
+    attachments::list(&ctx, commit_oid)
+}
+
+pub fn add_commit_attachment(
+    project: &Project,
+    commit_oid: git2::Oid,
+    kind: AttachmentKind,
+    url: &str,
+    title: Option<String>,
+) -> Result<CommitAttachment> {
+    let ctx = CommandContext::open(project)?;
+    attachments::add(&ctx, commit_oid, kind, url, title)
+}
+
+pub fn remove_commit_attachment(project: &Project, commit_oid: git2::Oid, url: &str) -> Result<()> {
+    let ctx = CommandContext::open(project)?;
+    attachments::remove(&ctx, commit_oid, url)
+}
+
 pub fn fetch_from_remotes(project: &Project, askpass: Option<String>) -> Result<FetchResult> {
     let ctx = CommandContext::open(project)?;
 
@@ -488,6 +830,26 @@ pub fn fetch_from_remotes(project: &Project, askpass: Option<String>) -> Result<
     Ok(project_data_last_fetched)
 }
 
+pub fn copy_commits_to_stack(
+    project: &Project,
+    target_stack_id: StackId,
+    commit_oids: &[git2::Oid],
+) -> Result<Vec<git2::Oid>> {
+    let ctx = open_with_verify(project)?;
+    assure_open_workspace_mode(&ctx).context("Copying commits requires open workspace mode")?;
+    let mut guard = project.exclusive_worktree_access();
+    let _ = ctx.project().create_snapshot(
+        SnapshotDetails::new(OperationKind::CherryPick),
+        guard.write_permission(),
+    );
+    crate::copy_commits::copy_commits_to_stack(
+        &ctx,
+        target_stack_id,
+        commit_oids,
+        guard.write_permission(),
+    )
+}
+
 pub fn move_commit(
     project: &Project,
     target_branch_id: StackId,
@@ -579,6 +941,59 @@ pub fn integrate_upstream(
     )
 }
 
+pub fn autostash_conflicts(project: &Project) -> Result<Option<AutoStashConflicts>> {
+    let ctx = CommandContext::open(project)?;
+    autostash::conflicts(&ctx)
+}
+
+pub fn dismiss_autostash_conflicts(project: &Project) -> Result<()> {
+    let ctx = CommandContext::open(project)?;
+    autostash::dismiss_conflicts(&ctx)
+}
+
+pub fn hunk_dependency_graph(project: &Project) -> Result<DependencyGraph> {
+    let ctx = CommandContext::open(project)?;
+    dependencies::hunk_dependency_graph(&ctx)
+}
+
+pub fn commit_dependencies(
+    project: &Project,
+    commit_id: git2::Oid,
+) -> Result<TransitiveDependencies> {
+    let ctx = CommandContext::open(project)?;
+    dependencies::commit_dependencies(&ctx, commit_id)
+}
+
+pub fn cross_stack_conflicts(project: &Project) -> Result<Vec<CrossStackConflict>> {
+    let ctx = CommandContext::open(project)?;
+    dependencies::cross_stack_conflicts(&ctx)
+}
+
+pub fn hunk_range_errors(project: &Project) -> Result<Vec<PathError>> {
+    let ctx = CommandContext::open(project)?;
+    dependencies::hunk_range_errors(&ctx)
+}
+
+pub fn dependency_timings(project: &Project) -> Result<DependencyTimings> {
+    let ctx = CommandContext::open(project)?;
+    dependencies::dependency_timings(&ctx)
+}
+
+pub fn remove_commit_preview(project: &Project, commit_id: git2::Oid) -> Result<RemovalPreview> {
+    let ctx = CommandContext::open(project)?;
+    dependencies::remove_commit_preview(&ctx, commit_id)
+}
+
+pub fn predict_operation(project: &Project, operation: &Operation) -> Result<OperationPrediction> {
+    let ctx = CommandContext::open(project)?;
+    dependencies::predict_operation(&ctx, operation)
+}
+
+pub fn line_owners(project: &Project, path: &Path) -> Result<Vec<LineOwner>> {
+    let ctx = CommandContext::open(project)?;
+    dependencies::line_owners(&ctx, path)
+}
+
 pub fn resolve_upstream_integration(
     project: &Project,
     resolution_approach: BaseBranchResolutionApproach,
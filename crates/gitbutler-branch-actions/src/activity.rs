@@ -0,0 +1,251 @@
+//! A feed of everything that happened to a stack, for a timeline of the branch.
+//!
+//! It combines what's already recorded elsewhere, so nothing needs to be tracked just for the feed:
+//!
+//! * Operations in the oplog whose details name one of the series of the stack or one of its commits.
+//! * Pushes, as found in the reflogs of the remote tracking branches of the series.
+//! * Forge events received via webhooks about the pull requests of the series or checks of its commits.
+use std::collections::HashSet;
+
+use anyhow::Result;
+use gitbutler_command_context::CommandContext;
+use gitbutler_forge::{event_log::ReceivedEvent, webhook::ForgeEvent};
+use gitbutler_oplog::{entry::OperationKind, OplogExt};
+use gitbutler_repo::{LogUntil, RepositoryExt as _};
+use gitbutler_stack::{ForgeIdentifier, StackId};
+use serde::Serialize;
+
+use crate::VirtualBranchesExt;
+
+/// How many of the most recent operations of the project are searched for those affecting the stack.
+const MAX_OPERATIONS: usize = 500;
+
+/// The start of the reflog messages Git writes when pushing updates a remote tracking branch.
+const PUSH_REFLOG_MESSAGE: &str = "update by push";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Activity {
+    /// When it happened, in seconds since the Unix epoch.
+    pub at: i64,
+    pub kind: ActivityKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", content = "subject", rename_all = "camelCase")]
+pub enum ActivityKind {
+    /// An operation in the oplog, which can be restored to.
+    #[serde(rename_all = "camelCase")]
+    Operation {
+        #[serde(with = "gitbutler_serde::oid")]
+        snapshot_id: git2::Oid,
+        operation: OperationKind,
+        title: String,
+    },
+    /// A series of the stack was pushed.
+    #[serde(rename_all = "camelCase")]
+    Push {
+        branch: String,
+        #[serde(with = "gitbutler_serde::oid")]
+        commit_id: git2::Oid,
+    },
+    /// A pull request, review or check changed at the forge.
+    Forge(ForgeEvent),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityPage {
+    /// The activity, the most recent first.
+    pub activity: Vec<Activity>,
+    /// Whether there is older activity after this page.
+    pub has_more: bool,
+}
+
+/// Return `limit` entries of the activity of the stack with `stack_id`, skipping the `offset` most recent ones.
+/// `forge_events` are all events received from the forge, of which only those about this stack are included.
+pub fn stack_activity(
+    ctx: &CommandContext,
+    stack_id: StackId,
+    forge_events: &[ReceivedEvent],
+    offset: usize,
+    limit: usize,
+) -> Result<ActivityPage> {
+    let vb_state = ctx.project().virtual_branches();
+    let stack = vb_state.get_branch(stack_id)?;
+    let target = vb_state.get_default_target()?;
+    let repo = ctx.repository();
+
+    let mut names: HashSet<&str> = stack.heads.iter().map(|head| head.name.as_str()).collect();
+    names.insert(stack.name.as_str());
+    let commit_ids: HashSet<String> = repo
+        .l(stack.head(), LogUntil::Commit(target.sha), false)?
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+
+    let mut activity = Vec::new();
+    for snapshot in ctx.project().list_snapshots(MAX_OPERATIONS, None)? {
+        let Some(details) = snapshot.details else {
+            continue;
+        };
+        let affects_stack = details.trailers.iter().any(|trailer| {
+            names.contains(trailer.value.as_str()) || commit_ids.contains(&trailer.value)
+        });
+        if affects_stack {
+            activity.push(Activity {
+                at: snapshot.created_at.seconds(),
+                kind: ActivityKind::Operation {
+                    snapshot_id: snapshot.commit_id,
+                    operation: details.operation,
+                    title: details.title,
+                },
+            });
+        }
+    }
+
+    let remote = target.push_remote_name();
+    for head in &stack.heads {
+        let reflog = repo.reflog(&head.remote_reference(&remote)?)?;
+        for entry in reflog.iter() {
+            if entry
+                .message()
+                .is_some_and(|message| message.starts_with(PUSH_REFLOG_MESSAGE))
+            {
+                activity.push(Activity {
+                    at: entry.committer().when().seconds(),
+                    kind: ActivityKind::Push {
+                        branch: head.name.clone(),
+                        commit_id: entry.id_new(),
+                    },
+                });
+            }
+        }
+    }
+
+    let pr_numbers: HashSet<u64> = stack
+        .heads
+        .iter()
+        .filter_map(|head| match &head.forge_id {
+            Some(ForgeIdentifier::GitHub(id)) => Some(id.pr_number as u64),
+            None => None,
+        })
+        .collect();
+    for received in forge_events.iter().rev() {
+        if is_about_stack(
+            &received.event,
+            &target.remote_url,
+            &pr_numbers,
+            &commit_ids,
+        ) {
+            activity.push(Activity {
+                at: received.received_at,
+                kind: ActivityKind::Forge(received.event.clone()),
+            });
+        }
+    }
+
+    // The sort is stable, so what happened at the same time stays in the order it was added in,
+    // which is the most recent first for each kind of activity.
+    activity.sort_by(|a, b| b.at.cmp(&a.at));
+    let has_more = activity.len() > offset.saturating_add(limit);
+    Ok(ActivityPage {
+        activity: activity.into_iter().skip(offset).take(limit).collect(),
+        has_more,
+    })
+}
+
+fn is_about_stack(
+    event: &ForgeEvent,
+    remote_url: &str,
+    pr_numbers: &HashSet<u64>,
+    commit_ids: &HashSet<String>,
+) -> bool {
+    match event {
+        ForgeEvent::PullRequest {
+            repository, number, ..
+        }
+        | ForgeEvent::Review {
+            repository, number, ..
+        } => is_remote_of(remote_url, repository) && pr_numbers.contains(number),
+        ForgeEvent::Checks { sha, .. } => commit_ids.contains(sha),
+        // Merge groups are made of several pull requests, which aren't known by the event.
+        ForgeEvent::MergeGroup { .. } => false,
+    }
+}
+
+/// Whether `remote_url` is the URL of the forge repository named `full_name`, like `owner/name`.
+fn is_remote_of(remote_url: &str, full_name: &str) -> bool {
+    let url = remote_url.trim_end_matches('/').to_lowercase();
+    let url = url.strip_suffix(".git").unwrap_or(&url);
+    let full_name = full_name.to_lowercase();
+    url.ends_with(&format!("/{full_name}")) || url.ends_with(&format!(":{full_name}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_of_repository() {
+        for url in [
+            "https://github.com/gitbutlerapp/gitbutler.git",
+            "https://github.com/GitButlerApp/gitbutler/",
+            "git@github.com:gitbutlerapp/gitbutler.git",
+        ] {
+            assert!(is_remote_of(url, "gitbutlerapp/gitbutler"), "{url}");
+        }
+        assert!(!is_remote_of(
+            "https://github.com/gitbutlerapp/gitbutler-docs.git",
+            "gitbutlerapp/gitbutler"
+        ));
+        assert!(!is_remote_of(
+            "https://github.com/fork/gitbutler.git",
+            "gitbutlerapp/gitbutler"
+        ));
+    }
+
+    #[test]
+    fn forge_events_of_stack() {
+        let remote_url = "https://github.com/gitbutlerapp/gitbutler.git";
+        let pr_numbers = HashSet::from([42]);
+        let commit_ids = HashSet::from(["abc".to_owned()]);
+        let review = |repository: &str, number| ForgeEvent::Review {
+            repository: repository.into(),
+            number,
+            state: "approved".into(),
+        };
+        assert!(is_about_stack(
+            &review("gitbutlerapp/gitbutler", 42),
+            remote_url,
+            &pr_numbers,
+            &commit_ids
+        ));
+        assert!(!is_about_stack(
+            &review("gitbutlerapp/gitbutler", 43),
+            remote_url,
+            &pr_numbers,
+            &commit_ids
+        ));
+        assert!(
+            !is_about_stack(
+                &review("other/repository", 42),
+                remote_url,
+                &pr_numbers,
+                &commit_ids
+            ),
+            "pull requests are numbered per repository"
+        );
+        assert!(is_about_stack(
+            &ForgeEvent::Checks {
+                repository: "gitbutlerapp/gitbutler".into(),
+                sha: "abc".into(),
+                status: "completed".into(),
+                conclusion: Some("success".into()),
+            },
+            remote_url,
+            &pr_numbers,
+            &commit_ids
+        ));
+    }
+}
@@ -0,0 +1,703 @@
+//! Applying patches as they are exchanged by mail, or written by `git diff`, onto a stack.
+//!
+//! The input is either an mbox as written by `git format-patch`, with one message per patch, or a
+//! plain unified diff. Each message becomes a commit with its author, date and message, and a
+//! plain diff becomes a single commit by the user. Like with `git apply --reject`, the hunks of a
+//! patch that don't apply are left out and reported, while the rest is committed.
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use bstr::{BString, ByteSlice as _};
+use gitbutler_command_context::CommandContext;
+use gitbutler_commit::commit_ext::CommitExt as _;
+use gitbutler_error::error::Code;
+use gitbutler_oxidize::gix_to_git2_signature;
+use gitbutler_project::access::WorktreeWritePermission;
+use gitbutler_repo::RepositoryExt as _;
+use gitbutler_stack::StackId;
+use serde::Serialize;
+
+use crate::{
+    branch_trees::{checkout_branch_trees, compute_updated_branch_head, BranchHeadAndTree},
+    status::get_applied_status,
+    VirtualBranchesExt as _,
+};
+
+/// The message of the commit made of a patch that doesn't come with one.
+const DEFAULT_MESSAGE: &str = "Apply patch";
+
+/// What [`apply_patch()`] committed, and what it couldn't apply.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyPatchReport {
+    /// The commits made of the patches, in application order.
+    #[serde(with = "gitbutler_serde::oid_vec")]
+    pub commit_ids: Vec<git2::Oid>,
+    pub rejected: Vec<RejectedHunk>,
+}
+
+/// A hunk of a patch that wasn't applied, or a whole file if `hunk` isn't set.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectedHunk {
+    /// The subject of the patch the hunk belongs to.
+    pub patch: String,
+    pub path: PathBuf,
+    /// The `@@ -a,b +c,d @@` header of the hunk.
+    pub hunk: Option<String>,
+    pub reason: RejectReason,
+}
+
+/// Why a part of a patch wasn't applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RejectReason {
+    /// The lines the hunk changes aren't in the file.
+    DoesNotApply,
+    /// The file the patch changes doesn't exist.
+    MissingFile,
+    /// The file the patch adds already exists.
+    FileExists,
+    /// The patch changes a binary file, which isn't supported.
+    Binary,
+}
+
+/// Apply the patches in `patch` as new commits on top of the stack with `stack_id`, and return the
+/// commits along with the hunks that were rejected.
+///
+/// This fails with [`Code::Validation`] if there is no patch in the input. Uncommitted changes of
+/// the stack stay uncommitted, and nothing is applied if they conflict with the patches.
+pub(crate) fn apply_patch(
+    ctx: &CommandContext,
+    stack_id: StackId,
+    patch: &[u8],
+    perm: &mut WorktreeWritePermission,
+) -> Result<ApplyPatchReport> {
+    ctx.assure_resolved()?;
+    let messages = parse(patch);
+    if messages.iter().all(|message| message.files.is_empty()) {
+        return Err(anyhow!("There is no patch to apply").context(Code::Validation));
+    }
+
+    // The status brings the uncommitted changes of the stack up to date.
+    let (mut stack, _) = get_applied_status(ctx, None)?
+        .branches
+        .into_iter()
+        .find(|(stack, _)| stack.id == stack_id)
+        .with_context(|| format!("could not find applied branch with id {stack_id}"))?;
+    let repo = ctx.repository();
+    let (user, committer) = repo.signatures()?;
+
+    let mut report = ApplyPatchReport::default();
+    let mut head = repo.find_commit(stack.head())?;
+    for message in &messages {
+        let subject = message.subject();
+        let tree = apply_files(repo, &head.tree()?, message, &subject, &mut report.rejected)?;
+        if tree.id() == head.tree_id() {
+            continue;
+        }
+        let author = match &message.author {
+            Some(author) => author.signature()?,
+            None => user.clone(),
+        };
+        let commit_id = repo
+            .commit_with_signature(
+                None,
+                &author,
+                &committer,
+                message.message.as_deref().unwrap_or(DEFAULT_MESSAGE),
+                &tree,
+                &[&head],
+                None,
+            )
+            .context("failed to commit patch")?;
+        report.commit_ids.push(commit_id);
+        head = repo.find_commit(commit_id)?;
+    }
+    if report.commit_ids.is_empty() {
+        return Ok(report);
+    }
+    crate::policy::assure_commits_allowed(ctx, &stack, &report.commit_ids)?;
+
+    let BranchHeadAndTree { head, tree } = compute_updated_branch_head(repo, &stack, head.id())?;
+    if repo.find_commit(head)?.is_conflicted() {
+        bail!("The patch conflicts with the uncommitted changes of the branch");
+    }
+    stack.set_stack_head(ctx, head, Some(tree))?;
+    checkout_branch_trees(ctx, perm)?;
+    crate::integration::update_workspace_commit(&ctx.project().virtual_branches(), ctx)
+        .context("failed to update gitbutler workspace")?;
+    Ok(report)
+}
+
+/// Return `tree` with the changes of the files in `message` applied, and add everything that
+/// couldn't be applied to `rejected`.
+fn apply_files<'repo>(
+    repo: &'repo git2::Repository,
+    tree: &git2::Tree,
+    message: &Message,
+    subject: &str,
+    rejected: &mut Vec<RejectedHunk>,
+) -> Result<git2::Tree<'repo>> {
+    let mut builder = git2::build::TreeUpdateBuilder::new();
+    for file in &message.files {
+        let Some(path) = file.new_path.as_ref().or(file.old_path.as_ref()) else {
+            continue;
+        };
+        let path = file_path(path);
+        let mut reject = |hunk: Option<&BString>, reason| {
+            rejected.push(RejectedHunk {
+                patch: subject.to_owned(),
+                path: path.clone(),
+                hunk: hunk.map(|hunk| {
+                    hunk.lines()
+                        .next()
+                        .unwrap_or_default()
+                        .to_str_lossy()
+                        .into_owned()
+                }),
+                reason,
+            })
+        };
+        if file.binary {
+            reject(None, RejectReason::Binary);
+            continue;
+        }
+
+        let old_entry = match &file.old_path {
+            Some(old_path) => match tree.get_path(&file_path(old_path)) {
+                Ok(entry) => Some(entry),
+                Err(_) => {
+                    reject(None, RejectReason::MissingFile);
+                    continue;
+                }
+            },
+            None => {
+                if file
+                    .new_path
+                    .as_ref()
+                    .is_some_and(|new_path| tree.get_path(&file_path(new_path)).is_ok())
+                {
+                    reject(None, RejectReason::FileExists);
+                    continue;
+                }
+                None
+            }
+        };
+        let mut content = match &old_entry {
+            Some(entry) => repo.find_blob(entry.id())?.content().to_owned(),
+            None => Vec::new(),
+        };
+        let mut all_applied = true;
+        for hunk in &file.hunks {
+            let applied = diffy::Patch::from_bytes(hunk)
+                .ok()
+                .and_then(|patch| diffy::apply_bytes(&content, &patch).ok());
+            match applied {
+                Some(applied) => content = applied,
+                None => {
+                    all_applied = false;
+                    reject(Some(hunk), RejectReason::DoesNotApply);
+                }
+            }
+        }
+
+        match (&file.old_path, &file.new_path) {
+            (Some(old_path), None) => {
+                // A file is only deleted if all of it was.
+                if all_applied {
+                    builder.remove(file_path(old_path));
+                }
+            }
+            (old_path, Some(new_path)) => {
+                let mode = match (file.new_mode, &old_entry) {
+                    (Some(0o100755), _) => git2::FileMode::BlobExecutable,
+                    (Some(_), _) | (None, None) => git2::FileMode::Blob,
+                    (None, Some(entry))
+                        if entry.filemode() == i32::from(git2::FileMode::BlobExecutable) =>
+                    {
+                        git2::FileMode::BlobExecutable
+                    }
+                    (None, Some(_)) => git2::FileMode::Blob,
+                };
+                if let Some(old_path) = old_path.as_ref().filter(|old_path| *old_path != new_path) {
+                    builder.remove(file_path(old_path));
+                }
+                builder.upsert(file_path(new_path), repo.blob(&content)?, mode);
+            }
+            (None, None) => {}
+        }
+    }
+    let tree_id = builder.create_updated(repo, tree)?;
+    Ok(repo.find_tree(tree_id)?)
+}
+
+fn file_path(path: &BString) -> PathBuf {
+    PathBuf::from(path.to_str_lossy().into_owned())
+}
+
+/// A patch, with the commit information it came with in a mail.
+#[derive(Debug, Default, PartialEq)]
+struct Message {
+    author: Option<Author>,
+    message: Option<String>,
+    files: Vec<FilePatch>,
+}
+
+impl Message {
+    fn subject(&self) -> String {
+        self.message
+            .as_deref()
+            .unwrap_or(DEFAULT_MESSAGE)
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_owned()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Author {
+    name: String,
+    email: String,
+    /// The `Date` header of the mail.
+    date: Option<String>,
+}
+
+impl Author {
+    fn signature(&self) -> Result<git2::Signature<'static>> {
+        let time = match &self.date {
+            Some(date) => gix::date::parse(date, Some(std::time::SystemTime::now()))
+                .with_context(|| format!("Invalid date of patch: {date}"))?,
+            None => gix::date::Time::now_local_or_utc(),
+        };
+        gix_to_git2_signature(gix::actor::SignatureRef {
+            name: self.name.as_str().into(),
+            email: self.email.as_str().into(),
+            time,
+        })
+    }
+}
+
+/// The changes of a patch to one file, with `None` as path for the side where it doesn't exist.
+#[derive(Debug, Default, PartialEq)]
+struct FilePatch {
+    old_path: Option<BString>,
+    new_path: Option<BString>,
+    new_mode: Option<u32>,
+    binary: bool,
+    /// The hunks, each starting with its `@@` line.
+    hunks: Vec<BString>,
+}
+
+/// Split `input` into the patches it contains.
+fn parse(input: &[u8]) -> Vec<Message> {
+    let lines: Vec<&[u8]> = input.lines_with_terminator().collect();
+    let mut starts: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| is_mbox_separator(line))
+        .map(|(index, _)| index + 1)
+        .collect();
+    if starts.is_empty() {
+        starts.push(0);
+    }
+    starts
+        .iter()
+        .enumerate()
+        .map(|(index, start)| {
+            let end = starts.get(index + 1).map_or(lines.len(), |next| next - 1);
+            parse_message(&lines[*start..end])
+        })
+        .collect()
+}
+
+/// Whether `line` starts a message in an mbox, like `From 1234abcd Mon Sep 17 00:00:00 2001`.
+fn is_mbox_separator(line: &[u8]) -> bool {
+    line.strip_prefix(b"From ").is_some_and(|rest| {
+        let hash = rest.split_str(" ").next().unwrap_or_default();
+        hash.len() >= 40 && hash.iter().all(u8::is_ascii_hexdigit)
+    })
+}
+
+fn parse_message(lines: &[&[u8]]) -> Message {
+    let mut message = Message::default();
+    let mut index = 0;
+    let has_headers = lines.first().is_some_and(|line| {
+        [&b"From: "[..], b"Subject: ", b"Date: "]
+            .iter()
+            .any(|header| line.starts_with(header))
+    });
+    if has_headers {
+        let mut headers: Vec<(String, String)> = Vec::new();
+        while let Some(line) = lines.get(index) {
+            index += 1;
+            let line = line.to_str_lossy();
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.is_empty() {
+                break;
+            }
+            if line.starts_with([' ', '\t']) {
+                if let Some((_, value)) = headers.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+            } else if let Some((key, value)) = line.split_once(':') {
+                headers.push((key.to_ascii_lowercase(), value.trim().to_owned()));
+            }
+        }
+        let header = |key: &str| {
+            headers
+                .iter()
+                .find(|(other, _)| other == key)
+                .map(|(_, value)| decode_header(value))
+        };
+
+        let mut body = String::new();
+        while let Some(line) = lines.get(index) {
+            if trim_line_end(line) == b"---" || starts_diff(lines, index) {
+                break;
+            }
+            body.push_str(&line.to_str_lossy());
+            index += 1;
+        }
+        let subject = header("subject").map(|subject| strip_subject_prefixes(&subject));
+        message.message = subject.map(|subject| match body.trim() {
+            "" => subject,
+            body => format!("{subject}\n\n{body}"),
+        });
+        message.author = header("from").and_then(|from| {
+            let (name, email) = from.strip_suffix('>')?.rsplit_once('<')?;
+            Some(Author {
+                name: name.trim().trim_matches('"').to_owned(),
+                email: email.trim().to_owned(),
+                date: header("date"),
+            })
+        });
+    }
+
+    while index < lines.len() && !starts_diff(lines, index) {
+        index += 1;
+    }
+    message.files = parse_files(&lines[index..]);
+    message
+}
+
+/// Whether the diff of a file starts at `lines[index]`.
+fn starts_diff(lines: &[&[u8]], index: usize) -> bool {
+    let line = lines[index];
+    line.starts_with(b"diff --git ")
+        || (line.starts_with(b"--- ")
+            && lines
+                .get(index + 1)
+                .is_some_and(|next| next.starts_with(b"+++ ")))
+}
+
+fn parse_files(lines: &[&[u8]]) -> Vec<FilePatch> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    // Whether the last file started with a `diff --git` line, and its `---` and `+++` lines are
+    // still to come.
+    let mut awaiting_paths = false;
+    let mut index = 0;
+    while let Some(line) = lines.get(index) {
+        let line = trim_line_end(line);
+        if let Some(paths) = line.strip_prefix(b"diff --git ") {
+            let (old_path, new_path) = paths.split_once_str(" b/").unwrap_or((paths, paths));
+            files.push(FilePatch {
+                old_path: Some(strip_path_prefix(old_path, b"a/")),
+                new_path: Some(new_path.into()),
+                ..Default::default()
+            });
+            awaiting_paths = true;
+            index += 1;
+        } else if starts_diff(lines, index) {
+            // Plain diffs have no `diff --git` line.
+            if !awaiting_paths {
+                files.push(FilePatch::default());
+            }
+            awaiting_paths = false;
+            let file = files.last_mut().expect("there is a file");
+            file.old_path = diff_path(&line[4..], b"a/");
+            file.new_path = diff_path(&trim_line_end(lines[index + 1])[4..], b"b/");
+            index += 2;
+        } else if line.starts_with(b"@@ ") {
+            awaiting_paths = false;
+            let (hunk, len) = parse_hunk(&lines[index..]);
+            if let Some(file) = files.last_mut() {
+                file.hunks.push(hunk);
+            }
+            index += len;
+        } else {
+            if let Some(file) = files.last_mut() {
+                parse_extended_header(file, line);
+            }
+            index += 1;
+        }
+    }
+    files
+}
+
+fn trim_line_end(line: &[u8]) -> &[u8] {
+    line.trim_end_with(|c| c == '\r' || c == '\n')
+}
+
+/// Apply the extended header `line` of `git diff`, like `new file mode 100644`, to `file`.
+fn parse_extended_header(file: &mut FilePatch, line: &[u8]) {
+    let mode = |value: &[u8]| u32::from_str_radix(value.to_str().ok()?.trim(), 8).ok();
+    if let Some(value) = line.strip_prefix(b"new file mode ") {
+        file.old_path = None;
+        file.new_mode = mode(value);
+    } else if line.starts_with(b"deleted file mode ") {
+        file.new_path = None;
+    } else if let Some(value) = line.strip_prefix(b"new mode ") {
+        file.new_mode = mode(value);
+    } else if let Some(path) = line.strip_prefix(b"rename from ") {
+        file.old_path = Some(path.into());
+    } else if let Some(path) = line.strip_prefix(b"rename to ") {
+        file.new_path = Some(path.into());
+    } else if line.starts_with(b"GIT binary patch")
+        || (line.starts_with(b"Binary files ") && line.ends_with(b" differ"))
+    {
+        file.binary = true;
+    }
+}
+
+/// Return the hunk starting with the `@@` header in `lines[0]`, and the number of lines it takes.
+///
+/// The lines of the hunk are counted as the header says, so that lines like the `-- ` separating
+/// the signature of a mail aren't taken for removed lines.
+fn parse_hunk(lines: &[&[u8]]) -> (BString, usize) {
+    let header = lines[0].to_str_lossy();
+    let (mut old, mut new) = hunk_line_counts(&header).unwrap_or_default();
+    let mut hunk = BString::from(lines[0]);
+    let mut len = 1;
+    while let Some(line) = lines.get(len) {
+        match line.first() {
+            Some(b'\\') => {}
+            _ if old == 0 && new == 0 => break,
+            Some(b' ') | Some(b'\r') | Some(b'\n') => {
+                old = old.saturating_sub(1);
+                new = new.saturating_sub(1);
+            }
+            Some(b'-') => old = old.saturating_sub(1),
+            Some(b'+') => new = new.saturating_sub(1),
+            _ => break,
+        }
+        hunk.extend_from_slice(line);
+        len += 1;
+    }
+    (hunk, len)
+}
+
+/// The number of old and new lines in a hunk with `header`, like `@@ -1,3 +1,4 @@`.
+fn hunk_line_counts(header: &str) -> Option<(usize, usize)> {
+    let mut ranges = header.strip_prefix("@@ ")?.split(' ');
+    let count = |range: &str| match range.split_once(',') {
+        Some((_, count)) => count.parse().ok(),
+        None => Some(1),
+    };
+    let old = count(ranges.next()?.strip_prefix('-')?)?;
+    let new = count(ranges.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+/// The path in a `---` or `+++` line without `prefix`, or `None` for `/dev/null`.
+fn diff_path(value: &[u8], prefix: &[u8]) -> Option<BString> {
+    // Diffs by tools other than Git may have a timestamp after the path.
+    let path = value.split_str("\t").next().unwrap_or_default();
+    (path != b"/dev/null").then(|| strip_path_prefix(path, prefix))
+}
+
+fn strip_path_prefix(path: &[u8], prefix: &[u8]) -> BString {
+    path.strip_prefix(prefix).unwrap_or(path).into()
+}
+
+/// Strip prefixes like `[PATCH v2 1/3]` from `subject`, like `git am` does.
+fn strip_subject_prefixes(subject: &str) -> String {
+    let mut subject = subject.trim();
+    while let Some(rest) = subject.strip_prefix('[') {
+        match rest.split_once(']') {
+            Some((_, rest)) => subject = rest.trim_start(),
+            None => break,
+        }
+    }
+    subject.to_owned()
+}
+
+/// Decode the words of `value` that are encoded as `=?UTF-8?q?...?=`, like `git format-patch`
+/// writes non-ASCII names and subjects.
+fn decode_header(value: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("=?") {
+        let Some((charset_and_encoding, text)) = rest[start + 2..]
+            .split_once("?q?")
+            .or_else(|| rest[start + 2..].split_once("?Q?"))
+        else {
+            break;
+        };
+        let Some((text, after)) = text.split_once("?=") else {
+            break;
+        };
+        if charset_and_encoding.contains(['?', ' ']) {
+            break;
+        }
+        decoded.push_str(&rest[..start]);
+        decoded.push_str(&decode_q(text));
+        // Whitespace between encoded words isn't part of the text.
+        rest = match after.trim_start().starts_with("=?") {
+            true => after.trim_start(),
+            false => after,
+        };
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_q(text: &str) -> String {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.bytes();
+    while let Some(byte) = chars.next() {
+        match byte {
+            b'_' => bytes.push(b' '),
+            b'=' => {
+                let hex = [chars.next().unwrap_or(b'0'), chars.next().unwrap_or(b'0')];
+                match std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(byte) => bytes.push(byte),
+                    None => bytes.extend_from_slice(&hex),
+                }
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MBOX: &str = r#"From 0123456789abcdef0123456789abcdef01234567 Mon Sep 17 00:00:00 2001
+From: =?UTF-8?q?J=C3=B6rg_Doe?= <joerg@example.com>
+Date: Mon, 14 Oct 2024 12:00:00 +0200
+Subject: [PATCH 1/2] Change the greeting and
+ say goodbye
+
+The greeting was too short.
+---
+ greeting.txt | 3 ++-
+ 1 file changed, 2 insertions(+), 1 deletion(-)
+
+diff --git a/greeting.txt b/greeting.txt
+index 1234567..89abcde 100644
+--- a/greeting.txt
++++ b/greeting.txt
+@@ -1,2 +1,3 @@
+--- hello
++hello world
+ line
++goodbye
+-- 
+2.47.0
+
+From 89abcdef0123456789abcdef0123456789abcdef Mon Sep 17 00:00:00 2001
+From: Jane Doe <jane@example.com>
+Date: Mon, 14 Oct 2024 13:00:00 +0200
+Subject: [PATCH 2/2] Add a script
+
+---
+diff --git a/script.sh b/script.sh
+new file mode 100755
+index 0000000..1234567
+--- /dev/null
++++ b/script.sh
+@@ -0,0 +1 @@
++echo hi
+-- 
+2.47.0
+"#;
+
+    #[test]
+    fn mbox_is_split_into_messages() {
+        let messages = parse(MBOX.as_bytes());
+        assert_eq!(messages.len(), 2);
+
+        let first = &messages[0];
+        assert_eq!(
+            first.author,
+            Some(Author {
+                name: "Jörg Doe".into(),
+                email: "joerg@example.com".into(),
+                date: Some("Mon, 14 Oct 2024 12:00:00 +0200".into()),
+            })
+        );
+        assert_eq!(
+            first.message.as_deref(),
+            Some("Change the greeting and say goodbye\n\nThe greeting was too short.")
+        );
+        assert_eq!(
+            first.files,
+            vec![FilePatch {
+                old_path: Some("greeting.txt".into()),
+                new_path: Some("greeting.txt".into()),
+                hunks: vec!["@@ -1,2 +1,3 @@\n--- hello\n+hello world\n line\n+goodbye\n".into()],
+                ..Default::default()
+            }],
+            "the signature isn't part of the hunk, even though it looks like a removed line"
+        );
+
+        let second = &messages[1];
+        assert_eq!(second.subject(), "Add a script");
+        assert_eq!(
+            second.files,
+            vec![FilePatch {
+                old_path: None,
+                new_path: Some("script.sh".into()),
+                new_mode: Some(0o100755),
+                hunks: vec!["@@ -0,0 +1 @@\n+echo hi\n".into()],
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn plain_diffs_have_no_message() {
+        let messages = parse(
+            b"--- a/one.txt\t2024-10-14 12:00:00\n+++ b/one.txt\n@@ -1 +1 @@\n-a\n+b\n--- two.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-c\n",
+        );
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].message, None);
+        assert_eq!(messages[0].subject(), DEFAULT_MESSAGE);
+        assert_eq!(
+            messages[0].files,
+            vec![
+                FilePatch {
+                    old_path: Some("one.txt".into()),
+                    new_path: Some("one.txt".into()),
+                    hunks: vec!["@@ -1 +1 @@\n-a\n+b\n".into()],
+                    ..Default::default()
+                },
+                FilePatch {
+                    old_path: Some("two.txt".into()),
+                    new_path: None,
+                    hunks: vec!["@@ -1 +0,0 @@\n-c\n".into()],
+                    ..Default::default()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn subject_prefixes_are_stripped() {
+        assert_eq!(strip_subject_prefixes("[PATCH v2 3/7] fix it"), "fix it");
+        assert_eq!(strip_subject_prefixes("[RFC][PATCH] fix it"), "fix it");
+        assert_eq!(strip_subject_prefixes("fix [it]"), "fix [it]");
+        assert_eq!(
+            decode_header("=?UTF-8?q?caf=C3=A9?= =?UTF-8?q?_au_lait?= <c@example.com>"),
+            "café au lait <c@example.com>"
+        );
+    }
+}
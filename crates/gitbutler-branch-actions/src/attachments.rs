@@ -0,0 +1,277 @@
+//! Links to evidence about a commit, like the CI run that tested it, the review that approved it
+//! or the issue it fixes.
+//!
+//! Attachments are keyed by the change id of a commit, which is carried along when it is rebased,
+//! amended or reworded, so they survive rewrites. They are persisted in `commit_attachments.toml`
+//! and, if `gitbutler.mirrorAttachmentsToNotes` is set, also written as notes on the commit. Notes
+//! are keyed by commit id instead, so they are written again for the rewritten commits whenever
+//! the workspace is updated.
+use std::{collections::BTreeMap, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use gitbutler_command_context::CommandContext;
+use gitbutler_commit::commit_ext::CommitExt;
+use gitbutler_config::git::GitConfig;
+use gitbutler_repo::{LogUntil, RepositoryExt as _, SignaturePurpose};
+use gitbutler_stack::Stack;
+use serde::{Deserialize, Serialize};
+
+/// The notes reference attachments are mirrored to.
+pub const ATTACHMENTS_NOTES_REF: &str = "refs/notes/gitbutler-attachments";
+
+const ATTACHMENTS_FILE_NAME: &str = "commit_attachments.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AttachmentKind {
+    CiRun,
+    Review,
+    Issue,
+    Document,
+    Other,
+}
+
+impl std::fmt::Display for AttachmentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            AttachmentKind::CiRun => "ci-run",
+            AttachmentKind::Review => "review",
+            AttachmentKind::Issue => "issue",
+            AttachmentKind::Document => "document",
+            AttachmentKind::Other => "other",
+        })
+    }
+}
+
+/// A link attached to a commit. A commit has at most one attachment per URL.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitAttachment {
+    pub kind: AttachmentKind,
+    pub url: String,
+    /// A human readable description, e.g. the title of the issue.
+    pub title: Option<String>,
+    /// When it was attached, in milliseconds since the Unix epoch.
+    pub created_at: i64,
+}
+
+/// The persisted attachments of all commits, by their change id.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Attachments {
+    #[serde(default)]
+    by_change_id: BTreeMap<String, Vec<CommitAttachment>>,
+}
+
+fn attachments_path(ctx: &CommandContext) -> PathBuf {
+    ctx.project().gb_dir().join(ATTACHMENTS_FILE_NAME)
+}
+
+fn read_attachments(ctx: &CommandContext) -> Result<Attachments> {
+    gitbutler_fs::read_toml_file_or_default(&attachments_path(ctx))
+}
+
+fn write_attachments(ctx: &CommandContext, attachments: &Attachments) -> Result<()> {
+    let contents =
+        toml::to_string(attachments).context("Failed to serialize commit attachments")?;
+    gitbutler_fs::write(attachments_path(ctx), contents)
+        .context("Failed to write commit attachments")
+}
+
+fn change_id(commit: &git2::Commit) -> Result<String> {
+    commit.change_id().with_context(|| {
+        format!(
+            "Commit {} has no change id, so attachments wouldn't survive it being rewritten",
+            commit.id()
+        )
+    })
+}
+
+/// Return the attachments of the commit with `commit_oid`, oldest first.
+pub fn list(ctx: &CommandContext, commit_oid: git2::Oid) -> Result<Vec<CommitAttachment>> {
+    let commit = ctx.repository().find_commit(commit_oid)?;
+    let Some(change_id) = commit.change_id() else {
+        return Ok(Vec::new());
+    };
+    Ok(read_attachments(ctx)?
+        .by_change_id
+        .remove(&change_id)
+        .unwrap_or_default())
+}
+
+/// Attach `url` to the commit with `commit_oid`, replacing the attachment with the same URL if there is one.
+pub fn add(
+    ctx: &CommandContext,
+    commit_oid: git2::Oid,
+    kind: AttachmentKind,
+    url: &str,
+    title: Option<String>,
+) -> Result<CommitAttachment> {
+    let parsed_url = url::Url::parse(url).with_context(|| format!("Invalid URL '{url}'"))?;
+    if !matches!(parsed_url.scheme(), "http" | "https") {
+        bail!("Only http and https URLs can be attached, got '{url}'");
+    }
+    let commit = ctx.repository().find_commit(commit_oid)?;
+    let change_id = change_id(&commit)?;
+
+    let attachment = CommitAttachment {
+        kind,
+        url: parsed_url.to_string(),
+        title: title.filter(|title| !title.trim().is_empty()),
+        created_at: gitbutler_time::time::now_since_unix_epoch_ms(),
+    };
+    let mut attachments = read_attachments(ctx)?;
+    let commit_attachments = attachments.by_change_id.entry(change_id).or_default();
+    commit_attachments.retain(|existing| existing.url != attachment.url);
+    commit_attachments.push(attachment.clone());
+    let commit_attachments = commit_attachments.clone();
+    write_attachments(ctx, &attachments)?;
+
+    mirror_to_notes(ctx, &commit, &commit_attachments)?;
+    Ok(attachment)
+}
+
+/// Remove the attachment with `url` from the commit with `commit_oid`, if it has it.
+pub fn remove(ctx: &CommandContext, commit_oid: git2::Oid, url: &str) -> Result<()> {
+    // Attached URLs are normalized, so the URL as it was passed to `add()` matches too.
+    let url = url::Url::parse(url).map_or_else(|_| url.to_owned(), |url| url.to_string());
+    let commit = ctx.repository().find_commit(commit_oid)?;
+    let change_id = change_id(&commit)?;
+
+    let mut attachments = read_attachments(ctx)?;
+    let Some(commit_attachments) = attachments.by_change_id.get_mut(&change_id) else {
+        return Ok(());
+    };
+    commit_attachments.retain(|existing| existing.url != url);
+    let commit_attachments = commit_attachments.clone();
+    if commit_attachments.is_empty() {
+        attachments.by_change_id.remove(&change_id);
+    }
+    write_attachments(ctx, &attachments)?;
+
+    mirror_to_notes(ctx, &commit, &commit_attachments)
+}
+
+/// Write the attachments of the commits of `stacks` as their notes if mirroring is enabled, unless
+/// they have them already. This carries notes over to commits that were rewritten.
+pub(crate) fn mirror_stacks_to_notes(
+    ctx: &CommandContext,
+    stacks: &[Stack],
+    target_sha: git2::Oid,
+) -> Result<()> {
+    if !mirroring_enabled(ctx)? {
+        return Ok(());
+    }
+    let attachments = read_attachments(ctx)?;
+    if attachments.by_change_id.is_empty() {
+        return Ok(());
+    }
+    let repo = ctx.repository();
+    for stack in stacks {
+        for commit_oid in repo.l(stack.head(), LogUntil::Commit(target_sha), false)? {
+            let commit = repo.find_commit(commit_oid)?;
+            let Some(commit_attachments) = commit
+                .change_id()
+                .and_then(|change_id| attachments.by_change_id.get(&change_id))
+            else {
+                continue;
+            };
+            let up_to_date = repo
+                .find_note(Some(ATTACHMENTS_NOTES_REF), commit_oid)
+                .is_ok_and(|note| {
+                    note.message() == Some(note_message(commit_attachments).as_str())
+                });
+            if !up_to_date {
+                write_note(ctx, &commit, commit_attachments)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn mirroring_enabled(ctx: &CommandContext) -> Result<bool> {
+    Ok(ctx
+        .repository()
+        .gb_config()?
+        .mirror_attachments_to_notes
+        .unwrap_or(false))
+}
+
+/// Write `attachments` as the note of `commit` if mirroring is enabled, or remove the note if there are none.
+fn mirror_to_notes(
+    ctx: &CommandContext,
+    commit: &git2::Commit,
+    attachments: &[CommitAttachment],
+) -> Result<()> {
+    if !mirroring_enabled(ctx)? {
+        return Ok(());
+    }
+    write_note(ctx, commit, attachments)
+}
+
+/// Write `attachments` as the note of `commit`, or remove the note if there are none.
+fn write_note(
+    ctx: &CommandContext,
+    commit: &git2::Commit,
+    attachments: &[CommitAttachment],
+) -> Result<()> {
+    let repo = ctx.repository();
+    let signature = gitbutler_repo::signature(SignaturePurpose::Committer)?;
+    if attachments.is_empty() {
+        return match repo.note_delete(
+            commit.id(),
+            Some(ATTACHMENTS_NOTES_REF),
+            &signature,
+            &signature,
+        ) {
+            Err(err) if err.code() != git2::ErrorCode::NotFound => Err(err.into()),
+            _ => Ok(()),
+        };
+    }
+    repo.note(
+        &signature,
+        &signature,
+        Some(ATTACHMENTS_NOTES_REF),
+        commit.id(),
+        &note_message(attachments),
+        true,
+    )
+    .context("Failed to mirror commit attachments to notes")?;
+    Ok(())
+}
+
+/// One line per attachment, like `review: https://github.com/org/repo/pull/1 (Fix the parser)`.
+fn note_message(attachments: &[CommitAttachment]) -> String {
+    attachments
+        .iter()
+        .map(|attachment| match &attachment.title {
+            Some(title) => format!("{}: {} ({title})\n", attachment.kind, attachment.url),
+            None => format!("{}: {}\n", attachment.kind, attachment.url),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn note_lists_one_attachment_per_line() {
+        let attachment = |kind, url: &str, title: Option<&str>| CommitAttachment {
+            kind,
+            url: url.into(),
+            title: title.map(Into::into),
+            created_at: 0,
+        };
+        assert_eq!(
+            note_message(&[
+                attachment(
+                    AttachmentKind::Review,
+                    "https://github.com/org/repo/pull/1",
+                    Some("Fix the parser")
+                ),
+                attachment(AttachmentKind::CiRun, "https://ci.example.com/runs/2", None),
+            ]),
+            "review: https://github.com/org/repo/pull/1 (Fix the parser)\nci-run: https://ci.example.com/runs/2\n"
+        );
+    }
+}
@@ -12,7 +12,12 @@ impl From<git2::Signature<'_>> for Author {
     fn from(value: git2::Signature) -> Self {
         let name = value.name().unwrap_or_default().to_string();
         let email = value.email().unwrap_or_default().to_string();
+        Author::new(name, email)
+    }
+}
 
+impl Author {
+    pub(crate) fn new(name: String, email: String) -> Self {
         let gravatar_url = url::Url::parse(&format!(
             "https://www.gravatar.com/avatar/{:x}?s=100&r=g&d=retro",
             md5::compute(email.to_lowercase())
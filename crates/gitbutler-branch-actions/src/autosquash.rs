@@ -0,0 +1,386 @@
+use anyhow::{bail, Context as _, Result};
+use bstr::ByteSlice as _;
+use gitbutler_command_context::CommandContext;
+use gitbutler_commit::{commit_ext::CommitExt as _, commit_headers::HasCommitHeaders as _};
+use gitbutler_repo::{rebase::cherry_rebase_group, LogUntil, RepositoryExt as _};
+use gitbutler_stack::StackId;
+use itertools::Itertools as _;
+
+use crate::VirtualBranchesExt as _;
+
+/// How a commit is folded into the commit it targets, like the commands of
+/// `git rebase --autosquash`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fold {
+    /// `fixup!`: keep the message of the target.
+    Fixup,
+    /// `squash!`: append the message of the commit to the one of the target.
+    Squash,
+    /// `amend!`: replace the message of the target with the one of the commit.
+    Amend,
+}
+
+const PREFIXES: [(&str, Fold); 3] = [
+    ("fixup! ", Fold::Fixup),
+    ("squash! ", Fold::Squash),
+    ("amend! ", Fold::Amend),
+];
+
+/// Fold the `fixup!`, `squash!` and `amend!` commits of the stack with `stack_id` into the
+/// commits they target, and rebase the other commits onto the result.
+///
+/// A commit targets the first commit below it whose subject is what follows the prefix, or whose
+/// ID or change ID starts with it, or, failing that, whose subject starts with it. Commits
+/// without a target stay where they are. Returns the number of commits that were folded.
+///
+/// Nothing changes if moving a commit next to its target would conflict.
+pub(crate) fn autosquash(ctx: &CommandContext, stack_id: StackId) -> Result<usize> {
+    ctx.assure_resolved()?;
+
+    let vb_state = ctx.project().virtual_branches();
+    let mut stack = vb_state.get_branch_in_workspace(stack_id)?;
+    let default_target = vb_state.get_default_target()?;
+    let repository = ctx.repository();
+
+    let AutosquashResult {
+        new_head,
+        folded_into,
+    } = inner_autosquash(
+        repository,
+        stack.head(),
+        default_target.sha,
+        ctx.project().auto_resolve_whitespace,
+    )?;
+    if folded_into.is_empty() {
+        return Ok(0);
+    }
+    let folded_into_ids = folded_into
+        .iter()
+        .map(|(_, target)| *target)
+        .unique()
+        .collect_vec();
+    crate::policy::assure_commits_allowed(ctx, &stack, &folded_into_ids)?;
+
+    let pushed_commit_ids = stack.upstream_head.map_or_else(
+        || Ok(vec![]),
+        |upstream_head| repository.l(upstream_head, LogUntil::Commit(default_target.sha), false),
+    )?;
+    if !pushed_commit_ids.is_empty() && !stack.allow_rebasing {
+        let kept = repository.l(new_head, LogUntil::Commit(default_target.sha), false)?;
+        if pushed_commit_ids.iter().any(|id| !kept.contains(id)) {
+            // rewriting pushed commits will cause a force push that is not allowed
+            bail!("force push not allowed");
+        }
+    }
+
+    stack.set_stack_head(ctx, new_head, None)?;
+    for (folded_id, target_id) in &folded_into {
+        let folded = repository.find_commit(*folded_id)?;
+        let target = repository.find_commit(*target_id)?;
+        stack.replace_head(ctx, &folded, &target)?;
+    }
+    crate::integration::update_workspace_commit(&vb_state, ctx)
+        .context("failed to update gitbutler workspace")?;
+    Ok(folded_into.len())
+}
+
+struct AutosquashResult {
+    new_head: git2::Oid,
+    /// The folded commits, each with the new commit it was folded into.
+    folded_into: Vec<(git2::Oid, git2::Oid)>,
+}
+
+/// A commit of the stack, and the commits that are folded into it, in application order.
+struct Pick<'repo> {
+    commit: git2::Commit<'repo>,
+    folds: Vec<(git2::Commit<'repo>, Fold)>,
+}
+
+fn inner_autosquash(
+    repository: &git2::Repository,
+    stack_head: git2::Oid,
+    base: git2::Oid,
+    auto_resolve_whitespace: bool,
+) -> Result<AutosquashResult> {
+    let mut commits = repository
+        .l(stack_head, LogUntil::Commit(base), false)?
+        .into_iter()
+        .map(|id| repository.find_commit(id))
+        .collect::<Result<Vec<_>, _>>()?;
+    commits.reverse();
+
+    let mut picks: Vec<Pick> = Vec::with_capacity(commits.len());
+    let mut folded = 0;
+    for commit in commits {
+        let target = fold_of(&commit)
+            .and_then(|(spec, fold)| find_target(&picks, &spec).map(|position| (position, fold)));
+        match target {
+            Some((position, fold)) => {
+                picks[position].folds.push((commit, fold));
+                folded += 1;
+            }
+            None => picks.push(Pick {
+                commit,
+                folds: vec![],
+            }),
+        }
+    }
+    if folded == 0 {
+        return Ok(AutosquashResult {
+            new_head: stack_head,
+            folded_into: vec![],
+        });
+    }
+
+    // The commits below the first target keep their place, and everything above is rewritten.
+    let first_target = picks
+        .iter()
+        .position(|pick| !pick.folds.is_empty())
+        .expect("there is a fold");
+    let mut parent = picks[first_target]
+        .commit
+        .parent(0)
+        .context("failed to find parent commit")?;
+    let reordered: Vec<(&git2::Commit, Option<Fold>)> = picks[first_target..]
+        .iter()
+        .flat_map(|pick| {
+            std::iter::once((&pick.commit, None)).chain(
+                pick.folds
+                    .iter()
+                    .map(|(commit, fold)| (commit, Some(*fold))),
+            )
+        })
+        .collect();
+
+    // Move the folded commits next to their targets, and fail before anything is written to the
+    // stack if that conflicts.
+    let ids_to_rebase: Vec<git2::Oid> = reordered
+        .iter()
+        .rev()
+        .map(|(commit, _)| commit.id())
+        .collect();
+    let rebased_head = cherry_rebase_group(
+        repository,
+        parent.id(),
+        &ids_to_rebase,
+        auto_resolve_whitespace,
+    )?;
+    let mut rebased = repository.l(rebased_head, LogUntil::Commit(parent.id()), false)?;
+    rebased.reverse();
+    for ((original, _), rebased) in reordered.iter().zip(&rebased) {
+        if repository.find_commit(*rebased)?.is_conflicted() {
+            bail!(
+                "Autosquashing would conflict at commit {}",
+                original
+                    .message_bstr()
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .as_bstr()
+            );
+        }
+    }
+
+    // Each rebased folded commit has the tree of its target with all folds so far applied, so the
+    // folded commits are written from their trees without merging again.
+    let mut pending: Option<(git2::Commit, git2::Tree, String)> = None;
+    // The commits folded into `pending`, which learn their new target once it is written.
+    let mut pending_folds = Vec::new();
+    let mut folded_into = Vec::with_capacity(folded);
+    for ((original, fold), rebased) in reordered.into_iter().zip(rebased) {
+        let tree = repository.find_commit(rebased)?.tree()?;
+        match (fold, pending.as_mut()) {
+            (Some(fold), Some((_, pending_tree, message))) => {
+                pending_folds.push(original.id());
+                *pending_tree = tree;
+                let body = original
+                    .message_bstr()
+                    .to_str_lossy()
+                    .split_once('\n')
+                    .map(|(_, body)| body.trim().to_owned())
+                    .unwrap_or_default();
+                match fold {
+                    Fold::Squash if !body.is_empty() => {
+                        *message = format!("{}\n\n{body}", message.trim_end());
+                    }
+                    Fold::Amend if !body.is_empty() => *message = body,
+                    _ => {}
+                }
+            }
+            _ => {
+                if let Some(written) = write_pending(repository, &parent, pending.take())? {
+                    folded_into.extend(pending_folds.drain(..).map(|id| (id, written.id())));
+                    parent = written;
+                }
+                let message = original.message_bstr().to_str_lossy().into_owned();
+                pending = Some((original.clone(), tree, message));
+            }
+        }
+    }
+    let new_head = write_pending(repository, &parent, pending)?
+        .expect("there is a commit")
+        .id();
+    folded_into.extend(pending_folds.into_iter().map(|id| (id, new_head)));
+
+    Ok(AutosquashResult {
+        new_head,
+        folded_into,
+    })
+}
+
+fn write_pending<'repo>(
+    repository: &'repo git2::Repository,
+    parent: &git2::Commit<'repo>,
+    pending: Option<(git2::Commit<'repo>, git2::Tree<'repo>, String)>,
+) -> Result<Option<git2::Commit<'repo>>> {
+    let Some((commit, tree, message)) = pending else {
+        return Ok(None);
+    };
+    let commit_id = repository
+        .commit_with_signature(
+            None,
+            &commit.author(),
+            &commit.committer(),
+            &message,
+            &tree,
+            &[parent],
+            commit.gitbutler_headers(),
+        )
+        .context("failed to create commit")?;
+    Ok(Some(repository.find_commit(commit_id)?))
+}
+
+/// Return what the commit targets if it is to be folded, with the prefixes of its subject removed,
+/// and how it's folded as told by its first prefix.
+fn fold_of(commit: &git2::Commit) -> Option<(String, Fold)> {
+    let message = commit.message_bstr().to_str_lossy();
+    let mut subject = message.lines().next()?;
+    let mut first = None;
+    while let Some((rest, fold)) = PREFIXES
+        .iter()
+        .find_map(|(prefix, fold)| subject.strip_prefix(prefix).map(|rest| (rest, *fold)))
+    {
+        first.get_or_insert(fold);
+        subject = rest;
+    }
+    let fold = first?;
+    let subject = subject.trim();
+    (!subject.is_empty()).then(|| (subject.to_owned(), fold))
+}
+
+/// Return the position of the pick whose commit `spec` refers to.
+fn find_target(picks: &[Pick], spec: &str) -> Option<usize> {
+    let subject = |pick: &Pick| {
+        pick.commit
+            .message_bstr()
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_str_lossy()
+            .into_owned()
+    };
+    picks
+        .iter()
+        .position(|pick| subject(pick) == spec)
+        .or_else(|| {
+            picks.iter().position(|pick| {
+                pick.commit.id().to_string().starts_with(spec)
+                    || pick
+                        .commit
+                        .change_id()
+                        .is_some_and(|change_id| change_id.starts_with(spec))
+            })
+        })
+        .or_else(|| {
+            picks
+                .iter()
+                .position(|pick| subject(pick).starts_with(spec))
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use gitbutler_testsupport::testing_repository::{
+        assert_commit_tree_matches, TestingRepository,
+    };
+
+    use super::*;
+
+    #[test]
+    fn folds_commits_into_their_targets() {
+        let test_repository = TestingRepository::open();
+        let repository = &test_repository.repository;
+
+        let base = test_repository.commit_tree(None, &[("base.txt", "base")]);
+        let setup = test_repository.commit_on(&base, "setup", &[("base.txt", "setup")]);
+        let add_foo = test_repository.commit_on(
+            &setup,
+            "add foo",
+            &[("base.txt", "setup"), ("foo.txt", "1")],
+        );
+        let add_bar = test_repository.commit_on(
+            &add_foo,
+            "add bar\n\nwith a body",
+            &[("base.txt", "setup"), ("foo.txt", "1"), ("bar.txt", "1")],
+        );
+        let fixup = test_repository.commit_on(
+            &add_bar,
+            "fixup! add foo",
+            &[("base.txt", "setup"), ("foo.txt", "2"), ("bar.txt", "1")],
+        );
+        let squash = test_repository.commit_on(
+            &fixup,
+            "squash! add b\n\nmore bar",
+            &[("base.txt", "setup"), ("foo.txt", "2"), ("bar.txt", "2")],
+        );
+
+        let AutosquashResult {
+            new_head,
+            folded_into,
+        } = inner_autosquash(repository, squash.id(), base.id(), false).unwrap();
+
+        let new_head = repository.find_commit(new_head).unwrap();
+        assert_eq!(
+            new_head.message(),
+            Some("add bar\n\nwith a body\n\nmore bar"),
+            "'squash! add b' targets the first commit starting with 'add b'"
+        );
+        assert_commit_tree_matches(
+            repository,
+            &new_head,
+            &[("foo.txt", b"2"), ("bar.txt", b"2")],
+        );
+        let folded_foo = new_head.parent(0).unwrap();
+        assert_eq!(folded_foo.message(), Some("add foo"));
+        assert_commit_tree_matches(repository, &folded_foo, &[("foo.txt", b"2")]);
+        assert_eq!(
+            folded_foo.parent_id(0).unwrap(),
+            setup.id(),
+            "commits below the first target stay unchanged"
+        );
+        assert_eq!(
+            folded_into,
+            [(fixup.id(), folded_foo.id()), (squash.id(), new_head.id())]
+        );
+    }
+
+    #[test]
+    fn commits_without_target_stay() {
+        let test_repository = TestingRepository::open();
+        let repository = &test_repository.repository;
+
+        let base = test_repository.commit_tree(None, &[("base.txt", "base")]);
+        let fixup = test_repository.commit_tree_with_message(
+            Some(&base),
+            "fixup! nothing",
+            &[("base.txt", "changed")],
+        );
+
+        let AutosquashResult {
+            new_head,
+            folded_into,
+        } = inner_autosquash(repository, fixup.id(), base.id(), false).unwrap();
+        assert!(folded_into.is_empty());
+        assert_eq!(new_head, fixup.id());
+    }
+}
@@ -0,0 +1,175 @@
+//! Keeping uncommitted changes which aren't part of any stack across operations that rewrite the worktree.
+//!
+//! Operations like updating the workspace base or applying a branch check out the merged trees of the
+//! stacks, which would drop any changes the stacks don't know about. Instead, these changes are stashed
+//! into a snapshot before the operation and replayed on top of its result afterwards.
+//!
+//! Where a stashed change conflicts with the result of the operation, the file is left as the operation
+//! made it, and its path is reported until the report is dismissed. The stashed version can always be
+//! recovered by restoring the snapshot.
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use gitbutler_command_context::CommandContext;
+use gitbutler_oplog::{
+    entry::{OperationKind, SnapshotDetails, Trailer},
+    OplogExt,
+};
+use gitbutler_project::{access::WorktreeWritePermission, feature_flags::FeatureFlag};
+use gitbutler_repo::RepositoryExt as _;
+use gitbutler_stack::Stack;
+use serde::{Deserialize, Serialize};
+
+use crate::{branch_trees::merge_trees_of_branches, VirtualBranchesExt as _};
+
+const AUTOSTASH_FILE_NAME: &str = "autostash.toml";
+
+/// Stashed changes which couldn't be replayed cleanly.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoStashConflicts {
+    /// The snapshot taken before the operation, from which the stashed changes can be restored.
+    #[serde(with = "gitbutler_serde::oid")]
+    pub snapshot_id: git2::Oid,
+    /// The operation the changes were stashed for.
+    pub operation: OperationKind,
+    /// The paths whose stashed changes were dropped in favor of the result of the operation.
+    pub paths: Vec<PathBuf>,
+}
+
+/// The changes that were stashed before an operation, to be [replayed](Self::replay) after it.
+pub(crate) struct AutoStash {
+    snapshot_id: git2::Oid,
+    operation: OperationKind,
+    /// The tree the stacks had checked out before the operation.
+    base: git2::Oid,
+    /// The tree of the worktree before the operation, which includes the stashed changes.
+    worktree: git2::Oid,
+}
+
+impl AutoStash {
+    /// Stash the changes in the worktree that aren't in the merged trees of the `applied` stacks,
+    /// or return `None` if there are none, or stashing is turned off.
+    pub(crate) fn capture(
+        ctx: &CommandContext,
+        operation: OperationKind,
+        applied: &[Stack],
+        perm: &mut WorktreeWritePermission,
+    ) -> Result<Option<Self>> {
+        if !ctx.project().feature_enabled(FeatureFlag::AutoStash) {
+            return Ok(None);
+        }
+        let repo = ctx.repository();
+        let base = if applied.is_empty() {
+            // Without stacks, the target is what's checked out.
+            repo.find_commit(ctx.project().virtual_branches().get_default_target()?.sha)?
+                .tree_id()
+        } else {
+            merge_trees_of_branches(repo, applied)?.id()
+        };
+        let worktree = repo.create_wd_tree()?.id();
+        if worktree == base {
+            return Ok(None);
+        }
+
+        let snapshot_id = ctx
+            .project()
+            .create_snapshot(
+                SnapshotDetails::new(OperationKind::AutoStash).with_trailers(vec![Trailer {
+                    key: "operation".to_string(),
+                    value: operation.to_string(),
+                }]),
+                perm,
+            )
+            .context("failed to stash unassigned changes")?;
+        Ok(Some(AutoStash {
+            snapshot_id,
+            operation,
+            base,
+            worktree,
+        }))
+    }
+
+    /// Apply the stashed changes to the worktree as the operation left it, and return the paths which
+    /// conflicted, if any. These are also persisted to be [listed](conflicts) later.
+    pub(crate) fn replay(
+        self,
+        ctx: &CommandContext,
+        _perm: &mut WorktreeWritePermission,
+    ) -> Result<Option<AutoStashConflicts>> {
+        let repo = ctx.repository();
+        let ours = repo.create_wd_tree()?;
+        let mut index = repo.merge_trees(
+            &repo.find_tree(self.base)?,
+            &ours,
+            &repo.find_tree(self.worktree)?,
+            None,
+        )?;
+
+        let mut paths = Vec::new();
+        for mut conflict in index.conflicts()?.flatten().collect::<Vec<_>>() {
+            let Some(entry) = conflict
+                .our
+                .as_ref()
+                .or(conflict.their.as_ref())
+                .or(conflict.ancestor.as_ref())
+            else {
+                continue;
+            };
+            let path = PathBuf::from(String::from_utf8_lossy(&entry.path).into_owned());
+            index.remove_path(&path)?;
+            if let Some(our) = &mut conflict.our {
+                let blob = repo.find_blob(our.id)?;
+                // The flags carry the stage of the conflict, and the resolution belongs to none.
+                our.flags = 0;
+                index.add_frombuffer(our, blob.content())?;
+            }
+            paths.push(path);
+        }
+
+        let tree = repo.find_tree(index.write_tree_to(repo)?)?;
+        if tree.id() != ours.id() {
+            repo.checkout_tree_builder(&tree)
+                .force()
+                .remove_untracked()
+                .checkout()?;
+        }
+
+        if paths.is_empty() {
+            return Ok(None);
+        }
+        let conflicts = AutoStashConflicts {
+            snapshot_id: self.snapshot_id,
+            operation: self.operation,
+            paths,
+        };
+        let contents =
+            toml::to_string(&conflicts).context("Failed to serialize auto-stash conflicts")?;
+        gitbutler_fs::write(autostash_path(ctx), contents)
+            .context("Failed to write auto-stash conflicts")?;
+        Ok(Some(conflicts))
+    }
+}
+
+fn autostash_path(ctx: &CommandContext) -> PathBuf {
+    ctx.project().gb_dir().join(AUTOSTASH_FILE_NAME)
+}
+
+/// Return the conflicts of the last replay of stashed changes, unless they were dismissed.
+pub fn conflicts(ctx: &CommandContext) -> Result<Option<AutoStashConflicts>> {
+    match std::fs::read_to_string(autostash_path(ctx)) {
+        Ok(contents) => Ok(Some(
+            toml::from_str(&contents).context("Failed to read auto-stash conflicts")?,
+        )),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Forget about the conflicts of the last replay of stashed changes, once they were taken care of.
+pub fn dismiss_conflicts(ctx: &CommandContext) -> Result<()> {
+    match std::fs::remove_file(autostash_path(ctx)) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
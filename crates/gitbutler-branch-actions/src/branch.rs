@@ -1,3 +1,4 @@
+use crate::snapshot::{BranchesChanged, BranchesSnapshot};
 use crate::{RemoteBranchFile, VirtualBranchesExt};
 use anyhow::{bail, Context, Result};
 use bstr::{BStr, ByteSlice};
@@ -49,7 +50,13 @@ pub(crate) fn get_uncommited_files(
     Ok(files)
 }
 
+/// How often listing branches is attempted if they change while being listed.
+const LIST_BRANCHES_ATTEMPTS: usize = 3;
+
 /// Returns a list of branches associated with this project.
+///
+/// The branches are listed as they were at one point in time, even if another operation changes them
+/// concurrently. If they keep changing, this fails with [`BranchesChanged`].
 pub fn list_branches(
     ctx: &CommandContext,
     filter: Option<BranchListingFilter>,
@@ -57,12 +64,38 @@ pub fn list_branches(
 ) -> Result<Vec<BranchListing>> {
     let mut repo = ctx.gix_repository()?;
     repo.object_cache_size_if_unset(1024 * 1024);
+    let vb_handle = ctx.project().virtual_branches();
+    for _ in 0..LIST_BRANCHES_ATTEMPTS {
+        let snapshot = BranchesSnapshot::capture(&repo, &vb_handle)?;
+        let branches =
+            list_branches_in_snapshot(&repo, &snapshot, filter, filter_branch_names.as_deref())?;
+        match snapshot.verify(&repo, &vb_handle) {
+            Ok(()) => return Ok(branches),
+            Err(err) if err.is::<BranchesChanged>() => {
+                tracing::debug!("Branches changed while listing them, listing them again");
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Err(BranchesChanged.into())
+}
+
+fn list_branches_in_snapshot(
+    repo: &gix::Repository,
+    snapshot: &BranchesSnapshot,
+    filter: Option<BranchListingFilter>,
+    filter_branch_names: Option<&[BranchIdentity]>,
+) -> Result<Vec<BranchListing>> {
     let has_filter = filter.is_some();
     let filter = filter.unwrap_or_default();
-    let vb_handle = ctx.project().virtual_branches();
     let platform = repo.references()?;
     let mut branches: Vec<GroupBranch> = vec![];
     for reference in platform.all()?.filter_map(Result::ok) {
+        // Only list what was there when the snapshot was taken
+        if snapshot.id(reference.name()).is_none() {
+            continue;
+        }
+
         // Loosely match on branch names
         if let Some(branch_names) = &filter_branch_names {
             let has_matching_name = branch_names.iter().any(|branch_name| {
@@ -90,12 +123,18 @@ pub fn list_branches(
     }
 
     branches.extend(
-        vb_handle
+        snapshot
+            .virtual_branches
             .list_all_branches()?
             .into_iter()
             .map(GroupBranch::Virtual),
     );
-    let mut branches = combine_branches(branches, &repo, vb_handle.get_default_target()?)?;
+    let mut branches = combine_branches(
+        branches,
+        repo,
+        snapshot,
+        snapshot.virtual_branches.get_default_target()?,
+    )?;
 
     // Apply the filter
     branches.retain(|branch| !has_filter || matches_all(branch, filter));
@@ -151,10 +190,10 @@ fn matches_all(branch: &BranchListing, filter: BranchListingFilter) -> bool {
 fn combine_branches(
     group_branches: Vec<GroupBranch>,
     repo: &gix::Repository,
+    snapshot: &BranchesSnapshot,
     target_branch: Target,
 ) -> Result<Vec<BranchListing>> {
     let remotes = repo.remote_names();
-    let packed = repo.refs.cached_packed_buffer()?;
 
     // Group branches by identity
     let mut groups: HashMap<BranchIdentity, Vec<GroupBranch>> = HashMap::new();
@@ -182,7 +221,7 @@ fn combine_branches(
                 &identity,
                 group_branches,
                 repo,
-                packed.as_ref().map(|p| &***p),
+                snapshot,
                 &remotes,
                 &target_branch,
             );
@@ -206,7 +245,7 @@ fn branch_group_to_branch(
     identity: &BranchIdentity,
     group_branches: Vec<GroupBranch>,
     repo: &gix::Repository,
-    packed: Option<&gix::refs::packed::Buffer>,
+    snapshot: &BranchesSnapshot,
     remotes: &BTreeSet<Cow<'_, BStr>>,
     target: &Target,
 ) -> Result<Option<BranchListing>> {
@@ -268,10 +307,10 @@ fn branch_group_to_branch(
     // If there are no local branches, pick the first remote branch.
     let head_commit = if let Some(vbranch) = virtual_branch {
         Some(git2_to_gix_object_id(vbranch.head()).attach(repo))
-    } else if let Some(mut branch) = local_branches.into_iter().next() {
-        branch.peel_to_id_in_place_packed(packed).ok()
-    } else if let Some(mut branch) = remote_branches.into_iter().next() {
-        branch.peel_to_id_in_place_packed(packed).ok()
+    } else if let Some(branch) = local_branches.first() {
+        snapshot.id(branch.name()).map(|id| id.attach(repo))
+    } else if let Some(branch) = remote_branches.first() {
+        snapshot.id(branch.name()).map(|id| id.attach(repo))
     } else {
         None
     }
@@ -5,7 +5,7 @@ use gitbutler_branch::{self, dedup};
 use gitbutler_cherry_pick::RepositoryExt as _;
 use gitbutler_commit::{commit_ext::CommitExt, commit_headers::HasCommitHeaders};
 use gitbutler_error::error::Marker;
-use gitbutler_oplog::SnapshotExt;
+use gitbutler_oplog::{entry::OperationKind, SnapshotExt};
 use gitbutler_project::access::WorktreeWritePermission;
 use gitbutler_reference::{Refname, RemoteRefname};
 use gitbutler_repo::{
@@ -19,8 +19,8 @@ use tracing::instrument;
 
 use super::BranchManager;
 use crate::{
-    conflicts::RepoConflictsExt, hunk::VirtualBranchHunk, integration::update_workspace_commit,
-    VirtualBranchesExt,
+    autostash::AutoStash, conflicts::RepoConflictsExt, hunk::VirtualBranchHunk,
+    integration::update_workspace_commit, VirtualBranchesExt,
 };
 
 impl BranchManager<'_> {
@@ -223,6 +223,10 @@ impl BranchManager<'_> {
         let mut branch = if let Ok(Some(mut branch)) =
             vb_state.find_by_source_refname_where_not_in_workspace(target)
         {
+            // a pull request is only recorded for stacks which may be published
+            if forge_id.is_some() {
+                vbranch::assure_not_draft(&branch, false)?;
+            }
             branch.upstream_head = upstream_branch.is_some().then_some(head_commit.id());
             branch.upstream = upstream_branch;
             branch.ownership = ownership;
@@ -289,6 +293,16 @@ impl BranchManager<'_> {
 
         let mut branch = vb_state.get_branch_in_workspace(branch_id)?;
 
+        // Changes that no branch carries are stashed, so checking out the merged trees doesn't drop them.
+        // The branch is already marked as applied, but its tree isn't checked out yet.
+        let checked_out: Vec<_> = vb_state
+            .list_branches_in_workspace()?
+            .into_iter()
+            .filter(|branch| branch.id != branch_id)
+            .collect();
+        let autostash =
+            AutoStash::capture(self.ctx, OperationKind::ApplyBranch, &checked_out, perm)?;
+
         // calculate the merge base and make sure it's the same as the target commit
         // if not, we need to merge or rebase the branch to get it up to date
 
@@ -340,7 +354,12 @@ impl BranchManager<'_> {
                 let commits_to_rebase =
                     repo.l(branch.head(), LogUntil::Commit(merge_base), false)?;
 
-                let head_oid = cherry_rebase_group(repo, default_target.sha, &commits_to_rebase)?;
+                let head_oid = cherry_rebase_group(
+                    repo,
+                    default_target.sha,
+                    &commits_to_rebase,
+                    self.ctx.project().auto_resolve_whitespace,
+                )?;
 
                 repo.find_commit(head_oid)?
             } else {
@@ -350,6 +369,7 @@ impl BranchManager<'_> {
                     repo.find_commit(default_target.sha)?,
                     &branch.name,
                     default_target.branch.branch(),
+                    self.ctx.project().auto_resolve_whitespace,
                 )?
             };
 
@@ -391,6 +411,9 @@ impl BranchManager<'_> {
 
         // Now that we've added a branch to the workspace, lets merge together all the trees
         checkout_branch_trees(self.ctx, perm)?;
+        if let Some(autostash) = autostash {
+            autostash.replay(self.ctx, perm)?;
+        }
 
         update_workspace_commit(&vb_state, self.ctx)?;
 
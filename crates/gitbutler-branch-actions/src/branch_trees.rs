@@ -2,6 +2,7 @@ use anyhow::{bail, Result};
 use gitbutler_cherry_pick::RepositoryExt;
 use gitbutler_command_context::CommandContext;
 use gitbutler_commit::commit_ext::CommitExt as _;
+use gitbutler_hunk_dependency::shift_claims;
 use gitbutler_project::access::WorktreeWritePermission;
 use gitbutler_repo::rebase::cherry_rebase_group;
 use gitbutler_repo::RepositoryExt as _;
@@ -25,44 +26,73 @@ pub fn checkout_branch_trees<'a>(
         return repository.create_wd_tree();
     };
 
-    if branches.len() == 1 {
-        let tree = repository.find_tree(branches[0].tree)?;
-        repository
-            .checkout_tree_builder(&tree)
-            .force()
-            .remove_untracked()
-            .checkout()?;
+    let final_tree = merge_trees_of_branches(repository, &branches)?;
+    repository
+        .checkout_tree_builder(&final_tree)
+        .force()
+        .remove_untracked()
+        .checkout()?;
 
-        Ok(tree)
-    } else {
-        let merge_base = repository
-            .merge_base_octopussy(&branches.iter().map(|b| b.head()).collect::<Vec<_>>())?;
+    Ok(final_tree)
+}
+
+/// Move the ownership claims of all applied stacks to where their lines ended up after the worktree
+/// changed from `old_worktree` to `new_worktree`, so the uncommitted changes stay with their stacks.
+///
+/// Operations that rewrite the commits of stacks call this with the worktree before and after
+/// [checking out](checkout_branch_trees) the result, as the lines of claimed hunks may have moved.
+pub(crate) fn shift_claims_to_worktree(
+    ctx: &CommandContext,
+    old_worktree: &git2::Tree,
+    new_worktree: &git2::Tree,
+) -> Result<()> {
+    let changes = gitbutler_diff::trees(ctx.repository(), old_worktree, new_worktree, false)?;
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let vb_state = ctx.project().virtual_branches();
+    for mut branch in vb_state.list_branches_in_workspace()? {
+        let ownership = shift_claims(&branch.ownership, &changes)?;
+        if ownership != branch.ownership {
+            branch.ownership = ownership;
+            vb_state.set_branch(branch)?;
+        }
+    }
+    Ok(())
+}
 
-        let merge_base_tree = repository.find_commit(merge_base)?.tree()?;
+/// Merges the trees of `branches` together, without touching the worktree.
+///
+/// This function will fail if the branches conflict with each other.
+pub(crate) fn merge_trees_of_branches<'a>(
+    repository: &'a git2::Repository,
+    branches: &[Stack],
+) -> Result<git2::Tree<'a>> {
+    if let [branch] = branches {
+        return Ok(repository.find_tree(branch.tree)?);
+    }
 
-        let mut final_tree = merge_base_tree.clone();
+    let merge_base =
+        repository.merge_base_octopussy(&branches.iter().map(|b| b.head()).collect::<Vec<_>>())?;
 
-        for branch in branches {
-            let theirs = repository.find_tree(branch.tree)?;
-            let mut merge_index =
-                repository.merge_trees(&merge_base_tree, &final_tree, &theirs, None)?;
+    let merge_base_tree = repository.find_commit(merge_base)?.tree()?;
 
-            if merge_index.has_conflicts() {
-                bail!("There appears to be conflicts between the virtual branches");
-            };
+    let mut final_tree = merge_base_tree.clone();
 
-            let tree_oid = merge_index.write_tree_to(repository)?;
-            final_tree = repository.find_tree(tree_oid)?;
-        }
+    for branch in branches {
+        let theirs = repository.find_tree(branch.tree)?;
+        let mut merge_index =
+            repository.merge_trees(&merge_base_tree, &final_tree, &theirs, None)?;
 
-        repository
-            .checkout_tree_builder(&final_tree)
-            .force()
-            .remove_untracked()
-            .checkout()?;
+        if merge_index.has_conflicts() {
+            bail!("There appears to be conflicts between the virtual branches");
+        };
 
-        Ok(final_tree)
+        let tree_oid = merge_index.write_tree_to(repository)?;
+        final_tree = repository.find_tree(tree_oid)?;
     }
+
+    Ok(final_tree)
 }
 
 pub struct BranchHeadAndTree {
@@ -121,7 +151,9 @@ pub fn compute_updated_branch_head_for_commits(
         Default::default(),
     )?;
 
-    let rebased_tree = cherry_rebase_group(repository, new_head, &[commited_tree])?;
+    // Whitespace conflicts of uncommitted changes are never resolved, as there is no commit which
+    // could record that.
+    let rebased_tree = cherry_rebase_group(repository, new_head, &[commited_tree], false)?;
     let rebased_tree = repository.find_commit(rebased_tree)?;
 
     if rebased_tree.is_conflicted() {
@@ -170,14 +202,14 @@ mod test {
 
         fs::write(test_project.path().join("foo.txt"), "content").unwrap();
 
-        crate::create_commit(&project, branch_1, "commit one", None, false).unwrap();
+        crate::create_commit(&project, branch_1, "commit one", None, false, &[]).unwrap();
 
         let branch_2 =
             crate::create_virtual_branch(&project, &BranchCreateRequest::default()).unwrap();
 
         fs::write(test_project.path().join("bar.txt"), "content").unwrap();
 
-        crate::create_commit(&project, branch_2, "commit two", None, false).unwrap();
+        crate::create_commit(&project, branch_2, "commit two", None, false, &[]).unwrap();
 
         let tree = test_project.local_repository.create_wd_tree().unwrap();
 
@@ -10,7 +10,8 @@ use gitbutler_stack::StackId;
 
 use crate::{
     branch_trees::{
-        checkout_branch_trees, compute_updated_branch_head_for_commits, BranchHeadAndTree,
+        checkout_branch_trees, compute_updated_branch_head_for_commits, shift_claims_to_worktree,
+        BranchHeadAndTree,
     },
     conflicts, VirtualBranchesExt as _,
 };
@@ -60,14 +61,18 @@ pub fn integrate_upstream_commits_for_series(
         remote_head: remote_head.id(),
         remote_branch_name: &subject_series.head.remote_reference(&remote)?,
         prefers_merge: !do_rebease,
+        auto_resolve_whitespace: ctx.project().auto_resolve_whitespace,
     };
 
     let (BranchHeadAndTree { head, tree }, new_series_head) =
         integrate_upstream_context.inner_integrate_upstream_commits_for_series(series_head.id())?;
 
+    // The claims of uncommitted changes refer to lines of the worktree as it is now.
+    let old_worktree = repo.create_wd_tree()?;
     let mut branch = branch.clone();
     branch.set_stack_head(ctx, head, Some(tree))?;
-    checkout_branch_trees(ctx, perm)?;
+    let new_worktree = checkout_branch_trees(ctx, perm)?;
+    shift_claims_to_worktree(ctx, &old_worktree, &new_worktree)?;
     branch.replace_head(ctx, &series_head, &repo.find_commit(new_series_head)?)?;
     crate::integration::update_workspace_commit(&vb_state, ctx)?;
     Ok(())
@@ -118,16 +123,20 @@ pub fn integrate_upstream_commits(
         remote_head: upstream_branch_head,
         remote_branch_name: upstream_branch.name()?.unwrap_or("Unknown"),
         prefers_merge: !branch.allow_rebasing,
+        auto_resolve_whitespace: project.auto_resolve_whitespace,
     };
 
     let BranchHeadAndTree { head, tree } =
         integrate_upstream_context.inner_integrate_upstream_commits()?;
 
+    // The claims of uncommitted changes refer to lines of the worktree as it is now.
+    let old_worktree = repository.create_wd_tree()?;
     let mut branch = branch.clone();
 
     branch.set_stack_head(ctx, head, Some(tree))?;
 
-    checkout_branch_trees(ctx, perm)?;
+    let new_worktree = checkout_branch_trees(ctx, perm)?;
+    shift_claims_to_worktree(ctx, &old_worktree, &new_worktree)?;
 
     crate::integration::update_workspace_commit(&vb_state, ctx)?;
 
@@ -153,6 +162,8 @@ struct IntegrateUpstreamContext<'a, 'b> {
 
     /// Whether to merge or rebase
     prefers_merge: bool,
+    /// Whether conflicts whose sides only differ in whitespace are resolved, as configured for the project.
+    auto_resolve_whitespace: bool,
 }
 
 impl IntegrateUpstreamContext<'_, '_> {
@@ -172,6 +183,7 @@ impl IntegrateUpstreamContext<'_, '_> {
                 remote_head_commit,
                 self.branch_name,        // for error messages only
                 self.remote_branch_name, // for error messages only
+                self.auto_resolve_whitespace,
             )?;
             // the are the same
             let new_stack_head = merge_commit.id();
@@ -189,15 +201,24 @@ impl IntegrateUpstreamContext<'_, '_> {
                 self.remote_head,
             )?;
             // First rebase the series with it's remote commits
-            let new_series_head =
-                cherry_rebase_group(self.repository, merge_base, &ordered_commits)?;
+            let new_series_head = cherry_rebase_group(
+                self.repository,
+                merge_base,
+                &ordered_commits,
+                self.auto_resolve_whitespace,
+            )?;
             // Get the commits that come after the series head, until the stack head
             let remaining_ids_to_rebase =
                 self.repository
                     .l(self.branch_head, LogUntil::Commit(series_head), false)?;
             // Rebase the remaining commits on top of the new series head in order to get the new stack head
             (
-                cherry_rebase_group(self.repository, new_series_head, &remaining_ids_to_rebase)?,
+                cherry_rebase_group(
+                    self.repository,
+                    new_series_head,
+                    &remaining_ids_to_rebase,
+                    self.auto_resolve_whitespace,
+                )?,
                 new_series_head,
             )
         };
@@ -224,6 +245,7 @@ impl IntegrateUpstreamContext<'_, '_> {
                 remote_head_commit,
                 self.branch_name,
                 self.remote_branch_name,
+                self.auto_resolve_whitespace,
             )?
             .id()
         } else {
@@ -237,7 +259,12 @@ impl IntegrateUpstreamContext<'_, '_> {
                 self.remote_head,
             )?;
 
-            cherry_rebase_group(self.repository, merge_base, &ordered_commits)?
+            cherry_rebase_group(
+                self.repository,
+                merge_base,
+                &ordered_commits,
+                self.auto_resolve_whitespace,
+            )?
         };
 
         // Find what the new head and branch tree should be
@@ -337,6 +364,7 @@ mod test {
                 remote_head: remote_y.id(),
                 remote_branch_name: "test",
                 prefers_merge: false,
+                auto_resolve_whitespace: false,
             };
 
             let BranchHeadAndTree { head, tree: _tree } =
@@ -392,6 +420,7 @@ mod test {
                 remote_head: remote_y.id(),
                 remote_branch_name: "test",
                 prefers_merge: false,
+                auto_resolve_whitespace: false,
             };
 
             let (BranchHeadAndTree { head, tree: _tree }, new_series_head) = ctx
@@ -461,6 +490,7 @@ mod test {
                 remote_head: remote_y.id(),
                 remote_branch_name: "test",
                 prefers_merge: false,
+                auto_resolve_whitespace: false,
             };
 
             let BranchHeadAndTree { head, tree: _tree } =
@@ -577,6 +607,7 @@ mod test {
                 remote_head: remote_y.id(),
                 remote_branch_name: "test",
                 prefers_merge: false,
+                auto_resolve_whitespace: false,
             };
 
             let BranchHeadAndTree { head, tree: _tree } =
@@ -718,6 +749,7 @@ mod test {
                 remote_head: remote_y.id(),
                 remote_branch_name: "test",
                 prefers_merge: false,
+                auto_resolve_whitespace: false,
             };
 
             let BranchHeadAndTree { head, tree: _tree } =
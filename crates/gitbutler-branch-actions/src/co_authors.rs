@@ -0,0 +1,262 @@
+//! Co-authors of commits, recorded as `Co-authored-by: Name <email>` trailers of their message.
+//!
+//! The co-authors used in a project are remembered in `co_authors.json` in its GitButler
+//! directory, so the most frequent ones can be offered when creating the next commit.
+use std::{
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context, Result};
+use gitbutler_error::error::Code;
+use gitbutler_project::Project;
+use serde::{Deserialize, Serialize};
+
+const CO_AUTHORS_FILE_NAME: &str = "co_authors.json";
+const CO_AUTHORED_BY: &str = "Co-authored-by";
+/// How many of the most frequent co-authors are offered.
+const MAX_FREQUENT_CO_AUTHORS: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoAuthor {
+    pub name: String,
+    pub email: String,
+}
+
+impl CoAuthor {
+    fn validate(&self) -> Result<()> {
+        let is_invalid =
+            |value: &str| value.trim().is_empty() || value.contains(['\n', '\r', '<', '>']);
+        if is_invalid(&self.name) || is_invalid(&self.email) {
+            return Err(
+                anyhow!("Invalid co-author '{} <{}>'", self.name, self.email)
+                    .context(Code::Validation),
+            );
+        }
+        Ok(())
+    }
+
+    fn trailer(&self) -> String {
+        format!(
+            "{CO_AUTHORED_BY}: {} <{}>",
+            self.name.trim(),
+            self.email.trim()
+        )
+    }
+}
+
+/// Return `message` with a `Co-authored-by` trailer for each of `co_authors` that it doesn't
+/// name yet, added to the trailers the message already ends with.
+pub fn add_trailers(message: &str, co_authors: &[CoAuthor]) -> Result<String> {
+    for co_author in co_authors {
+        co_author.validate()?;
+    }
+    let mut named = parse(message);
+    let mut trailers = Vec::new();
+    for co_author in co_authors {
+        let email = co_author.email.trim();
+        if named
+            .iter()
+            .any(|other| other.email.eq_ignore_ascii_case(email))
+        {
+            continue;
+        }
+        trailers.push(co_author.trailer());
+        named.push(CoAuthor {
+            name: co_author.name.clone(),
+            email: email.to_owned(),
+        });
+    }
+    if trailers.is_empty() {
+        return Ok(message.to_owned());
+    }
+
+    let message = message.trim_end();
+    let separator = if message.is_empty() {
+        ""
+    } else if ends_with_trailers(message) {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    Ok(format!("{message}{separator}{}\n", trailers.join("\n")))
+}
+
+/// Return the co-authors named in the `Co-authored-by` trailers at the end of `message`.
+pub fn parse(message: &str) -> Vec<CoAuthor> {
+    let message = message.trim_end();
+    if !ends_with_trailers(message) {
+        return Vec::new();
+    }
+    last_paragraph(message)
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            if !key.trim().eq_ignore_ascii_case(CO_AUTHORED_BY) {
+                return None;
+            }
+            let (name, email) = value.trim().strip_suffix('>')?.rsplit_once('<')?;
+            Some(CoAuthor {
+                name: name.trim().to_owned(),
+                email: email.trim().to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// The last paragraph of `message`, unless it's the subject as a message of one paragraph has no
+/// trailers.
+fn last_paragraph(message: &str) -> &str {
+    match message.rsplit_once("\n\n") {
+        Some((_, paragraph)) => paragraph,
+        None => "",
+    }
+}
+
+fn ends_with_trailers(message: &str) -> bool {
+    let paragraph = last_paragraph(message);
+    !paragraph.is_empty() && paragraph.lines().all(is_trailer)
+}
+
+/// Whether `line` looks like a `Key: value` trailer, with a key made of letters, digits and dashes.
+fn is_trailer(line: &str) -> bool {
+    line.split_once(':').is_some_and(|(key, _)| {
+        !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CoAuthorsFile {
+    #[serde(default)]
+    co_authors: BTreeMap<String, UsedCoAuthor>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsedCoAuthor {
+    co_author: CoAuthor,
+    /// How many commits were made with the co-author.
+    count: u64,
+    /// When the last commit was made with the co-author, in seconds since the epoch.
+    last_used: u64,
+}
+
+/// Remember that a commit was made with `co_authors` in `project`.
+pub fn remember(project: &Project, co_authors: &[CoAuthor]) -> Result<()> {
+    if co_authors.is_empty() {
+        return Ok(());
+    }
+    let path = project.gb_dir().join(CO_AUTHORS_FILE_NAME);
+    let mut file = read(project)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    for co_author in co_authors {
+        let used = file
+            .co_authors
+            .entry(co_author.email.trim().to_lowercase())
+            .or_insert_with(|| UsedCoAuthor {
+                co_author: co_author.clone(),
+                count: 0,
+                last_used: now,
+            });
+        // The name used last is the one to offer.
+        used.co_author = CoAuthor {
+            name: co_author.name.trim().to_owned(),
+            email: co_author.email.trim().to_owned(),
+        };
+        used.count += 1;
+        used.last_used = now;
+    }
+    let contents = serde_json::to_string(&file).context("Failed to serialize co-authors")?;
+    gitbutler_fs::write(&path, contents)
+}
+
+/// Return the co-authors used most often in `project`, the most frequent first, and those used
+/// last first if they were used as often.
+pub fn frequent(project: &Project) -> Result<Vec<CoAuthor>> {
+    let mut used: Vec<_> = read(project)?.co_authors.into_values().collect();
+    used.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| b.last_used.cmp(&a.last_used))
+    });
+    Ok(used
+        .into_iter()
+        .take(MAX_FREQUENT_CO_AUTHORS)
+        .map(|used| used.co_author)
+        .collect())
+}
+
+fn read(project: &Project) -> Result<CoAuthorsFile> {
+    let path = project.gb_dir().join(CO_AUTHORS_FILE_NAME);
+    match std::fs::read(&path) {
+        Ok(contents) => serde_json::from_slice(&contents)
+            .with_context(|| format!("Failed to read co-authors from {}", path.display())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(CoAuthorsFile::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn co_author(name: &str, email: &str) -> CoAuthor {
+        CoAuthor {
+            name: name.into(),
+            email: email.into(),
+        }
+    }
+
+    #[test]
+    fn trailers_are_added_once() {
+        let co_authors = [
+            co_author("Jane Doe", "jane@example.com"),
+            co_author("John Doe", "john@example.com"),
+            co_author("Jane", "JANE@example.com"),
+        ];
+        assert_eq!(
+            add_trailers("subject\n\nbody\n", &co_authors).unwrap(),
+            "subject\n\nbody\n\nCo-authored-by: Jane Doe <jane@example.com>\nCo-authored-by: John Doe <john@example.com>\n"
+        );
+        assert_eq!(
+            add_trailers(
+                "subject\n\nSigned-off-by: Me <me@example.com>\nCo-authored-by: John Doe <john@example.com>",
+                &co_authors
+            )
+            .unwrap(),
+            "subject\n\nSigned-off-by: Me <me@example.com>\nCo-authored-by: John Doe <john@example.com>\nCo-authored-by: Jane Doe <jane@example.com>\n",
+            "existing trailers are extended, and co-authors named there skipped"
+        );
+        assert_eq!(add_trailers("subject", &[]).unwrap(), "subject");
+        assert_eq!(
+            add_trailers("fix: a bug", &co_authors[1..2]).unwrap(),
+            "fix: a bug\n\nCo-authored-by: John Doe <john@example.com>\n",
+            "the subject is no trailer"
+        );
+        assert!(
+            add_trailers("subject", &[co_author("Evil\nTrailer: x", "e@example.com")]).is_err()
+        );
+    }
+
+    #[test]
+    fn co_authors_are_parsed_from_the_last_paragraph() {
+        assert_eq!(
+            parse("subject\n\nbody\n\nCo-authored-by: Jane Doe <jane@example.com>\nco-authored-by:John <john@example.com>\n"),
+            vec![
+                co_author("Jane Doe", "jane@example.com"),
+                co_author("John", "john@example.com")
+            ]
+        );
+        assert_eq!(
+            parse("subject\n\nCo-authored-by: Jane Doe <jane@example.com>\nthis is prose"),
+            vec![],
+            "it's not a trailer block"
+        );
+        assert_eq!(parse("Co-authored-by: Jane Doe <jane@example.com>"), vec![]);
+    }
+}
@@ -3,8 +3,8 @@ use anyhow::{anyhow, Result};
 use bstr::ByteSlice as _;
 use gitbutler_cherry_pick::ConflictedTreeKey;
 use gitbutler_command_context::CommandContext;
-use gitbutler_commit::commit_ext::CommitExt;
-use gitbutler_repo::rebase::ConflictEntries;
+use gitbutler_commit::{commit_ext::CommitExt, commit_headers::HasCommitHeaders};
+use gitbutler_repo::{rebase::ConflictEntries, RepositoryExt as _, SignatureVerification};
 use gitbutler_serde::BStringForFrontend;
 use gitbutler_stack::{Stack, StackId};
 use serde::Serialize;
@@ -25,6 +25,8 @@ pub struct VirtualBranchCommit {
     pub description: BStringForFrontend,
     pub created_at: u128,
     pub author: Author,
+    /// The co-authors named in the `Co-authored-by` trailers of the message.
+    pub co_authors: Vec<Author>,
     /// Dont use, favor `remote_commit_id` instead
     pub is_remote: bool,
     pub is_integrated: bool,
@@ -50,6 +52,11 @@ pub struct VirtualBranchCommit {
     #[serde(with = "gitbutler_serde::oid_opt")]
     pub remote_commit_id: Option<git2::Oid>,
     pub conflicted_files: ConflictEntries,
+    /// The paths of files whose conflicts were resolved automatically when rebasing or merging this commit.
+    pub auto_resolved: Vec<String>,
+    /// The id of the commit this one was copied from to its branch, as recorded when copying it.
+    #[serde(with = "gitbutler_serde::oid_opt")]
+    pub copied_from_id: Option<git2::Oid>,
 }
 
 pub(crate) fn commit_to_vbranch_commit(
@@ -89,10 +96,15 @@ pub(crate) fn commit_to_vbranch_commit(
         Default::default()
     };
 
+    let headers = commit.gitbutler_headers();
     let commit = VirtualBranchCommit {
         id: commit.id(),
         created_at: timestamp * 1000,
         author: commit.author().into(),
+        co_authors: crate::co_authors::parse(&message.to_str_lossy())
+            .into_iter()
+            .map(|co_author| Author::new(co_author.name, co_author.email))
+            .collect(),
         description: message.into(),
         is_remote,
         is_integrated,
@@ -104,7 +116,45 @@ pub(crate) fn commit_to_vbranch_commit(
         copied_from_remote_id,
         remote_commit_id,
         conflicted_files,
+        auto_resolved: headers
+            .as_ref()
+            .map(|headers| headers.auto_resolved.clone())
+            .unwrap_or_default(),
+        copied_from_id: headers
+            .and_then(|headers| headers.copied_from)
+            .and_then(|id| id.parse().ok()),
     };
 
     Ok(commit)
 }
+
+/// The verification of the signature of a commit, which runs the signing program and is thus
+/// requested separately from listing the commits.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitSignature {
+    #[serde(with = "gitbutler_serde::oid")]
+    pub commit_id: git2::Oid,
+    /// Whether the signature could be verified, and who signed it, or `None` if the commit isn't
+    /// signed or verifying it failed.
+    pub verification: Option<SignatureVerification>,
+}
+
+/// Verify the signatures of the commits with `commit_ids`, in the same order.
+pub(crate) fn verify_signatures(
+    repository: &git2::Repository,
+    commit_ids: &[git2::Oid],
+) -> Vec<CommitSignature> {
+    commit_ids
+        .iter()
+        .map(|&commit_id| CommitSignature {
+            commit_id,
+            verification: repository
+                .verify_signature(commit_id)
+                .unwrap_or_else(|err| {
+                    tracing::warn!(?err, "failed to verify the signature of commit {commit_id}");
+                    None
+                }),
+        })
+        .collect()
+}
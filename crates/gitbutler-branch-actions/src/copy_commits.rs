@@ -0,0 +1,100 @@
+use anyhow::{bail, Context as _, Result};
+use bstr::ByteSlice as _;
+use gitbutler_command_context::CommandContext;
+use gitbutler_commit::{commit_ext::CommitExt as _, commit_headers::CommitHeadersV2};
+use gitbutler_project::access::WorktreeWritePermission;
+use gitbutler_repo::{rebase::cherry_rebase_group, LogUntil, RepositoryExt as _};
+use gitbutler_stack::StackId;
+
+use crate::{branch_trees::checkout_branch_trees, VirtualBranchesExt as _};
+
+/// Copies the commits with `commit_ids`, in application order, to the top of the stack with
+/// `target_stack_id`, and returns the IDs of the copies.
+///
+/// The commits may come from any stack, applied or not, or from a remote branch. Each copy gets
+/// a change ID of its own and remembers the commit it was copied from, which is shown as
+/// `copied_from_id` of the commit. If a copy would conflict, nothing is copied and the commit that
+/// conflicts is named in the error.
+pub(crate) fn copy_commits_to_stack(
+    ctx: &CommandContext,
+    target_stack_id: StackId,
+    commit_ids: &[git2::Oid],
+    perm: &mut WorktreeWritePermission,
+) -> Result<Vec<git2::Oid>> {
+    ctx.assure_resolved()?;
+    if commit_ids.is_empty() {
+        bail!("no commits to copy");
+    }
+
+    let vb_state = ctx.project().virtual_branches();
+    let mut target_stack = vb_state.get_branch_in_workspace(target_stack_id)?;
+    let default_target = vb_state.get_default_target()?;
+    let repository = ctx.repository();
+    let target_commit_ids = repository.l(
+        target_stack.head(),
+        LogUntil::Commit(default_target.sha),
+        false,
+    )?;
+
+    let mut head = target_stack.head();
+    let mut copies = Vec::with_capacity(commit_ids.len());
+    for commit_id in commit_ids {
+        if target_commit_ids.contains(commit_id) {
+            bail!(
+                "commit {commit_id} is already in branch '{}'",
+                target_stack.name
+            );
+        }
+        let commit = repository
+            .find_commit(*commit_id)
+            .with_context(|| format!("commit {commit_id} to be copied could not be found"))?;
+        if commit.is_conflicted() {
+            bail!("Can not copy conflicted commits");
+        }
+
+        let picked = repository.find_commit(cherry_rebase_group(
+            repository,
+            head,
+            &[*commit_id],
+            ctx.project().auto_resolve_whitespace,
+        )?)?;
+        if picked.is_conflicted() {
+            bail!(
+                "Copying commit '{}' to branch '{}' would conflict",
+                commit
+                    .message_bstr()
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .as_bstr(),
+                target_stack.name
+            );
+        }
+
+        // The copy is a new patch, and not the same one at another point in time.
+        let parent = repository.find_commit(head)?;
+        head = repository
+            .commit_with_signature(
+                None,
+                &picked.author(),
+                &picked.committer(),
+                &picked.message_bstr().to_str_lossy(),
+                &picked.tree()?,
+                &[&parent],
+                Some(CommitHeadersV2 {
+                    copied_from: Some(commit_id.to_string()),
+                    ..CommitHeadersV2::new()
+                }),
+            )
+            .context("failed to create commit")?;
+        copies.push(head);
+    }
+
+    crate::policy::assure_commits_allowed(ctx, &target_stack, &copies)?;
+    target_stack.set_stack_head(ctx, head, None)?;
+    checkout_branch_trees(ctx, perm)?;
+    crate::integration::update_workspace_commit(&vb_state, ctx)
+        .context("failed to update gitbutler workspace")?;
+
+    Ok(copies)
+}
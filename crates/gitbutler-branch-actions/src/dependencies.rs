@@ -0,0 +1,227 @@
+//! Why commits of the workspace can't be reordered, and why uncommitted changes are locked to
+//! commits, as a graph or as the changes that are locked to more than one stack.
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use anyhow::{Context, Result};
+use gitbutler_command_context::CommandContext;
+use gitbutler_diff::{diff_files_into_hunks, GitHunk};
+use gitbutler_hunk_dependency::{path_hunk_locks, StackRanges, WorkspaceRanges};
+pub use gitbutler_hunk_dependency::{
+    set_validation_enabled, CrossStackConflict, DependencyGraph, LineOwner, Operation,
+    OperationPrediction, PathError, RemovalPreview, TransitiveDependencies,
+};
+use gitbutler_operating_modes::assure_open_workspace_mode;
+use gitbutler_stack::StackId;
+use serde::Serialize;
+
+use crate::{
+    integration::get_workspace_head, ranges_cache, status::hunk_dependency_input,
+    VirtualBranchesExt,
+};
+
+/// Return the graph of the dependencies between all commits of the workspace and of the
+/// uncommitted changes on them.
+pub fn hunk_dependency_graph(ctx: &CommandContext) -> Result<DependencyGraph> {
+    let ranges = workspace_ranges(ctx)?;
+    Ok(DependencyGraph::new(&ranges, &workdir_hunks(ctx)?))
+}
+
+/// Return the uncommitted hunks that can't be moved to any single stack, as they intersect commits
+/// of more than one.
+pub fn cross_stack_conflicts(ctx: &CommandContext) -> Result<Vec<CrossStackConflict>> {
+    let ranges = workspace_ranges(ctx)?;
+    Ok(gitbutler_hunk_dependency::cross_stack_conflicts(
+        &ranges,
+        &workdir_hunks(ctx)?,
+    ))
+}
+
+/// Return the paths whose ranges couldn't be computed, and which have no dependencies or locks
+/// as they were skipped.
+pub fn hunk_range_errors(ctx: &CommandContext) -> Result<Vec<PathError>> {
+    Ok(workspace_ranges(ctx)?.errors().cloned().collect())
+}
+
+/// Return the commits that depend on the commit with `commit_id`, and those it depends on, so
+/// callers can tell which commits an operation on it would affect.
+pub fn commit_dependencies(
+    ctx: &CommandContext,
+    commit_id: git2::Oid,
+) -> Result<TransitiveDependencies> {
+    Ok(workspace_ranges(ctx)?.transitive_dependencies(commit_id))
+}
+
+/// Return what would conflict if the commit with `commit_id` was removed from the workspace, to
+/// preview dropping or uncommitting it.
+pub fn remove_commit_preview(ctx: &CommandContext, commit_id: git2::Oid) -> Result<RemovalPreview> {
+    let ranges = workspace_ranges(ctx)?;
+    let (_, preview) = ranges.simulate_remove(commit_id, &workdir_hunks(ctx)?)?;
+    Ok(preview)
+}
+
+/// Predict whether `operation` would conflict, so the action can be disabled before it's
+/// attempted.
+pub fn predict_operation(
+    ctx: &CommandContext,
+    operation: &Operation,
+) -> Result<OperationPrediction> {
+    workspace_ranges(ctx)?.predict(operation)
+}
+
+/// Fail if reordering the commits of a stack as `commit_ids`, in application order, would move a
+/// submodule bump before an earlier bump of the same submodule.
+pub(crate) fn assure_submodule_bumps_ordered(
+    ctx: &CommandContext,
+    commit_ids: &[git2::Oid],
+) -> Result<()> {
+    workspace_ranges(ctx)?.assure_submodule_bumps_ordered(commit_ids)
+}
+
+/// Return the owner of each line of the file at `path` in the worktree, relative to the project,
+/// so it can be shown which stack and commit it comes from. Binary and missing files have no lines.
+pub fn line_owners(ctx: &CommandContext, path: &Path) -> Result<Vec<LineOwner>> {
+    let content = match std::fs::read(ctx.project().path.join(path)) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err).context("failed to read file"),
+    };
+    let hunks = workdir_hunks(ctx)?.remove(path).unwrap_or_default();
+    if hunks.iter().any(|hunk| hunk.binary) {
+        return Ok(vec![]);
+    }
+    let line_count = content.split_inclusive(|b| *b == b'\n').count();
+    Ok(gitbutler_hunk_dependency::line_owners(
+        &workspace_ranges(ctx)?,
+        path,
+        &hunks,
+        u32::try_from(line_count).context("file has too many lines")?,
+    ))
+}
+
+/// How long computing the dependencies of the workspace takes, and how much there is to compute.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyTimings {
+    pub stacks: Vec<StackTimings>,
+    /// The paths changed by any commit of the workspace.
+    pub paths: usize,
+    /// Combining the ranges of all stacks into those of the workspace.
+    pub combine_us: u64,
+    /// The uncommitted hunks whose locks were looked up.
+    pub hunks: usize,
+    /// Diffing the worktree and looking up the locks of its hunks.
+    pub locks_us: u64,
+    pub total_us: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackTimings {
+    pub stack_id: StackId,
+    pub commits: usize,
+    pub files: usize,
+    pub diffs: usize,
+    /// Diffing the commits of the stack.
+    pub input_us: u64,
+    /// Computing the ranges of the stack from its diffs.
+    pub ranges_us: u64,
+    /// The paths that were skipped as their ranges couldn't be computed.
+    pub errors: usize,
+}
+
+/// Compute the dependencies of the workspace from scratch, bypassing the cache of its ranges,
+/// and report how long each step took, so slow computations can be reported with numbers.
+pub fn dependency_timings(ctx: &CommandContext) -> Result<DependencyTimings> {
+    let started = Instant::now();
+    assure_open_workspace_mode(ctx).context("dependencies require open workspace mode")?;
+    let vb_state = ctx.project().virtual_branches();
+    let target = vb_state.get_default_target()?;
+    let mut stacks = vb_state.list_branches_in_workspace()?;
+    stacks.sort_by_key(|stack| stack.order);
+
+    let mut timings = Vec::with_capacity(stacks.len());
+    let mut ranges = Vec::with_capacity(stacks.len());
+    for stack in &stacks {
+        let input_started = Instant::now();
+        let input = hunk_dependency_input(ctx, &target.sha, std::slice::from_ref(stack), None)?
+            .pop()
+            .context("no input for stack")?;
+        let input_us = elapsed_us(input_started);
+        let ranges_started = Instant::now();
+        let stack_ranges = StackRanges::create_lenient(&input);
+        timings.push(StackTimings {
+            stack_id: stack.id,
+            commits: input.commits.len(),
+            files: input.commits.iter().map(|commit| commit.files.len()).sum(),
+            diffs: input
+                .commits
+                .iter()
+                .flat_map(|commit| &commit.files)
+                .map(|file| file.diffs.len())
+                .sum(),
+            input_us,
+            ranges_us: elapsed_us(ranges_started),
+            errors: stack_ranges.errors.len(),
+        });
+        ranges.push((input, stack_ranges));
+    }
+
+    let combine_started = Instant::now();
+    let ranges = WorkspaceRanges::from_stacks(ranges);
+    let combine_us = elapsed_us(combine_started);
+
+    let locks_started = Instant::now();
+    let workdir = workdir_hunks(ctx)?;
+    let hunks = workdir.values().map(Vec::len).sum();
+    let locks = workdir
+        .iter()
+        .flat_map(|(path, hunks)| path_hunk_locks(&ranges, path, hunks))
+        .count();
+    let locks_us = elapsed_us(locks_started);
+    let paths = ranges.paths().count();
+    tracing::info!(
+        stacks = timings.len(),
+        paths,
+        hunks,
+        locks,
+        combine_us,
+        locks_us,
+        "computed dependency timings"
+    );
+
+    Ok(DependencyTimings {
+        stacks: timings,
+        paths,
+        combine_us,
+        hunks,
+        locks_us,
+        total_us: elapsed_us(started),
+    })
+}
+
+fn elapsed_us(started: Instant) -> u64 {
+    u64::try_from(started.elapsed().as_micros()).unwrap_or(u64::MAX)
+}
+
+fn workdir_hunks(ctx: &CommandContext) -> Result<HashMap<PathBuf, Vec<GitHunk>>> {
+    let workspace_head = get_workspace_head(ctx)?;
+    Ok(diff_files_into_hunks(
+        gitbutler_diff::workdir(ctx.repository(), workspace_head)
+            .context("failed to diff workdir")?,
+    )
+    .collect())
+}
+
+/// Compute the ranges of all commits of the stacks in the workspace, in the order of the stacks.
+fn workspace_ranges(ctx: &CommandContext) -> Result<WorkspaceRanges> {
+    assure_open_workspace_mode(ctx).context("dependencies require open workspace mode")?;
+    let vb_state = ctx.project().virtual_branches();
+    let target = vb_state.get_default_target()?;
+    let mut stacks = vb_state.list_branches_in_workspace()?;
+    stacks.sort_by_key(|stack| stack.order);
+    ranges_cache::workspace_ranges(ctx, &target.sha, &stacks)
+}
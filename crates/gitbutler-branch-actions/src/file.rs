@@ -1,7 +1,4 @@
-use std::{
-    collections::HashMap,
-    path::{self, Path, PathBuf},
-};
+use std::path::{self, Path, PathBuf};
 
 use anyhow::{anyhow, Context, Result};
 use gitbutler_cherry_pick::RepositoryExt as _;
@@ -9,10 +6,7 @@ use gitbutler_command_context::CommandContext;
 use gitbutler_diff::FileDiff;
 use serde::Serialize;
 
-use crate::{
-    conflicts,
-    hunk::{file_hunks_from_diffs, VirtualBranchHunk},
-};
+use crate::{conflicts, hunk::VirtualBranchHunk};
 
 #[derive(Debug, PartialEq, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -105,39 +99,6 @@ impl Get<VirtualBranchFile> for Vec<VirtualBranchFile> {
     }
 }
 
-pub(crate) fn list_virtual_commit_files(
-    ctx: &CommandContext,
-    commit: &git2::Commit,
-    context_lines: bool,
-) -> Result<Vec<VirtualBranchFile>> {
-    if commit.parent_count() == 0 {
-        return Ok(vec![]);
-    }
-    let parent = commit.parent(0).context("failed to get parent commit")?;
-    let repository = ctx.repository();
-    let commit_tree = repository
-        .find_real_tree(commit, Default::default())
-        .context("failed to get commit tree")?;
-    let parent_tree = repository
-        .find_real_tree(&parent, Default::default())
-        .context("failed to get parent tree")?;
-    let diff = gitbutler_diff::trees(ctx.repository(), &parent_tree, &commit_tree, context_lines)?;
-    let hunks_by_filepath = virtual_hunks_by_file_diffs(&ctx.project().path, diff);
-    Ok(virtual_hunks_into_virtual_files(ctx, hunks_by_filepath))
-}
-
-fn virtual_hunks_by_file_diffs<'a>(
-    project_path: &'a Path,
-    diff: impl IntoIterator<Item = (PathBuf, FileDiff)> + 'a,
-) -> HashMap<PathBuf, Vec<VirtualBranchHunk>> {
-    file_hunks_from_diffs(
-        project_path,
-        diff.into_iter()
-            .map(move |(file_path, file)| (file_path, file.hunks)),
-        None,
-    )
-}
-
 /// NOTE: There is no use returning an iterator here as this acts like the final product.
 pub(crate) fn virtual_hunks_into_virtual_files(
     ctx: &CommandContext,
@@ -0,0 +1,222 @@
+//! Exporting the commits of a stack as a patch series, like `git format-patch --cover-letter`, to
+//! submit them to projects that take patches by mail.
+//!
+//! The series starts with a cover letter whose subject is the stack name and whose body is made
+//! of the descriptions of its branches, followed by a summary of the patches. It's written either
+//! as numbered `.patch` files, or as a single mbox that can be applied back with `apply_patch`.
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context as _, Result};
+use gitbutler_command_context::CommandContext;
+use gitbutler_commit::commit_ext::CommitExt as _;
+use gitbutler_error::error::Code;
+use gitbutler_repo::{LogUntil, RepositoryExt as _};
+use gitbutler_stack::StackId;
+use itertools::Itertools as _;
+use serde::Deserialize;
+
+use crate::VirtualBranchesExt as _;
+
+const COVER_LETTER_FILE_NAME: &str = "0000-cover-letter.patch";
+/// The longest a file name made of a commit subject may be, without number and extension, like
+/// with Git.
+const MAX_FILE_NAME_LEN: usize = 52;
+
+/// How a patch series is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PatchFormat {
+    /// A `.patch` file for each patch, numbered in application order.
+    Files,
+    /// All patches in a single `.mbox` file.
+    Mbox,
+}
+
+/// Write the commits of the stack with `stack_id` as a series of patches with a cover letter
+/// into `dir` in `format`, and return the paths of the files that were written.
+///
+/// This fails with [`Code::Validation`] if the stack has no commits, or conflicted ones.
+pub(crate) fn format_patch(
+    ctx: &CommandContext,
+    stack_id: StackId,
+    dir: &Path,
+    format: PatchFormat,
+) -> Result<Vec<PathBuf>> {
+    let repo = ctx.repository();
+    let stack = ctx
+        .project()
+        .virtual_branches()
+        .get_branch_in_workspace(stack_id)?;
+    let merge_base = stack.merge_base(ctx)?.id();
+    let commits = repo
+        .log(stack.head(), LogUntil::Commit(merge_base), false)?
+        .into_iter()
+        .rev()
+        .collect_vec();
+    if commits.is_empty() {
+        return Err(anyhow!("There are no commits to export").context(Code::Validation));
+    }
+    if let Some(commit) = commits.iter().find(|commit| commit.is_conflicted()) {
+        return Err(
+            anyhow!("Commit {} is conflicted and can't be exported", commit.id())
+                .context(Code::Validation),
+        );
+    }
+
+    let base_tree = repo.find_commit(merge_base)?.tree()?;
+    let head_tree = commits.last().context("commits can't be empty")?.tree()?;
+    let descriptions = stack
+        .heads
+        .iter()
+        .filter_map(|head| head.description.as_deref())
+        .map(str::trim)
+        .filter(|description| !description.is_empty())
+        .collect_vec();
+    let cover_letter = cover_letter(
+        repo,
+        &stack.name,
+        &descriptions,
+        &commits,
+        &repo.diff_tree_to_tree(Some(&base_tree), Some(&head_tree), None)?,
+    )?;
+
+    let mut patches = Vec::with_capacity(commits.len());
+    for (index, commit) in commits.iter().enumerate() {
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => parent.tree()?,
+            Err(_) => base_tree.clone(),
+        };
+        let diff = repo.diff_tree_to_tree(Some(&parent_tree), Some(&commit.tree()?), None)?;
+        let email = git2::Email::from_diff(
+            &diff,
+            index + 1,
+            commits.len(),
+            &commit.id(),
+            commit.summary().unwrap_or_default(),
+            commit.body().unwrap_or_default(),
+            &commit.author(),
+            &mut git2::EmailCreateOptions::new(),
+        )
+        .with_context(|| format!("failed to format commit {}", commit.id()))?;
+        patches.push((
+            format!("{:04}-{}.patch", index + 1, file_name(commit.summary())),
+            email.as_slice().to_owned(),
+        ));
+    }
+
+    std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let files = match format {
+        PatchFormat::Files => std::iter::once((COVER_LETTER_FILE_NAME.to_owned(), cover_letter))
+            .chain(patches)
+            .collect_vec(),
+        PatchFormat::Mbox => {
+            let mut mbox = cover_letter;
+            for (_, patch) in patches {
+                mbox.extend(patch);
+            }
+            vec![(format!("{}.mbox", file_name(Some(&stack.name))), mbox)]
+        }
+    };
+    files
+        .into_iter()
+        .map(|(name, contents)| {
+            let path = dir.join(name);
+            std::fs::write(&path, contents)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// The first mail of the series, introducing it with `descriptions`, and listing the patches by
+/// author along with the changes they make together in `diff`.
+fn cover_letter(
+    repo: &git2::Repository,
+    name: &str,
+    descriptions: &[&str],
+    commits: &[git2::Commit],
+    diff: &git2::Diff,
+) -> Result<Vec<u8>> {
+    let (author, _) = repo.signatures()?;
+    let date = gix::date::Time::now_local_or_utc().format(gix::date::time::format::RFC2822);
+    let mut letter = format!(
+        "From {} Mon Sep 17 00:00:00 2001\nFrom: {} <{}>\nDate: {date}\nSubject: [PATCH 0/{}] {}\n\n",
+        git2::Oid::zero(),
+        author.name().unwrap_or_default(),
+        author.email().unwrap_or_default(),
+        commits.len(),
+        name.trim(),
+    );
+    for description in descriptions {
+        letter.push_str(description);
+        letter.push_str("\n\n");
+    }
+
+    // Like `git shortlog`, the patches are grouped by author in the order they first appear.
+    let by_author = commits
+        .iter()
+        .into_group_map_by(|commit| commit.author().name().unwrap_or_default().to_owned());
+    let authors = commits
+        .iter()
+        .map(|commit| commit.author().name().unwrap_or_default().to_owned())
+        .unique();
+    for author in authors {
+        let commits = &by_author[&author];
+        letter.push_str(&format!("{author} ({}):\n", commits.len()));
+        for commit in commits {
+            letter.push_str(&format!("  {}\n", commit.summary().unwrap_or_default()));
+        }
+        letter.push('\n');
+    }
+
+    let stats = diff
+        .stats()?
+        .to_buf(git2::DiffStatsFormat::FULL, 72)
+        .context("failed to summarize the changes")?;
+    letter.push_str(&String::from_utf8_lossy(&stats));
+    letter.push_str("\n-- \nGitButler\n\n");
+    Ok(letter.into_bytes())
+}
+
+/// Turn `subject` into a file name like Git does, keeping letters, digits, dots and underscores,
+/// and replacing everything in between with a dash.
+fn file_name(subject: Option<&str>) -> String {
+    let mut name = String::new();
+    for c in subject.unwrap_or_default().chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            name.push(c);
+        } else if !name.is_empty() && !name.ends_with('-') {
+            name.push('-');
+        }
+        if name.len() >= MAX_FILE_NAME_LEN {
+            break;
+        }
+    }
+    let name = name.trim_end_matches(['-', '.']);
+    if name.is_empty() {
+        "patch".to_owned()
+    } else {
+        name.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_names_are_made_of_subjects() {
+        assert_eq!(
+            file_name(Some("Fix: the parser (again)")),
+            "Fix-the-parser-again"
+        );
+        assert_eq!(file_name(Some("bump v1.2.")), "bump-v1.2");
+        assert_eq!(file_name(Some("!!!")), "patch");
+        assert_eq!(file_name(None), "patch");
+        assert_eq!(
+            file_name(Some(&"a ".repeat(40))).len(),
+            MAX_FILE_NAME_LEN - 1,
+            "long subjects are cut, without the trailing dash"
+        );
+    }
+}
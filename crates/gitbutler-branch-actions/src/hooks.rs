@@ -0,0 +1,272 @@
+//! Running the `pre-commit`, `commit-msg` and `post-commit` hooks of a repository when committing.
+//!
+//! Hooks are looked up in `core.hooksPath` if it's set, and in `.git/hooks` otherwise, like Git
+//! does. Hooks in `.husky` in the worktree are used as well if there is none, for projects set up
+//! with older versions of Husky. They run in the worktree with their output captured, and are
+//! killed if they don't finish within [`HOOK_TIMEOUT`].
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use gitbutler_error::error::Code;
+
+/// How long a hook may run before it's killed and the commit fails.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+const PRE_COMMIT: &str = "pre-commit";
+const COMMIT_MSG: &str = "commit-msg";
+const POST_COMMIT: &str = "post-commit";
+
+/// The file the message is passed to the `commit-msg` hook in, relative to the Git directory.
+const COMMIT_MESSAGE_FILE: &str = "COMMIT_EDITMSG";
+/// The directory of hooks set up by older versions of Husky, relative to the worktree.
+const HUSKY_DIR: &str = ".husky";
+
+/// Run the `pre-commit` hook, and fail with [`Code::CommitHookFailed`] if it rejects the commit.
+pub(crate) fn pre_commit(repo: &git2::Repository) -> Result<()> {
+    match run(repo, PRE_COMMIT, &[], HOOK_TIMEOUT)? {
+        Some(output) if !output.success => {
+            Err(anyhow!("commit hook rejected: {}", output.output.trim())
+                .context(Code::CommitHookFailed))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Run the `commit-msg` hook with `message`, and fail with [`Code::CommitHookFailed`] if it
+/// rejects it. The hook may change the message, which is then written back to `message`.
+pub(crate) fn commit_msg(repo: &git2::Repository, message: &mut String) -> Result<()> {
+    if find(repo, COMMIT_MSG)?.is_none() {
+        return Ok(());
+    }
+    let message_path = repo.path().join(COMMIT_MESSAGE_FILE);
+    std::fs::write(&message_path, message.as_bytes())
+        .with_context(|| format!("failed to write {}", message_path.display()))?;
+    let output = run(
+        repo,
+        COMMIT_MSG,
+        &[message_path.as_os_str().to_string_lossy().as_ref()],
+        HOOK_TIMEOUT,
+    )?;
+    if let Some(output) = output.filter(|output| !output.success) {
+        return Err(
+            anyhow!("commit-msg hook rejected: {}", output.output.trim())
+                .context(Code::CommitHookFailed),
+        );
+    }
+    *message = std::fs::read_to_string(&message_path)
+        .with_context(|| format!("failed to read {}", message_path.display()))?;
+    Ok(())
+}
+
+/// Run the `post-commit` hook. The commit is made by then, so like with Git, it's only logged if
+/// the hook fails.
+pub(crate) fn post_commit(repo: &git2::Repository) {
+    match run(repo, POST_COMMIT, &[], HOOK_TIMEOUT) {
+        Ok(Some(output)) if !output.success => {
+            tracing::warn!("post-commit hook failed: {}", output.output.trim());
+        }
+        Ok(_) => {}
+        Err(err) => tracing::warn!("failed to run post-commit hook: {err:#}"),
+    }
+}
+
+#[derive(Debug)]
+struct HookOutput {
+    success: bool,
+    /// What the hook wrote to stdout, followed by what it wrote to stderr.
+    output: String,
+}
+
+/// Run the hook called `name` with `args`, and return its output, or `None` if there is no
+/// such hook.
+fn run(
+    repo: &git2::Repository,
+    name: &str,
+    args: &[&str],
+    timeout: Duration,
+) -> Result<Option<HookOutput>> {
+    let Some(hook) = find(repo, name)? else {
+        return Ok(None);
+    };
+    let workdir = repo.workdir().unwrap_or_else(|| repo.path());
+
+    let mut cmd = if cfg!(windows) {
+        // Hooks are scripts, which only Git's shell can run on Windows.
+        let mut cmd = Command::new("sh");
+        cmd.arg(&hook);
+        cmd
+    } else {
+        Command::new(&hook)
+    };
+    let mut child = cmd
+        .args(args)
+        .current_dir(workdir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run {name} hook at {}", hook.display()))
+        .context(Code::CommitHookFailed)?;
+
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            child.kill().ok();
+            child.wait().ok();
+            // The readers are left behind, as processes the hook started may still hold the
+            // pipes open.
+            return Err(
+                anyhow!("{name} hook timed out after {timeout:?}").context(Code::CommitHookFailed)
+            );
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    let mut output = stdout.join().unwrap_or_default();
+    output.push_str(&stderr.join().unwrap_or_default());
+    Ok(Some(HookOutput {
+        success: status.success(),
+        output,
+    }))
+}
+
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut buffer).ok();
+        }
+        String::from_utf8_lossy(&buffer).into_owned()
+    })
+}
+
+/// Return the path to the hook called `name` if there is one that can run.
+fn find(repo: &git2::Repository, name: &str) -> Result<Option<PathBuf>> {
+    let hooks_dir = hooks_dir(repo)?;
+    let husky_dir = repo.workdir().map(|workdir| workdir.join(HUSKY_DIR));
+    Ok(std::iter::once(hooks_dir)
+        .chain(husky_dir)
+        .map(|dir| dir.join(name))
+        .find(|path| is_executable(path)))
+}
+
+/// The directory of hooks, which is `core.hooksPath` relative to the worktree if it's set.
+fn hooks_dir(repo: &git2::Repository) -> Result<PathBuf> {
+    let hooks_path = match repo.config()?.get_path("core.hooksPath") {
+        Ok(path) => Some(path),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+    Ok(match hooks_path {
+        Some(path) if path.is_absolute() => path,
+        Some(path) => repo.workdir().unwrap_or_else(|| repo.path()).join(path),
+        None => repo.path().join("hooks"),
+    })
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt as _;
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use gitbutler_testsupport::testing_repository::TestingRepository;
+
+    use super::*;
+
+    fn create_hook(dir: &Path, name: &str, script: &str) {
+        use std::os::unix::fs::PermissionsExt as _;
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{script}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn hooks_are_found_in_core_hooks_path() {
+        let test_repository = TestingRepository::open();
+        let repo = &test_repository.repository;
+        let workdir = repo.workdir().unwrap();
+        create_hook(&repo.path().join("hooks"), PRE_COMMIT, "exit 0");
+        create_hook(
+            &workdir.join("custom-hooks"),
+            PRE_COMMIT,
+            "echo custom; exit 1",
+        );
+
+        assert!(pre_commit(repo).is_ok());
+
+        repo.config()
+            .unwrap()
+            .set_str("core.hooksPath", "custom-hooks")
+            .unwrap();
+        let err = pre_commit(repo).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "commit hook rejected: custom");
+        assert_eq!(err.downcast_ref::<Code>(), Some(&Code::CommitHookFailed));
+    }
+
+    #[test]
+    fn output_is_captured_from_stdout_and_stderr() {
+        let test_repository = TestingRepository::open();
+        let repo = &test_repository.repository;
+        create_hook(
+            &repo.path().join("hooks"),
+            PRE_COMMIT,
+            "echo out; echo err >&2; exit 1",
+        );
+
+        let output = run(repo, PRE_COMMIT, &[], HOOK_TIMEOUT).unwrap().unwrap();
+        assert!(!output.success);
+        assert_eq!(output.output, "out\nerr\n");
+        assert!(run(repo, POST_COMMIT, &[], HOOK_TIMEOUT).unwrap().is_none());
+    }
+
+    #[test]
+    fn commit_msg_can_change_the_message() {
+        let test_repository = TestingRepository::open();
+        let repo = &test_repository.repository;
+        create_hook(
+            &repo.path().join("hooks"),
+            COMMIT_MSG,
+            r#"echo "Signed-off-by: Me <me@example.com>" >> "$1""#,
+        );
+
+        let mut message = "subject\n\n".to_owned();
+        commit_msg(repo, &mut message).unwrap();
+        assert_eq!(message, "subject\n\nSigned-off-by: Me <me@example.com>\n");
+    }
+
+    #[test]
+    fn hooks_time_out() {
+        let test_repository = TestingRepository::open();
+        let repo = &test_repository.repository;
+        create_hook(&repo.path().join("hooks"), PRE_COMMIT, "sleep 10");
+
+        let started = Instant::now();
+        let err = run(repo, PRE_COMMIT, &[], Duration::from_millis(100)).unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "pre-commit hook timed out after 100ms"
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
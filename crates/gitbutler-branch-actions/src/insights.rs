@@ -0,0 +1,242 @@
+//! Statistics about who contributed to the target and the workspace, for an insights panel.
+//!
+//! Commits by bots and changes to generated files would skew them, so both are excluded as configured in
+//! `.gitbutler/insights.toml` in the worktree, with all settings being optional:
+//!
+//! ```toml
+//! # Regular expressions matched against author emails, by default only `\[bot\]@`.
+//! bot_email_patterns = ['\[bot\]@', '^ci@example\.com$']
+//! # Paths, or directories if they end with `/`, whose changes aren't counted.
+//! generated_paths = ["Cargo.lock", "dist/"]
+//! ```
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use gitbutler_cherry_pick::RepositoryExt as _;
+use gitbutler_command_context::CommandContext;
+use gitbutler_error::error::Code;
+use gitbutler_repo::{LogUntil, RepositoryExt as _};
+use serde::{Deserialize, Serialize};
+
+use crate::VirtualBranchesExt;
+
+/// The location of the insights configuration, relative to the root of the worktree.
+pub const INSIGHTS_FILE: &str = ".gitbutler/insights.toml";
+
+/// The most commits of the target that are looked at, so large histories stay fast to go through.
+const MAX_TARGET_COMMITS: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    rename_all(serialize = "camelCase", deserialize = "snake_case"),
+    default,
+    deny_unknown_fields
+)]
+pub struct InsightsConfig {
+    /// Regular expressions, and commits by authors with an email matching any of them are excluded.
+    pub bot_email_patterns: Vec<String>,
+    /// Paths, or directories if they end with `/`, whose changes aren't counted.
+    pub generated_paths: Vec<String>,
+}
+
+impl Default for InsightsConfig {
+    fn default() -> Self {
+        InsightsConfig {
+            bot_email_patterns: vec![r"\[bot\]@".into()],
+            generated_paths: Vec::new(),
+        }
+    }
+}
+
+impl InsightsConfig {
+    /// Read the configuration of the worktree at `worktree_dir`, which is the default if there is no file.
+    pub fn load(worktree_dir: &Path) -> Result<Self> {
+        let path = worktree_dir.join(INSIGHTS_FILE);
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err).with_context(|| format!("failed to read {INSIGHTS_FILE}")),
+        };
+        let config: InsightsConfig = toml::from_str(&contents)
+            .with_context(|| format!("{INSIGHTS_FILE} is invalid"))
+            .context(Code::Validation)?;
+        config.bot_emails()?;
+        Ok(config)
+    }
+
+    fn bot_emails(&self) -> Result<regex::RegexSet> {
+        regex::RegexSet::new(&self.bot_email_patterns)
+            .with_context(|| format!("{INSIGHTS_FILE} has an invalid bot_email_patterns"))
+            .context(Code::Validation)
+    }
+
+    fn is_generated(&self, path: &Path) -> bool {
+        self.generated_paths
+            .iter()
+            .any(|generated| match generated.strip_suffix('/') {
+                Some(dir) => path.starts_with(dir),
+                None => path == Path::new(generated),
+            })
+    }
+}
+
+/// What a single author contributed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorStats {
+    /// The name of the author in their most recent commit.
+    pub name: String,
+    pub email: String,
+    pub commits: usize,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    /// The amount of distinct files changed.
+    pub files_changed: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Contributors {
+    /// The authors, the one with the most commits first.
+    pub authors: Vec<AuthorStats>,
+    /// How many commits were left out as they are by bots.
+    pub excluded_commits: usize,
+}
+
+/// Compute the statistics of all authors of commits in the workspace and in the target, optionally only
+/// of commits made at or after `since`, in seconds since the Unix epoch. Merge commits aren't counted.
+pub fn contributors(ctx: &CommandContext, since: Option<i64>) -> Result<Contributors> {
+    let config = InsightsConfig::load(&ctx.project().path)?;
+    let bot_emails = config.bot_emails()?;
+    let repo = ctx.repository();
+    let vb_state = ctx.project().virtual_branches();
+    let target = vb_state.get_default_target()?;
+
+    let mut commit_ids = Vec::new();
+    for stack in vb_state.list_branches_in_workspace()? {
+        commit_ids.extend(repo.l(stack.head(), LogUntil::Commit(target.sha), false)?);
+    }
+    commit_ids.extend(repo.l(target.sha, LogUntil::Take(MAX_TARGET_COMMITS), false)?);
+
+    let mut seen = HashSet::new();
+    let mut by_email: BTreeMap<String, (AuthorStats, HashSet<PathBuf>)> = BTreeMap::new();
+    let mut excluded_commits = 0;
+    for commit_id in commit_ids {
+        if !seen.insert(commit_id) {
+            continue;
+        }
+        let commit = repo.find_commit(commit_id)?;
+        if commit.parent_count() > 1 || since.is_some_and(|since| commit.time().seconds() < since) {
+            continue;
+        }
+        let author = commit.author();
+        let email = String::from_utf8_lossy(author.email_bytes()).to_lowercase();
+        if bot_emails.is_match(&email) {
+            excluded_commits += 1;
+            continue;
+        }
+
+        let (stats, files) = by_email.entry(email.clone()).or_insert_with(|| {
+            (
+                AuthorStats {
+                    // Commits are visited newest first.
+                    name: String::from_utf8_lossy(author.name_bytes()).into_owned(),
+                    email,
+                    commits: 0,
+                    lines_added: 0,
+                    lines_removed: 0,
+                    files_changed: 0,
+                },
+                HashSet::new(),
+            )
+        });
+        stats.commits += 1;
+        add_changes(repo, &commit, &config, stats, files)?;
+    }
+
+    let mut authors: Vec<_> = by_email
+        .into_values()
+        .map(|(stats, files)| AuthorStats {
+            files_changed: files.len(),
+            ..stats
+        })
+        .collect();
+    authors.sort_by(|a, b| {
+        b.commits
+            .cmp(&a.commits)
+            .then_with(|| a.email.cmp(&b.email))
+    });
+    Ok(Contributors {
+        authors,
+        excluded_commits,
+    })
+}
+
+/// Add the lines `commit` changed compared to its parent to `stats`, and the paths it changed to `files`,
+/// except for generated files.
+fn add_changes(
+    repo: &git2::Repository,
+    commit: &git2::Commit,
+    config: &InsightsConfig,
+    stats: &mut AuthorStats,
+    files: &mut HashSet<PathBuf>,
+) -> Result<()> {
+    let tree = repo.find_real_tree(commit, Default::default())?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(repo.find_real_tree(&parent, Default::default())?),
+        Err(_) => None,
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+    for (index, delta) in diff.deltas().enumerate() {
+        let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+            continue;
+        };
+        if config.is_generated(path) {
+            continue;
+        }
+        files.insert(path.to_owned());
+        if let Some(patch) = git2::Patch::from_diff(&diff, index)? {
+            let (_context, added, removed) = patch.line_stats()?;
+            stats.lines_added += added;
+            stats.lines_removed += removed;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_config() -> Result<()> {
+        let config: InsightsConfig =
+            toml::from_str(r#"generated_paths = ["Cargo.lock", "dist/"]"#)?;
+        assert_eq!(
+            config.bot_email_patterns,
+            InsightsConfig::default().bot_email_patterns,
+            "bots are excluded by default"
+        );
+        assert!(config.is_generated(Path::new("Cargo.lock")));
+        assert!(config.is_generated(Path::new("dist/app.js")));
+        assert!(!config.is_generated(Path::new("src/Cargo.lock")));
+
+        assert!(toml::from_str::<InsightsConfig>("bots = []").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn bot_emails() -> Result<()> {
+        let bot_emails = InsightsConfig::default().bot_emails()?;
+        assert!(bot_emails.is_match("dependabot[bot]@users.noreply.github.com"));
+        assert!(!bot_emails.is_match("jane@example.com"));
+
+        let invalid = InsightsConfig {
+            bot_email_patterns: vec!["[".into()],
+            ..Default::default()
+        };
+        assert!(invalid.bot_emails().is_err());
+        Ok(())
+    }
+}
@@ -7,12 +7,13 @@ use gitbutler_branch::{self, GITBUTLER_WORKSPACE_REFERENCE};
 use gitbutler_cherry_pick::RepositoryExt as _;
 use gitbutler_command_context::CommandContext;
 use gitbutler_commit::commit_ext::CommitExt;
+use gitbutler_config::git::GitConfig;
 use gitbutler_error::error::Marker;
 use gitbutler_operating_modes::OPEN_WORKSPACE_REFS;
 use gitbutler_project::access::WorktreeWritePermission;
 use gitbutler_repo::SignaturePurpose;
 use gitbutler_repo::{LogUntil, RepositoryExt};
-use gitbutler_stack::{Stack, VirtualBranchesHandle};
+use gitbutler_stack::{Stack, Target, VirtualBranchesHandle};
 use tracing::instrument;
 
 use crate::{branch_manager::BranchManagerExt, conflicts, VirtualBranchesExt};
@@ -20,6 +21,8 @@ use crate::{branch_manager::BranchManagerExt, conflicts, VirtualBranchesExt};
 const WORKSPACE_HEAD: &str = "Workspace Head";
 const GITBUTLER_INTEGRATION_COMMIT_TITLE: &str = "GitButler Integration Commit";
 pub const GITBUTLER_WORKSPACE_COMMIT_TITLE: &str = "GitButler Workspace Commit";
+/// The title of the intermediate merges if the workspace commit is built from a chain of merges.
+const GITBUTLER_WORKSPACE_MERGE_TITLE: &str = "GitButler Workspace Merge";
 
 /// Creates and returns a merge commit of all active branch heads.
 ///
@@ -212,11 +215,13 @@ pub fn update_workspace_commit(
     // It would be nice if we could pass an `update_ref` parameter to this function, but that
     // requires committing to the tip of the branch, and we're mostly replacing the tip.
 
-    let parents = workspace_head.parents().collect::<Vec<_>>();
+    let mut parents = workspace_head.parents().collect::<Vec<_>>();
     let workspace_tree = workspace_head.tree()?;
+    if parents.len() > 2 && repo.gb_config()?.workspace_merge_chain.unwrap_or(false) {
+        parents = chain_of_merges(repo, parents, &target, &workspace_tree)?;
+    }
 
-    let final_commit = repo.commit(
-        None,
+    let final_commit = repo.commit_for_gitbutler_ref(
         &author,
         &committer,
         &message,
@@ -253,14 +258,12 @@ pub fn update_workspace_commit(
             message.push_str("This commit is used to store the state of the virtual branch\n");
             message.push_str("while you are working on it. It is not meant to be used for\n");
             message.push_str("anything else.\n\n");
-            let branch_head_oid = repo.commit(
-                None,
+            let branch_head_oid = repo.commit_for_gitbutler_ref(
                 &committer,
                 &committer,
                 &message,
                 &wip_tree,
                 &[&branch_head],
-                // None,
             )?;
             branch_head = repo.find_commit(branch_head_oid)?;
         }
@@ -273,9 +276,60 @@ pub fn update_workspace_commit(
         )?;
     }
 
+    // Commits may have been rewritten, and notes don't follow them on their own.
+    if let Err(err) = crate::attachments::mirror_stacks_to_notes(ctx, &virtual_branches, target.sha)
+    {
+        tracing::warn!(?err, "failed to mirror commit attachments to notes");
+    }
+
     Ok(final_commit)
 }
 
+/// Merge all `heads` except for the last one into a chain of merge commits with two parents each, the first
+/// parent being the previous merge, and return the end of the chain along with the last head. These are the
+/// parents of a workspace commit with the same tree as a merge of all `heads`, but without octopus merges.
+///
+/// Each merge has the tree of the heads merged so far, or `workspace_tree` if they don't merge cleanly
+/// on their own, like while resolving conflicts.
+fn chain_of_merges<'repo>(
+    repo: &'repo git2::Repository,
+    mut heads: Vec<git2::Commit<'repo>>,
+    target: &Target,
+    workspace_tree: &git2::Tree<'repo>,
+) -> Result<Vec<git2::Commit<'repo>>> {
+    let last = heads.pop().context("workspace has no parents")?;
+    let mut heads = heads.into_iter();
+    let mut chain = heads.next().context("workspace has a single parent")?;
+
+    let committer = gitbutler_repo::signature(SignaturePurpose::Committer)?;
+    let author = gitbutler_repo::signature(SignaturePurpose::Author)?;
+    let base_tree = repo.find_commit(target.sha)?.tree()?;
+    let mut merged_tree = Some(repo.find_real_tree(&chain, Default::default())?);
+    for head in heads {
+        let head_tree = repo.find_real_tree(&head, Default::default())?;
+        merged_tree = match merged_tree {
+            Some(merged_tree) => {
+                let mut index = repo.merge_trees(&base_tree, &merged_tree, &head_tree, None)?;
+                if index.has_conflicts() {
+                    None
+                } else {
+                    Some(repo.find_tree(index.write_tree_to(repo)?)?)
+                }
+            }
+            None => None,
+        };
+        let merge_id = repo.commit_for_gitbutler_ref(
+            &author,
+            &committer,
+            GITBUTLER_WORKSPACE_MERGE_TITLE,
+            merged_tree.as_ref().unwrap_or(workspace_tree),
+            &[&chain, &head],
+        )?;
+        chain = repo.find_commit(merge_id)?;
+    }
+    Ok(vec![chain, last])
+}
+
 pub fn verify_branch(ctx: &CommandContext, perm: &mut WorktreeWritePermission) -> Result<()> {
     verify_current_branch_name(ctx)
         .and_then(verify_head_is_set)
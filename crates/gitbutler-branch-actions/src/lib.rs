@@ -2,17 +2,24 @@
 mod actions;
 // This is our API
 pub use actions::{
-    amend, can_apply_remote_branch, create_commit, create_virtual_branch,
-    create_virtual_branch_from_branch, delete_local_branch, fetch_from_remotes, find_commit,
+    absorb, add_commit_attachment, amend, apply_patch, autosquash, autostash_conflicts,
+    branch_summary, can_apply_remote_branch, commit_dependencies, commit_summary, contributors,
+    copy_commits_to_stack, create_commit, create_commit_from_hunks, create_virtual_branch,
+    create_virtual_branch_from_branch, cross_stack_conflicts, delete_local_branch, delete_shelf,
+    dependency_timings, dismiss_autostash_conflicts, fetch_from_remotes, find_commit, format_patch,
     get_base_branch_data, get_remote_branch_data, get_uncommited_files,
-    get_uncommited_files_reusable, insert_blank_commit, integrate_upstream,
-    integrate_upstream_commits, list_commit_files, list_local_branches, list_virtual_branches,
-    list_virtual_branches_cached, move_commit, move_commit_file, push_base_branch,
-    push_virtual_branch, reorder_stack, reset_files, reset_virtual_branch,
-    resolve_upstream_integration, save_and_unapply_virutal_branch, set_base_branch,
-    set_target_push_remote, squash, unapply_ownership, unapply_without_saving_virtual_branch,
-    undo_commit, update_branch_order, update_commit_message, update_virtual_branch,
-    upstream_integration_statuses,
+    get_uncommited_files_reusable, hunk_dependency_graph, hunk_range_errors, insert_blank_commit,
+    integrate_upstream, integrate_upstream_commits, line_owners, list_commit_attachments,
+    list_commit_files, list_commits_touching, list_local_branches, list_shelves,
+    list_virtual_branches, list_virtual_branches_cached, move_commit, move_commit_file,
+    move_commit_hunks, policy_report, predict_operation, push_base_branch, push_virtual_branch,
+    remove_commit_attachment, remove_commit_preview, reorder_stack, reset_files,
+    reset_virtual_branch, resolve_upstream_integration, revert_commit,
+    save_and_unapply_virutal_branch, set_base_branch, set_target_push_remote, shelve_changes,
+    split_commit, squash, squash_commits, stack_activity, unapply_ownership,
+    unapply_without_saving_virtual_branch, undo_commit, unshelve_changes, update_branch_order,
+    update_commit_message, update_virtual_branch, upstream_integration_statuses,
+    validate_commit_message, verify_commit_signatures,
 };
 
 mod r#virtual;
@@ -33,6 +40,22 @@ pub use base::BaseBranch;
 
 pub mod upstream_integration;
 
+pub mod policy;
+
+pub mod summary;
+
+pub mod attachments;
+
+pub mod touches;
+
+pub mod insights;
+
+pub mod activity;
+
+pub mod autostash;
+
+pub mod dependencies;
+
 mod integration;
 pub use integration::{update_workspace_commit, verify_branch};
 
@@ -46,12 +69,30 @@ pub mod conflicts;
 
 pub mod branch_trees;
 pub mod branch_upstream_integration;
+mod move_commit_hunks;
 mod move_commits;
 pub mod reorder;
 pub use reorder::{SeriesOrder, StackOrder};
+mod absorb;
+mod apply_patch;
+mod autosquash;
+mod copy_commits;
+mod format_patch;
+mod revert_commit;
+mod shelves;
+mod split_commit;
+mod squash_commits;
 mod undo_commit;
+pub use absorb::{AbsorbReport, AbsorbedHunk, SkipReason, SkippedHunk};
+pub use apply_patch::{ApplyPatchReport, RejectReason, RejectedHunk};
+pub use format_patch::PatchFormat;
+pub use revert_commit::RevertOutcome;
+pub use shelves::Shelf;
+pub use split_commit::SplitAssignment;
 
 mod author;
+mod hooks;
+mod ranges_cache;
 mod status;
 use gitbutler_stack::VirtualBranchesHandle;
 pub use status::get_applied_status;
@@ -66,7 +107,10 @@ impl VirtualBranchesExt for gitbutler_project::Project {
 }
 
 mod branch;
+mod snapshot;
+pub use snapshot::BranchesChanged;
 mod commit;
+pub use commit::CommitSignature;
 mod hunk;
 
 pub use branch::{
@@ -76,4 +120,6 @@ pub use branch::{
 
 pub use integration::GITBUTLER_WORKSPACE_COMMIT_TITLE;
 
+pub mod co_authors;
 pub mod stack;
+pub mod stack_templates;
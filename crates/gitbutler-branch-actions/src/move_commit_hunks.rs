@@ -0,0 +1,396 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use bstr::ByteSlice as _;
+use gitbutler_command_context::CommandContext;
+use gitbutler_commit::{commit_ext::CommitExt as _, commit_headers::HasCommitHeaders as _};
+use gitbutler_diff::{GitHunk, Hunk};
+use gitbutler_error::error::Code;
+use gitbutler_repo::{rebase::cherry_rebase_group, LogUntil, RepositoryExt as _};
+use gitbutler_stack::{BranchOwnershipClaims, StackId};
+
+use crate::{split_commit::partial_tree, VirtualBranchesExt as _};
+
+/// Move the hunks in `ownership` out of the commit with `from_commit_id` and into the commit with
+/// `to_commit_id`, both in the stack with `stack_id`, and return the new ID of the "to" commit.
+///
+/// The hunks are those of the diff of the "from" commit to its parent, with their ranges in the
+/// files after the commit. The "to" commit may be above or below the "from" commit, and the
+/// commits in between are rebased. If this conflicts, nothing changes. A commit all of whose hunks
+/// are moved out stays in the stack without changes.
+pub(crate) fn move_commit_hunks(
+    ctx: &CommandContext,
+    stack_id: StackId,
+    from_commit_id: git2::Oid,
+    to_commit_id: git2::Oid,
+    ownership: &BranchOwnershipClaims,
+) -> Result<git2::Oid> {
+    ctx.assure_resolved()?;
+
+    let vb_state = ctx.project().virtual_branches();
+    let mut stack = vb_state.get_branch_in_workspace(stack_id)?;
+    let default_target = vb_state.get_default_target()?;
+    let repository = ctx.repository();
+
+    let MoveResult {
+        new_head,
+        to_commit_id,
+    } = inner_move_commit_hunks(
+        repository,
+        stack.head(),
+        default_target.sha,
+        from_commit_id,
+        to_commit_id,
+        ownership,
+        ctx.project().auto_resolve_whitespace,
+    )?;
+
+    let pushed_commit_ids = stack.upstream_head.map_or_else(
+        || Ok(vec![]),
+        |upstream_head| repository.l(upstream_head, LogUntil::Commit(default_target.sha), false),
+    )?;
+    if !pushed_commit_ids.is_empty() && !stack.allow_rebasing {
+        let kept = repository.l(new_head, LogUntil::Commit(default_target.sha), false)?;
+        if pushed_commit_ids.iter().any(|id| !kept.contains(id)) {
+            // rewriting pushed commits will cause a force push that is not allowed
+            bail!("force push not allowed");
+        }
+    }
+
+    stack.set_stack_head(ctx, new_head, None)?;
+    crate::integration::update_workspace_commit(&vb_state, ctx)
+        .context("failed to update gitbutler workspace")?;
+    Ok(to_commit_id)
+}
+
+struct MoveResult {
+    new_head: git2::Oid,
+    to_commit_id: git2::Oid,
+}
+
+fn inner_move_commit_hunks(
+    repository: &git2::Repository,
+    stack_head: git2::Oid,
+    base: git2::Oid,
+    from_commit_id: git2::Oid,
+    to_commit_id: git2::Oid,
+    ownership: &BranchOwnershipClaims,
+    auto_resolve_whitespace: bool,
+) -> Result<MoveResult> {
+    if from_commit_id == to_commit_id {
+        bail!("can not move hunks into the commit they are in");
+    }
+    let mut commits = repository.l(stack_head, LogUntil::Commit(base), false)?;
+    commits.reverse();
+    let position = |id: git2::Oid| {
+        commits
+            .iter()
+            .position(|other| *other == id)
+            .with_context(|| format!("commit {id} not in the branch"))
+    };
+    let (from_position, to_position) = (position(from_commit_id)?, position(to_commit_id)?);
+
+    let from_commit = repository.find_commit(from_commit_id)?;
+    let to_commit = repository.find_commit(to_commit_id)?;
+    if from_commit.is_conflicted() || to_commit.is_conflicted() {
+        bail!("Can not move hunks of conflicted commits");
+    }
+    let from_tree = from_commit.tree().context("failed to get commit tree")?;
+    let from_parent = from_commit
+        .parent(0)
+        .context("failed to find parent commit")?;
+    let from_parent_tree = from_parent.tree().context("failed to get parent tree")?;
+
+    let diff = gitbutler_diff::trees(repository, &from_parent_tree, &from_tree, true)?;
+    let diff: HashMap<PathBuf, Vec<GitHunk>> =
+        gitbutler_diff::diff_files_into_hunks(diff).collect();
+    let mut moved: HashMap<&Path, Vec<usize>> = HashMap::new();
+    for claim in &ownership.claims {
+        let hunks = diff
+            .get(&claim.file_path)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        for claimed in &claim.hunks {
+            let position = hunks
+                .iter()
+                .position(|hunk| Hunk::from(hunk) == *claimed)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Hunk {}:{claimed} isn't part of commit {from_commit_id}",
+                        claim.file_path.display()
+                    )
+                    .context(Code::Validation)
+                })?;
+            moved
+                .entry(claim.file_path.as_path())
+                .or_default()
+                .push(position);
+        }
+    }
+    if moved.is_empty() {
+        return Err(anyhow!("No hunks to move").context(Code::Validation));
+    }
+
+    // The "from" commit without the moved hunks, so the moved hunks are the diff from this tree
+    // to the tree of the "from" commit, which can be picked onto the "to" commit.
+    let kept = diff
+        .iter()
+        .filter_map(|(path, hunks)| {
+            let moved = moved.get(path.as_path());
+            let kept = hunks
+                .iter()
+                .enumerate()
+                .filter(|(position, _)| moved.map_or(true, |moved| !moved.contains(position)))
+                .map(|(_, hunk)| hunk)
+                .collect::<Vec<_>>();
+            (!kept.is_empty()).then_some((path.as_path(), kept))
+        })
+        .collect();
+    let without_moved = repository.find_tree(partial_tree(
+        repository,
+        &from_parent_tree,
+        &from_tree,
+        &diff,
+        kept,
+    )?)?;
+    let with_moved = |commit: &git2::Commit| -> Result<git2::Tree> {
+        let mut merge_index =
+            repository.merge_trees(&without_moved, &commit.tree()?, &from_tree, None)?;
+        if merge_index.has_conflicts() {
+            bail!(
+                "Moving the hunks into commit '{}' would conflict",
+                subject(commit)
+            );
+        }
+        Ok(repository.find_tree(merge_index.write_tree_to(repository)?)?)
+    };
+    let rewrite = |commit: &git2::Commit, tree: &git2::Tree| -> Result<git2::Oid> {
+        let parents: Vec<_> = commit.parents().collect();
+        repository
+            .commit_with_signature(
+                None,
+                &commit.author(),
+                &commit.committer(),
+                &commit.message_bstr().to_str_lossy(),
+                tree,
+                &parents.iter().collect::<Vec<_>>(),
+                commit.gitbutler_headers(),
+            )
+            .context("failed to create commit")
+    };
+
+    let (new_head, to_commit_id) = if to_position < from_position {
+        // Amending the "to" commit is enough, as the rebased "from" commit doesn't change the
+        // moved hunks anymore.
+        let new_to_commit_id = rewrite(&to_commit, &with_moved(&to_commit)?)?;
+        let new_head = rebase(
+            repository,
+            new_to_commit_id,
+            &commits[to_position + 1..],
+            auto_resolve_whitespace,
+        )?;
+        (new_head, new_to_commit_id)
+    } else {
+        let new_from_commit_id = rewrite(&from_commit, &without_moved)?;
+        let rebased_to_commit_id = rebase(
+            repository,
+            new_from_commit_id,
+            &commits[from_position + 1..=to_position],
+            auto_resolve_whitespace,
+        )?;
+        let rebased_to_commit = repository.find_commit(rebased_to_commit_id)?;
+        let new_to_commit_id = rewrite(&rebased_to_commit, &with_moved(&rebased_to_commit)?)?;
+        let new_head = rebase(
+            repository,
+            new_to_commit_id,
+            &commits[to_position + 1..],
+            auto_resolve_whitespace,
+        )?;
+        (new_head, new_to_commit_id)
+    };
+
+    Ok(MoveResult {
+        new_head,
+        to_commit_id,
+    })
+}
+
+/// Rebase `commits`, in application order, onto `onto` and return the new head, failing if any
+/// of the rebased commits conflicts.
+fn rebase(
+    repository: &git2::Repository,
+    onto: git2::Oid,
+    commits: &[git2::Oid],
+    auto_resolve_whitespace: bool,
+) -> Result<git2::Oid> {
+    if commits.is_empty() {
+        return Ok(onto);
+    }
+    let ids_to_rebase: Vec<_> = commits.iter().rev().copied().collect();
+    let new_head = cherry_rebase_group(repository, onto, &ids_to_rebase, auto_resolve_whitespace)?;
+    for rebased in repository.l(new_head, LogUntil::Commit(onto), false)? {
+        let rebased = repository.find_commit(rebased)?;
+        if rebased.is_conflicted() {
+            bail!(
+                "Moving the hunks would conflict at commit '{}'",
+                subject(&rebased)
+            );
+        }
+    }
+    Ok(new_head)
+}
+
+fn subject(commit: &git2::Commit) -> String {
+    commit
+        .message_bstr()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_str_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use gitbutler_testsupport::testing_repository::{
+        assert_commit_tree_matches, TestingRepository,
+    };
+
+    use super::*;
+
+    #[test]
+    fn moves_hunk_down() {
+        let test_repository = TestingRepository::open();
+        let repository = &test_repository.repository;
+
+        let base = test_repository.commit_tree(None, &[("foo.txt", "foo")]);
+        let to = test_repository.commit_on(&base, "to", &[("foo.txt", "foo"), ("bar.txt", "bar")]);
+        let between = test_repository.commit_on(
+            &to,
+            "between",
+            &[("foo.txt", "foo"), ("bar.txt", "bar"), ("baz.txt", "baz")],
+        );
+        let from = test_repository.commit_on(
+            &between,
+            "from",
+            &[
+                ("foo.txt", "foo"),
+                ("bar.txt", "bar"),
+                ("baz.txt", "baz"),
+                ("qux.txt", "qux"),
+                ("quux.txt", "quux"),
+            ],
+        );
+
+        let MoveResult {
+            new_head,
+            to_commit_id,
+        } = inner_move_commit_hunks(
+            repository,
+            from.id(),
+            base.id(),
+            from.id(),
+            to.id(),
+            &"qux.txt:1-2".parse().unwrap(),
+            false,
+        )
+        .unwrap();
+
+        let new_to = repository.find_commit(to_commit_id).unwrap();
+        assert_eq!(new_to.message(), Some("to"));
+        assert_eq!(new_to.change_id(), to.change_id());
+        assert_commit_tree_matches(
+            repository,
+            &new_to,
+            &[("bar.txt", b"bar"), ("qux.txt", b"qux")],
+        );
+
+        let new_head = repository.find_commit(new_head).unwrap();
+        assert_eq!(new_head.message(), Some("from"));
+        assert_eq!(
+            new_head.tree_id(),
+            from.tree_id(),
+            "the stack has the same changes"
+        );
+        let new_between = new_head.parent(0).unwrap();
+        assert!(new_between.tree().unwrap().get_name("qux.txt").is_some());
+        assert!(new_between.tree().unwrap().get_name("quux.txt").is_none());
+        assert_eq!(new_between.parent_id(0).unwrap(), to_commit_id);
+    }
+
+    #[test]
+    fn moves_hunk_up() {
+        let test_repository = TestingRepository::open();
+        let repository = &test_repository.repository;
+
+        let base = test_repository.commit_tree(None, &[("foo.txt", "foo")]);
+        let from = test_repository.commit_on(
+            &base,
+            "from",
+            &[("foo.txt", "foo"), ("bar.txt", "bar"), ("baz.txt", "baz")],
+        );
+        let to = test_repository.commit_on(
+            &from,
+            "to",
+            &[
+                ("foo.txt", "foo"),
+                ("bar.txt", "bar"),
+                ("baz.txt", "baz"),
+                ("qux.txt", "qux"),
+            ],
+        );
+        let above = test_repository.commit_on(
+            &to,
+            "above",
+            &[
+                ("foo.txt", "foo"),
+                ("bar.txt", "bar"),
+                ("baz.txt", "baz"),
+                ("qux.txt", "qux"),
+                ("quux.txt", "quux"),
+            ],
+        );
+
+        let MoveResult {
+            new_head,
+            to_commit_id,
+        } = inner_move_commit_hunks(
+            repository,
+            above.id(),
+            base.id(),
+            from.id(),
+            to.id(),
+            &"baz.txt:1-2".parse().unwrap(),
+            false,
+        )
+        .unwrap();
+
+        let new_to = repository.find_commit(to_commit_id).unwrap();
+        let new_from = new_to.parent(0).unwrap();
+        assert_eq!(new_from.message(), Some("from"));
+        assert!(new_from.tree().unwrap().get_name("baz.txt").is_none());
+        assert_eq!(new_from.parent_id(0).unwrap(), base.id());
+        assert_eq!(new_to.tree_id(), to.tree_id());
+
+        let new_head = repository.find_commit(new_head).unwrap();
+        assert_eq!(new_head.parent_id(0).unwrap(), to_commit_id);
+        assert_eq!(new_head.tree_id(), above.tree_id());
+
+        assert!(
+            inner_move_commit_hunks(
+                repository,
+                above.id(),
+                base.id(),
+                from.id(),
+                to.id(),
+                &"qux.txt:1-2".parse().unwrap(),
+                false,
+            )
+            .is_err(),
+            "the hunk isn't part of the commit"
+        );
+    }
+}
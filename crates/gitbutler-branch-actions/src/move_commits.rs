@@ -143,6 +143,7 @@ pub(crate) fn move_commit(
         ctx.repository(),
         destination_branch.head(),
         &[source_commit.id()],
+        ctx.project().auto_resolve_whitespace,
     )?;
 
     // if the source commit has children, move them to the source commit's parent
@@ -150,8 +151,12 @@ pub(crate) fn move_commit(
     let mut new_source_head_oid = source_commit_parent.id();
     if let Some(child_commits) = descendant_commits.as_ref() {
         let ids_to_rebase: Vec<git2::Oid> = child_commits.iter().map(|c| c.id()).collect();
-        new_source_head_oid =
-            cherry_rebase_group(ctx.repository(), source_commit_parent.id(), &ids_to_rebase)?;
+        new_source_head_oid = cherry_rebase_group(
+            ctx.repository(),
+            source_commit_parent.id(),
+            &ids_to_rebase,
+            ctx.project().auto_resolve_whitespace,
+        )?;
     }
 
     // reset the source branch to the newer parent commit
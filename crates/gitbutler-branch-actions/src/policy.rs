@@ -0,0 +1,722 @@
+//! Support for a repository-wide policy, committed to `.gitbutler/policy.toml` in the worktree.
+//!
+//! A policy file could look like this, with all rules being optional:
+//!
+//! ```toml
+//! branch_name_pattern = "^(feat|fix|chore)/"
+//! protected_paths = ["Cargo.lock", "infra/"]
+//! require_signed_commits = true
+//! max_file_size = 1048576
+//!
+//! [commit_message]
+//! conventional = true
+//! types = ["feat", "fix", "docs", "chore"]
+//! max_subject_length = 72
+//! pattern = "(?m)^Refs: [A-Z]+-[0-9]+$"
+//! ```
+//!
+//! The policy is enforced when committing, including rewording and rewriting commits, and when
+//! pushing. [`policy_report()`](crate::policy_report) shows the effective policy along with all
+//! current violations in the workspace. Messages can be checked while they are written with
+//! [`validate_commit_message()`](crate::validate_commit_message).
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use bstr::ByteSlice;
+use gitbutler_command_context::CommandContext;
+use gitbutler_commit::commit_ext::CommitExt;
+use gitbutler_config::git::GitConfig;
+use gitbutler_error::error::Code;
+use gitbutler_repo::{LogUntil, RepositoryExt};
+use gitbutler_stack::Stack;
+use serde::{Deserialize, Serialize};
+
+use crate::VirtualBranchesExt;
+
+/// The location of the policy file, relative to the root of the worktree.
+pub const POLICY_FILE: &str = ".gitbutler/policy.toml";
+
+/// The compiled patterns of policies, as the policy is loaded and checked for every commit and push.
+static PATTERNS: Mutex<BTreeMap<String, regex::Regex>> = Mutex::new(BTreeMap::new());
+
+/// How many patterns are kept compiled, which is more than the few of a single policy.
+const MAX_PATTERNS: usize = 32;
+
+/// Compile `pattern`, or return it compiled already.
+fn compiled(pattern: &str) -> Result<regex::Regex, regex::Error> {
+    let mut patterns = PATTERNS.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(regex) = patterns.get(pattern) {
+        return Ok(regex.clone());
+    }
+    let regex = regex::Regex::new(pattern)?;
+    if patterns.len() >= MAX_PATTERNS {
+        patterns.clear();
+    }
+    patterns.insert(pattern.to_owned(), regex.clone());
+    Ok(regex)
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    rename_all(serialize = "camelCase", deserialize = "snake_case"),
+    default,
+    deny_unknown_fields
+)]
+pub struct Policy {
+    /// A regular expression each branch name must match.
+    pub branch_name_pattern: Option<String>,
+    /// Paths, or directories if they end with `/`, which must not be changed by commits.
+    pub protected_paths: Vec<String>,
+    /// If `true`, commits must be signed, so signing must be enabled and pushed commits must carry a signature.
+    pub require_signed_commits: bool,
+    /// The maximum size in bytes of any committed file.
+    pub max_file_size: Option<u64>,
+    /// The rules for the messages of new commits.
+    pub commit_message: Option<CommitMessagePolicy>,
+}
+
+/// The types of conventional commits allowed if the policy doesn't list them.
+const DEFAULT_CONVENTIONAL_TYPES: &[&str] = &[
+    "build", "chore", "ci", "docs", "feat", "fix", "perf", "refactor", "revert", "style", "test",
+];
+
+/// The subject of a conventional commit, like `feat(parser)!: description`.
+const CONVENTIONAL_HEADER: &str = r"^(?P<type>[A-Za-z]+)(\([^()]+\))?!?:(?P<description>.*)$";
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    rename_all(serialize = "camelCase", deserialize = "snake_case"),
+    default,
+    deny_unknown_fields
+)]
+pub struct CommitMessagePolicy {
+    /// If `true`, messages must follow the [conventional commits](https://www.conventionalcommits.org)
+    /// format, like `feat(parser): support arrays`, followed by a blank line if there is a body.
+    pub conventional: bool,
+    /// The allowed types of conventional commits, or the common ones if empty.
+    pub types: Vec<String>,
+    /// The maximum number of characters of the subject, the first line of the message.
+    pub max_subject_length: Option<usize>,
+    /// A regular expression the whole message must match.
+    pub pattern: Option<String>,
+}
+
+/// A way in which a commit message breaks the [`CommitMessagePolicy`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "rule")]
+pub enum CommitMessageViolation {
+    /// The subject isn't of the form `type(scope)!: description`.
+    NotConventional,
+    #[serde(rename_all = "camelCase")]
+    UnknownType {
+        r#type: String,
+        allowed: Vec<String>,
+    },
+    /// There is nothing after the type and scope of the subject.
+    EmptyDescription,
+    /// The subject is followed by a body without a blank line in between.
+    MissingBlankLine,
+    #[serde(rename_all = "camelCase")]
+    SubjectTooLong {
+        length: usize,
+        max_length: usize,
+    },
+    PatternMismatch {
+        pattern: String,
+    },
+}
+
+impl fmt::Display for CommitMessageViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommitMessageViolation::NotConventional => write!(
+                f,
+                "the subject must be a conventional commit like 'type(scope): description'"
+            ),
+            CommitMessageViolation::UnknownType { r#type, allowed } => write!(
+                f,
+                "'{}' isn't one of the allowed types {}",
+                r#type,
+                allowed.join(", ")
+            ),
+            CommitMessageViolation::EmptyDescription => {
+                write!(f, "the subject has no description")
+            }
+            CommitMessageViolation::MissingBlankLine => {
+                write!(f, "the subject must be followed by a blank line")
+            }
+            CommitMessageViolation::SubjectTooLong { length, max_length } => write!(
+                f,
+                "the subject has {length} characters, but at most {max_length} are allowed"
+            ),
+            CommitMessageViolation::PatternMismatch { pattern } => {
+                write!(f, "the message doesn't match the pattern '{pattern}'")
+            }
+        }
+    }
+}
+
+impl CommitMessagePolicy {
+    /// Check `message` against all rules, and return each one it breaks.
+    pub fn check(&self, message: &str) -> Vec<CommitMessageViolation> {
+        let mut violations = Vec::new();
+        let mut lines = message.lines();
+        let subject = lines.next().unwrap_or_default();
+
+        if self.conventional {
+            violations.extend(self.check_conventional_subject(subject));
+            if lines.next().is_some_and(|line| !line.trim().is_empty()) {
+                violations.push(CommitMessageViolation::MissingBlankLine);
+            }
+        }
+        if let Some(max_length) = self.max_subject_length {
+            let length = subject.chars().count();
+            if length > max_length {
+                violations.push(CommitMessageViolation::SubjectTooLong { length, max_length });
+            }
+        }
+        if let Some(pattern) = &self.pattern {
+            // The pattern was validated when loading.
+            if !compiled(pattern).map_or(true, |re| re.is_match(message)) {
+                violations.push(CommitMessageViolation::PatternMismatch {
+                    pattern: pattern.clone(),
+                });
+            }
+        }
+        violations
+    }
+
+    fn check_conventional_subject(&self, subject: &str) -> Option<CommitMessageViolation> {
+        let header = compiled(CONVENTIONAL_HEADER).expect("valid regex");
+        let Some(captures) = header.captures(subject) else {
+            return Some(CommitMessageViolation::NotConventional);
+        };
+        let r#type = &captures["type"];
+        let allowed: Vec<String> = if self.types.is_empty() {
+            DEFAULT_CONVENTIONAL_TYPES
+                .iter()
+                .map(|r#type| r#type.to_string())
+                .collect()
+        } else {
+            self.types.clone()
+        };
+        if !allowed
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(r#type))
+        {
+            return Some(CommitMessageViolation::UnknownType {
+                r#type: r#type.to_owned(),
+                allowed,
+            });
+        }
+        let description = &captures["description"];
+        if !description.starts_with(' ') || description.trim().is_empty() {
+            return Some(if description.trim().is_empty() {
+                CommitMessageViolation::EmptyDescription
+            } else {
+                CommitMessageViolation::NotConventional
+            });
+        }
+        None
+    }
+}
+
+/// A single rule of a [`Policy`] that is broken.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum Violation {
+    #[serde(rename_all = "camelCase")]
+    BranchName {
+        branch: String,
+        pattern: String,
+    },
+    ProtectedPath {
+        path: PathBuf,
+    },
+    #[serde(rename_all = "camelCase")]
+    FileTooLarge {
+        path: PathBuf,
+        size: u64,
+        max_size: u64,
+    },
+    SigningDisabled,
+    CommitMessage(CommitMessageViolation),
+    #[serde(rename_all = "camelCase")]
+    UnsignedCommit {
+        branch: String,
+        #[serde(with = "gitbutler_serde::oid")]
+        commit_id: git2::Oid,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::BranchName { branch, pattern } => {
+                write!(f, "branch '{branch}' doesn't match the pattern '{pattern}'")
+            }
+            Violation::ProtectedPath { path } => {
+                write!(f, "'{}' is protected and can't be changed", path.display())
+            }
+            Violation::FileTooLarge {
+                path,
+                size,
+                max_size,
+            } => write!(
+                f,
+                "'{}' has {size} bytes, but at most {max_size} bytes are allowed",
+                path.display()
+            ),
+            Violation::SigningDisabled => {
+                write!(f, "commits must be signed, but commit signing is disabled")
+            }
+            Violation::CommitMessage(violation) => write!(f, "{violation}"),
+            Violation::UnsignedCommit { branch, commit_id } => {
+                write!(f, "commit {commit_id} in branch '{branch}' isn't signed")
+            }
+        }
+    }
+}
+
+/// The effective policy of a project, along with everything in the workspace that violates it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyReport {
+    /// The policy, or `None` if there is no policy file.
+    pub policy: Option<Policy>,
+    pub violations: Vec<Violation>,
+}
+
+impl Policy {
+    /// Read the policy of the worktree at `worktree_dir`, or return `None` if there is no policy file.
+    pub fn load(worktree_dir: &Path) -> Result<Option<Self>> {
+        let path = worktree_dir.join(POLICY_FILE);
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err).with_context(|| format!("failed to read {POLICY_FILE}")),
+        };
+        let policy: Policy = toml::from_str(&contents)
+            .with_context(|| format!("{POLICY_FILE} is invalid"))
+            .context(Code::Validation)?;
+        if let Some(pattern) = &policy.branch_name_pattern {
+            compiled(pattern)
+                .with_context(|| format!("{POLICY_FILE} has an invalid branch_name_pattern"))
+                .context(Code::Validation)?;
+        }
+        if let Some(pattern) = policy
+            .commit_message
+            .as_ref()
+            .and_then(|rules| rules.pattern.as_ref())
+        {
+            compiled(pattern)
+                .with_context(|| format!("{POLICY_FILE} has an invalid commit_message.pattern"))
+                .context(Code::Validation)?;
+        }
+        Ok(Some(policy))
+    }
+
+    /// Check the `message` of a new commit.
+    pub fn check_commit_message(&self, message: &str) -> Vec<Violation> {
+        self.commit_message
+            .as_ref()
+            .map(|rules| {
+                rules
+                    .check(message)
+                    .into_iter()
+                    .map(Violation::CommitMessage)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn check_branch_name(&self, branch: &str) -> Option<Violation> {
+        let pattern = self.branch_name_pattern.as_ref()?;
+        // The pattern was validated when loading.
+        let matches = compiled(pattern).map_or(true, |re| re.is_match(branch));
+        (!matches).then(|| Violation::BranchName {
+            branch: branch.to_owned(),
+            pattern: pattern.clone(),
+        })
+    }
+
+    /// Check the worktree-relative `paths` which are about to be committed, reading their sizes from `worktree_dir`.
+    pub fn check_paths<'a>(
+        &self,
+        worktree_dir: &Path,
+        paths: impl IntoIterator<Item = &'a Path>,
+    ) -> Vec<Violation> {
+        self.check_changes(paths.into_iter().map(|path| {
+            // Deleted files have no size and are always fine.
+            let size = std::fs::symlink_metadata(worktree_dir.join(path))
+                .ok()
+                .map(|metadata| metadata.len());
+            (path, size)
+        }))
+    }
+
+    /// Check the changed worktree-relative paths, each with its new size or `None` if it was deleted.
+    fn check_changes<'a>(
+        &self,
+        changes: impl IntoIterator<Item = (&'a Path, Option<u64>)>,
+    ) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for (path, size) in changes {
+            if self.is_protected(path) {
+                violations.push(Violation::ProtectedPath {
+                    path: path.to_owned(),
+                });
+            }
+            if let Some((size, max_size)) = size.zip(self.max_file_size) {
+                if size > max_size {
+                    violations.push(Violation::FileTooLarge {
+                        path: path.to_owned(),
+                        size,
+                        max_size,
+                    });
+                }
+            }
+        }
+        violations
+    }
+
+    fn is_protected(&self, path: &Path) -> bool {
+        self.protected_paths
+            .iter()
+            .any(|protected| match protected.strip_suffix('/') {
+                Some(dir) => path.starts_with(dir),
+                None => path == Path::new(protected),
+            })
+    }
+}
+
+/// Fail if the policy of the project doesn't allow `message` for a commit whose changes stay the same,
+/// like when rewording it.
+pub(crate) fn assure_commit_message_allowed(ctx: &CommandContext, message: &str) -> Result<()> {
+    let Some(policy) = Policy::load(&ctx.project().path)? else {
+        return Ok(());
+    };
+    into_result(policy.check_commit_message(message), "commit")
+}
+
+/// Fail if the policy of the project doesn't allow committing `paths` to `branch` with `message`.
+pub(crate) fn assure_commit_allowed<'a>(
+    ctx: &CommandContext,
+    branch: &Stack,
+    message: &str,
+    paths: impl IntoIterator<Item = &'a Path>,
+) -> Result<()> {
+    let worktree_dir = &ctx.project().path;
+    let Some(policy) = Policy::load(worktree_dir)? else {
+        return Ok(());
+    };
+    let mut violations: Vec<_> = policy.check_branch_name(&branch.name).into_iter().collect();
+    violations.extend(policy.check_commit_message(message));
+    violations.extend(policy.check_paths(worktree_dir, paths));
+    if policy.require_signed_commits && !signing_enabled(ctx)? {
+        violations.push(Violation::SigningDisabled);
+    }
+    into_result(violations, "commit")
+}
+
+/// Fail if the policy of the project doesn't allow the new `commit_ids` of `branch`, which were
+/// written by rewriting or copying other commits. Unlike with [`assure_commit_allowed()`], their
+/// changes aren't in the worktree, so they are read from the commits and their first parents.
+pub(crate) fn assure_commits_allowed(
+    ctx: &CommandContext,
+    branch: &Stack,
+    commit_ids: &[git2::Oid],
+) -> Result<()> {
+    let Some(policy) = Policy::load(&ctx.project().path)? else {
+        return Ok(());
+    };
+    let repo = ctx.repository();
+    let odb = repo.odb()?;
+    let mut violations: Vec<_> = policy.check_branch_name(&branch.name).into_iter().collect();
+    for commit_id in commit_ids {
+        let commit = repo.find_commit(*commit_id)?;
+        violations.extend(policy.check_commit_message(&commit.message_bstr().to_str_lossy()));
+        let parent_tree = commit
+            .parent(0)
+            .ok()
+            .map(|parent| parent.tree())
+            .transpose()?;
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        let mut changes = Vec::new();
+        for delta in diff.deltas() {
+            let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+                continue;
+            };
+            // Deleted files and submodules have no size of their own.
+            let size = if delta.new_file().id().is_zero()
+                || delta.new_file().mode() == git2::FileMode::Commit
+            {
+                None
+            } else {
+                Some(odb.read_header(delta.new_file().id())?.0 as u64)
+            };
+            changes.push((path.to_owned(), size));
+        }
+        violations.extend(
+            policy.check_changes(changes.iter().map(|(path, size)| (path.as_path(), *size))),
+        );
+    }
+    if policy.require_signed_commits && !signing_enabled(ctx)? {
+        violations.push(Violation::SigningDisabled);
+    }
+    into_result(violations, "commit")
+}
+
+/// Fail if the policy of the project doesn't allow pushing `branch` to the branches named
+/// `pushed_names` on the remote.
+pub(crate) fn assure_push_allowed<'a>(
+    ctx: &CommandContext,
+    branch: &Stack,
+    pushed_names: impl IntoIterator<Item = &'a str>,
+) -> Result<()> {
+    let Some(policy) = Policy::load(&ctx.project().path)? else {
+        return Ok(());
+    };
+    let mut violations: Vec<_> = pushed_names
+        .into_iter()
+        .filter_map(|name| policy.check_branch_name(name))
+        .collect();
+    if policy.require_signed_commits {
+        violations.extend(unsigned_commits(ctx, branch)?);
+    }
+    into_result(violations, "push")
+}
+
+/// Fail if the policy of the project doesn't allow creating branches named `names`.
+pub(crate) fn assure_branch_names_allowed<'a>(
+    ctx: &CommandContext,
+    names: impl IntoIterator<Item = &'a str>,
+) -> Result<()> {
+    let Some(policy) = Policy::load(&ctx.project().path)? else {
+        return Ok(());
+    };
+    let violations = names
+        .into_iter()
+        .filter_map(|name| policy.check_branch_name(name))
+        .collect();
+    into_result(violations, "branch")
+}
+
+/// Check `message` against the commit message rules of the policy of the project, if there are any.
+pub(crate) fn validate_commit_message(
+    ctx: &CommandContext,
+    message: &str,
+) -> Result<Vec<CommitMessageViolation>> {
+    Ok(Policy::load(&ctx.project().path)?
+        .and_then(|policy| policy.commit_message)
+        .map(|rules| rules.check(message))
+        .unwrap_or_default())
+}
+
+/// Evaluate the policy of the project against all applied branches, including their uncommitted changes.
+pub(crate) fn report(ctx: &CommandContext) -> Result<PolicyReport> {
+    let worktree_dir = &ctx.project().path;
+    let Some(policy) = Policy::load(worktree_dir)? else {
+        return Ok(PolicyReport {
+            policy: None,
+            violations: Vec::new(),
+        });
+    };
+
+    let mut violations = Vec::new();
+    if policy.require_signed_commits && !signing_enabled(ctx)? {
+        violations.push(Violation::SigningDisabled);
+    }
+    let statuses = crate::status::get_applied_status(ctx, None)?.branches;
+    for (branch, files) in &statuses {
+        // What's pushed are the heads of the series of the stack.
+        violations.extend(
+            branch
+                .heads()
+                .iter()
+                .filter_map(|name| policy.check_branch_name(name)),
+        );
+        violations
+            .extend(policy.check_paths(worktree_dir, files.iter().map(|file| file.path.as_path())));
+        if policy.require_signed_commits {
+            violations.extend(unsigned_commits(ctx, branch)?);
+        }
+    }
+    Ok(PolicyReport {
+        policy: Some(policy),
+        violations,
+    })
+}
+
+fn signing_enabled(ctx: &CommandContext) -> Result<bool> {
+    Ok(ctx.repository().gb_config()?.sign_commits.unwrap_or(false))
+}
+
+/// Find all commits of `branch` which aren't signed and aren't part of the target yet.
+fn unsigned_commits(ctx: &CommandContext, branch: &Stack) -> Result<Vec<Violation>> {
+    let repo = ctx.repository();
+    let target = ctx.project().virtual_branches().get_default_target()?;
+    let merge_base = repo.merge_base(target.sha, branch.head())?;
+    let mut violations = Vec::new();
+    for commit_id in repo.l(branch.head(), LogUntil::Commit(merge_base), false)? {
+        if !repo.find_commit(commit_id)?.is_signed() {
+            violations.push(Violation::UnsignedCommit {
+                branch: branch.name.clone(),
+                commit_id,
+            });
+        }
+    }
+    Ok(violations)
+}
+
+fn into_result(violations: Vec<Violation>, action: &str) -> Result<()> {
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let violations = violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(anyhow::anyhow!(
+        "The {action} is not allowed by {POLICY_FILE}: {violations}"
+    ))
+    .context(Code::Validation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_all_rules() -> Result<()> {
+        let policy: Policy = toml::from_str(
+            r#"
+            branch_name_pattern = "^feat/"
+            protected_paths = ["Cargo.lock", "infra/"]
+            require_signed_commits = true
+            max_file_size = 1024
+
+            [commit_message]
+            conventional = true
+            types = ["feat"]
+            max_subject_length = 50
+            pattern = "Refs: "
+            "#,
+        )?;
+        assert_eq!(
+            policy,
+            Policy {
+                branch_name_pattern: Some("^feat/".into()),
+                protected_paths: vec!["Cargo.lock".into(), "infra/".into()],
+                require_signed_commits: true,
+                max_file_size: Some(1024),
+                commit_message: Some(CommitMessagePolicy {
+                    conventional: true,
+                    types: vec!["feat".into()],
+                    max_subject_length: Some(50),
+                    pattern: Some("Refs: ".into()),
+                }),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_rules_are_rejected() {
+        assert!(toml::from_str::<Policy>("max_files = 3").is_err());
+    }
+
+    #[test]
+    fn branch_names() {
+        let policy = Policy {
+            branch_name_pattern: Some("^feat/".into()),
+            ..Default::default()
+        };
+        assert_eq!(policy.check_branch_name("feat/login"), None);
+        assert_eq!(
+            policy.check_branch_name("login"),
+            Some(Violation::BranchName {
+                branch: "login".into(),
+                pattern: "^feat/".into()
+            })
+        );
+        assert_eq!(Policy::default().check_branch_name("anything"), None);
+    }
+
+    #[test]
+    fn conventional_commit_messages() {
+        let rules = CommitMessagePolicy {
+            conventional: true,
+            ..Default::default()
+        };
+        assert_eq!(rules.check("feat(parser)!: support arrays\n\nbody"), vec![]);
+        assert_eq!(
+            rules.check("Fix: a bug"),
+            vec![],
+            "types are case-insensitive"
+        );
+        assert_eq!(
+            rules.check("support arrays"),
+            vec![CommitMessageViolation::NotConventional]
+        );
+        assert_eq!(
+            rules.check("feature: support arrays\nbody"),
+            vec![
+                CommitMessageViolation::UnknownType {
+                    r#type: "feature".into(),
+                    allowed: DEFAULT_CONVENTIONAL_TYPES
+                        .iter()
+                        .map(|r#type| r#type.to_string())
+                        .collect(),
+                },
+                CommitMessageViolation::MissingBlankLine
+            ]
+        );
+        assert_eq!(
+            rules.check("fix:  "),
+            vec![CommitMessageViolation::EmptyDescription]
+        );
+        assert_eq!(
+            rules.check("fix:no space"),
+            vec![CommitMessageViolation::NotConventional]
+        );
+    }
+
+    #[test]
+    fn commit_message_length_and_pattern() {
+        let rules = CommitMessagePolicy {
+            max_subject_length: Some(10),
+            pattern: Some("(?m)^Refs: [A-Z]+-[0-9]+$".into()),
+            ..Default::default()
+        };
+        assert_eq!(rules.check("short\n\nRefs: GB-12"), vec![]);
+        assert_eq!(
+            rules.check("a subject that is too long\n\nno refs"),
+            vec![
+                CommitMessageViolation::SubjectTooLong {
+                    length: 26,
+                    max_length: 10
+                },
+                CommitMessageViolation::PatternMismatch {
+                    pattern: "(?m)^Refs: [A-Z]+-[0-9]+$".into()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn protected_files_and_directories() {
+        let policy = Policy {
+            protected_paths: vec!["Cargo.lock".into(), "infra/".into()],
+            ..Default::default()
+        };
+        assert!(policy.is_protected(Path::new("Cargo.lock")));
+        assert!(policy.is_protected(Path::new("infra/main.tf")));
+        assert!(!policy.is_protected(Path::new("crates/Cargo.lock")));
+        assert!(!policy.is_protected(Path::new("infrastructure.md")));
+    }
+}
@@ -0,0 +1,159 @@
+//! A cache of the hunk ranges of the stacks in the workspace, persisted in `hunk_ranges.json`, so
+//! they don't have to be recomputed from all commits each time the app starts.
+//!
+//! The ranges of a stack only depend on its commits, so they are cached along with the head of the
+//! stack and the target they were computed from, and are recomputed once either of them changes.
+//! The cache is only read from disk once per project, and only written back if it changed.
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::{Context, Result};
+use gitbutler_command_context::CommandContext;
+use gitbutler_hunk_dependency::{InputStack, StackRanges, WorkspaceRanges};
+use gitbutler_project::feature_flags::FeatureFlag;
+use gitbutler_stack::Stack;
+use serde::{Deserialize, Serialize};
+
+use crate::status::hunk_dependency_input;
+
+const RANGES_CACHE_FILE_NAME: &str = "hunk_ranges.json";
+/// The version of how the input of the ranges is collected, which is bumped when it changes so
+/// previously cached ranges are recomputed, like when submodule bumps started to be included.
+const RANGES_CACHE_VERSION: u32 = 1;
+
+/// The caches of the projects used recently, by their path, as they were last read or written.
+static CACHES: Mutex<BTreeMap<PathBuf, RangesCache>> = Mutex::new(BTreeMap::new());
+
+/// How many caches are kept in memory, which is more than the projects open at a time.
+const MAX_CACHES: usize = 8;
+
+/// The cached ranges by the id of their stack.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RangesCache {
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    stacks: BTreeMap<String, CachedStack>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedStack {
+    #[serde(with = "gitbutler_serde::oid")]
+    head: git2::Oid,
+    #[serde(with = "gitbutler_serde::oid")]
+    target: git2::Oid,
+    /// Whether renames were detected, which changes the ranges of renamed files.
+    detect_renames: bool,
+    /// Whether the columns changed within lines were tracked.
+    #[serde(default)]
+    track_columns: bool,
+    input: InputStack,
+    ranges: StackRanges,
+}
+
+/// Return the ranges of all `stacks` on top of `target_sha`, in their order, reusing the cached
+/// ranges of each stack whose head and target didn't change since.
+pub(crate) fn workspace_ranges(
+    ctx: &CommandContext,
+    target_sha: &git2::Oid,
+    stacks: &[Stack],
+) -> Result<WorkspaceRanges> {
+    let path = ctx.project().gb_dir().join(RANGES_CACHE_FILE_NAME);
+    // Taken out while the ranges are computed, so other projects aren't blocked.
+    let cached = CACHES
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .remove(&path);
+    let mut cache = cached.unwrap_or_else(|| read_cache(&path));
+    if cache.version != RANGES_CACHE_VERSION {
+        cache = RangesCache {
+            version: RANGES_CACHE_VERSION,
+            ..RangesCache::default()
+        };
+    }
+    let detect_renames = ctx.project().feature_enabled(FeatureFlag::RenameTracking);
+    let track_columns = ctx.project().feature_enabled(FeatureFlag::ColumnLocking);
+
+    let cached_count = cache.stacks.len();
+    cache
+        .stacks
+        .retain(|stack_id, _| stacks.iter().any(|stack| stack.id.to_string() == *stack_id));
+    let mut changed = cache.stacks.len() != cached_count;
+
+    let mut ranges = Vec::with_capacity(stacks.len());
+    for stack in stacks {
+        let key = stack.id.to_string();
+        let is_current = cache.stacks.get(&key).is_some_and(|cached| {
+            cached.head == stack.head()
+                && cached.target == *target_sha
+                && cached.detect_renames == detect_renames
+                && cached.track_columns == track_columns
+        });
+        if !is_current {
+            let input = hunk_dependency_input(ctx, target_sha, std::slice::from_ref(stack), None)?
+                .pop()
+                .context("no input for stack")?;
+            // A path whose ranges can't be computed shouldn't disable the locks of all others.
+            let stack_ranges = StackRanges::create_lenient(&input);
+            for error in &stack_ranges.errors {
+                tracing::warn!(
+                    stack_id = %error.stack_id,
+                    path = %error.path.display(),
+                    commit_id = %error.commit_id,
+                    error = %error.message,
+                    "skipping path whose hunk ranges can't be computed"
+                );
+            }
+            cache.stacks.insert(
+                key.clone(),
+                CachedStack {
+                    head: stack.head(),
+                    target: *target_sha,
+                    detect_renames,
+                    track_columns,
+                    input,
+                    ranges: stack_ranges,
+                },
+            );
+            changed = true;
+        }
+        let cached = &cache.stacks[&key];
+        ranges.push((cached.input.clone(), cached.ranges.clone()));
+    }
+
+    if changed {
+        let contents =
+            serde_json::to_string(&cache).context("Failed to serialize hunk ranges cache")?;
+        if let Err(err) = gitbutler_fs::write(&path, contents) {
+            tracing::warn!(?err, "failed to write hunk ranges cache");
+        }
+    }
+
+    let mut caches = CACHES.lock().unwrap_or_else(|err| err.into_inner());
+    if caches.len() >= MAX_CACHES {
+        caches.clear();
+    }
+    caches.insert(path, cache);
+    Ok(WorkspaceRanges::from_stacks(ranges))
+}
+
+/// Read the cache at `path`, or start over with an empty one if it is missing or can't be read,
+/// e.g. as it was written by a version that stored ranges differently.
+fn read_cache(path: &Path) -> RangesCache {
+    let contents = match std::fs::read(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return RangesCache::default(),
+        Err(err) => {
+            tracing::warn!(?err, "failed to read hunk ranges cache");
+            return RangesCache::default();
+        }
+    };
+    serde_json::from_slice(&contents).unwrap_or_else(|err| {
+        tracing::warn!(?err, "discarding unreadable hunk ranges cache");
+        RangesCache::default()
+    })
+}
@@ -28,6 +28,7 @@ use crate::{
 /// - The series themselves in the reorder request must be the same as the ones in the stack (this API is about moving commits, not series)
 /// - The number of commits in the reorder request must match the number of commits in the stack
 /// - The commit ids in the reorder request must be in the stack
+/// - A commit that bumps a submodule must not move before an earlier bump of the same submodule
 pub fn reorder_stack(
     ctx: &CommandContext,
     branch_id: StackId,
@@ -54,7 +55,15 @@ pub fn reorder_stack(
         .flat_map(|s| s.commit_ids.iter())
         .cloned()
         .collect_vec();
-    let new_head = cherry_rebase_group(repo, merge_base, &ids_to_rebase)?;
+    // Bumps of the same submodule can't be resolved like lines, so they must keep their order.
+    let application_order = ids_to_rebase.iter().rev().copied().collect_vec();
+    crate::dependencies::assure_submodule_bumps_ordered(ctx, &application_order)?;
+    let new_head = cherry_rebase_group(
+        repo,
+        merge_base,
+        &ids_to_rebase,
+        ctx.project().auto_resolve_whitespace,
+    )?;
     // Calculate the new head and tree
     let BranchHeadAndTree {
         head: new_head_oid,
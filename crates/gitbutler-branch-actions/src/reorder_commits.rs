@@ -65,6 +65,7 @@ pub(crate) fn reorder_commit(
         offset,
         &original_commits,
         &repository.find_tree(branch.tree)?,
+        ctx.project().auto_resolve_whitespace,
     )?;
 
     let mut old_oid = None;
@@ -122,6 +123,7 @@ fn inner_reorder_commit(
     offset: i32,
     branch_commits: &[git2::Oid],
     branch_tree: &git2::Tree,
+    auto_resolve_whitespace: bool,
 ) -> Result<ReorderResult> {
     if branch_commits.len() < 2 {
         bail!("Cannot re-order less than two commits");
@@ -143,7 +145,12 @@ fn inner_reorder_commit(
     // We are passing all the commits to the cherry_rebase_group funcion, but
     // this is not a concern as it will verbaitm copy any commits that have
     // not had their parents changed.
-    let new_head_oid = cherry_rebase_group(repository, base_commit, &reordered_commits)?;
+    let new_head_oid = cherry_rebase_group(
+        repository,
+        base_commit,
+        &reordered_commits,
+        auto_resolve_whitespace,
+    )?;
 
     // Calculate the new head and tree
     let BranchHeadAndTree {
@@ -259,6 +266,7 @@ mod test {
                 0,
                 &[b.id()],
                 &b.tree().unwrap(),
+                false,
             );
 
             assert!(result.is_err());
@@ -285,6 +293,7 @@ mod test {
                 -1,
                 &[b.id(), a.id()],
                 &b.tree().unwrap(),
+                false,
             )
             .unwrap();
 
@@ -348,6 +357,7 @@ mod test {
                 -1,
                 &[b.id(), a.id()],
                 &b.tree().unwrap(),
+                false,
             )
             .unwrap();
 
@@ -404,6 +414,7 @@ mod test {
                 -1,
                 &[a_prime.id(), b_prime.id()],
                 &a_prime.tree().unwrap(),
+                false,
             )
             .unwrap();
 
@@ -435,6 +446,7 @@ mod test {
                 -1,
                 &[b.id(), a.id()],
                 &tree.tree().unwrap(),
+                false,
             )
             .unwrap();
 
@@ -476,6 +488,7 @@ mod test {
                 -1,
                 &[tree_commit.id(), a_prime.id(), b_prime.id()],
                 &tree.tree().unwrap(),
+                false,
             )
             .unwrap();
 
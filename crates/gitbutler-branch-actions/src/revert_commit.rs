@@ -0,0 +1,256 @@
+use anyhow::{bail, Context as _, Result};
+use bstr::ByteSlice as _;
+use gitbutler_command_context::CommandContext;
+use gitbutler_commit::commit_ext::CommitExt as _;
+use gitbutler_project::access::WorktreeWritePermission;
+use gitbutler_repo::{rebase::cherry_rebase_group, LogUntil, RepositoryExt as _};
+use gitbutler_repo_actions::RepoActionsExt as _;
+use gitbutler_stack::StackId;
+use serde::Serialize;
+
+use crate::{
+    branch_trees::{
+        checkout_branch_trees, compute_updated_branch_head_for_commits, BranchHeadAndTree,
+    },
+    dependencies, VirtualBranchesExt as _,
+};
+
+/// How a commit was reverted by [`revert_commit()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "subject", rename_all = "camelCase")]
+pub enum RevertOutcome {
+    /// A commit undoing the changes of the reverted commit was added to the top of the stack.
+    #[serde(rename_all = "camelCase")]
+    Reverted {
+        #[serde(with = "gitbutler_serde::oid")]
+        commit_id: git2::Oid,
+    },
+    /// The reverted commit was removed from the stack, and the commits above it were rebased onto
+    /// its parent.
+    Dropped,
+}
+
+/// Undo the changes of the commit with `commit_id` in the stack with `stack_id`, and remove them
+/// from the worktree.
+///
+/// By default a commit with the inverse changes is added to the top of the stack, so nothing that
+/// was pushed is rewritten. With `drop_if_independent`, the commit is removed from the stack
+/// instead if no other commit and no uncommitted change depends on it, and if that doesn't
+/// require a force push that isn't allowed.
+pub(crate) fn revert_commit(
+    ctx: &CommandContext,
+    stack_id: StackId,
+    commit_id: git2::Oid,
+    drop_if_independent: bool,
+    perm: &mut WorktreeWritePermission,
+) -> Result<RevertOutcome> {
+    ctx.assure_resolved()?;
+
+    let vb_state = ctx.project().virtual_branches();
+    let mut stack = vb_state.get_branch_in_workspace(stack_id)?;
+    let default_target = vb_state.get_default_target()?;
+    let repository = ctx.repository();
+
+    let stack_commit_ids =
+        repository.l(stack.head(), LogUntil::Commit(default_target.sha), false)?;
+    if !stack_commit_ids.contains(&commit_id) {
+        bail!("commit {commit_id} is not in branch '{}'", stack.name);
+    }
+    let commit = repository.find_commit(commit_id)?;
+    if commit.is_conflicted() {
+        bail!("Can not revert a conflicted commit");
+    }
+
+    let can_drop = drop_if_independent && {
+        let pushed = stack.upstream_head.map_or_else(
+            || Ok(vec![]),
+            |upstream_head| {
+                repository.l(upstream_head, LogUntil::Commit(default_target.sha), false)
+            },
+        )?;
+        let preview = dependencies::remove_commit_preview(ctx, commit_id)?;
+        (stack.allow_rebasing || !pushed.contains(&commit_id))
+            && preview.conflicting_commits.is_empty()
+            && preview.conflicting_hunks.is_empty()
+    };
+
+    let (new_head, outcome) = if can_drop {
+        let new_head = inner_drop_commit(
+            repository,
+            stack.head(),
+            commit_id,
+            ctx.project().auto_resolve_whitespace,
+        )?;
+        (new_head, RevertOutcome::Dropped)
+    } else {
+        let tree = repository.find_tree(inner_revert_tree(repository, stack.head(), commit_id)?)?;
+        let message = format!(
+            "Revert \"{}\"\n\nThis reverts commit {commit_id}.\n",
+            commit_subject(&commit)
+        );
+        let head = repository.find_commit(stack.head())?;
+        let new_head = ctx
+            .commit(&message, &tree, &[&head], None)
+            .context("failed to create revert commit")?;
+        (
+            new_head,
+            RevertOutcome::Reverted {
+                commit_id: new_head,
+            },
+        )
+    };
+
+    let BranchHeadAndTree { head, tree } =
+        compute_updated_branch_head_for_commits(repository, stack.head(), stack.tree, new_head)?;
+    if head != new_head {
+        bail!(
+            "Reverting commit '{}' would conflict with uncommitted changes",
+            commit_subject(&commit)
+        );
+    }
+
+    if outcome == RevertOutcome::Dropped {
+        stack.replace_head(ctx, &commit, &commit.parent(0)?)?;
+    }
+    stack.set_stack_head(ctx, new_head, Some(tree))?;
+    checkout_branch_trees(ctx, perm)?;
+    crate::integration::update_workspace_commit(&vb_state, ctx)
+        .context("failed to update gitbutler workspace")?;
+
+    Ok(outcome)
+}
+
+/// Return the tree of `stack_head` without the changes of the commit with `commit_id`.
+fn inner_revert_tree(
+    repository: &git2::Repository,
+    stack_head: git2::Oid,
+    commit_id: git2::Oid,
+) -> Result<git2::Oid> {
+    let commit = repository.find_commit(commit_id)?;
+    let parent_tree = commit
+        .parent(0)
+        .context("failed to find parent commit")?
+        .tree()?;
+    let head_tree = repository.find_commit(stack_head)?.tree()?;
+
+    // A revert is a cherry-pick of the parent onto the head, with the commit as the base.
+    let mut merge_index =
+        repository.merge_trees(&commit.tree()?, &head_tree, &parent_tree, None)?;
+    if merge_index.has_conflicts() {
+        bail!(
+            "Reverting commit '{}' would conflict with the commits above it",
+            commit_subject(&commit)
+        );
+    }
+    Ok(merge_index.write_tree_to(repository)?)
+}
+
+/// Return the head of the stack at `stack_head` once the commit with `commit_id` is removed, with
+/// the commits above it rebased onto its parent.
+fn inner_drop_commit(
+    repository: &git2::Repository,
+    stack_head: git2::Oid,
+    commit_id: git2::Oid,
+    auto_resolve_whitespace: bool,
+) -> Result<git2::Oid> {
+    let commit = repository.find_commit(commit_id)?;
+    let parent_id = commit
+        .parent_id(0)
+        .context("failed to find parent commit")?;
+    let commits_above = repository.l(stack_head, LogUntil::Commit(commit_id), false)?;
+    if commits_above.is_empty() {
+        return Ok(parent_id);
+    }
+
+    let new_head = cherry_rebase_group(
+        repository,
+        parent_id,
+        &commits_above,
+        auto_resolve_whitespace,
+    )?;
+    let rebased = repository.l(new_head, LogUntil::Commit(parent_id), false)?;
+    for rebased in rebased {
+        if repository.find_commit(rebased)?.is_conflicted() {
+            bail!(
+                "Dropping commit '{}' would conflict with the commits above it",
+                commit_subject(&commit)
+            );
+        }
+    }
+    Ok(new_head)
+}
+
+fn commit_subject(commit: &git2::Commit) -> String {
+    commit
+        .message_bstr()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_str_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use gitbutler_testsupport::testing_repository::{
+        assert_commit_tree_matches, assert_tree_matches, TestingRepository,
+    };
+
+    use super::*;
+
+    #[test]
+    fn reverted_tree_keeps_later_changes() {
+        let test_repository = TestingRepository::open();
+        let repository = &test_repository.repository;
+
+        let base = test_repository.commit_tree(None, &[("foo.txt", "1\n2\n3\n")]);
+        let change = test_repository.commit_tree(Some(&base), &[("foo.txt", "1\n2b\n3\n")]);
+        let later = test_repository.commit_tree(
+            Some(&change),
+            &[("foo.txt", "1\n2b\n3\n"), ("bar.txt", "bar")],
+        );
+
+        let tree = inner_revert_tree(repository, later.id(), change.id()).unwrap();
+        assert_tree_matches(
+            repository,
+            &repository.find_tree(tree).unwrap(),
+            &[("foo.txt", b"1\n2\n3\n"), ("bar.txt", b"bar")],
+        );
+
+        let conflicting = test_repository.commit_tree(Some(&later), &[("foo.txt", "1\n2c\n3\n")]);
+        assert!(
+            inner_revert_tree(repository, conflicting.id(), change.id()).is_err(),
+            "the line was changed again above the commit"
+        );
+    }
+
+    #[test]
+    fn dropped_commit_is_removed_from_history() {
+        let test_repository = TestingRepository::open();
+        let repository = &test_repository.repository;
+
+        let base = test_repository.commit_tree(None, &[("foo.txt", "foo")]);
+        let dropped =
+            test_repository.commit_tree(Some(&base), &[("foo.txt", "foo"), ("bar.txt", "bar")]);
+        let later = test_repository.commit_tree(
+            Some(&dropped),
+            &[("foo.txt", "foo"), ("bar.txt", "bar"), ("baz.txt", "baz")],
+        );
+
+        let new_head = inner_drop_commit(repository, later.id(), dropped.id(), false).unwrap();
+        let new_head = repository.find_commit(new_head).unwrap();
+        assert_commit_tree_matches(
+            repository,
+            &new_head,
+            &[("foo.txt", b"foo"), ("baz.txt", b"baz")],
+        );
+        assert!(new_head.tree().unwrap().get_name("bar.txt").is_none());
+        assert_eq!(new_head.parent_id(0).unwrap(), base.id());
+
+        assert_eq!(
+            inner_drop_commit(repository, later.id(), later.id(), false).unwrap(),
+            dropped.id(),
+            "dropping the head leaves its parent"
+        );
+    }
+}
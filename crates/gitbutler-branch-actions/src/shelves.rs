@@ -0,0 +1,264 @@
+//! Shelving the uncommitted changes of a stack, to get them out of the worktree for a while.
+//!
+//! A shelf is a commit on top of the head the stack had when shelving, whose tree has the
+//! uncommitted changes applied. It's referenced as `refs/gitbutler/shelves/<stack-id>/<name>`, so
+//! it's kept by Git as long as it's needed, and the ref is deleted once the shelf is unshelved.
+//!
+//! Unshelving merges the shelved changes with what the stack has now, which might include new
+//! commits and uncommitted changes. It fails without touching anything if they conflict.
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use gitbutler_cherry_pick::RepositoryExt as _;
+use gitbutler_command_context::CommandContext;
+use gitbutler_diff::Hunk;
+use gitbutler_error::error::Code;
+use gitbutler_project::access::WorktreeWritePermission;
+use gitbutler_repo::{RepositoryExt as _, SignaturePurpose};
+use gitbutler_stack::{OwnershipClaim, Stack, StackId};
+use itertools::Itertools as _;
+use serde::Serialize;
+
+use crate::{
+    file::VirtualBranchFile, integration::get_workspace_head, status::get_applied_status,
+    VirtualBranchesExt as _,
+};
+
+const SHELVES_REF_PREFIX: &str = "refs/gitbutler/shelves/";
+
+/// Uncommitted changes of a stack that were shelved.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Shelf {
+    pub name: String,
+    pub stack_id: StackId,
+    /// The commit holding the shelved changes on top of the head of the stack at the time.
+    #[serde(with = "gitbutler_serde::oid")]
+    pub commit_id: git2::Oid,
+    /// When the changes were shelved, in milliseconds since the epoch.
+    pub created_at: u128,
+    /// The paths of the files with shelved changes.
+    pub paths: Vec<PathBuf>,
+}
+
+/// Shelve the uncommitted changes of the stack with `stack_id` as `name`, and remove them from the
+/// worktree.
+pub(crate) fn shelve(
+    ctx: &CommandContext,
+    stack_id: StackId,
+    name: &str,
+    perm: &mut WorktreeWritePermission,
+) -> Result<Shelf> {
+    ctx.assure_resolved()?;
+    let repo = ctx.repository();
+    let name = name.trim();
+    let refname = shelf_refname(stack_id, name)?;
+    if repo.find_reference(&refname).is_ok() {
+        return Err(anyhow!("There already is a shelf named '{name}'").context(Code::Validation));
+    }
+
+    let (stack, files) = stack_status(ctx, stack_id)?;
+    if files.is_empty() {
+        return Err(anyhow!("There are no changes to shelve").context(Code::Validation));
+    }
+    let paths = files.iter().map(|file| file.path.clone()).collect_vec();
+    let tree_id = gitbutler_diff::write::hunks_onto_commit(
+        ctx,
+        stack.head(),
+        files.into_iter().map(|file| (file.path, file.hunks)),
+    )?;
+
+    let committer = gitbutler_repo::signature(SignaturePurpose::Committer)?;
+    let commit_id = repo.commit_for_gitbutler_ref(
+        &committer,
+        &committer,
+        name,
+        &repo.find_tree(tree_id)?,
+        &[&repo.find_commit(stack.head())?],
+    )?;
+    repo.reference(&refname, commit_id, false, "shelve changes")
+        .context("failed to create shelf")?;
+
+    crate::r#virtual::reset_files(ctx, stack_id, &paths, perm)
+        .context("failed to remove the shelved changes")?;
+
+    shelf(repo, stack_id, name, &repo.find_commit(commit_id)?)
+}
+
+/// Apply the changes shelved as `name` to the stack with `stack_id`, and delete the shelf.
+///
+/// This fails with [`Code::Validation`] and leaves the shelf as it is if the changes conflict with
+/// the stack or the other changes in the worktree.
+pub(crate) fn unshelve(
+    ctx: &CommandContext,
+    stack_id: StackId,
+    name: &str,
+    _perm: &mut WorktreeWritePermission,
+) -> Result<()> {
+    ctx.assure_resolved()?;
+    let repo = ctx.repository();
+    let mut reference = find_shelf(repo, stack_id, name)?;
+    let shelf_commit = reference.peel_to_commit()?;
+    let base = repo.find_real_tree(&shelf_commit.parent(0)?, Default::default())?;
+    let shelved = shelf_commit.tree()?;
+
+    let (stack, files) = stack_status(ctx, stack_id)?;
+    let ours = repo.find_tree(gitbutler_diff::write::hunks_onto_commit(
+        ctx,
+        stack.head(),
+        files.into_iter().map(|file| (file.path, file.hunks)),
+    )?)?;
+    let mut merge = repo.merge_trees(&base, &ours, &shelved, None)?;
+    if merge.has_conflicts() {
+        return Err(anyhow!(
+            "Unshelving '{name}' conflicts with the stack in {}",
+            conflicting_paths(&merge)?.join(", ")
+        )
+        .context(Code::Validation));
+    }
+    let unshelved = repo.find_tree(merge.write_tree_to(repo)?)?;
+
+    // The worktree also has the changes of the other stacks, which stay as they are.
+    let worktree = repo.create_wd_tree()?;
+    let mut merge = repo.merge_trees(&ours, &worktree, &unshelved, None)?;
+    if merge.has_conflicts() {
+        return Err(anyhow!(
+            "Unshelving '{name}' conflicts with the changes in the worktree in {}",
+            conflicting_paths(&merge)?.join(", ")
+        )
+        .context(Code::Validation));
+    }
+    let tree = repo.find_tree(merge.write_tree_to(repo)?)?;
+    repo.checkout_tree_builder(&tree)
+        .force()
+        .remove_untracked()
+        .checkout()
+        .context("failed to checkout the unshelved changes")?;
+
+    claim_unshelved_hunks(ctx, stack_id, &changed_paths(repo, &base, &shelved)?)?;
+    reference.delete().context("failed to delete shelf")?;
+    Ok(())
+}
+
+/// Return the shelves of the stack with `stack_id`, the latest first.
+pub(crate) fn list(ctx: &CommandContext, stack_id: StackId) -> Result<Vec<Shelf>> {
+    let repo = ctx.repository();
+    let prefix = format!("{SHELVES_REF_PREFIX}{stack_id}/");
+    let mut shelves = repo
+        .references_glob(&format!("{prefix}*"))?
+        .map(|reference| {
+            let reference = reference?;
+            let name = reference
+                .name()
+                .and_then(|refname| refname.strip_prefix(&prefix))
+                .context("shelf has an invalid name")?
+                .to_owned();
+            shelf(repo, stack_id, &name, &reference.peel_to_commit()?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    shelves.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(shelves)
+}
+
+/// Delete the shelf `name` of the stack with `stack_id` along with the changes on it.
+pub(crate) fn delete(ctx: &CommandContext, stack_id: StackId, name: &str) -> Result<()> {
+    find_shelf(ctx.repository(), stack_id, name)?
+        .delete()
+        .context("failed to delete shelf")
+}
+
+fn shelf_refname(stack_id: StackId, name: &str) -> Result<String> {
+    let refname = format!("{SHELVES_REF_PREFIX}{stack_id}/{name}");
+    if name.is_empty() || !git2::Reference::is_valid_name(&refname) {
+        return Err(anyhow!("'{name}' is not a valid name for a shelf").context(Code::Validation));
+    }
+    Ok(refname)
+}
+
+fn find_shelf<'repo>(
+    repo: &'repo git2::Repository,
+    stack_id: StackId,
+    name: &str,
+) -> Result<git2::Reference<'repo>> {
+    let name = name.trim();
+    repo.find_reference(&shelf_refname(stack_id, name)?)
+        .map_err(|_| anyhow!("There is no shelf named '{name}'").context(Code::Validation))
+}
+
+fn shelf(
+    repo: &git2::Repository,
+    stack_id: StackId,
+    name: &str,
+    commit: &git2::Commit,
+) -> Result<Shelf> {
+    let base = repo.find_real_tree(&commit.parent(0)?, Default::default())?;
+    Ok(Shelf {
+        name: name.to_owned(),
+        stack_id,
+        commit_id: commit.id(),
+        created_at: u128::try_from(commit.time().seconds()).unwrap_or_default() * 1000,
+        paths: changed_paths(repo, &base, &commit.tree()?)?,
+    })
+}
+
+fn stack_status(
+    ctx: &CommandContext,
+    stack_id: StackId,
+) -> Result<(Stack, Vec<VirtualBranchFile>)> {
+    get_applied_status(ctx, None)?
+        .branches
+        .into_iter()
+        .find(|(stack, _)| stack.id == stack_id)
+        .with_context(|| format!("could not find applied branch with id {stack_id}"))
+}
+
+fn changed_paths(
+    repo: &git2::Repository,
+    old: &git2::Tree,
+    new: &git2::Tree,
+) -> Result<Vec<PathBuf>> {
+    Ok(repo
+        .diff_tree_to_tree(Some(old), Some(new), None)?
+        .deltas()
+        .filter_map(|delta| delta.new_file().path().or(delta.old_file().path()))
+        .map(ToOwned::to_owned)
+        .collect())
+}
+
+fn conflicting_paths(index: &git2::Index) -> Result<Vec<String>> {
+    Ok(index
+        .conflicts()?
+        .flatten()
+        .filter_map(|conflict| conflict.our.or(conflict.their).or(conflict.ancestor))
+        .map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+        .collect())
+}
+
+/// Make the stack with `stack_id` own the uncommitted hunks in `paths` that no stack owns yet,
+/// which are the ones that were unshelved, so they don't end up in the default stack.
+fn claim_unshelved_hunks(ctx: &CommandContext, stack_id: StackId, paths: &[PathBuf]) -> Result<()> {
+    let vb_state = ctx.project().virtual_branches();
+    let stacks = vb_state.list_branches_in_workspace()?;
+    let mut stack = vb_state.get_branch_in_workspace(stack_id)?;
+    let diffs = gitbutler_diff::workdir(ctx.repository(), get_workspace_head(ctx)?)?;
+    for (path, diff) in diffs {
+        if !paths.contains(&path) {
+            continue;
+        }
+        for hunk in diff.hunks {
+            let is_claimed = stacks
+                .iter()
+                .flat_map(|stack| &stack.ownership.claims)
+                .filter(|claim| claim.file_path == path)
+                .flat_map(|claim| &claim.hunks)
+                .any(|claimed| claimed.intersects(&hunk));
+            if !is_claimed {
+                stack.ownership.put(OwnershipClaim {
+                    file_path: path.clone(),
+                    hunks: vec![Hunk::from(&hunk).with_hash(Hunk::hash_diff(&hunk.diff_lines))],
+                });
+            }
+        }
+    }
+    vb_state.set_branch(stack)
+}
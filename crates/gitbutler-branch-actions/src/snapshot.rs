@@ -0,0 +1,104 @@
+//! A consistent view of the branches of a repository for reading them, as another operation may change the
+//! references and the virtual branches state while they are read.
+//!
+//! The ids of all branch references and the state of virtual branches are captured once, objects are read
+//! by the captured ids, and [`BranchesSnapshot::verify()`] tells whether anything changed in the meantime.
+use std::collections::HashMap;
+
+use anyhow::Result;
+use gitbutler_stack::{VirtualBranchesHandle, VirtualBranchesState};
+use gix::reference::Category;
+
+/// Returned by read operations if the branches changed while they were read, so reading them again
+/// is expected to produce a consistent result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BranchesChanged;
+
+impl std::fmt::Display for BranchesChanged {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("The branches changed while they were read, please try again")
+    }
+}
+
+impl std::error::Error for BranchesChanged {}
+
+pub(crate) struct BranchesSnapshot {
+    /// The ids local and remote branches point to, by their full name.
+    ids: HashMap<gix::refs::FullName, gix::ObjectId>,
+    pub virtual_branches: VirtualBranchesState,
+}
+
+impl BranchesSnapshot {
+    pub fn capture(repo: &gix::Repository, vb_handle: &VirtualBranchesHandle) -> Result<Self> {
+        Ok(BranchesSnapshot {
+            ids: branch_ids(repo)?,
+            virtual_branches: vb_handle.read()?,
+        })
+    }
+
+    /// The id the branch called `name` pointed to when the snapshot was taken, if it existed.
+    pub fn id(&self, name: &gix::refs::FullNameRef) -> Option<gix::ObjectId> {
+        self.ids.get(name).copied()
+    }
+
+    /// Fail with [`BranchesChanged`] if the branches aren't the same as when the snapshot was taken.
+    pub fn verify(&self, repo: &gix::Repository, vb_handle: &VirtualBranchesHandle) -> Result<()> {
+        if branch_ids(repo)? != self.ids || vb_handle.read()? != self.virtual_branches {
+            return Err(BranchesChanged.into());
+        }
+        Ok(())
+    }
+}
+
+fn branch_ids(repo: &gix::Repository) -> Result<HashMap<gix::refs::FullName, gix::ObjectId>> {
+    let packed = repo.refs.cached_packed_buffer()?;
+    let mut ids = HashMap::new();
+    for mut reference in repo.references()?.all()?.filter_map(Result::ok) {
+        if !matches!(
+            reference.name().category(),
+            Some(Category::LocalBranch | Category::RemoteBranch)
+        ) {
+            continue;
+        }
+        let name = reference.name().to_owned();
+        let Ok(id) = reference.peel_to_id_in_place_packed(packed.as_ref().map(|p| &***p)) else {
+            continue;
+        };
+        ids.insert(name, id.detach());
+    }
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use gitbutler_testsupport::TestProject;
+
+    use super::*;
+
+    #[test]
+    fn changed_branches_are_detected() -> Result<()> {
+        let project = TestProject::default();
+        let state_dir = tempfile::tempdir()?;
+        let vb_handle = VirtualBranchesHandle::new(state_dir.path());
+        let repo = gix::open(project.path())?;
+
+        let snapshot = BranchesSnapshot::capture(&repo, &vb_handle)?;
+        snapshot.verify(&repo, &vb_handle)?;
+        let master = snapshot
+            .id("refs/heads/master".try_into()?)
+            .expect("the initial branch was captured");
+
+        project.commit_all("change master");
+        let err = snapshot.verify(&repo, &vb_handle).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<BranchesChanged>(),
+            Some(&BranchesChanged)
+        );
+        assert_eq!(
+            snapshot.id("refs/heads/master".try_into()?),
+            Some(master),
+            "reads stay on the captured ids"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,400 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use bstr::{BString, ByteSlice as _};
+use gitbutler_command_context::CommandContext;
+use gitbutler_commit::{
+    commit_ext::CommitExt as _,
+    commit_headers::{CommitHeadersV2, HasCommitHeaders as _},
+};
+use gitbutler_diff::{GitHunk, Hunk};
+use gitbutler_error::error::Code;
+use gitbutler_repo::{rebase::cherry_rebase_group, LogUntil, RepositoryExt as _};
+use gitbutler_stack::{BranchOwnershipClaims, StackId};
+use serde::Deserialize;
+
+use crate::VirtualBranchesExt as _;
+
+/// The hunks of a commit that go into one of the commits it is split into.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitAssignment {
+    pub message: String,
+    /// The hunks of the commit that is split, with their ranges in the files after the commit.
+    pub ownership: BranchOwnershipClaims,
+}
+
+/// Split the commit with `commit_id` of the stack with `stack_id` into one commit per assignment,
+/// in application order, and rebase the commits above it onto the last of them.
+///
+/// The hunks of the commit that aren't assigned stay in a last commit that keeps its message.
+/// Returns the IDs of the commits the commit was split into, in application order.
+///
+/// As the commits together change what the commit changed, the commits above it are rebased onto
+/// the same tree and can't conflict. Everything that could go wrong is checked before the stack is
+/// touched, like hunks that aren't part of the commit, or are assigned more than once.
+pub(crate) fn split_commit(
+    ctx: &CommandContext,
+    stack_id: StackId,
+    commit_id: git2::Oid,
+    assignments: &[SplitAssignment],
+) -> Result<Vec<git2::Oid>> {
+    ctx.assure_resolved()?;
+
+    let vb_state = ctx.project().virtual_branches();
+    let mut stack = vb_state.get_branch_in_workspace(stack_id)?;
+    let default_target = vb_state.get_default_target()?;
+    let repository = ctx.repository();
+    let stack_commit_ids =
+        repository.l(stack.head(), LogUntil::Commit(default_target.sha), false)?;
+    if !stack_commit_ids.contains(&commit_id) {
+        bail!("commit {commit_id} not in the branch");
+    }
+
+    let pushed_commit_ids = stack.upstream_head.map_or_else(
+        || Ok(vec![]),
+        |upstream_head| repository.l(upstream_head, LogUntil::Commit(default_target.sha), false),
+    )?;
+    if pushed_commit_ids.contains(&commit_id) && !stack.allow_rebasing {
+        // splitting a pushed commit will cause a force push that is not allowed
+        bail!("force push not allowed");
+    }
+
+    let SplitResult {
+        new_head,
+        commit_ids,
+    } = inner_split_commit(
+        repository,
+        stack.head(),
+        commit_id,
+        assignments,
+        ctx.project().auto_resolve_whitespace,
+    )?;
+    crate::policy::assure_commits_allowed(ctx, &stack, &commit_ids)?;
+
+    stack.set_stack_head(ctx, new_head, None)?;
+    let split_commit = repository.find_commit(commit_id)?;
+    let last_commit = repository.find_commit(*commit_ids.last().expect("split into commits"))?;
+    stack.replace_head(ctx, &split_commit, &last_commit)?;
+
+    crate::integration::update_workspace_commit(&vb_state, ctx)
+        .context("failed to update gitbutler workspace")?;
+
+    Ok(commit_ids)
+}
+
+struct SplitResult {
+    new_head: git2::Oid,
+    commit_ids: Vec<git2::Oid>,
+}
+
+fn inner_split_commit(
+    repository: &git2::Repository,
+    stack_head: git2::Oid,
+    commit_id: git2::Oid,
+    assignments: &[SplitAssignment],
+    auto_resolve_whitespace: bool,
+) -> Result<SplitResult> {
+    let commit = repository.find_commit(commit_id)?;
+    if commit.is_conflicted() {
+        bail!("Can not split a conflicted commit");
+    }
+    let tree = commit.tree().context("failed to get commit tree")?;
+    let parent = commit.parent(0).context("failed to find parent commit")?;
+    let parent_tree = parent.tree().context("failed to get parent tree")?;
+
+    let diff = gitbutler_diff::trees(repository, &parent_tree, &tree, true)?;
+    let diff: HashMap<PathBuf, Vec<GitHunk>> =
+        gitbutler_diff::diff_files_into_hunks(diff).collect();
+    let hunk_count: usize = diff.values().map(Vec::len).sum();
+
+    // The assignment of each hunk, by path and index into the hunks of the path.
+    let mut assigned: HashMap<(&Path, usize), usize> = HashMap::new();
+    for (index, assignment) in assignments.iter().enumerate() {
+        if assignment
+            .ownership
+            .claims
+            .iter()
+            .all(|claim| claim.hunks.is_empty())
+        {
+            return Err(
+                anyhow!("Commit {} of the split has no hunks", index + 1).context(Code::Validation)
+            );
+        }
+        for claim in &assignment.ownership.claims {
+            let hunks = diff
+                .get(&claim.file_path)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            for claimed in &claim.hunks {
+                let position = hunks
+                    .iter()
+                    .position(|hunk| Hunk::from(hunk) == *claimed)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Hunk {}:{claimed} isn't part of commit {commit_id}",
+                            claim.file_path.display()
+                        )
+                        .context(Code::Validation)
+                    })?;
+                if assigned
+                    .insert((claim.file_path.as_path(), position), index)
+                    .is_some()
+                {
+                    return Err(anyhow!(
+                        "Hunk {}:{claimed} is assigned more than once",
+                        claim.file_path.display()
+                    )
+                    .context(Code::Validation));
+                }
+            }
+        }
+    }
+    let has_remainder = assigned.len() < hunk_count;
+    if assignments.len() + usize::from(has_remainder) < 2 {
+        return Err(
+            anyhow!("Splitting commit {commit_id} needs hunks for at least two commits")
+                .context(Code::Validation),
+        );
+    }
+
+    // Write all trees before any commit, so a hunk that can't be applied fails the split early.
+    let mut trees = Vec::with_capacity(assignments.len());
+    for index in 0..assignments.len() {
+        let is_last = index + 1 == assignments.len() && !has_remainder;
+        let tree_id = if is_last {
+            tree.id()
+        } else {
+            let mut selected: HashMap<&Path, Vec<&GitHunk>> = HashMap::new();
+            for (&(path, position), _) in assigned.iter().filter(|(_, i)| **i <= index) {
+                selected
+                    .entry(path)
+                    .or_default()
+                    .push(&diff[path][position]);
+            }
+            partial_tree(repository, &parent_tree, &tree, &diff, selected)?
+        };
+        trees.push(repository.find_tree(tree_id)?);
+    }
+
+    let mut parent = parent;
+    let mut commit_ids = Vec::with_capacity(trees.len() + 1);
+    let mut write_commit =
+        |message: &str, tree: &git2::Tree, headers: Option<CommitHeadersV2>| -> Result<()> {
+            let new_commit_id = repository
+                .commit_with_signature(
+                    None,
+                    &commit.author(),
+                    &commit.committer(),
+                    message,
+                    tree,
+                    &[&parent],
+                    headers,
+                )
+                .context("failed to create commit")?;
+            parent = repository.find_commit(new_commit_id)?;
+            commit_ids.push(new_commit_id);
+            Ok(())
+        };
+    for (index, (assignment, tree)) in assignments.iter().zip(&trees).enumerate() {
+        // The last commit takes over the headers of the commit, and with them its change ID.
+        let headers = if index + 1 == assignments.len() && !has_remainder {
+            commit.gitbutler_headers()
+        } else {
+            Some(CommitHeadersV2::new())
+        };
+        write_commit(&assignment.message, tree, headers)?;
+    }
+    if has_remainder {
+        write_commit(
+            &commit.message_bstr().to_str_lossy(),
+            &tree,
+            commit.gitbutler_headers(),
+        )?;
+    }
+    let last_commit_id = *commit_ids.last().expect("split into commits");
+
+    let new_head = if stack_head == commit_id {
+        last_commit_id
+    } else {
+        let commits_to_rebase = repository.l(stack_head, LogUntil::Commit(commit_id), false)?;
+        cherry_rebase_group(
+            repository,
+            last_commit_id,
+            &commits_to_rebase,
+            auto_resolve_whitespace,
+        )?
+    };
+
+    Ok(SplitResult {
+        new_head,
+        commit_ids,
+    })
+}
+
+/// Return the tree of `parent_tree` with the `selected` hunks of `diff` applied, which is the diff
+/// from `parent_tree` to `tree`.
+pub(crate) fn partial_tree(
+    repository: &git2::Repository,
+    parent_tree: &git2::Tree,
+    tree: &git2::Tree,
+    diff: &HashMap<PathBuf, Vec<GitHunk>>,
+    selected: HashMap<&Path, Vec<&GitHunk>>,
+) -> Result<git2::Oid> {
+    let mut builder = git2::build::TreeUpdateBuilder::new();
+    for (path, mut hunks) in selected {
+        if hunks.len() == diff[path].len() {
+            // All changes of the file are selected, which also covers files that were added,
+            // deleted, or are binary.
+            match tree.get_path(path) {
+                Ok(entry) => builder.upsert(path, entry.id(), file_mode(entry.filemode())),
+                Err(_) => builder.remove(path),
+            };
+            continue;
+        }
+
+        let parent_entry = parent_tree
+            .get_path(path)
+            .with_context(|| format!("failed to find {} before the commit", path.display()))?;
+        let blob = parent_entry
+            .to_object(repository)?
+            .peel_to_blob()
+            .context("failed to get blob")?;
+        hunks.sort_by_key(|hunk| hunk.old_start);
+        let mut all_diffs = BString::default();
+        for hunk in hunks {
+            all_diffs.push_str(&hunk.diff_lines);
+        }
+        let patch = diffy::Patch::from_bytes(&all_diffs)?;
+        let contents = gitbutler_diff::write::apply(blob.content(), &patch)
+            .with_context(|| format!("failed to apply the hunks of {}", path.display()))?;
+        let mode = tree
+            .get_path(path)
+            .map_or(parent_entry.filemode(), |entry| entry.filemode());
+        builder.upsert(path, repository.blob(&contents)?, file_mode(mode));
+    }
+    builder
+        .create_updated(repository, parent_tree)
+        .context("failed to write split tree")
+}
+
+fn file_mode(mode: i32) -> git2::FileMode {
+    match mode {
+        0o100755 => git2::FileMode::BlobExecutable,
+        0o120000 => git2::FileMode::Link,
+        0o160000 => git2::FileMode::Commit,
+        _ => git2::FileMode::Blob,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use gitbutler_testsupport::testing_repository::{
+        assert_commit_tree_matches, TestingRepository,
+    };
+
+    use super::*;
+
+    fn assignment(message: &str, ownership: &str) -> SplitAssignment {
+        SplitAssignment {
+            message: message.to_owned(),
+            ownership: ownership.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn splitting_a_commit_keeps_its_changes() {
+        let test_repository = TestingRepository::open();
+        let repository = &test_repository.repository;
+
+        let file = |first: &str, last: &str| {
+            let middle = (2..20).map(|n| format!("{n}\n")).collect::<String>();
+            format!("{first}\n{middle}{last}\n")
+        };
+        let a = test_repository.commit_tree(None, &[("foo.txt", &file("1", "20"))]);
+        let changed = file("one", "twenty");
+        let b = test_repository.commit_tree(Some(&a), &[("foo.txt", &changed), ("bar.txt", "bar")]);
+        let c = test_repository.commit_tree(
+            Some(&b),
+            &[
+                ("foo.txt", &changed),
+                ("bar.txt", "bar"),
+                ("baz.txt", "baz"),
+            ],
+        );
+
+        let SplitResult {
+            new_head,
+            commit_ids,
+        } = inner_split_commit(
+            repository,
+            c.id(),
+            b.id(),
+            &[
+                assignment("first", "foo.txt:1-5"),
+                assignment("second", "bar.txt:1-2"),
+            ],
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(
+            commit_ids.len(),
+            3,
+            "the rest of foo.txt stays in a last commit"
+        );
+        let first = repository.find_commit(commit_ids[0]).unwrap();
+        assert_eq!(first.message(), Some("first"));
+        assert_commit_tree_matches(
+            repository,
+            &first,
+            &[("foo.txt", file("one", "20").as_bytes())],
+        );
+        let second = repository.find_commit(commit_ids[1]).unwrap();
+        assert_commit_tree_matches(
+            repository,
+            &second,
+            &[
+                ("foo.txt", file("one", "20").as_bytes()),
+                ("bar.txt", b"bar"),
+            ],
+        );
+        let last = repository.find_commit(commit_ids[2]).unwrap();
+        assert_eq!(last.message(), b.message());
+        assert_eq!(last.tree_id(), b.tree_id());
+
+        let new_head = repository.find_commit(new_head).unwrap();
+        assert_eq!(new_head.parent_id(0).unwrap(), last.id());
+        assert_eq!(new_head.tree_id(), c.tree_id());
+        assert!(!new_head.is_conflicted());
+    }
+
+    #[test]
+    fn invalid_assignments_fail() {
+        let test_repository = TestingRepository::open();
+        let repository = &test_repository.repository;
+
+        let a = test_repository.commit_tree(None, &[("foo.txt", "foo")]);
+        let b = test_repository.commit_tree(Some(&a), &[("foo.txt", "foo"), ("bar.txt", "bar")]);
+
+        let split = |assignments: &[SplitAssignment]| {
+            inner_split_commit(repository, b.id(), b.id(), assignments, false).map(|_| ())
+        };
+        assert!(
+            split(&[assignment("missing", "baz.txt:1-2")]).is_err(),
+            "hunks must be part of the commit"
+        );
+        assert!(
+            split(&[assignment("all", "bar.txt:1-2")]).is_err(),
+            "a commit with a single hunk can't be split"
+        );
+        assert!(split(&[
+            assignment("first", "bar.txt:1-2"),
+            assignment("again", "bar.txt:1-2")
+        ])
+        .is_err());
+    }
+}
@@ -0,0 +1,398 @@
+use anyhow::{bail, Context as _, Result};
+use bstr::ByteSlice as _;
+use gitbutler_command_context::CommandContext;
+use gitbutler_commit::{commit_ext::CommitExt as _, commit_headers::HasCommitHeaders as _};
+use gitbutler_hunk_dependency::Operation;
+use gitbutler_repo::{rebase::cherry_rebase_group, LogUntil, RepositoryExt as _};
+use gitbutler_stack::StackId;
+use itertools::Itertools as _;
+
+use crate::{dependencies, VirtualBranchesExt as _};
+
+/// The template of the message of a squashed commit if none is given.
+const DEFAULT_MESSAGE_TEMPLATE: &str = "{message}";
+
+/// Squash the commits with `commit_ids` of the stack with `stack_id` into the commit with
+/// `target_id`, and return the ID of the squashed commit.
+///
+/// The selected commits don't have to be adjacent to the target or to each other. They move to
+/// the target, which fails without changing anything if the hunk dependencies show that they
+/// would move past a commit they depend on, or that depends on them. The commits that aren't
+/// selected are rebased onto the result.
+///
+/// The message of the squashed commit is made of the messages of the target and then the selected
+/// commits in application order, each rendered with `message_template` and separated by a blank
+/// line. In the template, `{message}` is replaced with the whole message of a commit, `{subject}`
+/// with its first line and `{body}` with the rest.
+pub(crate) fn squash_commits(
+    ctx: &CommandContext,
+    stack_id: StackId,
+    target_id: git2::Oid,
+    commit_ids: &[git2::Oid],
+    message_template: Option<&str>,
+) -> Result<git2::Oid> {
+    ctx.assure_resolved()?;
+    let commit_ids = commit_ids
+        .iter()
+        .copied()
+        .filter(|id| *id != target_id)
+        .unique()
+        .collect_vec();
+    if commit_ids.is_empty() {
+        bail!("no commits to squash into {target_id}");
+    }
+
+    let vb_state = ctx.project().virtual_branches();
+    let mut stack = vb_state.get_branch_in_workspace(stack_id)?;
+    let default_target = vb_state.get_default_target()?;
+    let repository = ctx.repository();
+
+    let prediction = dependencies::predict_operation(
+        ctx,
+        &Operation::Squash {
+            commit_ids: commit_ids.clone(),
+            target_id: Some(target_id),
+        },
+    )?;
+    if prediction.conflicts {
+        bail!(
+            "Squashing the commits would conflict in {}",
+            prediction
+                .paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .join(", ")
+        );
+    }
+
+    let SquashResult {
+        new_head,
+        squashed_id,
+    } = inner_squash_commits(
+        repository,
+        stack.head(),
+        default_target.sha,
+        target_id,
+        &commit_ids,
+        message_template.unwrap_or(DEFAULT_MESSAGE_TEMPLATE),
+        ctx.project().auto_resolve_whitespace,
+    )?;
+
+    crate::policy::assure_commits_allowed(ctx, &stack, &[squashed_id])?;
+
+    let pushed_commit_ids = stack.upstream_head.map_or_else(
+        || Ok(vec![]),
+        |upstream_head| repository.l(upstream_head, LogUntil::Commit(default_target.sha), false),
+    )?;
+    if !pushed_commit_ids.is_empty() && !stack.allow_rebasing {
+        let kept = repository.l(new_head, LogUntil::Commit(default_target.sha), false)?;
+        if pushed_commit_ids.iter().any(|id| !kept.contains(id)) {
+            // rewriting pushed commits will cause a force push that is not allowed
+            bail!("force push not allowed");
+        }
+    }
+
+    stack.set_stack_head(ctx, new_head, None)?;
+    let squashed = repository.find_commit(squashed_id)?;
+    for commit_id in &commit_ids {
+        stack.replace_head(ctx, &repository.find_commit(*commit_id)?, &squashed)?;
+    }
+    crate::integration::update_workspace_commit(&vb_state, ctx)
+        .context("failed to update gitbutler workspace")?;
+    Ok(squashed_id)
+}
+
+struct SquashResult {
+    new_head: git2::Oid,
+    squashed_id: git2::Oid,
+}
+
+fn inner_squash_commits(
+    repository: &git2::Repository,
+    stack_head: git2::Oid,
+    base: git2::Oid,
+    target_id: git2::Oid,
+    commit_ids: &[git2::Oid],
+    message_template: &str,
+    auto_resolve_whitespace: bool,
+) -> Result<SquashResult> {
+    let mut commits = repository.l(stack_head, LogUntil::Commit(base), false)?;
+    commits.reverse();
+    let position = |id: &git2::Oid| {
+        commits
+            .iter()
+            .position(|other| other == id)
+            .with_context(|| format!("commit {id} not in the branch"))
+    };
+    let target = position(&target_id)?;
+    let selected = commit_ids
+        .iter()
+        .map(position)
+        .chain([Ok(target)])
+        .collect::<Result<Vec<_>>>()?;
+    let first = *selected.iter().min().expect("there is a commit");
+
+    let target_commit = repository.find_commit(target_id)?;
+    let squashed = selected
+        .iter()
+        .sorted()
+        .map(|position| repository.find_commit(commits[*position]))
+        .collect::<Result<Vec<_>, _>>()?;
+    if squashed.iter().any(|commit| commit.is_conflicted()) {
+        bail!("Can not squash conflicted commits");
+    }
+
+    // Move the selected commits next to the target, and fail before anything is written if that
+    // conflicts. The commits below the first selected one keep their place.
+    let (before, after): (Vec<_>, Vec<_>) = (first..commits.len())
+        .filter(|position| !selected.contains(position))
+        .partition(|position| *position < target);
+    let reordered = before
+        .iter()
+        .map(|position| commits[*position])
+        .chain(squashed.iter().map(|commit| commit.id()))
+        .chain(after.iter().map(|position| commits[*position]))
+        .collect_vec();
+    let parent_id = repository
+        .find_commit(commits[first])?
+        .parent_id(0)
+        .context("failed to find parent commit")?;
+    let ids_to_rebase = reordered.iter().rev().copied().collect_vec();
+    let rebased_head = cherry_rebase_group(
+        repository,
+        parent_id,
+        &ids_to_rebase,
+        auto_resolve_whitespace,
+    )?;
+    let mut rebased = repository.l(rebased_head, LogUntil::Commit(parent_id), false)?;
+    rebased.reverse();
+    for (original, rebased) in reordered.iter().zip(&rebased) {
+        if repository.find_commit(*rebased)?.is_conflicted() {
+            bail!(
+                "Squashing would conflict at commit {}",
+                subject(&repository.find_commit(*original)?)
+            );
+        }
+    }
+
+    // The last of the moved commits has the tree of all of them applied.
+    let squashed_parent = match before.len() {
+        0 => repository.find_commit(parent_id)?,
+        len => repository.find_commit(rebased[len - 1])?,
+    };
+    let squashed_tree = repository
+        .find_commit(rebased[before.len() + squashed.len() - 1])?
+        .tree()?;
+    let message = std::iter::once(&target_commit)
+        .chain(squashed.iter().filter(|commit| commit.id() != target_id))
+        .map(|commit| render_message(commit, message_template))
+        .filter(|message| !message.is_empty())
+        .join("\n\n");
+    let squashed_id = repository
+        .commit_with_signature(
+            None,
+            &target_commit.author(),
+            &target_commit.committer(),
+            &message,
+            &squashed_tree,
+            &[&squashed_parent],
+            // use the target's headers
+            target_commit.gitbutler_headers(),
+        )
+        .context("failed to commit")?;
+
+    let ids_to_rebase = rebased[before.len() + squashed.len()..]
+        .iter()
+        .rev()
+        .copied()
+        .collect_vec();
+    let new_head = if ids_to_rebase.is_empty() {
+        squashed_id
+    } else {
+        cherry_rebase_group(
+            repository,
+            squashed_id,
+            &ids_to_rebase,
+            auto_resolve_whitespace,
+        )?
+    };
+
+    Ok(SquashResult {
+        new_head,
+        squashed_id,
+    })
+}
+
+/// Substitute the placeholders of `template` with the parts of the message of `commit` in a single
+/// pass, so placeholders that appear in the message itself are left alone.
+fn render_message(commit: &git2::Commit, template: &str) -> String {
+    let message = commit.message_bstr().to_str_lossy();
+    let (subject, body) = message.split_once('\n').unwrap_or((&*message, ""));
+    let placeholders = [
+        ("{subject}", subject),
+        ("{body}", body),
+        ("{message}", &*message),
+    ];
+
+    let mut rendered = String::with_capacity(template.len() + message.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let (before, from_brace) = rest.split_at(start);
+        rendered.push_str(before);
+        match placeholders
+            .iter()
+            .find(|(placeholder, _)| from_brace.starts_with(placeholder))
+        {
+            Some((placeholder, value)) => {
+                rendered.push_str(value.trim());
+                rest = &from_brace[placeholder.len()..];
+            }
+            None => {
+                rendered.push('{');
+                rest = &from_brace[1..];
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered.trim().to_owned()
+}
+
+fn subject(commit: &git2::Commit) -> String {
+    commit
+        .message_bstr()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_str_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use gitbutler_testsupport::testing_repository::{
+        assert_commit_tree_matches, TestingRepository,
+    };
+
+    use super::*;
+
+    #[test]
+    fn squashes_selection_into_target() {
+        let test_repository = TestingRepository::open();
+        let repository = &test_repository.repository;
+
+        let base = test_repository.commit_tree(None, &[("foo.txt", "0")]);
+        let add_foo = test_repository.commit_on(&base, "change foo", &[("foo.txt", "1")]);
+        let add_bar =
+            test_repository.commit_on(&add_foo, "add bar", &[("foo.txt", "1"), ("bar.txt", "1")]);
+        let change_foo = test_repository.commit_on(
+            &add_bar,
+            "change foo again\n\nwith a body",
+            &[("foo.txt", "2"), ("bar.txt", "1")],
+        );
+        let add_baz = test_repository.commit_on(
+            &change_foo,
+            "add baz",
+            &[("foo.txt", "2"), ("bar.txt", "1"), ("baz.txt", "1")],
+        );
+
+        let SquashResult {
+            new_head,
+            squashed_id,
+        } = inner_squash_commits(
+            repository,
+            add_baz.id(),
+            base.id(),
+            add_foo.id(),
+            &[change_foo.id()],
+            "* {subject}",
+            false,
+        )
+        .unwrap();
+
+        let squashed = repository.find_commit(squashed_id).unwrap();
+        assert_eq!(
+            squashed.message(),
+            Some("* change foo\n\n* change foo again")
+        );
+        assert_eq!(squashed.parent_id(0).unwrap(), base.id());
+        assert_commit_tree_matches(repository, &squashed, &[("foo.txt", b"2")]);
+        assert!(squashed.tree().unwrap().get_name("bar.txt").is_none());
+
+        let new_head = repository.find_commit(new_head).unwrap();
+        assert_eq!(new_head.message(), Some("add baz"));
+        assert_commit_tree_matches(
+            repository,
+            &new_head,
+            &[("foo.txt", b"2"), ("bar.txt", b"1"), ("baz.txt", b"1")],
+        );
+        let moved_bar = new_head.parent(0).unwrap();
+        assert_eq!(moved_bar.message(), Some("add bar"));
+        assert_eq!(moved_bar.parent_id(0).unwrap(), squashed_id);
+    }
+
+    #[test]
+    fn squashes_into_a_later_target() {
+        let test_repository = TestingRepository::open();
+        let repository = &test_repository.repository;
+
+        let base = test_repository.commit_tree(None, &[("foo.txt", "0")]);
+        let add_foo = test_repository.commit_on(&base, "change foo", &[("foo.txt", "1")]);
+        let add_bar =
+            test_repository.commit_on(&add_foo, "add bar", &[("foo.txt", "1"), ("bar.txt", "1")]);
+        let add_baz = test_repository.commit_on(
+            &add_bar,
+            "add baz",
+            &[("foo.txt", "1"), ("bar.txt", "1"), ("baz.txt", "1")],
+        );
+
+        let SquashResult {
+            new_head,
+            squashed_id,
+        } = inner_squash_commits(
+            repository,
+            add_baz.id(),
+            base.id(),
+            add_baz.id(),
+            &[add_foo.id()],
+            DEFAULT_MESSAGE_TEMPLATE,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(new_head, squashed_id, "the target was the head");
+        let squashed = repository.find_commit(squashed_id).unwrap();
+        assert_eq!(squashed.message(), Some("add baz\n\nchange foo"));
+        assert_commit_tree_matches(
+            repository,
+            &squashed,
+            &[("foo.txt", b"1"), ("bar.txt", b"1"), ("baz.txt", b"1")],
+        );
+        let moved_bar = squashed.parent(0).unwrap();
+        assert_eq!(moved_bar.message(), Some("add bar"));
+        assert_commit_tree_matches(
+            repository,
+            &moved_bar,
+            &[("foo.txt", b"0"), ("bar.txt", b"1")],
+        );
+        assert_eq!(moved_bar.parent_id(0).unwrap(), base.id());
+    }
+
+    #[test]
+    fn placeholders_are_substituted_once() {
+        let test_repository = TestingRepository::open();
+        let commit = test_repository.commit_tree_with_message(
+            None,
+            "use {body} literally\n\nand {subject} too",
+            &[("foo.txt", "0")],
+        );
+
+        assert_eq!(
+            render_message(&commit, "{subject} ({body}) {unknown}"),
+            "use {body} literally (and {subject} too) {unknown}"
+        );
+        assert_eq!(
+            render_message(&commit, "{message}"),
+            "use {body} literally\n\nand {subject} too"
+        );
+    }
+}
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use anyhow::{Context, Result};
 use gitbutler_command_context::CommandContext;
 use gitbutler_commit::commit_ext::CommitExt;
+use gitbutler_forge::forge::MergeQueueStatus;
 use gitbutler_oplog::entry::{OperationKind, SnapshotDetails};
 use gitbutler_oplog::{OplogExt, SnapshotExt};
 use gitbutler_project::Project;
@@ -16,7 +17,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     actions::open_with_verify,
     commit::{commit_to_vbranch_commit, VirtualBranchCommit},
-    r#virtual::{CommitData, IsCommitIntegrated, PatchSeries},
+    r#virtual::{assure_not_draft, CommitData, IsCommitIntegrated, PatchSeries},
     VirtualBranchesExt,
 };
 use gitbutler_operating_modes::assure_open_workspace_mode;
@@ -55,6 +56,7 @@ pub fn create_series(
                 description: req.description,
                 forge_id: Default::default(),
                 archived: Default::default(),
+                merge_queue: Default::default(),
             },
             req.preceding_head,
         )
@@ -152,6 +154,7 @@ pub fn update_series_description(
 ///  - The series does not exist
 ///  - The stack cant be found
 ///  - The stack has not been initialized
+///  - A forge identifier is set on a draft stack and `allow_draft` isn't set
 ///  - The project is not in workspace mode
 ///  - Persisting the changes failed
 pub fn update_series_forge_id(
@@ -159,6 +162,7 @@ pub fn update_series_forge_id(
     stack_id: StackId,
     head_name: String,
     forge_id: Option<ForgeIdentifier>,
+    allow_draft: bool,
 ) -> Result<()> {
     let ctx = &open_with_verify(project)?;
     let mut guard = project.exclusive_worktree_access();
@@ -168,16 +172,49 @@ pub fn update_series_forge_id(
     );
     assure_open_workspace_mode(ctx).context("Requires an open workspace mode")?;
     let mut stack = ctx.project().virtual_branches().get_branch(stack_id)?;
+    if forge_id.is_some() {
+        assure_not_draft(&stack, allow_draft)?;
+    }
     stack.set_forge_id(ctx, &head_name, forge_id)
 }
 
+/// Records the merge queue status of a series, as reported by the forge.
+/// No snapshot is created as this reflects the state of the forge, not a change made by the user.
+///
+/// # Errors
+/// This method will return an error if:
+///  - The series does not exist
+///  - The stack cant be found
+///  - The stack has not been initialized
+///  - The project is not in workspace mode
+///  - Persisting the changes failed
+pub fn update_series_merge_queue_status(
+    project: &Project,
+    stack_id: StackId,
+    head_name: String,
+    status: Option<MergeQueueStatus>,
+) -> Result<()> {
+    let ctx = &open_with_verify(project)?;
+    let _guard = project.exclusive_worktree_access();
+    assure_open_workspace_mode(ctx).context("Requires an open workspace mode")?;
+    let mut stack = ctx.project().virtual_branches().get_branch(stack_id)?;
+    stack.set_merge_queue_status(ctx, &head_name, status)
+}
+
 /// Pushes all series in the stack to the remote.
-/// This operation will error out if the target has no push remote configured.
-pub fn push_stack(project: &Project, branch_id: StackId, with_force: bool) -> Result<()> {
+/// This operation will error out if the target has no push remote configured,
+/// if the stack is a draft and `allow_draft` isn't set, or if the policy of the project forbids it.
+pub fn push_stack(
+    project: &Project,
+    branch_id: StackId,
+    with_force: bool,
+    allow_draft: bool,
+) -> Result<()> {
     let ctx = &open_with_verify(project)?;
     assure_open_workspace_mode(ctx).context("Requires an open workspace mode")?;
     let state = ctx.project().virtual_branches();
     let stack = state.get_branch(branch_id)?;
+    assure_not_draft(&stack, allow_draft)?;
 
     let repo = ctx.repository();
     let default_target = state.get_default_target()?;
@@ -198,6 +235,7 @@ pub fn push_stack(project: &Project, branch_id: StackId, with_force: bool) -> Re
     let mut graph = gix_repo.revision_graph(cache.as_ref());
     let mut check_commit = IsCommitIntegrated::new(ctx, &default_target, &gix_repo, &mut graph)?;
     let stack_series = stack.list_series(ctx)?;
+    let mut heads_to_push = Vec::new();
     for series in stack_series {
         if series.local_commits.is_empty() {
             // Nothing to push for this one
@@ -211,7 +249,12 @@ pub fn push_stack(project: &Project, branch_id: StackId, with_force: bool) -> Re
             // Already integrated, nothing to push
             continue;
         }
-        let push_details = stack.push_details(ctx, series.head.name)?;
+        heads_to_push.push(series.head.name);
+    }
+    // Check all heads first, so the stack isn't pushed only partially.
+    crate::policy::assure_push_allowed(ctx, &stack, heads_to_push.iter().map(String::as_str))?;
+    for head_name in heads_to_push {
+        let push_details = stack.push_details(ctx, head_name)?;
         ctx.push(
             push_details.head,
             &push_details.remote_refname,
@@ -326,6 +369,7 @@ pub(crate) fn stack_series(
             upstream_patches,
             forge_id: series.head.forge_id,
             archived: series.head.archived,
+            merge_queue: series.head.merge_queue,
         });
     }
     api_series.reverse();
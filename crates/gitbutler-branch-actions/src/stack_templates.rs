@@ -0,0 +1,254 @@
+//! Named templates for stacks with a standardized structure, committed to `.gitbutler/stack-templates.toml`
+//! in the worktree so the whole team shares them.
+//!
+//! A template lists its series from the bottom of the stack to the top, each with placeholder commits
+//! to fill in later. `{name}` is replaced with the name given when creating a stack from the template:
+//!
+//! ```toml
+//! [[template]]
+//! name = "migration + code + docs"
+//!
+//! [[template.series]]
+//! name = "{name}-migration"
+//! commits = ["Migrate the schema for {name}"]
+//!
+//! [[template.series]]
+//! name = "{name}-code"
+//! description = "The implementation, based on the migration"
+//! commits = ["Implement {name}", "Test {name}"]
+//!
+//! [[template.series]]
+//! name = "{name}-docs"
+//! commits = ["Document {name}"]
+//! ```
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_cherry_pick::RepositoryExt as _;
+use gitbutler_command_context::CommandContext;
+use gitbutler_error::error::Code;
+use gitbutler_operating_modes::assure_open_workspace_mode;
+use gitbutler_oplog::{
+    entry::{OperationKind, SnapshotDetails, Trailer},
+    OplogExt,
+};
+use gitbutler_project::Project;
+use gitbutler_reference::normalize_branch_name;
+use gitbutler_repo_actions::RepoActionsExt;
+use gitbutler_stack::{PatchReferenceUpdate, Stack, StackId};
+use serde::{Deserialize, Serialize};
+
+use crate::{actions::open_with_verify, branch_manager::BranchManagerExt, VirtualBranchesExt};
+
+/// The location of the templates file, relative to the root of the worktree.
+pub const STACK_TEMPLATES_FILE: &str = ".gitbutler/stack-templates.toml";
+
+/// The placeholder for the name of the stack in templates.
+const NAME_PLACEHOLDER: &str = "{name}";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    rename_all(serialize = "camelCase", deserialize = "snake_case"),
+    deny_unknown_fields
+)]
+pub struct StackTemplate {
+    pub name: String,
+    /// The series of the stack, the bottom-most first.
+    pub series: Vec<SeriesTemplate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    rename_all(serialize = "camelCase", deserialize = "snake_case"),
+    deny_unknown_fields
+)]
+pub struct SeriesTemplate {
+    /// The name of the series, which becomes its branch name.
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The messages of the empty placeholder commits of the series, the oldest first.
+    #[serde(default)]
+    pub commits: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StackTemplates {
+    #[serde(default)]
+    template: Vec<StackTemplate>,
+}
+
+/// Read the stack templates of the worktree at `worktree_dir`, which are none if there is no templates file.
+pub fn load(worktree_dir: &Path) -> Result<Vec<StackTemplate>> {
+    let path = worktree_dir.join(STACK_TEMPLATES_FILE);
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read {STACK_TEMPLATES_FILE}"))
+        }
+    };
+    parse(&contents)
+        .with_context(|| format!("{STACK_TEMPLATES_FILE} is invalid"))
+        .context(Code::Validation)
+}
+
+fn parse(contents: &str) -> Result<Vec<StackTemplate>> {
+    let templates = toml::from_str::<StackTemplates>(contents)?.template;
+    for template in &templates {
+        if template.series.is_empty() {
+            bail!("template '{}' has no series", template.name);
+        }
+    }
+    Ok(templates)
+}
+
+/// Create a new stack called `name` with the series and placeholder commits of the template called `template_name`.
+///
+/// The names of all series are checked against the policy of the project first, and if creating any of them fails,
+/// the stack is removed again instead of being left half-built.
+pub fn create_stack_from_template(
+    project: &Project,
+    template_name: &str,
+    name: &str,
+) -> Result<StackId> {
+    let ctx = &open_with_verify(project)?;
+    assure_open_workspace_mode(ctx).context("Requires an open workspace mode")?;
+    let template = load(&project.worktree_path())?
+        .into_iter()
+        .find(|template| template.name == template_name)
+        .with_context(|| format!("There is no stack template named '{template_name}'"))
+        .context(Code::Validation)?;
+    let series_names = template
+        .series
+        .iter()
+        .map(|series| normalize_branch_name(&series.name.replace(NAME_PLACEHOLDER, name)))
+        .collect::<Result<Vec<_>>>()?;
+    crate::policy::assure_branch_names_allowed(ctx, series_names.iter().map(String::as_str))?;
+
+    let mut guard = project.exclusive_worktree_access();
+    let _ = ctx.project().create_snapshot(
+        SnapshotDetails::new(OperationKind::CreateBranch).with_trailers(vec![Trailer {
+            key: "name".to_string(),
+            value: name.to_string(),
+        }]),
+        guard.write_permission(),
+    );
+    let mut stack = ctx.branch_manager().create_virtual_branch(
+        &BranchCreateRequest {
+            name: Some(name.to_owned()),
+            ..Default::default()
+        },
+        guard.write_permission(),
+    )?;
+    if let Err(err) = instantiate(ctx, &mut stack, &template, &series_names, name) {
+        if let Err(rollback_err) = remove(ctx, &stack) {
+            tracing::warn!("failed to remove the partially created stack: {rollback_err:#}");
+        }
+        return Err(err);
+    }
+    crate::integration::update_workspace_commit(&ctx.project().virtual_branches(), ctx)
+        .context("failed to update gitbutler workspace")?;
+    Ok(stack.id)
+}
+
+/// Rename the only series of the new `stack` after the first series of `template`, then add the others on top,
+/// each with its placeholder commits. The series are named `series_names`, in the same order.
+fn instantiate(
+    ctx: &CommandContext,
+    stack: &mut Stack,
+    template: &StackTemplate,
+    series_names: &[String],
+    name: &str,
+) -> Result<()> {
+    let repo = ctx.repository();
+    let expand = |value: &str| value.replace(NAME_PLACEHOLDER, name);
+    for (index, (series, series_name)) in template.series.iter().zip(series_names).enumerate() {
+        let description = series.description.as_deref().map(expand);
+        if index == 0 {
+            let initial_head = stack
+                .heads
+                .last()
+                .context("Stack is in an invalid state - heads list is empty")?
+                .name
+                .clone();
+            stack.update_series(
+                ctx,
+                initial_head,
+                &PatchReferenceUpdate {
+                    name: Some(series_name.clone()),
+                    description: Some(description),
+                    ..Default::default()
+                },
+            )?;
+        } else {
+            stack.add_series_top_of_stack(ctx, series_name.clone(), description)?;
+        }
+
+        for message in &series.commits {
+            let head = repo.find_commit(stack.head())?;
+            let tree = repo.find_real_tree(&head, Default::default())?;
+            let placeholder = ctx.commit(&expand(message), &tree, &[&head], None)?;
+            stack.set_stack_head(ctx, placeholder, None)?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove the new `stack` entirely, which only has placeholder commits and nothing in the worktree.
+fn remove(ctx: &CommandContext, stack: &Stack) -> Result<()> {
+    let vb_state = ctx.project().virtual_branches();
+    ctx.delete_branch_reference(stack)?;
+    vb_state.delete_branch_entry(&stack.id)?;
+    crate::r#virtual::ensure_selected_for_changes(&vb_state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_templates() -> Result<()> {
+        let templates = parse(
+            r#"
+            [[template]]
+            name = "feature"
+
+            [[template.series]]
+            name = "{name}-migration"
+            commits = ["Migrate {name}"]
+
+            [[template.series]]
+            name = "{name}-code"
+            description = "The implementation"
+            "#,
+        )?;
+        assert_eq!(
+            templates,
+            vec![StackTemplate {
+                name: "feature".into(),
+                series: vec![
+                    SeriesTemplate {
+                        name: "{name}-migration".into(),
+                        description: None,
+                        commits: vec!["Migrate {name}".into()],
+                    },
+                    SeriesTemplate {
+                        name: "{name}-code".into(),
+                        description: Some("The implementation".into()),
+                        commits: vec![],
+                    },
+                ],
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn templates_need_series() {
+        assert!(parse("[[template]]\nname = \"empty\"\nseries = []").is_err());
+        assert!(parse("[[template]]\nname = \"typo\"\nserie = []").is_err());
+    }
+}
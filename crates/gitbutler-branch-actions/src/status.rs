@@ -1,14 +1,17 @@
 use std::collections::HashSet;
-use std::{collections::HashMap, path::PathBuf, vec};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    vec,
+};
 
-use crate::file::list_virtual_commit_files;
 use crate::integration::get_workspace_head;
 use crate::BranchStatus;
 use crate::{
     conflicts::RepoConflictsExt,
     file::{virtual_hunks_into_virtual_files, VirtualBranchFile},
     hunk::{file_hunks_from_diffs, VirtualBranchHunk},
-    BranchManagerExt, VirtualBranchesExt,
+    ranges_cache, BranchManagerExt, VirtualBranchesExt,
 };
 use anyhow::{bail, Context, Result};
 use git2::Tree;
@@ -17,11 +20,11 @@ use gitbutler_cherry_pick::RepositoryExt as _;
 use gitbutler_command_context::CommandContext;
 use gitbutler_diff::{diff_files_into_hunks, GitHunk, Hunk, HunkHash};
 use gitbutler_hunk_dependency::{
-    compute_hunk_locks, HunkDependencyOptions, HunkLock, InputCommit, InputDiff, InputFile,
-    InputStack,
+    changed_columns, file_scopes, owned_path_hunk_locks, scoped_hunk_locks, HunkLock, InputCommit,
+    InputDiff, InputFile, InputStack, RangeKind, Scope,
 };
 use gitbutler_operating_modes::assure_open_workspace_mode;
-use gitbutler_project::access::WorktreeWritePermission;
+use gitbutler_project::{access::WorktreeWritePermission, feature_flags::FeatureFlag};
 use gitbutler_repo::{LogUntil, RepositoryExt as _};
 use gitbutler_stack::{BranchOwnershipClaims, OwnershipClaim, Stack, StackId};
 use itertools::Itertools;
@@ -98,11 +101,14 @@ pub fn get_applied_status_cached(
     let vb_state = ctx.project().virtual_branches();
     let default_target = vb_state.get_default_target()?;
 
-    let locks = if ctx.project().use_experimental_locking {
+    let locks = if ctx
+        .project()
+        .feature_enabled(FeatureFlag::ExperimentalLocking)
+    {
         compute_locks(
             ctx,
-            &workspace_head,
             &default_target.sha,
+            workspace_head,
             &base_diffs,
             &virtual_branches,
         )?
@@ -258,85 +264,311 @@ pub fn get_applied_status_cached(
     })
 }
 
+/// Compute the locks of the uncommitted hunks in `base_diffs` from the ranges of `stacks`, which
+/// are [cached](ranges_cache) as long as the stacks don't change.
+///
+/// With [`FeatureFlag::ScopeLocking`], the scopes of the files in `workspace_head`, which the
+/// uncommitted hunks apply to, keep hunks from being locked to commits of other scopes.
 fn compute_locks(
     ctx: &CommandContext,
-    workspace_head: &git2::Oid,
     target_sha: &git2::Oid,
+    workspace_head: git2::Oid,
     base_diffs: &BranchStatus,
-    stacks: &Vec<Stack>,
+    stacks: &[Stack],
 ) -> Result<HashMap<HunkHash, Vec<HunkLock>>> {
+    let ranges = ranges_cache::workspace_ranges(ctx, target_sha, stacks)?;
     let repo = ctx.repository();
-    let base_commit = repo.find_commit(*target_sha)?;
-    let workspace_commit = repo.find_commit(*workspace_head)?;
-
-    let diff = &ctx.repository().diff_tree_to_tree(
-        Some(&base_commit.tree()?),
-        Some(&workspace_commit.tree()?),
-        None,
-    )?;
+    let workspace_tree = if ctx.project().feature_enabled(FeatureFlag::ScopeLocking) {
+        Some(repo.find_commit(workspace_head)?.tree()?)
+    } else {
+        None
+    };
+    // Hunks go to the stack that claims them, or to the default one like below.
+    let max_selected_for_changes = stacks
+        .iter()
+        .filter_map(|stack| stack.selected_for_changes)
+        .max();
+    let default_stack = stacks
+        .iter()
+        .find(|stack| stack.selected_for_changes == max_selected_for_changes)
+        .or(stacks.first())
+        .map(|stack| stack.id);
+    Ok(base_diffs
+        .iter()
+        .flat_map(|(path, hunks)| {
+            let stack_of = |hunk: &GitHunk| {
+                stacks
+                    .iter()
+                    .find(|stack| {
+                        stack.ownership.claims.iter().any(|claim| {
+                            claim.file_path == *path
+                                && claim.hunks.iter().any(|claimed| claimed.intersects(hunk))
+                        })
+                    })
+                    .map(|stack| stack.id)
+                    .or(default_stack)
+            };
+            match workspace_tree
+                .as_ref()
+                .and_then(|tree| path_scopes(repo, tree, path))
+            {
+                Some(scopes) => {
+                    scoped_hunk_locks(&ranges, path, hunks, &scopes, stack_of).collect_vec()
+                }
+                None => owned_path_hunk_locks(&ranges, path, hunks, stack_of).collect_vec(),
+            }
+        })
+        .collect())
+}
 
-    let files_touched_by_commits = diff
-        .deltas()
-        .filter_map(|d| d.new_file().path())
-        .map(|c| c.to_path_buf())
-        .unique()
-        .sorted()
-        .collect::<HashSet<_>>();
-    let files_touched_by_diffs = base_diffs.keys().cloned().collect::<HashSet<_>>();
-
-    let touched_by_both = files_touched_by_commits
-        .intersection(&files_touched_by_diffs)
-        .cloned()
-        .collect_vec();
+/// Return the scopes of the file at `path` in `tree`, if it exists and its language is supported.
+fn path_scopes(repo: &git2::Repository, tree: &Tree, path: &Path) -> Option<Vec<Scope>> {
+    let blob = tree
+        .get_path(path)
+        .ok()?
+        .to_object(repo)
+        .ok()?
+        .peel_to_blob()
+        .ok()?;
+    file_scopes(path, blob.content())
+}
 
+/// Return the commits of `stacks` on top of `target_sha` as input for computing hunk dependencies.
+/// If `paths` is set, only the files with these paths at the top of their stack are included.
+#[instrument(level = tracing::Level::DEBUG, skip(ctx, stacks, paths), fields(stacks = stacks.len()))]
+pub(crate) fn hunk_dependency_input(
+    ctx: &CommandContext,
+    target_sha: &git2::Oid,
+    stacks: &[Stack],
+    paths: Option<&HashSet<PathBuf>>,
+) -> Result<Vec<InputStack>> {
+    let repo = ctx.repository();
+    let detect_renames = ctx.project().feature_enabled(FeatureFlag::RenameTracking);
+    let track_columns = ctx.project().feature_enabled(FeatureFlag::ColumnLocking);
     let mut stacks_input: Vec<InputStack> = vec![];
     for stack in stacks {
         let mut commits_input: Vec<InputCommit> = vec![];
-        // Commit IDs in application order
+        // Maps the paths files had before being renamed by a later commit to the paths they have
+        // at the top of the stack, so their earlier changes are kept along with the later ones.
+        let mut aliases: HashMap<PathBuf, PathBuf> = HashMap::new();
+        // Commit IDs from the top of the stack, to learn about renames before the commits they
+        // affect.
         let commit_ids = repo
             .l(stack.head(), LogUntil::Commit(*target_sha), false)
-            .context("failed to list commits")?
-            .into_iter()
-            .rev()
-            .collect_vec();
+            .context("failed to list commits")?;
 
         for commit_id in commit_ids {
-            let mut files_input: Vec<InputFile> = vec![];
+            let span = tracing::trace_span!(
+                "commit_input_files",
+                %commit_id,
+                files = tracing::field::Empty,
+                diffs = tracing::field::Empty
+            )
+            .entered();
             let commit = repo.find_commit(commit_id)?;
-            let files = list_virtual_commit_files(ctx, &commit, false)?;
+            let files =
+                commit_input_files(repo, &commit, target_sha, detect_renames, track_columns)?;
+            span.record("files", files.len());
+            span.record(
+                "diffs",
+                files.iter().map(|file| file.diffs.len()).sum::<usize>(),
+            );
+            let mut files_input: Vec<InputFile> = vec![];
+            let mut renames = vec![];
             for file in files {
-                if touched_by_both.contains(&file.path) {
-                    let value = InputFile {
-                        path: file.path,
-                        diffs: file
-                            .hunks
-                            .iter()
-                            .map(|hunk| InputDiff {
-                                old_start: hunk.old_start,
-                                old_lines: hunk.old_lines,
-                                new_start: hunk.start,
-                                new_lines: hunk.end - hunk.start,
-                            })
-                            .collect::<Vec<_>>(),
-                    };
-                    files_input.push(value);
+                let top_path = aliases.get(&file.path).unwrap_or(&file.path).clone();
+                if let Some(old_path) = &file.old_path {
+                    renames.push((old_path.clone(), top_path.clone()));
+                }
+                if paths.map_or(true, |paths| paths.contains(&top_path)) {
+                    files_input.push(file);
                 }
             }
+            aliases.extend(renames);
             commits_input.push(InputCommit {
                 commit_id,
                 files: files_input,
             });
         }
+        // Back to application order.
+        commits_input.reverse();
         stacks_input.push(InputStack {
             stack_id: stack.id,
             commits: commits_input,
         });
     }
+    Ok(stacks_input)
+}
 
-    compute_hunk_locks(HunkDependencyOptions {
-        workdir: base_diffs,
-        stacks: stacks_input,
-    })
+/// Return the files changed by `commit` and their diffs. If `detect_renames` is set, renames are
+/// detected so the ranges of a file can follow it to its new path. If `track_columns` is set, the
+/// diffs replacing a single line also have the [columns](InputDiff::columns) they changed.
+///
+/// Merge commits are diffed against their first parent. If they merge parents which are part of the
+/// target, the diffs outside of the lines the merge changed compared to them are marked as
+/// [merged](InputDiff::merged), as the workspace is based on these changes already.
+fn commit_input_files(
+    repo: &git2::Repository,
+    commit: &git2::Commit,
+    target_sha: &git2::Oid,
+    detect_renames: bool,
+    track_columns: bool,
+) -> Result<Vec<InputFile>> {
+    if commit.parent_count() == 0 {
+        return Ok(vec![]);
+    }
+    let parent = commit.parent(0).context("failed to get parent commit")?;
+    let commit_tree = repo
+        .find_real_tree(commit, Default::default())
+        .context("failed to get commit tree")?;
+    let parent_tree = repo
+        .find_real_tree(&parent, Default::default())
+        .context("failed to get parent tree")?;
+
+    let mut diff_opts = git2::DiffOptions::new();
+    // Submodule bumps are kept, as commits that bump the same submodule depend on each other.
+    diff_opts
+        .show_binary(true)
+        .ignore_submodules(false)
+        .context_lines(0);
+    let mut diff =
+        repo.diff_tree_to_tree(Some(&parent_tree), Some(&commit_tree), Some(&mut diff_opts))?;
+    if detect_renames {
+        diff.find_similar(Some(git2::DiffFindOptions::new().renames(true)))?;
+    }
+
+    let conflicts = target_merge_conflicts(repo, commit, &commit_tree, target_sha)?;
+
+    let old_paths: HashMap<PathBuf, PathBuf> = diff
+        .deltas()
+        .filter(|delta| delta.status() == git2::Delta::Renamed)
+        .filter_map(|delta| {
+            Some((
+                delta.new_file().path()?.to_owned(),
+                delta.old_file().path()?.to_owned(),
+            ))
+        })
+        .collect();
+    let submodules: HashSet<PathBuf> = diff
+        .deltas()
+        .filter(|delta| {
+            delta.old_file().mode() == git2::FileMode::Commit
+                || delta.new_file().mode() == git2::FileMode::Commit
+        })
+        .filter_map(|delta| {
+            delta
+                .new_file()
+                .path()
+                .or_else(|| delta.old_file().path())
+                .map(|path| path.to_owned())
+        })
+        .collect();
+    Ok(gitbutler_diff::hunks_by_filepath(None, &diff)?
+        .into_iter()
+        .map(|(path, file)| {
+            let old_path = old_paths.get(&path).cloned();
+            let conflicts = conflicts
+                .as_ref()
+                .map(|conflicts| conflicts.get(&path).map_or(&[][..], Vec::as_slice));
+            let kind = if submodules.contains(&path) {
+                RangeKind::Submodule
+            } else {
+                RangeKind::Lines
+            };
+            InputFile {
+                diffs: file
+                    .hunks
+                    .iter()
+                    // A rename without changes to the content has no actual hunks.
+                    .filter(|hunk| old_path.is_none() || hunk.old_lines + hunk.new_lines > 0)
+                    .map(|hunk| InputDiff {
+                        old_start: hunk.old_start,
+                        old_lines: hunk.old_lines,
+                        new_start: hunk.new_start,
+                        new_lines: hunk.new_lines,
+                        merged: conflicts.is_some_and(|conflicts| {
+                            !conflicts.iter().any(|conflict| {
+                                overlaps((hunk.new_start, hunk.new_lines), *conflict)
+                            })
+                        }),
+                        kind,
+                        columns: (track_columns && hunk.old_lines == 1 && hunk.new_lines == 1)
+                            .then(|| changed_columns(&hunk.diff_lines))
+                            .flatten()
+                            .map(|changed| changed.new),
+                    })
+                    .collect(),
+                path,
+                old_path,
+            }
+        })
+        .collect())
+}
+
+/// If `commit` merges parents which are part of the target, return the lines of each file it
+/// changed compared to all of them, as `(start, lines)` in the commit. These are conflicts the merge
+/// resolved or changes it made itself, whereas all other lines it changed compared to its first
+/// parent were merged from the target.
+fn target_merge_conflicts(
+    repo: &git2::Repository,
+    commit: &git2::Commit,
+    commit_tree: &git2::Tree,
+    target_sha: &git2::Oid,
+) -> Result<Option<HashMap<PathBuf, Vec<(u32, u32)>>>> {
+    let mut conflicts: Option<HashMap<PathBuf, Vec<(u32, u32)>>> = None;
+    for parent in commit.parents().skip(1) {
+        let in_target = parent.id() == *target_sha
+            || repo
+                .graph_descendant_of(*target_sha, parent.id())
+                .context("failed to check if merged parent is in target")?;
+        if !in_target {
+            continue;
+        }
+        let parent_tree = repo
+            .find_real_tree(&parent, Default::default())
+            .context("failed to get merged parent tree")?;
+        let mut diff_opts = git2::DiffOptions::new();
+        diff_opts
+            .show_binary(true)
+            .ignore_submodules(false)
+            .context_lines(0);
+        let diff =
+            repo.diff_tree_to_tree(Some(&parent_tree), Some(commit_tree), Some(&mut diff_opts))?;
+        let changed: HashMap<PathBuf, Vec<(u32, u32)>> =
+            gitbutler_diff::hunks_by_filepath(None, &diff)?
+                .into_iter()
+                .map(|(path, file)| {
+                    let ranges = file
+                        .hunks
+                        .iter()
+                        .map(|hunk| (hunk.new_start, hunk.new_lines))
+                        .collect();
+                    (path, ranges)
+                })
+                .collect();
+        conflicts = Some(match conflicts {
+            None => changed,
+            // With several merged parents, only lines that differ from all of them are conflicts.
+            Some(conflicts) => conflicts
+                .into_iter()
+                .filter_map(|(path, ranges)| {
+                    let other = changed.get(&path)?;
+                    let ranges: Vec<_> = ranges
+                        .into_iter()
+                        .filter(|range| other.iter().any(|other| overlaps(*range, *other)))
+                        .collect();
+                    (!ranges.is_empty()).then_some((path, ranges))
+                })
+                .collect(),
+        });
+    }
+    Ok(conflicts)
+}
+
+/// Whether the lines `(start, lines)` of `a` and `b` overlap or touch, which includes ranges of no
+/// lines, like deletions, at the same position.
+fn overlaps(a: (u32, u32), b: (u32, u32)) -> bool {
+    a.0 <= b.0 + b.1 && b.0 <= a.0 + a.1
 }
 
 fn compute_old_locks(
@@ -0,0 +1,204 @@
+//! Plain-language summaries of branches and commits, for screen readers and everywhere else
+//! a visual diff can't convey the gist of a change.
+use std::path::Path;
+
+use bstr::{BStr, ByteSlice};
+
+use crate::{RemoteBranchFile, VirtualBranch};
+
+/// The amount of changed lines in one file.
+struct FileChange<'a> {
+    path: &'a Path,
+    added: usize,
+    removed: usize,
+    binary: bool,
+}
+
+impl FileChange<'_> {
+    fn from_diffs<'a>(
+        path: &'a Path,
+        binary: bool,
+        diffs: impl IntoIterator<Item = &'a BStr>,
+    ) -> FileChange<'a> {
+        let mut change = FileChange {
+            path,
+            added: 0,
+            removed: 0,
+            binary,
+        };
+        for line in diffs.into_iter().flat_map(|diff| diff.lines()) {
+            match line.first() {
+                Some(b'+') => change.added += 1,
+                Some(b'-') => change.removed += 1,
+                _ => {}
+            }
+        }
+        change
+    }
+
+    fn size(&self) -> usize {
+        self.added + self.removed
+    }
+}
+
+/// Summarize `branch`, e.g. "Branch 'feature' has 2 commits, 1 of them not pushed yet.
+/// 3 files have uncommitted changes, with 10 lines added and 2 removed, mostly in src/main.rs. No conflicts."
+pub fn branch_summary(branch: &VirtualBranch) -> String {
+    let (commits, unpushed) = commit_counts(branch);
+    let mut summary = format!("Branch '{}' has {}", branch.name, count(commits, "commit"));
+    if unpushed == commits && commits > 0 {
+        summary.push_str(", none of them pushed yet");
+    } else if unpushed > 0 {
+        summary.push_str(&format!(", {unpushed} of them not pushed yet"));
+    }
+    summary.push_str(". ");
+
+    let changes: Vec<_> = branch
+        .files
+        .iter()
+        .map(|file| {
+            FileChange::from_diffs(
+                &file.path,
+                file.binary,
+                file.hunks.iter().map(|hunk| hunk.diff.as_bstr()),
+            )
+        })
+        .collect();
+    if changes.is_empty() {
+        summary.push_str("There are no uncommitted changes.");
+    } else {
+        summary.push_str(&format!(
+            "{} uncommitted changes, {}.",
+            if changes.len() == 1 {
+                "1 file has".to_owned()
+            } else {
+                format!("{} files have", changes.len())
+            },
+            describe_changes(&changes)
+        ));
+    }
+
+    summary.push_str(if branch.conflicted {
+        " It has conflicts which need to be resolved."
+    } else {
+        " No conflicts."
+    });
+    summary
+}
+
+/// Summarize the commit with `title` which changed `files`, e.g.
+/// "Commit 'Fix the parser' changes 2 files, with 4 lines added and 1 removed, mostly in src/parser.rs."
+pub fn commit_summary(title: &str, files: &[RemoteBranchFile]) -> String {
+    let changes: Vec<_> = files
+        .iter()
+        .map(|file| {
+            FileChange::from_diffs(
+                &file.path,
+                file.binary,
+                file.hunks.iter().map(|hunk| hunk.diff_lines.as_bstr()),
+            )
+        })
+        .collect();
+    if changes.is_empty() {
+        return format!("Commit '{title}' changes no files.");
+    }
+    format!(
+        "Commit '{title}' changes {}, {}.",
+        count(changes.len(), "file"),
+        describe_changes(&changes)
+    )
+}
+
+fn describe_changes(changes: &[FileChange<'_>]) -> String {
+    let added: usize = changes.iter().map(|change| change.added).sum();
+    let removed: usize = changes.iter().map(|change| change.removed).sum();
+    let mut description = format!("with {} added and {removed} removed", count(added, "line"));
+    let binary = changes.iter().filter(|change| change.binary).count();
+    if binary > 0 {
+        description.push_str(&format!(", and {} changed", count(binary, "binary file")));
+    }
+    if changes.len() > 1 {
+        if let Some(largest) = changes.iter().max_by_key(|change| change.size()) {
+            description.push_str(&format!(", mostly in {}", largest.path.display()));
+        }
+    }
+    description
+}
+
+/// Return the total amount of commits of `branch`, and how many of them aren't pushed.
+/// The series are the most accurate source, but are optional, so fall back to the commits.
+fn commit_counts(branch: &VirtualBranch) -> (usize, usize) {
+    if branch.series.is_empty() {
+        let unpushed = branch
+            .commits
+            .iter()
+            .filter(|commit| !commit.is_remote && !commit.is_integrated)
+            .count();
+        (branch.commits.len(), unpushed)
+    } else {
+        let patches = || branch.series.iter().flat_map(|series| &series.patches);
+        let unpushed = patches()
+            .filter(|commit| commit.remote_commit_id.is_none() && !commit.is_integrated)
+            .count();
+        (patches().count(), unpushed)
+    }
+}
+
+fn count(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("1 {noun}")
+    } else {
+        format!("{n} {noun}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gitbutler_diff::{ChangeType, GitHunk};
+
+    fn file(path: &str, diff: &str) -> RemoteBranchFile {
+        RemoteBranchFile {
+            path: path.into(),
+            hunks: vec![GitHunk {
+                old_start: 1,
+                old_lines: 1,
+                new_start: 1,
+                new_lines: 1,
+                diff_lines: diff.into(),
+                binary: false,
+                change_type: ChangeType::Modified,
+            }],
+            binary: false,
+            large: false,
+        }
+    }
+
+    #[test]
+    fn commit_with_multiple_files() {
+        let files = [
+            file("a.txt", "@@ -1,1 +1,1 @@\n-old\n+new\n"),
+            file(
+                "src/b.rs",
+                "@@ -1,1 +1,3 @@\n context\n+one\n+two\n+three\n",
+            ),
+        ];
+        assert_eq!(
+            commit_summary("Add things", &files),
+            "Commit 'Add things' changes 2 files, with 4 lines added and 1 removed, mostly in src/b.rs."
+        );
+    }
+
+    #[test]
+    fn commit_with_single_file() {
+        let files = [file("a.txt", "@@ -1,1 +1,1 @@\n+new\n")];
+        assert_eq!(
+            commit_summary("Add a line", &files),
+            "Commit 'Add a line' changes 1 file, with 1 line added and 0 removed."
+        );
+        assert_eq!(
+            commit_summary("Empty", &[]),
+            "Commit 'Empty' changes no files."
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! An index of the paths changed by the commits in the workspace and the recent history of the target,
+//! to find all commits that touched a path without diffing each of them on every lookup.
+//!
+//! As the changes of a commit never change, the index is a cache of them by commit id, persisted in
+//! `commit_touches.toml`. Commits that are new since the last lookup, e.g. after committing or
+//! fetching, are diffed and added, and those that are no longer reachable are dropped.
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use gitbutler_cherry_pick::RepositoryExt as _;
+use gitbutler_command_context::CommandContext;
+use gitbutler_repo::{LogUntil, RepositoryExt as _};
+use gitbutler_stack::StackId;
+use serde::{Deserialize, Serialize};
+
+use crate::VirtualBranchesExt;
+
+const TOUCHES_FILE_NAME: &str = "commit_touches.toml";
+
+/// How many commits of the target, starting at the one the workspace is based on, are indexed.
+const TARGET_HISTORY_LIMIT: usize = 1000;
+
+/// A commit which changed a path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitTouch {
+    #[serde(with = "gitbutler_serde::oid")]
+    pub commit_id: git2::Oid,
+    /// The stack of the workspace the commit is in, or `None` if it is part of the target.
+    pub stack_id: Option<StackId>,
+}
+
+/// The paths changed by each commit, keyed by their hex id.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TouchesIndex {
+    #[serde(default)]
+    commits: BTreeMap<String, Vec<PathBuf>>,
+}
+
+/// Return all commits in the workspace and in the recent history of the target which changed `path`,
+/// the commits of each stack first and the target last, each newest first.
+pub fn commits_touching(ctx: &CommandContext, path: &Path) -> Result<Vec<CommitTouch>> {
+    let commits = indexed_commits(ctx)?;
+    let index = update_index(ctx, &commits)?;
+    Ok(commits
+        .into_iter()
+        .filter(|(commit_id, _)| {
+            index
+                .commits
+                .get(&commit_id.to_string())
+                .is_some_and(|paths| paths.iter().any(|touched| touched == path))
+        })
+        .map(|(commit_id, stack_id)| CommitTouch {
+            commit_id,
+            stack_id,
+        })
+        .collect())
+}
+
+/// The commits to index along with the stack they are in, in the order lookups return them.
+fn indexed_commits(ctx: &CommandContext) -> Result<Vec<(git2::Oid, Option<StackId>)>> {
+    let repo = ctx.repository();
+    let vb_state = ctx.project().virtual_branches();
+    let target = vb_state.get_default_target()?;
+
+    let mut commits = Vec::new();
+    for stack in vb_state.list_branches_in_workspace()? {
+        let stack_commits = repo
+            .l(stack.head(), LogUntil::Commit(target.sha), false)
+            .context("failed to list commits")?;
+        commits.extend(
+            stack_commits
+                .into_iter()
+                .map(|commit_id| (commit_id, Some(stack.id))),
+        );
+    }
+    let target_commits = repo
+        .l(target.sha, LogUntil::Take(TARGET_HISTORY_LIMIT), false)
+        .context("failed to list target commits")?;
+    commits.extend(
+        target_commits
+            .into_iter()
+            .map(|commit_id| (commit_id, None)),
+    );
+    Ok(commits)
+}
+
+/// Add the changed paths of all `commits` that aren't indexed yet, drop those of commits not among them
+/// anymore, and persist the index if it changed.
+fn update_index(
+    ctx: &CommandContext,
+    commits: &[(git2::Oid, Option<StackId>)],
+) -> Result<TouchesIndex> {
+    let path = ctx.project().gb_dir().join(TOUCHES_FILE_NAME);
+    let mut index: TouchesIndex = gitbutler_fs::read_toml_file_or_default(&path)?;
+
+    let commit_ids: HashSet<String> = commits
+        .iter()
+        .map(|(commit_id, _)| commit_id.to_string())
+        .collect();
+    let indexed_count = index.commits.len();
+    index
+        .commits
+        .retain(|commit_id, _| commit_ids.contains(commit_id));
+    let mut changed = index.commits.len() != indexed_count;
+
+    let repo = ctx.repository();
+    for (commit_id, _) in commits {
+        let key = commit_id.to_string();
+        if index.commits.contains_key(&key) {
+            continue;
+        }
+        index.commits.insert(key, changed_paths(repo, *commit_id)?);
+        changed = true;
+    }
+
+    if changed {
+        let contents = toml::to_string(&index).context("Failed to serialize commit touches")?;
+        gitbutler_fs::write(&path, contents).context("Failed to write commit touches")?;
+    }
+    Ok(index)
+}
+
+/// The paths `commit_id` changed compared to its first parent.
+fn changed_paths(repo: &git2::Repository, commit_id: git2::Oid) -> Result<Vec<PathBuf>> {
+    let commit = repo.find_commit(commit_id)?;
+    let tree = repo.find_real_tree(&commit, Default::default())?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(repo.find_real_tree(&parent, Default::default())?),
+        Err(_) => None,
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+    let mut paths: Vec<PathBuf> = diff
+        .deltas()
+        .flat_map(|delta| [delta.old_file().path(), delta.new_file().path()])
+        .flatten()
+        .map(Path::to_path_buf)
+        .collect();
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
@@ -32,7 +32,12 @@ pub(crate) fn undo_commit(
     let UndoResult {
         new_head: new_head_commit,
         ownership_update,
-    } = inner_undo_commit(ctx.repository(), branch.head(), commit_oid)?;
+    } = inner_undo_commit(
+        ctx.repository(),
+        branch.head(),
+        commit_oid,
+        ctx.project().auto_resolve_whitespace,
+    )?;
 
     for ownership in ownership_update {
         branch.ownership.put(ownership);
@@ -58,6 +63,7 @@ fn inner_undo_commit(
     repository: &git2::Repository,
     branch_head_commit: git2::Oid,
     commit_to_remove: git2::Oid,
+    auto_resolve_whitespace: bool,
 ) -> Result<UndoResult> {
     let commit_to_remove = repository.find_commit(commit_to_remove)?;
 
@@ -109,6 +115,7 @@ fn inner_undo_commit(
         repository,
         commit_to_remove.parent_id(0)?,
         &commits_to_rebase,
+        auto_resolve_whitespace,
     )?;
 
     Ok(UndoResult {
@@ -140,7 +147,7 @@ mod test {
             let c = test_repository.commit_tree(Some(&a), &[("bar.txt", "baz")]);
 
             let conflicted_commit =
-                gitbutler_merge_commits(&test_repository.repository, b, c, "", "").unwrap();
+                gitbutler_merge_commits(&test_repository.repository, b, c, "", "", false).unwrap();
 
             // Branch looks like "A -> ConflictedCommit"
 
@@ -148,6 +155,7 @@ mod test {
                 &test_repository.repository,
                 conflicted_commit.id(),
                 conflicted_commit.id(),
+                false,
             );
 
             assert!(
@@ -167,7 +175,7 @@ mod test {
             let UndoResult {
                 new_head,
                 ownership_update,
-            } = inner_undo_commit(&test_repository.repository, c.id(), c.id()).unwrap();
+            } = inner_undo_commit(&test_repository.repository, c.id(), c.id(), false).unwrap();
 
             assert_eq!(new_head, b.id(), "The new head should be C's parent");
             assert_eq!(
@@ -206,7 +214,7 @@ mod test {
             let UndoResult {
                 new_head,
                 ownership_update,
-            } = inner_undo_commit(&test_repository.repository, c.id(), b.id()).unwrap();
+            } = inner_undo_commit(&test_repository.repository, c.id(), b.id(), false).unwrap();
 
             let new_head_commit: git2::Commit =
                 test_repository.repository.find_commit(new_head).unwrap();
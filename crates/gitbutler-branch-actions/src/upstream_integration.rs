@@ -1,6 +1,7 @@
 use anyhow::{anyhow, bail, Result};
 use gitbutler_cherry_pick::RepositoryExt as _;
 use gitbutler_command_context::CommandContext;
+use gitbutler_oplog::entry::OperationKind;
 use gitbutler_project::access::WorktreeWritePermission;
 use gitbutler_repo::{
     rebase::{cherry_rebase_group, gitbutler_merge_commits},
@@ -11,7 +12,11 @@ use gitbutler_stack::{Stack, StackId, Target, VirtualBranchesHandle};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    branch_trees::{checkout_branch_trees, compute_updated_branch_head, BranchHeadAndTree},
+    autostash::AutoStash,
+    branch_trees::{
+        checkout_branch_trees, compute_updated_branch_head, shift_claims_to_worktree,
+        BranchHeadAndTree,
+    },
     BranchManagerExt, VirtualBranchesExt as _,
 };
 
@@ -89,12 +94,14 @@ enum IntegrationResult {
 }
 
 pub struct UpstreamIntegrationContext<'a> {
-    _permission: Option<&'a mut WorktreeWritePermission>,
+    permission: Option<&'a mut WorktreeWritePermission>,
     repository: &'a git2::Repository,
     virtual_branches_in_workspace: Vec<Stack>,
     new_target: git2::Commit<'a>,
     old_target: git2::Commit<'a>,
     target_branch_name: String,
+    /// Whether conflicts whose sides only differ in whitespace are resolved, as configured for the project.
+    auto_resolve_whitespace: bool,
 }
 
 impl<'a> UpstreamIntegrationContext<'a> {
@@ -119,12 +126,13 @@ impl<'a> UpstreamIntegrationContext<'a> {
         let virtual_branches_in_workspace = virtual_branches_handle.list_branches_in_workspace()?;
 
         Ok(Self {
-            _permission: Some(permission),
+            permission: Some(permission),
             repository,
             new_target,
             old_target,
             virtual_branches_in_workspace,
             target_branch_name: target.branch.branch().to_string(),
+            auto_resolve_whitespace: command_context.project().auto_resolve_whitespace,
         })
     }
 }
@@ -211,7 +219,10 @@ pub fn upstream_integration_statuses(
 
                 // Identical trees will have the same Oid so we can compare
                 // the two
-                if tree_merge_index_tree == new_target_tree.id() {
+                // Branches waiting in a merge queue are only cleaned up once the queue merged them.
+                if tree_merge_index_tree == new_target_tree.id()
+                    && !virtual_branch.has_pending_merge_queue_entry()
+                {
                     return Ok((virtual_branch.id, BranchStatus::FullyIntegrated));
                 }
             }
@@ -233,7 +244,8 @@ pub(crate) fn integrate_upstream(
         .map(|r| (Some(r.target_commit_oid), Some(r.approach)))
         .unwrap_or((None, None));
 
-    let context = UpstreamIntegrationContext::open(command_context, target_commit_oid, permission)?;
+    let mut context =
+        UpstreamIntegrationContext::open(command_context, target_commit_oid, permission)?;
     let virtual_branches_state = VirtualBranchesHandle::new(command_context.project().gb_dir());
     let default_target = virtual_branches_state.get_default_target()?;
 
@@ -278,6 +290,18 @@ pub(crate) fn integrate_upstream(
         }
     }
 
+    // Changes that no branch will carry over are stashed, so checking out the result doesn't drop them.
+    let autostash = AutoStash::capture(
+        command_context,
+        OperationKind::UpdateWorkspaceBase,
+        &context.virtual_branches_in_workspace,
+        context
+            .permission
+            .as_deref_mut()
+            .expect("Permission provided above"),
+    )?;
+    // The claims of uncommitted changes refer to lines of the worktree as it is now.
+    let old_worktree = context.repository.create_wd_tree()?;
     let integration_results =
         compute_resolutions(&context, resolutions, base_branch_resolution_approach)?;
 
@@ -296,7 +320,7 @@ pub(crate) fn integrate_upstream(
             command_context.delete_branch_reference(&branch)?;
         }
 
-        let permission = context._permission.expect("Permission provided above");
+        let permission = context.permission.expect("Permission provided above");
 
         // Unapply branches
         for (branch_id, integration_result) in &integration_results {
@@ -339,7 +363,12 @@ pub(crate) fn integrate_upstream(
         } else {
             // Now that we've potentially updated the branch trees, lets checkout
             // the result of merging them all together.
-            checkout_branch_trees(command_context, permission)?;
+            let new_worktree = checkout_branch_trees(command_context, permission)?;
+            shift_claims_to_worktree(command_context, &old_worktree, &new_worktree)?;
+        }
+
+        if let Some(autostash) = autostash {
+            autostash.replay(command_context, permission)?;
         }
 
         virtual_branches_state.set_default_target(Target {
@@ -373,13 +402,19 @@ pub(crate) fn resolve_upstream_integration(
                 context.new_target,
                 &context.target_branch_name,
                 &context.target_branch_name,
+                context.auto_resolve_whitespace,
             )?;
 
             Ok(new_head.id())
         }
         BaseBranchResolutionApproach::Rebase => {
             let commits = repo.l(old_target_id, LogUntil::Commit(fork_point), false)?;
-            let new_head = cherry_rebase_group(repo, new_target_id, &commits)?;
+            let new_head = cherry_rebase_group(
+                repo,
+                new_target_id,
+                &commits,
+                context.auto_resolve_whitespace,
+            )?;
 
             Ok(new_head)
         }
@@ -397,6 +432,7 @@ fn compute_resolutions(
         old_target,
         virtual_branches_in_workspace,
         target_branch_name,
+        auto_resolve_whitespace,
         ..
     } = context;
 
@@ -429,6 +465,7 @@ fn compute_resolutions(
                         new_target.clone(),
                         &virtual_branch.name,
                         target_branch_name,
+                        *auto_resolve_whitespace,
                     )?;
 
                     // Get the updated tree oid
@@ -464,8 +501,12 @@ fn compute_resolutions(
                         false,
                     )?;
 
-                    let new_head =
-                        cherry_rebase_group(repository, new_target.id(), &virtual_branch_commits)?;
+                    let new_head = cherry_rebase_group(
+                        repository,
+                        new_target.id(),
+                        &virtual_branch_commits,
+                        *auto_resolve_whitespace,
+                    )?;
 
                     // Get the updated tree oid
                     let BranchHeadAndTree {
@@ -521,12 +562,13 @@ mod test {
         let head_commit = test_repository.commit_tree(Some(&initial_commit), &[("foo.txt", "baz")]);
 
         let context = UpstreamIntegrationContext {
-            _permission: None,
+            permission: None,
             old_target: head_commit.clone(),
             new_target: head_commit,
             repository: &test_repository.repository,
             virtual_branches_in_workspace: vec![],
             target_branch_name: "main".to_string(),
+            auto_resolve_whitespace: false,
         };
 
         assert_eq!(
@@ -543,12 +585,13 @@ mod test {
         let new_target = test_repository.commit_tree(Some(&old_target), &[("foo.txt", "qux")]);
 
         let context = UpstreamIntegrationContext {
-            _permission: None,
+            permission: None,
             old_target,
             new_target,
             repository: &test_repository.repository,
             virtual_branches_in_workspace: vec![],
             target_branch_name: "main".to_string(),
+            auto_resolve_whitespace: false,
         };
 
         assert_eq!(
@@ -567,12 +610,13 @@ mod test {
         let branch = make_branch(old_target.id(), old_target.tree_id());
 
         let context = UpstreamIntegrationContext {
-            _permission: None,
+            permission: None,
             old_target,
             new_target,
             repository: &test_repository.repository,
             virtual_branches_in_workspace: vec![branch.clone()],
             target_branch_name: "main".to_string(),
+            auto_resolve_whitespace: false,
         };
 
         assert_eq!(
@@ -592,12 +636,13 @@ mod test {
         let branch = make_branch(branch_head.id(), branch_head.tree_id());
 
         let context = UpstreamIntegrationContext {
-            _permission: None,
+            permission: None,
             old_target,
             new_target: new_target.clone(),
             repository: &test_repository.repository,
             virtual_branches_in_workspace: vec![branch.clone()],
             target_branch_name: "main".to_string(),
+            auto_resolve_whitespace: false,
         };
 
         assert_eq!(
@@ -649,12 +694,13 @@ mod test {
         let branch = make_branch(branch_head.id(), branch_head.tree_id());
 
         let context = UpstreamIntegrationContext {
-            _permission: None,
+            permission: None,
             old_target,
             new_target: new_target.clone(),
             repository: &test_repository.repository,
             virtual_branches_in_workspace: vec![branch.clone()],
             target_branch_name: "main".to_string(),
+            auto_resolve_whitespace: false,
         };
 
         assert_eq!(
@@ -708,12 +754,13 @@ mod test {
         let branch = make_branch(branch_head.id(), branch_head.tree_id());
 
         let context = UpstreamIntegrationContext {
-            _permission: None,
+            permission: None,
             old_target,
             new_target: new_target.clone(),
             repository: &test_repository.repository,
             virtual_branches_in_workspace: vec![branch.clone()],
             target_branch_name: "main".to_string(),
+            auto_resolve_whitespace: false,
         };
 
         assert_eq!(
@@ -771,11 +818,12 @@ mod test {
             &test_repository.repository,
             new_target.id(),
             &commits_to_rebase,
+            false,
         )
         .unwrap();
 
         let context = UpstreamIntegrationContext {
-            _permission: None,
+            permission: None,
             old_target,
             new_target: test_repository
                 .repository
@@ -784,6 +832,7 @@ mod test {
             repository: &test_repository.repository,
             virtual_branches_in_workspace: vec![branch.clone()],
             target_branch_name: "main".to_string(),
+            auto_resolve_whitespace: false,
         };
 
         assert_eq!(
@@ -846,11 +895,12 @@ mod test {
             &test_repository.repository,
             new_target.id(),
             &commits_to_rebase,
+            false,
         )
         .unwrap();
 
         let context = UpstreamIntegrationContext {
-            _permission: None,
+            permission: None,
             old_target,
             new_target: test_repository
                 .repository
@@ -859,6 +909,7 @@ mod test {
             repository: &test_repository.repository,
             virtual_branches_in_workspace: vec![branch.clone()],
             target_branch_name: "main".to_string(),
+            auto_resolve_whitespace: false,
         };
 
         assert_eq!(
@@ -910,16 +961,18 @@ mod test {
             new_target,
             "main",
             "main",
+            false,
         )
         .unwrap();
 
         let context = UpstreamIntegrationContext {
-            _permission: None,
+            permission: None,
             old_target,
             new_target: merge_commit.clone(),
             repository: &test_repository.repository,
             virtual_branches_in_workspace: vec![branch.clone()],
             target_branch_name: "main".to_string(),
+            auto_resolve_whitespace: false,
         };
 
         assert_eq!(
@@ -976,16 +1029,18 @@ mod test {
             new_target,
             "main",
             "main",
+            false,
         )
         .unwrap();
 
         let context = UpstreamIntegrationContext {
-            _permission: None,
+            permission: None,
             old_target,
             new_target: merge_commit.clone(),
             repository: &test_repository.repository,
             virtual_branches_in_workspace: vec![branch.clone()],
             target_branch_name: "main".to_string(),
+            auto_resolve_whitespace: false,
         };
 
         assert_eq!(
@@ -1031,12 +1086,13 @@ mod test {
         let branch = make_branch(old_target.id(), branch_head.tree_id());
 
         let context = UpstreamIntegrationContext {
-            _permission: None,
+            permission: None,
             old_target,
             new_target,
             repository: &test_repository.repository,
             virtual_branches_in_workspace: vec![branch.clone()],
             target_branch_name: "main".to_string(),
+            auto_resolve_whitespace: false,
         };
 
         assert_eq!(
@@ -1062,12 +1118,13 @@ mod test {
         let branch = make_branch(branch_head.id(), branch_tree.tree_id());
 
         let context = UpstreamIntegrationContext {
-            _permission: None,
+            permission: None,
             old_target,
             new_target,
             repository: &test_repository.repository,
             virtual_branches_in_workspace: vec![branch.clone()],
             target_branch_name: "main".to_string(),
+            auto_resolve_whitespace: false,
         };
 
         assert_eq!(
@@ -1091,12 +1148,13 @@ mod test {
         let branch = make_branch(new_target.id(), new_target.tree_id());
 
         let context = UpstreamIntegrationContext {
-            _permission: None,
+            permission: None,
             old_target,
             new_target,
             repository: &test_repository.repository,
             virtual_branches_in_workspace: vec![branch.clone()],
             target_branch_name: "main".to_string(),
+            auto_resolve_whitespace: false,
         };
 
         assert_eq!(
@@ -1122,12 +1180,13 @@ mod test {
         let branch = make_branch(new_target.id(), tree.tree_id());
 
         let context = UpstreamIntegrationContext {
-            _permission: None,
+            permission: None,
             old_target,
             new_target,
             repository: &test_repository.repository,
             virtual_branches_in_workspace: vec![branch.clone()],
             target_branch_name: "main".to_string(),
+            auto_resolve_whitespace: false,
         };
 
         assert_eq!(
@@ -1162,12 +1221,13 @@ mod test {
         let branch = make_branch(branch_head.id(), branch_tree.tree_id());
 
         let context = UpstreamIntegrationContext {
-            _permission: None,
+            permission: None,
             old_target,
             new_target,
             repository: &test_repository.repository,
             virtual_branches_in_workspace: vec![branch.clone()],
             target_branch_name: "main".to_string(),
+            auto_resolve_whitespace: false,
         };
 
         assert_eq!(
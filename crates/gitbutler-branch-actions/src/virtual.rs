@@ -1,7 +1,9 @@
 use crate::{
+    co_authors::{self, CoAuthor},
     commit::{commit_to_vbranch_commit, VirtualBranchCommit},
     conflicts::{self, RepoConflictsExt},
     file::VirtualBranchFile,
+    hooks,
     hunk::VirtualBranchHunk,
     integration::get_workspace_head,
     remote::{branch_to_remote_branch, RemoteBranch},
@@ -11,7 +13,6 @@ use crate::{
 };
 use anyhow::{anyhow, bail, Context, Result};
 use bstr::{BString, ByteSlice};
-use git2_hooks::HookResult;
 use gitbutler_branch::BranchUpdateRequest;
 use gitbutler_branch::{dedup, dedup_fmt};
 use gitbutler_cherry_pick::RepositoryExt as _;
@@ -19,6 +20,7 @@ use gitbutler_command_context::CommandContext;
 use gitbutler_commit::{commit_ext::CommitExt, commit_headers::HasCommitHeaders};
 use gitbutler_diff::{trees, GitHunk, Hunk};
 use gitbutler_error::error::Code;
+use gitbutler_forge::forge::MergeQueueStatus;
 use gitbutler_operating_modes::assure_open_workspace_mode;
 use gitbutler_oxidize::{git2_signature_to_gix_signature, git2_to_gix_object_id, gix_to_git2_oid};
 use gitbutler_project::access::WorktreeWritePermission;
@@ -68,6 +70,8 @@ pub struct VirtualBranch {
     pub updated_at: u128,
     pub selected_for_changes: bool,
     pub allow_rebasing: bool,
+    /// If `true`, the branch can't be pushed or have a PR created for it, see [`Stack::draft`].
+    pub draft: bool,
     #[serde(with = "gitbutler_serde::oid")]
     pub head: git2::Oid,
     /// The merge base between the target branch and the virtual branch
@@ -104,6 +108,8 @@ pub struct PatchSeries {
     /// Archived represents the state when series/branch has been integrated and is below the merge base of the branch.
     /// This would occur when the branch has been merged at the remote and the workspace has been updated with that change.
     pub archived: bool,
+    /// The position or state of the review unit in the merge queue of the forge, if it is queued.
+    pub merge_queue: Option<MergeQueueStatus>,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize)]
@@ -491,6 +497,7 @@ pub fn list_virtual_branches_cached(
             updated_at: branch.updated_timestamp_ms,
             selected_for_changes: branch.selected_for_changes == Some(max_selected_for_changes),
             allow_rebasing: branch.allow_rebasing,
+            draft: branch.draft,
             head,
             merge_base,
             fork_point,
@@ -643,6 +650,10 @@ pub fn update_branch(ctx: &CommandContext, branch_update: &BranchUpdateRequest)
         branch.allow_rebasing = allow_rebasing;
     };
 
+    if let Some(draft) = branch_update.draft {
+        branch.draft = draft;
+    };
+
     vb_state.set_branch(branch.clone())?;
     Ok(branch)
 }
@@ -784,32 +795,56 @@ pub fn commit(
     message: &str,
     ownership: Option<&BranchOwnershipClaims>,
     run_hooks: bool,
+    co_authors: &[CoAuthor],
 ) -> Result<git2::Oid> {
-    let mut message_buffer = message.to_owned();
-
-    if run_hooks {
-        let hook_result = git2_hooks::hooks_commit_msg(
-            ctx.repository(),
-            Some(&["../.husky"]),
-            &mut message_buffer,
-        )
-        .context("failed to run hook")
-        .context(Code::CommitHookFailed)?;
+    commit_claimed(
+        ctx, branch_id, message, ownership, false, run_hooks, co_authors,
+    )
+}
 
-        if let HookResult::RunNotSuccessful { stdout, .. } = hook_result {
-            return Err(anyhow!("commit-msg hook rejected: {}", stdout.trim())
-                .context(Code::CommitHookFailed));
-        }
+/// Commit only the hunks claimed by `claims` to the branch with `branch_id`, and leave all other
+/// changes in the worktree.
+///
+/// Unlike [`commit()`], which commits whichever of the claimed hunks it finds, this fails with
+/// [`Code::Validation`] if a claimed hunk isn't among the uncommitted hunks of the branch, so a
+/// commit never misses changes that were picked for it, e.g. because the file changed since.
+pub fn commit_hunks(
+    ctx: &CommandContext,
+    branch_id: StackId,
+    message: &str,
+    claims: &BranchOwnershipClaims,
+    run_hooks: bool,
+    co_authors: &[CoAuthor],
+) -> Result<git2::Oid> {
+    if claims.claims.iter().all(|claim| claim.hunks.is_empty()) {
+        return Err(anyhow!("No hunks to commit").context(Code::Validation));
+    }
+    commit_claimed(
+        ctx,
+        branch_id,
+        message,
+        Some(claims),
+        true,
+        run_hooks,
+        co_authors,
+    )
+}
 
-        let hook_result = git2_hooks::hooks_pre_commit(ctx.repository(), Some(&["../.husky"]))
-            .context("failed to run hook")
-            .context(Code::CommitHookFailed)?;
+fn commit_claimed(
+    ctx: &CommandContext,
+    branch_id: StackId,
+    message: &str,
+    ownership: Option<&BranchOwnershipClaims>,
+    require_claimed: bool,
+    run_hooks: bool,
+    co_authors: &[CoAuthor],
+) -> Result<git2::Oid> {
+    let run_hooks = run_hooks && !ctx.project().skip_commit_hooks;
+    let mut message_buffer = co_authors::add_trailers(message, co_authors)?;
 
-        if let HookResult::RunNotSuccessful { stdout, .. } = hook_result {
-            return Err(
-                anyhow!("commit hook rejected: {}", stdout.trim()).context(Code::CommitHookFailed)
-            );
-        }
+    if run_hooks {
+        hooks::commit_msg(ctx.repository(), &mut message_buffer)?;
+        hooks::pre_commit(ctx.repository())?;
     }
 
     let message = &message_buffer;
@@ -824,11 +859,25 @@ pub fn commit(
         .find(|(branch, _)| branch.id == branch_id)
         .with_context(|| format!("branch {branch_id} not found"))?;
 
+    if let Some(ownership) = ownership.filter(|_| require_claimed) {
+        assure_hunks_claimable(&files, ownership, branch_id)?;
+    }
+
     update_conflict_markers(ctx, files.clone()).context(Code::CommitMergeConflictFailure)?;
 
     ctx.assure_unconflicted()
         .context(Code::CommitMergeConflictFailure)?;
 
+    let paths_to_commit = files.iter().map(|file| file.path.as_path()).filter(|path| {
+        ownership.map_or(true, |ownership| {
+            ownership
+                .claims
+                .iter()
+                .any(|claim| claim.file_path.as_path() == *path)
+        })
+    });
+    crate::policy::assure_commit_allowed(ctx, branch, message, paths_to_commit)?;
+
     let tree_oid = if let Some(ownership) = ownership {
         let files = files.into_iter().filter_map(|file| {
             let hunks = file
@@ -890,25 +939,72 @@ pub fn commit(
         None => ctx.commit(message, &tree, &[&parent_commit], None)?,
     };
 
-    if run_hooks {
-        git2_hooks::hooks_post_commit(ctx.repository(), Some(&["../.husky"]))
-            .context("failed to run hook")
-            .context(Code::CommitHookFailed)?;
-    }
-
     let vb_state = ctx.project().virtual_branches();
     branch.set_stack_head(ctx, commit_oid, Some(tree_oid))?;
 
     crate::integration::update_workspace_commit(&vb_state, ctx)
         .context("failed to update gitbutler workspace")?;
 
+    if run_hooks {
+        hooks::post_commit(ctx.repository());
+    }
+
     Ok(commit_oid)
 }
 
+/// Fail with [`Code::Validation`] unless each hunk claimed by `ownership` is one of the uncommitted
+/// hunks in `files`, with the same range and, if the claim has one, the same hash.
+fn assure_hunks_claimable(
+    files: &[VirtualBranchFile],
+    ownership: &BranchOwnershipClaims,
+    branch_id: StackId,
+) -> Result<()> {
+    let missing: Vec<String> = ownership
+        .claims
+        .iter()
+        .flat_map(|claim| {
+            let file = files.iter().find(|file| file.path == claim.file_path);
+            claim
+                .hunks
+                .iter()
+                .filter(move |claimed| {
+                    !file.is_some_and(|file| {
+                        file.hunks.iter().any(|hunk| {
+                            Hunk::new(hunk.start, hunk.end, Some(hunk.hash))
+                                .is_ok_and(|hunk| hunk == **claimed)
+                        })
+                    })
+                })
+                .map(move |claimed| format!("{}:{claimed}", claim.file_path.display()))
+        })
+        .collect();
+    if !missing.is_empty() {
+        return Err(anyhow!(
+            "Hunks {} aren't uncommitted changes of branch {branch_id}",
+            missing.join(", ")
+        )
+        .context(Code::Validation));
+    }
+    Ok(())
+}
+
+/// Fail with [`Code::DraftBranch`] if `stack` is a draft, unless `allow_draft` overrides it.
+/// This keeps exploratory work from being published by accident, e.g. when pushing all branches.
+pub(crate) fn assure_not_draft(stack: &Stack, allow_draft: bool) -> Result<()> {
+    if stack.draft && !allow_draft {
+        return Err(
+            anyhow!("Branch '{}' is a draft and can't be published", stack.name)
+                .context(Code::DraftBranch),
+        );
+    }
+    Ok(())
+}
+
 pub(crate) fn push(
     ctx: &CommandContext,
     branch_id: StackId,
     with_force: bool,
+    allow_draft: bool,
     askpass: Option<Option<StackId>>,
 ) -> Result<PushResult> {
     let vb_state = ctx.project().virtual_branches();
@@ -920,6 +1016,8 @@ pub(crate) fn push(
     };
 
     let mut vbranch = vb_state.get_branch_in_workspace(branch_id)?;
+    assure_not_draft(&vbranch, allow_draft)?;
+
     let remote_branch = if let Some(upstream_branch) = &vbranch.upstream {
         upstream_branch.clone()
     } else {
@@ -947,6 +1045,7 @@ pub(crate) fn push(
             "-",
         ))
     };
+    crate::policy::assure_push_allowed(ctx, &vbranch, [remote_branch.branch()])?;
 
     ctx.push(vbranch.head(), &remote_branch, with_force, None, askpass)?;
 
@@ -1355,6 +1454,7 @@ pub(crate) fn amend(
     branch_id: StackId,
     commit_oid: git2::Oid,
     target_ownership: &BranchOwnershipClaims,
+    co_authors: &[CoAuthor],
     _perm: &mut WorktreeWritePermission,
 ) -> Result<git2::Oid> {
     ctx.assure_resolved()?;
@@ -1439,6 +1539,8 @@ pub(crate) fn amend(
         .find_tree(new_tree_oid)
         .context("failed to find new tree")?;
 
+    let message =
+        co_authors::add_trailers(&amend_commit.message_bstr().to_str_lossy(), co_authors)?;
     let parents: Vec<_> = amend_commit.parents().collect();
     let commit_oid = ctx
         .repository()
@@ -1446,12 +1548,13 @@ pub(crate) fn amend(
             None,
             &amend_commit.author(),
             &amend_commit.committer(),
-            &amend_commit.message_bstr().to_str_lossy(),
+            &message,
             &new_tree,
             &parents.iter().collect::<Vec<_>>(),
             amend_commit.gitbutler_headers(),
         )
         .context("failed to create commit")?;
+    crate::policy::assure_commits_allowed(ctx, target_branch, &[commit_oid])?;
 
     // now rebase upstream commits, if needed
     let upstream_commits = ctx.repository().l(
@@ -1599,6 +1702,7 @@ pub(crate) fn squash(ctx: &CommandContext, branch_id: StackId, commit_id: git2::
             commit_to_squash.gitbutler_headers(),
         )
         .context("failed to commit")?;
+    crate::policy::assure_commits_allowed(ctx, &branch, &[new_commit_oid])?;
 
     let ids_to_rebase = {
         let ids = branch_commit_oids
@@ -1609,7 +1713,12 @@ pub(crate) fn squash(ctx: &CommandContext, branch_id: StackId, commit_id: git2::
     .with_context(|| format!("commit {commit_id} not in the branch"))?;
     let ids_to_rebase = ids_to_rebase.to_vec();
 
-    match cherry_rebase_group(ctx.repository(), new_commit_oid, &ids_to_rebase) {
+    match cherry_rebase_group(
+        ctx.repository(),
+        new_commit_oid,
+        &ids_to_rebase,
+        ctx.project().auto_resolve_whitespace,
+    ) {
         Ok(new_head_id) => {
             // save new branch head
             branch.set_stack_head(ctx, new_head_id, None)?;
@@ -1633,6 +1742,7 @@ pub(crate) fn update_commit_message(
         bail!("commit message can not be empty");
     }
     ctx.assure_unconflicted()?;
+    crate::policy::assure_commit_message_allowed(ctx, message)?;
 
     let vb_state = ctx.project().virtual_branches();
     let default_target = vb_state.get_default_target()?;
@@ -1688,8 +1798,13 @@ pub(crate) fn update_commit_message(
     .with_context(|| format!("commit {commit_id} not in the branch"))?;
     let ids_to_rebase = ids_to_rebase.to_vec();
 
-    let new_head_id = cherry_rebase_group(ctx.repository(), new_commit_oid, &ids_to_rebase)
-        .map_err(|err| err.context("rebase error"))?;
+    let new_head_id = cherry_rebase_group(
+        ctx.repository(),
+        new_commit_oid,
+        &ids_to_rebase,
+        ctx.project().auto_resolve_whitespace,
+    )
+    .map_err(|err| err.context("rebase error"))?;
     // save new branch head
     branch.set_stack_head(ctx, new_head_id, None)?;
 
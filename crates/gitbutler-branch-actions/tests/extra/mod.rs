@@ -742,6 +742,8 @@ fn commit_id_can_be_generated_or_specified() -> Result<()> {
             Some(CommitHeadersV2 {
                 change_id: "my-change-id".to_string(),
                 conflicted: None,
+                auto_resolved: Vec::new(),
+                copied_from: None,
             }),
         )
         .expect("failed to commit");
@@ -0,0 +1,67 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_branch_actions::SkipReason;
+
+use super::*;
+
+#[test]
+fn hunks_go_to_the_commits_they_depend_on() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+
+    fs::write(repository.path().join("one.txt"), "a\nb\nc\n")?;
+    let commit_one_id = gitbutler_branch_actions::create_commit(
+        project,
+        branch_id,
+        "commit one",
+        None,
+        false,
+        &[],
+    )?;
+
+    fs::write(repository.path().join("two.txt"), "a\nb\nc\n")?;
+    gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false, &[])?;
+
+    fs::write(repository.path().join("one.txt"), "a\nB\nc\n")?;
+    fs::write(repository.path().join("three.txt"), "new")?;
+
+    let report = gitbutler_branch_actions::absorb(project, branch_id)?;
+    assert_eq!(report.absorbed.len(), 1);
+    assert_eq!(report.absorbed[0].path, PathBuf::from("one.txt"));
+    assert_eq!(report.absorbed[0].commit_id, commit_one_id);
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0].path, PathBuf::from("three.txt"));
+    assert_eq!(report.skipped[0].reason, SkipReason::Independent);
+
+    let branch = gitbutler_branch_actions::list_virtual_branches(project)?
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+    assert_eq!(branch.commits.len(), 2);
+    assert_eq!(branch.files.len(), 1);
+    assert_eq!(branch.files[0].path, PathBuf::from("three.txt"));
+
+    let git_repository = &repository.local_repository;
+    let commit_one = git_repository.find_commit(branch.commits[1].id)?;
+    assert_eq!(commit_one.message(), Some("commit one"));
+    let blob = commit_one
+        .tree()?
+        .get_path(path::Path::new("one.txt"))?
+        .to_object(git_repository)?
+        .peel_to_blob()?;
+    assert_eq!(blob.content(), b"a\nB\nc\n");
+    Ok(())
+}
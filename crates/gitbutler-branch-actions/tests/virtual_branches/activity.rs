@@ -0,0 +1,47 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_branch_actions::activity::ActivityKind;
+use gitbutler_oplog::entry::OperationKind;
+
+use super::*;
+
+#[test]
+fn operations_and_pushes_of_stack() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let commit_id =
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit", None, false, &[])
+            .unwrap();
+    gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, false, None).unwrap();
+
+    let page = gitbutler_branch_actions::stack_activity(project, branch_id, &[], 0, 100).unwrap();
+    assert!(!page.has_more);
+    assert!(page.activity.iter().any(|activity| matches!(
+        activity.kind,
+        ActivityKind::Push { commit_id: pushed, .. } if pushed == commit_id
+    )));
+    assert!(page.activity.iter().any(|activity| matches!(
+        activity.kind,
+        ActivityKind::Operation {
+            operation: OperationKind::CreateCommit,
+            ..
+        }
+    )));
+
+    let first = gitbutler_branch_actions::stack_activity(project, branch_id, &[], 0, 1).unwrap();
+    assert_eq!(first.activity, page.activity[..1]);
+    assert!(first.has_more);
+}
@@ -1,5 +1,5 @@
 use gitbutler_branch::{BranchCreateRequest, BranchUpdateRequest};
-use gitbutler_branch_actions::list_commit_files;
+use gitbutler_branch_actions::{co_authors::CoAuthor, list_commit_files};
 use gitbutler_stack::BranchOwnershipClaims;
 
 use super::*;
@@ -41,16 +41,16 @@ fn forcepush_allowed() -> anyhow::Result<()> {
     // create commit
     fs::write(repository.path().join("file.txt"), "content").unwrap();
     let commit_id =
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap();
 
-    gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, None).unwrap();
+    gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, false, None).unwrap();
 
     {
         // amend another hunk
         fs::write(repository.path().join("file2.txt"), "content2").unwrap();
         let to_amend: BranchOwnershipClaims = "file2.txt:1-2".parse().unwrap();
-        gitbutler_branch_actions::amend(project, branch_id, commit_id, &to_amend).unwrap();
+        gitbutler_branch_actions::amend(project, branch_id, commit_id, &to_amend, &[]).unwrap();
 
         let branch = gitbutler_branch_actions::list_virtual_branches(project)
             .unwrap()
@@ -97,16 +97,16 @@ fn forcepush_forbidden() {
     // create commit
     fs::write(repository.path().join("file.txt"), "content").unwrap();
     let commit_oid =
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap();
 
-    gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, None).unwrap();
+    gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, false, None).unwrap();
 
     {
         fs::write(repository.path().join("file2.txt"), "content2").unwrap();
         let to_amend: BranchOwnershipClaims = "file2.txt:1-2".parse().unwrap();
         assert_eq!(
-            gitbutler_branch_actions::amend(project, branch_id, commit_oid, &to_amend)
+            gitbutler_branch_actions::amend(project, branch_id, commit_oid, &to_amend, &[])
                 .unwrap_err()
                 .to_string(),
             "force-push is not allowed"
@@ -135,7 +135,7 @@ fn non_locked_hunk() -> anyhow::Result<()> {
     // create commit
     fs::write(repository.path().join("file.txt"), "content").unwrap();
     let commit_oid =
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap();
 
     let branch = gitbutler_branch_actions::list_virtual_branches(project)
@@ -151,7 +151,7 @@ fn non_locked_hunk() -> anyhow::Result<()> {
         // amend another hunk
         fs::write(repository.path().join("file2.txt"), "content2").unwrap();
         let to_amend: BranchOwnershipClaims = "file2.txt:1-2".parse().unwrap();
-        gitbutler_branch_actions::amend(project, branch_id, commit_oid, &to_amend).unwrap();
+        gitbutler_branch_actions::amend(project, branch_id, commit_oid, &to_amend, &[]).unwrap();
 
         let branch = gitbutler_branch_actions::list_virtual_branches(project)
             .unwrap()
@@ -187,7 +187,7 @@ fn locked_hunk() -> anyhow::Result<()> {
     // create commit
     fs::write(repository.path().join("file.txt"), "content").unwrap();
     let commit_oid =
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap();
 
     let branch = gitbutler_branch_actions::list_virtual_branches(project)
@@ -207,7 +207,7 @@ fn locked_hunk() -> anyhow::Result<()> {
         // amend another hunk
         fs::write(repository.path().join("file.txt"), "more content").unwrap();
         let to_amend: BranchOwnershipClaims = "file.txt:1-2".parse().unwrap();
-        gitbutler_branch_actions::amend(project, branch_id, commit_oid, &to_amend).unwrap();
+        gitbutler_branch_actions::amend(project, branch_id, commit_oid, &to_amend, &[]).unwrap();
 
         let branch = gitbutler_branch_actions::list_virtual_branches(project)
             .unwrap()
@@ -247,7 +247,7 @@ fn non_existing_ownership() {
     // create commit
     fs::write(repository.path().join("file.txt"), "content").unwrap();
     let commit_oid =
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap();
 
     let branch = gitbutler_branch_actions::list_virtual_branches(project)
@@ -263,10 +263,109 @@ fn non_existing_ownership() {
         // amend non existing hunk
         let to_amend: BranchOwnershipClaims = "file2.txt:1-2".parse().unwrap();
         assert_eq!(
-            gitbutler_branch_actions::amend(project, branch_id, commit_oid, &to_amend)
+            gitbutler_branch_actions::amend(project, branch_id, commit_oid, &to_amend, &[])
                 .unwrap_err()
                 .to_string(),
             "target ownership not found"
         );
     }
 }
+
+#[test]
+fn with_co_authors() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let commit_oid =
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
+            .unwrap();
+
+    fs::write(repository.path().join("file2.txt"), "content2").unwrap();
+    let to_amend: BranchOwnershipClaims = "file2.txt:1-2".parse().unwrap();
+    let co_author = CoAuthor {
+        name: "Jane Doe".into(),
+        email: "jane@example.com".into(),
+    };
+    gitbutler_branch_actions::amend(
+        project,
+        branch_id,
+        commit_oid,
+        &to_amend,
+        std::slice::from_ref(&co_author),
+    )?;
+
+    let branch = gitbutler_branch_actions::list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+    let commit = &branch.commits[0];
+    assert_eq!(
+        commit.description,
+        "commit one\n\nCo-authored-by: Jane Doe <jane@example.com>\n"
+    );
+    assert_eq!(commit.co_authors.len(), 1);
+    assert_eq!(commit.co_authors[0].name, "Jane Doe");
+    assert_eq!(commit.co_authors[0].email, "jane@example.com");
+    Ok(())
+}
+
+#[test]
+fn should_reject_amends_violating_the_policy() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let commit_oid =
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
+            .unwrap();
+
+    fs::create_dir_all(repository.path().join(".gitbutler")).unwrap();
+    fs::write(
+        repository.path().join(".gitbutler/policy.toml"),
+        r#"protected_paths = ["protected.txt"]"#,
+    )
+    .unwrap();
+    fs::write(repository.path().join("protected.txt"), "content").unwrap();
+
+    let to_amend: BranchOwnershipClaims = "protected.txt:1-2".parse().unwrap();
+    let err = gitbutler_branch_actions::amend(project, branch_id, commit_oid, &to_amend, &[])
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("'protected.txt' is protected"));
+
+    let branch = gitbutler_branch_actions::list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+    assert_eq!(branch.commits[0].id, commit_oid);
+}
@@ -0,0 +1,151 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_branch_actions::RejectReason;
+use gitbutler_error::error::Code;
+
+use super::*;
+
+const MBOX: &str = r#"From 0123456789abcdef0123456789abcdef01234567 Mon Sep 17 00:00:00 2001
+From: Jane Doe <jane@example.com>
+Date: Mon, 14 Oct 2024 12:00:00 +0200
+Subject: [PATCH 1/2] Add a greeting
+
+---
+ greeting.txt | 1 +
+ 1 file changed, 1 insertion(+)
+
+diff --git a/greeting.txt b/greeting.txt
+new file mode 100644
+index 0000000..ce01362
+--- /dev/null
++++ b/greeting.txt
+@@ -0,0 +1 @@
++hello
+-- 
+2.47.0
+
+From 89abcdef0123456789abcdef0123456789abcdef Mon Sep 17 00:00:00 2001
+From: Jane Doe <jane@example.com>
+Date: Mon, 14 Oct 2024 12:05:00 +0200
+Subject: [PATCH 2/2] Say goodbye
+
+It's polite.
+---
+ greeting.txt | 1 +
+ 1 file changed, 1 insertion(+)
+
+diff --git a/greeting.txt b/greeting.txt
+index ce01362..b81f4a8 100644
+--- a/greeting.txt
++++ b/greeting.txt
+@@ -1 +1,2 @@
+ hello
++goodbye
+-- 
+2.47.0
+"#;
+
+#[test]
+fn mbox_patches_become_commits() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+
+    let report = gitbutler_branch_actions::apply_patch(project, branch_id, MBOX.as_bytes())?;
+    assert_eq!(report.commit_ids.len(), 2);
+    assert!(report.rejected.is_empty());
+    assert_eq!(
+        fs::read_to_string(repository.path().join("greeting.txt"))?,
+        "hello\ngoodbye\n"
+    );
+
+    let branch = gitbutler_branch_actions::list_virtual_branches(project)?
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+    assert_eq!(branch.commits.len(), 2);
+    assert_eq!(branch.files.len(), 0);
+
+    let git_repository = &repository.local_repository;
+    let first = git_repository.find_commit(report.commit_ids[0])?;
+    assert_eq!(first.message(), Some("Add a greeting"));
+    assert_eq!(first.author().email(), Some("jane@example.com"));
+    let second = git_repository.find_commit(report.commit_ids[1])?;
+    assert_eq!(second.message(), Some("Say goodbye\n\nIt's polite."));
+    assert_eq!(second.parent_id(0)?, first.id());
+    assert_eq!(second.id(), branch.commits[0].id);
+    Ok(())
+}
+
+#[test]
+fn hunks_that_do_not_apply_are_rejected() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+
+    fs::write(repository.path().join("one.txt"), "a\nb\nc\n")?;
+    gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])?;
+
+    let patch = "--- a/one.txt
++++ b/one.txt
+@@ -1,3 +1,3 @@
+ a
+-b
++B
+ c
+@@ -10,2 +10,2 @@
+ x
+-y
++Y
+--- a/missing.txt
++++ b/missing.txt
+@@ -1 +1 @@
+-old
++new
+";
+    let report = gitbutler_branch_actions::apply_patch(project, branch_id, patch.as_bytes())?;
+    assert_eq!(report.commit_ids.len(), 1);
+    assert_eq!(report.rejected.len(), 2);
+    assert_eq!(report.rejected[0].path, PathBuf::from("one.txt"));
+    assert_eq!(
+        report.rejected[0].hunk.as_deref(),
+        Some("@@ -10,2 +10,2 @@")
+    );
+    assert_eq!(report.rejected[0].reason, RejectReason::DoesNotApply);
+    assert_eq!(report.rejected[1].path, PathBuf::from("missing.txt"));
+    assert_eq!(report.rejected[1].hunk, None);
+    assert_eq!(report.rejected[1].reason, RejectReason::MissingFile);
+    assert_eq!(
+        fs::read_to_string(repository.path().join("one.txt"))?,
+        "a\nB\nc\n"
+    );
+
+    let err =
+        gitbutler_branch_actions::apply_patch(project, branch_id, b"no patch here\n").unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::Validation));
+    Ok(())
+}
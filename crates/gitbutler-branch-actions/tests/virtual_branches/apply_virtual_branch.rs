@@ -37,8 +37,15 @@ fn rebase_commit() {
         .unwrap();
         fs::write(repository.path().join("another_file.txt"), "virtual").unwrap();
 
-        gitbutler_branch_actions::create_commit(project, branch1_id, "virtual commit", None, false)
-            .unwrap();
+        gitbutler_branch_actions::create_commit(
+            project,
+            branch1_id,
+            "virtual commit",
+            None,
+            false,
+            &[],
+        )
+        .unwrap();
 
         let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
         assert_eq!(branches.len(), 1);
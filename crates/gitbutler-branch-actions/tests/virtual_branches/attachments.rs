@@ -0,0 +1,251 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_branch_actions::attachments::{AttachmentKind, ATTACHMENTS_NOTES_REF};
+use gitbutler_stack::BranchOwnershipClaims;
+
+use super::*;
+
+#[test]
+fn survive_rewording() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+
+    let commit_oid = {
+        fs::write(repository.path().join("file.txt"), "content").unwrap();
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit", None, false, &[])
+            .unwrap()
+    };
+
+    gitbutler_branch_actions::add_commit_attachment(
+        project,
+        commit_oid,
+        AttachmentKind::Review,
+        "https://github.com/org/repo/pull/1",
+        Some("Review".into()),
+    )
+    .unwrap();
+    assert!(
+        gitbutler_branch_actions::add_commit_attachment(
+            project,
+            commit_oid,
+            AttachmentKind::Other,
+            "file:///etc/passwd",
+            None,
+        )
+        .is_err(),
+        "only web links can be attached"
+    );
+
+    gitbutler_branch_actions::update_commit_message(
+        project,
+        branch_id,
+        commit_oid,
+        "commit updated",
+    )
+    .unwrap();
+
+    let branch = gitbutler_branch_actions::list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+    assert_ne!(branch.head, commit_oid);
+
+    let attachments =
+        gitbutler_branch_actions::list_commit_attachments(project, branch.head).unwrap();
+    assert_eq!(attachments.len(), 1);
+    assert_eq!(attachments[0].kind, AttachmentKind::Review);
+    assert_eq!(attachments[0].url, "https://github.com/org/repo/pull/1");
+
+    gitbutler_branch_actions::remove_commit_attachment(
+        project,
+        branch.head,
+        "https://github.com/org/repo/pull/1",
+    )
+    .unwrap();
+    assert!(
+        gitbutler_branch_actions::list_commit_attachments(project, branch.head)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn add_remove_and_list_round_trip() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let commit_oid =
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit", None, false, &[])
+            .unwrap();
+
+    assert!(
+        gitbutler_branch_actions::list_commit_attachments(project, commit_oid)
+            .unwrap()
+            .is_empty()
+    );
+
+    for (kind, url) in [
+        (AttachmentKind::CiRun, "https://ci.example.com/runs/1"),
+        (AttachmentKind::Issue, "HTTPS://Example.com/issues/2"),
+    ] {
+        gitbutler_branch_actions::add_commit_attachment(project, commit_oid, kind, url, None)
+            .unwrap();
+    }
+    gitbutler_branch_actions::add_commit_attachment(
+        project,
+        commit_oid,
+        AttachmentKind::CiRun,
+        "https://ci.example.com/runs/1",
+        Some("Rerun".into()),
+    )
+    .unwrap();
+
+    let attachments = gitbutler_branch_actions::list_commit_attachments(project, commit_oid)
+        .unwrap()
+        .into_iter()
+        .map(|attachment| (attachment.kind, attachment.url, attachment.title))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        attachments,
+        [
+            (
+                AttachmentKind::Issue,
+                "https://example.com/issues/2".to_owned(),
+                None
+            ),
+            (
+                AttachmentKind::CiRun,
+                "https://ci.example.com/runs/1".to_owned(),
+                Some("Rerun".to_owned())
+            ),
+        ],
+        "URLs are normalized, and attaching one again replaces it"
+    );
+
+    gitbutler_branch_actions::remove_commit_attachment(
+        project,
+        commit_oid,
+        "HTTPS://Example.com/issues/2",
+    )
+    .unwrap();
+    let attachments =
+        gitbutler_branch_actions::list_commit_attachments(project, commit_oid).unwrap();
+    assert_eq!(
+        attachments.len(),
+        1,
+        "the URL is normalized like when adding"
+    );
+    assert_eq!(attachments[0].url, "https://ci.example.com/runs/1");
+
+    gitbutler_branch_actions::remove_commit_attachment(
+        project,
+        commit_oid,
+        "https://ci.example.com/runs/1",
+    )
+    .unwrap();
+    assert!(
+        gitbutler_branch_actions::list_commit_attachments(project, commit_oid)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn notes_follow_rewritten_commits() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    repo.config()
+        .unwrap()
+        .set_bool("gitbutler.mirrorAttachmentsToNotes", true)
+        .unwrap();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let commit_oid =
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit", None, false, &[])
+            .unwrap();
+
+    gitbutler_branch_actions::add_commit_attachment(
+        project,
+        commit_oid,
+        AttachmentKind::Review,
+        "https://github.com/org/repo/pull/1",
+        None,
+    )
+    .unwrap();
+    let note = |commit_oid| {
+        repo.find_note(Some(ATTACHMENTS_NOTES_REF), commit_oid)
+            .ok()
+            .and_then(|note| note.message().map(ToOwned::to_owned))
+    };
+    assert_eq!(
+        note(commit_oid).as_deref(),
+        Some("review: https://github.com/org/repo/pull/1\n")
+    );
+
+    fs::write(repository.path().join("file2.txt"), "content2").unwrap();
+    let to_amend: BranchOwnershipClaims = "file2.txt:1-2".parse().unwrap();
+    let amended_oid =
+        gitbutler_branch_actions::amend(project, branch_id, commit_oid, &to_amend, &[]).unwrap();
+    assert_ne!(amended_oid, commit_oid);
+    assert_eq!(
+        note(amended_oid),
+        note(commit_oid),
+        "amending keeps the note"
+    );
+
+    gitbutler_branch_actions::update_commit_message(project, branch_id, amended_oid, "reworded")
+        .unwrap();
+    let branch = gitbutler_branch_actions::list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+    assert_ne!(branch.head, amended_oid);
+    assert_eq!(
+        note(branch.head),
+        note(commit_oid),
+        "rebasing keeps the note"
+    );
+}
@@ -0,0 +1,83 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_oplog::{entry::OperationKind, OplogExt};
+use gitbutler_project::feature_flags::FeatureFlag;
+
+use super::*;
+
+#[test]
+fn unassigned_changes_survive_applying_a_branch() {
+    let mut test = Test::default();
+    test.project
+        .feature_flags
+        .insert(FeatureFlag::AutoStash, true);
+    let Test {
+        repository,
+        project,
+        ..
+    } = &test;
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_name = {
+        let branch_id = gitbutler_branch_actions::create_virtual_branch(
+            project,
+            &BranchCreateRequest::default(),
+        )
+        .unwrap();
+        fs::write(repository.path().join("file.txt"), "first\n").unwrap();
+        gitbutler_branch_actions::create_commit(project, branch_id, "first", None, false, &[])
+            .unwrap();
+        gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, false, None)
+            .unwrap();
+        let branch = gitbutler_branch_actions::list_virtual_branches(project)
+            .unwrap()
+            .0
+            .into_iter()
+            .find(|branch| branch.id == branch_id)
+            .unwrap();
+        gitbutler_branch_actions::unapply_without_saving_virtual_branch(project, branch_id)
+            .unwrap();
+        branch.upstream.unwrap().name
+    };
+
+    // Neither of these are known to any branch.
+    fs::write(repository.path().join("notes.txt"), "notes\n").unwrap();
+    fs::write(repository.path().join("file.txt"), "mine\n").unwrap();
+
+    gitbutler_branch_actions::create_virtual_branch_from_branch(project, &branch_name, None, None)
+        .unwrap();
+
+    assert_eq!(
+        fs::read_to_string(repository.path().join("notes.txt")).unwrap(),
+        "notes\n",
+        "changes that don't conflict are reapplied"
+    );
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "first\n",
+        "conflicting changes are left as the branch has them"
+    );
+
+    let conflicts = gitbutler_branch_actions::autostash_conflicts(project)
+        .unwrap()
+        .expect("the conflict is reported");
+    assert_eq!(conflicts.operation, OperationKind::ApplyBranch);
+    assert_eq!(conflicts.paths, [PathBuf::from("file.txt")]);
+    let stash = project
+        .list_snapshots(10, None)
+        .unwrap()
+        .into_iter()
+        .find(|snapshot| snapshot.commit_id == conflicts.snapshot_id)
+        .expect("the stashed changes can be restored");
+    assert_eq!(stash.details.unwrap().operation, OperationKind::AutoStash);
+
+    gitbutler_branch_actions::dismiss_autostash_conflicts(project).unwrap();
+    assert_eq!(
+        gitbutler_branch_actions::autostash_conflicts(project).unwrap(),
+        None
+    );
+}
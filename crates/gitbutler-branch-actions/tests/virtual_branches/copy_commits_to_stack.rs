@@ -0,0 +1,62 @@
+use gitbutler_branch::BranchCreateRequest;
+
+use super::Test;
+
+#[test]
+fn copies_keep_the_source() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    std::fs::write(repository.path().join("file.txt"), "content").unwrap();
+
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    let source_branch_id = branches[0].id;
+    let commit_oid = gitbutler_branch_actions::create_commit(
+        project,
+        source_branch_id,
+        "commit",
+        None,
+        false,
+        &[],
+    )
+    .unwrap();
+
+    let target_branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+
+    let copies =
+        gitbutler_branch_actions::copy_commits_to_stack(project, target_branch_id, &[commit_oid])
+            .unwrap();
+    assert_eq!(copies.len(), 1);
+
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    let source_branch = branches.iter().find(|b| b.id == source_branch_id).unwrap();
+    let target_branch = branches.iter().find(|b| b.id == target_branch_id).unwrap();
+
+    assert_eq!(source_branch.commits.len(), 1);
+    assert_eq!(source_branch.commits[0].id, commit_oid);
+    assert_eq!(target_branch.commits.len(), 1);
+    assert_eq!(target_branch.commits[0].id, copies[0]);
+    assert_eq!(target_branch.commits[0].copied_from_id, Some(commit_oid));
+    assert_ne!(
+        target_branch.commits[0].change_id, source_branch.commits[0].change_id,
+        "the copy is a patch of its own"
+    );
+    assert_eq!(target_branch.files.len(), 0);
+
+    assert!(
+        gitbutler_branch_actions::copy_commits_to_stack(project, target_branch_id, &copies)
+            .is_err(),
+        "commits can't be copied to the branch they are in"
+    );
+}
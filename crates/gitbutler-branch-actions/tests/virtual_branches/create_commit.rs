@@ -1,7 +1,9 @@
 use gitbutler_branch::{BranchCreateRequest, BranchUpdateRequest};
-use gitbutler_branch_actions::VirtualBranch;
+use gitbutler_branch_actions::{
+    co_authors::CoAuthor, policy::CommitMessageViolation, VirtualBranch,
+};
 use gitbutler_id::id::Id;
-use gitbutler_stack::Stack;
+use gitbutler_stack::{BranchOwnershipClaims, Stack};
 
 use super::*;
 
@@ -34,7 +36,7 @@ fn should_lock_updated_hunks() {
         assert!(!branch.files[0].hunks[0].locked);
     }
 
-    gitbutler_branch_actions::create_commit(project, branch_id, "test", None, false).unwrap();
+    gitbutler_branch_actions::create_commit(project, branch_id, "test", None, false, &[]).unwrap();
 
     {
         // change in the committed hunks leads to hunk locking
@@ -91,6 +93,7 @@ fn should_reset_into_same_branch() {
         "commit to branch 2",
         None,
         false,
+        &[],
     )
     .unwrap();
 
@@ -115,6 +118,197 @@ fn should_reset_into_same_branch() {
     assert_eq!(files.len(), 1);
 }
 
+#[test]
+fn should_reject_commits_violating_the_policy() {
+    let Test {
+        project,
+        repository,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+
+    std::fs::create_dir_all(repository.path().join(".gitbutler")).unwrap();
+    repository.write_file(
+        ".gitbutler/policy.toml",
+        &[r#"protected_paths = ["protected.txt"]"#.to_string()],
+    );
+    repository.write_file("protected.txt", &["content".to_string()]);
+
+    let err = gitbutler_branch_actions::create_commit(project, branch_id, "test", None, false, &[])
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("'protected.txt' is protected"));
+
+    let report = gitbutler_branch_actions::policy_report(project).unwrap();
+    assert!(report.policy.is_some());
+    assert_eq!(report.violations.len(), 1);
+
+    std::fs::remove_file(repository.path().join("protected.txt")).unwrap();
+    gitbutler_branch_actions::create_commit(project, branch_id, "test", None, false, &[]).unwrap();
+}
+
+#[test]
+fn should_reject_messages_violating_the_policy() {
+    let Test {
+        project,
+        repository,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+
+    std::fs::create_dir_all(repository.path().join(".gitbutler")).unwrap();
+    repository.write_file(
+        ".gitbutler/policy.toml",
+        &[
+            "[commit_message]".to_string(),
+            "conventional = true".to_string(),
+        ],
+    );
+    repository.write_file("file.txt", &["content".to_string()]);
+
+    assert_eq!(
+        gitbutler_branch_actions::validate_commit_message(project, "add a file").unwrap(),
+        vec![CommitMessageViolation::NotConventional]
+    );
+    let err =
+        gitbutler_branch_actions::create_commit(project, branch_id, "add a file", None, false, &[])
+            .unwrap_err();
+    assert!(format!("{err:#}").contains("must be a conventional commit"));
+
+    assert_eq!(
+        gitbutler_branch_actions::validate_commit_message(project, "feat: add a file").unwrap(),
+        vec![]
+    );
+    gitbutler_branch_actions::create_commit(
+        project,
+        branch_id,
+        "feat: add a file",
+        None,
+        false,
+        &[],
+    )
+    .unwrap();
+}
+
+#[test]
+fn should_reject_pushes_violating_the_policy() {
+    let Test {
+        project,
+        repository,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+    repository.write_file("file.txt", &["content".to_string()]);
+    gitbutler_branch_actions::create_commit(project, branch_id, "test", None, false, &[]).unwrap();
+
+    std::fs::create_dir_all(repository.path().join(".gitbutler")).unwrap();
+    repository.write_file(
+        ".gitbutler/policy.toml",
+        &[r#"branch_name_pattern = "^feat/""#.to_string()],
+    );
+
+    let err =
+        gitbutler_branch_actions::stack::push_stack(project, branch_id, false, false).unwrap_err();
+    assert!(format!("{err:#}").contains("doesn't match the pattern '^feat/'"));
+    let err = gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, false, None)
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("doesn't match the pattern '^feat/'"));
+}
+
+#[test]
+fn should_commit_only_claimed_hunks() {
+    let Test {
+        project,
+        repository,
+        ..
+    } = &Test::default();
+
+    let mut lines = repository.gen_file("file.txt", 20);
+    commit_and_push_initial(repository);
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+
+    lines[0] = "change 1".to_string();
+    lines[15] = "change 2".to_string();
+    repository.write_file("file.txt", &lines);
+
+    let hunks = get_virtual_branch(project, branch_id).files[0]
+        .hunks
+        .clone();
+    assert_eq!(hunks.len(), 2);
+    let claims: BranchOwnershipClaims = format!("file.txt:{}-{}", hunks[0].start, hunks[0].end)
+        .parse()
+        .unwrap();
+    gitbutler_branch_actions::create_commit_from_hunks(
+        project,
+        branch_id,
+        "first",
+        &claims,
+        false,
+        &[],
+    )
+    .unwrap();
+
+    let branch = get_virtual_branch(project, branch_id);
+    assert_eq!(branch.commits.len(), 1);
+    assert_eq!(branch.files.len(), 1);
+    assert_eq!(
+        branch.files[0].hunks.len(),
+        1,
+        "the hunk that wasn't claimed stays in the worktree"
+    );
+    assert_eq!(branch.files[0].hunks[0].diff, hunks[1].diff);
+
+    let err = gitbutler_branch_actions::create_commit_from_hunks(
+        project,
+        branch_id,
+        "stale",
+        &claims,
+        false,
+        &[],
+    )
+    .unwrap_err();
+    assert!(
+        format!("{err:#}").contains("aren't uncommitted changes"),
+        "claiming a hunk that was committed already fails instead of committing nothing"
+    );
+    assert_eq!(get_virtual_branch(project, branch_id).commits.len(), 1);
+}
+
 fn commit_and_push_initial(repository: &TestProject) {
     repository.commit_all("initial commit");
     repository.push();
@@ -128,3 +322,46 @@ fn get_virtual_branch(project: &Project, branch_id: Id<Stack>) -> VirtualBranch
         .find(|b| b.id == branch_id)
         .unwrap()
 }
+
+#[test]
+fn should_add_co_authors_as_trailers() -> anyhow::Result<()> {
+    let Test {
+        project,
+        repository,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )?;
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())?;
+
+    fs::write(repository.path().join("file.txt"), "content")?;
+    let co_author = CoAuthor {
+        name: "Jane Doe".into(),
+        email: "jane@example.com".into(),
+    };
+    gitbutler_branch_actions::create_commit(
+        project,
+        branch_id,
+        "commit one",
+        None,
+        false,
+        std::slice::from_ref(&co_author),
+    )?;
+
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project)?;
+    let commit = &branches[0].commits[0];
+    assert_eq!(
+        commit.description,
+        "commit one\n\nCo-authored-by: Jane Doe <jane@example.com>\n"
+    );
+    assert_eq!(
+        gitbutler_branch_actions::co_authors::frequent(project)?,
+        vec![co_author],
+        "co-authors are remembered once the commit is made"
+    );
+    Ok(())
+}
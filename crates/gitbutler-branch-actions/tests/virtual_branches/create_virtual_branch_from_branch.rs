@@ -28,8 +28,10 @@ fn integration() {
         .unwrap();
 
         std::fs::write(repository.path().join("file.txt"), "first\n").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "first", None, false).unwrap();
-        gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, None).unwrap();
+        gitbutler_branch_actions::create_commit(project, branch_id, "first", None, false, &[])
+            .unwrap();
+        gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, false, None)
+            .unwrap();
 
         let branch = gitbutler_branch_actions::list_virtual_branches(project)
             .unwrap()
@@ -59,7 +61,8 @@ fn integration() {
         // add a commit
         std::fs::write(repository.path().join("file.txt"), "first\nsecond").unwrap();
 
-        gitbutler_branch_actions::create_commit(project, branch_id, "second", None, false).unwrap();
+        gitbutler_branch_actions::create_commit(project, branch_id, "second", None, false, &[])
+            .unwrap();
     }
 
     {
@@ -73,7 +76,8 @@ fn integration() {
 
     {
         // merge branch into master
-        gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, None).unwrap();
+        gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, false, None)
+            .unwrap();
 
         let branch = gitbutler_branch_actions::list_virtual_branches(project)
             .unwrap()
@@ -238,7 +242,7 @@ fn conflicts_with_commited() {
         let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
         assert_eq!(branches.len(), 1);
 
-        gitbutler_branch_actions::create_commit(project, branches[0].id, "hej", None, false)
+        gitbutler_branch_actions::create_commit(project, branches[0].id, "hej", None, false, &[])
             .unwrap();
     };
 
@@ -0,0 +1,110 @@
+use gitbutler_branch::{BranchCreateRequest, BranchUpdateRequest};
+use gitbutler_error::error::Code;
+use gitbutler_stack::{ForgeIdentifier, GitHubIdentifier};
+
+use super::*;
+
+#[test]
+fn drafts_are_only_pushed_when_allowed() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    gitbutler_branch_actions::create_commit(project, branch_id, "commit", None, false, &[])
+        .unwrap();
+
+    gitbutler_branch_actions::update_virtual_branch(
+        project,
+        BranchUpdateRequest {
+            id: branch_id,
+            draft: Some(true),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    assert!(branches[0].draft);
+
+    let err = gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, false, None)
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::DraftBranch));
+    let err =
+        gitbutler_branch_actions::stack::push_stack(project, branch_id, false, false).unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::DraftBranch));
+
+    gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, true, None).unwrap();
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    assert!(branches[0].commits[0].is_remote);
+}
+
+#[test]
+fn pull_requests_are_only_recorded_for_drafts_when_allowed() {
+    let Test { project, .. } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+    gitbutler_branch_actions::update_virtual_branch(
+        project,
+        BranchUpdateRequest {
+            id: branch_id,
+            draft: Some(true),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    let head_name = branches[0].series[0].name.clone();
+    let forge_id = ForgeIdentifier::GitHub(GitHubIdentifier { pr_number: 42 });
+
+    let err = gitbutler_branch_actions::stack::update_series_forge_id(
+        project,
+        branch_id,
+        head_name.clone(),
+        Some(forge_id.clone()),
+        false,
+    )
+    .unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::DraftBranch));
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    assert_eq!(branches[0].series[0].forge_id, None);
+
+    gitbutler_branch_actions::stack::update_series_forge_id(
+        project,
+        branch_id,
+        head_name.clone(),
+        Some(forge_id.clone()),
+        true,
+    )
+    .unwrap();
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    assert_eq!(branches[0].series[0].forge_id, Some(forge_id));
+
+    gitbutler_branch_actions::stack::update_series_forge_id(
+        project, branch_id, head_name, None, false,
+    )
+    .unwrap();
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    assert_eq!(
+        branches[0].series[0].forge_id, None,
+        "forgetting a pull request is always possible"
+    );
+}
@@ -0,0 +1,123 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_branch_actions::PatchFormat;
+use gitbutler_error::error::Code;
+
+use super::*;
+
+#[test]
+fn commits_are_written_as_numbered_patches() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id = gitbutler_branch_actions::create_virtual_branch(
+        project,
+        &BranchCreateRequest {
+            name: Some("greetings".into()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let out = tempfile::tempdir()?;
+    let err =
+        gitbutler_branch_actions::format_patch(project, branch_id, out.path(), PatchFormat::Files)
+            .unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::Validation));
+
+    fs::write(repository.path().join("greeting.txt"), "hello\n")?;
+    gitbutler_branch_actions::create_commit(
+        project,
+        branch_id,
+        "Add a greeting",
+        None,
+        false,
+        &[],
+    )?;
+    fs::write(repository.path().join("greeting.txt"), "hello\ngoodbye\n")?;
+    gitbutler_branch_actions::create_commit(
+        project,
+        branch_id,
+        "Say goodbye\n\nIt's polite.",
+        None,
+        false,
+        &[],
+    )?;
+
+    let paths =
+        gitbutler_branch_actions::format_patch(project, branch_id, out.path(), PatchFormat::Files)?;
+    assert_eq!(
+        paths,
+        vec![
+            out.path().join("0000-cover-letter.patch"),
+            out.path().join("0001-Add-a-greeting.patch"),
+            out.path().join("0002-Say-goodbye.patch"),
+        ]
+    );
+
+    let cover_letter = fs::read_to_string(&paths[0])?;
+    assert!(cover_letter.contains("Subject: [PATCH 0/2] greetings\n"));
+    assert!(cover_letter.contains("  Add a greeting\n  Say goodbye\n"));
+    let second = fs::read_to_string(&paths[2])?;
+    assert!(second.contains("Subject: [PATCH 2/2] Say goodbye\n"));
+    assert!(second.contains("It's polite."));
+    assert!(second.contains(" hello\n+goodbye\n"));
+    Ok(())
+}
+
+#[test]
+fn mbox_has_the_cover_letter_and_all_patches() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id = gitbutler_branch_actions::create_virtual_branch(
+        project,
+        &BranchCreateRequest {
+            name: Some("my stack".into()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    fs::write(repository.path().join("one.txt"), "one\n")?;
+    gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])?;
+    fs::write(repository.path().join("two.txt"), "two\n")?;
+    gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false, &[])?;
+
+    let out = tempfile::tempdir()?;
+    let paths =
+        gitbutler_branch_actions::format_patch(project, branch_id, out.path(), PatchFormat::Mbox)?;
+    assert_eq!(paths, vec![out.path().join("my-stack.mbox")]);
+
+    let mbox = fs::read_to_string(&paths[0])?;
+    let subjects = mbox
+        .lines()
+        .filter(|line| line.starts_with("Subject: "))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        subjects,
+        vec![
+            "Subject: [PATCH 0/2] my stack",
+            "Subject: [PATCH 1/2] commit one",
+            "Subject: [PATCH 2/2] commit two",
+        ]
+    );
+    Ok(())
+}
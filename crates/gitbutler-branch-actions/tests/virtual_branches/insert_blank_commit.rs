@@ -24,21 +24,27 @@ fn insert_blank_commit_down() -> anyhow::Result<()> {
     // create commit
     fs::write(repository.path().join("file.txt"), "content").unwrap();
     let _commit1_id =
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap();
 
     // create commit
     fs::write(repository.path().join("file2.txt"), "content2").unwrap();
     fs::write(repository.path().join("file3.txt"), "content3").unwrap();
     let commit2_id =
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false, &[])
             .unwrap();
 
     // create commit
     fs::write(repository.path().join("file4.txt"), "content4").unwrap();
-    let _commit3_id =
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit three", None, false)
-            .unwrap();
+    let _commit3_id = gitbutler_branch_actions::create_commit(
+        project,
+        branch_id,
+        "commit three",
+        None,
+        false,
+        &[],
+    )
+    .unwrap();
 
     gitbutler_branch_actions::insert_blank_commit(project, branch_id, commit2_id, 1).unwrap();
 
@@ -89,21 +95,27 @@ fn insert_blank_commit_up() -> anyhow::Result<()> {
     // create commit
     fs::write(repository.path().join("file.txt"), "content").unwrap();
     let _commit1_id =
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap();
 
     // create commit
     fs::write(repository.path().join("file2.txt"), "content2").unwrap();
     fs::write(repository.path().join("file3.txt"), "content3").unwrap();
     let commit2_id =
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false, &[])
             .unwrap();
 
     // create commit
     fs::write(repository.path().join("file4.txt"), "content4").unwrap();
-    let _commit3_id =
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit three", None, false)
-            .unwrap();
+    let _commit3_id = gitbutler_branch_actions::create_commit(
+        project,
+        branch_id,
+        "commit three",
+        None,
+        false,
+        &[],
+    )
+    .unwrap();
 
     gitbutler_branch_actions::insert_blank_commit(project, branch_id, commit2_id, -1).unwrap();
 
@@ -1,7 +1,8 @@
 use super::*;
 use gitbutler_branch::BranchCreateRequest;
 use gitbutler_branch_actions::{
-    create_commit, create_virtual_branch, list_virtual_branches, set_base_branch,
+    create_commit, create_virtual_branch, integrate_upstream, list_virtual_branches,
+    set_base_branch, upstream_integration::Resolution,
 };
 
 // This test ensures hunk lock detection works when a lines are shifted.
@@ -28,7 +29,7 @@ async fn hunk_locking_confused_by_line_number_shift() -> anyhow::Result<()> {
 
     // We're forced to call this before making a second commit.
     let (branches, _) = list_virtual_branches(project).unwrap();
-    create_commit(project, branches[0].id, "first commit", None, false)?;
+    create_commit(project, branches[0].id, "first commit", None, false, &[])?;
 
     let (branches, _) = list_virtual_branches(project).unwrap();
     assert_eq!(branches[0].files.len(), 0);
@@ -53,7 +54,7 @@ async fn hunk_locking_confused_by_line_number_shift() -> anyhow::Result<()> {
 
     // We're forced to call this before making a second commit.
     let (branches, _) = list_virtual_branches(project).unwrap();
-    create_commit(project, branches[1].id, "second commit", None, false)?;
+    create_commit(project, branches[1].id, "second commit", None, false, &[])?;
 
     // At this point we expect no uncommitted files, and one commit per branch.
     let (branches, _) = list_virtual_branches(project).unwrap();
@@ -104,7 +105,7 @@ async fn hunk_locking_with_deleted_lines_only() -> anyhow::Result<()> {
 
     // We have to do this before creating a commit.
     let (branches, _) = list_virtual_branches(project).unwrap();
-    create_commit(project, branches[0].id, "first commit", None, false)?;
+    create_commit(project, branches[0].id, "first commit", None, false, &[])?;
 
     let (branches, _) = list_virtual_branches(project).unwrap();
     assert_eq!(branches[0].commits.len(), 1);
@@ -129,3 +130,83 @@ async fn hunk_locking_with_deleted_lines_only() -> anyhow::Result<()> {
     assert_eq!(branches[1].files.len(), 0);
     Ok(())
 }
+
+// This test ensures the changes a stack merges from its upstream don't lock hunks to the merge.
+//
+// The merge commit differs from its first parent by the changes of the target, which the
+// workspace is based on already, so changing these lines again is not a change of the stack.
+#[test]
+fn hunks_are_not_locked_to_merges_of_the_target() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+    let mut lines = repository.gen_file("file.txt", 20);
+    repository.commit_all("initial commit");
+    repository.push();
+
+    set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())?;
+
+    lines[15] = "stack change".to_string();
+    repository.write_file("file.txt", &lines);
+    let (branches, _) = list_virtual_branches(project)?;
+    let stack_id = branches[0].id;
+    let stack_commit = create_commit(project, stack_id, "stack commit", None, false, &[])?;
+
+    // The target moves on with a change to another line, as if it was fetched.
+    let repo = git2::Repository::open(repository.path())?;
+    let base = repo
+        .find_reference("refs/remotes/origin/master")?
+        .peel_to_commit()?;
+    let mut upstream_lines: Vec<_> = (0..20).map(|i| format!("line {i}")).collect();
+    upstream_lines[2] = "upstream change".to_string();
+    let blob = repo.blob(upstream_lines.join("\n").as_bytes())?;
+    let mut tree = repo.treebuilder(Some(&base.tree()?))?;
+    tree.insert("file.txt", blob, git2::FileMode::Blob.into())?;
+    let tree = repo.find_tree(tree.write()?)?;
+    let signature = git2::Signature::now("upstream", "upstream@example.com")?;
+    let upstream_commit = repo.commit(
+        Some("refs/remotes/origin/master"),
+        &signature,
+        &signature,
+        "upstream commit",
+        &tree,
+        &[&base],
+    )?;
+
+    let (branches, _) = list_virtual_branches(project)?;
+    let resolution: Resolution = serde_json::from_value(serde_json::json!({
+        "branchId": stack_id,
+        "branchTree": branches[0].tree.to_string(),
+        "approach": { "type": "merge" },
+    }))?;
+    integrate_upstream(project, &[resolution], None)?;
+
+    let (branches, _) = list_virtual_branches(project)?;
+    let merge = &branches[0].commits[0];
+    assert_eq!(
+        merge.parent_ids,
+        [stack_commit, upstream_commit],
+        "the stack merged the target"
+    );
+    assert_eq!(branches[0].files.len(), 0);
+
+    lines[2] = "changed upstream change".to_string();
+    repository.write_file("file.txt", &lines);
+    let (branches, _) = list_virtual_branches(project)?;
+    assert!(
+        !branches[0].files[0].hunks[0].locked,
+        "the lines of the target are not locked to the merge"
+    );
+
+    lines[2] = "upstream change".to_string();
+    lines[15] = "changed stack change".to_string();
+    repository.write_file("file.txt", &lines);
+    let (branches, _) = list_virtual_branches(project)?;
+    let hunk_locks = branches[0].files[0].hunks[0].locked_to.clone().unwrap();
+    assert_eq!(hunk_locks.len(), 1);
+    assert_eq!(hunk_locks[0].branch_id, stack_id);
+    assert_eq!(hunk_locks[0].commit_id, stack_commit);
+    Ok(())
+}
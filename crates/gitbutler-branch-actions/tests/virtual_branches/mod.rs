@@ -55,11 +55,19 @@ impl Test {
     }
 }
 
+mod absorb;
+mod activity;
 mod amend;
+mod apply_patch;
 mod apply_virtual_branch;
+mod attachments;
+mod autostash;
 mod branch_trees;
+mod copy_commits_to_stack;
 mod create_commit;
 mod create_virtual_branch_from_branch;
+mod draft;
+mod format_patch;
 mod init;
 mod insert_blank_commit;
 mod list;
@@ -68,16 +76,24 @@ mod locking;
 mod move_commit_file;
 mod move_commit_to_vbranch;
 mod oplog;
+mod ranges_cache;
 mod references;
 mod reset_virtual_branch;
+mod revert_commit;
 mod save_and_unapply_virtual_branch;
 mod selected_for_changes;
 mod set_base_branch;
+mod shelves;
+mod shifted_claims;
 mod squash;
+mod stack_templates;
+mod submodule_bumps;
+mod touches;
 mod unapply_ownership;
 mod unapply_without_saving_virtual_branch;
 mod undo_commit;
 mod update_commit_message;
 mod upstream;
 mod verify_branch;
+mod workspace_merge_chain;
 mod workspace_migration;
@@ -26,9 +26,15 @@ fn no_diffs() {
 
     let source_branch_id = branches[0].id;
 
-    let commit_oid =
-        gitbutler_branch_actions::create_commit(project, source_branch_id, "commit", None, false)
-            .unwrap();
+    let commit_oid = gitbutler_branch_actions::create_commit(
+        project,
+        source_branch_id,
+        "commit",
+        None,
+        false,
+        &[],
+    )
+    .unwrap();
 
     let target_branch_id =
         gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
@@ -79,21 +85,27 @@ fn multiple_commits() {
     let source_branch_id = branches[0].id;
 
     // Create a commit on the source branch
-    gitbutler_branch_actions::create_commit(project, source_branch_id, "Add a", None, false)
+    gitbutler_branch_actions::create_commit(project, source_branch_id, "Add a", None, false, &[])
         .unwrap();
 
     std::fs::write(repository.path().join("b.txt"), "This is b").unwrap();
 
     // Create a second commit on the source branch, to be moved
-    let commit_oid =
-        gitbutler_branch_actions::create_commit(project, source_branch_id, "Add b", None, false)
-            .unwrap();
+    let commit_oid = gitbutler_branch_actions::create_commit(
+        project,
+        source_branch_id,
+        "Add b",
+        None,
+        false,
+        &[],
+    )
+    .unwrap();
 
     std::fs::write(repository.path().join("c.txt"), "This is c").unwrap();
 
     // Create a third commit on the source branch
 
-    gitbutler_branch_actions::create_commit(project, source_branch_id, "Add c", None, false)
+    gitbutler_branch_actions::create_commit(project, source_branch_id, "Add c", None, false, &[])
         .unwrap();
 
     let target_branch_id = gitbutler_branch_actions::create_virtual_branch(
@@ -108,7 +120,7 @@ fn multiple_commits() {
     std::fs::write(repository.path().join("d.txt"), "This is d").unwrap();
 
     // Create a commit on the destination branch
-    gitbutler_branch_actions::create_commit(project, target_branch_id, "Add d", None, false)
+    gitbutler_branch_actions::create_commit(project, target_branch_id, "Add d", None, false, &[])
         .unwrap();
 
     // Move the top commit from the source branch to the destination branch
@@ -166,15 +178,21 @@ fn multiple_commits_with_diffs() {
     let source_branch_id = branches[0].id;
 
     // Create a commit on the source branch
-    gitbutler_branch_actions::create_commit(project, source_branch_id, "Add a", None, false)
+    gitbutler_branch_actions::create_commit(project, source_branch_id, "Add a", None, false, &[])
         .unwrap();
 
     std::fs::write(repository.path().join("b.txt"), "This is b").unwrap();
 
     // Create as second commit on the source branch, to be moved
-    let commit_oid =
-        gitbutler_branch_actions::create_commit(project, source_branch_id, "Add b", None, false)
-            .unwrap();
+    let commit_oid = gitbutler_branch_actions::create_commit(
+        project,
+        source_branch_id,
+        "Add b",
+        None,
+        false,
+        &[],
+    )
+    .unwrap();
 
     // Uncommitted changes on the source branch
     std::fs::write(repository.path().join("c.txt"), "This is c").unwrap();
@@ -202,7 +220,7 @@ fn multiple_commits_with_diffs() {
     std::fs::write(repository.path().join("d.txt"), "This is d").unwrap();
 
     // Create a commit on the destination branch
-    gitbutler_branch_actions::create_commit(project, target_branch_id, "Add d", None, false)
+    gitbutler_branch_actions::create_commit(project, target_branch_id, "Add d", None, false, &[])
         .unwrap();
 
     // Uncommitted changes on the destination branch
@@ -283,9 +301,15 @@ fn diffs_on_source_branch() {
 
     let source_branch_id = branches[0].id;
 
-    let commit_oid =
-        gitbutler_branch_actions::create_commit(project, source_branch_id, "commit", None, false)
-            .unwrap();
+    let commit_oid = gitbutler_branch_actions::create_commit(
+        project,
+        source_branch_id,
+        "commit",
+        None,
+        false,
+        &[],
+    )
+    .unwrap();
 
     std::fs::write(
         repository.path().join("another file.txt"),
@@ -350,9 +374,15 @@ fn diffs_on_target_branch() {
 
     let source_branch_id = branches[0].id;
 
-    let commit_oid =
-        gitbutler_branch_actions::create_commit(project, source_branch_id, "commit", None, false)
-            .unwrap();
+    let commit_oid = gitbutler_branch_actions::create_commit(
+        project,
+        source_branch_id,
+        "commit",
+        None,
+        false,
+        &[],
+    )
+    .unwrap();
 
     let target_branch_id = gitbutler_branch_actions::create_virtual_branch(
         project,
@@ -422,9 +452,15 @@ fn diffs_on_both_branches() {
 
     let source_branch_id = branches[0].id;
 
-    let commit_oid =
-        gitbutler_branch_actions::create_commit(project, source_branch_id, "commit", None, false)
-            .unwrap();
+    let commit_oid = gitbutler_branch_actions::create_commit(
+        project,
+        source_branch_id,
+        "commit",
+        None,
+        false,
+        &[],
+    )
+    .unwrap();
 
     // Uncommitted changes on the source branch
     std::fs::write(
@@ -543,7 +579,7 @@ fn target_commit_locked_to_ancestors() {
 
     let source_branch_id = branches[0].id;
 
-    gitbutler_branch_actions::create_commit(project, source_branch_id, "Add a", None, false)
+    gitbutler_branch_actions::create_commit(project, source_branch_id, "Add a", None, false, &[])
         .unwrap();
 
     std::fs::write(repository.path().join("a.txt"), "This is a \n\n Updated").unwrap();
@@ -555,6 +591,7 @@ fn target_commit_locked_to_ancestors() {
         "Add b and update b",
         None,
         false,
+        &[],
     )
     .unwrap();
 
@@ -596,7 +633,7 @@ fn target_commit_locked_to_descendants() {
 
     let source_branch_id = branches[0].id;
 
-    gitbutler_branch_actions::create_commit(project, source_branch_id, "Add a", None, false)
+    gitbutler_branch_actions::create_commit(project, source_branch_id, "Add a", None, false, &[])
         .unwrap();
 
     std::fs::write(repository.path().join("b.txt"), "This is b").unwrap();
@@ -607,13 +644,21 @@ fn target_commit_locked_to_descendants() {
         "Add b and update b",
         None,
         false,
+        &[],
     )
     .unwrap();
 
     std::fs::write(repository.path().join("b.txt"), "This is b and an update").unwrap();
 
-    gitbutler_branch_actions::create_commit(project, source_branch_id, "Update b", None, false)
-        .unwrap();
+    gitbutler_branch_actions::create_commit(
+        project,
+        source_branch_id,
+        "Update b",
+        None,
+        false,
+        &[],
+    )
+    .unwrap();
 
     let target_branch_id =
         gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
@@ -653,9 +698,15 @@ fn locked_hunks_on_source_branch() {
 
     let source_branch_id = branches[0].id;
 
-    let commit_oid =
-        gitbutler_branch_actions::create_commit(project, source_branch_id, "commit", None, false)
-            .unwrap();
+    let commit_oid = gitbutler_branch_actions::create_commit(
+        project,
+        source_branch_id,
+        "commit",
+        None,
+        false,
+        &[],
+    )
+    .unwrap();
 
     std::fs::write(repository.path().join("file.txt"), "locked content").unwrap();
 
@@ -697,7 +748,7 @@ fn no_commit() {
 
     let source_branch_id = branches[0].id;
 
-    gitbutler_branch_actions::create_commit(project, source_branch_id, "commit", None, false)
+    gitbutler_branch_actions::create_commit(project, source_branch_id, "commit", None, false, &[])
         .unwrap();
 
     let target_branch_id =
@@ -739,9 +790,15 @@ fn no_branch() {
 
     let source_branch_id = branches[0].id;
 
-    let commit_oid =
-        gitbutler_branch_actions::create_commit(project, source_branch_id, "commit", None, false)
-            .unwrap();
+    let commit_oid = gitbutler_branch_actions::create_commit(
+        project,
+        source_branch_id,
+        "commit",
+        None,
+        false,
+        &[],
+    )
+    .unwrap();
 
     let id = StackId::generate();
     assert_eq!(
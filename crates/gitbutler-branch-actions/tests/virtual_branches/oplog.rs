@@ -43,7 +43,7 @@ fn workdir_vbranch_restore() -> anyhow::Result<()> {
             branch_id,
             &format!("commit {round}"),
             None,
-            false, /* run hook */
+            false, /* run hook */, &[]
         )?;
         assert_eq!(
             wd_file_count(&worktree_dir)?,
@@ -113,7 +113,7 @@ fn basic_oplog() -> anyhow::Result<()> {
     // create commit
     fs::write(repository.path().join("file.txt"), "content")?;
     let _commit1_id =
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)?;
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])?;
 
     // dont store large files
     let file_path = repository.path().join("large.txt");
@@ -128,7 +128,7 @@ fn basic_oplog() -> anyhow::Result<()> {
     fs::write(repository.path().join("file2.txt"), "content2")?;
     fs::write(repository.path().join("file3.txt"), "content3")?;
     let commit2_id =
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false)?;
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false, &[])?;
 
     // Create conflict state
     let conflicts_path = repository.path().join(".git").join("conflicts");
@@ -145,7 +145,7 @@ fn basic_oplog() -> anyhow::Result<()> {
 
     fs::write(repository.path().join("file4.txt"), "content4")?;
     let _commit3_id =
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit three", None, false)?;
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit three", None, false, &[])?;
 
     let branch = gitbutler_branch_actions::list_virtual_branches(project)?
         .0
@@ -265,7 +265,7 @@ fn restores_gitbutler_workspace() -> anyhow::Result<()> {
     // create commit
     fs::write(repository.path().join("file.txt"), "content")?;
     let _commit1_id =
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)?;
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])?;
 
     let repo = git2::Repository::open(&project.path)?;
 
@@ -279,7 +279,7 @@ fn restores_gitbutler_workspace() -> anyhow::Result<()> {
     // create second commit
     fs::write(repository.path().join("file.txt"), "changed content")?;
     let _commit2_id =
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false)?;
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false, &[])?;
 
     // check the workspace commit changed
     let head = repo.head().expect("never unborn");
@@ -401,3 +401,35 @@ fn head_corrupt_is_recreated_automatically() {
         "it should have just reset the oplog head, so only 1, not 2"
     );
 }
+
+#[test]
+fn snapshots_share_their_objects() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    assert_eq!(
+        project.snapshot_storage_stats()?.snapshots,
+        0,
+        "no oplog yet"
+    );
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )?;
+    fs::write(repository.path().join("file.txt"), make_lines(10))?;
+    gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())?;
+    gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())?;
+
+    let stats = project.snapshot_storage_stats()?;
+    assert_eq!(stats.snapshots, project.list_snapshots(10, None)?.len());
+    assert!(stats.unique_objects > stats.snapshots);
+    assert!(
+        stats.logical_bytes > stats.unique_bytes,
+        "the target tree is shared by all snapshots, and stored only once"
+    );
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_stack::StackId;
+
+use super::*;
+
+#[test]
+fn cached_ranges_follow_the_stacks_of_the_workspace() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )?;
+
+    let first =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())?;
+    fs::write(repository.path().join("first.txt"), "content")?;
+    let commit = gitbutler_branch_actions::create_commit(project, first, "one", None, false, &[])?;
+    gitbutler_branch_actions::list_virtual_branches(project)?;
+    assert_eq!(cached_heads(project)?, heads(&[(first, commit)]));
+
+    fs::remove_file(cache_path(project))?;
+    gitbutler_branch_actions::list_virtual_branches(project)?;
+    assert!(
+        !cache_path(project).exists(),
+        "the cache isn't written again while nothing changes"
+    );
+
+    // The head of the stack changes.
+    fs::write(repository.path().join("first.txt"), "changed content")?;
+    let commit = gitbutler_branch_actions::create_commit(project, first, "two", None, false, &[])?;
+    gitbutler_branch_actions::list_virtual_branches(project)?;
+    assert_eq!(cached_heads(project)?, heads(&[(first, commit)]));
+
+    // Stacks are added to and removed from the workspace.
+    let second = gitbutler_branch_actions::create_virtual_branch(
+        project,
+        &BranchCreateRequest {
+            selected_for_changes: Some(true),
+            ..Default::default()
+        },
+    )?;
+    fs::write(repository.path().join("second.txt"), "content")?;
+    let second_commit =
+        gitbutler_branch_actions::create_commit(project, second, "three", None, false, &[])?;
+    gitbutler_branch_actions::list_virtual_branches(project)?;
+    assert_eq!(
+        cached_heads(project)?,
+        heads(&[(first, commit), (second, second_commit)])
+    );
+
+    gitbutler_branch_actions::unapply_without_saving_virtual_branch(project, first)?;
+    gitbutler_branch_actions::list_virtual_branches(project)?;
+    assert_eq!(cached_heads(project)?, heads(&[(second, second_commit)]));
+    Ok(())
+}
+
+fn cache_path(project: &Project) -> PathBuf {
+    project.gb_dir().join("hunk_ranges.json")
+}
+
+/// The heads of the stacks in the cache on disk, by the id of their stack.
+fn cached_heads(project: &Project) -> anyhow::Result<BTreeMap<String, String>> {
+    let cache: serde_json::Value = serde_json::from_slice(&fs::read(cache_path(project))?)?;
+    Ok(cache["stacks"]
+        .as_object()
+        .expect("stacks are cached by their id")
+        .iter()
+        .map(|(stack_id, stack)| (stack_id.clone(), stack["head"].as_str().unwrap().to_owned()))
+        .collect())
+}
+
+fn heads(stacks: &[(StackId, git2::Oid)]) -> BTreeMap<String, String> {
+    stacks
+        .iter()
+        .map(|(stack_id, head)| (stack_id.to_string(), head.to_string()))
+        .collect()
+}
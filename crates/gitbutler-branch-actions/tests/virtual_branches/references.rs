@@ -232,8 +232,10 @@ mod push_virtual_branch {
 
         fs::write(repository.path().join("file.txt"), "content").unwrap();
 
-        gitbutler_branch_actions::create_commit(project, branch1_id, "test", None, false).unwrap();
-        gitbutler_branch_actions::push_virtual_branch(project, branch1_id, false, None).unwrap();
+        gitbutler_branch_actions::create_commit(project, branch1_id, "test", None, false, &[])
+            .unwrap();
+        gitbutler_branch_actions::push_virtual_branch(project, branch1_id, false, false, None)
+            .unwrap();
 
         let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
         assert_eq!(branches.len(), 1);
@@ -278,9 +280,9 @@ mod push_virtual_branch {
             )
             .unwrap();
             fs::write(repository.path().join("file.txt"), "content").unwrap();
-            gitbutler_branch_actions::create_commit(project, branch1_id, "test", None, false)
+            gitbutler_branch_actions::create_commit(project, branch1_id, "test", None, false, &[])
                 .unwrap();
-            gitbutler_branch_actions::push_virtual_branch(project, branch1_id, false, None)
+            gitbutler_branch_actions::push_virtual_branch(project, branch1_id, false, false, None)
                 .unwrap();
             branch1_id
         };
@@ -307,9 +309,9 @@ mod push_virtual_branch {
             )
             .unwrap();
             fs::write(repository.path().join("file.txt"), "updated content").unwrap();
-            gitbutler_branch_actions::create_commit(project, branch2_id, "test", None, false)
+            gitbutler_branch_actions::create_commit(project, branch2_id, "test", None, false, &[])
                 .unwrap();
-            gitbutler_branch_actions::push_virtual_branch(project, branch2_id, false, None)
+            gitbutler_branch_actions::push_virtual_branch(project, branch2_id, false, false, None)
                 .unwrap();
             branch2_id
         };
@@ -26,9 +26,15 @@ fn to_head() {
         fs::write(repository.path().join("file.txt"), "content").unwrap();
 
         // commit changes
-        let oid =
-            gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false)
-                .unwrap();
+        let oid = gitbutler_branch_actions::create_commit(
+            project,
+            branch1_id,
+            "commit",
+            None,
+            false,
+            &[],
+        )
+        .unwrap();
 
         let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
         assert_eq!(branches.len(), 1);
@@ -83,9 +89,15 @@ fn to_target() {
         fs::write(repository.path().join("file.txt"), "content").unwrap();
 
         // commit changes
-        let oid =
-            gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false)
-                .unwrap();
+        let oid = gitbutler_branch_actions::create_commit(
+            project,
+            branch1_id,
+            "commit",
+            None,
+            false,
+            &[],
+        )
+        .unwrap();
 
         let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
         assert_eq!(branches.len(), 1);
@@ -139,9 +151,15 @@ fn to_commit() {
 
         fs::write(repository.path().join("file.txt"), "content").unwrap();
 
-        let oid =
-            gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false)
-                .unwrap();
+        let oid = gitbutler_branch_actions::create_commit(
+            project,
+            branch1_id,
+            "commit",
+            None,
+            false,
+            &[],
+        )
+        .unwrap();
 
         let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
         assert_eq!(branches.len(), 1);
@@ -161,9 +179,15 @@ fn to_commit() {
         // commit some more
         fs::write(repository.path().join("file.txt"), "more content").unwrap();
 
-        let second_commit_oid =
-            gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false)
-                .unwrap();
+        let second_commit_oid = gitbutler_branch_actions::create_commit(
+            project,
+            branch1_id,
+            "commit",
+            None,
+            false,
+            &[],
+        )
+        .unwrap();
 
         let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
         assert_eq!(branches.len(), 1);
@@ -218,9 +242,15 @@ fn to_non_existing() {
         fs::write(repository.path().join("file.txt"), "content").unwrap();
 
         // commit changes
-        let oid =
-            gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false)
-                .unwrap();
+        let oid = gitbutler_branch_actions::create_commit(
+            project,
+            branch1_id,
+            "commit",
+            None,
+            false,
+            &[],
+        )
+        .unwrap();
 
         let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
         assert_eq!(branches.len(), 1);
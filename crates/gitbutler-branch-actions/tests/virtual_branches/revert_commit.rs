@@ -0,0 +1,80 @@
+use gitbutler_branch_actions::RevertOutcome;
+
+use super::Test;
+
+#[test]
+fn revert_adds_inverse_commit() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    std::fs::write(repository.path().join("file.txt"), "content").unwrap();
+
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    let branch_id = branches[0].id;
+    let commit_oid =
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit", None, false, &[])
+            .unwrap();
+
+    let outcome =
+        gitbutler_branch_actions::revert_commit(project, branch_id, commit_oid, false).unwrap();
+    let RevertOutcome::Reverted { commit_id } = outcome else {
+        panic!("expected a revert commit, got {outcome:?}");
+    };
+
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    assert_eq!(branches[0].commits.len(), 2);
+    assert_eq!(branches[0].commits[0].id, commit_id);
+    assert_eq!(
+        branches[0].commits[0].description.to_string(),
+        format!("Revert \"commit\"\n\nThis reverts commit {commit_oid}.\n")
+    );
+    assert_eq!(branches[0].commits[1].id, commit_oid);
+    assert_eq!(branches[0].files.len(), 0);
+    assert!(!repository.path().join("file.txt").exists());
+}
+
+#[test]
+fn independent_commit_is_dropped() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    std::fs::write(repository.path().join("file.txt"), "content").unwrap();
+
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    let branch_id = branches[0].id;
+    let commit_oid =
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit", None, false, &[])
+            .unwrap();
+
+    std::fs::write(repository.path().join("other.txt"), "content").unwrap();
+    gitbutler_branch_actions::create_commit(project, branch_id, "other", None, false, &[]).unwrap();
+
+    let outcome =
+        gitbutler_branch_actions::revert_commit(project, branch_id, commit_oid, true).unwrap();
+    assert_eq!(outcome, RevertOutcome::Dropped);
+
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    assert_eq!(branches[0].commits.len(), 1);
+    assert_eq!(branches[0].commits[0].description.to_string(), "other");
+    assert_eq!(branches[0].files.len(), 0);
+    assert!(!repository.path().join("file.txt").exists());
+    assert!(repository.path().join("other.txt").exists());
+}
@@ -356,8 +356,15 @@ fn new_locked_hunk_without_modifying_existing() {
     let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
     assert_eq!(branches[0].files.len(), 1);
 
-    gitbutler_branch_actions::create_commit(project, branches[0].id, "second commit", None, false)
-        .expect("failed to create commit");
+    gitbutler_branch_actions::create_commit(
+        project,
+        branches[0].id,
+        "second commit",
+        None,
+        false,
+        &[],
+    )
+    .expect("failed to create commit");
 
     let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
     assert_eq!(branches[0].files.len(), 0);
@@ -65,7 +65,8 @@ mod go_back_to_workspace {
         .unwrap();
 
         std::fs::write(repository.path().join("another file.txt"), "content").unwrap();
-        gitbutler_branch_actions::create_commit(project, vbranch_id, "one", None, false).unwrap();
+        gitbutler_branch_actions::create_commit(project, vbranch_id, "one", None, false, &[])
+            .unwrap();
 
         let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
         assert_eq!(branches.len(), 1);
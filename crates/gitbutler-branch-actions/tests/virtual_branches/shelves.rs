@@ -0,0 +1,90 @@
+use gitbutler_error::error::Code;
+
+use super::Test;
+
+#[test]
+fn shelved_changes_can_be_unshelved() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    std::fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    let branch_id = branches[0].id;
+
+    let shelf = gitbutler_branch_actions::shelve_changes(project, branch_id, "wip").unwrap();
+    assert_eq!(shelf.name, "wip");
+    assert_eq!(shelf.paths, vec![std::path::PathBuf::from("file.txt")]);
+    assert!(!repository.path().join("file.txt").exists());
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    assert_eq!(branches[0].files.len(), 0);
+    assert_eq!(
+        gitbutler_branch_actions::list_shelves(project, branch_id).unwrap(),
+        vec![shelf]
+    );
+
+    let err = gitbutler_branch_actions::shelve_changes(project, branch_id, "wip").unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::Validation));
+
+    gitbutler_branch_actions::unshelve_changes(project, branch_id, "wip").unwrap();
+    assert_eq!(
+        std::fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "content"
+    );
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    assert_eq!(branches[0].files.len(), 1);
+    assert!(gitbutler_branch_actions::list_shelves(project, branch_id)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn unshelving_conflicting_changes_fails() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    std::fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    let branch_id = branches[0].id;
+    gitbutler_branch_actions::create_commit(project, branch_id, "commit", None, false, &[])
+        .unwrap();
+
+    std::fs::write(repository.path().join("file.txt"), "shelved").unwrap();
+    gitbutler_branch_actions::shelve_changes(project, branch_id, "wip").unwrap();
+    assert_eq!(
+        std::fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "content"
+    );
+
+    std::fs::write(repository.path().join("file.txt"), "changed since").unwrap();
+    let err = gitbutler_branch_actions::unshelve_changes(project, branch_id, "wip").unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::Validation));
+    assert_eq!(
+        std::fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "changed since"
+    );
+    assert_eq!(
+        gitbutler_branch_actions::list_shelves(project, branch_id)
+            .unwrap()
+            .len(),
+        1,
+        "the shelf is kept"
+    );
+}
@@ -0,0 +1,146 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_branch_actions::upstream_integration::Resolution;
+use gitbutler_stack::StackId;
+
+use super::*;
+
+#[test]
+fn claims_follow_their_lines_when_updating_the_base() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+    let claimed = stack(repository, project)?;
+    let other = claim_change(repository, project, claimed)?;
+
+    // The target inserts lines above the claimed change, as if it was fetched.
+    commit_inserted_lines(repository, "refs/remotes/origin/master")?;
+
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project)?;
+    let resolutions = branches
+        .iter()
+        .map(|branch| {
+            serde_json::from_value(serde_json::json!({
+                "branchId": branch.id,
+                "branchTree": branch.tree.to_string(),
+                "approach": { "type": "rebase" },
+            }))
+        })
+        .collect::<Result<Vec<Resolution>, _>>()?;
+    gitbutler_branch_actions::integrate_upstream(project, &resolutions, None)?;
+
+    assert_claimed_by(project, claimed, other);
+    Ok(())
+}
+
+#[test]
+fn claims_follow_their_lines_when_restacking_onto_the_upstream() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+    let claimed = stack(repository, project)?;
+    fs::write(repository.path().join("other.txt"), "content")?;
+    gitbutler_branch_actions::create_commit(project, claimed, "commit", None, false, &[])?;
+    gitbutler_branch_actions::push_virtual_branch(project, claimed, false, false, None)?;
+    let other = claim_change(repository, project, claimed)?;
+
+    // The upstream of the stack inserts lines above the claimed change, as if it was fetched.
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project)?;
+    let upstream = branches
+        .iter()
+        .find(|branch| branch.id == claimed)
+        .and_then(|branch| branch.upstream.as_ref())
+        .expect("the stack was pushed")
+        .name
+        .to_string();
+    commit_inserted_lines(repository, &upstream)?;
+
+    gitbutler_branch_actions::integrate_upstream_commits(project, claimed, None)?;
+
+    assert_claimed_by(project, claimed, other);
+    Ok(())
+}
+
+/// Create a stack based on a target with a `file.txt` of 30 lines.
+fn stack(repository: &TestProject, project: &Project) -> anyhow::Result<StackId> {
+    repository.gen_file("file.txt", 30);
+    repository.commit_all("initial commit");
+    repository.push();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )?;
+    gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+}
+
+/// Change a line near the end of `file.txt` so it's claimed by the stack with `claimed`, and create
+/// another stack which is selected for changes. Returns the id of the other stack.
+fn claim_change(
+    repository: &TestProject,
+    project: &Project,
+    claimed: StackId,
+) -> anyhow::Result<StackId> {
+    let mut lines: Vec<_> = (0..30).map(|i| format!("line {i}")).collect();
+    lines[25] = "claimed change".to_string();
+    repository.write_file("file.txt", &lines);
+    gitbutler_branch_actions::list_virtual_branches(project)?;
+
+    let other = gitbutler_branch_actions::create_virtual_branch(
+        project,
+        &BranchCreateRequest {
+            selected_for_changes: Some(true),
+            ..Default::default()
+        },
+    )?;
+    assert_claimed_by(project, claimed, other);
+    Ok(other)
+}
+
+/// Commit ten lines inserted at the top of `file.txt` onto the commit `refname` points to, and
+/// point it to the new commit.
+fn commit_inserted_lines(repository: &TestProject, refname: &str) -> anyhow::Result<()> {
+    let repo = git2::Repository::open(repository.path())?;
+    let parent = repo.find_reference(refname)?.peel_to_commit()?;
+    let content = parent
+        .tree()?
+        .get_path(path::Path::new("file.txt"))?
+        .to_object(&repo)?
+        .peel_to_blob()?
+        .content()
+        .to_vec();
+    let mut lines: Vec<_> = (0..10).map(|i| format!("inserted line {i}\n")).collect();
+    lines.push(String::from_utf8(content)?);
+    let blob = repo.blob(lines.concat().as_bytes())?;
+    let mut tree = repo.treebuilder(Some(&parent.tree()?))?;
+    tree.insert("file.txt", blob, git2::FileMode::Blob.into())?;
+    let tree = repo.find_tree(tree.write()?)?;
+    let signature = git2::Signature::now("upstream", "upstream@example.com")?;
+    repo.commit(
+        Some(refname),
+        &signature,
+        &signature,
+        "insert lines",
+        &tree,
+        &[&parent],
+    )?;
+    Ok(())
+}
+
+/// Assert that the claimed change is still uncommitted in the stack with `claimed`, instead of
+/// being given to `other` as a new change.
+fn assert_claimed_by(project: &Project, claimed: StackId, other: StackId) {
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    let claimed = branches.iter().find(|branch| branch.id == claimed).unwrap();
+    assert_eq!(claimed.files.len(), 1);
+    assert_eq!(claimed.files[0].hunks.len(), 1);
+    assert!(claimed.files[0].hunks[0]
+        .diff
+        .to_string()
+        .contains("+claimed change"));
+    let other = branches.iter().find(|branch| branch.id == other).unwrap();
+    assert_eq!(other.files.len(), 0);
+}
@@ -1,4 +1,5 @@
 use gitbutler_branch::{BranchCreateRequest, BranchUpdateRequest};
+use gitbutler_stack::StackId;
 
 use super::*;
 
@@ -22,25 +23,32 @@ fn head() {
 
     {
         fs::write(repository.path().join("file one.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap()
     };
 
     {
         fs::write(repository.path().join("file two.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false, &[])
             .unwrap()
     };
 
     {
         fs::write(repository.path().join("file three.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit three", None, false)
-            .unwrap()
+        gitbutler_branch_actions::create_commit(
+            project,
+            branch_id,
+            "commit three",
+            None,
+            false,
+            &[],
+        )
+        .unwrap()
     };
 
     let commit_four_oid = {
         fs::write(repository.path().join("file four.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit four", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit four", None, false, &[])
             .unwrap()
     };
 
@@ -84,25 +92,32 @@ fn middle() {
 
     {
         fs::write(repository.path().join("file one.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap()
     };
 
     let commit_two_oid = {
         fs::write(repository.path().join("file two.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false, &[])
             .unwrap()
     };
 
     {
         fs::write(repository.path().join("file three.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit three", None, false)
-            .unwrap()
+        gitbutler_branch_actions::create_commit(
+            project,
+            branch_id,
+            "commit three",
+            None,
+            false,
+            &[],
+        )
+        .unwrap()
     };
 
     {
         fs::write(repository.path().join("file four.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit four", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit four", None, false, &[])
             .unwrap()
     };
 
@@ -155,27 +170,34 @@ fn forcepush_allowed() {
 
     {
         fs::write(repository.path().join("file one.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap()
     };
 
-    gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, None).unwrap();
+    gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, false, None).unwrap();
 
     let commit_two_oid = {
         fs::write(repository.path().join("file two.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false, &[])
             .unwrap()
     };
 
     {
         fs::write(repository.path().join("file three.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit three", None, false)
-            .unwrap()
+        gitbutler_branch_actions::create_commit(
+            project,
+            branch_id,
+            "commit three",
+            None,
+            false,
+            &[],
+        )
+        .unwrap()
     };
 
     {
         fs::write(repository.path().join("file four.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit four", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit four", None, false, &[])
             .unwrap()
     };
 
@@ -230,27 +252,34 @@ fn forcepush_forbidden() {
 
     {
         fs::write(repository.path().join("file one.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap()
     };
 
-    gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, None).unwrap();
+    gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, false, None).unwrap();
 
     let commit_two_oid = {
         fs::write(repository.path().join("file two.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false, &[])
             .unwrap()
     };
 
     {
         fs::write(repository.path().join("file three.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit three", None, false)
-            .unwrap()
+        gitbutler_branch_actions::create_commit(
+            project,
+            branch_id,
+            "commit three",
+            None,
+            false,
+            &[],
+        )
+        .unwrap()
     };
 
     {
         fs::write(repository.path().join("file four.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit four", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit four", None, false, &[])
             .unwrap()
     };
 
@@ -282,7 +311,7 @@ fn root_forbidden() {
 
     let commit_one_oid = {
         fs::write(repository.path().join("file one.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap()
     };
 
@@ -293,3 +322,242 @@ fn root_forbidden() {
         "can not squash root commit"
     );
 }
+
+#[test]
+fn rejects_messages_violating_the_policy() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+
+    fs::create_dir_all(repository.path().join(".gitbutler")).unwrap();
+    repository.write_file(
+        ".gitbutler/policy.toml",
+        &[
+            "[commit_message]".to_string(),
+            "conventional = true".to_string(),
+        ],
+    );
+
+    fs::write(repository.path().join("file one.txt"), "").unwrap();
+    gitbutler_branch_actions::create_commit(project, branch_id, "feat: one", None, false, &[])
+        .unwrap();
+    fs::write(repository.path().join("file two.txt"), "").unwrap();
+    let commit_two_oid =
+        gitbutler_branch_actions::create_commit(project, branch_id, "feat: two", None, false, &[])
+            .unwrap();
+
+    // The messages are joined by a single newline.
+    let err = gitbutler_branch_actions::squash(project, branch_id, commit_two_oid).unwrap_err();
+    assert!(format!("{err:#}").contains("must be followed by a blank line"));
+
+    let branch = list_branch(project, branch_id);
+    assert_eq!(branch.commits.len(), 2, "nothing was squashed");
+}
+
+#[test]
+fn selection_into_target() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+
+    let commit_one_oid = {
+        fs::write(repository.path().join("file one.txt"), "").unwrap();
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
+            .unwrap()
+    };
+
+    {
+        fs::write(repository.path().join("file two.txt"), "").unwrap();
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false, &[])
+            .unwrap()
+    };
+
+    let commit_three_oid = {
+        fs::write(repository.path().join("file three.txt"), "").unwrap();
+        gitbutler_branch_actions::create_commit(
+            project,
+            branch_id,
+            "commit three",
+            None,
+            false,
+            &[],
+        )
+        .unwrap()
+    };
+
+    {
+        fs::write(repository.path().join("file four.txt"), "").unwrap();
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit four", None, false, &[])
+            .unwrap()
+    };
+
+    gitbutler_branch_actions::squash_commits(
+        project,
+        branch_id,
+        commit_three_oid,
+        &[commit_one_oid],
+        Some("- {subject}"),
+    )
+    .unwrap();
+
+    let branch = gitbutler_branch_actions::list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+
+    let descriptions = branch
+        .commits
+        .iter()
+        .map(|c| c.description.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        descriptions,
+        vec![
+            "commit four",
+            "- commit three\n\n- commit one",
+            "commit two"
+        ]
+    );
+}
+
+#[test]
+fn autosquash_moves_series_heads_off_folded_commits() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    let stack_id = stack_from_template(
+        project,
+        repository,
+        r#"
+[[template.series]]
+name = "{name}-foo"
+commits = ["add foo", "fixup! add foo"]
+
+[[template.series]]
+name = "{name}-bar"
+commits = ["add bar"]
+"#,
+    );
+
+    assert_eq!(
+        gitbutler_branch_actions::autosquash(project, stack_id).unwrap(),
+        1
+    );
+    assert_eq!(
+        series_descriptions(project, stack_id),
+        [
+            ("x-bar".to_owned(), vec!["add bar".to_owned()]),
+            ("x-foo".to_owned(), vec!["add foo".to_owned()]),
+        ]
+    );
+}
+
+#[test]
+fn squash_commits_moves_series_heads_off_squashed_commits() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    let stack_id = stack_from_template(
+        project,
+        repository,
+        r#"
+[[template.series]]
+name = "{name}-foo"
+commits = ["add foo", "change foo"]
+
+[[template.series]]
+name = "{name}-bar"
+commits = ["add bar"]
+"#,
+    );
+    let branch = list_branch(project, stack_id);
+    let foo = &branch.series[1].patches;
+    assert_eq!(foo.len(), 2);
+
+    gitbutler_branch_actions::squash_commits(project, stack_id, foo[1].id, &[foo[0].id], None)
+        .unwrap();
+    assert_eq!(
+        series_descriptions(project, stack_id),
+        [
+            ("x-bar".to_owned(), vec!["add bar".to_owned()]),
+            ("x-foo".to_owned(), vec!["add foo\n\nchange foo".to_owned()]),
+        ]
+    );
+}
+
+/// Create a stack named `x` from a template with `series`.
+fn stack_from_template(project: &Project, repository: &TestProject, series: &str) -> StackId {
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    fs::create_dir_all(repository.path().join(".gitbutler")).unwrap();
+    fs::write(
+        repository.path().join(".gitbutler/stack-templates.toml"),
+        format!("[[template]]\nname = \"template\"\n{series}"),
+    )
+    .unwrap();
+    gitbutler_branch_actions::stack_templates::create_stack_from_template(project, "template", "x")
+        .unwrap()
+}
+
+fn list_branch(project: &Project, stack_id: StackId) -> gitbutler_branch_actions::VirtualBranch {
+    gitbutler_branch_actions::list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == stack_id)
+        .unwrap()
+}
+
+/// The names of the series of the stack, topmost first, with the messages of their commits.
+fn series_descriptions(project: &Project, stack_id: StackId) -> Vec<(String, Vec<String>)> {
+    list_branch(project, stack_id)
+        .series
+        .into_iter()
+        .map(|series| {
+            (
+                series.name,
+                series
+                    .patches
+                    .iter()
+                    .map(|commit| commit.description.to_string())
+                    .collect(),
+            )
+        })
+        .collect()
+}
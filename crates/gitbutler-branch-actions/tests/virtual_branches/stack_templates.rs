@@ -0,0 +1,214 @@
+use gitbutler_oplog::{entry::OperationKind, OplogExt};
+
+use super::*;
+
+#[test]
+fn create_stack_with_series_and_placeholder_commits() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    fs::create_dir_all(repository.path().join(".gitbutler")).unwrap();
+    fs::write(
+        repository.path().join(".gitbutler/stack-templates.toml"),
+        r#"
+[[template]]
+name = "migration + code"
+
+[[template.series]]
+name = "{name}-migration"
+commits = ["Migrate {name}"]
+
+[[template.series]]
+name = "{name}-code"
+description = "The implementation"
+commits = ["Implement {name}", "Test {name}"]
+"#,
+    )
+    .unwrap();
+
+    let stack_id = gitbutler_branch_actions::stack_templates::create_stack_from_template(
+        project,
+        "migration + code",
+        "login",
+    )
+    .unwrap();
+
+    let branch = gitbutler_branch_actions::list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == stack_id)
+        .unwrap();
+    assert_eq!(branch.name, "login");
+
+    // The topmost series comes first.
+    let series: Vec<_> = branch
+        .series
+        .iter()
+        .map(|series| {
+            (
+                series.name.as_str(),
+                series.description.as_deref(),
+                series
+                    .patches
+                    .iter()
+                    .map(|commit| commit.description.to_string())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        series,
+        [
+            (
+                "login-code",
+                Some("The implementation"),
+                vec!["Test login".to_owned(), "Implement login".to_owned()]
+            ),
+            ("login-migration", None, vec!["Migrate login".to_owned()]),
+        ]
+    );
+
+    assert!(
+        gitbutler_branch_actions::stack_templates::create_stack_from_template(
+            project, "unknown", "login"
+        )
+        .is_err(),
+        "only templates in the templates file can be used"
+    );
+}
+
+#[test]
+fn creating_a_stack_from_a_template_can_be_undone() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+    set_base_and_templates(repository, project);
+
+    gitbutler_branch_actions::stack_templates::create_stack_from_template(
+        project,
+        "two series",
+        "login",
+    )
+    .unwrap();
+
+    let snapshots = project.list_snapshots(10, None).unwrap();
+    assert_eq!(
+        snapshots[0]
+            .details
+            .as_ref()
+            .map(|details| details.operation),
+        Some(OperationKind::CreateBranch)
+    );
+}
+
+#[test]
+fn series_names_are_checked_against_the_policy() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+    set_base_and_templates(repository, project);
+    repository.write_file(
+        ".gitbutler/policy.toml",
+        &[r#"branch_name_pattern = "^login-""#.to_string()],
+    );
+    let stacks = stack_count(project);
+
+    let err = gitbutler_branch_actions::stack_templates::create_stack_from_template(
+        project,
+        "two series",
+        "signup",
+    )
+    .unwrap_err();
+    assert!(format!("{err:#}").contains("doesn't match the pattern"));
+    assert_eq!(stack_count(project), stacks, "nothing was created");
+
+    gitbutler_branch_actions::stack_templates::create_stack_from_template(
+        project,
+        "two series",
+        "login",
+    )
+    .unwrap();
+    assert_eq!(stack_count(project), stacks + 1);
+}
+
+#[test]
+fn stacks_are_removed_if_a_series_cannot_be_created() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+    set_base_and_templates(repository, project);
+    let stacks = stack_count(project);
+
+    assert!(
+        gitbutler_branch_actions::stack_templates::create_stack_from_template(
+            project,
+            "same series twice",
+            "login",
+        )
+        .is_err(),
+        "series can't share a name"
+    );
+    assert_eq!(
+        stack_count(project),
+        stacks,
+        "the half-built stack was removed"
+    );
+}
+
+fn set_base_and_templates(repository: &TestProject, project: &Project) {
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    fs::create_dir_all(repository.path().join(".gitbutler")).unwrap();
+    fs::write(
+        repository.path().join(".gitbutler/stack-templates.toml"),
+        r#"
+[[template]]
+name = "two series"
+
+[[template.series]]
+name = "{name}-first"
+
+[[template.series]]
+name = "{name}-second"
+
+[[template]]
+name = "same series twice"
+
+[[template.series]]
+name = "{name}"
+commits = ["First"]
+
+[[template.series]]
+name = "{name}"
+commits = ["Second"]
+"#,
+    )
+    .unwrap();
+}
+
+fn stack_count(project: &Project) -> usize {
+    gitbutler_branch_actions::list_virtual_branches(project)
+        .unwrap()
+        .0
+        .len()
+}
@@ -0,0 +1,188 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_branch_actions::{SeriesOrder, StackOrder};
+use gitbutler_stack::StackId;
+
+use super::*;
+
+#[test]
+fn reordering_keeps_bumps_of_a_submodule_in_order() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+    let origin = TestProject::default();
+    let Bumps {
+        submodule,
+        stack_id,
+        first,
+        second,
+        other,
+    } = bumps(repository, project, &origin)?;
+    let series = list_branch(project, stack_id).series[0].name.clone();
+    let reorder = |commit_ids: Vec<git2::Oid>| {
+        gitbutler_branch_actions::reorder_stack(
+            project,
+            stack_id,
+            StackOrder {
+                series: vec![SeriesOrder {
+                    name: series.clone(),
+                    commit_ids,
+                }],
+            },
+        )
+    };
+
+    let err = reorder(vec![other, first, second]).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("bumps the submodule at 'submodule'"),
+        "the second bump can't move before the first"
+    );
+    assert_eq!(
+        descriptions(project, stack_id),
+        ["add file", "bump two", "bump one"]
+    );
+
+    reorder(vec![second, other, first])?;
+    assert_eq!(
+        descriptions(project, stack_id),
+        ["bump two", "add file", "bump one"]
+    );
+    let head = repository.find_commit(list_branch(project, stack_id).head)?;
+    assert_eq!(
+        head.tree()?.get_path(path::Path::new("submodule"))?.id(),
+        submodule.head()?.peel_to_commit()?.id(),
+        "the submodule points to the last bump"
+    );
+    Ok(())
+}
+
+#[test]
+fn squashing_moves_bumps_of_a_submodule_as_a_whole() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+    let origin = TestProject::default();
+    let Bumps {
+        submodule,
+        stack_id,
+        first,
+        second,
+        other,
+    } = bumps(repository, project, &origin)?;
+
+    let err = gitbutler_branch_actions::squash_commits(project, stack_id, other, &[first], None)
+        .unwrap_err();
+    assert!(
+        format!("{err:#}").contains("would conflict in submodule"),
+        "the first bump can't move past the second"
+    );
+    assert_eq!(
+        descriptions(project, stack_id),
+        ["add file", "bump two", "bump one"]
+    );
+
+    let squashed =
+        gitbutler_branch_actions::squash_commits(project, stack_id, first, &[second], None)?;
+    assert_eq!(
+        descriptions(project, stack_id),
+        ["add file", "bump one\n\nbump two"]
+    );
+    assert_eq!(
+        repository
+            .find_commit(squashed)?
+            .tree()?
+            .get_path(path::Path::new("submodule"))?
+            .id(),
+        submodule.head()?.peel_to_commit()?.id(),
+        "the squashed commit points the submodule to the second bump"
+    );
+    Ok(())
+}
+
+/// A stack with two commits bumping the same submodule, and a commit changing a file on top.
+struct Bumps {
+    submodule: git2::Repository,
+    stack_id: StackId,
+    first: git2::Oid,
+    second: git2::Oid,
+    other: git2::Oid,
+}
+
+fn bumps(
+    repository: &TestProject,
+    project: &Project,
+    origin: &TestProject,
+) -> anyhow::Result<Bumps> {
+    let url: gitbutler_url::Url = origin.path().display().to_string().parse().unwrap();
+    repository.add_submodule(&url, path::Path::new("submodule"));
+    repository.commit_all("add submodule");
+    repository.push();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )?;
+    let stack_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())?;
+
+    let submodule = git2::Repository::open(repository.path().join("submodule"))?;
+    bump(&submodule, "one")?;
+    let first =
+        gitbutler_branch_actions::create_commit(project, stack_id, "bump one", None, false, &[])?;
+    bump(&submodule, "two")?;
+    let second =
+        gitbutler_branch_actions::create_commit(project, stack_id, "bump two", None, false, &[])?;
+    fs::write(repository.path().join("file.txt"), "content")?;
+    let other =
+        gitbutler_branch_actions::create_commit(project, stack_id, "add file", None, false, &[])?;
+
+    Ok(Bumps {
+        submodule,
+        stack_id,
+        first,
+        second,
+        other,
+    })
+}
+
+/// Commit a file called `name` in `submodule`, which moves the commit it's checked out at.
+fn bump(submodule: &git2::Repository, name: &str) -> anyhow::Result<()> {
+    let workdir = submodule.workdir().expect("submodules have a worktree");
+    fs::write(workdir.join(name), name)?;
+    let mut index = submodule.index()?;
+    index.add_path(path::Path::new(name))?;
+    index.write()?;
+    let tree = submodule.find_tree(index.write_tree()?)?;
+    let parent = submodule.head()?.peel_to_commit()?;
+    let signature = git2::Signature::now("submodule", "submodule@example.com")?;
+    submodule.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        name,
+        &tree,
+        &[&parent],
+    )?;
+    Ok(())
+}
+
+fn list_branch(project: &Project, stack_id: StackId) -> gitbutler_branch_actions::VirtualBranch {
+    gitbutler_branch_actions::list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == stack_id)
+        .unwrap()
+}
+
+/// The messages of the commits of the stack, newest first.
+fn descriptions(project: &Project, stack_id: StackId) -> Vec<String> {
+    list_branch(project, stack_id)
+        .commits
+        .iter()
+        .map(|commit| commit.description.to_string())
+        .collect()
+}
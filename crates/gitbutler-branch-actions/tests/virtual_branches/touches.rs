@@ -0,0 +1,58 @@
+use gitbutler_branch::BranchCreateRequest;
+
+use super::*;
+
+#[test]
+fn commits_touching_path() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+
+    let first = {
+        fs::write(repository.path().join("a.txt"), "a").unwrap();
+        gitbutler_branch_actions::create_commit(project, branch_id, "first", None, false, &[])
+            .unwrap()
+    };
+    {
+        fs::write(repository.path().join("b.txt"), "b").unwrap();
+        gitbutler_branch_actions::create_commit(project, branch_id, "second", None, false, &[])
+            .unwrap();
+    }
+    let third = {
+        fs::write(repository.path().join("a.txt"), "a2").unwrap();
+        gitbutler_branch_actions::create_commit(project, branch_id, "third", None, false, &[])
+            .unwrap()
+    };
+
+    let touching = |file: &str| {
+        gitbutler_branch_actions::list_commits_touching(project, path::Path::new(file))
+            .unwrap()
+            .into_iter()
+            .map(|touch| (touch.commit_id, touch.stack_id))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        touching("a.txt"),
+        vec![(third, Some(branch_id)), (first, Some(branch_id))]
+    );
+
+    gitbutler_branch_actions::undo_commit(project, branch_id, third).unwrap();
+    assert_eq!(
+        touching("a.txt"),
+        vec![(first, Some(branch_id))],
+        "commits no longer in the workspace are dropped"
+    );
+    assert!(touching("c.txt").is_empty());
+}
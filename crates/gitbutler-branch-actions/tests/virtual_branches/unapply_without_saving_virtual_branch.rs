@@ -26,6 +26,7 @@ fn should_unapply_diff() {
         "asdf",
         None,
         false,
+        &[],
     );
     assert!(c.is_ok());
 
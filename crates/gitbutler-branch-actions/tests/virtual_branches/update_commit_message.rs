@@ -23,20 +23,27 @@ fn head() {
 
     {
         fs::write(repository.path().join("file one.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap()
     };
 
     {
         fs::write(repository.path().join("file two.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false, &[])
             .unwrap()
     };
 
     let commit_three_oid = {
         fs::write(repository.path().join("file three.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit three", None, false)
-            .unwrap()
+        gitbutler_branch_actions::create_commit(
+            project,
+            branch_id,
+            "commit three",
+            None,
+            false,
+            &[],
+        )
+        .unwrap()
     };
     let commit_three = repository.find_commit(commit_three_oid).unwrap();
     let before_change_id = &commit_three.change_id();
@@ -95,20 +102,27 @@ fn middle() {
 
     {
         fs::write(repository.path().join("file one.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap()
     };
 
     let commit_two_oid = {
         fs::write(repository.path().join("file two.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false, &[])
             .unwrap()
     };
 
     {
         fs::write(repository.path().join("file three.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit three", None, false)
-            .unwrap()
+        gitbutler_branch_actions::create_commit(
+            project,
+            branch_id,
+            "commit three",
+            None,
+            false,
+            &[],
+        )
+        .unwrap()
     };
 
     gitbutler_branch_actions::update_commit_message(
@@ -166,11 +180,11 @@ fn forcepush_allowed() {
 
     let commit_one_oid = {
         fs::write(repository.path().join("file one.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap()
     };
 
-    gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, None).unwrap();
+    gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, false, None).unwrap();
 
     gitbutler_branch_actions::update_commit_message(
         project,
@@ -226,11 +240,11 @@ fn forcepush_forbidden() {
 
     let commit_one_oid = {
         fs::write(repository.path().join("file one.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap()
     };
 
-    gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, None).unwrap();
+    gitbutler_branch_actions::push_virtual_branch(project, branch_id, false, false, None).unwrap();
 
     assert_eq!(
         gitbutler_branch_actions::update_commit_message(
@@ -265,20 +279,27 @@ fn root() {
 
     let commit_one_oid = {
         fs::write(repository.path().join("file one.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap()
     };
 
     {
         fs::write(repository.path().join("file two.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit two", None, false, &[])
             .unwrap()
     };
 
     {
         fs::write(repository.path().join("file three.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit three", None, false)
-            .unwrap()
+        gitbutler_branch_actions::create_commit(
+            project,
+            branch_id,
+            "commit three",
+            None,
+            false,
+            &[],
+        )
+        .unwrap()
     };
 
     gitbutler_branch_actions::update_commit_message(
@@ -327,7 +348,7 @@ fn empty() {
 
     let commit_one_oid = {
         fs::write(repository.path().join("file one.txt"), "").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false)
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
             .unwrap()
     };
 
@@ -338,3 +359,53 @@ fn empty() {
         "commit message can not be empty"
     );
 }
+
+#[test]
+fn rejects_messages_violating_the_policy() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+
+    let branch_id =
+        gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+            .unwrap();
+
+    fs::write(repository.path().join("file one.txt"), "").unwrap();
+    let commit_one_oid =
+        gitbutler_branch_actions::create_commit(project, branch_id, "commit one", None, false, &[])
+            .unwrap();
+
+    fs::create_dir_all(repository.path().join(".gitbutler")).unwrap();
+    repository.write_file(
+        ".gitbutler/policy.toml",
+        &[
+            "[commit_message]".to_string(),
+            "conventional = true".to_string(),
+        ],
+    );
+
+    let err = gitbutler_branch_actions::update_commit_message(
+        project,
+        branch_id,
+        commit_one_oid,
+        "commit one updated",
+    )
+    .unwrap_err();
+    assert!(format!("{err:#}").contains("must be a conventional commit"));
+
+    gitbutler_branch_actions::update_commit_message(
+        project,
+        branch_id,
+        commit_one_oid,
+        "feat: commit one updated",
+    )
+    .unwrap();
+}
@@ -23,22 +23,25 @@ fn detect_upstream_commits() {
     let oid1 = {
         // create first commit
         fs::write(repository.path().join("file.txt"), "content").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false).unwrap()
+        gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false, &[])
+            .unwrap()
     };
 
     let oid2 = {
         // create second commit
         fs::write(repository.path().join("file.txt"), "content2").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false).unwrap()
+        gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false, &[])
+            .unwrap()
     };
 
     // push
-    gitbutler_branch_actions::push_virtual_branch(project, branch1_id, false, None).unwrap();
+    gitbutler_branch_actions::push_virtual_branch(project, branch1_id, false, false, None).unwrap();
 
     let oid3 = {
         // create third commit
         fs::write(repository.path().join("file.txt"), "content3").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false).unwrap()
+        gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false, &[])
+            .unwrap()
     };
 
     {
@@ -77,17 +80,19 @@ fn detect_integrated_commits() {
     let oid1 = {
         // create first commit
         fs::write(repository.path().join("file.txt"), "content").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false).unwrap()
+        gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false, &[])
+            .unwrap()
     };
 
     let oid2 = {
         // create second commit
         fs::write(repository.path().join("file.txt"), "content2").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false).unwrap()
+        gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false, &[])
+            .unwrap()
     };
 
     // push
-    gitbutler_branch_actions::push_virtual_branch(project, branch1_id, false, None).unwrap();
+    gitbutler_branch_actions::push_virtual_branch(project, branch1_id, false, false, None).unwrap();
 
     {
         // merge branch upstream
@@ -104,7 +109,8 @@ fn detect_integrated_commits() {
     let oid3 = {
         // create third commit
         fs::write(repository.path().join("file.txt"), "content3").unwrap();
-        gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false).unwrap()
+        gitbutler_branch_actions::create_commit(project, branch1_id, "commit", None, false, &[])
+            .unwrap()
     };
 
     {
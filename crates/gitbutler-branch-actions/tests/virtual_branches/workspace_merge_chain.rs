@@ -0,0 +1,96 @@
+use gitbutler_branch::BranchCreateRequest;
+
+use super::*;
+
+fn set_merge_chain(repository: &TestProject, enabled: bool) {
+    git2::Repository::open(repository.path())
+        .unwrap()
+        .config()
+        .unwrap()
+        .set_bool("gitbutler.workspaceMergeChain", enabled)
+        .unwrap();
+}
+
+fn workspace_commit(repository: &TestProject) -> (usize, git2::Oid, git2::Oid) {
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let commit = repo
+        .find_reference("refs/heads/gitbutler/workspace")
+        .unwrap()
+        .peel_to_commit()
+        .unwrap();
+    (
+        commit.parent_count(),
+        commit.parent_id(0).unwrap(),
+        commit.tree_id(),
+    )
+}
+
+#[test]
+fn workspace_commit_without_octopus_merges() {
+    let Test {
+        repository,
+        project,
+        ..
+    } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        project,
+        &"refs/remotes/origin/master".parse().unwrap(),
+    )
+    .unwrap();
+    set_merge_chain(repository, true);
+
+    for file in ["a.txt", "b.txt", "c.txt"] {
+        let branch_id = gitbutler_branch_actions::create_virtual_branch(
+            project,
+            &BranchCreateRequest {
+                selected_for_changes: Some(true),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        fs::write(repository.path().join(file), file).unwrap();
+        gitbutler_branch_actions::create_commit(project, branch_id, file, None, false, &[])
+            .unwrap();
+    }
+
+    let (parent_count, first_parent, chain_tree) = workspace_commit(repository);
+    assert_eq!(parent_count, 2);
+    {
+        let repo = git2::Repository::open(repository.path()).unwrap();
+        let merge = repo.find_commit(first_parent).unwrap();
+        assert_eq!(merge.parent_count(), 2);
+        assert_eq!(merge.summary(), Some("GitButler Workspace Merge"));
+        let tree = repo.find_tree(chain_tree).unwrap();
+        for file in ["a.txt", "b.txt", "c.txt"] {
+            assert!(tree.get_name(file).is_some(), "{file} is in the workspace");
+        }
+    }
+
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    assert_eq!(branches.len(), 3);
+    assert!(branches
+        .iter()
+        .all(|branch| branch.series[0].patches.len() == 1));
+
+    set_merge_chain(repository, false);
+    gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+    let (parent_count, _, octopus_tree) = workspace_commit(repository);
+    assert_eq!(parent_count, 3);
+    assert_eq!(octopus_tree, chain_tree, "both modes have the same tree");
+
+    set_merge_chain(repository, true);
+    gitbutler_branch_actions::create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+    fs::write(repository.path().join("d.txt"), "d").unwrap();
+    repository.commit_all("commit on top of the workspace");
+
+    let (branches, _) = gitbutler_branch_actions::list_virtual_branches(project).unwrap();
+    assert_eq!(
+        branches.len(),
+        6,
+        "the commit on top of the workspace is moved into a new branch"
+    );
+    assert_eq!(workspace_commit(repository).0, 2);
+}
@@ -25,6 +25,10 @@ pub enum Subcommands {
     /// List and restore snapshots.
     #[clap(visible_alias = "snapshots")]
     Snapshot(snapshot::Platform),
+    /// Summarize the workspace: stacks, their claims and unpushed commits.
+    Status(status::Platform),
+    /// Serve JSON-RPC requests on stdin and answer on stdout, for use by editor extensions.
+    Rpc,
 }
 
 pub mod vbranch {
@@ -124,12 +128,30 @@ pub mod project {
             /// The long name of the remote reference to track, like `refs/remotes/origin/main`.
             remote_ref_name: RemoteRefname,
         },
+        /// Check the project for problems which get in the way of GitButler.
+        Doctor {
+            /// Fix the problems which can be fixed automatically.
+            #[clap(long)]
+            fix: bool,
+        },
     }
 }
 
 pub mod snapshot {
+    use std::path::PathBuf;
+
     #[derive(Debug, clap::Parser)]
     pub struct Platform {
+        /// The location of the directory with the app data, whose settings decide how times are shown.
+        ///
+        /// Defaults to the standard location on this platform if unset.
+        #[clap(short = 'd', long, env = "GITBUTLER_CLI_DATA_DIR")]
+        pub app_data_dir: Option<PathBuf>,
+        /// A suffix like `dev` to use the settings of the development version of the application.
+        ///
+        /// The production version is used if unset.
+        #[clap(short = 's', long)]
+        pub app_suffix: Option<String>,
         #[clap(subcommand)]
         pub cmd: Option<SubCommands>,
     }
@@ -144,6 +166,17 @@ pub mod snapshot {
     }
 }
 
+pub mod status {
+    #[derive(Debug, clap::Parser)]
+    pub struct Platform {
+        /// Print a stable, line-oriented format meant to be parsed by scripts and shell prompts.
+        ///
+        /// The format is versioned through its first line and will not change within a version.
+        #[clap(long)]
+        pub porcelain: bool,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,18 +1,26 @@
 pub mod prepare;
 pub mod project;
+pub mod rpc;
+pub mod status;
 pub mod vbranch;
 
 pub mod snapshot {
+    use std::path::PathBuf;
+
     use anyhow::Result;
     use gitbutler_oplog::OplogExt;
     use gitbutler_project::Project;
 
-    pub fn list(project: Project) -> Result<()> {
+    pub fn list(
+        project: Project,
+        app_suffix: Option<String>,
+        app_data_dir: Option<PathBuf>,
+    ) -> Result<()> {
         let snapshots = project.list_snapshots(100, None)?;
+        let formatting = super::prepare::formatting(app_suffix, app_data_dir);
         for snapshot in snapshots {
-            let ts = chrono::DateTime::from_timestamp(snapshot.created_at.seconds(), 0);
-            let details = snapshot.details;
-            if let (Some(ts), Some(details)) = (ts, details) {
+            if let Some(details) = snapshot.details {
+                let ts = formatting.format_time(snapshot.created_at.seconds());
                 println!("{} {} {}", ts, snapshot.commit_id, details.operation);
             }
         }
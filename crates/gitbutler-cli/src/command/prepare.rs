@@ -2,6 +2,7 @@ use std::path::PathBuf;
 
 use anyhow::{bail, Context};
 use gitbutler_project::Project;
+use gitbutler_settings::format::Formatting;
 
 pub fn project_from_path(path: PathBuf) -> anyhow::Result<Project> {
     let worktree_dir = gix::discover(path)?
@@ -22,18 +23,7 @@ pub fn project_controller(
         std::fs::create_dir_all(&dir).context("Failed to assure the designated data-dir exists")?;
         dir
     } else {
-        dirs_next::data_dir()
-            .map(|dir| {
-                dir.join(format!(
-                    "com.gitbutler.app{}",
-                    app_suffix
-                        .map(|mut suffix| {
-                            suffix.insert(0, '.');
-                            suffix
-                        })
-                        .unwrap_or_default()
-                ))
-            })
+        gitbutler_settings::app_data_dir(app_suffix.as_deref())
             .context("no data-directory available on this platform")?
     };
     if !path.is_dir() {
@@ -42,3 +32,14 @@ pub fn project_controller(
     eprintln!("Using projects from '{}'", path.display());
     Ok(gitbutler_project::Controller::from_path(path))
 }
+
+/// Read the formatting preferences from the settings of the app with `app_suffix`, or of the one with
+/// the data in `app_data_dir`. Use the defaults if there are none.
+pub fn formatting(app_suffix: Option<String>, app_data_dir: Option<PathBuf>) -> Formatting {
+    let settings: serde_json::Value = app_data_dir
+        .or_else(|| gitbutler_settings::app_data_dir(app_suffix.as_deref()))
+        .and_then(|dir| std::fs::read(dir.join(gitbutler_settings::SETTINGS_FILE)).ok())
+        .and_then(|settings| serde_json::from_slice(&settings).ok())
+        .unwrap_or_default();
+    Formatting::from_settings(|key| settings.get(key)?.as_str().map(ToOwned::to_owned))
+}
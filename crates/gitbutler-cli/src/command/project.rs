@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
-use anyhow::{Context, Result};
-use gitbutler_project::Project;
+use anyhow::{bail, Context, Result};
+use gitbutler_project::{hygiene, Project};
 use gitbutler_reference::RemoteRefname;
 
 use crate::command::debug_print;
@@ -40,3 +40,24 @@ pub fn switch_to_workspace(project: Project, refname: RemoteRefname) -> Result<(
         &project, &refname,
     )?)
 }
+
+pub fn doctor(project: Project, fix: bool) -> Result<()> {
+    let mut issues = hygiene::verify(&project)?;
+    if fix && issues.iter().any(hygiene::Issue::is_fixable) {
+        hygiene::ensure(&project)?;
+        issues = hygiene::verify(&project)?;
+    }
+    if issues.is_empty() {
+        println!("No problems found");
+        return Ok(());
+    }
+    for issue in &issues {
+        let hint = if issue.is_fixable() && !fix {
+            " (fix with --fix)"
+        } else {
+            ""
+        };
+        println!("{issue}{hint}");
+    }
+    bail!("found {} problem(s)", issues.len())
+}
@@ -0,0 +1,396 @@
+//! A long-running JSON-RPC 2.0 service for editor extensions, speaking one JSON message per line
+//! on stdin and stdout.
+//!
+//! It works directly on top of `gitbutler-branch-actions`, so editors can use it without the app running.
+//! Supported methods are:
+//!
+//! * `claims` - list all applied stacks along with the uncommitted hunks they claim.
+//! * `attribution` `{ path }` - for each uncommitted hunk in `path`, the stack it belongs to and
+//!   the commits it is locked to.
+//! * `commitLines` `{ stackId, message, path, start, end, runHooks? }` - commit all hunks of `path`
+//!   which overlap the 1-based, inclusive line range `start..=end` to the given stack.
+//! * `subscribe` - start sending `claimsChanged` notifications with the result of `claims`
+//!   whenever it changes.
+//!
+//! Note that claims are tracked per hunk, so a selection of lines always commits the whole hunks it touches.
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use gitbutler_project::Project;
+use gitbutler_stack::{BranchOwnershipClaims, OwnershipClaim, StackId};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// How often to check for changed claims once a client has subscribed.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Error code for requests that can't be parsed as JSON, as defined by JSON-RPC 2.0.
+const PARSE_ERROR: i64 = -32700;
+/// Error code for unknown methods, as defined by JSON-RPC 2.0.
+const METHOD_NOT_FOUND: i64 = -32601;
+/// Error code for requests whose parameters don't match the method, as defined by JSON-RPC 2.0.
+const INVALID_PARAMS: i64 = -32602;
+/// Error code for failures while performing the request.
+const INTERNAL_ERROR: i64 = -32603;
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct AttributionParams {
+    path: PathBuf,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CommitLinesParams {
+    stack_id: StackId,
+    message: String,
+    path: PathBuf,
+    start: u32,
+    end: u32,
+    #[serde(default)]
+    run_hooks: bool,
+}
+
+/// Serve requests from stdin until it is closed.
+pub fn serve(project: Project) -> Result<()> {
+    let subscribed = Arc::new(AtomicBool::new(false));
+    for line in std::io::stdin().lock().lines() {
+        if let Some(response) = respond(&project, &line?, &subscribed) {
+            send(&response)?;
+        }
+    }
+    Ok(())
+}
+
+/// Handle the request on `line`, and return the response to it if it needs one.
+fn respond(project: &Project, line: &str, subscribed: &Arc<AtomicBool>) -> Option<Value> {
+    if line.trim().is_empty() {
+        return None;
+    }
+    let request: Request = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(err) => return Some(error_response(Value::Null, PARSE_ERROR, err.to_string())),
+    };
+    let response = handle(project, &request, subscribed);
+    // Requests without `id` are notifications, which never get a response.
+    let id = request.id?;
+    Some(match response {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, err)) => error_response(id, code, format!("{err:#}")),
+    })
+}
+
+fn handle(
+    project: &Project,
+    request: &Request,
+    subscribed: &Arc<AtomicBool>,
+) -> std::result::Result<Value, (i64, anyhow::Error)> {
+    let internal = |err| (INTERNAL_ERROR, err);
+    match request.method.as_str() {
+        "claims" => claims(project).map_err(internal),
+        "attribution" => {
+            let params = params::<AttributionParams>(&request.params)?;
+            attribution(project, params).map_err(internal)
+        }
+        "commitLines" => {
+            let params = params::<CommitLinesParams>(&request.params)?;
+            commit_lines(project, params).map_err(internal)
+        }
+        "subscribe" => {
+            if !subscribed.swap(true, Ordering::SeqCst) {
+                let project = project.clone();
+                std::thread::spawn(move || watch_claims(project));
+            }
+            Ok(Value::Bool(true))
+        }
+        unknown => Err((METHOD_NOT_FOUND, anyhow!("Unknown method '{unknown}'"))),
+    }
+}
+
+fn params<T: serde::de::DeserializeOwned>(
+    params: &Value,
+) -> std::result::Result<T, (i64, anyhow::Error)> {
+    T::deserialize(params).map_err(|err| (INVALID_PARAMS, err.into()))
+}
+
+fn claims(project: &Project) -> Result<Value> {
+    let (branches, _skipped) = gitbutler_branch_actions::list_virtual_branches(project)?;
+    let stacks: Vec<_> = branches
+        .iter()
+        .map(|branch| {
+            let claims: Vec<_> = branch
+                .ownership
+                .claims
+                .iter()
+                .map(|claim| {
+                    json!({
+                        "path": claim.file_path,
+                        "hunks": claim.hunks.iter().map(|hunk| json!({
+                            "start": hunk.start,
+                            "end": hunk.end,
+                        })).collect::<Vec<_>>(),
+                    })
+                })
+                .collect();
+            json!({
+                "stackId": branch.id,
+                "name": branch.name,
+                "default": branch.selected_for_changes,
+                "claims": claims,
+            })
+        })
+        .collect();
+    Ok(Value::Array(stacks))
+}
+
+fn attribution(project: &Project, params: AttributionParams) -> Result<Value> {
+    let (branches, _skipped) = gitbutler_branch_actions::list_virtual_branches(project)?;
+    let mut ranges = Vec::new();
+    for branch in &branches {
+        for file in branch.files.iter().filter(|file| file.path == params.path) {
+            for hunk in &file.hunks {
+                ranges.push(json!({
+                    "start": hunk.start,
+                    "end": hunk.end,
+                    "stackId": branch.id,
+                    "lockedTo": hunk.locked_to,
+                    "poisoned": hunk.poisoned,
+                }));
+            }
+        }
+    }
+    Ok(Value::Array(ranges))
+}
+
+fn commit_lines(project: &Project, params: CommitLinesParams) -> Result<Value> {
+    if params.start > params.end {
+        bail!("Line range {}-{} is empty", params.start, params.end);
+    }
+    let (branches, _skipped) = gitbutler_branch_actions::list_virtual_branches(project)?;
+    let branch = branches
+        .iter()
+        .find(|branch| branch.id == params.stack_id)
+        .with_context(|| format!("Stack {} is not applied", params.stack_id))?;
+    let hunks: Vec<_> = branch
+        .files
+        .iter()
+        .filter(|file| file.path == params.path)
+        .flat_map(|file| &file.hunks)
+        // Hunks span `start..end`, with `end` being exclusive.
+        .filter(|hunk| hunk.start <= params.end && params.start < hunk.end)
+        .map(|hunk| gitbutler_diff::Hunk::new(hunk.start, hunk.end, Some(hunk.hash)))
+        .collect::<Result<_>>()?;
+    if hunks.is_empty() {
+        bail!(
+            "No changes of stack '{}' in {}:{}-{}",
+            branch.name,
+            params.path.display(),
+            params.start,
+            params.end
+        );
+    }
+    let ownership = BranchOwnershipClaims {
+        claims: vec![OwnershipClaim {
+            file_path: params.path,
+            hunks,
+        }],
+    };
+    let commit_id = gitbutler_branch_actions::create_commit(
+        project,
+        params.stack_id,
+        &params.message,
+        Some(&ownership),
+        params.run_hooks,
+        &[],
+    )?;
+    Ok(json!({ "commitId": commit_id.to_string() }))
+}
+
+/// Send a `claimsChanged` notification each time the claims differ from what was sent last.
+fn watch_claims(project: Project) {
+    let mut last = None;
+    loop {
+        match claims(&project) {
+            Ok(current) if last.as_ref() != Some(&current) => {
+                let notification = json!({
+                    "jsonrpc": "2.0",
+                    "method": "claimsChanged",
+                    "params": current,
+                });
+                if send(&notification).is_err() {
+                    return;
+                }
+                last = Some(current);
+            }
+            Ok(_) => {}
+            Err(err) => tracing::warn!("Failed to list claims: {err:#}"),
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn error_response(id: Value, code: i64, message: String) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+/// Write `message` as a single line, holding the lock so messages from different threads don't interleave.
+fn send(message: &Value) -> Result<()> {
+    let mut out = std::io::stdout().lock();
+    serde_json::to_writer(&mut out, message)?;
+    out.write_all(b"\n")?;
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use gitbutler_branch::BranchCreateRequest;
+    use gitbutler_testsupport::{virtual_branches::set_test_target, Case, Suite};
+
+    use super::*;
+
+    fn call(project: &Project, request: Value) -> Value {
+        respond(
+            project,
+            &request.to_string(),
+            &Arc::new(AtomicBool::new(false)),
+        )
+        .expect("requests with an id get a response")
+    }
+
+    fn error_code(response: &Value) -> Option<i64> {
+        response["error"]["code"].as_i64()
+    }
+
+    #[test]
+    fn invalid_requests_are_mapped_to_their_error_codes() {
+        let project = Project::default();
+        let subscribed = Arc::new(AtomicBool::new(false));
+
+        let response = respond(&project, "{ not json", &subscribed).unwrap();
+        assert_eq!(
+            response["id"],
+            Value::Null,
+            "the id of the request is unknown"
+        );
+        assert_eq!(error_code(&response), Some(PARSE_ERROR));
+        assert_eq!(
+            respond(&project, "  ", &subscribed),
+            None,
+            "blank lines are skipped"
+        );
+
+        let response = call(
+            &project,
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "rebase" }),
+        );
+        assert_eq!(response["id"], 1);
+        assert_eq!(error_code(&response), Some(METHOD_NOT_FOUND));
+        assert_eq!(response["error"]["message"], "Unknown method 'rebase'");
+
+        for method in ["attribution", "commitLines"] {
+            let response = call(
+                &project,
+                json!({ "jsonrpc": "2.0", "id": 2, "method": method, "params": {} }),
+            );
+            assert_eq!(error_code(&response), Some(INVALID_PARAMS), "{method}");
+        }
+
+        let notification = json!({ "jsonrpc": "2.0", "method": "rebase" }).to_string();
+        assert_eq!(
+            respond(&project, &notification, &subscribed),
+            None,
+            "notifications never get a response, not even an error"
+        );
+    }
+
+    #[test]
+    fn requests_are_dispatched_to_the_workspace() -> Result<()> {
+        let suite = Suite::default();
+        let Case { project, ctx, .. } = &suite.new_case_with_files(HashMap::from([(
+            PathBuf::from("file.txt"),
+            "line1\nline2\nline3\n",
+        )]));
+
+        let response = call(
+            project,
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "claims" }),
+        );
+        assert_eq!(
+            error_code(&response),
+            Some(INTERNAL_ERROR),
+            "failures to perform the request are internal errors"
+        );
+
+        set_test_target(ctx)?;
+        let stack_id = gitbutler_branch_actions::create_virtual_branch(
+            project,
+            &BranchCreateRequest::default(),
+        )?;
+        std::fs::write(project.path.join("file.txt"), "line1\nchanged\nline3\n")?;
+
+        let response = call(
+            project,
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "claims" }),
+        );
+        assert_eq!(response["result"][0]["stackId"], json!(stack_id));
+        assert_eq!(response["result"][0]["claims"][0]["path"], "file.txt");
+
+        let response = call(
+            project,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 3,
+                "method": "attribution",
+                "params": { "path": "file.txt" },
+            }),
+        );
+        assert_eq!(response["result"][0]["stackId"], json!(stack_id));
+
+        let response = call(
+            project,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 4,
+                "method": "commitLines",
+                "params": {
+                    "stackId": stack_id,
+                    "message": "change line 2",
+                    "path": "file.txt",
+                    "start": 2,
+                    "end": 2,
+                },
+            }),
+        );
+        assert!(response["result"]["commitId"].is_string(), "{response}");
+
+        let response = call(
+            project,
+            json!({ "jsonrpc": "2.0", "id": 5, "method": "claims" }),
+        );
+        assert_eq!(
+            response["result"][0]["claims"],
+            json!([]),
+            "the committed hunk isn't claimed anymore"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+//! The `status` command, with a human-readable and a stable `--porcelain` output.
+//!
+//! ### Porcelain format (v1)
+//!
+//! Every line starts with a token identifying its kind, and fields are separated by a single space.
+//! Paths and names are always the last field. Like `git status --porcelain` does, they are quoted
+//! as a C string literal if they contain spaces, double quotes, backslashes, control characters or
+//! non-ASCII bytes, so `a "b"` is printed as `"a \"b\""` and `ä` as `"\303\244"`.
+//!
+//! ```text
+//! # gitbutler.status v1
+//! # target <base-sha> <behind> <target-branch>
+//! stack <stack-id> <default:0|1> <conflicted:0|1> <commits> <unpushed> <head-sha> <name>
+//! claim <stack-id> <start>-<end>[,<start>-<end>...] <path>
+//! ```
+//!
+//! The `target` line is omitted if no target is set. New line kinds may be added within a version,
+//! so parsers should ignore lines they don't know.
+use std::{borrow::Cow, fmt::Write as _, io::Write};
+
+use anyhow::Result;
+use gitbutler_branch_actions::VirtualBranch;
+use gitbutler_project::Project;
+use gitbutler_stack::{OwnershipClaim, StackId};
+
+/// The version of the porcelain format, printed as its first line.
+const PORCELAIN_VERSION: &str = "v1";
+
+pub fn porcelain(project: Project) -> Result<()> {
+    let base = gitbutler_branch_actions::get_base_branch_data(&project).ok();
+    let (branches, _skipped) = gitbutler_branch_actions::list_virtual_branches(&project)?;
+
+    let mut out = std::io::stdout().lock();
+    writeln!(out, "# gitbutler.status {PORCELAIN_VERSION}")?;
+    if let Some(base) = base {
+        writeln!(
+            out,
+            "# target {sha} {behind} {name}",
+            sha = base.base_sha,
+            behind = base.behind,
+            name = quoted(base.branch_name.as_bytes())
+        )?;
+    }
+    for branch in &branches {
+        writeln!(
+            out,
+            "stack {id} {default} {conflicted} {commits} {unpushed} {head} {name}",
+            id = branch.id,
+            default = u8::from(branch.selected_for_changes),
+            conflicted = u8::from(branch.conflicted),
+            commits = branch.commits.len(),
+            unpushed = unpushed_commits(branch),
+            head = branch.head,
+            name = quoted(branch.name.as_bytes())
+        )?;
+    }
+    for branch in &branches {
+        for claim in &branch.ownership.claims {
+            write_claim(&mut out, branch.id, claim)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_claim(out: &mut impl Write, stack_id: StackId, claim: &OwnershipClaim) -> Result<()> {
+    let hunks = claim
+        .hunks
+        .iter()
+        .map(|hunk| format!("{}-{}", hunk.start, hunk.end))
+        .collect::<Vec<_>>()
+        .join(",");
+    writeln!(
+        out,
+        "claim {stack_id} {hunks} {path}",
+        path = quoted(&gix::path::into_bstr(&claim.file_path))
+    )?;
+    Ok(())
+}
+
+/// Quote `name` as a C string literal if it can't be printed as is, the way Git quotes paths in
+/// the output of `git status --porcelain`.
+fn quoted(name: &[u8]) -> Cow<'_, str> {
+    let needs_quoting = |byte: u8| byte <= b' ' || byte >= 0x7f || byte == b'"' || byte == b'\\';
+    if !name.iter().copied().any(needs_quoting) {
+        // Only printable ASCII is left, so nothing is lost.
+        return String::from_utf8_lossy(name);
+    }
+
+    let mut out = String::from("\"");
+    for &byte in name {
+        match byte {
+            b'\x07' => out.push_str("\\a"),
+            b'\x08' => out.push_str("\\b"),
+            b'\t' => out.push_str("\\t"),
+            b'\n' => out.push_str("\\n"),
+            b'\x0b' => out.push_str("\\v"),
+            b'\x0c' => out.push_str("\\f"),
+            b'\r' => out.push_str("\\r"),
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b' ' => out.push(' '),
+            byte if needs_quoting(byte) => {
+                write!(out, "\\{byte:03o}").expect("writing to a string never fails")
+            }
+            byte => out.push(char::from(byte)),
+        }
+    }
+    out.push('"');
+    Cow::Owned(out)
+}
+
+pub fn human(project: Project) -> Result<()> {
+    let (branches, skipped) = gitbutler_branch_actions::list_virtual_branches(&project)?;
+    if branches.is_empty() {
+        println!("No stacks in the workspace");
+    }
+    for branch in &branches {
+        println!(
+            "{default} {name} ({commits} commits, {unpushed} unpushed){conflicted}",
+            default = if branch.selected_for_changes {
+                "*"
+            } else {
+                " "
+            },
+            name = branch.name,
+            commits = branch.commits.len(),
+            unpushed = unpushed_commits(branch),
+            conflicted = if branch.conflicted {
+                " [conflicted]"
+            } else {
+                ""
+            }
+        );
+        for claim in &branch.ownership.claims {
+            println!("      {}", claim.file_path.display());
+        }
+    }
+    if !skipped.is_empty() {
+        eprintln!(
+            "{} files could not be processed (binary or large size)",
+            skipped.len()
+        )
+    }
+    Ok(())
+}
+
+/// Count the commits of `branch` which are neither on the remote nor integrated.
+///
+/// The series carry the most accurate information, but they are optional, so fall back
+/// to the flat list of commits.
+fn unpushed_commits(branch: &VirtualBranch) -> usize {
+    if branch.series.is_empty() {
+        branch
+            .commits
+            .iter()
+            .filter(|commit| !commit.is_remote && !commit.is_integrated)
+            .count()
+    } else {
+        branch
+            .series
+            .iter()
+            .flat_map(|series| &series.patches)
+            .filter(|commit| commit.remote_commit_id.is_none() && !commit.is_integrated)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_quoted_like_git() {
+        assert_eq!(quoted(b"src/main.rs"), "src/main.rs");
+        assert_eq!(quoted(b"feat/my-branch"), "feat/my-branch");
+        assert_eq!(quoted(b"my file.txt"), r#""my file.txt""#);
+        assert_eq!(quoted(br#"a "b"\c"#), r#""a \"b\"\\c""#);
+        assert_eq!(quoted(b"tab\tnew\nline"), r#""tab\tnew\nline""#);
+        assert_eq!(quoted("\u{e4}".as_bytes()), r#""\303\244""#);
+        assert_eq!(quoted(b"\x7f"), r#""\177""#);
+    }
+
+    #[test]
+    fn claims_have_one_line_each() -> Result<()> {
+        let stack_id = StackId::generate();
+        let mut out = Vec::new();
+        write_claim(&mut out, stack_id, &"src/main.rs:1-5,10-12".parse()?)?;
+        write_claim(&mut out, stack_id, &"new\nline.txt:3-4".parse()?)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            format!(
+                "claim {stack_id} 1-5,10-12 src/main.rs\nclaim {stack_id} 3-4 \"new\\nline.txt\"\n"
+            )
+        );
+        Ok(())
+    }
+}
@@ -85,6 +85,7 @@ fn set_default_branch(project: &Project, branch: &Stack) -> Result<()> {
             upstream: None,
             selected_for_changes: Some(true),
             allow_rebasing: None,
+            draft: None,
         },
     )
 }
@@ -142,6 +143,7 @@ pub fn commit(project: Project, branch_name: String, message: String) -> Result<
         &message,
         Some(&populated_branch.ownership),
         run_hooks,
+        &[],
     )?)
 }
 
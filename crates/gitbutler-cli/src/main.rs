@@ -3,7 +3,7 @@ use anyhow::Result;
 mod args;
 use args::Args;
 
-use crate::args::{project, snapshot, vbranch};
+use crate::args::{project, snapshot, status, vbranch};
 
 mod command;
 
@@ -53,6 +53,10 @@ fn main() -> Result<()> {
                 let project = command::prepare::project_from_path(args.current_dir)?;
                 command::project::switch_to_workspace(project, remote_ref_name)
             }
+            Some(project::SubCommands::Doctor { fix }) => {
+                let project = command::prepare::project_from_path(args.current_dir)?;
+                command::project::doctor(project, fix)
+            }
             Some(project::SubCommands::Add {
                 switch_to_workspace,
                 path,
@@ -65,15 +69,31 @@ fn main() -> Result<()> {
                 command::project::list(ctrl)
             }
         },
-        args::Subcommands::Snapshot(snapshot::Platform { cmd }) => {
+        args::Subcommands::Snapshot(snapshot::Platform {
+            app_data_dir,
+            app_suffix,
+            cmd,
+        }) => {
             let project = command::prepare::project_from_path(args.current_dir)?;
             match cmd {
                 Some(snapshot::SubCommands::Restore { snapshot_id }) => {
                     command::snapshot::restore(project, snapshot_id)
                 }
-                None => command::snapshot::list(project),
+                None => command::snapshot::list(project, app_suffix, app_data_dir),
             }
         }
+        args::Subcommands::Status(status::Platform { porcelain }) => {
+            let project = command::prepare::project_from_path(args.current_dir)?;
+            if porcelain {
+                command::status::porcelain(project)
+            } else {
+                command::status::human(project)
+            }
+        }
+        args::Subcommands::Rpc => {
+            let project = command::prepare::project_from_path(args.current_dir)?;
+            command::rpc::serve(project)
+        }
     }
 }
 
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use gitbutler_error::error::Code;
 use gitbutler_project::Project;
 
 pub struct CommandContext {
@@ -11,7 +12,17 @@ pub struct CommandContext {
 impl CommandContext {
     /// Open the repository identified by `project` and perform some checks.
     pub fn open(project: &Project) -> Result<Self> {
-        let repo = git2::Repository::open(&project.path)?;
+        let repo = match git2::Repository::open(&project.path) {
+            Ok(repo) => repo,
+            Err(err) if err.code() == git2::ErrorCode::Owner => {
+                return Err(anyhow::Error::from(err).context(Code::RepositoryUntrusted));
+            }
+            Err(err) => {
+                // Give a clear reason if the repository is unreadable as it's stored in an unsupported way.
+                gitbutler_project::assure_supported_format(&project.path)?;
+                return Err(err.into());
+            }
+        };
 
         // XXX(qix-): This is a temporary measure to disable GC on the project repository.
         // XXX(qix-): We do this because the internal repository we use to store the "virtual"
@@ -20,6 +20,8 @@ const V2_HEADERS_VERSION: &str = "2";
 
 const V2_CHANGE_ID_HEADER: &str = "gitbutler-change-id";
 const V2_CONFLICTED_HEADER: &str = "gitbutler-conflicted";
+const V2_AUTO_RESOLVED_HEADER: &str = "gitbutler-auto-resolved";
+const V2_COPIED_FROM_HEADER: &str = "gitbutler-copied-from";
 #[derive(Debug, Clone)]
 pub struct CommitHeadersV2 {
     /// A property we can use to determine if two different commits are
@@ -30,6 +32,13 @@ pub struct CommitHeadersV2 {
     /// commit. This is only written if the property is present. Conflicted
     /// commits should never make it into the main trunk.
     pub conflicted: Option<u64>,
+    /// The paths of files with conflicts which were resolved automatically, as both sides only
+    /// differed in whitespace. It's kept when rebasing so it can be audited, and is only written
+    /// if not empty.
+    pub auto_resolved: Vec<String>,
+    /// The ID of the commit this one is a copy of, if it was copied from another stack. It's kept
+    /// when rebasing so copies can be traced back, and is only written if present.
+    pub copied_from: Option<String>,
 }
 
 impl Default for CommitHeadersV2 {
@@ -38,6 +47,8 @@ impl Default for CommitHeadersV2 {
             // Change ID using base16 encoding
             change_id: Uuid::new_v4().to_string(),
             conflicted: None,
+            auto_resolved: Vec::new(),
+            copied_from: None,
         }
     }
 }
@@ -47,6 +58,8 @@ impl From<CommitHeadersV1> for CommitHeadersV2 {
         CommitHeadersV2 {
             change_id: commit_headers_v1.change_id,
             conflicted: None,
+            auto_resolved: Vec::new(),
+            copied_from: None,
         }
     }
 }
@@ -64,6 +77,15 @@ impl From<CommitHeadersV2> for Vec<(BString, BString)> {
         if let Some(conflicted) = hdr.conflicted {
             out.push((V2_CONFLICTED_HEADER.into(), conflicted.to_string().into()));
         }
+        if !hdr.auto_resolved.is_empty() {
+            out.push((
+                V2_AUTO_RESOLVED_HEADER.into(),
+                hdr.auto_resolved.join("\n").into(),
+            ));
+        }
+        if let Some(copied_from) = hdr.copied_from {
+            out.push((V2_COPIED_FROM_HEADER.into(), copied_from.into()));
+        }
         out
     }
 }
@@ -92,9 +114,26 @@ impl HasCommitHeaders for git2::Commit<'_> {
                     Err(_) => None,
                 };
 
+                // These headers are informational, so if they can't be read the commit is treated
+                // as if it didn't have them instead of losing all of its headers.
+                let auto_resolved = self
+                    .header_field_bytes(V2_AUTO_RESOLVED_HEADER)
+                    .ok()
+                    .and_then(|value| {
+                        Some(value.as_str()?.lines().map(ToOwned::to_owned).collect())
+                    })
+                    .unwrap_or_default();
+
+                let copied_from = self
+                    .header_field_bytes(V2_COPIED_FROM_HEADER)
+                    .ok()
+                    .and_then(|value| value.as_str().map(ToOwned::to_owned));
+
                 Some(CommitHeadersV2 {
                     change_id,
                     conflicted,
+                    auto_resolved,
+                    copied_from,
                 })
             } else {
                 // Must be for a version we don't recognise
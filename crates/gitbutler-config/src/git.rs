@@ -10,12 +10,32 @@ pub struct GbConfig {
     pub signing_format: Option<String>,
     pub gpg_program: Option<String>,
     pub gpg_ssh_program: Option<String>,
+    /// Also store the attachments of commits as notes on them, so they are available to other Git tools.
+    pub mirror_attachments_to_notes: Option<bool>,
+    /// Sign the workspace commit and the other commits GitButler creates for its own references.
+    /// If unset, they are left unsigned, even if `sign_commits` is set.
+    pub sign_workspace_commits: Option<bool>,
+    /// Build the workspace commit from a chain of merges with two parents each instead of one merge of
+    /// all applied branches, for tools that mishandle octopus merges.
+    pub workspace_merge_chain: Option<bool>,
 }
+
+impl GbConfig {
+    /// Whether the commits GitButler creates for its own references should be signed, which they
+    /// are only if asked to explicitly.
+    pub fn signs_workspace_commits(&self) -> bool {
+        self.sign_workspace_commits.unwrap_or(false)
+    }
+}
+
 const SIGN_COMMITS: &str = "gitbutler.signCommits";
 const SIGNING_KEY: &str = "user.signingKey";
 const SIGNING_FORMAT: &str = "gpg.format";
 const GPG_PROGRAM: &str = "gpg.program";
 const GPG_SSH_PROGRAM: &str = "gpg.ssh.program";
+const MIRROR_ATTACHMENTS_TO_NOTES: &str = "gitbutler.mirrorAttachmentsToNotes";
+const SIGN_WORKSPACE_COMMITS: &str = "gitbutler.signWorkspaceCommits";
+const WORKSPACE_MERGE_CHAIN: &str = "gitbutler.workspaceMergeChain";
 
 pub trait GitConfig {
     fn gb_config(&self) -> Result<GbConfig>;
@@ -29,12 +49,18 @@ impl GitConfig for git2::Repository {
         let signing_format = get_string(self, SIGNING_FORMAT)?;
         let gpg_program = get_string(self, GPG_PROGRAM)?;
         let gpg_ssh_program = get_string(self, GPG_SSH_PROGRAM)?;
+        let mirror_attachments_to_notes = get_bool(self, MIRROR_ATTACHMENTS_TO_NOTES)?;
+        let sign_workspace_commits = get_bool(self, SIGN_WORKSPACE_COMMITS)?;
+        let workspace_merge_chain = get_bool(self, WORKSPACE_MERGE_CHAIN)?;
         Ok(GbConfig {
             sign_commits,
             signing_key,
             signing_format,
             gpg_program,
             gpg_ssh_program,
+            mirror_attachments_to_notes,
+            sign_workspace_commits,
+            workspace_merge_chain,
         })
     }
     fn set_gb_config(&self, config: GbConfig) -> Result<()> {
@@ -53,6 +79,19 @@ impl GitConfig for git2::Repository {
         if let Some(gpg_ssh_program) = config.gpg_ssh_program {
             set_local_string(self, GPG_SSH_PROGRAM, &gpg_ssh_program)?;
         }
+        if let Some(mirror_attachments_to_notes) = config.mirror_attachments_to_notes {
+            set_local_bool(
+                self,
+                MIRROR_ATTACHMENTS_TO_NOTES,
+                mirror_attachments_to_notes,
+            )?;
+        }
+        if let Some(sign_workspace_commits) = config.sign_workspace_commits {
+            set_local_bool(self, SIGN_WORKSPACE_COMMITS, sign_workspace_commits)?;
+        }
+        if let Some(workspace_merge_chain) = config.workspace_merge_chain {
+            set_local_bool(self, WORKSPACE_MERGE_CHAIN, workspace_merge_chain)?;
+        }
         Ok(())
     }
 }
@@ -135,6 +135,9 @@ pub enum Code {
     CommitMergeConflictFailure,
     ProjectMissing,
     AuthorMissing,
+    DraftBranch,
+    RepositoryUntrusted,
+    RepositoryFormatUnsupported,
 }
 
 impl std::fmt::Display for Code {
@@ -149,6 +152,9 @@ impl std::fmt::Display for Code {
             Code::CommitMergeConflictFailure => "errors.commit.merge_conflict_failure",
             Code::AuthorMissing => "errors.git.author_missing",
             Code::ProjectMissing => "errors.projects.missing",
+            Code::DraftBranch => "errors.branch.draft",
+            Code::RepositoryUntrusted => "errors.projects.untrusted",
+            Code::RepositoryFormatUnsupported => "errors.projects.unsupported_format",
         };
         f.write_str(code)
     }
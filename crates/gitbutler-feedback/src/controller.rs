@@ -6,6 +6,9 @@ use gitbutler_project::ProjectId;
 
 use crate::zipper::Zipper;
 
+/// The directory within the logs directory which holds the profiles of slow operations.
+pub const PROFILES_DIR: &str = "profiles";
+
 pub struct Archival {
     pub cache_dir: PathBuf,
     pub logs_dir: PathBuf,
@@ -27,4 +30,10 @@ impl Archival {
     pub fn logs_archive(&self) -> Result<PathBuf> {
         self.zipper().zip(&self.logs_dir).map_err(Into::into)
     }
+
+    pub fn profiles_archive(&self) -> Result<PathBuf> {
+        self.zipper()
+            .zip(self.logs_dir.join(PROFILES_DIR))
+            .map_err(Into::into)
+    }
 }
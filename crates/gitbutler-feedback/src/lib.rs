@@ -1,3 +1,3 @@
 mod controller;
-pub use controller::Archival;
+pub use controller::{Archival, PROFILES_DIR};
 mod zipper;
@@ -0,0 +1,405 @@
+//! A shared HTTP client for forge APIs which keeps polling within the limits of the forge.
+//!
+//! * Requests to the same host are spaced out by at least [`MIN_REQUEST_INTERVAL`].
+//! * Responses carrying an `ETag` are cached and revalidated with `If-None-Match`, which
+//!   GitHub doesn't count against the rate limit if the resource didn't change. As they may contain
+//!   private data and are persisted, only [`MAX_CACHE_ENTRIES`] responses of up to
+//!   [`MAX_CACHED_BODY_LEN`] bytes are kept, each for at most [`MAX_CACHE_AGE`].
+//! * If the forge asks to back off, via `Retry-After` or an exhausted `X-RateLimit-Remaining`,
+//!   all requests to that host wait until the given time, and the failed request is retried.
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use reqwest::header::{HeaderMap, ETAG, IF_NONE_MATCH, LINK, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+
+const USER_AGENT: &str = "GitButler Client";
+/// The minimal time between two requests to the same host.
+pub const MIN_REQUEST_INTERVAL: Duration = Duration::from_millis(250);
+/// How often a request is retried after the forge asked to back off.
+const MAX_RETRIES: usize = 2;
+/// The longest time to wait for a backoff before giving up, to not block the UI for too long.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// The most responses that are cached, the least recently stored ones are dropped first.
+pub const MAX_CACHE_ENTRIES: usize = 512;
+/// The largest response body that is cached.
+pub const MAX_CACHED_BODY_LEN: usize = 256 * 1024;
+/// How long a cached response may be revalidated, after which it is fetched again.
+pub const MAX_CACHE_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForgeResponse {
+    pub status: u16,
+    pub body: String,
+    /// The `Link` header, which points to the other pages of paginated responses.
+    pub link: Option<String>,
+    /// `true` if the forge reported the resource as unchanged and `body` comes from the cache.
+    pub from_cache: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedResponse {
+    etag: String,
+    body: String,
+    #[serde(default)]
+    link: Option<String>,
+    /// When the response was stored, in seconds since the Unix epoch.
+    #[serde(default)]
+    stored_at: u64,
+}
+
+impl CachedResponse {
+    fn is_expired(&self, now: u64) -> bool {
+        now.saturating_sub(self.stored_at) > MAX_CACHE_AGE.as_secs()
+    }
+}
+
+#[derive(Debug, Default)]
+struct HostState {
+    last_request: Option<Instant>,
+    blocked_until: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    hosts: HashMap<String, HostState>,
+    cache: HashMap<String, CachedResponse>,
+    /// Incremented whenever the cache changes, to not persist outdated snapshots of it.
+    cache_generation: u64,
+}
+
+pub struct ForgeClient {
+    http: reqwest::Client,
+    /// Where the cache is persisted, or `None` to only keep it in memory.
+    cache_path: Option<PathBuf>,
+    state: Mutex<State>,
+    /// The generation of the cache that was persisted last, held while persisting.
+    persisted_generation: Mutex<u64>,
+}
+
+impl ForgeClient {
+    /// Create a new client which persists its response cache at `cache_path`, loading what's there already.
+    pub fn new(cache_path: Option<PathBuf>) -> Self {
+        let mut cache: HashMap<String, CachedResponse> = cache_path
+            .as_ref()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        let now = unix_now();
+        cache.retain(|_, cached| !cached.is_expired(now));
+        // Forges like GitHub reject requests without a user agent.
+        let http = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .build()
+            .unwrap_or_default();
+        ForgeClient {
+            http,
+            cache_path,
+            state: Mutex::new(State {
+                hosts: HashMap::new(),
+                cache,
+                cache_generation: 0,
+            }),
+            persisted_generation: Mutex::new(0),
+        }
+    }
+
+    /// Perform a `GET` request to `url`, authenticated with `token` if given.
+    pub async fn get(&self, url: &str, token: Option<&str>) -> Result<ForgeResponse> {
+        let parsed = url::Url::parse(url).with_context(|| format!("Invalid URL '{url}'"))?;
+        let host = parsed.host_str().unwrap_or_default().to_owned();
+        // Tokens may see different content, so they must not share cache entries.
+        let cache_key = format!(
+            "{}:{url}",
+            token.map_or_else(|| "anonymous".to_owned(), fingerprint)
+        );
+
+        let mut attempt = 0;
+        loop {
+            self.wait_for_host(&host).await?;
+
+            let cached_etag = {
+                let state = self.state.lock().await;
+                state
+                    .cache
+                    .get(&cache_key)
+                    .filter(|cached| !cached.is_expired(unix_now()))
+                    .map(|cached| cached.etag.clone())
+            };
+            let mut request = self.http.get(parsed.clone());
+            if let Some(token) = token {
+                request = request.bearer_auth(token);
+            }
+            if let Some(etag) = cached_etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            let response = request
+                .send()
+                .await
+                .with_context(|| format!("Request to {host} failed"))?;
+
+            let status = response.status();
+            let backoff = backoff_from_headers(status, response.headers(), SystemTime::now());
+            if let Some(backoff) = backoff {
+                let mut state = self.state.lock().await;
+                state.hosts.entry(host.clone()).or_default().blocked_until =
+                    Some(Instant::now() + backoff);
+            }
+
+            if status == StatusCode::NOT_MODIFIED {
+                let state = self.state.lock().await;
+                if let Some(cached) = state.cache.get(&cache_key) {
+                    return Ok(ForgeResponse {
+                        status: StatusCode::OK.as_u16(),
+                        body: cached.body.clone(),
+                        link: cached.link.clone(),
+                        from_cache: true,
+                    });
+                }
+            }
+
+            if is_rate_limited(status) && backoff.is_some() && attempt < MAX_RETRIES {
+                attempt += 1;
+                tracing::warn!(%host, attempt, "rate limited by forge, retrying");
+                continue;
+            }
+
+            let header = |name: reqwest::header::HeaderName| {
+                response
+                    .headers()
+                    .get(name)
+                    .and_then(|value| value.to_str().ok())
+                    .map(ToOwned::to_owned)
+            };
+            let etag = header(ETAG);
+            let link = header(LINK);
+            let body = response
+                .text()
+                .await
+                .context("Failed to read response body")?;
+            if let (true, Some(etag)) = (status.is_success(), etag) {
+                if body.len() <= MAX_CACHED_BODY_LEN {
+                    self.store(
+                        cache_key,
+                        CachedResponse {
+                            etag,
+                            body: body.clone(),
+                            link: link.clone(),
+                            stored_at: unix_now(),
+                        },
+                    )
+                    .await;
+                }
+            }
+            return Ok(ForgeResponse {
+                status: status.as_u16(),
+                body,
+                link,
+                from_cache: false,
+            });
+        }
+    }
+
+    /// Sleep until we may send the next request to `host`, and reserve the slot.
+    async fn wait_for_host(&self, host: &str) -> Result<()> {
+        let wait_until = {
+            let mut state = self.state.lock().await;
+            let host_state = state.hosts.entry(host.to_owned()).or_default();
+            let now = Instant::now();
+            let next_slot = host_state
+                .last_request
+                .map_or(now, |last| last + MIN_REQUEST_INTERVAL);
+            let wait_until = next_slot.max(host_state.blocked_until.unwrap_or(now));
+            if wait_until.saturating_duration_since(now) > MAX_BACKOFF {
+                anyhow::bail!("{host} is rate limited, try again later");
+            }
+            host_state.last_request = Some(wait_until);
+            wait_until
+        };
+        tokio::time::sleep_until(wait_until.into()).await;
+        Ok(())
+    }
+
+    /// Cache `response` under `key`, dropping expired and the oldest responses to stay within
+    /// the bounds of the cache, and persist it.
+    async fn store(&self, key: String, response: CachedResponse) {
+        let snapshot = {
+            let mut state = self.state.lock().await;
+            state.cache.insert(key, response);
+            evict(&mut state.cache, unix_now());
+            state.cache_generation += 1;
+            self.cache_path
+                .is_some()
+                .then(|| (state.cache_generation, serde_json::to_vec(&state.cache)))
+        };
+        let (Some(path), Some((generation, data))) = (self.cache_path.clone(), snapshot) else {
+            return;
+        };
+        // Writing happens outside of the state lock, and never replaces a newer snapshot.
+        let mut persisted_generation = self.persisted_generation.lock().await;
+        if *persisted_generation >= generation {
+            return;
+        }
+        let write = |data| {
+            let path = path.clone();
+            tokio::task::spawn_blocking(move || gitbutler_fs::create_dirs_then_write(path, data))
+        };
+        let result = match data {
+            Ok(data) => write(data)
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|result| result.map_err(anyhow::Error::from)),
+            Err(err) => Err(err.into()),
+        };
+        match result {
+            Ok(()) => *persisted_generation = generation,
+            // The cache is an optimization, so failing to write it is fine.
+            Err(err) => tracing::warn!(?path, "failed to persist forge response cache: {err:#}"),
+        }
+    }
+}
+
+/// Drop the expired responses of `cache`, and then the oldest ones until at most
+/// [`MAX_CACHE_ENTRIES`] remain.
+fn evict(cache: &mut HashMap<String, CachedResponse>, now: u64) {
+    cache.retain(|_, cached| !cached.is_expired(now));
+    if cache.len() <= MAX_CACHE_ENTRIES {
+        return;
+    }
+    let mut by_age: Vec<_> = cache
+        .iter()
+        .map(|(key, cached)| (cached.stored_at, key.clone()))
+        .collect();
+    by_age.sort_unstable();
+    for (_, key) in by_age.into_iter().take(cache.len() - MAX_CACHE_ENTRIES) {
+        cache.remove(&key);
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs())
+}
+
+fn is_rate_limited(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::FORBIDDEN
+}
+
+/// Determine how long to back off from a host, based on `Retry-After`
+/// or, if the rate limit is exhausted, `X-RateLimit-Reset`.
+fn backoff_from_headers(
+    status: StatusCode,
+    headers: &HeaderMap,
+    now: SystemTime,
+) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    if is_rate_limited(status) {
+        if let Some(seconds) = header(RETRY_AFTER.as_str()).and_then(|v| v.trim().parse().ok()) {
+            return Some(Duration::from_secs(seconds));
+        }
+    }
+    if header("x-ratelimit-remaining")?.trim() != "0" {
+        return None;
+    }
+    let reset: u64 = header("x-ratelimit-reset")?.trim().parse().ok()?;
+    let now = now.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(Duration::from_secs(reset.saturating_sub(now)))
+}
+
+/// A stable identifier of `token` which doesn't reveal it, as the cache keys are persisted.
+fn fingerprint(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    fn cached(stored_at: u64) -> CachedResponse {
+        CachedResponse {
+            etag: "etag".into(),
+            body: "{}".into(),
+            link: None,
+            stored_at,
+        }
+    }
+
+    #[test]
+    fn cache_drops_expired_and_oldest_responses() {
+        let now = MAX_CACHE_AGE.as_secs() + 10_000;
+        let mut cache: HashMap<_, _> = (0..MAX_CACHE_ENTRIES + 2)
+            .map(|i| (format!("fresh-{i}"), cached(now - i as u64)))
+            .collect();
+        cache.insert("expired".into(), cached(now - MAX_CACHE_AGE.as_secs() - 1));
+
+        evict(&mut cache, now);
+        assert_eq!(cache.len(), MAX_CACHE_ENTRIES);
+        assert!(!cache.contains_key("expired"));
+        for oldest in [MAX_CACHE_ENTRIES, MAX_CACHE_ENTRIES + 1] {
+            assert!(!cache.contains_key(&format!("fresh-{oldest}")));
+        }
+        assert!(cache.contains_key("fresh-0"));
+    }
+
+    #[test]
+    fn token_fingerprints_are_stable() {
+        assert_eq!(
+            fingerprint("token"),
+            "3c469e9d6c5875d37a43f353d4f88e61fcf812c66eee3457465a40b0da4153e0"
+        );
+    }
+
+    #[test]
+    fn retry_after_when_rate_limited() {
+        let backoff = backoff_from_headers(
+            StatusCode::TOO_MANY_REQUESTS,
+            &headers(&[("retry-after", "30")]),
+            UNIX_EPOCH,
+        );
+        assert_eq!(backoff, Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn retry_after_is_ignored_on_success() {
+        let backoff = backoff_from_headers(
+            StatusCode::OK,
+            &headers(&[("retry-after", "30")]),
+            UNIX_EPOCH,
+        );
+        assert_eq!(backoff, None);
+    }
+
+    #[test]
+    fn exhausted_rate_limit_waits_for_reset() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000);
+        let exhausted = headers(&[
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", "1042"),
+        ]);
+        assert_eq!(
+            backoff_from_headers(StatusCode::OK, &exhausted, now),
+            Some(Duration::from_secs(42))
+        );
+        let remaining = headers(&[
+            ("x-ratelimit-remaining", "12"),
+            ("x-ratelimit-reset", "1042"),
+        ]);
+        assert_eq!(backoff_from_headers(StatusCode::OK, &remaining, now), None);
+    }
+}
@@ -0,0 +1,88 @@
+//! A log of the [`ForgeEvent`]s received via webhooks, so they can be shown along with other activity
+//! after the fact, and not only while the app is listening.
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::webhook::ForgeEvent;
+
+/// How many events are kept, the oldest ones are dropped first.
+const MAX_EVENTS: usize = 1000;
+
+/// A [`ForgeEvent`] along with the time it was received.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceivedEvent {
+    /// When the event was received, in seconds since the Unix epoch.
+    pub received_at: i64,
+    pub event: ForgeEvent,
+}
+
+pub struct EventLog {
+    path: PathBuf,
+    /// Serializes writers, as each write replaces the whole file.
+    lock: Mutex<()>,
+}
+
+impl EventLog {
+    /// Create a log persisted at `path`.
+    pub fn new(path: PathBuf) -> Self {
+        EventLog {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Add `event` as received just now.
+    pub fn record(&self, event: ForgeEvent) -> Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|err| err.into_inner());
+        let mut events = self.list()?;
+        events.push(ReceivedEvent {
+            received_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs() as i64),
+            event,
+        });
+        if events.len() > MAX_EVENTS {
+            events.drain(..events.len() - MAX_EVENTS);
+        }
+        gitbutler_fs::create_dirs_then_write(&self.path, serde_json::to_vec(&events)?)?;
+        Ok(())
+    }
+
+    /// All recorded events, the oldest first.
+    pub fn list(&self) -> Result<Vec<ReceivedEvent>> {
+        match std::fs::read(&self.path) {
+            Ok(data) => Ok(serde_json::from_slice(&data)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_and_list() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("forge-events-{}.json", std::process::id()));
+        let log = EventLog::new(path.clone());
+        assert!(log.list()?.is_empty());
+
+        let event = ForgeEvent::Review {
+            repository: "gitbutlerapp/gitbutler".into(),
+            number: 42,
+            state: "approved".into(),
+        };
+        log.record(event.clone())?;
+        let events = log.list()?;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, event);
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
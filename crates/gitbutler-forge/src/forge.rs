@@ -9,3 +9,24 @@ pub enum ForgeName {
     Bitbucket,
     Azure,
 }
+
+/// The state of a review unit (eg. a Pull Request) in the merge queue of a forge.
+///
+/// Forges without merge queues simply never report a status.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Clone)]
+#[serde(tag = "type", content = "subject", rename_all = "camelCase")]
+pub enum MergeQueueStatus {
+    /// Waiting to be merged, with the `position` in the queue if the forge provides it (`0` is next).
+    Queued { position: Option<usize> },
+    /// Checks are running on the merge group, and it will be merged once they pass.
+    Merging,
+    /// Removed from the queue without being merged, eg. because checks failed.
+    Failed,
+}
+
+impl MergeQueueStatus {
+    /// Returns `true` if the forge is still going to merge the review unit.
+    pub fn is_pending(&self) -> bool {
+        matches!(self, Self::Queued { .. } | Self::Merging)
+    }
+}
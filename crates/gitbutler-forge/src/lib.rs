@@ -1,2 +1,5 @@
+pub mod client;
+pub mod event_log;
 pub mod forge;
 pub mod review;
+pub mod webhook;
@@ -0,0 +1,356 @@
+//! An optional listener for forge webhooks, to learn about review and CI changes without polling.
+//!
+//! The listener speaks just enough HTTP to accept `POST` deliveries from GitHub, or from a relay
+//! forwarding them, and turns the interesting ones into [`ForgeEvent`]s.
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+
+/// Deliveries larger than this are rejected. The events we care about are a few kilobytes,
+/// so there is no need to accept the 25MB GitHub can send.
+const MAX_BODY_SIZE: u64 = 1024 * 1024;
+/// The most we read for the request line and headers together.
+const MAX_HEADER_SIZE: u64 = 16 * 1024;
+/// How long a single read may block, so a stalled sender can't hold up the listener.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A change at the forge which affects the review state of a branch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "subject", rename_all = "camelCase")]
+pub enum ForgeEvent {
+    /// A Pull Request was opened, closed, merged, or otherwise changed.
+    #[serde(rename_all = "camelCase")]
+    PullRequest {
+        repository: String,
+        number: u64,
+        action: String,
+        merged: bool,
+    },
+    /// A review was submitted or dismissed.
+    #[serde(rename_all = "camelCase")]
+    Review {
+        repository: String,
+        number: u64,
+        state: String,
+    },
+    /// A check or commit status changed for a commit.
+    #[serde(rename_all = "camelCase")]
+    Checks {
+        repository: String,
+        sha: String,
+        status: String,
+        conclusion: Option<String>,
+    },
+    /// An entry of the merge queue was created or removed.
+    #[serde(rename_all = "camelCase")]
+    MergeGroup {
+        repository: String,
+        action: String,
+        head_sha: String,
+    },
+}
+
+/// Turn a GitHub webhook delivery of kind `event` (the `X-GitHub-Event` header) into a [`ForgeEvent`],
+/// or return `None` if it isn't of interest.
+pub fn parse_github_event(event: &str, body: &[u8]) -> Result<Option<ForgeEvent>> {
+    let payload: Value = serde_json::from_slice(body).context("webhook payload is not JSON")?;
+    let str_at = |pointer: &str| payload.pointer(pointer).and_then(Value::as_str);
+    let Some(repository) = str_at("/repository/full_name").map(ToOwned::to_owned) else {
+        return Ok(None);
+    };
+
+    let event = match event {
+        "pull_request" => ForgeEvent::PullRequest {
+            repository,
+            number: payload["pull_request"]["number"]
+                .as_u64()
+                .context("pull_request event without number")?,
+            action: str_at("/action").unwrap_or_default().to_owned(),
+            merged: payload["pull_request"]["merged"].as_bool().unwrap_or(false),
+        },
+        "pull_request_review" => ForgeEvent::Review {
+            repository,
+            number: payload["pull_request"]["number"]
+                .as_u64()
+                .context("pull_request_review event without number")?,
+            state: str_at("/review/state").unwrap_or_default().to_owned(),
+        },
+        "check_run" | "check_suite" => {
+            let Some(sha) = str_at(&format!("/{event}/head_sha")) else {
+                return Ok(None);
+            };
+            ForgeEvent::Checks {
+                repository,
+                sha: sha.to_owned(),
+                status: str_at(&format!("/{event}/status"))
+                    .unwrap_or_default()
+                    .to_owned(),
+                conclusion: str_at(&format!("/{event}/conclusion")).map(ToOwned::to_owned),
+            }
+        }
+        "status" => ForgeEvent::Checks {
+            repository,
+            sha: str_at("/sha")
+                .context("status event without sha")?
+                .to_owned(),
+            status: "completed".to_owned(),
+            conclusion: str_at("/state").map(ToOwned::to_owned),
+        },
+        "merge_group" => ForgeEvent::MergeGroup {
+            repository,
+            action: str_at("/action").unwrap_or_default().to_owned(),
+            head_sha: str_at("/merge_group/head_sha")
+                .context("merge_group event without head_sha")?
+                .to_owned(),
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some(event))
+}
+
+/// Check the `X-Hub-Signature-256` header value `signature` of `body` against `secret`.
+pub fn verify_github_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(expected) = signature
+        .strip_prefix("sha256=")
+        .and_then(|digest| hex::decode(digest).ok())
+    else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// Listen for webhook deliveries on `addr` in a background thread, and call `on_event` for each event of interest.
+///
+/// Deliveries without a valid signature for `secret` are rejected.
+pub fn listen(
+    addr: SocketAddr,
+    secret: String,
+    on_event: impl Fn(ForgeEvent) + Send + 'static,
+) -> Result<std::thread::JoinHandle<()>> {
+    let listener = TcpListener::bind(addr)
+        .with_context(|| format!("failed to listen for webhooks on {addr}"))?;
+    tracing::info!(%addr, "listening for forge webhooks");
+    Ok(std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let status = match handle_delivery(&mut stream, &secret) {
+                Ok(Some(event)) => {
+                    on_event(event);
+                    "204 No Content"
+                }
+                Ok(None) => "204 No Content",
+                Err(err) => {
+                    tracing::warn!("rejected webhook delivery: {err:#}");
+                    "400 Bad Request"
+                }
+            };
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            );
+        }
+    }))
+}
+
+fn handle_delivery(stream: &mut TcpStream, secret: &str) -> Result<Option<ForgeEvent>> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.take(MAX_HEADER_SIZE + MAX_BODY_SIZE));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    if !request_line.starts_with("POST ") {
+        bail!("expected a POST request, got '{}'", request_line.trim());
+    }
+
+    let mut content_length: u64 = 0;
+    let mut event = None;
+    let mut signature = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().to_owned();
+        match name.to_ascii_lowercase().as_str() {
+            "content-length" => content_length = value.parse().context("invalid Content-Length")?,
+            "x-github-event" => event = Some(value),
+            "x-hub-signature-256" => signature = Some(value),
+            _ => {}
+        }
+    }
+    if content_length > MAX_BODY_SIZE {
+        bail!("payload of {content_length} bytes is too large");
+    }
+    let mut body = Vec::new();
+    reader.take(content_length).read_to_end(&mut body)?;
+    if body.len() as u64 != content_length {
+        bail!(
+            "delivery ended after {} of {content_length} bytes",
+            body.len()
+        );
+    }
+
+    let valid = signature.map_or(false, |signature| {
+        verify_github_signature(secret, &body, &signature)
+    });
+    if !valid {
+        bail!("invalid or missing signature");
+    }
+    match event {
+        Some(event) => parse_github_event(&event, &body),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_merged_pull_request() -> Result<()> {
+        let body = br#"{
+            "action": "closed",
+            "pull_request": { "number": 42, "merged": true },
+            "repository": { "full_name": "gitbutlerapp/gitbutler" }
+        }"#;
+        assert_eq!(
+            parse_github_event("pull_request", body)?,
+            Some(ForgeEvent::PullRequest {
+                repository: "gitbutlerapp/gitbutler".into(),
+                number: 42,
+                action: "closed".into(),
+                merged: true,
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_check_run() -> Result<()> {
+        let body = br#"{
+            "action": "completed",
+            "check_run": { "head_sha": "abc", "status": "completed", "conclusion": "failure" },
+            "repository": { "full_name": "gitbutlerapp/gitbutler" }
+        }"#;
+        assert_eq!(
+            parse_github_event("check_run", body)?,
+            Some(ForgeEvent::Checks {
+                repository: "gitbutlerapp/gitbutler".into(),
+                sha: "abc".into(),
+                status: "completed".into(),
+                conclusion: Some("failure".into()),
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn uninteresting_events_are_ignored() -> Result<()> {
+        let body = br#"{ "repository": { "full_name": "gitbutlerapp/gitbutler" } }"#;
+        assert_eq!(parse_github_event("star", body)?, None);
+        assert_eq!(parse_github_event("ping", br#"{ "zen": "hi" }"#)?, None);
+        Ok(())
+    }
+
+    fn deliver(request: &[u8]) -> Result<Option<ForgeEvent>> {
+        let listener = TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0))?;
+        let mut sender = TcpStream::connect(listener.local_addr()?)?;
+        sender.write_all(request)?;
+        let (mut stream, _) = listener.accept()?;
+        handle_delivery(&mut stream, "secret")
+    }
+
+    fn request(body: &str, signature: Option<&str>) -> Vec<u8> {
+        let signature = signature
+            .map(|signature| format!("X-Hub-Signature-256: {signature}\r\n"))
+            .unwrap_or_default();
+        format!(
+            "POST / HTTP/1.1\r\nX-GitHub-Event: pull_request\r\n{signature}Content-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .into_bytes()
+    }
+
+    fn sign(body: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(body.as_bytes());
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn deliveries_must_be_signed() -> Result<()> {
+        let body =
+            r#"{"action":"opened","pull_request":{"number":1},"repository":{"full_name":"a/b"}}"#;
+        assert!(deliver(&request(body, None)).is_err());
+        assert!(deliver(&request(body, Some("sha256=00"))).is_err());
+        assert_eq!(
+            deliver(&request(body, Some(&sign(body))))?,
+            Some(ForgeEvent::PullRequest {
+                repository: "a/b".into(),
+                number: 1,
+                action: "opened".into(),
+                merged: false,
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn oversized_and_truncated_deliveries_are_rejected() -> Result<()> {
+        let oversized = format!(
+            "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_SIZE + 1
+        );
+        let err = deliver(oversized.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("too large"));
+
+        let truncated = "POST / HTTP/1.1\r\nContent-Length: 100\r\n\r\n{}";
+        let listener = TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0))?;
+        let mut sender = TcpStream::connect(listener.local_addr()?)?;
+        sender.write_all(truncated.as_bytes())?;
+        drop(sender);
+        let (mut stream, _) = listener.accept()?;
+        let err = handle_delivery(&mut stream, "secret").unwrap_err();
+        assert!(err.to_string().contains("ended after 2 of 100 bytes"));
+        Ok(())
+    }
+
+    #[test]
+    fn signature_verification() {
+        // The example from the GitHub documentation on validating webhook deliveries.
+        let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+        assert!(verify_github_signature(
+            "It's a Secret to Everybody",
+            b"Hello, World!",
+            signature
+        ));
+        assert!(!verify_github_signature(
+            "another secret",
+            b"Hello, World!",
+            signature
+        ));
+        assert!(!verify_github_signature(
+            "It's a Secret to Everybody",
+            b"Hello, World!",
+            "sha256=zz"
+        ));
+    }
+}
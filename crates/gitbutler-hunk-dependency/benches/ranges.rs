@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use gitbutler_hunk_dependency::{
+    InputCommit, InputDiff, InputFile, InputStack, RangeKind, WorkspaceRanges,
+};
+use gitbutler_stack::StackId;
+
+const NUM_COMMITS: u32 = 10;
+
+/// A stack of commits that each add a line to every one of `num_paths` files named after `name`.
+fn stack(name: &str, num_paths: usize) -> InputStack {
+    InputStack {
+        stack_id: StackId::generate(),
+        commits: (0..NUM_COMMITS)
+            .map(|commit| InputCommit {
+                commit_id: git2::Oid::from_str(&format!("{:x}", commit + 1)).unwrap(),
+                files: (0..num_paths)
+                    .map(|path| InputFile {
+                        path: PathBuf::from(format!("{name}-{path}.txt")),
+                        old_path: None,
+                        diffs: vec![InputDiff {
+                            old_start: commit * 2 + 1,
+                            old_lines: 0,
+                            new_start: commit * 2 + 1,
+                            new_lines: 1,
+                            merged: false,
+                            kind: RangeKind::Lines,
+                            columns: None,
+                        }],
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+pub fn benchmark_workspace_ranges(c: &mut Criterion) {
+    let mut group = c.benchmark_group("workspace-ranges");
+    // Around the threshold of computing paths in parallel, and way beyond it.
+    for num_paths in [16, 32, 64, 128, 1024, 8192] {
+        // Stacks can't change the same lines, so each changes its own files.
+        let stacks = vec![stack("a", num_paths), stack("b", num_paths)];
+        group.throughput(Throughput::Elements(num_paths as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(num_paths),
+            &stacks,
+            |b, stacks| b.iter(|| WorkspaceRanges::create(black_box(stacks.clone())).unwrap()),
+        );
+    }
+}
+
+criterion_group!(benches, benchmark_workspace_ranges);
+criterion_main!(benches);
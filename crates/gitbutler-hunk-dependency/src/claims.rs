@@ -0,0 +1,177 @@
+use gitbutler_diff::{DiffByPathMap, GitHunk, Hunk};
+use gitbutler_stack::{BranchOwnershipClaims, OwnershipClaim};
+
+use crate::{InputDiff, RangeKind};
+
+/// Move the hunks of `claims` along with the lines they were claimed for, after the worktree
+/// changed underneath them as described by `changes`, e.g. when integrating upstream changes.
+///
+/// `changes` is expected to be diffed without context lines, from the worktree the claims were
+/// made in to the current one. Hunks are shifted by the lines added or removed before them, split
+/// around lines inserted or replaced within them, and dropped if all of their lines were replaced.
+/// Claims of entire files stay as they are.
+pub fn shift_claims(
+    claims: &BranchOwnershipClaims,
+    changes: &DiffByPathMap,
+) -> anyhow::Result<BranchOwnershipClaims> {
+    let mut shifted = Vec::with_capacity(claims.claims.len());
+    for claim in &claims.claims {
+        let diffs = match changes.get(&claim.file_path) {
+            Some(file) if !claim.hunks.is_empty() && !file.binary && !file.skipped => {
+                let mut diffs: Vec<InputDiff> = file.hunks.iter().map(input_diff).collect();
+                diffs.sort_by_key(|diff| diff.old_start);
+                diffs
+            }
+            _ => {
+                shifted.push(claim.clone());
+                continue;
+            }
+        };
+
+        let mut hunks = Vec::with_capacity(claim.hunks.len());
+        for hunk in &claim.hunks {
+            hunks.extend(shift_hunk(hunk, &diffs)?);
+        }
+        if !hunks.is_empty() {
+            shifted.push(OwnershipClaim {
+                file_path: claim.file_path.clone(),
+                hunks,
+            });
+        }
+    }
+    Ok(BranchOwnershipClaims { claims: shifted })
+}
+
+/// Like the parsed diffs, a hunk which only inserts or only deletes lines is positioned at the line
+/// following the change, whereas Git positions it at the line before it.
+fn input_diff(hunk: &GitHunk) -> InputDiff {
+    InputDiff {
+        old_start: hunk.old_start + u32::from(hunk.old_lines == 0),
+        old_lines: hunk.old_lines,
+        new_start: hunk.new_start + u32::from(hunk.new_lines == 0),
+        new_lines: hunk.new_lines,
+        merged: false,
+        kind: RangeKind::Lines,
+        columns: None,
+    }
+}
+
+/// Return the parts of `hunk` which remain after applying `diffs`, which are sorted by their start.
+fn shift_hunk(hunk: &Hunk, diffs: &[InputDiff]) -> anyhow::Result<Vec<Hunk>> {
+    let shifted = |start: u32, end: u32, line_shift: i32| Hunk {
+        start: start.saturating_add_signed(line_shift),
+        end: end.saturating_add_signed(line_shift),
+        hash: None,
+    };
+
+    let mut parts = Vec::new();
+    let mut line_shift = 0;
+    let mut next_unchanged_line = hunk.start;
+    let mut touched = false;
+    for diff in diffs {
+        let old_end = diff.old_start + diff.old_lines;
+        if old_end <= hunk.start {
+            line_shift += diff.net_lines()?;
+            continue;
+        }
+        if diff.old_start >= hunk.end {
+            break;
+        }
+        touched = true;
+        if next_unchanged_line < diff.old_start {
+            parts.push(shifted(next_unchanged_line, diff.old_start, line_shift));
+        }
+        next_unchanged_line = next_unchanged_line.max(old_end);
+        line_shift += diff.net_lines()?;
+    }
+
+    if !touched {
+        // The content is unchanged, so is its hash.
+        return Ok(vec![Hunk {
+            hash: hunk.hash,
+            ..shifted(hunk.start, hunk.end, line_shift)
+        }]);
+    }
+    if next_unchanged_line < hunk.end {
+        parts.push(shifted(next_unchanged_line, hunk.end, line_shift));
+    }
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use gitbutler_diff::{ChangeType, FileDiff};
+
+    use super::*;
+
+    /// Produce the changes of `path` as a zero-context diff would from `(old_start, old_lines, new_start, new_lines)`.
+    fn changes(path: &str, hunks: &[(u32, u32, u32, u32)]) -> DiffByPathMap {
+        let hunks = hunks
+            .iter()
+            .map(|&(old_start, old_lines, new_start, new_lines)| GitHunk {
+                old_start,
+                old_lines,
+                new_start,
+                new_lines,
+                diff_lines: Default::default(),
+                binary: false,
+                change_type: ChangeType::Modified,
+            })
+            .collect();
+        [(
+            PathBuf::from(path),
+            FileDiff {
+                path: path.into(),
+                hunks,
+                skipped: false,
+                binary: false,
+                old_size_bytes: 0,
+                new_size_bytes: 0,
+            },
+        )]
+        .into()
+    }
+
+    fn shift(claims: &str, changes: &DiffByPathMap) -> anyhow::Result<String> {
+        Ok(shift_claims(&claims.parse()?, changes)?.to_string())
+    }
+
+    #[test]
+    fn shifted_by_lines_added_and_removed_before() -> anyhow::Result<()> {
+        // Three lines inserted after line 2, and line 4 removed.
+        let changes = changes("a.txt", &[(2, 0, 3, 3), (4, 1, 6, 0)]);
+        assert_eq!(
+            shift("a.txt:10-12\nb.txt:1-2", &changes)?,
+            "a.txt:12-14\nb.txt:1-2\n"
+        );
+        assert_eq!(
+            shift("a.txt:1-2", &changes)?,
+            "a.txt:1-2\n",
+            "nothing changed before"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn split_around_lines_inserted_within() -> anyhow::Result<()> {
+        // Two lines inserted after line 11.
+        let changes = changes("a.txt", &[(11, 0, 12, 2)]);
+        assert_eq!(shift("a.txt:10-14", &changes)?, "a.txt:10-12,14-16\n");
+        Ok(())
+    }
+
+    #[test]
+    fn dropped_if_all_lines_were_replaced() -> anyhow::Result<()> {
+        // Lines 5 to 9 were replaced by a single line.
+        let changes = changes("a.txt", &[(5, 5, 5, 1)]);
+        assert_eq!(shift("a.txt:6-8", &changes)?, "");
+        assert_eq!(
+            shift("a.txt:6-8,20-22", &changes)?,
+            "a.txt:16-18\n",
+            "other hunks of the claim remain"
+        );
+        Ok(())
+    }
+}
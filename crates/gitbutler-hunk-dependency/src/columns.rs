@@ -0,0 +1,124 @@
+//! The columns a diff changed within a line, so changes to different words of the same line don't
+//! have to depend on each other.
+use serde::{Deserialize, Serialize};
+
+/// The bytes of a line from `start` up to `end`, excluding it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Columns {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Columns {
+    /// Whether the columns overlap or touch, which includes insertions at the boundary of the
+    /// other columns.
+    pub fn overlaps(&self, other: Columns) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+/// The columns changed by a diff which replaces a single line with another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangedColumns {
+    /// The number of context lines before the changed line.
+    pub offset: u32,
+    /// The changed columns of the line before the diff.
+    pub old: Columns,
+    /// The changed columns of the line after the diff.
+    pub new: Columns,
+}
+
+/// Return the columns changed by `diff_lines` if they replace a single line with another, widened
+/// to whole words so edits within the same word still overlap.
+pub fn changed_columns(diff_lines: &[u8]) -> Option<ChangedColumns> {
+    let mut offset = 0;
+    let (mut old, mut new) = (None, None);
+    for line in diff_lines.split(|b| *b == b'\n') {
+        match line.split_first() {
+            Some((b'-', content)) if old.is_none() => old = Some(content),
+            Some((b'+', content)) if new.is_none() => new = Some(content),
+            Some((b'-' | b'+', _)) => return None,
+            Some((b' ', _)) if old.is_none() && new.is_none() => offset += 1,
+            // Context after the change, markers like `\ No newline at end of file`, and the empty
+            // line after the last separator.
+            _ => {}
+        }
+    }
+    let (old, new) = (old?, new?);
+
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    Some(ChangedColumns {
+        offset,
+        old: word_columns(old, prefix, old.len() - suffix)?,
+        new: word_columns(new, prefix, new.len() - suffix)?,
+    })
+}
+
+/// Return the columns from `start` to `end` of `line`, widened to the words they start and end in.
+fn word_columns(line: &[u8], mut start: usize, mut end: usize) -> Option<Columns> {
+    let is_word = |b: &u8| b.is_ascii_alphanumeric() || *b == b'_' || !b.is_ascii();
+    while start > 0 && is_word(&line[start - 1]) {
+        start -= 1;
+    }
+    while end < line.len() && is_word(&line[end]) {
+        end += 1;
+    }
+    Some(Columns {
+        start: u32::try_from(start).ok()?,
+        end: u32::try_from(end).ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(start: u32, end: u32) -> Columns {
+        Columns { start, end }
+    }
+
+    #[test]
+    fn changed_words() {
+        let changed = changed_columns(b"-name = old\n+name = new\n").unwrap();
+        assert_eq!(changed.offset, 0);
+        assert_eq!(changed.old, columns(7, 10));
+        assert_eq!(changed.new, columns(7, 10));
+
+        let changed = changed_columns(b" a\n b\n-key = value\n+key = values\n c\n").unwrap();
+        assert_eq!(
+            changed.offset, 2,
+            "context lines before the change are counted"
+        );
+        assert_eq!(
+            changed.new,
+            columns(6, 12),
+            "appending to a word widens to the whole word"
+        );
+    }
+
+    #[test]
+    fn changes_of_other_lines_have_no_columns() {
+        assert!(changed_columns(b"+added\n").is_none());
+        assert!(changed_columns(b"-removed\n").is_none());
+        assert!(changed_columns(b"-a\n-b\n+c\n").is_none());
+    }
+
+    #[test]
+    fn overlapping_columns() {
+        assert!(columns(0, 4).overlaps(columns(2, 6)));
+        assert!(
+            columns(0, 4).overlaps(columns(4, 4)),
+            "touching columns overlap"
+        );
+        assert!(!columns(0, 4).overlaps(columns(5, 9)));
+    }
+}
@@ -0,0 +1,248 @@
+//! The dependencies between the commits of the workspace, and of uncommitted hunks on commits, as a
+//! graph that can be serialized or [rendered](DependencyGraph::to_dot) for tools to visualize.
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Write as _,
+    path::PathBuf,
+};
+
+use gitbutler_diff::{GitHunk, Hunk};
+use gitbutler_stack::StackId;
+use serde::Serialize;
+
+use crate::WorkspaceRanges;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyGraph {
+    /// All commits of the workspace, in application order per stack.
+    pub commits: Vec<CommitNode>,
+    /// Commits that changed lines that earlier commits changed, and can't be reordered before them.
+    pub commit_dependencies: Vec<CommitDependency>,
+    /// Uncommitted hunks that change lines that commits changed.
+    pub hunk_dependencies: Vec<HunkDependency>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitNode {
+    #[serde(with = "gitbutler_serde::oid")]
+    pub commit_id: git2::Oid,
+    pub stack_id: StackId,
+    /// The submodules whose bump by the commit is still in the workspace.
+    pub submodules: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitDependency {
+    #[serde(with = "gitbutler_serde::oid")]
+    pub commit_id: git2::Oid,
+    #[serde(with = "gitbutler_serde::oid")]
+    pub depends_on: git2::Oid,
+    /// The paths in which the lines of both commits overlap.
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkDependency {
+    pub path: PathBuf,
+    /// The hash of the diff of the hunk, as used to identify hunks elsewhere.
+    pub hunk_hash: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    #[serde(with = "gitbutler_serde::oid")]
+    pub depends_on: git2::Oid,
+    pub stack_id: StackId,
+}
+
+impl DependencyGraph {
+    /// Build the graph of the commits in `ranges` and the uncommitted hunks in `workdir`.
+    pub fn new(ranges: &WorkspaceRanges, workdir: &HashMap<PathBuf, Vec<GitHunk>>) -> Self {
+        let commits = ranges
+            .commits()
+            .map(|(stack_id, commit_id)| CommitNode {
+                commit_id,
+                stack_id,
+                submodules: ranges
+                    .commit_ranges(commit_id)
+                    .into_iter()
+                    .filter(|(_, range)| range.is_submodule())
+                    .map(|(path, _)| path.to_owned())
+                    .collect(),
+            })
+            .collect();
+
+        let mut paths_by_dependency: BTreeMap<(git2::Oid, git2::Oid), BTreeSet<PathBuf>> =
+            BTreeMap::new();
+        for (path, commit_id, depends_on) in ranges.commit_dependencies() {
+            paths_by_dependency
+                .entry((commit_id, depends_on))
+                .or_default()
+                .insert(path.to_owned());
+        }
+        let commit_dependencies = paths_by_dependency
+            .into_iter()
+            .map(|((commit_id, depends_on), paths)| CommitDependency {
+                commit_id,
+                depends_on,
+                paths: paths.into_iter().collect(),
+            })
+            .collect();
+
+        let mut hunk_dependencies = Vec::new();
+        for (path, hunks) in workdir.iter().collect::<BTreeMap<_, _>>() {
+            for hunk in hunks {
+                let Some(intersection) = ranges.intersection(path, hunk.old_start, hunk.old_lines)
+                else {
+                    continue;
+                };
+                let hunk_hash = format!("{:x}", Hunk::hash_diff(&hunk.diff_lines));
+                hunk_dependencies.extend(intersection.into_iter().map(|range| HunkDependency {
+                    path: path.clone(),
+                    hunk_hash: hunk_hash.clone(),
+                    old_start: hunk.old_start,
+                    old_lines: hunk.old_lines,
+                    depends_on: range.commit_id,
+                    stack_id: range.stack_id,
+                }));
+            }
+        }
+
+        DependencyGraph {
+            commits,
+            commit_dependencies,
+            hunk_dependencies,
+        }
+    }
+
+    /// Render the graph in the DOT language of Graphviz, with edges pointing from what depends on
+    /// something to what it depends on, and the commits of each stack in a cluster.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dependencies {\n    rankdir=BT;\n");
+        let mut stacks: Vec<StackId> = Vec::new();
+        for commit in &self.commits {
+            if !stacks.contains(&commit.stack_id) {
+                stacks.push(commit.stack_id);
+            }
+        }
+        for (index, stack_id) in stacks.iter().enumerate() {
+            writeln!(dot, "    subgraph cluster_{index} {{").ok();
+            writeln!(dot, "        label=\"{stack_id}\";").ok();
+            for commit in self.commits.iter().filter(|c| c.stack_id == *stack_id) {
+                writeln!(
+                    dot,
+                    "        \"{}\" [label=\"{}\"];",
+                    commit.commit_id,
+                    short_id(commit.commit_id)
+                )
+                .ok();
+            }
+            writeln!(dot, "    }}").ok();
+        }
+        for dependency in &self.commit_dependencies {
+            let paths = dependency
+                .paths
+                .iter()
+                .map(|path| escape(&path.display().to_string()))
+                .collect::<Vec<_>>()
+                .join("\\n");
+            writeln!(
+                dot,
+                "    \"{}\" -> \"{}\" [label=\"{}\"];",
+                dependency.commit_id, dependency.depends_on, paths
+            )
+            .ok();
+        }
+        for dependency in &self.hunk_dependencies {
+            let node = format!("{}:{}", dependency.path.display(), dependency.hunk_hash);
+            writeln!(
+                dot,
+                "    \"{}\" [shape=box, label=\"{}:{}\"];",
+                escape(&node),
+                escape(&dependency.path.display().to_string()),
+                dependency.old_start
+            )
+            .ok();
+            writeln!(
+                dot,
+                "    \"{}\" -> \"{}\";",
+                escape(&node),
+                dependency.depends_on
+            )
+            .ok();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn short_id(id: git2::Oid) -> String {
+    id.to_string().chars().take(7).collect()
+}
+
+/// Escape `value` for use within a quoted DOT string.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use gitbutler_diff::ChangeType;
+
+    use crate::{test_utils::input_commit, InputStack};
+
+    use super::*;
+
+    #[test]
+    fn commits_and_hunks_depend_on_overlapping_commits() -> anyhow::Result<()> {
+        let path = PathBuf::from_str("/test.txt")?;
+        let stack_id = StackId::generate();
+        let commit1_id = git2::Oid::from_str("a")?;
+        let commit2_id = git2::Oid::from_str("b")?;
+        let commit3_id = git2::Oid::from_str("c")?;
+
+        let ranges = WorkspaceRanges::create(vec![InputStack {
+            stack_id,
+            commits: vec![
+                input_commit(commit1_id, &path, "@@ -1,2 +1,3 @@\n1\n+2\n3\n")?,
+                input_commit(commit2_id, &path, "@@ -1,3 +1,3 @@\n1\n-2\n+2b\n3\n")?,
+                input_commit(commit3_id, &path, "@@ -9,2 +9,3 @@\n9\n+10\n11\n")?,
+            ],
+        }])?;
+        let workdir = HashMap::from([(
+            path.clone(),
+            vec![GitHunk {
+                old_start: 2,
+                old_lines: 1,
+                new_start: 2,
+                new_lines: 1,
+                diff_lines: "-2b\n+2c\n".into(),
+                binary: false,
+                change_type: ChangeType::Modified,
+            }],
+        )]);
+
+        let graph = DependencyGraph::new(&ranges, &workdir);
+        assert_eq!(graph.commits.len(), 3);
+        assert_eq!(
+            graph.commit_dependencies,
+            [CommitDependency {
+                commit_id: commit2_id,
+                depends_on: commit1_id,
+                paths: vec![path.clone()],
+            }],
+            "the third commit changes other lines"
+        );
+        assert_eq!(graph.hunk_dependencies.len(), 1);
+        assert_eq!(graph.hunk_dependencies[0].depends_on, commit2_id);
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph dependencies {"));
+        assert!(dot.contains(&format!("\"{commit2_id}\" -> \"{commit1_id}\"")));
+        Ok(())
+    }
+}
@@ -1,17 +1,51 @@
 use gitbutler_stack::StackId;
+use serde::{Deserialize, Serialize};
+
+use crate::Columns;
 
 /// A struct for tracking what stack and commit a hunk belongs to as its line numbers shift with
 /// new changes come in from other commits and/or stacks.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct HunkRange {
     pub stack_id: StackId,
+    #[serde(with = "gitbutler_serde::oid")]
     pub commit_id: git2::Oid,
     pub start: u32,
     pub lines: u32,
     pub line_shift: i32,
+    #[serde(default)]
+    pub kind: RangeKind,
+    /// The columns that were changed, if the range is a single line of which only these changed.
+    #[serde(default)]
+    pub columns: Option<Columns>,
+}
+
+/// What the lines of a [`HunkRange`] are.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RangeKind {
+    /// Lines of the content of a file.
+    #[default]
+    Lines,
+    /// The single line of a submodule entry, which is the commit it points to. A change to it is
+    /// a bump of the submodule, and is always replaced as a whole.
+    Submodule,
 }
 
 impl HunkRange {
+    pub fn is_submodule(&self) -> bool {
+        self.kind == RangeKind::Submodule
+    }
+
+    /// Whether `columns` of `line` are apart from those the range changed, as the range is that
+    /// line and only changed other parts of it.
+    pub fn apart_from(&self, line: u32, columns: Columns) -> bool {
+        self.lines == 1
+            && self.start == line
+            && self.columns.is_some_and(|own| !own.overlaps(columns))
+    }
+
     pub fn intersects(&self, start: u32, lines: u32) -> bool {
         if self.lines == 0 {
             if self.start == 0 {
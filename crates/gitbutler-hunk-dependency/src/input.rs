@@ -1,9 +1,13 @@
 use std::path::PathBuf;
 
-use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, Context};
 use gitbutler_stack::StackId;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+use crate::{Columns, RangeKind};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct InputStack {
     pub stack_id: StackId,
     /// The commits in the stack.
@@ -12,26 +16,44 @@ pub struct InputStack {
     pub commits: Vec<InputCommit>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct InputCommit {
+    #[serde(with = "gitbutler_serde::oid")]
     pub commit_id: git2::Oid,
     pub files: Vec<InputFile>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct InputFile {
     pub path: PathBuf,
+    /// The path of the file before the commit, if the commit renamed it. The `diffs` are relative
+    /// to the file at this path.
+    pub old_path: Option<PathBuf>,
     pub diffs: Vec<InputDiff>,
 }
 
 /// Please note that the From conversions and parsing of diffs exists to facilitate testing, in
 /// the client code we get the line numbers from elsewhere.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct InputDiff {
     pub old_start: u32,
     pub old_lines: u32,
     pub new_start: u32,
     pub new_lines: u32,
+    /// Whether the diff is part of a merge commit and only brings in the changes of a merged parent
+    /// which the workspace is based on already, e.g. as the stack merged its upstream. Its lines are
+    /// shifted like those of any other diff, but aren't attributed to the commit.
+    #[serde(default)]
+    pub merged: bool,
+    /// Whether the diff changes lines of a file, or the commit a submodule points to.
+    #[serde(default)]
+    pub kind: RangeKind,
+    /// The changed columns of the new line, if the diff replaces a single line with another.
+    #[serde(default)]
+    pub columns: Option<Columns>,
 }
 
 impl InputDiff {
@@ -40,23 +62,34 @@ impl InputDiff {
             .checked_signed_diff(self.old_lines)
             .ok_or(anyhow!("u32 -> i32 conversion overflow"))
     }
-}
 
-fn count_context_lines<I, S>(iter: I) -> u32
-where
-    I: Iterator<Item = S>,
-    S: AsRef<str>,
-{
-    iter.take_while(|line| {
-        let line_ref = line.as_ref(); // Convert to &str
-        !line_ref.starts_with('-') && !line_ref.starts_with('+')
-    })
-    .fold(0u32, |acc, _| acc + 1)
+    /// Parse all hunks of the unified diff of a single file, which may start with the file headers Git
+    /// produces, like `diff --git`, mode changes, renames and copies. A diff without hunks, e.g. of a
+    /// pure rename, yields no `InputDiff`.
+    ///
+    /// The line counts of each hunk header are checked against its body, so malformed diffs are
+    /// rejected instead of producing wrong line numbers.
+    pub fn parse_all(value: &str) -> anyhow::Result<Vec<InputDiff>> {
+        let mut lines = value.lines().peekable();
+        while let Some(line) = lines.next_if(|line| !line.starts_with("@@")) {
+            if !is_file_header(line) {
+                bail!("Unexpected line before the first hunk: {line:?}");
+            }
+        }
+
+        let mut diffs = Vec::new();
+        while let Some(header) = lines.next() {
+            let body: Vec<&str> =
+                std::iter::from_fn(|| lines.next_if(|line| !line.starts_with("@@"))).collect();
+            diffs.push(parse_hunk(header, &body)?);
+        }
+        Ok(diffs)
+    }
 }
 
 impl TryFrom<String> for InputDiff {
     fn try_from(value: String) -> Result<Self, anyhow::Error> {
-        parse_diff(value)
+        parse_single_diff(&value)
     }
 
     type Error = anyhow::Error;
@@ -64,23 +97,88 @@ impl TryFrom<String> for InputDiff {
 
 impl TryFrom<&str> for InputDiff {
     fn try_from(value: &str) -> Result<Self, anyhow::Error> {
-        parse_diff(value)
+        parse_single_diff(value)
     }
 
     type Error = anyhow::Error;
 }
 
-fn parse_diff(value: impl AsRef<str>) -> Result<InputDiff, anyhow::Error> {
-    let value = value.as_ref();
-    let header = value.lines().next().context("No header found")?;
-    if !header.starts_with("@@") {
-        return Err(anyhow!("Malformed undiff"));
-    }
-    let parts: Vec<&str> = header.split_whitespace().collect();
-    let (old_start, old_lines) = parse_header(parts[1]);
-    let (new_start, new_lines) = parse_header(parts[2]);
-    let head_context_lines = count_context_lines(value.lines().skip(1).take(3));
-    let tail_context_lines = count_context_lines(value.rsplit_terminator('\n').take(3));
+fn parse_single_diff(value: &str) -> anyhow::Result<InputDiff> {
+    let mut diffs = InputDiff::parse_all(value)?.into_iter();
+    match (diffs.next(), diffs.next()) {
+        (Some(diff), None) => Ok(diff),
+        (None, _) => Err(anyhow!("No hunk found")),
+        (Some(_), Some(_)) => Err(anyhow!(
+            "Expected a single hunk, use InputDiff::parse_all() for multiple hunks"
+        )),
+    }
+}
+
+/// Return `true` if `line` is one of the lines Git puts in front of the hunks of a file.
+fn is_file_header(line: &str) -> bool {
+    const PREFIXES: &[&str] = &[
+        "diff --git ",
+        "index ",
+        "old mode ",
+        "new mode ",
+        "deleted file mode ",
+        "new file mode ",
+        "similarity index ",
+        "dissimilarity index ",
+        "rename from ",
+        "rename to ",
+        "copy from ",
+        "copy to ",
+        "Binary files ",
+        "--- ",
+        "+++ ",
+    ];
+    PREFIXES.iter().any(|prefix| line.starts_with(prefix))
+}
+
+/// Parse the hunk with `header` and `body`, stripping its leading and trailing context lines.
+fn parse_hunk(header: &str, body: &[&str]) -> anyhow::Result<InputDiff> {
+    let mut parts = header.split_whitespace();
+    if parts.next() != Some("@@") {
+        bail!("Malformed hunk header {header:?}");
+    }
+    let (old_start, old_lines) = parse_range(parts.next(), '-')
+        .with_context(|| format!("Malformed old range in hunk header {header:?}"))?;
+    let (new_start, new_lines) = parse_range(parts.next(), '+')
+        .with_context(|| format!("Malformed new range in hunk header {header:?}"))?;
+
+    // `\ No newline at end of file` refers to the line before it, but isn't a line itself.
+    let lines: Vec<&str> = body
+        .iter()
+        .copied()
+        .filter(|line| !line.starts_with('\\'))
+        .collect();
+    if let Some(line) = lines.iter().find(|line| line.starts_with("diff --git ")) {
+        bail!("Expected the diff of a single file, found {line:?}");
+    }
+    let (mut old_count, mut new_count) = (0u32, 0u32);
+    for line in &lines {
+        match line.as_bytes().first() {
+            Some(b'-') => old_count += 1,
+            Some(b'+') => new_count += 1,
+            _ => {
+                old_count += 1;
+                new_count += 1;
+            }
+        }
+    }
+    if (old_count, new_count) != (old_lines, new_lines) {
+        bail!(
+            "Hunk header {header:?} announces {old_lines} old and {new_lines} new lines, \
+             but the hunk has {old_count} old and {new_count} new lines"
+        );
+    }
+
+    if lines.iter().all(|line| is_context(line)) {
+        bail!("Hunk {header:?} has no changes");
+    }
+    let head_context_lines = count_context_lines(lines.iter());
+    let tail_context_lines = count_context_lines(lines.iter().rev());
     let context_lines = head_context_lines + tail_context_lines;
 
     Ok(InputDiff {
@@ -88,19 +186,31 @@ fn parse_diff(value: impl AsRef<str>) -> Result<InputDiff, anyhow::Error> {
         old_lines: old_lines - context_lines,
         new_start: new_start + head_context_lines,
         new_lines: new_lines - context_lines,
+        merged: false,
+        kind: RangeKind::Lines,
+        columns: None,
     })
 }
 
-fn parse_header(hunk_info: &str) -> (u32, u32) {
-    let hunk_info = hunk_info.trim_start_matches(&['-', '+'][..]); // Remove the leading '-' or '+'
-    let parts: Vec<&str> = hunk_info.split(',').collect();
-    let start = parts[0].parse().unwrap();
-    let lines = if parts.len() > 1 {
-        parts[1].parse().unwrap()
-    } else {
-        1
-    };
-    (start, lines)
+fn is_context(line: &str) -> bool {
+    !line.starts_with('-') && !line.starts_with('+')
+}
+
+fn count_context_lines<'a>(lines: impl Iterator<Item = &'a &'a str>) -> u32 {
+    lines
+        .take_while(|line| is_context(line))
+        .fold(0u32, |acc, _| acc + 1)
+}
+
+/// Parse a range like `-5,7`, `+5` or `-0,0` whose first character is `sign`.
+fn parse_range(range: Option<&str>, sign: char) -> anyhow::Result<(u32, u32)> {
+    let range = range
+        .and_then(|range| range.strip_prefix(sign))
+        .context("Range is missing")?;
+    Ok(match range.split_once(',') {
+        Some((start, lines)) => (start.parse()?, lines.parse()?),
+        None => (range.parse()?, 1),
+    })
 }
 
 #[cfg(test)]
@@ -147,4 +257,208 @@ mod tests {
         assert_eq!(header.new_lines, 1);
         Ok(())
     }
+
+    #[test]
+    fn more_than_three_context_lines() -> anyhow::Result<()> {
+        let diff = InputDiff::try_from(
+            "@@ -1,9 +1,9 @@
+1
+2
+3
+4
+-5
++a
+6
+7
+8
+9
+",
+        )?;
+        assert_eq!(
+            (
+                diff.old_start,
+                diff.old_lines,
+                diff.new_start,
+                diff.new_lines
+            ),
+            (5, 1, 5, 1)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn no_newline_at_end_of_file() -> anyhow::Result<()> {
+        let diff = InputDiff::try_from(
+            r"@@ -1,3 +1,3 @@
+ 1
+ 2
+-3
+\ No newline at end of file
++3
+",
+        )?;
+        assert_eq!(
+            (
+                diff.old_start,
+                diff.old_lines,
+                diff.new_start,
+                diff.new_lines
+            ),
+            (3, 1, 3, 1),
+            "the marker is neither a context line nor counted"
+        );
+
+        let diff = InputDiff::try_from(
+            r"@@ -1,2 +1,3 @@
+ 1
+ 2
++3
+\ No newline at end of file
+",
+        )?;
+        assert_eq!(
+            (
+                diff.old_start,
+                diff.old_lines,
+                diff.new_start,
+                diff.new_lines
+            ),
+            (3, 0, 3, 1)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn git_file_headers_with_rename_and_mode_change() -> anyhow::Result<()> {
+        let diff = InputDiff::try_from(
+            "diff --git a/old.txt b/new.txt
+old mode 100644
+new mode 100755
+similarity index 90%
+rename from old.txt
+rename to new.txt
+index 1234567..89abcde
+--- a/old.txt
++++ b/new.txt
+@@ -1,3 +1,3 @@ fn main() {
+ 1
+-2
++b
+ 3
+",
+        )?;
+        assert_eq!(
+            (
+                diff.old_start,
+                diff.old_lines,
+                diff.new_start,
+                diff.new_lines
+            ),
+            (2, 1, 2, 1)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn pure_rename_has_no_hunks() -> anyhow::Result<()> {
+        let diff = "diff --git a/old.txt b/new.txt
+similarity index 100%
+copy from old.txt
+copy to new.txt
+";
+        assert!(InputDiff::parse_all(diff)?.is_empty());
+        assert!(InputDiff::try_from(diff).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn multiple_hunks() -> anyhow::Result<()> {
+        let diff = "--- a/file.txt
++++ b/file.txt
+@@ -1,3 +1,4 @@
+ 1
++a
+ 2
+ 3
+@@ -10,3 +11,2 @@
+ 10
+-11
+ 12
+@@ -20 +20 @@
+-20
++t
+";
+        let diffs: Vec<_> = InputDiff::parse_all(diff)?
+            .into_iter()
+            .map(|diff| {
+                (
+                    diff.old_start,
+                    diff.old_lines,
+                    diff.new_start,
+                    diff.new_lines,
+                )
+            })
+            .collect();
+        assert_eq!(diffs, [(2, 0, 2, 1), (11, 1, 12, 0), (20, 1, 20, 1)]);
+        assert!(
+            InputDiff::try_from(diff).is_err(),
+            "a single hunk is expected"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn new_and_deleted_files() -> anyhow::Result<()> {
+        let diff = InputDiff::try_from(
+            "diff --git a/new.txt b/new.txt
+new file mode 100644
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1,2 @@
++1
++2
+",
+        )?;
+        assert_eq!(
+            (
+                diff.old_start,
+                diff.old_lines,
+                diff.new_start,
+                diff.new_lines
+            ),
+            (0, 0, 1, 2)
+        );
+
+        let diff = InputDiff::try_from(
+            "deleted file mode 100644
+@@ -1 +0,0 @@
+-1
+",
+        )?;
+        assert_eq!(
+            (
+                diff.old_start,
+                diff.old_lines,
+                diff.new_start,
+                diff.new_lines
+            ),
+            (1, 1, 0, 0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn malformed_diffs_are_rejected() {
+        for diff in [
+            "",
+            "1\n+2\n",
+            "@@ -1,2 +1,2 @@\n 1\n-2\n",
+            "@@ -1,x +1,1 @@\n-1\n+2\n",
+            "@@ 1,1 1,1 @@\n-1\n+2\n",
+            "@@ -1,2 +1,2 @@\n 1\n 2\n",
+            "@@ -1 +1 @@\n-1\n+2\ndiff --git a/b b/b\n",
+        ] {
+            assert!(InputDiff::try_from(diff).is_err(), "{diff:?}");
+        }
+    }
 }
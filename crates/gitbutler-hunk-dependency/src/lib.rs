@@ -1,16 +1,35 @@
 #![feature(unsigned_signed_diff)]
+pub mod claims;
+pub(crate) mod columns;
+pub mod graph;
 pub(crate) mod hunk;
 pub mod input;
 pub mod locks;
+pub mod ownership;
 pub(crate) mod path;
+pub mod prediction;
+pub mod scopes;
+pub mod simulation;
 pub(crate) mod stack;
+#[cfg(test)]
+mod test_utils;
 pub(crate) mod workspace;
 
 pub use {
-    hunk::HunkRange,
+    claims::shift_claims,
+    columns::{changed_columns, ChangedColumns, Columns},
+    graph::DependencyGraph,
+    hunk::{HunkRange, RangeKind},
     input::{InputCommit, InputDiff, InputFile, InputStack},
-    locks::{compute_hunk_locks, HunkDependencyOptions, HunkLock},
-    path::PathRanges,
-    stack::StackRanges,
-    workspace::WorkspaceRanges,
+    locks::{
+        compute_hunk_locks, cross_stack_conflicts, owned_path_hunk_locks, path_hunk_locks,
+        CrossStackConflict, HunkDependencyOptions, HunkLock,
+    },
+    ownership::{line_owners, LineOwner},
+    path::{set_validation_enabled, PathRanges, RangesCorruption, Violation},
+    prediction::{Operation, OperationPrediction},
+    scopes::{file_scopes, scoped_hunk_locks, Scope},
+    simulation::RemovalPreview,
+    stack::{PathError, StackRanges},
+    workspace::{TransitiveDependencies, WorkspaceRanges},
 };
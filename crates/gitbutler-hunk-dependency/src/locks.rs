@@ -1,11 +1,14 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 use gitbutler_diff::{GitHunk, Hunk, HunkHash};
 use gitbutler_stack::StackId;
 use itertools::Itertools;
 use serde::Serialize;
 
-use crate::{InputStack, WorkspaceRanges};
+use crate::{changed_columns, InputStack, WorkspaceRanges};
 
 // A hunk is locked when it depends on changes in commits that are in your workspace. A hunk can
 // be locked to more than one branch if it overlaps with more than one committed hunk.
@@ -43,21 +46,322 @@ pub fn compute_hunk_locks(
 
     Ok(workdir
         .iter()
-        .flat_map(|(path, workspace_hunks)| {
-            workspace_hunks.iter().filter_map(|hunk| {
-                ranges
-                    .intersection(path, hunk.old_start, hunk.old_lines)
-                    .map(|intersection| {
-                        intersection
-                            .iter()
-                            .map(|dependency| HunkLock {
-                                commit_id: dependency.commit_id,
-                                branch_id: dependency.stack_id,
+        .flat_map(|(path, workspace_hunks)| path_hunk_locks(&ranges, path, workspace_hunks))
+        .collect())
+}
+
+/// Returns the locks of the uncommitted `hunks` of the file at `path`, by their hash.
+///
+/// As the ranges only depend on commits, this is all that's needed when only the uncommitted
+/// changes to a file changed.
+pub fn path_hunk_locks<'a>(
+    ranges: &'a WorkspaceRanges,
+    path: &'a Path,
+    hunks: &'a [GitHunk],
+) -> impl Iterator<Item = (HunkHash, Vec<HunkLock>)> + 'a {
+    hunks
+        .iter()
+        .filter_map(move |hunk| hunk_locks(ranges, path, hunk, None))
+}
+
+/// Like [`path_hunk_locks()`], but a hunk that replaces a single line isn't locked to a commit
+/// that only changed other words of that line, if the ranges know the
+/// [columns](crate::HunkRange::columns) the commit changed and `stack_of` the hunk is the stack
+/// of the commit. In any other stack, the hunk would conflict with the commit on that line.
+pub fn owned_path_hunk_locks<'a>(
+    ranges: &'a WorkspaceRanges,
+    path: &'a Path,
+    hunks: &'a [GitHunk],
+    stack_of: impl Fn(&GitHunk) -> Option<StackId> + 'a,
+) -> impl Iterator<Item = (HunkHash, Vec<HunkLock>)> + 'a {
+    hunks
+        .iter()
+        .filter_map(move |hunk| hunk_locks(ranges, path, hunk, stack_of(hunk)))
+}
+
+/// The locks of `hunk` as described in [`owned_path_hunk_locks()`], if it has any.
+pub(crate) fn hunk_locks(
+    ranges: &WorkspaceRanges,
+    path: &Path,
+    hunk: &GitHunk,
+    stack_id: Option<StackId>,
+) -> Option<(HunkHash, Vec<HunkLock>)> {
+    let changed = changed_columns(&hunk.diff_lines)
+        .map(|changed| (hunk.old_start + changed.offset, changed.old));
+    let locks = ranges
+        .intersection(path, hunk.old_start, hunk.old_lines)?
+        .iter()
+        .filter(|dependency| {
+            stack_id != Some(dependency.stack_id)
+                || !changed.is_some_and(|(line, columns)| dependency.apart_from(line, columns))
+        })
+        .map(|dependency| HunkLock {
+            commit_id: dependency.commit_id,
+            branch_id: dependency.stack_id,
+        })
+        .collect_vec();
+    (!locks.is_empty()).then(|| (Hunk::hash_diff(&hunk.diff_lines), locks))
+}
+
+/// An uncommitted hunk that intersects commits of more than one stack, so it can't be moved to or
+/// committed in any single one of them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrossStackConflict {
+    pub path: PathBuf,
+    /// The hash of the diff of the hunk, as used to identify hunks elsewhere.
+    pub hunk_hash: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    /// The intersections with each stack, in the order of the stacks in the workspace.
+    pub stacks: Vec<StackIntersection>,
+}
+
+/// The parts of an uncommitted hunk that intersect commits of a stack.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackIntersection {
+    pub stack_id: StackId,
+    /// The ranges of lines of the commits that intersect, in workspace line numbers.
+    pub ranges: Vec<CommitRange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitRange {
+    #[serde(with = "gitbutler_serde::oid")]
+    pub commit_id: git2::Oid,
+    pub start: u32,
+    pub lines: u32,
+}
+
+/// Returns the uncommitted hunks in `workdir` that intersect commits of more than one stack,
+/// ordered by path and line.
+pub fn cross_stack_conflicts(
+    ranges: &WorkspaceRanges,
+    workdir: &HashMap<PathBuf, Vec<GitHunk>>,
+) -> Vec<CrossStackConflict> {
+    let stack_order: Vec<StackId> = ranges
+        .commits()
+        .map(|(stack_id, _)| stack_id)
+        .unique()
+        .collect();
+    workdir
+        .iter()
+        .sorted_by_key(|(path, _)| *path)
+        .flat_map(|(path, hunks)| {
+            hunks.iter().filter_map(|hunk| {
+                let intersection = ranges.intersection(path, hunk.old_start, hunk.old_lines)?;
+                let stacks = intersection
+                    .iter()
+                    .into_group_map_by(|range| range.stack_id)
+                    .into_iter()
+                    .sorted_by_key(|(stack_id, _)| stack_order.iter().position(|id| id == stack_id))
+                    .map(|(stack_id, stack_ranges)| StackIntersection {
+                        stack_id,
+                        ranges: stack_ranges
+                            .into_iter()
+                            .map(|range| CommitRange {
+                                commit_id: range.commit_id,
+                                start: range.start,
+                                lines: range.lines,
                             })
-                            .collect_vec()
+                            .collect(),
                     })
-                    .map(|locks| (Hunk::hash_diff(&hunk.diff_lines), locks))
+                    .collect_vec();
+                (stacks.len() > 1).then(|| CrossStackConflict {
+                    path: path.clone(),
+                    hunk_hash: format!("{:x}", Hunk::hash_diff(&hunk.diff_lines)),
+                    old_start: hunk.old_start,
+                    old_lines: hunk.old_lines,
+                    stacks,
+                })
             })
         })
-        .collect())
+        .sorted_by_key(|conflict| (conflict.path.clone(), conflict.old_start))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use gitbutler_diff::ChangeType;
+
+    use crate::{InputCommit, InputDiff, InputFile};
+
+    use super::*;
+
+    #[test]
+    fn hunks_intersecting_two_stacks_conflict() -> anyhow::Result<()> {
+        let path = PathBuf::from_str("/test.txt")?;
+        let stack1_id = StackId::generate();
+        let stack2_id = StackId::generate();
+        let commit1_id = git2::Oid::from_str("a")?;
+        let commit2_id = git2::Oid::from_str("b")?;
+
+        let stack = |stack_id, commit_id, diffs: &[&str]| -> anyhow::Result<InputStack> {
+            Ok(InputStack {
+                stack_id,
+                commits: vec![InputCommit {
+                    commit_id,
+                    files: vec![InputFile {
+                        path: path.clone(),
+                        old_path: None,
+                        diffs: diffs
+                            .iter()
+                            .map(|diff| InputDiff::try_from(*diff))
+                            .collect::<Result<_, _>>()?,
+                    }],
+                }],
+            })
+        };
+        let ranges = WorkspaceRanges::create(vec![
+            stack(
+                stack1_id,
+                commit1_id,
+                &["@@ -1,6 +1,7 @@\n1\n2\n3\n+4\n5\n6\n7\n"],
+            )?,
+            stack(
+                stack2_id,
+                commit2_id,
+                &["@@ -10,6 +10,7 @@\n10\n11\n12\n+13\n14\n15\n16\n"],
+            )?,
+        ])?;
+        let hunk = |old_start, old_lines| GitHunk {
+            old_start,
+            old_lines,
+            new_start: old_start,
+            new_lines: old_lines,
+            diff_lines: format!("-{old_start}\n+{old_start}b\n").into(),
+            binary: false,
+            change_type: ChangeType::Modified,
+        };
+        let workdir = HashMap::from([(path.clone(), vec![hunk(4, 1), hunk(1, 15)])]);
+
+        let conflicts = cross_stack_conflicts(&ranges, &workdir);
+        assert_eq!(
+            conflicts.len(),
+            1,
+            "the first hunk only intersects the first stack"
+        );
+        let conflict = &conflicts[0];
+        assert_eq!((conflict.old_start, conflict.old_lines), (1, 15));
+        assert_eq!(
+            conflict
+                .stacks
+                .iter()
+                .map(|stack| (
+                    stack.stack_id,
+                    stack.ranges.iter().map(|r| r.commit_id).collect_vec()
+                ))
+                .collect_vec(),
+            [(stack1_id, vec![commit1_id]), (stack2_id, vec![commit2_id])]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn hunks_changing_other_words_of_a_line_are_not_locked() -> anyhow::Result<()> {
+        let path = PathBuf::from_str("/config.toml")?;
+        let stack_id = StackId::generate();
+        let commit_id = git2::Oid::from_str("a")?;
+        let diff = "-a = 1\n+a = 2\n";
+        let ranges = WorkspaceRanges::create(vec![InputStack {
+            stack_id,
+            commits: vec![InputCommit {
+                commit_id,
+                files: vec![InputFile {
+                    path: path.clone(),
+                    old_path: None,
+                    diffs: vec![InputDiff {
+                        columns: changed_columns(diff.as_bytes()).map(|changed| changed.new),
+                        ..InputDiff::try_from(format!("@@ -2,1 +2,1 @@\n{diff}").as_str())?
+                    }],
+                }],
+            }],
+        }])?;
+        let hunk = |diff_lines: &str| GitHunk {
+            old_start: 1,
+            old_lines: 3,
+            new_start: 1,
+            new_lines: 3,
+            diff_lines: diff_lines.into(),
+            binary: false,
+            change_type: ChangeType::Modified,
+        };
+
+        let key = hunk(" [section]\n-a = 2\n+b = 2\n c = 3\n");
+        let value = hunk(" [section]\n-a = 2\n+a = 3\n c = 3\n");
+        let hunks = [key.clone(), value.clone()];
+        let locks: HashMap<_, _> =
+            owned_path_hunk_locks(&ranges, &path, &hunks, |_| Some(stack_id)).collect();
+        assert!(
+            !locks.contains_key(&Hunk::hash_diff(&key.diff_lines)),
+            "the commit only changed the value"
+        );
+        assert_eq!(
+            locks.get(&Hunk::hash_diff(&value.diff_lines)),
+            Some(&vec![HunkLock {
+                branch_id: stack_id,
+                commit_id,
+            }])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn hunks_changing_other_words_of_a_line_in_another_stack_are_locked() -> anyhow::Result<()> {
+        let path = PathBuf::from_str("/config.toml")?;
+        let stack_id = StackId::generate();
+        let other_stack_id = StackId::generate();
+        let commit_id = git2::Oid::from_str("a")?;
+        let diff = "-a = 1\n+a = 2\n";
+        let ranges = WorkspaceRanges::create(vec![InputStack {
+            stack_id,
+            commits: vec![InputCommit {
+                commit_id,
+                files: vec![InputFile {
+                    path: path.clone(),
+                    old_path: None,
+                    diffs: vec![InputDiff {
+                        columns: changed_columns(diff.as_bytes()).map(|changed| changed.new),
+                        ..InputDiff::try_from(format!("@@ -2,1 +2,1 @@\n{diff}").as_str())?
+                    }],
+                }],
+            }],
+        }])?;
+        let key = GitHunk {
+            old_start: 1,
+            old_lines: 3,
+            new_start: 1,
+            new_lines: 3,
+            diff_lines: " [section]\n-a = 2\n+b = 2\n c = 3\n".into(),
+            binary: false,
+            change_type: ChangeType::Modified,
+        };
+        let hunks = [key.clone()];
+
+        let lock = vec![HunkLock {
+            branch_id: stack_id,
+            commit_id,
+        }];
+        let locks: HashMap<_, _> =
+            owned_path_hunk_locks(&ranges, &path, &hunks, |_| Some(other_stack_id)).collect();
+        assert_eq!(
+            locks.get(&Hunk::hash_diff(&key.diff_lines)),
+            Some(&lock),
+            "committing it in the other stack would conflict on the line"
+        );
+        let locks: HashMap<_, _> =
+            owned_path_hunk_locks(&ranges, &path, &hunks, |_| None).collect();
+        assert_eq!(
+            locks.get(&Hunk::hash_diff(&key.diff_lines)),
+            Some(&lock),
+            "without a stack it could end up anywhere"
+        );
+        let locks: HashMap<_, _> = path_hunk_locks(&ranges, &path, &hunks).collect();
+        assert_eq!(locks.get(&Hunk::hash_diff(&key.diff_lines)), Some(&lock));
+        Ok(())
+    }
 }
@@ -0,0 +1,146 @@
+//! Which commit of the workspace each line of a file in the worktree comes from, like `git blame`
+//! limited to the commits of the stacks.
+use std::path::Path;
+
+use gitbutler_diff::GitHunk;
+use gitbutler_stack::StackId;
+use serde::Serialize;
+
+use crate::WorkspaceRanges;
+
+/// Where a line of the worktree comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "subject", rename_all = "camelCase")]
+pub enum LineOwner {
+    /// The line is unchanged since the target.
+    Base,
+    /// The line was last changed by a commit of a stack in the workspace.
+    #[serde(rename_all = "camelCase")]
+    Commit {
+        stack_id: StackId,
+        #[serde(with = "gitbutler_serde::oid")]
+        commit_id: git2::Oid,
+    },
+    /// The line is part of the uncommitted changes.
+    Uncommitted,
+}
+
+/// Return the owner of each of the `line_count` lines of the file at `path` in the worktree, whose
+/// uncommitted changes are `hunks`, with the owner of line `n` at index `n - 1`.
+pub fn line_owners(
+    ranges: &WorkspaceRanges,
+    path: &Path,
+    hunks: &[GitHunk],
+    line_count: u32,
+) -> Vec<LineOwner> {
+    let owner = |workspace_line: u32| {
+        ranges
+            .intersection(path, workspace_line, 1)
+            .and_then(|intersection| {
+                // Ranges without lines mark deletions, which don't own any remaining line.
+                intersection.into_iter().find(|range| range.lines > 0)
+            })
+            .map_or(LineOwner::Base, |range| LineOwner::Commit {
+                stack_id: range.stack_id,
+                commit_id: range.commit_id,
+            })
+    };
+
+    let mut owners = Vec::with_capacity(line_count as usize);
+    // The next line of the worktree and of the workspace, which are kept in step outside of hunks.
+    let (mut new_line, mut old_line) = (1u32, 1u32);
+    let mut hunks: Vec<&GitHunk> = hunks.iter().filter(|hunk| !hunk.binary).collect();
+    hunks.sort_by_key(|hunk| hunk.new_start);
+    for hunk in hunks {
+        // A hunk without lines on one side is positioned at the line before the change.
+        let hunk_new_start = hunk.new_start + u32::from(hunk.new_lines == 0);
+        let hunk_old_start = hunk.old_start + u32::from(hunk.old_lines == 0);
+        while new_line < hunk_new_start && new_line <= line_count {
+            owners.push(owner(old_line));
+            new_line += 1;
+            old_line += 1;
+        }
+        old_line = hunk_old_start;
+        for line in hunk.diff_lines.split(|b| *b == b'\n') {
+            match line.first() {
+                Some(b'+') => {
+                    owners.push(LineOwner::Uncommitted);
+                    new_line += 1;
+                }
+                Some(b'-') => old_line += 1,
+                Some(b' ') => {
+                    owners.push(owner(old_line));
+                    new_line += 1;
+                    old_line += 1;
+                }
+                // Empty lines after the last separator, and markers like `\ No newline at end of file`.
+                _ => {}
+            }
+        }
+    }
+    while new_line <= line_count {
+        owners.push(owner(old_line));
+        new_line += 1;
+        old_line += 1;
+    }
+    owners.truncate(line_count as usize);
+    owners
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, str::FromStr};
+
+    use gitbutler_diff::ChangeType;
+
+    use crate::{test_utils::input_commit, InputStack};
+
+    use super::*;
+
+    #[test]
+    fn lines_are_owned_by_commits_or_uncommitted() -> anyhow::Result<()> {
+        let path = PathBuf::from_str("/test.txt")?;
+        let stack_id = StackId::generate();
+        let commit_id = git2::Oid::from_str("a")?;
+        let ranges = WorkspaceRanges::create(vec![InputStack {
+            stack_id,
+            commits: vec![input_commit(
+                commit_id,
+                &path,
+                "@@ -1,2 +1,3 @@\n1\n+2\n3\n",
+            )?],
+        }])?;
+        // The workspace has the lines 1, 2, 3, and the worktree inserts a line after 3 and
+        // removes nothing.
+        let hunks = vec![GitHunk {
+            old_start: 2,
+            old_lines: 2,
+            new_start: 2,
+            new_lines: 3,
+            diff_lines: " 2\n 3\n+4\n".into(),
+            binary: false,
+            change_type: ChangeType::Modified,
+        }];
+
+        let commit = LineOwner::Commit {
+            stack_id,
+            commit_id,
+        };
+        assert_eq!(
+            line_owners(&ranges, &path, &hunks, 5),
+            [
+                LineOwner::Base,
+                commit,
+                LineOwner::Base,
+                LineOwner::Uncommitted,
+                LineOwner::Base
+            ]
+        );
+        assert_eq!(
+            line_owners(&ranges, &path, &[], 3),
+            [LineOwner::Base, commit, LineOwner::Base],
+            "without uncommitted changes lines map to the workspace directly"
+        );
+        Ok(())
+    }
+}
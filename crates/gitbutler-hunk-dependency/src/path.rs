@@ -1,7 +1,16 @@
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+};
 
 use anyhow::bail;
 use gitbutler_stack::StackId;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
 use crate::{HunkRange, InputDiff};
 
@@ -16,12 +25,97 @@ use crate::{HunkRange, InputDiff};
 ///
 /// Therefore, a) if we are processing a new diff we know it overwrites anything it conflicts
 /// with, b) when processing an old diff we e.g. omit it if has been overwritten.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PathRanges {
     pub hunks: Vec<HunkRange>,
+    #[serde(with = "oid_set")]
     commit_ids: HashSet<git2::Oid>,
+    /// The commits whose ranges each commit changed when it was added, by the commit that changed them.
+    #[serde(with = "oid_dependencies")]
+    dependencies: HashMap<git2::Oid, HashSet<git2::Oid>>,
+    /// The diffs added so far by commit, in order, kept only if [validation is enabled](validation_enabled).
+    /// They aren't persisted, so ranges that were deserialized only report the diffs added since.
+    #[serde(skip)]
+    added: Vec<(git2::Oid, Vec<InputDiff>)>,
 }
 
+/// If set, the invariants of [`PathRanges`] are also validated in release builds.
+const VALIDATE_ENV: &str = "GITBUTLER_VALIDATE_HUNK_RANGES";
+
+static VALIDATION_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Validate the invariants of all [`PathRanges`] after each addition if `enabled`, e.g. as the user
+/// turned on the debug setting for it. This only affects ranges created from then on.
+pub fn set_validation_enabled(enabled: bool) {
+    VALIDATION_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Validating is always enabled in debug builds, and opt-in for release builds as it keeps all added
+/// diffs in memory to report them.
+fn validation_enabled() -> bool {
+    static ENV_ENABLED: OnceLock<bool> = OnceLock::new();
+    cfg!(debug_assertions)
+        || VALIDATION_ENABLED.load(Ordering::Relaxed)
+        || *ENV_ENABLED.get_or_init(|| std::env::var_os(VALIDATE_ENV).is_some())
+}
+
+/// What is wrong with [`PathRanges`] whose invariants don't hold.
+#[derive(Debug, Clone, Copy)]
+pub enum Violation {
+    /// The range ends beyond the last possible line.
+    InvalidLength(HunkRange),
+    /// The range starts before the range preceding it.
+    Unsorted {
+        previous: HunkRange,
+        hunk: HunkRange,
+    },
+    /// The range starts within the range preceding it.
+    Overlap {
+        previous: HunkRange,
+        hunk: HunkRange,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::InvalidLength(hunk) => write!(f, "{hunk:?} has an invalid length"),
+            Violation::Unsorted { previous, hunk } => {
+                write!(f, "{hunk:?} starts before {previous:?}")
+            }
+            Violation::Overlap { previous, hunk } => write!(f, "{hunk:?} overlaps {previous:?}"),
+        }
+    }
+}
+
+/// The diagnostics of [`PathRanges`] that failed to [validate](PathRanges::validate).
+#[derive(Debug, Clone)]
+pub struct RangesCorruption {
+    pub violation: Violation,
+    /// All ranges of the path.
+    pub hunks: Vec<HunkRange>,
+    /// The diffs that were added by each commit in order, which is empty unless validation was
+    /// enabled when they were added.
+    pub added: Vec<(git2::Oid, Vec<InputDiff>)>,
+}
+
+impl fmt::Display for RangesCorruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Hunk ranges are corrupted as {}. The diffs were added in this order:",
+            self.violation
+        )?;
+        for (commit_id, diffs) in &self.added {
+            writeln!(f, "{commit_id}: {diffs:?}")?;
+        }
+        write!(f, "The ranges are: {:?}", self.hunks)
+    }
+}
+
+impl std::error::Error for RangesCorruption {}
+
 impl PathRanges {
     pub fn add(
         &mut self,
@@ -32,6 +126,23 @@ impl PathRanges {
         if !self.commit_ids.insert(commit_id) {
             bail!("Commit ID already in stack: {}", commit_id)
         }
+        let validate = validation_enabled();
+        if validate {
+            self.added.push((commit_id, diffs.clone()));
+        }
+        // The old side of the new diffs is in the line numbers of the ranges so far.
+        for diff in &diffs {
+            for hunk in self
+                .hunks
+                .iter()
+                .filter(|hunk| hunk.intersects(diff.old_start, diff.old_lines))
+            {
+                self.dependencies
+                    .entry(commit_id)
+                    .or_default()
+                    .insert(hunk.commit_id);
+            }
+        }
 
         // Cumulative count of net line change, used to update start lines.
         let mut net_lines = 0;
@@ -65,10 +176,68 @@ impl PathRanges {
             new_hunks.push(last_hunk);
         };
 
+        // Changes merged from the base of the workspace are no changes of the stack, but the lines
+        // around them are shifted already.
+        let merged: Vec<(u32, u32)> = diffs
+            .iter()
+            .filter(|diff| diff.merged)
+            .map(|diff| (diff.new_start, diff.new_lines))
+            .collect();
+        if !merged.is_empty() {
+            new_hunks.retain(|hunk| {
+                hunk.commit_id != commit_id || !merged.contains(&(hunk.start, hunk.lines))
+            });
+        }
+
         self.hunks = new_hunks;
+        if validate {
+            self.validate()?;
+        }
         Ok(())
     }
 
+    /// Assure the hunks are sorted, don't overlap and have valid lengths, or fail with what is
+    /// wrong and all diffs that led to it, so it can be reproduced.
+    ///
+    /// This runs after every [`add()`](Self::add) if [validation is enabled](set_validation_enabled),
+    /// but can be called at any time.
+    pub fn validate(&self) -> Result<(), RangesCorruption> {
+        let invalid_length = self.hunks.iter().find_map(|hunk| {
+            hunk.start
+                .checked_add(hunk.lines)
+                .is_none()
+                .then_some(Violation::InvalidLength(*hunk))
+        });
+        let violation = invalid_length.or_else(|| {
+            self.hunks
+                .iter()
+                .tuple_windows()
+                .find_map(|(previous, hunk)| {
+                    let (previous, hunk) = (*previous, *hunk);
+                    if hunk.start < previous.start {
+                        Some(Violation::Unsorted { previous, hunk })
+                    } else if hunk.start < previous.start + previous.lines {
+                        Some(Violation::Overlap { previous, hunk })
+                    } else {
+                        None
+                    }
+                })
+        });
+        match violation {
+            None => Ok(()),
+            Some(violation) => Err(RangesCorruption {
+                violation,
+                hunks: self.hunks.clone(),
+                added: self.added.clone(),
+            }),
+        }
+    }
+
+    /// Returns the commits that each commit depends on, as it changed lines they changed before.
+    pub fn commit_dependencies(&self) -> &HashMap<git2::Oid, HashSet<git2::Oid>> {
+        &self.dependencies
+    }
+
     pub fn intersection(&self, start: u32, lines: u32) -> Vec<&HunkRange> {
         self.hunks
             .iter()
@@ -92,6 +261,8 @@ fn add_new(
             start: new_diff.new_start,
             lines: new_diff.new_lines,
             line_shift: new_diff.net_lines()?,
+            kind: new_diff.kind,
+            columns: new_diff.columns,
         }]);
     }
     let last_hunk = last_hunk.unwrap();
@@ -106,6 +277,8 @@ fn add_new(
                 start: new_diff.new_start,
                 lines: new_diff.new_lines,
                 line_shift: new_diff.net_lines()?,
+                kind: new_diff.kind,
+                columns: new_diff.columns,
             },
         ]);
     }
@@ -121,6 +294,8 @@ fn add_new(
                 start: last_hunk.start,
                 lines: new_diff.new_start - last_hunk.start,
                 line_shift: 0,
+                kind: last_hunk.kind,
+                columns: None,
             },
             HunkRange {
                 commit_id,
@@ -128,6 +303,8 @@ fn add_new(
                 start: new_diff.new_start,
                 lines: new_diff.new_lines,
                 line_shift: new_diff.net_lines()?,
+                kind: new_diff.kind,
+                columns: new_diff.columns,
             },
             HunkRange {
                 commit_id: last_hunk.commit_id,
@@ -137,6 +314,8 @@ fn add_new(
                     - new_diff.old_lines
                     - (new_diff.old_start - last_hunk.start),
                 line_shift: last_hunk.line_shift,
+                kind: last_hunk.kind,
+                columns: None,
             },
         ]);
     }
@@ -149,6 +328,8 @@ fn add_new(
             start: new_diff.new_start,
             lines: new_diff.new_lines,
             line_shift: new_diff.net_lines()?,
+            kind: new_diff.kind,
+            columns: new_diff.columns,
         }]);
     }
 
@@ -160,6 +341,8 @@ fn add_new(
             start: last_hunk.start,
             lines: new_diff.new_start - last_hunk.start,
             line_shift: last_hunk.line_shift,
+            kind: last_hunk.kind,
+            columns: None,
         },
         HunkRange {
             commit_id,
@@ -167,6 +350,8 @@ fn add_new(
             start: new_diff.new_start,
             lines: new_diff.new_lines,
             line_shift: new_diff.net_lines()?,
+            kind: new_diff.kind,
+            columns: new_diff.columns,
         },
     ])
 }
@@ -189,6 +374,8 @@ fn add_existing(hunk: &HunkRange, last_hunk: Option<HunkRange>, shift: i32) -> V
                 start: hunk.start.saturating_add_signed(shift),
                 lines: hunk.lines,
                 line_shift: hunk.line_shift,
+                kind: hunk.kind,
+                columns: hunk.columns,
             },
         ]
     } else if last_hunk.covered_by(hunk.start.saturating_add_signed(shift), hunk.lines) {
@@ -202,13 +389,81 @@ fn add_existing(hunk: &HunkRange, last_hunk: Option<HunkRange>, shift: i32) -> V
                 start: hunk.start.saturating_add_signed(shift),
                 lines: hunk.lines - (last_hunk.start + last_hunk.lines - hunk.start),
                 line_shift: hunk.line_shift,
+                kind: hunk.kind,
+                columns: None,
             },
         ]
     }
 }
 
+/// The ids of commits as a sorted list of hex ids, as `git2::Oid` can't be serialized itself.
+mod oid_set {
+    use std::collections::HashSet;
+
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(v: &HashSet<git2::Oid>, s: S) -> Result<S::Ok, S::Error> {
+        let mut ids: Vec<git2::Oid> = v.iter().copied().collect();
+        ids.sort();
+        gitbutler_serde::oid_vec::serialize(&ids, s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<HashSet<git2::Oid>, D::Error> {
+        Ok(gitbutler_serde::oid_vec::deserialize(d)?
+            .into_iter()
+            .collect())
+    }
+}
+
+/// The dependencies of commits, as a map from the hex id of each commit to those of the commits it
+/// depends on.
+mod oid_dependencies {
+    use std::collections::{BTreeMap, HashMap, HashSet};
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        v: &HashMap<git2::Oid, HashSet<git2::Oid>>,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        let map: BTreeMap<String, Vec<String>> = v
+            .iter()
+            .map(|(commit_id, depends_on)| {
+                let mut depends_on: Vec<String> =
+                    depends_on.iter().map(ToString::to_string).collect();
+                depends_on.sort();
+                (commit_id.to_string(), depends_on)
+            })
+            .collect();
+        map.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<HashMap<git2::Oid, HashSet<git2::Oid>>, D::Error> {
+        let parse = |hex: &str| -> Result<git2::Oid, D::Error> {
+            git2::Oid::from_str(hex).map_err(|err| serde::de::Error::custom(err.to_string()))
+        };
+        BTreeMap::<String, Vec<String>>::deserialize(d)?
+            .into_iter()
+            .map(|(commit_id, depends_on)| -> Result<_, D::Error> {
+                Ok((
+                    parse(&commit_id)?,
+                    depends_on
+                        .iter()
+                        .map(String::as_str)
+                        .map(parse)
+                        .collect::<Result<_, _>>()?,
+                ))
+            })
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::RangeKind;
+
     use super::*;
 
     #[test]
@@ -765,4 +1020,123 @@ a
 
         Ok(())
     }
+
+    #[test]
+    fn merged_diffs_shift_but_are_not_attributed() -> anyhow::Result<()> {
+        let stack_id = StackId::generate();
+        let commit_id = git2::Oid::from_str("a")?;
+        let merge_id = git2::Oid::from_str("b")?;
+        let diff = |old_start, new_start, new_lines, merged| InputDiff {
+            old_start,
+            old_lines: 0,
+            new_start,
+            new_lines,
+            merged,
+            kind: RangeKind::Lines,
+            columns: None,
+        };
+
+        let mut stack_ranges = PathRanges::default();
+        stack_ranges.add(stack_id, commit_id, vec![diff(5, 5, 1, false)])?;
+        stack_ranges.add(
+            stack_id,
+            merge_id,
+            vec![diff(1, 1, 2, true), diff(10, 12, 1, false)],
+        )?;
+
+        assert_eq!(
+            stack_ranges.intersection(7, 1)[0].commit_id,
+            commit_id,
+            "the merged lines shift the commit"
+        );
+        assert!(stack_ranges.intersection(1, 2).is_empty());
+        assert_eq!(
+            stack_ranges.intersection(12, 1)[0].commit_id,
+            merge_id,
+            "the conflict resolution is kept"
+        );
+        assert_eq!(stack_ranges.hunks.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn submodule_bumps_replace_each_other() -> anyhow::Result<()> {
+        let stack_id = StackId::generate();
+        let [commit1_id, commit2_id] = ["a", "b"].map(|id| git2::Oid::from_str(id).unwrap());
+        let bump = |old_lines| InputDiff {
+            old_start: 1,
+            old_lines,
+            new_start: 1,
+            new_lines: 1,
+            merged: false,
+            kind: RangeKind::Submodule,
+            columns: None,
+        };
+
+        let mut stack_ranges = PathRanges::default();
+        stack_ranges.add(stack_id, commit1_id, vec![bump(0)])?;
+        stack_ranges.add(stack_id, commit2_id, vec![bump(1)])?;
+
+        let hunks = stack_ranges.intersection(1, 1);
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].commit_id, commit2_id);
+        assert!(hunks[0].is_submodule());
+        assert_eq!(
+            stack_ranges.commit_dependencies().get(&commit2_id),
+            Some(&HashSet::from([commit1_id])),
+            "the second bump can't be reordered before the first"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn corrupted_ranges_are_reported() -> anyhow::Result<()> {
+        let stack_id = StackId::generate();
+        let commit_id = git2::Oid::from_str("a")?;
+        let hunk = |start, lines| HunkRange {
+            stack_id,
+            commit_id,
+            start,
+            lines,
+            line_shift: 0,
+            kind: RangeKind::Lines,
+            columns: None,
+        };
+        let mut stack_ranges = PathRanges::default();
+        stack_ranges.add(
+            stack_id,
+            commit_id,
+            vec![InputDiff::try_from("@@ -1,0 +1,2 @@\n+a\n+b\n")?],
+        )?;
+        assert!(stack_ranges.validate().is_ok());
+
+        stack_ranges.hunks = vec![hunk(1, 3), hunk(3, 1)];
+        let corruption = stack_ranges.validate().unwrap_err();
+        assert!(matches!(
+            corruption.violation,
+            Violation::Overlap { previous, hunk } if previous.start == 1 && hunk.start == 3
+        ));
+        assert_eq!(corruption.hunks.len(), 2);
+        let err = corruption.to_string();
+        assert!(err.contains("overlaps"), "{err}");
+        if validation_enabled() {
+            assert!(
+                err.contains("old_start: 1, old_lines: 0, new_start: 1, new_lines: 2"),
+                "the added diffs are included: {err}"
+            );
+        }
+
+        stack_ranges.hunks = vec![hunk(5, 1), hunk(2, 1)];
+        assert!(matches!(
+            stack_ranges.validate().unwrap_err().violation,
+            Violation::Unsorted { .. }
+        ));
+
+        stack_ranges.hunks = vec![hunk(5, u32::MAX)];
+        assert!(matches!(
+            stack_ranges.validate().unwrap_err().violation,
+            Violation::InvalidLength(_)
+        ));
+        Ok(())
+    }
 }
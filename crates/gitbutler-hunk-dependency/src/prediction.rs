@@ -0,0 +1,361 @@
+//! Predictions of whether operations on the commits of the workspace would conflict, computed from
+//! its ranges alone so actions that would fail can be disabled before they are attempted.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use gitbutler_stack::StackId;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::WorkspaceRanges;
+
+/// An operation on the commits of the workspace to predict the outcome of.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "subject", rename_all = "camelCase")]
+pub enum Operation {
+    /// Amend the uncommitted hunk at `old_start` of the file at `path` into a commit.
+    #[serde(rename_all = "camelCase")]
+    Amend {
+        #[serde(with = "gitbutler_serde::oid")]
+        commit_id: git2::Oid,
+        path: PathBuf,
+        old_start: u32,
+        old_lines: u32,
+    },
+    /// Swap two adjacent commits of a stack.
+    #[serde(rename_all = "camelCase")]
+    Swap {
+        #[serde(with = "gitbutler_serde::oid")]
+        commit_id: git2::Oid,
+        #[serde(with = "gitbutler_serde::oid")]
+        other_id: git2::Oid,
+    },
+    /// Squash commits of a stack into the one with `target_id`, or into the first of them in
+    /// application order if there is no target.
+    #[serde(rename_all = "camelCase")]
+    Squash {
+        #[serde(with = "gitbutler_serde::oid_vec")]
+        commit_ids: Vec<git2::Oid>,
+        #[serde(default, with = "gitbutler_serde::oid_opt")]
+        target_id: Option<git2::Oid>,
+    },
+}
+
+/// The predicted outcome of an [`Operation`], as found by [`WorkspaceRanges::predict()`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationPrediction {
+    pub conflicts: bool,
+    /// The files in which the operation would conflict.
+    pub paths: Vec<PathBuf>,
+    /// The lines that would conflict, in workspace line numbers, along with the commit they are
+    /// attributed to.
+    pub at_risk: Vec<AtRiskRange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtRiskRange {
+    pub path: PathBuf,
+    #[serde(with = "gitbutler_serde::oid")]
+    pub commit_id: git2::Oid,
+    pub start: u32,
+    pub lines: u32,
+}
+
+impl WorkspaceRanges {
+    /// Predict whether `operation` would conflict, and which lines would.
+    ///
+    /// Reordering commits conflicts if a commit moves past one it depends on or that depends on it,
+    /// and amending a hunk conflicts if it changes lines of a later commit, or of another stack.
+    pub fn predict(&self, operation: &Operation) -> anyhow::Result<OperationPrediction> {
+        let mut at_risk: BTreeMap<PathBuf, BTreeSet<(u32, u32, git2::Oid)>> = BTreeMap::new();
+        match operation {
+            Operation::Amend {
+                commit_id,
+                path,
+                old_start,
+                old_lines,
+            } => {
+                let (stack_id, commits) = self.stack_of(*commit_id)?;
+                let position = |id: git2::Oid| commits.iter().position(|other| *other == id);
+                let target = position(*commit_id);
+                for range in self
+                    .intersection(path, *old_start, *old_lines)
+                    .unwrap_or_default()
+                {
+                    let later = range.stack_id != stack_id || position(range.commit_id) > target;
+                    if later {
+                        at_risk.entry(path.clone()).or_default().insert((
+                            range.start,
+                            range.lines,
+                            range.commit_id,
+                        ));
+                    }
+                }
+            }
+            Operation::Swap {
+                commit_id,
+                other_id,
+            } => {
+                let (_, commits) = self.stack_of(*commit_id)?;
+                let [first, second] =
+                    [*commit_id, *other_id].map(|id| commits.iter().position(|other| *other == id));
+                let (Some(first), Some(second)) = (first, second) else {
+                    bail!("Commits {commit_id} and {other_id} are not in the same stack");
+                };
+                if first.abs_diff(second) != 1 {
+                    bail!("Commits {commit_id} and {other_id} are not adjacent");
+                }
+                let (lower, upper) = (commits[first.min(second)], commits[first.max(second)]);
+                self.add_crossing(&mut at_risk, upper, lower);
+            }
+            Operation::Squash {
+                commit_ids,
+                target_id,
+            } => {
+                let Some(first) = target_id.as_ref().or(commit_ids.first()) else {
+                    bail!("No commits to squash");
+                };
+                let (_, commits) = self.stack_of(*first)?;
+                let positions = commit_ids
+                    .iter()
+                    .chain(target_id)
+                    .map(|id| {
+                        commits
+                            .iter()
+                            .position(|other| other == id)
+                            .with_context(|| format!("Commit {id} is not in the same stack"))
+                    })
+                    .collect::<anyhow::Result<BTreeSet<_>>>()?;
+                let target = match target_id {
+                    Some(target_id) => commits
+                        .iter()
+                        .position(|other| other == target_id)
+                        .expect("the target is in the stack"),
+                    None => *positions.first().expect("there is a commit"),
+                };
+                // Each commit moves to the target, past the commits between them that aren't
+                // squashed. Moving down conflicts if the moved commit depends on a crossed one,
+                // and moving up if a crossed commit depends on the moved one.
+                for &moved in &positions {
+                    for crossed in target + 1..moved {
+                        if !positions.contains(&crossed) {
+                            self.add_crossing(&mut at_risk, commits[moved], commits[crossed]);
+                        }
+                    }
+                    for crossed in moved + 1..target {
+                        if !positions.contains(&crossed) {
+                            self.add_crossing(&mut at_risk, commits[crossed], commits[moved]);
+                        }
+                    }
+                }
+            }
+        }
+
+        let paths = at_risk.keys().cloned().collect_vec();
+        let at_risk = at_risk
+            .into_iter()
+            .flat_map(|(path, ranges)| {
+                ranges
+                    .into_iter()
+                    .map(move |(start, lines, commit_id)| AtRiskRange {
+                        path: path.clone(),
+                        commit_id,
+                        start,
+                        lines,
+                    })
+            })
+            .collect_vec();
+        Ok(OperationPrediction {
+            conflicts: !paths.is_empty(),
+            paths,
+            at_risk,
+        })
+    }
+
+    /// Fail if the commits of a stack were reordered as `commit_ids`, in application order, and a
+    /// submodule bump moved before an earlier bump of the same submodule.
+    ///
+    /// Unlike lines, a bump replaces the commit the submodule points to as a whole, so bumps that
+    /// swap places can't be resolved and each bump has to move as one.
+    pub fn assure_submodule_bumps_ordered(&self, commit_ids: &[git2::Oid]) -> anyhow::Result<()> {
+        let position = |id: git2::Oid| commit_ids.iter().position(|other| *other == id);
+        for (path, commit_id, depends_on) in self.commit_dependencies() {
+            if !self.is_submodule(path) {
+                continue;
+            }
+            if let (Some(bump), Some(earlier)) = (position(commit_id), position(depends_on)) {
+                if bump < earlier {
+                    bail!(
+                        "Commit {commit_id} bumps the submodule at '{}' after commit {depends_on} \
+                         and can't be moved before it",
+                        path.display()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the stack of the commit with `commit_id`, and its commits in application order.
+    fn stack_of(&self, commit_id: git2::Oid) -> anyhow::Result<(StackId, Vec<git2::Oid>)> {
+        let stack_id = self
+            .commits()
+            .find_map(|(stack_id, id)| (id == commit_id).then_some(stack_id))
+            .with_context(|| format!("Commit {commit_id} is not in the workspace"))?;
+        let commits = self
+            .commits()
+            .filter(|(other, _)| *other == stack_id)
+            .map(|(_, id)| id)
+            .collect();
+        Ok((stack_id, commits))
+    }
+
+    /// Add the lines at risk as the commit `moved` moves down past `crossed`, which are its lines
+    /// in the paths where it depends on `crossed`.
+    fn add_crossing(
+        &self,
+        at_risk: &mut BTreeMap<PathBuf, BTreeSet<(u32, u32, git2::Oid)>>,
+        moved: git2::Oid,
+        crossed: git2::Oid,
+    ) {
+        let paths: BTreeSet<&Path> = self
+            .commit_dependencies()
+            .filter(|(_, commit_id, depends_on)| *commit_id == moved && *depends_on == crossed)
+            .map(|(path, _, _)| path)
+            .collect();
+        for path in &paths {
+            at_risk.entry(path.to_path_buf()).or_default();
+        }
+        for (path, range) in self.commit_ranges(moved) {
+            if paths.contains(path) {
+                at_risk.entry(path.to_owned()).or_default().insert((
+                    range.start,
+                    range.lines,
+                    moved,
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        test_utils::input_commit, InputCommit, InputDiff, InputFile, InputStack, RangeKind,
+    };
+
+    use super::*;
+
+    #[test]
+    fn operations_conflict_with_dependencies() -> anyhow::Result<()> {
+        let path = PathBuf::from_str("/test.txt")?;
+        let stack_id = StackId::generate();
+        let [commit1_id, commit2_id, commit3_id] =
+            ["a", "b", "c"].map(|id| git2::Oid::from_str(id).unwrap());
+
+        let ranges = WorkspaceRanges::create(vec![InputStack {
+            stack_id,
+            commits: vec![
+                input_commit(commit1_id, &path, "@@ -1,2 +1,3 @@\n1\n+2\n3\n")?,
+                input_commit(commit2_id, &path, "@@ -1,3 +1,3 @@\n1\n-2\n+2b\n3\n")?,
+                input_commit(commit3_id, &path, "@@ -9,2 +9,3 @@\n9\n+10\n11\n")?,
+            ],
+        }])?;
+        let conflicts = |operation: Operation| -> anyhow::Result<bool> {
+            Ok(ranges.predict(&operation)?.conflicts)
+        };
+        let swap = |commit_id, other_id| Operation::Swap {
+            commit_id,
+            other_id,
+        };
+        let squash = |commit_ids: &[git2::Oid]| Operation::Squash {
+            commit_ids: commit_ids.to_vec(),
+            target_id: None,
+        };
+        let squash_into = |target_id, commit_ids: &[git2::Oid]| Operation::Squash {
+            commit_ids: commit_ids.to_vec(),
+            target_id: Some(target_id),
+        };
+        let amend = |commit_id, old_start| Operation::Amend {
+            commit_id,
+            path: path.clone(),
+            old_start,
+            old_lines: 1,
+        };
+
+        let prediction = ranges.predict(&swap(commit1_id, commit2_id))?;
+        assert!(prediction.conflicts, "the second commit changes the first");
+        assert_eq!(prediction.paths, [path.clone()]);
+        assert_eq!(prediction.at_risk.len(), 1);
+        assert_eq!(prediction.at_risk[0].commit_id, commit2_id);
+        assert!(!conflicts(swap(commit3_id, commit2_id))?);
+        assert!(ranges.predict(&swap(commit1_id, commit3_id)).is_err());
+
+        assert!(!conflicts(squash(&[commit1_id, commit2_id]))?);
+        assert!(
+            !conflicts(squash(&[commit3_id, commit1_id]))?,
+            "the third commit moves past the second, which it doesn't depend on"
+        );
+        assert!(
+            conflicts(squash_into(commit3_id, &[commit1_id]))?,
+            "the second commit depends on the first, which moves up past it"
+        );
+        assert!(!conflicts(squash_into(commit3_id, &[commit2_id]))?);
+
+        assert!(conflicts(amend(commit1_id, 2))?, "the line is the second's");
+        assert!(!conflicts(amend(commit2_id, 2))?);
+        assert!(!conflicts(amend(commit3_id, 2))?);
+        assert!(conflicts(amend(commit1_id, 10))?);
+        Ok(())
+    }
+
+    #[test]
+    fn submodule_bumps_keep_their_order() -> anyhow::Result<()> {
+        let path = PathBuf::from_str("/test.txt")?;
+        let stack_id = StackId::generate();
+        let [commit1_id, commit2_id, commit3_id, commit4_id] =
+            ["a", "b", "c", "d"].map(|id| git2::Oid::from_str(id).unwrap());
+        let bump = |commit_id, old_lines| InputCommit {
+            commit_id,
+            files: vec![InputFile {
+                path: PathBuf::from("submodule"),
+                old_path: None,
+                diffs: vec![InputDiff {
+                    old_start: 1,
+                    old_lines,
+                    new_start: 1,
+                    new_lines: 1,
+                    merged: false,
+                    kind: RangeKind::Submodule,
+                }],
+            }],
+        };
+
+        let ranges = WorkspaceRanges::create(vec![InputStack {
+            stack_id,
+            commits: vec![
+                bump(commit1_id, 0),
+                bump(commit2_id, 1),
+                input_commit(commit3_id, &path, "@@ -1,2 +1,3 @@\n1\n+2\n3\n")?,
+                input_commit(commit4_id, &path, "@@ -1,3 +1,3 @@\n1\n-2\n+2b\n3\n")?,
+            ],
+        }])?;
+
+        let err = ranges
+            .assure_submodule_bumps_ordered(&[commit2_id, commit1_id, commit3_id, commit4_id])
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("bumps the submodule at 'submodule'"));
+        ranges.assure_submodule_bumps_ordered(&[commit1_id, commit3_id, commit2_id, commit4_id])?;
+        ranges.assure_submodule_bumps_ordered(&[commit1_id, commit2_id, commit4_id, commit3_id])?;
+        Ok(())
+    }
+}
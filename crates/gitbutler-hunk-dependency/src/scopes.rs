@@ -0,0 +1,553 @@
+//! The syntactic scopes of a file, like functions and methods, so uncommitted changes only depend
+//! on the commits that changed the same scope.
+//!
+//! Line ranges over-approximate dependencies, as the context lines of a hunk reach into whatever
+//! surrounds the change. With scopes, a hunk that changes lines of a single function isn't locked
+//! to commits that only changed other functions.
+//!
+//! Scopes are found by a lightweight scanner rather than a full parser, which is enough to tell
+//! functions apart: blocks in braces for C-like languages, and indented `def` and `class` blocks
+//! for Python. Blocks of containers like `impl`, `class` or `namespace` aren't scopes themselves,
+//! but the blocks in them are. Whenever the scanner can't be sure, like with unbalanced braces or
+//! strings it doesn't understand, the whole file is a single scope, so no lock is dropped.
+use std::path::Path;
+
+use bstr::ByteSlice as _;
+use gitbutler_diff::{GitHunk, HunkHash};
+use gitbutler_stack::StackId;
+use itertools::Itertools as _;
+
+use crate::{locks::hunk_locks, HunkLock, HunkRange, WorkspaceRanges};
+
+/// The extensions of languages whose blocks are delimited by braces.
+const BRACE_EXTENSIONS: &[&str] = &[
+    "c", "cc", "cpp", "cs", "cxx", "go", "h", "hpp", "java", "js", "jsx", "kt", "mjs", "php", "rs",
+    "scala", "swift", "ts", "tsx",
+];
+/// The extensions of the languages above in which `'` starts a string rather than a character.
+const SINGLE_QUOTED_STRING_EXTENSIONS: &[&str] = &["js", "jsx", "mjs", "php", "ts", "tsx"];
+/// The extensions of languages whose blocks are delimited by indentation.
+const INDENTATION_EXTENSIONS: &[&str] = &["py", "pyi"];
+
+/// The keywords that start a block which contains scopes instead of being one.
+const CONTAINER_KEYWORDS: &[&str] = &[
+    "class",
+    "enum",
+    "extension",
+    "impl",
+    "interface",
+    "mod",
+    "namespace",
+    "object",
+    "protocol",
+    "trait",
+];
+/// The words that may precede a container keyword.
+const MODIFIERS: &[&str] = &[
+    "abstract",
+    "crate",
+    "data",
+    "default",
+    "export",
+    "final",
+    "in",
+    "internal",
+    "open",
+    "partial",
+    "private",
+    "protected",
+    "pub",
+    "public",
+    "sealed",
+    "self",
+    "static",
+    "super",
+    "unsafe",
+];
+
+/// A block of lines from `start` to `end`, both included and counting from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scope {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Scope {
+    fn contains(&self, line: u32) -> bool {
+        self.start <= line && line <= self.end
+    }
+
+    /// Whether any line of `range` is in the scope.
+    fn overlaps(&self, range: &HunkRange) -> bool {
+        if range.start == 0 {
+            // The file was deleted, which touches every scope.
+            return true;
+        }
+        let end = range.start + range.lines.max(1) - 1;
+        self.start <= end && range.start <= self.end
+    }
+}
+
+/// Return the scopes of the file at `path` with `content`, ordered by line, or `None` if its
+/// language isn't supported. If the structure of `content` couldn't be determined reliably, the
+/// whole file is returned as the only scope.
+pub fn file_scopes(path: &Path, content: &[u8]) -> Option<Vec<Scope>> {
+    let extension = path.extension()?.to_str()?;
+    let lines: Vec<&[u8]> = content.lines().collect();
+    let scopes = if BRACE_EXTENSIONS.contains(&extension) {
+        brace_scopes(&lines, SINGLE_QUOTED_STRING_EXTENSIONS.contains(&extension))
+    } else if INDENTATION_EXTENSIONS.contains(&extension) {
+        indentation_scopes(&lines)
+    } else {
+        return None;
+    };
+    Some(scopes.unwrap_or_else(|| {
+        vec![Scope {
+            start: 1,
+            end: u32::try_from(lines.len()).unwrap_or(u32::MAX).max(1),
+        }]
+    }))
+}
+
+/// Returns the locks of the uncommitted `hunks` of the file at `path` like
+/// [`owned_path_hunk_locks()`](crate::owned_path_hunk_locks), except that a hunk which only changes
+/// lines of one of `scopes` isn't locked to commits whose ranges are all outside of it.
+pub fn scoped_hunk_locks<'a>(
+    ranges: &'a WorkspaceRanges,
+    path: &'a Path,
+    hunks: &'a [GitHunk],
+    scopes: &'a [Scope],
+    stack_of: impl Fn(&GitHunk) -> Option<StackId> + 'a,
+) -> impl Iterator<Item = (HunkHash, Vec<HunkLock>)> + 'a {
+    hunks.iter().filter_map(move |hunk| {
+        let (hash, locks) = hunk_locks(ranges, path, hunk, stack_of(hunk))?;
+        let Some(scope) = hunk_scope(scopes, hunk) else {
+            return Some((hash, locks));
+        };
+        let in_scope = ranges
+            .intersection(path, hunk.old_start, hunk.old_lines)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|range| scope.overlaps(range))
+            .collect_vec();
+        let locks = locks
+            .into_iter()
+            .filter(|lock| {
+                in_scope.iter().any(|range| {
+                    range.commit_id == lock.commit_id && range.stack_id == lock.branch_id
+                })
+            })
+            .collect_vec();
+        (!locks.is_empty()).then_some((hash, locks))
+    })
+}
+
+/// The scope that contains all lines `hunk` changes, not counting its context lines.
+fn hunk_scope(scopes: &[Scope], hunk: &GitHunk) -> Option<Scope> {
+    let (first, last) = changed_lines(hunk)?;
+    scopes
+        .iter()
+        .find(|scope| scope.contains(first) && scope.contains(last))
+        .copied()
+}
+
+/// The first and last line before the change that `hunk` removes or inserts lines at. Lines
+/// inserted between two others, and not in place of removed ones, count as changing both.
+fn changed_lines(hunk: &GitHunk) -> Option<(u32, u32)> {
+    let mut line = hunk.old_start;
+    let mut changed: Option<(u32, u32)> = None;
+    // Whether the lines being read replace removed ones, rather than being inserted.
+    let mut replacing = false;
+    let mut add = |first: u32, last: u32| {
+        changed = Some(match changed {
+            Some((start, end)) => (start.min(first), end.max(last)),
+            None => (first, last),
+        });
+    };
+    for diff_line in hunk.diff_lines.lines() {
+        if diff_line.starts_with(b"@@") {
+            continue;
+        }
+        match diff_line.first() {
+            Some(b' ') => {
+                line += 1;
+                replacing = false;
+            }
+            Some(b'-') => {
+                add(line, line);
+                line += 1;
+                replacing = true;
+            }
+            Some(b'+') if !replacing => add(line.saturating_sub(1).max(1), line),
+            _ => {}
+        }
+    }
+    changed
+}
+
+/// Find the scopes of `lines` of a language with blocks in braces, skipping braces in strings
+/// and comments, or `None` if braces are unbalanced or a comment or string isn't terminated.
+fn brace_scopes(lines: &[&[u8]], single_quoted_strings: bool) -> Option<Vec<Scope>> {
+    let mut scopes = Vec::new();
+    // The blocks that are open, with the line they start on and whether they are containers.
+    let mut open: Vec<(u32, bool)> = Vec::new();
+    let mut in_block_comment = false;
+    let mut string: Option<u8> = None;
+    for (index, &line) in lines.iter().enumerate() {
+        let number = u32::try_from(index + 1).unwrap_or(u32::MAX);
+        let mut i = 0;
+        while i < line.len() {
+            let rest = &line[i..];
+            if in_block_comment {
+                if rest.starts_with(b"*/") {
+                    in_block_comment = false;
+                    i += 1;
+                }
+            } else if let Some(quote) = string {
+                if rest[0] == b'\\' {
+                    i += 1;
+                } else if rest[0] == quote {
+                    string = None;
+                }
+            } else if rest.starts_with(b"//") {
+                break;
+            } else if rest.starts_with(b"/*") {
+                in_block_comment = true;
+                i += 1;
+            } else {
+                match rest[0] {
+                    b'"' | b'`' => string = Some(rest[0]),
+                    b'\'' if single_quoted_strings => string = Some(b'\''),
+                    b'\'' => {
+                        // A character, unless it's a lifetime or label that isn't closed.
+                        if let Some(len) = char_literal_len(rest) {
+                            i += len - 1;
+                        }
+                    }
+                    b'{' => {
+                        let at_top = open.iter().all(|(_, is_container)| *is_container);
+                        let (start, header) = block_header(lines, index, &line[..i]);
+                        open.push((start, at_top && is_container(header)));
+                    }
+                    b'}' => {
+                        let (start, is_container) = open.pop()?;
+                        let at_top = open.iter().all(|(_, is_container)| *is_container);
+                        if at_top && !is_container {
+                            scopes.push(Scope { start, end: number });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            i += 1;
+        }
+        // Only template strings span lines, the others are raw or multi-line strings which
+        // aren't understood.
+        if string.is_some_and(|quote| quote != b'`') {
+            return None;
+        }
+    }
+    if !open.is_empty() || in_block_comment || string.is_some() {
+        return None;
+    }
+    scopes.sort_by_key(|scope| scope.start);
+    Some(scopes)
+}
+
+/// The length of the character literal `text` starts with, like `'a'` or `'\n'`.
+fn char_literal_len(text: &[u8]) -> Option<usize> {
+    match text.get(1)? {
+        b'\\' => text
+            .iter()
+            .skip(2)
+            .take(10)
+            .position(|b| *b == b'\'')
+            .map(|pos| pos + 3),
+        _ => text
+            .iter()
+            .skip(1)
+            .take(4)
+            .position(|b| *b == b'\'')
+            .filter(|pos| *pos > 0)
+            .map(|pos| pos + 2),
+    }
+}
+
+/// The line a block opened on line `index` starts on, and the text before its brace. A brace on
+/// a line of its own belongs to the header on the line before.
+fn block_header<'a>(lines: &[&'a [u8]], index: usize, before: &'a [u8]) -> (u32, &'a [u8]) {
+    let number = |index: usize| u32::try_from(index + 1).unwrap_or(u32::MAX);
+    if !before.trim().is_empty() || index == 0 {
+        return (number(index), before);
+    }
+    match lines[..index]
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+    {
+        Some(header) => (number(header), lines[header]),
+        None => (number(index), before),
+    }
+}
+
+/// Whether the block with `header` is a container, which starts with a container keyword after
+/// modifiers like `pub` or `export`.
+fn is_container(header: &[u8]) -> bool {
+    header
+        .split(|b| !(b.is_ascii_alphanumeric() || *b == b'_'))
+        .filter(|word| !word.is_empty())
+        .filter_map(|word| word.to_str().ok())
+        .find(|word| !MODIFIERS.contains(word))
+        .is_some_and(|word| CONTAINER_KEYWORDS.contains(&word))
+}
+
+/// Find the scopes of `lines` of Python, which are `def` blocks that aren't in other ones, or
+/// `None` if indentation mixes tabs and spaces or a string spans lines, as both hide the structure.
+fn indentation_scopes(lines: &[&[u8]]) -> Option<Vec<Scope>> {
+    let mut scopes = Vec::new();
+    // The blocks that are open, with their indentation, the line they start on and whether they
+    // are classes.
+    let mut open: Vec<(usize, u32, bool)> = Vec::new();
+    let mut last_line = 0;
+    let close = |open: &mut Vec<(usize, u32, bool)>, end: u32, scopes: &mut Vec<Scope>| {
+        if let Some((_, start, is_class)) = open.pop() {
+            if !is_class && open.iter().all(|(_, _, is_class)| *is_class) {
+                scopes.push(Scope { start, end });
+            }
+        }
+    };
+    for (index, line) in lines.iter().enumerate() {
+        let number = u32::try_from(index + 1).unwrap_or(u32::MAX);
+        let code = line.trim_start();
+        if code.is_empty() || code.starts_with(b"#") {
+            continue;
+        }
+        let indent = line.len() - code.len();
+        let indentation = &line[..indent];
+        if (indentation.contains(&b'\t') && indentation.contains(&b' '))
+            || code.find_iter("\"\"\"").count() % 2 == 1
+            || code.find_iter("'''").count() % 2 == 1
+        {
+            return None;
+        }
+        while open
+            .last()
+            .is_some_and(|(open_indent, _, _)| *open_indent >= indent)
+        {
+            close(&mut open, last_line, &mut scopes);
+        }
+        if code.starts_with(b"class ") {
+            open.push((indent, number, true));
+        } else if code.starts_with(b"def ") || code.starts_with(b"async def ") {
+            open.push((indent, number, false));
+        }
+        last_line = number;
+    }
+    while !open.is_empty() {
+        close(&mut open, last_line, &mut scopes);
+    }
+    scopes.sort_by_key(|scope| scope.start);
+    Some(scopes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use gitbutler_diff::ChangeType;
+
+    use crate::{path_hunk_locks, test_utils::input_commit, InputStack};
+
+    use super::*;
+
+    fn scope(start: u32, end: u32) -> Scope {
+        Scope { start, end }
+    }
+
+    #[test]
+    fn functions_are_scopes() {
+        let source = r#"use std::fmt;
+
+impl Foo {
+    fn a<'a>(&'a self) -> &'a str {
+        "}"
+    }
+
+    /* } */
+    fn b(&self) -> char
+    {
+        '{'
+    }
+}
+
+fn c() {
+    if true {
+        // }
+    }
+}
+"#;
+        assert_eq!(
+            file_scopes(Path::new("lib.rs"), source.as_bytes()),
+            Some(vec![scope(4, 6), scope(9, 12), scope(15, 19)])
+        );
+        assert_eq!(file_scopes(Path::new("notes.txt"), source.as_bytes()), None);
+    }
+
+    #[test]
+    fn nested_blocks_belong_to_their_function() {
+        let source = r#"fn a() {
+    let f = |x| {
+        match x {
+            _ => { "{{" }
+        }
+    };
+}
+
+mod m {
+    struct S { s: &'static str }
+    fn b() {
+        /* { */ let c = '}';
+    }
+}
+"#;
+        assert_eq!(
+            file_scopes(Path::new("lib.rs"), source.as_bytes()),
+            Some(vec![scope(1, 7), scope(10, 10), scope(11, 13)])
+        );
+    }
+
+    #[test]
+    fn uncertain_structure_is_a_single_scope() {
+        let whole_file = Some(vec![scope(1, 4)]);
+        for (name, source) in [
+            ("a closing brace too many", "fn a() {\n}\n}\nfn b() {}\n"),
+            ("an unclosed block", "fn a() {\n    {\n}\nfn b() {}\n"),
+            ("an unterminated comment", "fn a() {\n}\n/* fn b() {\n}\n"),
+            ("a raw string", "fn a() {\n    r#\"\" }\"#;\n}\nfn b() {}\n"),
+            ("a string spanning lines", "fn a() {\n    \"{\n    \";\n}\n"),
+        ] {
+            assert_eq!(
+                file_scopes(Path::new("lib.rs"), source.as_bytes()),
+                whole_file,
+                "{name}"
+            );
+        }
+
+        assert_eq!(
+            file_scopes(
+                Path::new("foo.py"),
+                b"def a():\n    \"\"\"\ndef b():\n    \"\"\"\n"
+            ),
+            whole_file,
+            "a docstring spanning lines"
+        );
+        assert_eq!(
+            file_scopes(
+                Path::new("foo.py"),
+                b"def a():\n\t pass\ndef b():\n    pass\n"
+            ),
+            whole_file,
+            "tabs and spaces"
+        );
+        assert_eq!(
+            file_scopes(Path::new("foo.py"), b"def a():\n    \"\"\"Doc.\"\"\"\n"),
+            Some(vec![scope(1, 2)]),
+            "docstrings on one line are fine"
+        );
+    }
+
+    #[test]
+    fn python_functions_are_scopes() {
+        let source = "import os
+
+class Foo:
+    def a(self):
+        return 1
+
+    def b(self):
+        def inner():
+            pass
+
+        return inner
+
+def c():
+    pass
+";
+        assert_eq!(
+            file_scopes(Path::new("foo.py"), source.as_bytes()),
+            Some(vec![scope(4, 5), scope(7, 11), scope(13, 14)])
+        );
+    }
+
+    #[test]
+    fn hunks_are_not_locked_to_commits_of_other_scopes() -> anyhow::Result<()> {
+        let path = PathBuf::from("lib.rs");
+        let stack_id = StackId::generate();
+        let commit_id = git2::Oid::from_str("a")?;
+        // The commit changed the body of `a`.
+        let ranges = WorkspaceRanges::create(vec![InputStack {
+            stack_id,
+            commits: vec![input_commit(
+                commit_id,
+                &path,
+                "@@ -2,1 +2,1 @@\n-    zero();\n+    one();\n",
+            )?],
+        }])?;
+        let content = "fn a() {\n    one();\n}\nfn b() {\n    two();\n}\n";
+        let scopes = file_scopes(&path, content.as_bytes()).unwrap();
+
+        // Changing the body of `b` has context lines reaching into `a`.
+        let hunk = GitHunk {
+            old_start: 2,
+            old_lines: 5,
+            new_start: 2,
+            new_lines: 5,
+            diff_lines:
+                "@@ -2,5 +2,5 @@\n     one();\n }\n fn b() {\n-    two();\n+    three();\n }\n"
+                    .into(),
+            binary: false,
+            change_type: ChangeType::Modified,
+        };
+        let hunks = [hunk];
+        assert_eq!(
+            path_hunk_locks(&ranges, &path, &hunks).count(),
+            1,
+            "the lines intersect"
+        );
+        assert_eq!(
+            scoped_hunk_locks(&ranges, &path, &hunks, &scopes, |_| None).count(),
+            0
+        );
+
+        // Changing the body of `a` is still locked.
+        let hunks = [GitHunk {
+            old_start: 1,
+            old_lines: 3,
+            new_start: 1,
+            new_lines: 3,
+            diff_lines: "@@ -1,3 +1,3 @@\n fn a() {\n-    one();\n+    uno();\n }\n".into(),
+            ..hunks[0].clone()
+        }];
+        let locks = scoped_hunk_locks(&ranges, &path, &hunks, &scopes, |_| None).collect_vec();
+        assert_eq!(locks.len(), 1);
+        assert_eq!(locks[0].1[0].commit_id, commit_id);
+
+        // An unbalanced brace makes the scopes uncertain, and no lock is dropped.
+        let content = "fn a() {\n    one();\n}\nfn b() {\n    two();\n}\n}\n";
+        let scopes = file_scopes(&path, content.as_bytes()).unwrap();
+        let hunks = [GitHunk {
+            old_start: 2,
+            old_lines: 5,
+            new_start: 2,
+            new_lines: 5,
+            diff_lines:
+                "@@ -2,5 +2,5 @@\n     one();\n }\n fn b() {\n-    two();\n+    three();\n }\n"
+                    .into(),
+            ..hunks[0].clone()
+        }];
+        assert_eq!(
+            scoped_hunk_locks(&ranges, &path, &hunks, &scopes, |_| None).count(),
+            path_hunk_locks(&ranges, &path, &hunks).count(),
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+//! Previews of operations on the commits of the workspace, computed from its ranges alone so they
+//! can be shown before the repository is touched.
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::Context;
+use gitbutler_diff::{GitHunk, Hunk};
+use gitbutler_stack::StackId;
+use serde::Serialize;
+
+use crate::WorkspaceRanges;
+
+/// What would conflict if a commit was removed from the workspace, as found by
+/// [`WorkspaceRanges::simulate_remove()`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemovalPreview {
+    #[serde(with = "gitbutler_serde::oid")]
+    pub commit_id: git2::Oid,
+    pub stack_id: StackId,
+    /// The commits that changed lines the commit changed, and can't be applied without it, in
+    /// application order.
+    #[serde(with = "gitbutler_serde::oid_vec")]
+    pub conflicting_commits: Vec<git2::Oid>,
+    /// The uncommitted hunks that change lines the commit changed.
+    pub conflicting_hunks: Vec<ConflictingHunk>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictingHunk {
+    pub path: PathBuf,
+    /// The hash of the diff of the hunk, as used to identify hunks elsewhere.
+    pub hunk_hash: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+}
+
+impl WorkspaceRanges {
+    /// Simulate removing the commit with `commit_id` from its stack, and return the ranges of the
+    /// workspace without it along with what would conflict, given the uncommitted hunks in `workdir`.
+    ///
+    /// The ranges of the other commits shift back by the lines the commit added or removed, just as
+    /// they would once it's removed, while `self` stays untouched.
+    pub fn simulate_remove(
+        &self,
+        commit_id: git2::Oid,
+        workdir: &HashMap<PathBuf, Vec<GitHunk>>,
+    ) -> anyhow::Result<(WorkspaceRanges, RemovalPreview)> {
+        let stack_id = self
+            .commits()
+            .find_map(|(stack_id, id)| (id == commit_id).then_some(stack_id))
+            .with_context(|| format!("Commit {commit_id} is not in the workspace"))?;
+
+        let mut conflicting_commits: Vec<git2::Oid> = self
+            .commit_dependencies()
+            .filter(|(_, _, depends_on)| *depends_on == commit_id)
+            .map(|(_, dependent, _)| dependent)
+            .collect();
+        let order: Vec<git2::Oid> = self.commits().map(|(_, id)| id).collect();
+        conflicting_commits.sort_by_key(|id| order.iter().position(|other| other == id));
+        conflicting_commits.dedup();
+
+        let removed = self.commit_ranges(commit_id);
+        let mut conflicting_hunks: Vec<ConflictingHunk> = workdir
+            .iter()
+            .flat_map(|(path, hunks)| {
+                let removed = &removed;
+                hunks
+                    .iter()
+                    .filter(move |hunk| {
+                        removed.iter().any(|(removed_path, range)| {
+                            *removed_path == path.as_path()
+                                && range.intersects(hunk.old_start, hunk.old_lines)
+                        })
+                    })
+                    .map(move |hunk| ConflictingHunk {
+                        path: path.clone(),
+                        hunk_hash: format!("{:x}", Hunk::hash_diff(&hunk.diff_lines)),
+                        old_start: hunk.old_start,
+                        old_lines: hunk.old_lines,
+                    })
+            })
+            .collect();
+        conflicting_hunks.sort_by(|a, b| (&a.path, a.old_start).cmp(&(&b.path, b.old_start)));
+
+        let mut ranges = self.clone();
+        ranges.remove_commit(stack_id, commit_id)?;
+        Ok((
+            ranges,
+            RemovalPreview {
+                commit_id,
+                stack_id,
+                conflicting_commits,
+                conflicting_hunks,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use gitbutler_diff::ChangeType;
+
+    use crate::{test_utils::input_commit, InputStack};
+
+    use super::*;
+
+    #[test]
+    fn removing_a_commit_conflicts_with_its_dependents() -> anyhow::Result<()> {
+        let path = PathBuf::from_str("/test.txt")?;
+        let stack_id = StackId::generate();
+        let [commit1_id, commit2_id, commit3_id] =
+            ["a", "b", "c"].map(|id| git2::Oid::from_str(id).unwrap());
+
+        let ranges = WorkspaceRanges::create(vec![InputStack {
+            stack_id,
+            commits: vec![
+                input_commit(commit1_id, &path, "@@ -1,2 +1,3 @@\n1\n+2\n3\n")?,
+                input_commit(commit2_id, &path, "@@ -1,3 +1,3 @@\n1\n-2\n+2b\n3\n")?,
+                input_commit(commit3_id, &path, "@@ -9,2 +9,4 @@\n9\n+10\n+11\n12\n")?,
+            ],
+        }])?;
+        let hunk = |old_start| GitHunk {
+            old_start,
+            old_lines: 1,
+            new_start: old_start,
+            new_lines: 1,
+            diff_lines: format!("-{old_start}\n+{old_start}b\n").into(),
+            binary: false,
+            change_type: ChangeType::Modified,
+        };
+        let workdir = HashMap::from([(path.clone(), vec![hunk(10), hunk(20)])]);
+
+        let (_, preview) = ranges.simulate_remove(commit1_id, &workdir)?;
+        assert_eq!(preview.conflicting_commits, [commit2_id]);
+        assert!(preview.conflicting_hunks.is_empty());
+
+        let (without, preview) = ranges.simulate_remove(commit3_id, &workdir)?;
+        assert!(preview.conflicting_commits.is_empty());
+        assert_eq!(
+            preview
+                .conflicting_hunks
+                .iter()
+                .map(|hunk| hunk.old_start)
+                .collect::<Vec<_>>(),
+            [10],
+            "only the hunk changing lines the commit added conflicts"
+        );
+        assert!(without.commit_ranges(commit3_id).is_empty());
+        assert!(
+            !ranges.commit_ranges(commit3_id).is_empty(),
+            "the ranges themselves are untouched"
+        );
+        Ok(())
+    }
+}
@@ -1,21 +1,220 @@
 use std::{
     collections::{HashMap, HashSet},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
+use anyhow::bail;
 use gitbutler_stack::StackId;
 use itertools::Itertools;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::{instrument, Span};
 
-use crate::{HunkRange, InputDiff, PathRanges};
+use crate::{HunkRange, InputDiff, InputStack, PathRanges};
 
-#[derive(Debug, Default)]
+/// The number of paths from which their ranges are computed in parallel. With fewer paths, handing
+/// them to the thread pool costs more than it saves. It can be tuned with the `ranges` benchmark.
+pub(crate) const PARALLEL_THRESHOLD: usize = 64;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct StackRanges {
     pub paths: HashMap<PathBuf, PathRanges>,
+    /// The paths that were skipped as their ranges couldn't be computed, if created
+    /// [leniently](Self::create_lenient).
+    #[serde(default)]
+    pub errors: Vec<PathError>,
+}
+
+/// Why the ranges of a path couldn't be computed, e.g. as one of the diffs of a commit is
+/// malformed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathError {
+    pub stack_id: StackId,
+    pub path: PathBuf,
+    /// The commit whose diffs couldn't be added.
+    #[serde(with = "gitbutler_serde::oid")]
+    pub commit_id: git2::Oid,
+    pub message: String,
+}
+
+/// Collect the ranges of the file at `path` from the diffs `commits` made to it, in application
+/// order, within a span that is a child of `parent` even if this runs on another thread.
+fn path_ranges(
+    parent: &Span,
+    stack_id: StackId,
+    path: &Path,
+    commits: Vec<(git2::Oid, &Vec<InputDiff>)>,
+) -> Result<PathRanges, PathError> {
+    let _span = tracing::trace_span!(
+        parent: parent,
+        "path_ranges",
+        path = %path.display(),
+        commits = commits.len()
+    )
+    .entered();
+    let mut ranges = PathRanges::default();
+    for (commit_id, diffs) in commits {
+        ranges
+            .add(stack_id, commit_id, diffs.clone())
+            .map_err(|err| PathError::new(stack_id, path, commit_id, &err))?;
+    }
+    Ok(ranges)
+}
+
+impl PathError {
+    fn new(stack_id: StackId, path: &Path, commit_id: git2::Oid, err: &anyhow::Error) -> Self {
+        PathError {
+            stack_id,
+            path: path.to_owned(),
+            commit_id,
+            message: format!("{err:#}"),
+        }
+    }
 }
 
 /// A struct for collecting hunk ranges by path, before they get merged into a single dimension
 /// representing the workspace view.
 impl StackRanges {
+    /// Collect the ranges of all commits of `stack`, failing if those of any path can't be
+    /// computed.
+    ///
+    /// With at least [`PARALLEL_THRESHOLD`] paths, the ranges of each path are computed in parallel,
+    /// unless a commit renames a file, which ties the ranges of its paths together.
+    pub fn create(stack: &InputStack) -> anyhow::Result<StackRanges> {
+        let ranges = Self::create_lenient(stack);
+        if let Some(error) = ranges.errors.first() {
+            bail!(
+                "Failed to add the diffs of commit {} to {}: {}",
+                error.commit_id,
+                error.path.display(),
+                error.message
+            );
+        }
+        Ok(ranges)
+    }
+
+    /// Like [`create()`](Self::create), but skip each path whose ranges can't be computed and
+    /// record why in [`errors`](Self::errors), so a single malformed diff doesn't prevent all
+    /// other paths from having ranges.
+    #[instrument(
+        level = tracing::Level::DEBUG,
+        skip(stack),
+        fields(
+            stack_id = %stack.stack_id,
+            commits = stack.commits.len(),
+            paths = tracing::field::Empty
+        )
+    )]
+    pub fn create_lenient(stack: &InputStack) -> StackRanges {
+        let has_renames = stack
+            .commits
+            .iter()
+            .flat_map(|commit| &commit.files)
+            .any(|file| file.old_path.is_some());
+        let mut diffs_by_path: HashMap<&PathBuf, Vec<(git2::Oid, &Vec<InputDiff>)>> =
+            HashMap::new();
+        if !has_renames {
+            for commit in &stack.commits {
+                for file in &commit.files {
+                    diffs_by_path
+                        .entry(&file.path)
+                        .or_default()
+                        .push((commit.commit_id, &file.diffs));
+                }
+            }
+        }
+        if diffs_by_path.len() >= PARALLEL_THRESHOLD {
+            let parent = Span::current();
+            let results: Vec<_> = diffs_by_path
+                .into_par_iter()
+                .map(|(path, commits)| {
+                    path_ranges(&parent, stack.stack_id, path, commits)
+                        .map(|ranges| (path.clone(), ranges))
+                })
+                .collect();
+            let mut ranges = StackRanges::default();
+            for result in results {
+                match result {
+                    Ok((path, path_deps)) => {
+                        ranges.paths.insert(path, path_deps);
+                    }
+                    Err(error) => ranges.errors.push(error),
+                }
+            }
+            ranges.errors.sort_by(|a, b| a.path.cmp(&b.path));
+            Span::current().record("paths", ranges.paths.len());
+            return ranges;
+        }
+
+        let mut ranges = StackRanges::default();
+        let mut skipped: HashSet<&Path> = HashSet::new();
+        for commit in &stack.commits {
+            let _span = tracing::trace_span!(
+                "commit_ranges",
+                commit_id = %commit.commit_id,
+                files = commit.files.len()
+            )
+            .entered();
+            for file in &commit.files {
+                if let Some(old_path) = &file.old_path {
+                    // Whether the path is skipped continues at its new name, like its ranges.
+                    if skipped.contains(old_path.as_path()) {
+                        skipped.insert(&file.path);
+                    } else {
+                        skipped.remove(file.path.as_path());
+                    }
+                    ranges.rename(old_path, &file.path);
+                }
+                if skipped.contains(file.path.as_path()) {
+                    continue;
+                }
+                if let Err(err) = ranges.add(
+                    stack.stack_id,
+                    commit.commit_id,
+                    &file.path,
+                    file.diffs.clone(),
+                ) {
+                    ranges.paths.remove(&file.path);
+                    ranges.errors.push(PathError::new(
+                        stack.stack_id,
+                        &file.path,
+                        commit.commit_id,
+                        &err,
+                    ));
+                    skipped.insert(&file.path);
+                }
+            }
+        }
+        Span::current().record("paths", ranges.paths.len());
+        ranges
+    }
+
+    /// Collect the ranges of `paths` anew from the commits of `stack`, keeping those of all other
+    /// paths. This assumes that none of the commits renames a file.
+    pub fn update_paths(
+        &mut self,
+        stack: &InputStack,
+        paths: &HashSet<PathBuf>,
+    ) -> anyhow::Result<()> {
+        self.paths.retain(|path, _| !paths.contains(path));
+        for commit in &stack.commits {
+            for file in commit
+                .files
+                .iter()
+                .filter(|file| paths.contains(&file.path))
+            {
+                self.add(
+                    stack.stack_id,
+                    commit.commit_id,
+                    &file.path,
+                    file.diffs.clone(),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn add(
         &mut self,
         stack_id: StackId,
@@ -33,6 +232,22 @@ impl StackRanges {
         Ok(())
     }
 
+    /// Let the ranges of the file at `from` continue at `to`, as a commit renamed it, replacing
+    /// any ranges of a previous file at `to`.
+    pub fn rename(&mut self, from: &Path, to: &Path) {
+        if from == to {
+            return;
+        }
+        match self.paths.remove(from) {
+            Some(path_deps) => {
+                self.paths.insert(to.to_owned(), path_deps);
+            }
+            None => {
+                self.paths.remove(to);
+            }
+        }
+    }
+
     pub fn unique_paths(&self) -> HashSet<PathBuf> {
         self.paths
             .keys()
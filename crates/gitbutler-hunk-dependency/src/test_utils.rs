@@ -0,0 +1,20 @@
+//! Helpers shared by the tests of this crate.
+use std::path::Path;
+
+use crate::{InputCommit, InputDiff, InputFile};
+
+/// A commit with id `commit_id` which changes the file at `path` with the unified `diff`.
+pub(crate) fn input_commit(
+    commit_id: git2::Oid,
+    path: &Path,
+    diff: &str,
+) -> anyhow::Result<InputCommit> {
+    Ok(InputCommit {
+        commit_id,
+        files: vec![InputFile {
+            path: path.to_owned(),
+            old_path: None,
+            diffs: vec![InputDiff::try_from(diff)?],
+        }],
+    })
+}
@@ -1,15 +1,40 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
 };
 
+use anyhow::Context;
+use gitbutler_stack::StackId;
 use itertools::Itertools;
+use rayon::prelude::*;
+use serde::Serialize;
+use tracing::{instrument, Span};
 
-use crate::{HunkRange, InputCommit, InputStack, StackRanges};
+use crate::{
+    stack::PARALLEL_THRESHOLD, HunkRange, InputCommit, InputStack, PathError, RangeKind,
+    StackRanges,
+};
+
+/// The commits related to a commit through their dependencies, as found by
+/// [`WorkspaceRanges::transitive_dependencies()`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransitiveDependencies {
+    /// The commits that depend on the commit, and would be affected by changing it.
+    #[serde(with = "gitbutler_serde::oid_vec")]
+    pub dependents: Vec<git2::Oid>,
+    /// The commits the commit depends on, and can't be moved before.
+    #[serde(with = "gitbutler_serde::oid_vec")]
+    pub dependencies: Vec<git2::Oid>,
+}
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct WorkspaceRanges {
     paths: HashMap<PathBuf, Vec<HunkRange>>,
+    /// The stacks the ranges were created from, to update them as single commits change.
+    input_stacks: Vec<InputStack>,
+    /// The ranges of each of the `input_stacks`, in the same order.
+    stacks: Vec<StackRanges>,
 }
 
 /// Provides blame-like functionality for looking up what commit(s) have touched a specific line
@@ -21,32 +46,141 @@ pub struct WorkspaceRanges {
 /// It then combines the changes per branch into a single vector with line numbers that should
 /// match the workspace commit. These per branch changes are assumed and required to be
 /// independent without overlap.
+///
+/// Once created, commits can be [amended](Self::amend_commit) or [removed](Self::remove_commit),
+/// which only recomputes the paths the commit touches, instead of everything.
 impl WorkspaceRanges {
     pub fn create(input_stacks: Vec<InputStack>) -> anyhow::Result<WorkspaceRanges> {
-        let mut stacks = vec![];
-        for input_stack in input_stacks {
-            let mut stack = StackRanges::default();
-            let InputStack { stack_id, commits } = input_stack;
-            for commit in commits {
-                let InputCommit { commit_id, files } = commit;
-                for file in files {
-                    stack.add(stack_id, commit_id, &file.path, file.diffs)?;
-                }
-            }
-            stacks.push(stack);
-        }
+        let stacks = input_stacks
+            .iter()
+            .map(StackRanges::create)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self::from_ranges(input_stacks, stacks))
+    }
+
+    /// Like [`create()`](Self::create), but skip the paths whose ranges can't be computed, which
+    /// are then listed by [`errors()`](Self::errors), instead of failing for all paths.
+    pub fn create_lenient(input_stacks: Vec<InputStack>) -> WorkspaceRanges {
+        let stacks = input_stacks
+            .iter()
+            .map(StackRanges::create_lenient)
+            .collect();
+        Self::from_ranges(input_stacks, stacks)
+    }
+
+    /// Combine the ranges of stacks which were [created](StackRanges::create) from the stacks
+    /// they are paired with before, e.g. to reuse ranges that were persisted.
+    pub fn from_stacks(stacks: Vec<(InputStack, StackRanges)>) -> WorkspaceRanges {
+        let (input_stacks, stacks) = stacks.into_iter().unzip();
+        Self::from_ranges(input_stacks, stacks)
+    }
+
+    #[instrument(
+        level = tracing::Level::DEBUG,
+        skip_all,
+        fields(stacks = stacks.len(), paths = tracing::field::Empty)
+    )]
+    fn from_ranges(input_stacks: Vec<InputStack>, stacks: Vec<StackRanges>) -> WorkspaceRanges {
         let paths = stacks
             .iter()
             .flat_map(StackRanges::unique_paths)
             .unique()
             .collect_vec();
+        Span::current().record("paths", paths.len());
+
+        let combine = |path: &PathBuf| (path.clone(), combine_path_ranges(path, &stacks));
+        let paths = if paths.len() >= PARALLEL_THRESHOLD {
+            paths.par_iter().map(combine).collect()
+        } else {
+            paths.iter().map(combine).collect()
+        };
+
+        WorkspaceRanges {
+            paths,
+            input_stacks,
+            stacks,
+        }
+    }
 
-        Ok(WorkspaceRanges {
-            paths: paths
+    /// Replace the commit with `commit_id` in the stack with `stack_id` with `commit`, as it was
+    /// amended. The commits on top of it are expected to be unchanged, or to be amended as well.
+    pub fn amend_commit(
+        &mut self,
+        stack_id: StackId,
+        commit_id: git2::Oid,
+        commit: InputCommit,
+    ) -> anyhow::Result<()> {
+        let (stack_index, commit_index) = self.find_commit(stack_id, commit_id)?;
+        let commits = &mut self.input_stacks[stack_index].commits;
+        let amended = std::mem::replace(&mut commits[commit_index], commit);
+        let paths = touched_paths(&amended)
+            .chain(touched_paths(&commits[commit_index]))
+            .collect();
+        self.update_paths(stack_index, paths)
+    }
+
+    /// Remove the commit with `commit_id` from the stack with `stack_id`, as it was dropped or
+    /// moved out of the workspace.
+    pub fn remove_commit(&mut self, stack_id: StackId, commit_id: git2::Oid) -> anyhow::Result<()> {
+        let (stack_index, commit_index) = self.find_commit(stack_id, commit_id)?;
+        let removed = self.input_stacks[stack_index].commits.remove(commit_index);
+        let paths = touched_paths(&removed).collect();
+        self.update_paths(stack_index, paths)
+    }
+
+    fn find_commit(
+        &self,
+        stack_id: StackId,
+        commit_id: git2::Oid,
+    ) -> anyhow::Result<(usize, usize)> {
+        let stack_index = self
+            .input_stacks
+            .iter()
+            .position(|stack| stack.stack_id == stack_id)
+            .with_context(|| format!("Stack {stack_id} is not in the workspace"))?;
+        let commit_index = self.input_stacks[stack_index]
+            .commits
+            .iter()
+            .position(|commit| commit.commit_id == commit_id)
+            .with_context(|| format!("Commit {commit_id} is not in stack {stack_id}"))?;
+        Ok((stack_index, commit_index))
+    }
+
+    /// Recompute the ranges of `paths` in the stack at `stack_index`, and combine them with the
+    /// other stacks again.
+    fn update_paths(
+        &mut self,
+        stack_index: usize,
+        mut paths: HashSet<PathBuf>,
+    ) -> anyhow::Result<()> {
+        let input_stack = &self.input_stacks[stack_index];
+        let has_renames = input_stack
+            .commits
+            .iter()
+            .flat_map(|commit| &commit.files)
+            .any(|file| file.old_path.is_some());
+        if has_renames {
+            // Renames move ranges from one path to another, so any path of the stack may be affected.
+            paths.extend(self.stacks[stack_index].unique_paths());
+            self.stacks[stack_index] = StackRanges::create(input_stack)?;
+            paths.extend(self.stacks[stack_index].unique_paths());
+        } else {
+            self.stacks[stack_index].update_paths(input_stack, &paths)?;
+        }
+
+        for path in paths {
+            if self
+                .stacks
                 .iter()
-                .map(|path| (path.clone(), combine_path_ranges(path, &stacks)))
-                .collect(),
-        })
+                .any(|stack| stack.paths.contains_key(&path))
+            {
+                let ranges = combine_path_ranges(&path, &self.stacks);
+                self.paths.insert(path, ranges);
+            } else {
+                self.paths.remove(&path);
+            }
+        }
+        Ok(())
     }
 
     /// Finds commits that intersect with a given path and range combination.
@@ -62,6 +196,124 @@ impl WorkspaceRanges {
         }
         None
     }
+
+    /// Returns the commits of all stacks along with the ID of their stack, in application order.
+    pub fn commits(&self) -> impl Iterator<Item = (StackId, git2::Oid)> + '_ {
+        self.input_stacks.iter().flat_map(|stack| {
+            stack
+                .commits
+                .iter()
+                .map(|commit| (stack.stack_id, commit.commit_id))
+        })
+    }
+
+    /// Returns each commit that changed lines an earlier commit of its stack changed, along with
+    /// the earlier commit and the path of the lines, as `(path, commit_id, depends_on)`.
+    pub fn commit_dependencies(&self) -> impl Iterator<Item = (&Path, git2::Oid, git2::Oid)> {
+        self.stacks
+            .iter()
+            .flat_map(|stack| &stack.paths)
+            .flat_map(|(path, ranges)| {
+                ranges
+                    .commit_dependencies()
+                    .iter()
+                    .flat_map(move |(commit_id, depends_on)| {
+                        depends_on
+                            .iter()
+                            .map(move |depends_on| (path.as_path(), *commit_id, *depends_on))
+                    })
+            })
+    }
+
+    /// Returns the commits that depend on the commit with `commit_id`, and those it depends on,
+    /// directly or through other commits, each in application order.
+    pub fn transitive_dependencies(&self, commit_id: git2::Oid) -> TransitiveDependencies {
+        let mut dependencies: HashMap<git2::Oid, HashSet<git2::Oid>> = HashMap::new();
+        let mut dependents: HashMap<git2::Oid, HashSet<git2::Oid>> = HashMap::new();
+        for (_, commit_id, depends_on) in self.commit_dependencies() {
+            dependencies
+                .entry(commit_id)
+                .or_default()
+                .insert(depends_on);
+            dependents.entry(depends_on).or_default().insert(commit_id);
+        }
+
+        let reachable = |edges: &HashMap<git2::Oid, HashSet<git2::Oid>>| -> Vec<git2::Oid> {
+            let mut seen = HashSet::new();
+            let mut queue = vec![commit_id];
+            while let Some(id) = queue.pop() {
+                for next in edges.get(&id).into_iter().flatten() {
+                    if seen.insert(*next) {
+                        queue.push(*next);
+                    }
+                }
+            }
+            self.commits()
+                .map(|(_, id)| id)
+                .filter(|id| seen.contains(id))
+                .collect()
+        };
+        TransitiveDependencies {
+            dependents: reachable(&dependents),
+            dependencies: reachable(&dependencies),
+        }
+    }
+
+    /// Returns the paths that are changed by any commit in the workspace.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.paths.keys().map(PathBuf::as_path)
+    }
+
+    /// Returns whether any commit in the workspace bumps a submodule at `path`, even if the bump
+    /// was replaced by a later one and has no range anymore.
+    pub(crate) fn is_submodule(&self, path: &Path) -> bool {
+        self.input_stacks
+            .iter()
+            .flat_map(|stack| &stack.commits)
+            .flat_map(|commit| &commit.files)
+            .filter(|file| file.path == path)
+            .flat_map(|file| &file.diffs)
+            .any(|diff| diff.kind == RangeKind::Submodule)
+    }
+
+    /// Returns why paths were skipped as their ranges couldn't be computed, which only happens if
+    /// the ranges were created [leniently](Self::create_lenient).
+    pub fn errors(&self) -> impl Iterator<Item = &PathError> {
+        self.stacks.iter().flat_map(|stack| &stack.errors)
+    }
+
+    /// Returns the ranges of each path that are still attributed to the commit with `commit_id`,
+    /// in workspace line numbers.
+    pub fn commit_ranges(&self, commit_id: git2::Oid) -> Vec<(&Path, &HunkRange)> {
+        self.ranges_matching(|hunk| hunk.commit_id == commit_id)
+    }
+
+    /// Returns the ranges of each path that are attributed to any commit of the stack with `stack_id`,
+    /// in workspace line numbers.
+    pub fn stack_ranges(&self, stack_id: StackId) -> Vec<(&Path, &HunkRange)> {
+        self.ranges_matching(|hunk| hunk.stack_id == stack_id)
+    }
+
+    fn ranges_matching(&self, predicate: impl Fn(&HunkRange) -> bool) -> Vec<(&Path, &HunkRange)> {
+        self.paths
+            .iter()
+            .flat_map(|(path, hunks)| {
+                hunks
+                    .iter()
+                    .filter(|hunk| predicate(hunk))
+                    .map(move |hunk| (path.as_path(), hunk))
+            })
+            .sorted_by_key(|(path, hunk)| (*path, hunk.start))
+            .collect()
+    }
+}
+
+/// The paths of files changed by `commit`, including the paths renamed files had before.
+fn touched_paths(commit: &InputCommit) -> impl Iterator<Item = PathBuf> + '_ {
+    commit
+        .files
+        .iter()
+        .flat_map(|file| std::iter::once(file.path.clone()).chain(file.old_path.clone()))
 }
 
 /// Combines ranges from muiltiple branches/stacks into a single vector
@@ -135,9 +387,10 @@ fn combine_path_ranges(path: &Path, stacks: &[StackRanges]) -> Vec<HunkRange> {
 mod tests {
     use std::str::FromStr;
 
-    use gitbutler_stack::StackId;
-
-    use crate::input::{InputDiff, InputFile};
+    use crate::{
+        input::{InputDiff, InputFile},
+        test_utils::input_commit,
+    };
 
     use super::*;
 
@@ -158,6 +411,7 @@ mod tests {
                     commit_id: commit1_id,
                     files: vec![InputFile {
                         path: path.to_owned(),
+                        old_path: None,
                         diffs: vec![InputDiff::try_from(
                             "@@ -1,6 +1,7 @@
 1
@@ -178,6 +432,7 @@ mod tests {
                     commit_id: commit2_id,
                     files: vec![InputFile {
                         path: path.to_owned(),
+                        old_path: None,
                         diffs: vec![
                             InputDiff::try_from(
                                 "@@ -1,5 +1,3 @@
@@ -215,6 +470,274 @@ mod tests {
         assert_eq!(dependencies_2[0].commit_id, commit2_id);
         assert_eq!(dependencies_2[0].stack_id, stack2_id);
 
+        assert_eq!(workspace_ranges.paths().collect_vec(), [path.as_path()]);
+        let starts = |ranges: Vec<(&Path, &HunkRange)>| {
+            ranges
+                .into_iter()
+                .map(|(_, hunk)| (hunk.start, hunk.lines))
+                .collect_vec()
+        };
+        assert_eq!(starts(workspace_ranges.commit_ranges(commit1_id)), [(2, 1)]);
+        assert_eq!(
+            starts(workspace_ranges.stack_ranges(stack2_id)),
+            starts(workspace_ranges.commit_ranges(commit2_id)),
+            "the stack has a single commit"
+        );
+        assert_eq!(workspace_ranges.stack_ranges(stack2_id).len(), 2);
+        assert!(workspace_ranges
+            .commit_ranges(git2::Oid::from_str("c")?)
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn workspace_follows_renames() -> anyhow::Result<()> {
+        let old_path = PathBuf::from_str("/old.txt")?;
+        let path = PathBuf::from_str("/new.txt")?;
+
+        let commit1_id = git2::Oid::from_str("a")?;
+        let commit2_id = git2::Oid::from_str("b")?;
+        let stack_id = StackId::generate();
+
+        let workspace_ranges = WorkspaceRanges::create(vec![InputStack {
+            stack_id,
+            commits: vec![
+                InputCommit {
+                    commit_id: commit1_id,
+                    files: vec![InputFile {
+                        path: old_path.to_owned(),
+                        old_path: None,
+                        diffs: vec![InputDiff::try_from(
+                            "@@ -1,3 +1,4 @@
+1
++2
+3
+4
+",
+                        )?],
+                    }],
+                },
+                InputCommit {
+                    commit_id: commit2_id,
+                    files: vec![InputFile {
+                        path: path.to_owned(),
+                        old_path: Some(old_path.to_owned()),
+                        diffs: vec![InputDiff::try_from(
+                            "@@ -1,2 +1,3 @@
++0
+1
+2
+",
+                        )?],
+                    }],
+                },
+            ],
+        }])?;
+
+        assert_eq!(workspace_ranges.paths().collect_vec(), [path.as_path()]);
+        let dependencies = workspace_ranges.intersection(&path, 3, 1).unwrap();
+        assert_eq!(dependencies.len(), 1);
+        assert_eq!(
+            dependencies[0].commit_id, commit1_id,
+            "the line added before the rename is shifted by the line added with it"
+        );
+        let dependencies = workspace_ranges.intersection(&path, 1, 1).unwrap();
+        assert_eq!(dependencies[0].commit_id, commit2_id);
+        assert!(workspace_ranges.intersection(&old_path, 2, 1).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn workspace_amend_and_remove_commits() -> anyhow::Result<()> {
+        let path = PathBuf::from_str("/test.txt")?;
+        let other_path = PathBuf::from_str("/other.txt")?;
+        let stack_id = StackId::generate();
+        let commit1_id = git2::Oid::from_str("a")?;
+        let commit2_id = git2::Oid::from_str("b")?;
+        let amended_id = git2::Oid::from_str("c")?;
+
+        let insert_second_line = "@@ -1,2 +1,3 @@\n1\n+2\n3\n";
+        let insert_first_line = "@@ -1,1 +1,2 @@\n+0\n1\n";
+        let ranges = |workspace_ranges: &WorkspaceRanges| {
+            workspace_ranges
+                .stack_ranges(stack_id)
+                .into_iter()
+                .map(|(path, hunk)| (path.to_owned(), hunk.commit_id, hunk.start, hunk.lines))
+                .collect_vec()
+        };
+
+        let mut workspace_ranges = WorkspaceRanges::create(vec![InputStack {
+            stack_id,
+            commits: vec![
+                input_commit(commit1_id, &path, insert_second_line)?,
+                input_commit(commit2_id, &path, insert_first_line)?,
+            ],
+        }])?;
+        assert_eq!(
+            ranges(&workspace_ranges),
+            [
+                (path.clone(), commit2_id, 1, 1),
+                (path.clone(), commit1_id, 3, 1)
+            ]
+        );
+
+        workspace_ranges.amend_commit(
+            stack_id,
+            commit1_id,
+            input_commit(amended_id, &other_path, insert_second_line)?,
+        )?;
+        let expected = WorkspaceRanges::create(vec![InputStack {
+            stack_id,
+            commits: vec![
+                input_commit(amended_id, &other_path, insert_second_line)?,
+                input_commit(commit2_id, &path, insert_first_line)?,
+            ],
+        }])?;
+        assert_eq!(ranges(&workspace_ranges), ranges(&expected));
+        assert_eq!(
+            workspace_ranges.paths().sorted().collect_vec(),
+            [other_path.as_path(), path.as_path()]
+        );
+
+        workspace_ranges.remove_commit(stack_id, commit2_id)?;
+        assert_eq!(
+            ranges(&workspace_ranges),
+            [(other_path.clone(), amended_id, 2, 1)]
+        );
+        assert_eq!(
+            workspace_ranges.paths().collect_vec(),
+            [other_path.as_path()]
+        );
+        assert!(workspace_ranges
+            .remove_commit(stack_id, commit2_id)
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn workspace_transitive_dependencies() -> anyhow::Result<()> {
+        let path = PathBuf::from_str("/test.txt")?;
+        let stack_id = StackId::generate();
+        let [commit1_id, commit2_id, commit3_id, commit4_id] =
+            ["a", "b", "c", "d"].map(|id| git2::Oid::from_str(id).unwrap());
+
+        let workspace_ranges = WorkspaceRanges::create(vec![InputStack {
+            stack_id,
+            commits: vec![
+                input_commit(commit1_id, &path, "@@ -1,2 +1,3 @@\n1\n+2\n3\n")?,
+                input_commit(commit2_id, &path, "@@ -1,3 +1,3 @@\n1\n-2\n+2b\n3\n")?,
+                input_commit(commit3_id, &path, "@@ -1,3 +1,3 @@\n1\n-2b\n+2c\n3\n")?,
+                input_commit(commit4_id, &path, "@@ -9,2 +9,3 @@\n9\n+10\n11\n")?,
+            ],
+        }])?;
+
+        assert_eq!(
+            workspace_ranges.transitive_dependencies(commit2_id),
+            TransitiveDependencies {
+                dependents: vec![commit3_id],
+                dependencies: vec![commit1_id],
+            }
+        );
+        assert_eq!(
+            workspace_ranges
+                .transitive_dependencies(commit1_id)
+                .dependents,
+            [commit2_id, commit3_id],
+            "dependents of dependents are included"
+        );
+        assert_eq!(
+            workspace_ranges.transitive_dependencies(commit4_id),
+            TransitiveDependencies {
+                dependents: vec![],
+                dependencies: vec![],
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn workspace_from_persisted_stacks() -> anyhow::Result<()> {
+        let path = PathBuf::from_str("/test.txt")?;
+        let stack_id = StackId::generate();
+        let commit1_id = git2::Oid::from_str("a")?;
+        let commit2_id = git2::Oid::from_str("b")?;
+
+        let input = InputStack {
+            stack_id,
+            commits: vec![
+                input_commit(commit1_id, &path, "@@ -1,2 +1,3 @@\n1\n+2\n3\n")?,
+                input_commit(commit2_id, &path, "@@ -1,3 +1,3 @@\n1\n-2\n+2b\n3\n")?,
+            ],
+        };
+        let ranges = StackRanges::create(&input)?;
+
+        let input: InputStack = serde_json::from_str(&serde_json::to_string(&input)?)?;
+        let ranges: StackRanges = serde_json::from_str(&serde_json::to_string(&ranges)?)?;
+        let workspace_ranges = WorkspaceRanges::from_stacks(vec![(input, ranges)]);
+
+        let expected = WorkspaceRanges::create(vec![workspace_ranges.input_stacks[0].clone()])?;
+        let starts = |ranges: &WorkspaceRanges| {
+            ranges
+                .stack_ranges(stack_id)
+                .into_iter()
+                .map(|(_, hunk)| (hunk.commit_id, hunk.start, hunk.lines))
+                .collect_vec()
+        };
+        assert_eq!(starts(&workspace_ranges), starts(&expected));
+        assert_eq!(
+            workspace_ranges.transitive_dependencies(commit2_id),
+            expected.transitive_dependencies(commit2_id),
+            "dependencies survive the round trip"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lenient_ranges_skip_paths_that_fail() -> anyhow::Result<()> {
+        let [path1, path2] = ["/a.txt", "/b.txt"].map(|path| PathBuf::from_str(path).unwrap());
+        let stack_id = StackId::generate();
+        let commit_id = git2::Oid::from_str("a")?;
+
+        let file = |path: &PathBuf, diff: &str| -> anyhow::Result<InputFile> {
+            Ok(InputFile {
+                path: path.to_owned(),
+                old_path: None,
+                diffs: vec![InputDiff::try_from(diff)?],
+            })
+        };
+        // The same path appearing twice in a commit can't be added, as the commit already has
+        // ranges for it then.
+        let input = vec![InputStack {
+            stack_id,
+            commits: vec![InputCommit {
+                commit_id,
+                files: vec![
+                    file(&path1, "@@ -1,2 +1,3 @@\n1\n+2\n3\n")?,
+                    file(&path1, "@@ -5,2 +6,3 @@\n5\n+6\n7\n")?,
+                    file(&path2, "@@ -1,2 +1,3 @@\n1\n+2\n3\n")?,
+                ],
+            }],
+        }];
+        assert!(WorkspaceRanges::create(input.clone()).is_err());
+
+        let ranges = WorkspaceRanges::create_lenient(input);
+        let errors = ranges.errors().collect_vec();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].stack_id, stack_id);
+        assert_eq!(errors[0].path, path1);
+        assert_eq!(errors[0].commit_id, commit_id);
+        assert!(
+            ranges.intersection(&path1, 1, 10).is_none(),
+            "the failing path is skipped"
+        );
+        assert_eq!(
+            ranges.intersection(&path2, 2, 1).map(|hunks| hunks.len()),
+            Some(1),
+            "other paths still have ranges"
+        );
         Ok(())
     }
 }
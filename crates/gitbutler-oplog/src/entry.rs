@@ -162,6 +162,12 @@ pub enum OperationKind {
     UpdateDependentBranchName,
     UpdateDependentBranchDescription,
     UpdateDependentBranchForgeId,
+    AutoStash,
+    SplitCommit,
+    RevertCommit,
+    ShelveChanges,
+    UnshelveChanges,
+    ApplyPatch,
     #[default]
     Unknown,
 }
@@ -1,6 +1,6 @@
 pub mod entry;
 mod oplog;
-pub use oplog::OplogExt;
+pub use oplog::{OplogExt, SnapshotStorageStats};
 mod reflog;
 mod snapshot;
 pub use snapshot::SnapshotExt;
@@ -17,6 +17,7 @@ use gitbutler_project::{
 use gitbutler_repo::RepositoryExt;
 use gitbutler_repo::SignaturePurpose;
 use gitbutler_stack::{Stack, VirtualBranchesHandle, VirtualBranchesState};
+use serde::Serialize;
 use tracing::instrument;
 
 use super::{
@@ -26,6 +27,25 @@ use super::{
 };
 
 const SNAPSHOT_FILE_LIMIT_BYTES: u64 = 32 * 1024 * 1024;
+/// If enabled with [`Project::pack_snapshots`], loose objects are packed after this many snapshots.
+const SNAPSHOTS_PER_PACKING: usize = 100;
+
+/// How much space the snapshots of the oplog take up in the object database.
+///
+/// Snapshots are stored as Git objects, so each distinct blob or tree is only stored once,
+/// no matter how many snapshots refer to it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotStorageStats {
+    /// The amount of snapshots in the oplog.
+    pub snapshots: usize,
+    /// The amount of distinct objects the snapshots consist of.
+    pub unique_objects: usize,
+    /// The uncompressed size of all distinct objects, i.e. what is actually stored.
+    pub unique_bytes: u64,
+    /// The uncompressed size all snapshots would have if each stored a full copy of its objects.
+    pub logical_bytes: u64,
+}
 
 /// The Oplog allows for crating snapshots of the current state of the project as well as restoring to a previous snapshot.
 /// Snapshots include the state of the working directory as well as all additional GitButler state (e.g. virtual branches, conflict state).
@@ -133,6 +153,10 @@ pub trait OplogExt {
 
     /// Gets the sha of the last snapshot commit if present.
     fn oplog_head(&self) -> Result<Option<git2::Oid>>;
+
+    /// Computes how much space all snapshots take up, and how much is saved by sharing objects between them.
+    /// Note that this traverses all snapshot trees, so it should not be called on a hot path.
+    fn snapshot_storage_stats(&self) -> Result<SnapshotStorageStats>;
 }
 
 impl OplogExt for Project {
@@ -310,6 +334,101 @@ impl OplogExt for Project {
         let oplog_state = OplogHandle::new(&self.gb_dir());
         oplog_state.oplog_head()
     }
+
+    #[instrument(skip(self), err(Debug))]
+    fn snapshot_storage_stats(&self) -> Result<SnapshotStorageStats> {
+        let mut stats = SnapshotStorageStats::default();
+        let Some(oplog_head) = OplogHandle::new(&self.gb_dir()).oplog_head()? else {
+            return Ok(stats);
+        };
+        let repo = git2::Repository::open(self.path.as_path())?;
+        let odb = repo.odb()?;
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(oplog_head)?;
+        let mut sizes_by_id = HashMap::new();
+        for commit_id in revwalk {
+            let commit = repo.find_commit(commit_id?)?;
+            let (commit_size, _) = odb.read_header(commit.id())?;
+            stats.snapshots += 1;
+            stats.unique_objects += 1;
+            stats.unique_bytes += commit_size as u64;
+            stats.logical_bytes += commit_size as u64
+                + accumulated_object_size(
+                    &repo,
+                    &odb,
+                    commit.tree_id(),
+                    &mut sizes_by_id,
+                    &mut stats,
+                )?;
+        }
+        Ok(stats)
+    }
+}
+
+/// Returns the size of the object `id` and all objects it refers to, and accounts for each object
+/// in `stats` the first time it is seen. `sizes_by_id` remembers the result for each seen object.
+fn accumulated_object_size(
+    repo: &git2::Repository,
+    odb: &git2::Odb,
+    id: git2::Oid,
+    sizes_by_id: &mut HashMap<git2::Oid, u64>,
+    stats: &mut SnapshotStorageStats,
+) -> Result<u64> {
+    if let Some(size) = sizes_by_id.get(&id) {
+        return Ok(*size);
+    }
+    let (size, kind) = odb.read_header(id)?;
+    stats.unique_objects += 1;
+    stats.unique_bytes += size as u64;
+
+    let mut accumulated = size as u64;
+    if kind == git2::ObjectType::Tree {
+        let children: Vec<_> = repo
+            .find_tree(id)?
+            .iter()
+            // Submodule commits aren't part of this repository.
+            .filter(|entry| entry.kind() != Some(git2::ObjectType::Commit))
+            .map(|entry| entry.id())
+            .collect();
+        for child in children {
+            accumulated += accumulated_object_size(repo, odb, child, sizes_by_id, stats)?;
+        }
+    }
+    sizes_by_id.insert(id, accumulated);
+    Ok(accumulated)
+}
+
+/// Let Git pack loose objects in the background, which it only does if there are enough of them.
+/// Snapshots create many small objects, which are much cheaper to keep in a pack.
+fn pack_loose_objects(worktree_dir: &std::path::Path) {
+    let result = std::process::Command::new(gix::path::env::exe_invocation())
+        .args(["gc", "--auto", "--quiet"])
+        .current_dir(worktree_dir)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn();
+    let mut child = match result {
+        Ok(child) => child,
+        Err(err) => {
+            tracing::warn!(?worktree_dir, "failed to pack snapshot objects: {err}");
+            return;
+        }
+    };
+    // Reap the process once it's done so it doesn't linger as a zombie, without making the
+    // snapshot wait for it.
+    let worktree_dir = worktree_dir.to_owned();
+    std::thread::spawn(move || match child.wait() {
+        Ok(status) if !status.success() => {
+            tracing::warn!(
+                ?worktree_dir,
+                "packing snapshot objects failed with {status}"
+            );
+        }
+        Ok(_) => {}
+        Err(err) => tracing::warn!(?worktree_dir, "failed to pack snapshot objects: {err}"),
+    });
 }
 
 /// Get a tree of the working dir (applied branches merged)
@@ -466,8 +585,8 @@ fn commit_snapshot(
         .as_ref()
         .map(|head| vec![head])
         .unwrap_or_default();
-    let snapshot_commit_id = repo.commit(
-        None,
+    // Snapshots are signed like the other commits of GitButler's own references.
+    let snapshot_commit_id = repo.commit_for_gitbutler_ref(
         &author,
         &committer,
         &details.to_string(),
@@ -481,6 +600,10 @@ fn commit_snapshot(
     let target_commit_id = vb_state.get_default_target()?.sha;
     set_reference_to_oplog(&ctx.path, target_commit_id, snapshot_commit_id)?;
 
+    if ctx.pack_snapshots && oplog_state.count_snapshot_for_packing(SNAPSHOTS_PER_PACKING)? {
+        pack_loose_objects(&ctx.path);
+    }
+
     Ok(snapshot_commit_id)
 }
 
@@ -148,6 +148,8 @@ fn serialize_line(line: gix::refs::file::log::LineRef<'_>) -> String {
 mod set_target_ref {
     use std::path::PathBuf;
 
+    use gitbutler_reference::Refname;
+    use gitbutler_repo::RepositoryExt;
     use gix::refs::file::log::LineRef;
     use pretty_assertions::assert_eq;
     use tempfile::tempdir;
@@ -368,13 +370,19 @@ mod set_target_ref {
         let name = "Your name";
         let email = "your.email@example.com";
         let signature = git2::Signature::now(name, email)?;
-        let commit_id = repo.commit(
-            Some("HEAD"),
+        let branch: Refname = repo
+            .find_reference("HEAD")?
+            .symbolic_target()
+            .expect("HEAD of a new repository points to its unborn branch")
+            .parse()?;
+        let commit_id = repo.commit_with_signature(
+            Some(&branch),
             &signature,
             &signature,
             "initial commit",
             &repo.find_tree(oid)?,
             &[],
+            None,
         )?;
         Ok((dir, commit_id))
     }
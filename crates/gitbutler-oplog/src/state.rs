@@ -34,6 +34,9 @@ pub struct Oplog {
         default = "unix_epoch"
     )]
     pub modified_at: SystemTime,
+    /// The amount of snapshots created since loose objects were last packed.
+    #[serde(default)]
+    pub snapshots_since_packing: usize,
 }
 
 impl Default for Oplog {
@@ -41,6 +44,7 @@ impl Default for Oplog {
         Self {
             head_sha: None,
             modified_at: SystemTime::UNIX_EPOCH,
+            snapshots_since_packing: 0,
         }
     }
 }
@@ -83,6 +87,21 @@ impl OplogHandle {
         Ok(oplog.modified_at)
     }
 
+    /// Counts a new snapshot, and returns `true` if `every` snapshots were created since objects
+    /// were last packed. The counter then starts over, assuming the caller packs them.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn count_snapshot_for_packing(&self, every: usize) -> Result<bool> {
+        let mut oplog = self.read_file()?;
+        oplog.snapshots_since_packing += 1;
+        let should_pack = oplog.snapshots_since_packing >= every;
+        if should_pack {
+            oplog.snapshots_since_packing = 0;
+        }
+        self.write_file(oplog)?;
+        Ok(should_pack)
+    }
+
     /// Reads and parses the state file.
     ///
     /// If the file does not exist, it will be created.
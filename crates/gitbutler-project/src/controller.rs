@@ -1,15 +1,25 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use anyhow::{anyhow, bail, Context, Result};
 use gitbutler_error::error;
 
 use super::{storage, storage::UpdateRequest, Project, ProjectId};
+use crate::ownership::{self, Ownership};
+use crate::repository_format;
 use crate::AuthKey;
 
 #[derive(Clone)]
 pub struct Controller {
     local_data_dir: PathBuf,
     projects_storage: storage::Storage,
+    /// The paths of the projects that were found to be trusted, so looking up who owns them only
+    /// happens once. Should the trust be lost while the app runs, opening the repository fails with
+    /// the same error.
+    trusted: Arc<parking_lot::Mutex<BTreeMap<ProjectId, PathBuf>>>,
 }
 
 impl Controller {
@@ -18,6 +28,7 @@ impl Controller {
         Self {
             projects_storage: storage::Storage::from_path(&path),
             local_data_dir: path,
+            trusted: Default::default(),
         }
     }
 
@@ -36,6 +47,8 @@ impl Controller {
         if !path.is_dir() {
             bail!("not a directory");
         }
+        assure_trusted(path)?;
+        assure_supported_format(path)?;
         match gix::open_opts(path, gix::open::Options::isolated()) {
             Ok(repo) if repo.is_bare() => {
                 bail!("bare repositories are unsupported");
@@ -80,10 +93,11 @@ impl Controller {
         self.projects_storage
             .add(&project)
             .context("failed to add project to storage")?;
+        self.trusted.lock().insert(project.id, project.path.clone());
 
-        // Create a .git/gitbutler directory for app data
-        if let Err(error) = std::fs::create_dir_all(project.gb_dir()) {
-            tracing::error!(project_id = %project.id, ?error, "failed to create {:?} on project add", project.gb_dir());
+        // Create a .git/gitbutler directory for app data, and keep our files out of the user's way
+        if let Err(error) = crate::hygiene::ensure(&project) {
+            tracing::error!(project_id = %project.id, ?error, "failed to set up {:?} on project add", project.path);
         }
 
         Ok(project)
@@ -151,6 +165,7 @@ impl Controller {
         let mut project = self.projects_storage.get(id)?;
         if validate {
             let worktree_dir = &project.path;
+            assure_supported_format(worktree_dir)?;
             if gix::open_opts(worktree_dir, gix::open::Options::isolated()).is_err() {
                 let suffix = if !worktree_dir.exists() {
                     " as it does not exist"
@@ -163,6 +178,7 @@ impl Controller {
                 )
                 .context(error::Code::ProjectMissing));
             }
+            self.assure_trusted_once(&project)?;
         }
 
         if !project.gb_dir().exists() {
@@ -196,6 +212,7 @@ impl Controller {
             return Ok(());
         };
 
+        self.trusted.lock().remove(&project.id);
         self.projects_storage
             .purge(project.id)
             .map_err(anyhow::Error::from)?;
@@ -220,4 +237,40 @@ impl Controller {
     pub fn project_metadata_dir(&self, id: ProjectId) -> PathBuf {
         self.local_data_dir.join("projects").join(id.to_string())
     }
+
+    /// Like [`assure_trusted()`], but only check each project once while its path stays the same.
+    fn assure_trusted_once(&self, project: &Project) -> Result<()> {
+        if self.trusted.lock().get(&project.id) == Some(&project.path) {
+            return Ok(());
+        }
+        assure_trusted(&project.path)?;
+        self.trusted.lock().insert(project.id, project.path.clone());
+        Ok(())
+    }
+}
+
+/// Fail with [`error::Code::RepositoryUntrusted`] if Git won't open the repository at `worktree_dir`
+/// as it's owned by someone else.
+fn assure_trusted(worktree_dir: &Path) -> Result<()> {
+    if let Ownership::Untrusted { path } = ownership::ownership(worktree_dir)? {
+        return Err(anyhow!(
+            "The repository at '{}' is owned by another user and isn't trusted",
+            path.display()
+        )
+        .context(error::Code::RepositoryUntrusted));
+    }
+    Ok(())
+}
+
+/// Fail with [`error::Code::RepositoryFormatUnsupported`] if the repository at `worktree_dir` is stored
+/// in a way that isn't supported, like with SHA-256 object ids.
+pub fn assure_supported_format(worktree_dir: &Path) -> Result<()> {
+    if let Some(format) = repository_format::unsupported_format(worktree_dir)? {
+        return Err(anyhow!(
+            "The repository at '{}' can't be used as its {format}",
+            worktree_dir.display()
+        )
+        .context(error::Code::RepositoryFormatUnsupported));
+    }
+    Ok(())
 }
@@ -0,0 +1,127 @@
+//! Flags that turn risky changes to the engine on or off, so they can be rolled out incrementally.
+//!
+//! Whether a flag is enabled is decided by the first of these which has a value for it:
+//!
+//! * The override stored with the project, set by the user.
+//! * The `use_experimental_locking` setting of the project, which predates the flags.
+//! * The remote configuration, which the app fetches and [sets](set_remote) once it has it.
+//! * The [default](FeatureFlag::default_enabled) of the flag.
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Project;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FeatureFlag {
+    /// Compute hunk locks from the hunk ranges of the commits in the workspace, instead of by
+    /// blaming each uncommitted hunk.
+    ExperimentalLocking,
+    /// Stash changes that aren't part of any stack before operations that rewrite the worktree, and
+    /// replay them afterwards.
+    AutoStash,
+    /// Detect renames in commits, so their hunk ranges follow renamed files.
+    RenameTracking,
+    /// Don't lock uncommitted hunks that change a single function to commits that only changed
+    /// other functions of the file.
+    ScopeLocking,
+    /// Track the words commits changed within a line, so uncommitted changes to other words of
+    /// the same line aren't locked to them.
+    ColumnLocking,
+}
+
+impl FeatureFlag {
+    pub const ALL: [FeatureFlag; 5] = [
+        FeatureFlag::ExperimentalLocking,
+        FeatureFlag::AutoStash,
+        FeatureFlag::RenameTracking,
+        FeatureFlag::ScopeLocking,
+        FeatureFlag::ColumnLocking,
+    ];
+
+    /// Whether the flag is enabled if neither the project nor the remote configuration say otherwise.
+    pub fn default_enabled(self) -> bool {
+        match self {
+            // This flag guards a change that already shipped, so it can be turned off again if it
+            // misbehaves, while the others are still being tried out.
+            FeatureFlag::ExperimentalLocking => true,
+            FeatureFlag::AutoStash
+            | FeatureFlag::RenameTracking
+            | FeatureFlag::ScopeLocking
+            | FeatureFlag::ColumnLocking => false,
+        }
+    }
+}
+
+/// Where the value of a flag comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FlagSource {
+    Default,
+    Remote,
+    Project,
+    /// A project setting from before the flags existed.
+    Legacy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlagState {
+    pub flag: FeatureFlag,
+    pub enabled: bool,
+    pub source: FlagSource,
+}
+
+static REMOTE: RwLock<BTreeMap<FeatureFlag, bool>> = RwLock::new(BTreeMap::new());
+
+/// Use `flags` as the remote configuration for all projects, replacing the previous one.
+pub fn set_remote(flags: BTreeMap<FeatureFlag, bool>) {
+    *REMOTE.write().unwrap_or_else(|err| err.into_inner()) = flags;
+}
+
+impl Project {
+    /// Return whether `flag` is enabled for this project.
+    pub fn feature_enabled(&self, flag: FeatureFlag) -> bool {
+        self.feature_flag(flag).enabled
+    }
+
+    /// Return whether `flag` is enabled for this project, and why.
+    pub fn feature_flag(&self, flag: FeatureFlag) -> FlagState {
+        let remote = REMOTE.read().unwrap_or_else(|err| err.into_inner());
+        self.feature_flag_with_remote(flag, &remote)
+    }
+
+    /// Like [`feature_flag()`](Self::feature_flag), but with `remote` as the remote configuration
+    /// instead of the one that was [set](set_remote).
+    pub fn feature_flag_with_remote(
+        &self,
+        flag: FeatureFlag,
+        remote: &BTreeMap<FeatureFlag, bool>,
+    ) -> FlagState {
+        let (enabled, source) = if let Some(enabled) = self.feature_flags.get(&flag) {
+            (*enabled, FlagSource::Project)
+        } else if flag == FeatureFlag::ExperimentalLocking && !self.use_experimental_locking {
+            // The setting keeps applying until it's overridden.
+            (false, FlagSource::Legacy)
+        } else if let Some(enabled) = remote.get(&flag) {
+            (*enabled, FlagSource::Remote)
+        } else {
+            (flag.default_enabled(), FlagSource::Default)
+        };
+        FlagState {
+            flag,
+            enabled,
+            source,
+        }
+    }
+
+    /// Return the state of all flags for this project.
+    pub fn feature_flag_states(&self) -> Vec<FlagState> {
+        FeatureFlag::ALL
+            .into_iter()
+            .map(|flag| self.feature_flag(flag))
+            .collect()
+    }
+}
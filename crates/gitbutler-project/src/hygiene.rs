@@ -0,0 +1,90 @@
+//! Keep GitButler's own artifacts out of the way of the user's Git workflows, and vice versa.
+//!
+//! Most state lives in `.git/gitbutler` and in references below `refs/gitbutler/`, which `git status`,
+//! `git clean` and `git reset` never touch, and GitButler doesn't write to the worktree. The files it
+//! reads from `.gitbutler/` are shared configuration which is meant to be committed, so nothing needs
+//! to be excluded. Finally, the workspace branch needs `refs/heads/gitbutler/` to be available, and its
+//! commit must be signed if the project is configured so, as servers may reject unsigned commits.
+use anyhow::{Context, Result};
+use gitbutler_config::api::ProjectCommands;
+use serde::Serialize;
+
+use crate::Project;
+
+const WORKSPACE_REF: &str = "refs/heads/gitbutler/workspace";
+
+/// A problem with the project which gets in the way of GitButler.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "subject", rename_all = "camelCase")]
+pub enum Issue {
+    /// The directory for GitButler state, `.git/gitbutler`, doesn't exist.
+    MissingDataDir,
+    /// A branch named `gitbutler` exists, and the workspace branch can't be created next to it.
+    #[serde(rename_all = "camelCase")]
+    WorkspaceRefBlocked { refname: String },
+    /// Signing is configured for the workspace commit, but it isn't signed.
+    #[serde(rename_all = "camelCase")]
+    UnsignedWorkspaceCommit { commit_id: String },
+}
+
+impl Issue {
+    /// Returns `true` if [`ensure()`] can fix the issue.
+    pub fn is_fixable(&self) -> bool {
+        !matches!(
+            self,
+            Issue::WorkspaceRefBlocked { .. } | Issue::UnsignedWorkspaceCommit { .. }
+        )
+    }
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Issue::MissingDataDir => f.write_str("the .git/gitbutler directory is missing"),
+            Issue::WorkspaceRefBlocked { refname } => write!(
+                f,
+                "'{refname}' prevents the creation of the workspace branch, please rename it"
+            ),
+            Issue::UnsignedWorkspaceCommit { commit_id } => write!(
+                f,
+                "the workspace commit {commit_id} isn't signed, please check the signing setup"
+            ),
+        }
+    }
+}
+
+/// Set up the repository of `project` so GitButler and the user's own Git operations don't interfere.
+/// This is idempotent, and called whenever a project is added.
+pub fn ensure(project: &Project) -> Result<()> {
+    std::fs::create_dir_all(project.gb_dir())
+        .with_context(|| format!("failed to create {:?}", project.gb_dir()))?;
+    Ok(())
+}
+
+/// Find everything which isn't set up as [`ensure()`] would do it, or which the user has to fix.
+pub fn verify(project: &Project) -> Result<Vec<Issue>> {
+    let mut issues = Vec::new();
+    if !project.gb_dir().is_dir() {
+        issues.push(Issue::MissingDataDir);
+    }
+
+    let repo = gix::open_opts(&project.path, gix::open::Options::isolated())?;
+    let blocking_ref = "refs/heads/gitbutler";
+    if repo.try_find_reference(blocking_ref)?.is_some() {
+        issues.push(Issue::WorkspaceRefBlocked {
+            refname: blocking_ref.to_owned(),
+        });
+    }
+
+    if project.gb_config()?.signs_workspace_commits() {
+        if let Some(mut workspace_ref) = repo.try_find_reference(WORKSPACE_REF)? {
+            let commit = workspace_ref.peel_to_commit()?;
+            if commit.decode()?.extra_headers().pgp_signature().is_none() {
+                issues.push(Issue::UnsignedWorkspaceCommit {
+                    commit_id: commit.id.to_string(),
+                });
+            }
+        }
+    }
+    Ok(issues)
+}
@@ -1,10 +1,14 @@
 pub mod access;
 mod controller;
 mod default_true;
+pub mod feature_flags;
+pub mod hygiene;
+pub mod ownership;
 mod project;
+pub mod repository_format;
 mod storage;
 
-pub use controller::Controller;
+pub use controller::{assure_supported_format, Controller};
 pub use project::{ApiProject, AuthKey, CodePushState, FetchResult, Project, ProjectId};
 pub use storage::UpdateRequest;
 
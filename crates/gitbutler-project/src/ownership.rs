@@ -0,0 +1,130 @@
+//! Support for repositories owned by another OS user.
+//!
+//! Like Git, `git2` refuses to open such repositories unless they are listed in the `safe.directory`
+//! configuration, which protects against configuration planted by other users on shared machines.
+//! Rather than failing deep within an operation, the state is detected upfront so the user can
+//! decide to trust the repository.
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+const SAFE_DIRECTORY: &str = "safe.directory";
+
+/// Whether Git will work with a repository, based on who owns it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "subject", rename_all = "camelCase")]
+pub enum Ownership {
+    /// The repository is owned by the current user.
+    Owned,
+    /// The repository is owned by another user, but trusted through `safe.directory`.
+    Trusted,
+    /// The repository is owned by another user and isn't trusted, so it can't be opened.
+    /// Use [`trust()`] to change that.
+    #[serde(rename_all = "camelCase")]
+    Untrusted { path: PathBuf },
+}
+
+/// Determine the ownership of the repository with `worktree_dir`.
+pub fn ownership(worktree_dir: &Path) -> Result<Ownership> {
+    let is_owned = |path: &Path| {
+        gix::sec::identity::is_path_owned_by_current_user(path)
+            .with_context(|| format!("failed to determine the owner of {path:?}"))
+    };
+    let git_dir = worktree_dir.join(".git");
+    if is_owned(worktree_dir)? && (!git_dir.is_dir() || is_owned(&git_dir)?) {
+        return Ok(Ownership::Owned);
+    }
+
+    let config = git2::Config::open_default()?;
+    let path = safe_directory_value(worktree_dir)?;
+    let mut trusted = false;
+    let mut entries = match config.multivar(SAFE_DIRECTORY, None) {
+        Ok(entries) => entries,
+        Err(err) if err.code() == git2::ErrorCode::NotFound => {
+            return Ok(Ownership::Untrusted { path });
+        }
+        Err(err) => return Err(err.into()),
+    };
+    while let Some(entry) = entries.next() {
+        match entry?.value() {
+            // An empty value resets the list.
+            Some("") => trusted = false,
+            Some(value) if value == "*" || Path::new(value) == path => trusted = true,
+            _ => {}
+        }
+    }
+    Ok(if trusted {
+        Ownership::Trusted
+    } else {
+        Ownership::Untrusted { path }
+    })
+}
+
+/// Trust the repository with `worktree_dir` by adding it to `safe.directory` in the global configuration
+/// of the current user, which is the scope in which Git honors it. This affects only this repository.
+pub fn trust(worktree_dir: &Path) -> Result<()> {
+    let global_config_path = match git2::Config::find_global() {
+        Ok(path) => path,
+        Err(_) => gix::path::env::home_dir()
+            .context("can't find the home directory to store the global configuration in")?
+            .join(".gitconfig"),
+    };
+    let mut config = git2::Config::open(&global_config_path)?;
+    add_safe_directory(&mut config, &safe_directory_value(worktree_dir)?)
+}
+
+/// Add `path` to `safe.directory` in `config`, unless it's already there.
+fn add_safe_directory(config: &mut git2::Config, path: &Path) -> Result<()> {
+    let value = path.to_string_lossy();
+    // Only an existing entry for exactly `value` is replaced, by itself. Empty entries, which reset
+    // the list, and entries of other repositories are kept.
+    config
+        .set_multivar(SAFE_DIRECTORY, &exact_value_pattern(&value), &value)
+        .with_context(|| format!("failed to add {path:?} to {SAFE_DIRECTORY}"))
+}
+
+/// A POSIX extended regular expression, as used by `set_multivar()`, which only matches `value`.
+fn exact_value_pattern(value: &str) -> String {
+    let mut pattern = String::from("^");
+    for c in value.chars() {
+        if r".[\(){*+?|^$".contains(c) {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('$');
+    pattern
+}
+
+/// Git compares `safe.directory` entries with the absolute worktree path, using forward slashes on Windows.
+fn safe_directory_value(worktree_dir: &Path) -> Result<PathBuf> {
+    let path = gix::path::realpath(worktree_dir)?;
+    let path = gix::path::to_unix_separators_on_windows(gix::path::into_bstr(path));
+    Ok(gix::path::from_bstr(path).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trusting_keeps_other_entries() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let mut config = git2::Config::open(&tmp.path().join("config"))?;
+        config.set_multivar(SAFE_DIRECTORY, "^$", "/other")?;
+        config.set_multivar(SAFE_DIRECTORY, "^_$", "")?;
+
+        let path = Path::new("/path/to/repo.git (copy)");
+        add_safe_directory(&mut config, path)?;
+        add_safe_directory(&mut config, path)?;
+
+        let mut values = Vec::new();
+        let mut entries = config.multivar(SAFE_DIRECTORY, None)?;
+        while let Some(entry) = entries.next() {
+            values.push(entry?.value().unwrap_or_default().to_owned());
+        }
+        assert_eq!(values, ["/other", "", "/path/to/repo.git (copy)"]);
+        Ok(())
+    }
+}
@@ -1,12 +1,18 @@
 use std::{
+    collections::BTreeMap,
     path::{self, PathBuf},
     time,
 };
 
+use anyhow::Result;
+use gitbutler_config::{
+    api::ProjectCommands,
+    git::{GbConfig, GitConfig},
+};
 use gitbutler_id::id::Id;
 use serde::{Deserialize, Serialize};
 
-use crate::default_true::DefaultTrue;
+use crate::{default_true::DefaultTrue, feature_flags::FeatureFlag};
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -94,9 +100,23 @@ pub struct Project {
     pub omit_certificate_check: Option<bool>,
     // The number of changed lines that will trigger a snapshot
     pub snapshot_lines_threshold: Option<usize>,
+    /// If `true`, loose objects are packed every once in a while after creating snapshots,
+    /// to keep the many small snapshot objects from bloating the object database.
+    #[serde(default)]
+    pub pack_snapshots: bool,
+    /// If `true`, the hooks of the repository don't run when committing, even if asked to.
+    #[serde(default)]
+    pub skip_commit_hooks: bool,
+    /// If `true`, conflicts whose sides only differ in whitespace are resolved when rebasing and merging,
+    /// and the paths resolved that way are recorded in the commit.
+    #[serde(default)]
+    pub auto_resolve_whitespace: bool,
     // Experimental flag for new hunk dependency algorithm
     #[serde(default = "default_true")]
     pub use_experimental_locking: bool,
+    /// The [feature flags](crate::feature_flags) the user turned on or off for this project.
+    #[serde(default)]
+    pub feature_flags: BTreeMap<FeatureFlag, bool>,
 }
 
 // TODO: Remove after `use_experimental` has been removed.
@@ -146,3 +166,14 @@ impl Project {
         self.path.clone()
     }
 }
+
+impl ProjectCommands for Project {
+    fn gb_config(&self) -> Result<GbConfig> {
+        let repo = git2::Repository::open(&self.path)?;
+        repo.gb_config()
+    }
+    fn set_gb_config(&self, config: GbConfig) -> Result<()> {
+        let repo = git2::Repository::open(&self.path)?;
+        repo.set_gb_config(config)
+    }
+}
@@ -0,0 +1,62 @@
+//! Support for the storage formats of repositories.
+//!
+//! Git can identify objects by SHA-256 instead of SHA-1, and store references in a reftable instead
+//! of files. Neither is supported by `git2`, which would fail in odd ways deep within an operation,
+//! so such repositories are detected upfront from the extensions in their configuration.
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+/// A way of storing a repository that isn't supported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "subject", rename_all = "camelCase")]
+pub enum UnsupportedFormat {
+    /// Objects are identified by a hash other than SHA-1, like `sha256`.
+    ObjectFormat(String),
+    /// References are stored in a backend other than files, like `reftable`.
+    RefStorage(String),
+}
+
+impl std::fmt::Display for UnsupportedFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnsupportedFormat::ObjectFormat(name) => {
+                write!(
+                    f,
+                    "objects are identified by {name}, but only sha1 is supported"
+                )
+            }
+            UnsupportedFormat::RefStorage(name) => {
+                write!(
+                    f,
+                    "references are stored as {name}, but only files are supported"
+                )
+            }
+        }
+    }
+}
+
+/// Return the first way the repository with `worktree_dir` is stored in that isn't supported, if any.
+/// Directories without a repository are left to be rejected by whoever tries to open them.
+pub fn unsupported_format(worktree_dir: &Path) -> Result<Option<UnsupportedFormat>> {
+    let config_path = worktree_dir.join(".git").join("config");
+    if !config_path.is_file() {
+        return Ok(None);
+    }
+    let config = git2::Config::open(&config_path)
+        .with_context(|| format!("failed to read {}", config_path.display()))?;
+    let get = |key: &str| match config.get_string(key) {
+        Ok(value) => Ok(Some(value.to_lowercase())),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(err) => Err(err),
+    };
+
+    if let Some(name) = get("extensions.objectFormat")?.filter(|name| name != "sha1") {
+        return Ok(Some(UnsupportedFormat::ObjectFormat(name)));
+    }
+    if let Some(name) = get("extensions.refStorage")?.filter(|name| name != "files") {
+        return Ok(Some(UnsupportedFormat::RefStorage(name)));
+    }
+    Ok(None)
+}
@@ -1,9 +1,11 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::{ApiProject, AuthKey, CodePushState, FetchResult, Project, ProjectId};
+use crate::{
+    feature_flags::FeatureFlag, ApiProject, AuthKey, CodePushState, FetchResult, Project, ProjectId,
+};
 
 const PROJECTS_FILE: &str = "projects.json";
 
@@ -27,7 +29,11 @@ pub struct UpdateRequest {
     pub omit_certificate_check: Option<bool>,
     pub use_diff_context: Option<bool>,
     pub snapshot_lines_threshold: Option<usize>,
+    pub pack_snapshots: Option<bool>,
+    pub skip_commit_hooks: Option<bool>,
+    pub auto_resolve_whitespace: Option<bool>,
     pub use_experimental_locking: Option<bool>,
+    pub feature_flags: Option<BTreeMap<FeatureFlag, bool>>,
 }
 
 impl Storage {
@@ -124,10 +130,26 @@ impl Storage {
             project.snapshot_lines_threshold = Some(snapshot_lines_threshold);
         }
 
+        if let Some(pack_snapshots) = update_request.pack_snapshots {
+            project.pack_snapshots = pack_snapshots;
+        }
+
+        if let Some(skip_commit_hooks) = update_request.skip_commit_hooks {
+            project.skip_commit_hooks = skip_commit_hooks;
+        }
+
+        if let Some(auto_resolve_whitespace) = update_request.auto_resolve_whitespace {
+            project.auto_resolve_whitespace = auto_resolve_whitespace;
+        }
+
         if let Some(use_experimental_locking) = &update_request.use_experimental_locking {
             project.use_experimental_locking = *use_experimental_locking;
         }
 
+        if let Some(feature_flags) = &update_request.feature_flags {
+            project.feature_flags = feature_flags.clone();
+        }
+
         self.inner
             .write(PROJECTS_FILE, &serde_json::to_string_pretty(&projects)?)?;
 
@@ -120,6 +120,49 @@ mod add {
             assert_eq!(err.to_string(), "can only work in main worktrees");
         }
 
+        #[test]
+        fn unsupported_format() {
+            use gitbutler_error::error::Code;
+            use gitbutler_project::repository_format::{self, UnsupportedFormat};
+
+            let (controller, _tmp) = new();
+            for (key, value, expected) in [
+                (
+                    "extensions.objectFormat",
+                    "sha256",
+                    UnsupportedFormat::ObjectFormat("sha256".into()),
+                ),
+                (
+                    "extensions.refStorage",
+                    "reftable",
+                    UnsupportedFormat::RefStorage("reftable".into()),
+                ),
+            ] {
+                let tmp = tempfile::tempdir().unwrap();
+                let repo = git2::Repository::init(tmp.path()).unwrap();
+                create_initial_commit(&repo);
+                assert_eq!(
+                    repository_format::unsupported_format(tmp.path()).unwrap(),
+                    None
+                );
+
+                let mut config = repo.config().unwrap();
+                config.set_i32("core.repositoryFormatVersion", 1).unwrap();
+                config.set_str(key, value).unwrap();
+                assert_eq!(
+                    repository_format::unsupported_format(tmp.path()).unwrap(),
+                    Some(expected)
+                );
+
+                let err = controller.add(tmp.path()).unwrap_err();
+                assert_eq!(
+                    err.downcast_ref::<Code>(),
+                    Some(&Code::RepositoryFormatUnsupported),
+                    "{key}"
+                );
+            }
+        }
+
         fn create_initial_commit(repo: &git2::Repository) -> git2::Oid {
             let signature = git2::Signature::now("test", "test@email.com").unwrap();
 
@@ -147,6 +190,118 @@ mod add {
     }
 }
 
+mod hygiene {
+    use gitbutler_project::hygiene::{self, Issue};
+
+    use super::*;
+
+    #[test]
+    fn added_projects_pass_verification() {
+        let (controller, _tmp) = new();
+        let repository = gitbutler_testsupport::TestProject::default();
+        let exclude = repository.path().join(".git/info/exclude");
+        std::fs::create_dir_all(exclude.parent().unwrap()).unwrap();
+        std::fs::write(&exclude, "*.log").unwrap();
+
+        let project = controller.add(repository.path()).unwrap();
+        assert_eq!(hygiene::verify(&project).unwrap(), []);
+        assert_eq!(
+            std::fs::read_to_string(&exclude).unwrap(),
+            "*.log",
+            "user excludes are left alone"
+        );
+
+        hygiene::ensure(&project).unwrap();
+        assert_eq!(hygiene::verify(&project).unwrap(), [], "it's idempotent");
+    }
+
+    #[test]
+    fn problems_are_detected() {
+        let (controller, _tmp) = new();
+        let repository = gitbutler_testsupport::TestProject::default();
+        let project = controller.add(repository.path()).unwrap();
+
+        std::fs::remove_dir_all(project.gb_dir()).unwrap();
+        let repo = git2::Repository::open(repository.path()).unwrap();
+        let head = repo.head().unwrap().target().unwrap();
+        repo.reference("refs/heads/gitbutler", head, false, "")
+            .unwrap();
+
+        let issues = hygiene::verify(&project).unwrap();
+        assert_eq!(
+            issues,
+            [
+                Issue::MissingDataDir,
+                Issue::WorkspaceRefBlocked {
+                    refname: "refs/heads/gitbutler".into()
+                }
+            ]
+        );
+
+        hygiene::ensure(&project).unwrap();
+        let remaining = hygiene::verify(&project).unwrap();
+        assert!(remaining.iter().all(|issue| !issue.is_fixable()));
+        assert_eq!(remaining.len(), 1);
+    }
+
+    #[test]
+    fn unsigned_workspace_commits_are_detected() {
+        let (controller, _tmp) = new();
+        let repository = gitbutler_testsupport::TestProject::default();
+        let project = controller.add(repository.path()).unwrap();
+
+        let repo = git2::Repository::open(repository.path()).unwrap();
+        let head = repo.head().unwrap().target().unwrap();
+        repo.reference("refs/heads/gitbutler/workspace", head, false, "")
+            .unwrap();
+        assert_eq!(
+            hygiene::verify(&project).unwrap(),
+            [],
+            "signing isn't configured"
+        );
+
+        let mut config = repo.config().unwrap();
+        config.set_bool("gitbutler.signCommits", true).unwrap();
+        assert_eq!(
+            hygiene::verify(&project).unwrap(),
+            [],
+            "signing the commits of the user doesn't sign workspace commits"
+        );
+
+        config
+            .set_bool("gitbutler.signWorkspaceCommits", false)
+            .unwrap();
+        assert_eq!(
+            hygiene::verify(&project).unwrap(),
+            [],
+            "workspace commits are explicitly left unsigned"
+        );
+
+        config
+            .set_bool("gitbutler.signWorkspaceCommits", true)
+            .unwrap();
+        assert_eq!(
+            hygiene::verify(&project).unwrap(),
+            [Issue::UnsignedWorkspaceCommit {
+                commit_id: head.to_string()
+            }]
+        );
+    }
+}
+
+mod ownership {
+    use gitbutler_project::ownership::{self, Ownership};
+
+    #[test]
+    fn repositories_of_the_current_user_are_owned() {
+        let repository = gitbutler_testsupport::TestProject::default();
+        assert_eq!(
+            ownership::ownership(repository.path()).unwrap(),
+            Ownership::Owned
+        );
+    }
+}
+
 mod delete {
     use super::*;
     #[test]
@@ -162,3 +317,73 @@ mod delete {
         assert!(!project.path.join(".gitbutler.json").exists());
     }
 }
+
+mod feature_flags {
+    use std::collections::BTreeMap;
+
+    use gitbutler_project::feature_flags::{FeatureFlag, FlagSource};
+    use gitbutler_project::UpdateRequest;
+
+    use super::*;
+
+    #[test]
+    fn project_overrides_remote_and_default() {
+        let (controller, _tmp) = new();
+        let repository = gitbutler_testsupport::TestProject::default();
+        let project = controller.add(repository.path()).unwrap();
+        let no_remote = BTreeMap::new();
+        let state = project.feature_flag_with_remote(FeatureFlag::AutoStash, &no_remote);
+        assert!(!state.enabled);
+        assert_eq!(state.source, FlagSource::Default);
+
+        let remote = BTreeMap::from([(FeatureFlag::AutoStash, true)]);
+        let state = project.feature_flag_with_remote(FeatureFlag::AutoStash, &remote);
+        assert!(state.enabled);
+        assert_eq!(state.source, FlagSource::Remote);
+
+        let project = controller
+            .update(&UpdateRequest {
+                id: project.id,
+                feature_flags: Some(BTreeMap::from([(FeatureFlag::AutoStash, false)])),
+                ..Default::default()
+            })
+            .unwrap();
+        let project = controller.get(project.id).unwrap();
+        let state = project.feature_flag_with_remote(FeatureFlag::AutoStash, &remote);
+        assert!(!state.enabled);
+        assert_eq!(
+            state.source,
+            FlagSource::Project,
+            "the override is persisted"
+        );
+    }
+
+    #[test]
+    fn legacy_locking_setting_applies_until_overridden() {
+        let (controller, _tmp) = new();
+        let repository = gitbutler_testsupport::TestProject::default();
+        let project = controller.add(repository.path()).unwrap();
+        let remote = BTreeMap::from([(FeatureFlag::ExperimentalLocking, true)]);
+        let project = controller
+            .update(&UpdateRequest {
+                id: project.id,
+                use_experimental_locking: Some(false),
+                ..Default::default()
+            })
+            .unwrap();
+        let state = project.feature_flag_with_remote(FeatureFlag::ExperimentalLocking, &remote);
+        assert!(!state.enabled);
+        assert_eq!(state.source, FlagSource::Legacy);
+
+        let project = controller
+            .update(&UpdateRequest {
+                id: project.id,
+                feature_flags: Some(BTreeMap::from([(FeatureFlag::ExperimentalLocking, true)])),
+                ..Default::default()
+            })
+            .unwrap();
+        let state = project.feature_flag_with_remote(FeatureFlag::ExperimentalLocking, &remote);
+        assert!(state.enabled);
+        assert_eq!(state.source, FlagSource::Project);
+    }
+}
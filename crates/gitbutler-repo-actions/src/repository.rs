@@ -262,60 +262,82 @@ impl RepoActionsExt for CommandContext {
         if self.project().preferred_key == AuthKey::SystemExecutable {
             let path = self.project().worktree_path();
             let remote = remote_name.to_string();
-            return std::thread::spawn(move || {
+            let cli_refspec = refspec.clone();
+            let result = std::thread::spawn(move || {
                 tokio::runtime::Runtime::new()
                     .unwrap()
                     .block_on(gitbutler_git::fetch(
                         path,
                         gitbutler_git::tokio::TokioExecutor,
                         &remote,
-                        gitbutler_git::RefSpec::parse(refspec).unwrap(),
+                        gitbutler_git::RefSpec::parse(cli_refspec).unwrap(),
                         handle_git_prompt_fetch,
                         askpass,
                     ))
             })
             .join()
-            .unwrap()
-            .map_err(Into::into);
+            .unwrap();
+            return match result {
+                Ok(()) => Ok(()),
+                Err(err) => {
+                    // Public repositories can still be fetched without any credentials set up.
+                    let Some(anonymous_flow) = credentials::help_anonymous(self, remote_name)?
+                    else {
+                        return Err(err.into());
+                    };
+                    // The error of the CLI is more helpful, as it's the one with the credentials.
+                    fetch_with_credentials(self, vec![anonymous_flow], &refspec)
+                        .map_err(|_anonymous_err| err.into())
+                }
+            };
         }
 
-        let auth_flows = credentials::help(self, remote_name)?;
-        for (mut remote, callbacks) in auth_flows {
-            for callback in callbacks {
-                let mut fetch_opts = git2::FetchOptions::new();
-                let mut cbs: git2::RemoteCallbacks = callback.into();
-                if self.project().omit_certificate_check.unwrap_or(false) {
-                    cbs.certificate_check(|_, _| Ok(git2::CertificateCheckStatus::CertificateOk));
-                }
-                fetch_opts.remote_callbacks(cbs);
-                fetch_opts.prune(git2::FetchPrune::On);
+        let auth_flows = credentials::help_read_only(self, remote_name)?;
+        fetch_with_credentials(self, auth_flows, &refspec)
+    }
+}
 
-                match remote.fetch(&[&refspec], Some(&mut fetch_opts), None) {
-                    Ok(()) => {
-                        tracing::info!(project_id = %self.project().id, %refspec, "git fetched");
-                        return Ok(());
+/// Fetch `refspec` by trying each remote with each of its credentials, until one succeeds.
+fn fetch_with_credentials(
+    ctx: &CommandContext,
+    auth_flows: Vec<(git2::Remote<'_>, Vec<credentials::Credential>)>,
+    refspec: &str,
+) -> Result<()> {
+    for (mut remote, callbacks) in auth_flows {
+        for callback in callbacks {
+            let mut fetch_opts = git2::FetchOptions::new();
+            let mut cbs: git2::RemoteCallbacks = callback.into();
+            if ctx.project().omit_certificate_check.unwrap_or(false) {
+                cbs.certificate_check(|_, _| Ok(git2::CertificateCheckStatus::CertificateOk));
+            }
+            fetch_opts.remote_callbacks(cbs);
+            fetch_opts.prune(git2::FetchPrune::On);
+
+            match remote.fetch(&[refspec], Some(&mut fetch_opts), None) {
+                Ok(()) => {
+                    tracing::info!(project_id = %ctx.project().id, %refspec, "git fetched");
+                    return Ok(());
+                }
+                Err(err) => match err.class() {
+                    git2::ErrorClass::Net | git2::ErrorClass::Http => {
+                        tracing::warn!(project_id = %ctx.project().id, ?err, "fetch failed due to network");
+                        continue;
                     }
-                    Err(err) => match err.class() {
-                        git2::ErrorClass::Net | git2::ErrorClass::Http => {
-                            tracing::warn!(project_id = %self.project().id, ?err, "fetch failed due to network");
+                    _ => match err.code() {
+                        git2::ErrorCode::Auth => {
+                            tracing::warn!(project_id = %ctx.project().id, ?err, "fetch failed due to auth");
                             continue;
                         }
-                        _ => match err.code() {
-                            git2::ErrorCode::Auth => {
-                                tracing::warn!(project_id = %self.project().id, ?err, "fetch failed due to auth");
-                                continue;
-                            }
-                            _ => {
-                                return Err(err.into());
-                            }
-                        },
+                        _ => {
+                            return Err(err.into());
+                        }
                     },
-                }
+                },
             }
         }
-
-        Err(anyhow!("authentication failed")).context(Code::ProjectGitAuth)
     }
+
+    Err(anyhow!("authentication failed")).context(Code::ProjectGitAuth)
 }
 
 async fn handle_git_prompt_push(
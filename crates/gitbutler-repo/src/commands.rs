@@ -95,6 +95,59 @@ impl FileInfo {
     }
 }
 
+/// Context lines around a hunk, to reveal more of the surrounding code in a diff.
+#[derive(Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkContext {
+    /// The 1-based number of the first line in `before`.
+    pub before_start: u32,
+    /// The lines right before the hunk, without line separator.
+    pub before: Vec<String>,
+    /// The 1-based number of the first line in `after`.
+    pub after_start: u32,
+    /// The lines right after the hunk, without line separator.
+    pub after: Vec<String>,
+    /// `true` if `before` starts at the first line of the file, so there is nothing left to expand.
+    pub reached_start: bool,
+    /// `true` if `after` ends at the last line of the file, so there is nothing left to expand.
+    pub reached_end: bool,
+}
+
+impl HunkContext {
+    /// Collect up to `lines_before` and `lines_after` lines of `content` around the hunk spanning
+    /// `hunk_lines` lines from the 1-based `hunk_start`, or all lines in that direction if `None`.
+    pub fn from_content(
+        content: &str,
+        hunk_start: u32,
+        hunk_lines: u32,
+        lines_before: Option<u32>,
+        lines_after: Option<u32>,
+    ) -> Self {
+        let lines: Vec<&str> = content.lines().collect();
+        let total = lines.len();
+        // Empty hunks, e.g. of deletions, are placed right after the line they start at.
+        let hunk_start = if hunk_lines == 0 {
+            hunk_start
+        } else {
+            hunk_start.max(1) - 1
+        } as usize;
+        let hunk_end = (hunk_start + hunk_lines as usize).min(total);
+        let hunk_start = hunk_start.min(hunk_end);
+
+        let before_start = lines_before.map_or(0, |n| hunk_start.saturating_sub(n as usize));
+        let after_end = lines_after.map_or(total, |n| (hunk_end + n as usize).min(total));
+        let to_owned = |lines: &[&str]| lines.iter().map(|line| (*line).to_owned()).collect();
+        HunkContext {
+            before_start: before_start as u32 + 1,
+            before: to_owned(&lines[before_start..hunk_start]),
+            after_start: hunk_end as u32 + 1,
+            after: to_owned(&lines[hunk_end..after_end]),
+            reached_start: before_start == 0,
+            reached_end: after_end == total,
+        }
+    }
+}
+
 pub trait RepoCommands {
     fn add_remote(&self, name: &str, url: &str) -> Result<()>;
     fn remotes(&self) -> Result<Vec<String>>;
@@ -120,6 +173,21 @@ pub trait RepoCommands {
         treeish: Option<Oid>,
         probably_relative_path: &Path,
     ) -> Result<FileInfo>;
+    /// Return context lines around the hunk at `hunk_start` spanning `hunk_lines` in the new version
+    /// of `relative_path`, which is read like [`Self::read_file_from_workspace()`] does, so from the worktree
+    /// for uncommitted changes, or from `treeish` if it's a diff of a commit.
+    ///
+    /// If `lines_before` or `lines_after` are `None`, all lines up to the start or end of the file are returned.
+    /// As context lines are unchanged, the hunk can be expanded without computing the diff again.
+    fn expand_hunk_context(
+        &self,
+        treeish: Option<Oid>,
+        relative_path: &Path,
+        hunk_start: u32,
+        hunk_lines: u32,
+        lines_before: Option<u32>,
+        lines_after: Option<u32>,
+    ) -> Result<HunkContext>;
 }
 
 impl RepoCommands for Project {
@@ -217,6 +285,33 @@ impl RepoCommands for Project {
             Err(err) => return Err(err.into()),
         })
     }
+
+    fn expand_hunk_context(
+        &self,
+        treeish: Option<Oid>,
+        relative_path: &Path,
+        hunk_start: u32,
+        hunk_lines: u32,
+        lines_before: Option<u32>,
+        lines_after: Option<u32>,
+    ) -> Result<HunkContext> {
+        let file = self.read_file_from_workspace(treeish, relative_path)?;
+        let content = match (file.content, file.mime_type, file.size) {
+            (Some(content), None, _) => content,
+            (None, _, None) => bail!("'{}' was deleted", relative_path.display()),
+            _ => bail!(
+                "Can't show context of binary file '{}'",
+                relative_path.display()
+            ),
+        };
+        Ok(HunkContext::from_content(
+            &content,
+            hunk_start,
+            hunk_lines,
+            lines_before,
+            lines_after,
+        ))
+    }
 }
 
 fn read_file_from_tree(
@@ -238,3 +333,56 @@ fn read_file_from_tree(
         Err(e) => return Err(e.into()),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::HunkContext;
+
+    const CONTENT: &str = "1\n2\n3\n4\n5\n6\n7\n8\n";
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| (*line).to_owned()).collect()
+    }
+
+    #[test]
+    fn context_is_limited_to_the_requested_lines() {
+        let context = HunkContext::from_content(CONTENT, 4, 2, Some(2), Some(1));
+        assert_eq!(
+            context,
+            HunkContext {
+                before_start: 2,
+                before: lines(&["2", "3"]),
+                after_start: 6,
+                after: lines(&["6"]),
+                reached_start: false,
+                reached_end: false,
+            }
+        );
+    }
+
+    #[test]
+    fn context_stops_at_the_file_boundaries() {
+        let context = HunkContext::from_content(CONTENT, 2, 1, Some(10), Some(10));
+        assert_eq!(context.before, lines(&["1"]));
+        assert_eq!(context.after, lines(&["3", "4", "5", "6", "7", "8"]));
+        assert!(context.reached_start && context.reached_end);
+    }
+
+    #[test]
+    fn context_of_empty_hunks() {
+        // A deletion after line 2.
+        let context = HunkContext::from_content(CONTENT, 2, 0, Some(1), Some(1));
+        assert_eq!(context.before, lines(&["2"]));
+        assert_eq!(context.after, lines(&["3"]));
+        assert_eq!(context.after_start, 3);
+    }
+
+    #[test]
+    fn full_file_on_demand() {
+        let context = HunkContext::from_content(CONTENT, 8, 1, None, None);
+        assert_eq!(context.before.len(), 7);
+        assert!(context.after.is_empty());
+        assert_eq!(context.after_start, 9);
+        assert!(context.reached_start && context.reached_end);
+    }
+}
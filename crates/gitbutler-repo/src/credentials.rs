@@ -127,6 +127,38 @@ pub fn help<'a>(
     }
 }
 
+/// Like [`help()`], but for operations which only read from the remote, like fetching. These can also be
+/// done anonymously via HTTPS, which is tried last so public repositories can be read without credentials.
+pub fn help_read_only<'a>(
+    ctx: &'a CommandContext,
+    remote_name: &str,
+) -> Result<Vec<(git2::Remote<'a>, Vec<Credential>)>, HelpError> {
+    let mut flows = help(ctx, remote_name)?;
+    flows.extend(help_anonymous(ctx, remote_name)?);
+    Ok(flows)
+}
+
+/// The flow to read from the remote anonymously via HTTPS, if it is reachable that way and isn't local
+/// to begin with.
+pub fn help_anonymous<'a>(
+    ctx: &'a CommandContext,
+    remote_name: &str,
+) -> Result<Option<(git2::Remote<'a>, Vec<Credential>)>, HelpError> {
+    let remote = ctx.repository().find_remote(remote_name)?;
+    let remote_url = Url::from_str(remote.url().ok_or(HelpError::NoUrlSet)?)
+        .context("failed to parse remote url")?;
+
+    let https_remote = match remote_url.scheme {
+        Scheme::File => return Ok(None),
+        Scheme::Https => remote,
+        _ => match remote_url.as_https() {
+            Ok(url) => ctx.repository().remote_anonymous(&url.to_string())?,
+            Err(_) => return Ok(None),
+        },
+    };
+    Ok(Some((https_remote, vec![Credential::Noop])))
+}
+
 fn https_flow(ctx: &CommandContext, remote_url: &Url) -> Result<Vec<HttpsCredential>, HelpError> {
     let mut flow = vec![];
 
@@ -1,11 +1,14 @@
 pub mod rebase;
 
 mod commands;
-pub use commands::{FileInfo, RepoCommands};
+pub use commands::{FileInfo, HunkContext, RepoCommands};
 
 mod repository_ext;
 pub use repository_ext::{GixRepositoryExt, LogUntil, RepositoryExt};
 
+mod signing;
+pub use signing::{SignatureStatus, SignatureVerification};
+
 pub mod credentials;
 
 mod config;
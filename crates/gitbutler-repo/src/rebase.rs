@@ -5,6 +5,7 @@ use std::{
 
 use anyhow::{Context, Result};
 use bstr::ByteSlice;
+use git2::MergeOptions;
 use gitbutler_cherry_pick::{ConflictedTreeKey, RepositoryExt};
 use gitbutler_command_context::CommandContext;
 use gitbutler_commit::{
@@ -35,7 +36,12 @@ pub fn cherry_rebase(
         return Ok(None);
     }
 
-    let new_head_id = cherry_rebase_group(ctx.repository(), target_commit_oid, &ids_to_rebase)?;
+    let new_head_id = cherry_rebase_group(
+        ctx.repository(),
+        target_commit_oid,
+        &ids_to_rebase,
+        ctx.project().auto_resolve_whitespace,
+    )?;
 
     Ok(Some(new_head_id))
 }
@@ -46,10 +52,14 @@ pub fn cherry_rebase(
 /// rebase empty commits (two commits with identical trees)
 ///
 /// the commit id's to rebase should be ordered such that the child most commit is first
+///
+/// If `auto_resolve_whitespace` is set, conflicts whose sides only differ in whitespace are resolved,
+/// as configured for the project.
 pub fn cherry_rebase_group(
     repository: &git2::Repository,
     target_commit_oid: git2::Oid,
     ids_to_rebase: &[git2::Oid],
+    auto_resolve_whitespace: bool,
 ) -> Result<git2::Oid> {
     // now, rebase unchanged commits onto the new commit
     let commits_to_rebase = ids_to_rebase
@@ -72,9 +82,12 @@ pub fn cherry_rebase_group(
                     return Ok(to_rebase);
                 };
 
-                let mut cherrypick_index = repository
-                    .cherry_pick_gitbutler(&head, &to_rebase, None)
-                    .context("failed to cherry pick")?;
+                let (mut cherrypick_index, auto_resolved) =
+                    merge_resolving_whitespace(auto_resolve_whitespace, |merge_options| {
+                        repository
+                            .cherry_pick_gitbutler(&head, &to_rebase, merge_options)
+                            .context("failed to cherry pick")
+                    })?;
 
                 if cherrypick_index.has_conflicts() {
                     commit_conflicted_cherry_result(
@@ -82,9 +95,16 @@ pub fn cherry_rebase_group(
                         head,
                         to_rebase,
                         &mut cherrypick_index,
+                        &auto_resolved,
                     )
                 } else {
-                    commit_unconflicted_cherry_result(repository, head, to_rebase, cherrypick_index)
+                    commit_unconflicted_cherry_result(
+                        repository,
+                        head,
+                        to_rebase,
+                        cherrypick_index,
+                        &auto_resolved,
+                    )
                 }
             },
         )?
@@ -98,6 +118,7 @@ fn commit_unconflicted_cherry_result<'repository>(
     head: git2::Commit<'repository>,
     to_rebase: git2::Commit,
     mut cherrypick_index: git2::Index,
+    auto_resolved: &[String],
 ) -> Result<git2::Commit<'repository>> {
     let is_merge_commit = to_rebase.parent_count() > 0;
 
@@ -117,8 +138,10 @@ fn commit_unconflicted_cherry_result<'repository>(
     // Set conflicted header to None
     let commit_headers = to_rebase
         .gitbutler_headers()
+        .or_else(|| (!auto_resolved.is_empty()).then(Default::default))
         .map(|commit_headers| CommitHeadersV2 {
             conflicted: None,
+            auto_resolved: with_auto_resolved(commit_headers.auto_resolved, auto_resolved),
             ..commit_headers
         });
 
@@ -144,6 +167,7 @@ fn commit_conflicted_cherry_result<'repository>(
     head: git2::Commit,
     to_rebase: git2::Commit,
     cherrypick_index: &mut git2::Index,
+    auto_resolved: &[String],
 ) -> Result<git2::Commit<'repository>> {
     let commit_headers = to_rebase.gitbutler_headers();
 
@@ -197,6 +221,7 @@ fn commit_conflicted_cherry_result<'repository>(
             .or_else(|| Some(Default::default()))
             .map(|commit_headers| CommitHeadersV2 {
                 conflicted: Some(conflicted_files.total_entries() as u64),
+                auto_resolved: with_auto_resolved(commit_headers.auto_resolved, auto_resolved),
                 ..commit_headers
             });
 
@@ -224,12 +249,16 @@ fn commit_conflicted_cherry_result<'repository>(
 /// The `target_commit` and `incoming_commit` must have a common ancestor.
 ///
 /// If there is a merge conflict, the
+///
+/// If `auto_resolve_whitespace` is set, conflicts whose sides only differ in whitespace are resolved,
+/// as configured for the project.
 pub fn gitbutler_merge_commits<'repository>(
     repository: &'repository git2::Repository,
     target_commit: git2::Commit<'repository>,
     incoming_commit: git2::Commit<'repository>,
     target_branch_name: &str,
     incoming_branch_name: &str,
+    auto_resolve_whitespace: bool,
 ) -> Result<git2::Commit<'repository>> {
     let merge_base = repository.merge_base(target_commit.id(), incoming_commit.id())?;
     let merge_base = repository.find_commit(merge_base)?;
@@ -242,8 +271,17 @@ pub fn gitbutler_merge_commits<'repository>(
 
     let target_merge_tree = repository.find_real_tree(&target_commit, Default::default())?;
     let incoming_merge_tree = repository.find_real_tree(&incoming_commit, Default::default())?;
-    let mut merged_index =
-        repository.merge_trees(&base_tree, &incoming_merge_tree, &target_merge_tree, None)?;
+    let (mut merged_index, auto_resolved) =
+        merge_resolving_whitespace(auto_resolve_whitespace, |merge_options| {
+            repository
+                .merge_trees(
+                    &base_tree,
+                    &incoming_merge_tree,
+                    &target_merge_tree,
+                    merge_options,
+                )
+                .map_err(Into::into)
+        })?;
 
     let tree_oid;
     let conflicted_files;
@@ -293,11 +331,13 @@ pub fn gitbutler_merge_commits<'repository>(
     let commit_headers = if conflicted_file_count > 0 {
         CommitHeadersV2 {
             conflicted: Some(conflicted_file_count),
+            auto_resolved,
             ..Default::default()
         }
     } else {
         CommitHeadersV2 {
             conflicted: None,
+            auto_resolved,
             ..Default::default()
         }
     };
@@ -428,12 +468,66 @@ fn resolve_index(
     })
 }
 
+/// Merge with `merge`, and if that conflicts and `auto_resolve_whitespace` is enabled, merge again with
+/// changes that only differ in whitespace not conflicting. Identical changes never conflict.
+///
+/// Returns the merged index along with the paths of the files whose conflicts were resolved by
+/// ignoring whitespace.
+fn merge_resolving_whitespace(
+    auto_resolve_whitespace: bool,
+    merge: impl Fn(Option<&MergeOptions>) -> Result<git2::Index>,
+) -> Result<(git2::Index, Vec<String>)> {
+    let index = merge(None)?;
+    if !auto_resolve_whitespace || !index.has_conflicts() {
+        return Ok((index, Vec::new()));
+    }
+
+    let mut merge_options = MergeOptions::new();
+    merge_options
+        .ignore_whitespace_change(true)
+        .ignore_whitespace_eol(true);
+    let resolved_index = merge(Some(&merge_options))?;
+    let still_conflicted = conflicted_paths(&resolved_index)?;
+    let auto_resolved: Vec<_> = conflicted_paths(&index)?
+        .into_iter()
+        .filter(|path| !still_conflicted.contains(path))
+        .collect();
+    if !auto_resolved.is_empty() {
+        tracing::info!(?auto_resolved, "resolved conflicts by ignoring whitespace");
+    }
+    Ok((resolved_index, auto_resolved))
+}
+
+fn conflicted_paths(index: &git2::Index) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+            paths.push(entry.path.to_str_lossy().into_owned());
+        }
+    }
+    Ok(paths)
+}
+
+/// Add the paths `auto_resolved` by the current operation to those that were `previously_auto_resolved`.
+fn with_auto_resolved(
+    mut previously_auto_resolved: Vec<String>,
+    auto_resolved: &[String],
+) -> Vec<String> {
+    for path in auto_resolved {
+        if !previously_auto_resolved.contains(path) {
+            previously_auto_resolved.push(path.clone());
+        }
+    }
+    previously_auto_resolved
+}
+
 #[cfg(test)]
 mod test {
     #[cfg(test)]
     mod cherry_rebase_group {
         use crate::repository_ext::RepositoryExt as _;
-        use gitbutler_commit::commit_ext::CommitExt;
+        use gitbutler_commit::{commit_ext::CommitExt, commit_headers::HasCommitHeaders};
         use gitbutler_testsupport::testing_repository::{
             assert_commit_tree_matches, TestingRepository,
         };
@@ -450,9 +544,13 @@ mod test {
             let c = test_repository.commit_tree(Some(&b), &[("foo.txt", "c"), ("bar.txt", "a")]);
             let d = test_repository.commit_tree(Some(&a), &[("foo.txt", "a"), ("bar.txt", "x")]);
 
-            let result =
-                cherry_rebase_group(&test_repository.repository, d.id(), &[c.id(), b.id()])
-                    .unwrap();
+            let result = cherry_rebase_group(
+                &test_repository.repository,
+                d.id(),
+                &[c.id(), b.id()],
+                false,
+            )
+            .unwrap();
 
             let commit: git2::Commit = test_repository.repository.find_commit(result).unwrap();
 
@@ -480,7 +578,7 @@ mod test {
 
             // Rebase C on top of B
             let result =
-                cherry_rebase_group(&test_repository.repository, b.id(), &[c.id()]).unwrap();
+                cherry_rebase_group(&test_repository.repository, b.id(), &[c.id()], false).unwrap();
 
             let commit: git2::Commit = test_repository.repository.find_commit(result).unwrap();
 
@@ -509,11 +607,11 @@ mod test {
 
             // Rebase C on top of B => C'
             let result =
-                cherry_rebase_group(&test_repository.repository, b.id(), &[c.id()]).unwrap();
+                cherry_rebase_group(&test_repository.repository, b.id(), &[c.id()], false).unwrap();
 
             // Rebase C' on top of D => C''
             let result =
-                cherry_rebase_group(&test_repository.repository, d.id(), &[result]).unwrap();
+                cherry_rebase_group(&test_repository.repository, d.id(), &[result], false).unwrap();
 
             let commit: git2::Commit = test_repository.repository.find_commit(result).unwrap();
 
@@ -543,7 +641,7 @@ mod test {
 
             // Rebase D on top of B => D'
             let result =
-                cherry_rebase_group(&test_repository.repository, b.id(), &[d.id()]).unwrap();
+                cherry_rebase_group(&test_repository.repository, b.id(), &[d.id()], false).unwrap();
 
             let commit: git2::Commit = test_repository.repository.find_commit(result).unwrap();
             assert!(commit.is_conflicted());
@@ -561,7 +659,7 @@ mod test {
 
             // Rebase D' on top of C => D''
             let result =
-                cherry_rebase_group(&test_repository.repository, c.id(), &[result]).unwrap();
+                cherry_rebase_group(&test_repository.repository, c.id(), &[result], false).unwrap();
 
             let commit: git2::Commit = test_repository.repository.find_commit(result).unwrap();
             assert!(commit.is_conflicted());
@@ -588,9 +686,13 @@ mod test {
             let d = test_repository.commit_tree(Some(&a), &[("foo.txt", "c"), ("bar.txt", "c")]);
 
             // Rebase C on top of B
-            let result =
-                cherry_rebase_group(&test_repository.repository, d.id(), &[c.id(), b.id()])
-                    .unwrap();
+            let result = cherry_rebase_group(
+                &test_repository.repository,
+                d.id(),
+                &[c.id(), b.id()],
+                false,
+            )
+            .unwrap();
 
             let commit: git2::Commit = test_repository.repository.find_commit(result).unwrap();
 
@@ -633,6 +735,98 @@ mod test {
                 ],
             );
         }
+
+        fn auto_resolved(test_repository: &TestingRepository, id: git2::Oid) -> Vec<String> {
+            test_repository
+                .repository
+                .find_commit(id)
+                .unwrap()
+                .gitbutler_headers()
+                .map(|headers| headers.auto_resolved)
+                .unwrap_or_default()
+        }
+
+        #[test]
+        fn whitespace_changes_on_both_sides_are_resolved() {
+            let test_repository = TestingRepository::open();
+
+            let a = test_repository.commit_tree(None, &[("foo.txt", "fn a() {\n    x\n}\n")]);
+            let b = test_repository.commit_tree(Some(&a), &[("foo.txt", "fn a() {\n\tx\n}\n")]);
+            let c = test_repository.commit_tree(Some(&a), &[("foo.txt", "fn a() {\n  x \n}\n")]);
+
+            let conflicted =
+                cherry_rebase_group(&test_repository.repository, b.id(), &[c.id()], false).unwrap();
+            assert!(
+                test_repository
+                    .repository
+                    .find_commit(conflicted)
+                    .unwrap()
+                    .is_conflicted(),
+                "without the project setting, whitespace changes conflict"
+            );
+            assert!(auto_resolved(&test_repository, conflicted).is_empty());
+
+            let result =
+                cherry_rebase_group(&test_repository.repository, b.id(), &[c.id()], true).unwrap();
+            let commit = test_repository.repository.find_commit(result).unwrap();
+            assert!(!commit.is_conflicted());
+            assert_commit_tree_matches(
+                &test_repository.repository,
+                &commit,
+                &[("foo.txt", b"fn a() {\n  x \n}\n")],
+            );
+            assert_eq!(auto_resolved(&test_repository, result), ["foo.txt"]);
+        }
+
+        #[test]
+        fn whitespace_changes_on_one_side_give_way_to_the_other() {
+            let test_repository = TestingRepository::open();
+
+            let a = test_repository.commit_tree(None, &[("foo.txt", "fn a() {\n    x\n}\n")]);
+            let b = test_repository.commit_tree(Some(&a), &[("foo.txt", "fn a() {\n\tx\n}\n")]);
+            let c = test_repository.commit_tree(Some(&a), &[("foo.txt", "fn a() {\n    y\n}\n")]);
+
+            let result =
+                cherry_rebase_group(&test_repository.repository, b.id(), &[c.id()], true).unwrap();
+            let commit = test_repository.repository.find_commit(result).unwrap();
+            assert!(!commit.is_conflicted());
+            assert_commit_tree_matches(
+                &test_repository.repository,
+                &commit,
+                &[("foo.txt", b"fn a() {\n    y\n}\n")],
+            );
+            assert_eq!(auto_resolved(&test_repository, result), ["foo.txt"]);
+        }
+
+        #[test]
+        fn other_conflicts_are_not_resolved() {
+            let test_repository = TestingRepository::open();
+
+            let a = test_repository.commit_tree(None, &[("foo.txt", "a\n"), ("bar.txt", "  a\n")]);
+            let b =
+                test_repository.commit_tree(Some(&a), &[("foo.txt", "b\n"), ("bar.txt", "\ta\n")]);
+            let c = test_repository
+                .commit_tree(Some(&a), &[("foo.txt", "c\n"), ("bar.txt", "    a \n")]);
+
+            let result =
+                cherry_rebase_group(&test_repository.repository, b.id(), &[c.id()], true).unwrap();
+            let commit = test_repository.repository.find_commit(result).unwrap();
+            assert!(commit.is_conflicted());
+            assert_commit_tree_matches(
+                &test_repository.repository,
+                &commit,
+                &[
+                    (".auto-resolution/foo.txt", b"b\n"),
+                    (".conflict-side-0/foo.txt", b"b\n"),
+                    (".conflict-side-1/foo.txt", b"c\n"),
+                ],
+            );
+            assert_eq!(
+                auto_resolved(&test_repository, result),
+                ["bar.txt"],
+                "only the whitespace conflict is resolved"
+            );
+        }
     }
 
     #[cfg(test)]
@@ -652,9 +846,15 @@ mod test {
             let b = test_repository.commit_tree(Some(&a), &[("foo.txt", "b")]);
             let c = test_repository.commit_tree(Some(&a), &[("foo.txt", "a"), ("bar.txt", "a")]);
 
-            let result =
-                gitbutler_merge_commits(&test_repository.repository, b, c, "master", "feature")
-                    .unwrap();
+            let result = gitbutler_merge_commits(
+                &test_repository.repository,
+                b,
+                c,
+                "master",
+                "feature",
+                false,
+            )
+            .unwrap();
 
             assert!(!result.is_conflicted());
 
@@ -674,9 +874,15 @@ mod test {
             let b = test_repository.commit_tree(Some(&a), &[("foo.txt", "b")]);
             let c = test_repository.commit_tree(Some(&a), &[("foo.txt", "c")]);
 
-            let result =
-                gitbutler_merge_commits(&test_repository.repository, b, c, "master", "feature")
-                    .unwrap();
+            let result = gitbutler_merge_commits(
+                &test_repository.repository,
+                b,
+                c,
+                "master",
+                "feature",
+                false,
+            )
+            .unwrap();
 
             assert!(result.is_conflicted());
 
@@ -702,9 +908,15 @@ mod test {
             let c = test_repository.commit_tree(Some(&a), &[("foo.txt", "c")]);
             let d = test_repository.commit_tree(Some(&a), &[("foo.txt", "a"), ("bar.txt", "a")]);
 
-            let bc_result =
-                gitbutler_merge_commits(&test_repository.repository, b, c, "master", "feature")
-                    .unwrap();
+            let bc_result = gitbutler_merge_commits(
+                &test_repository.repository,
+                b,
+                c,
+                "master",
+                "feature",
+                false,
+            )
+            .unwrap();
 
             let result = gitbutler_merge_commits(
                 &test_repository.repository,
@@ -712,6 +924,7 @@ mod test {
                 d,
                 "master",
                 "feature",
+                false,
             )
             .unwrap();
 
@@ -742,13 +955,25 @@ mod test {
             let d = test_repository.commit_tree(Some(&a), &[("foo.txt", "a"), ("bar.txt", "b")]);
             let e = test_repository.commit_tree(Some(&a), &[("foo.txt", "a"), ("bar.txt", "c")]);
 
-            let bc_result =
-                gitbutler_merge_commits(&test_repository.repository, b, c, "master", "feature")
-                    .unwrap();
+            let bc_result = gitbutler_merge_commits(
+                &test_repository.repository,
+                b,
+                c,
+                "master",
+                "feature",
+                false,
+            )
+            .unwrap();
 
-            let de_result =
-                gitbutler_merge_commits(&test_repository.repository, d, e, "master", "feature")
-                    .unwrap();
+            let de_result = gitbutler_merge_commits(
+                &test_repository.repository,
+                d,
+                e,
+                "master",
+                "feature",
+                false,
+            )
+            .unwrap();
 
             let result = gitbutler_merge_commits(
                 &test_repository.repository,
@@ -756,6 +981,7 @@ mod test {
                 de_result,
                 "master",
                 "feature",
+                false,
             )
             .unwrap();
 
@@ -791,13 +1017,25 @@ mod test {
             let d = test_repository.commit_tree(Some(&a), &[("foo.txt", "d")]);
             let e = test_repository.commit_tree(Some(&a), &[("foo.txt", "f")]);
 
-            let bc_result =
-                gitbutler_merge_commits(&test_repository.repository, b, c, "master", "feature")
-                    .unwrap();
+            let bc_result = gitbutler_merge_commits(
+                &test_repository.repository,
+                b,
+                c,
+                "master",
+                "feature",
+                false,
+            )
+            .unwrap();
 
-            let de_result =
-                gitbutler_merge_commits(&test_repository.repository, d, e, "master", "feature")
-                    .unwrap();
+            let de_result = gitbutler_merge_commits(
+                &test_repository.repository,
+                d,
+                e,
+                "master",
+                "feature",
+                false,
+            )
+            .unwrap();
 
             let result = gitbutler_merge_commits(
                 &test_repository.repository,
@@ -805,6 +1043,7 @@ mod test {
                 de_result,
                 "master",
                 "feature",
+                false,
             )
             .unwrap();
 
@@ -1,11 +1,8 @@
-#[cfg(unix)]
-use std::os::unix::fs::PermissionsExt;
-#[cfg(windows)]
-use std::os::windows::process::CommandExt;
-use std::{io::Write, path::Path, process::Stdio, str};
+use std::{path::Path, str};
 
 use crate::Config;
 use crate::SignaturePurpose;
+use crate::SignatureVerification;
 use anyhow::{anyhow, bail, Context, Result};
 use bstr::BString;
 use git2::{BlameOptions, StatusOptions, Tree};
@@ -64,8 +61,10 @@ pub trait RepositoryExt {
     /// Fetches the workspace commit from the gitbutler/workspace branch
     fn workspace_commit(&self) -> Result<git2::Commit<'_>>;
     /// `buffer` is the commit object to sign, but in theory could be anything to compute the signature for.
-    /// Returns the computed signature.
+    /// Returns the computed signature, made with the key and format configured in Git, see `signing::sign_buffer()`.
     fn sign_buffer(&self, buffer: &[u8]) -> Result<BString>;
+    /// Verify the signature of the commit with `commit_id` with the keys trusted in Git, or return `None` if it isn't signed.
+    fn verify_signature(&self, commit_id: git2::Oid) -> Result<Option<SignatureVerification>>;
 
     fn checkout_index_builder<'a>(&'a self, index: &'a mut git2::Index)
         -> CheckoutIndexBuilder<'a>;
@@ -94,6 +93,17 @@ pub trait RepositoryExt {
         commit_headers: Option<CommitHeadersV2>,
     ) -> Result<git2::Oid>;
 
+    /// Like [`Self::commit_with_signature()`], but for the commits GitButler creates for its own references,
+    /// like the workspace commit, which are signed according to `gitbutler.signWorkspaceCommits`.
+    fn commit_for_gitbutler_ref(
+        &self,
+        author: &git2::Signature<'_>,
+        committer: &git2::Signature<'_>,
+        message: &str,
+        tree: &git2::Tree<'_>,
+        parents: &[&git2::Commit<'_>],
+    ) -> Result<git2::Oid>;
+
     fn blame(
         &self,
         path: &Path,
@@ -287,42 +297,16 @@ impl RepositoryExt for git2::Repository {
         parents: &[&git2::Commit<'_>],
         commit_headers: Option<CommitHeadersV2>,
     ) -> Result<git2::Oid> {
-        let repo = gix::open(self.path())?;
-        let mut commit = gix::objs::Commit {
-            message: message.into(),
-            tree: git2_to_gix_object_id(tree.id()),
-            author: git2_signature_to_gix_signature(author),
-            committer: git2_signature_to_gix_signature(committer),
-            encoding: None,
-            parents: parents
-                .iter()
-                .map(|commit| git2_to_gix_object_id(commit.id()))
-                .collect(),
-            extra_headers: commit_headers.unwrap_or_default().into(),
-        };
-
-        if self.gb_config()?.sign_commits.unwrap_or(false) {
-            let mut buf = Vec::new();
-            commit.write_to(&mut buf)?;
-            let signature = self.sign_buffer(&buf);
-            match signature {
-                Ok(signature) => {
-                    commit.extra_headers.push(("gpgsig".into(), signature));
-                }
-                Err(e) => {
-                    // If signing fails, set the "gitbutler.signCommits" config to false before erroring out
-                    self.set_gb_config(GbConfig {
-                        sign_commits: Some(false),
-                        ..GbConfig::default()
-                    })?;
-                    return Err(
-                        anyhow!("Failed to sign commit: {}", e).context(Code::CommitSigningFailed)
-                    );
-                }
-            }
-        }
-        // TODO: extra-headers should be supported in `gix` directly.
-        let oid = gix_to_git2_oid(repo.write_object(&commit)?);
+        let oid = write_commit(
+            self,
+            author,
+            committer,
+            message,
+            tree,
+            parents,
+            commit_headers.unwrap_or_default().into(),
+            CommitKind::User,
+        )?;
 
         // update reference
         if let Some(refname) = update_ref {
@@ -331,6 +315,26 @@ impl RepositoryExt for git2::Repository {
         Ok(oid)
     }
 
+    fn commit_for_gitbutler_ref(
+        &self,
+        author: &git2::Signature<'_>,
+        committer: &git2::Signature<'_>,
+        message: &str,
+        tree: &git2::Tree<'_>,
+        parents: &[&git2::Commit<'_>],
+    ) -> Result<git2::Oid> {
+        write_commit(
+            self,
+            author,
+            committer,
+            message,
+            tree,
+            parents,
+            Vec::new(),
+            CommitKind::GitButlerRef,
+        )
+    }
+
     fn blame(
         &self,
         path: &Path,
@@ -349,131 +353,11 @@ impl RepositoryExt for git2::Repository {
     }
 
     fn sign_buffer(&self, buffer: &[u8]) -> Result<BString> {
-        // check git config for gpg.signingkey
-        // TODO: support gpg.ssh.defaultKeyCommand to get the signing key if this value doesn't exist
-        let signing_key = self.config()?.get_string("user.signingkey");
-        if let Ok(signing_key) = signing_key {
-            let sign_format = self.config()?.get_string("gpg.format");
-            let is_ssh = if let Ok(sign_format) = sign_format {
-                sign_format == "ssh"
-            } else {
-                false
-            };
-
-            if is_ssh {
-                // write commit data to a temp file so we can sign it
-                let mut signature_storage = tempfile::NamedTempFile::new()?;
-                signature_storage.write_all(buffer)?;
-                let buffer_file_to_sign_path = signature_storage.into_temp_path();
-
-                let gpg_program = self.config()?.get_string("gpg.ssh.program");
-                let mut gpg_program = gpg_program.unwrap_or("ssh-keygen".to_string());
-                // if cmd is "", use gpg
-                if gpg_program.is_empty() {
-                    gpg_program = "ssh-keygen".to_string();
-                }
-
-                let mut cmd = std::process::Command::new(gpg_program);
-                cmd.args(["-Y", "sign", "-n", "git", "-f"]);
-
-                #[cfg(windows)]
-                cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-
-                let output;
-                // support literal ssh key
-                if let (true, signing_key) = is_literal_ssh_key(&signing_key) {
-                    // write the key to a temp file
-                    let mut key_storage = tempfile::NamedTempFile::new()?;
-                    key_storage.write_all(signing_key.as_bytes())?;
-
-                    // if on unix
-                    #[cfg(unix)]
-                    {
-                        // make sure the tempfile permissions are acceptable for a private ssh key
-                        let mut permissions = key_storage.as_file().metadata()?.permissions();
-                        permissions.set_mode(0o600);
-                        key_storage.as_file().set_permissions(permissions)?;
-                    }
-
-                    let key_file_path = key_storage.into_temp_path();
-
-                    cmd.arg(&key_file_path);
-                    cmd.arg("-U");
-                    cmd.arg(&buffer_file_to_sign_path);
-                    cmd.stderr(Stdio::piped());
-                    cmd.stdout(Stdio::piped());
-                    cmd.stdin(Stdio::null());
-
-                    let child = cmd.spawn()?;
-                    output = child.wait_with_output()?;
-                } else {
-                    cmd.arg(signing_key);
-                    cmd.arg(&buffer_file_to_sign_path);
-                    cmd.stderr(Stdio::piped());
-                    cmd.stdout(Stdio::piped());
-                    cmd.stdin(Stdio::null());
-
-                    let child = cmd.spawn()?;
-                    output = child.wait_with_output()?;
-                }
+        crate::signing::sign_buffer(self, buffer)
+    }
 
-                if output.status.success() {
-                    // read signed_storage path plus .sig
-                    let signature_path = buffer_file_to_sign_path.with_extension("sig");
-                    let sig_data = std::fs::read(signature_path)?;
-                    let signature = BString::new(sig_data);
-                    return Ok(signature);
-                } else {
-                    let stderr = BString::new(output.stderr);
-                    let stdout = BString::new(output.stdout);
-                    let std_both = format!("{} {}", stdout, stderr);
-                    bail!("Failed to sign SSH: {}", std_both);
-                }
-            } else {
-                let gpg_program = self
-                    .config()?
-                    .get_path("gpg.program")
-                    .ok()
-                    .filter(|gpg| !gpg.as_os_str().is_empty())
-                    .unwrap_or_else(|| "gpg".into());
-
-                let mut cmd = std::process::Command::new(&gpg_program);
-
-                cmd.args(["--status-fd=2", "-bsau", &signing_key])
-                    .arg("-")
-                    .stdout(Stdio::piped())
-                    .stderr(Stdio::piped())
-                    .stdin(Stdio::piped());
-
-                #[cfg(windows)]
-                cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-
-                let mut child = match cmd.spawn() {
-                    Ok(child) => child,
-                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
-                        bail!("Could not find '{}'. Please make sure it is in your `PATH` or configure the full path using `gpg.program` in the Git configuration", gpg_program.display())
-                    }
-                    Err(err) => {
-                        return Err(err)
-                            .context(format!("Could not execute GPG program using {:?}", cmd))
-                    }
-                };
-                child.stdin.take().expect("configured").write_all(buffer)?;
-
-                let output = child.wait_with_output()?;
-                if output.status.success() {
-                    // read stdout
-                    let signature = BString::new(output.stdout);
-                    return Ok(signature);
-                } else {
-                    let stderr = BString::new(output.stderr);
-                    let stdout = BString::new(output.stdout);
-                    let std_both = format!("{} {}", stdout, stderr);
-                    bail!("Failed to sign GPG: {}", std_both);
-                }
-            }
-        }
-        Err(anyhow::anyhow!("No signing key found"))
+    fn verify_signature(&self, commit_id: git2::Oid) -> Result<Option<SignatureVerification>> {
+        crate::signing::verify_commit_signature(self, commit_id)
     }
 
     fn remotes_as_string(&self) -> Result<Vec<String>> {
@@ -631,15 +515,73 @@ impl RepositoryExt for git2::Repository {
     }
 }
 
-/// Signs the buffer with the configured gpg key, returning the signature.
-pub fn is_literal_ssh_key(string: &str) -> (bool, &str) {
-    if let Some(key) = string.strip_prefix("key::") {
-        return (true, key);
-    }
-    if string.starts_with("ssh-") {
-        return (true, string);
+/// Which commits are created, which decides whether they are signed.
+#[derive(Debug, Clone, Copy)]
+enum CommitKind {
+    /// Commits of the user, signed according to `gitbutler.signCommits`.
+    User,
+    /// Commits GitButler creates for its own references, signed according to `gitbutler.signWorkspaceCommits`.
+    GitButlerRef,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_commit(
+    repo: &git2::Repository,
+    author: &git2::Signature<'_>,
+    committer: &git2::Signature<'_>,
+    message: &str,
+    tree: &git2::Tree<'_>,
+    parents: &[&git2::Commit<'_>],
+    extra_headers: Vec<(BString, BString)>,
+    kind: CommitKind,
+) -> Result<git2::Oid> {
+    let gix_repo = gix::open(repo.path())?;
+    let mut commit = gix::objs::Commit {
+        message: message.into(),
+        tree: git2_to_gix_object_id(tree.id()),
+        author: git2_signature_to_gix_signature(author),
+        committer: git2_signature_to_gix_signature(committer),
+        encoding: None,
+        parents: parents
+            .iter()
+            .map(|commit| git2_to_gix_object_id(commit.id()))
+            .collect(),
+        extra_headers,
+    };
+
+    let config = repo.gb_config()?;
+    let sign = match kind {
+        CommitKind::User => config.sign_commits.unwrap_or(false),
+        CommitKind::GitButlerRef => config.signs_workspace_commits(),
+    };
+    if sign {
+        let mut buf = Vec::new();
+        commit.write_to(&mut buf)?;
+        let signature = repo.sign_buffer(&buf);
+        match signature {
+            Ok(signature) => {
+                commit.extra_headers.push(("gpgsig".into(), signature));
+            }
+            Err(e) => {
+                // If signing fails, turn off signing of this kind of commits before erroring out
+                repo.set_gb_config(match kind {
+                    CommitKind::User => GbConfig {
+                        sign_commits: Some(false),
+                        ..GbConfig::default()
+                    },
+                    CommitKind::GitButlerRef => GbConfig {
+                        sign_workspace_commits: Some(false),
+                        ..GbConfig::default()
+                    },
+                })?;
+                return Err(
+                    anyhow!("Failed to sign commit: {}", e).context(Code::CommitSigningFailed)
+                );
+            }
+        }
     }
-    (false, string)
+    // TODO: extra-headers should be supported in `gix` directly.
+    Ok(gix_to_git2_oid(gix_repo.write_object(&commit)?))
 }
 
 pub struct CheckoutTreeBuidler<'a> {
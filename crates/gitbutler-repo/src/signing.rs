@@ -0,0 +1,730 @@
+//! Signing of commits with the key and program configured in Git, the way `git commit -S` does it,
+//! and verifying their signatures like `git verify-commit`.
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use anyhow::{bail, Context, Result};
+use bstr::{BString, ByteSlice};
+use serde::Serialize;
+
+/// The outcome of verifying the signature of a commit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureVerification {
+    pub status: SignatureStatus,
+    /// Who made the signature: the principal of the key in `gpg.ssh.allowedSignersFile`, or the
+    /// user ID of the GPG key. If the key isn't known, this is its fingerprint or ID instead.
+    pub signer: Option<String>,
+}
+
+/// Whether the signature of a commit can be trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SignatureStatus {
+    /// The signature is valid, and was made with a key that is known and trusted.
+    Verified,
+    /// The signature is invalid, or was made with a key that expired, was revoked or isn't allowed
+    /// for the signer.
+    Unverified,
+    /// The signature is valid, but was made with a key that isn't known, so the signer can't be told.
+    UnknownKey,
+}
+
+/// The verifications of signatures by repository and commit, as they only change once the
+/// [verifier](Verifier) does.
+static VERIFICATIONS: Mutex<Verifications> = Mutex::new(Verifications {
+    entries: BTreeMap::new(),
+    order: VecDeque::new(),
+});
+
+/// How many verifications are cached before the oldest ones are dropped.
+const MAX_VERIFICATIONS: usize = 10_000;
+
+/// The files GnuPG keeps its keys and their trust in, relative to its home directory.
+const GPG_KEY_FILES: &[&str] = &["pubring.kbx", "pubring.gpg", "trustdb.gpg"];
+
+type VerificationKey = (PathBuf, git2::Oid);
+
+struct Verifications {
+    entries: BTreeMap<VerificationKey, (Verifier, Option<SignatureVerification>)>,
+    /// The keys of `entries`, oldest first.
+    order: VecDeque<VerificationKey>,
+}
+
+impl Verifications {
+    fn get(
+        &self,
+        key: &VerificationKey,
+        verifier: &Verifier,
+    ) -> Option<Option<SignatureVerification>> {
+        self.entries
+            .get(key)
+            .filter(|(cached_by, _)| cached_by == verifier)
+            .map(|(_, verification)| verification.clone())
+    }
+
+    fn insert(
+        &mut self,
+        key: VerificationKey,
+        verifier: Verifier,
+        verification: Option<SignatureVerification>,
+    ) {
+        if self
+            .entries
+            .insert(key.clone(), (verifier, verification))
+            .is_none()
+        {
+            self.order.push_back(key);
+        }
+        while self.order.len() > MAX_VERIFICATIONS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// What the outcome of verifying a signature depends on besides the commit: the configured
+/// programs and files, and when the files with the keys that are trusted last changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Verifier {
+    config: Vec<Option<String>>,
+    modified: Vec<Option<SystemTime>>,
+}
+
+impl Verifier {
+    fn from_config(config: &git2::Config) -> Result<Self> {
+        let config_values = [
+            "gpg.program",
+            "gpg.openpgp.program",
+            "gpg.x509.program",
+            "gpg.ssh.program",
+            "gpg.ssh.allowedSignersFile",
+            "gpg.ssh.revocationFile",
+        ]
+        .iter()
+        .map(|key| get_string(config, key))
+        .collect::<Result<_>>()?;
+
+        let mut key_files = Vec::new();
+        for key in ["gpg.ssh.allowedSignersFile", "gpg.ssh.revocationFile"] {
+            key_files.extend(get_path(config, key)?);
+        }
+        let gpg_home = std::env::var_os("GNUPGHOME")
+            .map(PathBuf::from)
+            .or_else(|| gix::path::env::home_dir().map(|home| home.join(".gnupg")));
+        if let Some(gpg_home) = gpg_home {
+            key_files.extend(GPG_KEY_FILES.iter().map(|file| gpg_home.join(file)));
+        }
+        let modified = key_files
+            .iter()
+            .map(|path| {
+                std::fs::metadata(path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+            })
+            .collect();
+
+        Ok(Verifier {
+            config: config_values,
+            modified,
+        })
+    }
+}
+
+/// The kind of signature to create, as configured with `gpg.format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SigningFormat {
+    OpenPgp,
+    X509,
+    Ssh,
+}
+
+impl SigningFormat {
+    fn from_config(config: &git2::Config) -> Result<Self> {
+        let Some(format) = get_string(config, "gpg.format")? else {
+            return Ok(SigningFormat::OpenPgp);
+        };
+        Ok(match format.as_str() {
+            "" | "openpgp" => SigningFormat::OpenPgp,
+            "x509" => SigningFormat::X509,
+            "ssh" => SigningFormat::Ssh,
+            other => bail!("Unsupported signing format in `gpg.format`: '{other}'"),
+        })
+    }
+
+    /// The program to sign with, `gpg.openpgp.program` falling back to `gpg.program` like in Git.
+    fn program(&self, config: &git2::Config) -> Result<PathBuf> {
+        let (keys, default): (&[&str], _) = match self {
+            SigningFormat::OpenPgp => (&["gpg.openpgp.program", "gpg.program"], "gpg"),
+            SigningFormat::X509 => (&["gpg.x509.program"], "gpgsm"),
+            SigningFormat::Ssh => (&["gpg.ssh.program"], "ssh-keygen"),
+        };
+        for key in keys {
+            if let Some(program) =
+                get_path(config, key)?.filter(|program| !program.as_os_str().is_empty())
+            {
+                return Ok(program);
+            }
+        }
+        Ok(default.into())
+    }
+
+    fn program_config_key(&self) -> &'static str {
+        match self {
+            SigningFormat::OpenPgp => "gpg.program",
+            SigningFormat::X509 => "gpg.x509.program",
+            SigningFormat::Ssh => "gpg.ssh.program",
+        }
+    }
+}
+
+/// The key to sign with, as configured with `user.signingKey`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SigningKey {
+    /// A public SSH key, whose private half is held by `ssh-agent`.
+    Literal(String),
+    /// The path to a private SSH key, or to a public SSH key whose private half is held by `ssh-agent`.
+    Path(PathBuf),
+    /// The ID of a GPG key, or the identity of the committer to find it by.
+    Id(String),
+}
+
+impl SigningKey {
+    fn from_config(config: &git2::Config, format: SigningFormat) -> Result<Self> {
+        let key = get_string(config, "user.signingkey")?.filter(|key| !key.is_empty());
+        match (format, key) {
+            (SigningFormat::Ssh, Some(key)) => Ok(match is_literal_ssh_key(&key) {
+                (true, key) => SigningKey::Literal(key.to_owned()),
+                // Read it again, now as path, to have `~/` expanded.
+                (false, _) => SigningKey::Path(config.get_path("user.signingkey")?),
+            }),
+            (SigningFormat::Ssh, None) => {
+                let Some(command) = get_string(config, "gpg.ssh.defaultKeyCommand")? else {
+                    bail!(
+                        "No signing key found in `user.signingKey` or `gpg.ssh.defaultKeyCommand`"
+                    );
+                };
+                default_ssh_key(&command)
+            }
+            (_, Some(key)) => Ok(SigningKey::Id(key)),
+            // Like Git, let GPG find the key by the identity of the committer.
+            (_, None) => {
+                let name = get_string(config, "user.name")?;
+                let email = get_string(config, "user.email")?;
+                match (name, email) {
+                    (Some(name), Some(email)) => Ok(SigningKey::Id(format!("{name} <{email}>"))),
+                    _ => bail!("No signing key found"),
+                }
+            }
+        }
+    }
+}
+
+/// Sign `buffer` according to `gpg.format`, with the key of `user.signingKey` or the one
+/// `gpg.ssh.defaultKeyCommand` provides, and return the signature.
+pub(crate) fn sign_buffer(repo: &git2::Repository, buffer: &[u8]) -> Result<BString> {
+    let config = repo.config()?;
+    let format = SigningFormat::from_config(&config)?;
+    let key = SigningKey::from_config(&config, format)?;
+    let program = format.program(&config)?;
+    match format {
+        SigningFormat::Ssh => sign_ssh(&program, &key, buffer),
+        SigningFormat::OpenPgp | SigningFormat::X509 => sign_gpg(&program, format, &key, buffer),
+    }
+}
+
+fn sign_ssh(program: &Path, key: &SigningKey, buffer: &[u8]) -> Result<BString> {
+    // write commit data to a temp file so we can sign it
+    let mut signature_storage = tempfile::NamedTempFile::new()?;
+    signature_storage.write_all(buffer)?;
+    let buffer_file_to_sign_path = signature_storage.into_temp_path();
+
+    let mut cmd = Command::new(program);
+    cmd.args(["-Y", "sign", "-n", "git", "-f"]);
+
+    // Keep the file with the literal key alive until the program is done.
+    let _key_file_path = match key {
+        SigningKey::Literal(key) => {
+            let mut key_storage = tempfile::NamedTempFile::new()?;
+            key_storage.write_all(key.as_bytes())?;
+            #[cfg(unix)]
+            {
+                // make sure the tempfile permissions are acceptable for an ssh key
+                let mut permissions = key_storage.as_file().metadata()?.permissions();
+                permissions.set_mode(0o600);
+                key_storage.as_file().set_permissions(permissions)?;
+            }
+            let key_file_path = key_storage.into_temp_path();
+            cmd.arg(&key_file_path);
+            // the private key is in the agent
+            cmd.arg("-U");
+            Some(key_file_path)
+        }
+        SigningKey::Path(path) => {
+            cmd.arg(path);
+            None
+        }
+        SigningKey::Id(key) => {
+            bail!("SSH signing needs a literal key or the path to one, not the key ID '{key}'")
+        }
+    };
+    cmd.arg(&buffer_file_to_sign_path);
+
+    let output = run(cmd, SigningFormat::Ssh, program, None)?;
+    if !output.status.success() {
+        bail!(
+            "Failed to sign SSH: {} {}",
+            output.stdout.as_bstr(),
+            output.stderr.as_bstr()
+        );
+    }
+    let signature_path = buffer_file_to_sign_path.with_extension("sig");
+    let signature = std::fs::read(&signature_path)
+        .with_context(|| format!("Could not read signature from {}", signature_path.display()))?;
+    std::fs::remove_file(&signature_path).ok();
+    Ok(signature.into())
+}
+
+fn sign_gpg(
+    program: &Path,
+    format: SigningFormat,
+    key: &SigningKey,
+    buffer: &[u8],
+) -> Result<BString> {
+    let SigningKey::Id(key) = key else {
+        bail!("GPG signing needs the ID of a key, not an SSH key");
+    };
+    let mut cmd = Command::new(program);
+    cmd.args(["--status-fd=2", "-bsau", key.as_str()]).arg("-");
+
+    let output = run(cmd, format, program, Some(buffer))?;
+    if !output.status.success() {
+        bail!(
+            "Failed to sign GPG: {} {}",
+            output.stdout.as_bstr(),
+            output.stderr.as_bstr()
+        );
+    }
+    Ok(output.stdout.into())
+}
+
+/// Run `cmd` with `stdin` as its input, and return its output.
+fn run(
+    mut cmd: Command,
+    format: SigningFormat,
+    program: &Path,
+    stdin: Option<&[u8]>,
+) -> Result<std::process::Output> {
+    cmd.stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        });
+
+    #[cfg(windows)]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            bail!(
+                "Could not find '{}'. Please make sure it is in your `PATH` or configure the full path using `{}` in the Git configuration",
+                program.display(),
+                format.program_config_key()
+            )
+        }
+        Err(err) => {
+            return Err(err).context(format!("Could not execute signing program using {:?}", cmd))
+        }
+    };
+    if let Some(stdin) = stdin {
+        child.stdin.take().expect("configured").write_all(stdin)?;
+    }
+    Ok(child.wait_with_output()?)
+}
+
+/// Run `gpg.ssh.defaultKeyCommand` like Git does, and use the first line of its output that is a
+/// literal SSH key.
+fn default_ssh_key(command: &str) -> Result<SigningKey> {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    };
+    cmd.stdin(Stdio::null());
+    #[cfg(windows)]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let output = cmd
+        .output()
+        .with_context(|| format!("Could not run `gpg.ssh.defaultKeyCommand` '{command}'"))?;
+    if !output.status.success() {
+        bail!(
+            "`gpg.ssh.defaultKeyCommand` '{command}' failed: {}",
+            output.stderr.as_bstr()
+        );
+    }
+    output
+        .stdout
+        .lines()
+        .filter_map(|line| line.to_str().ok())
+        .find_map(|line| match is_literal_ssh_key(line.trim()) {
+            (true, key) => Some(SigningKey::Literal(key.to_owned())),
+            (false, _) => None,
+        })
+        .with_context(|| format!("`gpg.ssh.defaultKeyCommand` '{command}' didn't output a key"))
+}
+
+/// Verify the signature of the commit with `commit_id` with the GPG keyring, or with
+/// `gpg.ssh.allowedSignersFile` for SSH signatures, and return `None` if it isn't signed.
+pub(crate) fn verify_commit_signature(
+    repo: &git2::Repository,
+    commit_id: git2::Oid,
+) -> Result<Option<SignatureVerification>> {
+    let config = repo.config()?;
+    let verifier = Verifier::from_config(&config)?;
+    let key = (repo.path().to_owned(), commit_id);
+    if let Some(verification) = VERIFICATIONS
+        .lock()
+        .expect("not poisoned")
+        .get(&key, &verifier)
+    {
+        return Ok(verification);
+    }
+
+    let verification = match repo.extract_signature(&commit_id, None) {
+        Ok((signature, signed_data)) => {
+            Some(if signature.starts_with(b"-----BEGIN SSH SIGNATURE-----") {
+                verify_ssh(&config, &signature, &signed_data)?
+            } else {
+                let format = if signature.starts_with(b"-----BEGIN SIGNED MESSAGE-----") {
+                    SigningFormat::X509
+                } else {
+                    SigningFormat::OpenPgp
+                };
+                verify_gpg(&config, format, &signature, &signed_data)?
+            })
+        }
+        Err(err) if err.code() == git2::ErrorCode::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+    VERIFICATIONS
+        .lock()
+        .expect("not poisoned")
+        .insert(key, verifier, verification.clone());
+    Ok(verification)
+}
+
+fn verify_ssh(
+    config: &git2::Config,
+    signature: &[u8],
+    signed_data: &[u8],
+) -> Result<SignatureVerification> {
+    let program = SigningFormat::Ssh.program(config)?;
+    let mut signature_storage = tempfile::NamedTempFile::new()?;
+    signature_storage.write_all(signature)?;
+    let signature_path = signature_storage.into_temp_path();
+
+    if let Some(allowed_signers) = get_path(config, "gpg.ssh.allowedSignersFile")? {
+        let mut cmd = Command::new(&program);
+        cmd.args(["-Y", "find-principals", "-f"])
+            .arg(&allowed_signers)
+            .arg("-s")
+            .arg(&signature_path);
+        let output = run(cmd, SigningFormat::Ssh, &program, None)?;
+        let principals = if output.status.success() {
+            output
+                .stdout
+                .lines()
+                .filter_map(|line| line.to_str().ok())
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(ToOwned::to_owned)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let revocations = get_path(config, "gpg.ssh.revocationFile")?;
+        for principal in &principals {
+            let mut cmd = Command::new(&program);
+            cmd.args(["-Y", "verify", "-n", "git", "-f"])
+                .arg(&allowed_signers)
+                .arg("-I")
+                .arg(principal)
+                .arg("-s")
+                .arg(&signature_path);
+            if let Some(revocations) = &revocations {
+                cmd.arg("-r").arg(revocations);
+            }
+            if run(cmd, SigningFormat::Ssh, &program, Some(signed_data))?
+                .status
+                .success()
+            {
+                return Ok(SignatureVerification {
+                    status: SignatureStatus::Verified,
+                    signer: Some(principal.clone()),
+                });
+            }
+        }
+        if let Some(principal) = principals.into_iter().next() {
+            return Ok(SignatureVerification {
+                status: SignatureStatus::Unverified,
+                signer: Some(principal),
+            });
+        }
+    }
+
+    // The key isn't allowed for anyone, so only check if the signature itself is valid.
+    let mut cmd = Command::new(&program);
+    cmd.args(["-Y", "check-novalidate", "-n", "git", "-s"])
+        .arg(&signature_path);
+    let output = run(cmd, SigningFormat::Ssh, &program, Some(signed_data))?;
+    Ok(if output.status.success() {
+        SignatureVerification {
+            status: SignatureStatus::UnknownKey,
+            signer: ssh_key_fingerprint(&output.stdout)
+                .or_else(|| ssh_key_fingerprint(&output.stderr)),
+        }
+    } else {
+        SignatureVerification {
+            status: SignatureStatus::Unverified,
+            signer: None,
+        }
+    })
+}
+
+/// Parse the fingerprint from `Good "git" signature with ED25519 key SHA256:…`.
+fn ssh_key_fingerprint(output: &[u8]) -> Option<String> {
+    output.lines().find_map(|line| {
+        let (_, fingerprint) = line.to_str().ok()?.split_once(" key ")?;
+        Some(fingerprint.split_whitespace().next()?.to_owned())
+    })
+}
+
+fn verify_gpg(
+    config: &git2::Config,
+    format: SigningFormat,
+    signature: &[u8],
+    signed_data: &[u8],
+) -> Result<SignatureVerification> {
+    let program = format.program(config)?;
+    let mut signature_storage = tempfile::NamedTempFile::new()?;
+    signature_storage.write_all(signature)?;
+    let signature_path = signature_storage.into_temp_path();
+
+    let mut cmd = Command::new(&program);
+    cmd.args(["--keyid-format=long", "--status-fd=1", "--verify"])
+        .arg(&signature_path)
+        .arg("-");
+    let output = run(cmd, format, &program, Some(signed_data))?;
+    match parse_gpg_status(&output.stdout) {
+        Some(verification) => Ok(verification),
+        None => bail!(
+            "Failed to verify GPG signature: {} {}",
+            output.stdout.as_bstr(),
+            output.stderr.as_bstr()
+        ),
+    }
+}
+
+/// Parse the machine-readable status lines GPG writes with `--status-fd`.
+fn parse_gpg_status(output: &[u8]) -> Option<SignatureVerification> {
+    let mut verification = None;
+    for line in output.lines() {
+        let Some(line) = line.strip_prefix(b"[GNUPG:] ") else {
+            continue;
+        };
+        let line = line.to_str_lossy();
+        let mut fields = line.splitn(3, ' ');
+        let (Some(keyword), key_id, user_id) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let user_id = user_id.map(ToOwned::to_owned);
+        let status = match keyword {
+            "GOODSIG" => SignatureStatus::Verified,
+            "BADSIG" | "EXPSIG" | "EXPKEYSIG" | "REVKEYSIG" => SignatureStatus::Unverified,
+            "NO_PUBKEY" => {
+                verification = Some(SignatureVerification {
+                    status: SignatureStatus::UnknownKey,
+                    signer: key_id.map(ToOwned::to_owned),
+                });
+                continue;
+            }
+            // A good signature made with a key that is explicitly not trusted.
+            "TRUST_NEVER" => {
+                if let Some(verification) = &mut verification {
+                    verification.status = SignatureStatus::Unverified;
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        verification = Some(SignatureVerification {
+            status,
+            signer: user_id,
+        });
+    }
+    verification
+}
+
+/// Return `true` and the key if `string` is a literal SSH key instead of a path to one.
+fn is_literal_ssh_key(string: &str) -> (bool, &str) {
+    if let Some(key) = string.strip_prefix("key::") {
+        return (true, key);
+    }
+    if string.starts_with("ssh-") {
+        return (true, string);
+    }
+    (false, string)
+}
+
+fn get_string(config: &git2::Config, key: &str) -> Result<Option<String>> {
+    match config.get_string(key) {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn get_path(config: &git2::Config, key: &str) -> Result<Option<PathBuf>> {
+    match config.get_path(key) {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with(entries: &[(&str, &str)]) -> (tempfile::TempDir, git2::Config) {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = git2::Config::open(&dir.path().join("config")).unwrap();
+        for (key, value) in entries {
+            config.set_str(key, value).unwrap();
+        }
+        (dir, config)
+    }
+
+    #[test]
+    fn format_defaults_to_openpgp() {
+        let (_dir, config) = config_with(&[]);
+        assert_eq!(
+            SigningFormat::from_config(&config).unwrap(),
+            SigningFormat::OpenPgp
+        );
+        let (_dir, config) = config_with(&[("gpg.format", "ssh")]);
+        assert_eq!(
+            SigningFormat::from_config(&config).unwrap(),
+            SigningFormat::Ssh
+        );
+        let (_dir, config) = config_with(&[("gpg.format", "pgp")]);
+        assert!(SigningFormat::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn ssh_keys_are_literal_or_paths() {
+        let (_dir, config) = config_with(&[("user.signingkey", "key::ssh-ed25519 AAAA")]);
+        assert_eq!(
+            SigningKey::from_config(&config, SigningFormat::Ssh).unwrap(),
+            SigningKey::Literal("ssh-ed25519 AAAA".into())
+        );
+        let (_dir, config) = config_with(&[("user.signingkey", "/keys/id_ed25519.pub")]);
+        assert_eq!(
+            SigningKey::from_config(&config, SigningFormat::Ssh).unwrap(),
+            SigningKey::Path("/keys/id_ed25519.pub".into())
+        );
+        assert_eq!(
+            SigningKey::from_config(&config, SigningFormat::OpenPgp).unwrap(),
+            SigningKey::Id("/keys/id_ed25519.pub".into()),
+            "other formats pass the key on as is"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn ssh_key_from_default_key_command() {
+        let (_dir, config) = config_with(&[(
+            "gpg.ssh.defaultKeyCommand",
+            "echo not a key; echo 'ssh-ed25519 AAAA comment'",
+        )]);
+        assert_eq!(
+            SigningKey::from_config(&config, SigningFormat::Ssh).unwrap(),
+            SigningKey::Literal("ssh-ed25519 AAAA comment".into())
+        );
+        let (_dir, config) = config_with(&[]);
+        assert!(SigningKey::from_config(&config, SigningFormat::Ssh).is_err());
+    }
+
+    #[test]
+    fn gpg_status_is_parsed() {
+        let status = b"[GNUPG:] NEWSIG\n[GNUPG:] GOODSIG 0123456789ABCDEF Author <author@example.com>\n[GNUPG:] VALIDSIG 0123\n[GNUPG:] TRUST_ULTIMATE 0 pgp\n";
+        assert_eq!(
+            parse_gpg_status(status),
+            Some(SignatureVerification {
+                status: SignatureStatus::Verified,
+                signer: Some("Author <author@example.com>".into()),
+            })
+        );
+
+        let status = b"[GNUPG:] GOODSIG 0123456789ABCDEF Author <author@example.com>\n[GNUPG:] TRUST_NEVER 0 pgp\n";
+        assert_eq!(
+            parse_gpg_status(status).unwrap().status,
+            SignatureStatus::Unverified
+        );
+
+        let status = b"[GNUPG:] ERRSIG 0123456789ABCDEF 1 10 00 1700000000 9 -\n[GNUPG:] NO_PUBKEY 0123456789ABCDEF\n";
+        assert_eq!(
+            parse_gpg_status(status),
+            Some(SignatureVerification {
+                status: SignatureStatus::UnknownKey,
+                signer: Some("0123456789ABCDEF".into()),
+            })
+        );
+        assert_eq!(
+            parse_gpg_status(b"gpg: no valid OpenPGP data found.\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn ssh_key_fingerprint_is_parsed() {
+        assert_eq!(
+            ssh_key_fingerprint(b"Good \"git\" signature with ED25519 key SHA256:abc/def+123\n")
+                .as_deref(),
+            Some("SHA256:abc/def+123")
+        );
+        assert_eq!(ssh_key_fingerprint(b"Could not verify signature.\n"), None);
+    }
+
+    #[test]
+    fn gpg_key_defaults_to_committer_identity() {
+        let (_dir, config) = config_with(&[
+            ("user.name", "Author"),
+            ("user.email", "author@example.com"),
+        ]);
+        assert_eq!(
+            SigningKey::from_config(&config, SigningFormat::OpenPgp).unwrap(),
+            SigningKey::Id("Author <author@example.com>".into())
+        );
+    }
+}
@@ -2,7 +2,7 @@ use std::{path::PathBuf, str};
 
 use gitbutler_command_context::CommandContext;
 use gitbutler_project as projects;
-use gitbutler_repo::credentials::{help, Credential, SshCredential};
+use gitbutler_repo::credentials::{help, help_read_only, Credential, SshCredential};
 use gitbutler_testsupport::{temp_dir, test_repository};
 use gitbutler_user as users;
 
@@ -11,6 +11,7 @@ struct TestCase<'a> {
     remote_url: &'a str,
     with_github_login: bool,
     preferred_key: projects::AuthKey,
+    read_only: bool,
 }
 
 impl TestCase<'_> {
@@ -36,7 +37,11 @@ impl TestCase<'_> {
         };
         let ctx = CommandContext::open(&project).unwrap();
 
-        let flow = help(&ctx, "origin").unwrap();
+        let flow = if self.read_only {
+            help_read_only(&ctx, "origin").unwrap()
+        } else {
+            help(&ctx, "origin").unwrap()
+        };
         flow.into_iter()
             .map(|(remote, credentials)| (remote.url().as_ref().unwrap().to_string(), credentials))
             .collect::<Vec<_>>()
@@ -57,6 +62,7 @@ mod not_github {
                 preferred_key: projects::AuthKey::Local {
                     private_key_path: PathBuf::from("/tmp/id_rsa"),
                 },
+                ..Default::default()
             };
             let flow = test_case.run();
             assert_eq!(flow.len(), 1);
@@ -81,6 +87,7 @@ mod not_github {
                 preferred_key: projects::AuthKey::Local {
                     private_key_path: PathBuf::from("/tmp/id_rsa"),
                 },
+                ..Default::default()
             };
             let flow = test_case.run();
             assert_eq!(flow.len(), 1);
@@ -116,6 +123,7 @@ mod github {
                     preferred_key: projects::AuthKey::Local {
                         private_key_path: PathBuf::from("/tmp/id_rsa"),
                     },
+                    ..Default::default()
                 };
                 let flow = test_case.run();
                 assert_eq!(flow.len(), 1);
@@ -140,6 +148,7 @@ mod github {
                     preferred_key: projects::AuthKey::Local {
                         private_key_path: PathBuf::from("/tmp/id_rsa"),
                     },
+                    ..Default::default()
                 };
                 let flow = test_case.run();
                 assert_eq!(flow.len(), 1);
@@ -158,3 +167,49 @@ mod github {
         }
     }
 }
+
+mod read_only {
+    use super::*;
+
+    #[test]
+    fn ends_with_anonymous_https() {
+        let test_case = TestCase {
+            remote_url: "git@github.com:gitbutlerapp/gitbutler.git",
+            preferred_key: projects::AuthKey::Local {
+                private_key_path: PathBuf::from("/tmp/id_rsa"),
+            },
+            read_only: true,
+            ..Default::default()
+        };
+        let flow = test_case.run();
+        assert_eq!(flow.len(), 2);
+        assert_eq!(
+            flow[0].0,
+            "git@github.com:gitbutlerapp/gitbutler.git".to_string(),
+        );
+        assert_eq!(
+            flow[1].0,
+            "https://github.com/gitbutlerapp/gitbutler.git".to_string(),
+        );
+        assert_eq!(flow[1].1, vec![Credential::Noop]);
+    }
+
+    #[test]
+    fn without_credentials() {
+        let test_case = TestCase {
+            remote_url: "https://github.com/gitbutlerapp/gitbutler.git",
+            preferred_key: projects::AuthKey::GitCredentialsHelper,
+            read_only: true,
+            ..Default::default()
+        };
+        let flow = test_case.run();
+        assert_eq!(
+            flow.last(),
+            Some(&(
+                "https://github.com/gitbutlerapp/gitbutler.git".to_string(),
+                vec![Credential::Noop]
+            )),
+            "public repositories can be fetched anonymously"
+        );
+    }
+}
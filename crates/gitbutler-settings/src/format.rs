@@ -0,0 +1,229 @@
+//! How timestamps and sizes are presented, so the app, the CLI and anything else reading our
+//! serialized structs show the same values the same way.
+use std::str::FromStr;
+
+use serde::Serialize;
+
+/// Whether clock times use a 12-hour or a 24-hour clock.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeFormat {
+    /// E.g. `2:05 PM`.
+    H12,
+    /// E.g. `14:05`.
+    #[default]
+    H24,
+}
+
+impl FromStr for TimeFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "12h" => TimeFormat::H12,
+            "24h" => TimeFormat::H24,
+            _ => anyhow::bail!("unknown time format '{s}', expected '12h' or '24h'"),
+        })
+    }
+}
+
+/// Which multiples of bytes sizes are shown in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SizeUnits {
+    /// Powers of 1024, e.g. `1.5 KiB`.
+    #[default]
+    Binary,
+    /// Powers of 1000, e.g. `1.5 kB`.
+    Decimal,
+}
+
+impl FromStr for SizeUnits {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "binary" => SizeUnits::Binary,
+            "decimal" => SizeUnits::Decimal,
+            _ => anyhow::bail!("unknown size units '{s}', expected 'binary' or 'decimal'"),
+        })
+    }
+}
+
+/// The formatting preferences of the user.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Formatting {
+    /// A BCP 47 language tag like `de-CH`, which decides the decimal separator.
+    /// If `None`, the conventions of English are used.
+    pub locale: Option<String>,
+    pub time_format: TimeFormat,
+    pub size_units: SizeUnits,
+}
+
+/// A size in bytes along with its formatted form.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Size {
+    pub bytes: u64,
+    /// E.g. `1.5 KiB`.
+    pub formatted: String,
+}
+
+/// A point in time along with its formatted forms.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Timestamp {
+    /// Seconds since the Unix epoch.
+    pub seconds: i64,
+    /// The date and the local clock time, e.g. `2024-05-01 14:05`.
+    pub formatted: String,
+    /// The time relative to when it was formatted, e.g. `5 minutes ago`.
+    pub relative: String,
+}
+
+impl Formatting {
+    /// Read the preferences from the `locale`, `timeFormat` and `sizeUnits` values of `settings.json`,
+    /// as returned by `get_string`. Missing or invalid values fall back to the defaults.
+    pub fn from_settings(get_string: impl Fn(&str) -> Option<String>) -> Self {
+        Formatting {
+            locale: get_string("locale"),
+            time_format: get_string("timeFormat")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_default(),
+            size_units: get_string("sizeUnits")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn size(&self, bytes: u64) -> Size {
+        Size {
+            bytes,
+            formatted: self.format_size(bytes),
+        }
+    }
+
+    /// Format `seconds` since the Unix epoch, with `now_seconds` as reference for the relative time.
+    pub fn timestamp(&self, seconds: i64, now_seconds: i64) -> Timestamp {
+        Timestamp {
+            seconds,
+            formatted: self.format_time(seconds),
+            relative: format_relative_time(seconds, now_seconds),
+        }
+    }
+
+    pub fn format_size(&self, bytes: u64) -> String {
+        let (base, units) = match self.size_units {
+            SizeUnits::Binary => (1024.0, ["KiB", "MiB", "GiB", "TiB"]),
+            SizeUnits::Decimal => (1000.0, ["kB", "MB", "GB", "TB"]),
+        };
+        if (bytes as f64) < base {
+            return format!("{bytes} B");
+        }
+        let mut size = bytes as f64 / base;
+        let mut unit = units[0];
+        for next in &units[1..] {
+            if size < base {
+                break;
+            }
+            size /= base;
+            unit = next;
+        }
+        let size = format!("{size:.1}").replace('.', &self.decimal_separator().to_string());
+        format!("{size} {unit}")
+    }
+
+    /// Format `seconds` since the Unix epoch as date and clock time in the local timezone.
+    pub fn format_time(&self, seconds: i64) -> String {
+        let Some(time) = chrono::DateTime::from_timestamp(seconds, 0) else {
+            return seconds.to_string();
+        };
+        let format = match self.time_format {
+            TimeFormat::H12 => "%Y-%m-%d %-I:%M %p",
+            TimeFormat::H24 => "%Y-%m-%d %H:%M",
+        };
+        time.with_timezone(&chrono::Local)
+            .format(format)
+            .to_string()
+    }
+
+    fn decimal_separator(&self) -> char {
+        // Using the language is enough, as the few regions with different conventions than their
+        // language, like Switzerland, agree on the decimal separator.
+        let language = self
+            .locale
+            .as_deref()
+            .and_then(|locale| locale.split(['-', '_']).next())
+            .unwrap_or("en")
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "de" | "fr" | "es" | "it" | "nl" | "pt" | "ru" | "pl" | "cs" | "sk" | "sv" | "da"
+            | "nb" | "nn" | "no" | "fi" | "tr" | "uk" | "id" | "vi" | "hu" | "ro" | "el" => ',',
+            _ => '.',
+        }
+    }
+}
+
+/// Describe how far `seconds` is from `now_seconds` in words, like `3 hours ago` or `in 2 days`.
+pub fn format_relative_time(seconds: i64, now_seconds: i64) -> String {
+    const UNITS: &[(i64, &str)] = &[
+        (60 * 60 * 24 * 365, "year"),
+        (60 * 60 * 24 * 30, "month"),
+        (60 * 60 * 24 * 7, "week"),
+        (60 * 60 * 24, "day"),
+        (60 * 60, "hour"),
+        (60, "minute"),
+    ];
+    let delta = now_seconds - seconds;
+    let Some((amount, unit)) = UNITS
+        .iter()
+        .find(|(unit_seconds, _)| delta.abs() >= *unit_seconds)
+        .map(|(unit_seconds, unit)| (delta.abs() / unit_seconds, unit))
+    else {
+        return "just now".to_owned();
+    };
+    let amount = if amount == 1 {
+        format!("1 {unit}")
+    } else {
+        format!("{amount} {unit}s")
+    };
+    if delta > 0 {
+        format!("{amount} ago")
+    } else {
+        format!("in {amount}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes() {
+        let binary = Formatting::default();
+        assert_eq!(binary.format_size(512), "512 B");
+        assert_eq!(binary.format_size(1536), "1.5 KiB");
+        assert_eq!(binary.format_size(5 * 1024 * 1024 * 1024), "5.0 GiB");
+
+        let decimal = Formatting {
+            locale: Some("de-CH".into()),
+            size_units: SizeUnits::Decimal,
+            ..Default::default()
+        };
+        assert_eq!(decimal.format_size(1500), "1,5 kB");
+        assert_eq!(decimal.format_size(2_000_000), "2,0 MB");
+    }
+
+    #[test]
+    fn relative_times() {
+        assert_eq!(format_relative_time(100, 130), "just now");
+        assert_eq!(format_relative_time(0, 60), "1 minute ago");
+        assert_eq!(format_relative_time(0, 3 * 60 * 60 + 5), "3 hours ago");
+        assert_eq!(format_relative_time(2 * 60 * 60 * 24, 0), "in 2 days");
+    }
+
+    #[test]
+    fn settings_values() {
+        assert_eq!("12h".parse::<TimeFormat>().unwrap(), TimeFormat::H12);
+        assert_eq!("decimal".parse::<SizeUnits>().unwrap(), SizeUnits::Decimal);
+        assert!("metric".parse::<SizeUnits>().is_err());
+    }
+}
@@ -1,3 +1,24 @@
+use std::path::PathBuf;
+
+pub mod format;
+
+/// The file the settings are stored in, within the data directory of the app.
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// The identifier of the app, which also names its data directory. A `suffix` like `nightly` or `dev`
+/// identifies the builds other than the release.
+pub fn app_identifier(suffix: Option<&str>) -> String {
+    match suffix {
+        Some(suffix) => format!("com.gitbutler.app.{suffix}"),
+        None => "com.gitbutler.app".to_owned(),
+    }
+}
+
+/// The data directory of the app identified by `suffix`, or `None` if there is none on this platform.
+pub fn app_data_dir(suffix: Option<&str>) -> Option<PathBuf> {
+    dirs_next::data_dir().map(|dir| dir.join(app_identifier(suffix)))
+}
+
 /// Application settings
 /// Constructed via the `tauri_plugin_store::Store` from `settings.json`
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -8,4 +29,14 @@ pub struct AppSettings {
     pub app_analytics_confirmed: Option<bool>,
     /// Client ID for the GitHub OAuth application
     pub github_oauth_client_id: Option<String>,
+    /// The release channel to check for updates, `stable` or `nightly`
+    pub update_channel: Option<String>,
+    /// The local port to receive forge webhooks on, if webhooks should be used
+    pub forge_webhook_port: Option<u16>,
+    /// How timestamps and sizes are presented
+    pub formatting: format::Formatting,
+    /// Operations taking longer than this many milliseconds are profiled
+    pub slow_operation_threshold_ms: Option<u64>,
+    /// Whether hunk ranges are validated after each change, to diagnose wrong hunk locks
+    pub validate_hunk_ranges: Option<bool>,
 }
@@ -147,6 +147,7 @@ mod test {
             description: None,
             forge_id: None,
             archived: true,
+            merge_queue: None,
         };
         let head_2 = Branch {
             target: CommitOrChangeId::ChangeId("11609175-039d-44ee-9d4a-6baa9ad2a750".to_string()),
@@ -154,6 +155,7 @@ mod test {
             description: None,
             forge_id: None,
             archived: false,
+            merge_queue: None,
         };
         let existing_heads = vec![head_1_archived.clone(), head_2.clone()];
         let new_head = Branch {
@@ -162,6 +164,7 @@ mod test {
             description: None,
             forge_id: None,
             archived: false,
+            merge_queue: None,
         };
         let patches = vec![
             CommitOrChangeId::ChangeId("92a89ae608d77ff75c1ce52ea9dccc0bccd577e9".to_string()),
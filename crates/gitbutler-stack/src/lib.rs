@@ -6,7 +6,9 @@ mod state;
 mod target;
 
 pub use file_ownership::OwnershipClaim;
-pub use ownership::{reconcile_claims, BranchOwnershipClaims, ClaimOutcome};
+pub use ownership::{
+    reconcile_claims, BranchOwnershipClaims, ClaimOutcome, ClaimOverlap, OwnershipConflict,
+};
 pub use stack::{Stack, StackId};
 pub use state::{VirtualBranches as VirtualBranchesState, VirtualBranchesHandle};
 pub use target::Target;
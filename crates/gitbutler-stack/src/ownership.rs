@@ -1,10 +1,16 @@
-use std::{collections::HashSet, fmt, str::FromStr};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use anyhow::Result;
+use gitbutler_diff::Hunk;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize, Serializer};
 
-use crate::{file_ownership::OwnershipClaim, Stack};
+use crate::{file_ownership::OwnershipClaim, Stack, StackId};
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct BranchOwnershipClaims {
@@ -128,22 +134,81 @@ pub fn reconcile_claims(
         removed_claims: Vec::new(),
     });
 
-    // Check the outcomes consistency and error out if they would result in a hunk being claimed by multiple branches
-    let mut seen = HashSet::new();
-    for outcome in claim_outcomes.clone() {
-        for claim in outcome.updated_branch.ownership.claims {
-            for hunk in claim.hunks {
-                if !seen.insert(format!(
-                    "{}-{}-{}",
-                    claim.file_path.to_str().unwrap_or_default(),
-                    hunk.start,
-                    hunk.end
-                )) {
-                    return Err(anyhow::anyhow!("inconsistent ownership claims"));
+    let overlaps = find_overlaps(&claim_outcomes);
+    if !overlaps.is_empty() {
+        return Err(OwnershipConflict { overlaps }.into());
+    }
+
+    Ok(claim_outcomes)
+}
+
+/// Lines of a file which are claimed by two stacks at once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClaimOverlap {
+    pub file_path: PathBuf,
+    /// The stacks claiming the lines.
+    pub stack_ids: [StackId; 2],
+    /// The overlapping hunks, in the order of `stack_ids`.
+    pub hunks: [Hunk; 2],
+}
+
+impl fmt::Display for ClaimOverlap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b] = &self.hunks;
+        let [a_stack_id, b_stack_id] = &self.stack_ids;
+        write!(
+            f,
+            "{path}:{}-{} of stack {a_stack_id} overlaps {path}:{}-{} of stack {b_stack_id}",
+            a.start,
+            a.end,
+            b.start,
+            b.end,
+            path = self.file_path.display()
+        )
+    }
+}
+
+/// Claims would have made stacks own the same lines, so they were rejected.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("ownership claims would overlap: {}", .overlaps.iter().join(", "))]
+pub struct OwnershipConflict {
+    pub overlaps: Vec<ClaimOverlap>,
+}
+
+/// Find all hunks claimed by more than one stack in `outcomes`. Claims of entire files are not
+/// considered, as they can't be taken by claiming hunks.
+fn find_overlaps(outcomes: &[ClaimOutcome]) -> Vec<ClaimOverlap> {
+    let mut hunks_by_path = BTreeMap::<&Path, Vec<(&Hunk, StackId)>>::new();
+    for outcome in outcomes {
+        let stack = &outcome.updated_branch;
+        for claim in &stack.ownership.claims {
+            hunks_by_path
+                .entry(&claim.file_path)
+                .or_default()
+                .extend(claim.hunks.iter().map(|hunk| (hunk, stack.id)));
+        }
+    }
+
+    let mut overlaps = Vec::new();
+    for (file_path, mut hunks) in hunks_by_path {
+        // With the hunks sorted by start, each one can only overlap the ones following it
+        // until the first that starts after it ends.
+        hunks.sort_by_key(|(hunk, _)| (hunk.start, hunk.end));
+        for (i, (hunk, stack_id)) in hunks.iter().enumerate() {
+            for (other, other_stack_id) in hunks
+                .iter()
+                .skip(i + 1)
+                .take_while(|(other, _)| other.start <= hunk.end)
+            {
+                if stack_id != other_stack_id {
+                    overlaps.push(ClaimOverlap {
+                        file_path: file_path.to_owned(),
+                        stack_ids: [*stack_id, *other_stack_id],
+                        hunks: [(*hunk).clone(), (*other).clone()],
+                    });
                 }
             }
         }
     }
-
-    Ok(claim_outcomes)
+    overlaps
 }
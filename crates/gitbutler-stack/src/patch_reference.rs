@@ -2,6 +2,7 @@ use anyhow::Result;
 use git2::Commit;
 use gitbutler_command_context::CommandContext;
 use gitbutler_commit::commit_ext::{CommitExt, CommitVecExt};
+use gitbutler_forge::forge::MergeQueueStatus;
 use gitbutler_repo::{LogUntil, RepositoryExt};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
@@ -30,6 +31,10 @@ pub struct Branch {
     /// This would occur when the branch has been merged at the remote and the workspace has been updated with that change.
     #[serde(default)]
     pub archived: bool,
+    /// The state of the review unit in the merge queue of the forge, as last reported by the forge.
+    /// None if it isn't queued or the forge has no merge queue.
+    #[serde(default)]
+    pub merge_queue: Option<MergeQueueStatus>,
 }
 
 /// Represents identifiers for the series at possible forges, eg. GitHub PR numbers.
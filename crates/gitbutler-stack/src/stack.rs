@@ -10,6 +10,7 @@ use git2::Commit;
 use gitbutler_command_context::CommandContext;
 use gitbutler_commit::commit_ext::CommitExt;
 use gitbutler_commit::commit_ext::CommitVecExt;
+use gitbutler_forge::forge::MergeQueueStatus;
 use gitbutler_id::id::Id;
 use gitbutler_reference::{normalize_branch_name, Refname, RemoteRefname, VirtualRefname};
 use gitbutler_repo::{LogUntil, RepositoryExt};
@@ -72,6 +73,10 @@ pub struct Stack {
     pub selected_for_changes: Option<i64>,
     #[serde(default = "default_true")]
     pub allow_rebasing: bool,
+    /// If `true`, the stack holds exploratory work which must not be pushed or reviewed
+    /// unless explicitly overridden.
+    #[serde(default)]
+    pub draft: bool,
     /// This is the new metric for determining whether the branch is in the workspace, which means it's applied
     /// and its effects are available to the user.
     #[serde(default = "default_true")]
@@ -152,6 +157,7 @@ impl Stack {
             order,
             selected_for_changes,
             allow_rebasing,
+            draft: false,
             in_workspace: true,
             not_in_workspace_wip_change_id: None,
             heads: Default::default(),
@@ -286,6 +292,7 @@ impl Stack {
             description: None,
             forge_id: Default::default(),
             archived: Default::default(),
+            merge_queue: Default::default(),
         };
         let state = branch_state(ctx);
 
@@ -367,6 +374,7 @@ impl Stack {
             description,
             forge_id: Default::default(),
             archived: Default::default(),
+            merge_queue: Default::default(),
         };
         self.add_series(ctx, new_head, Some(current_top_head.name.clone()))
     }
@@ -504,6 +512,8 @@ impl Stack {
         for head in self.heads.iter_mut() {
             if !commit_ids.contains(&head.target) {
                 head.archived = true;
+                // Once integrated, the merge queue is done with it.
+                head.merge_queue = None;
             }
         }
         state.set_branch(self.clone())
@@ -759,6 +769,43 @@ impl Stack {
         }
     }
 
+    /// Sets the merge queue status for a given series/branch.
+    /// Existing value is overwritten - passing `None` means the series is not queued.
+    ///
+    /// # Errors
+    /// If the series does not exist, this method will return an error.
+    /// If the stack has not been initialized, this method will return an error.
+    pub fn set_merge_queue_status(
+        &mut self,
+        ctx: &CommandContext,
+        series_name: &str,
+        status: Option<MergeQueueStatus>,
+    ) -> Result<()> {
+        self.initialized()?;
+        match self.heads.iter_mut().find(|r| r.name == series_name) {
+            Some(head) => {
+                head.merge_queue = status;
+                branch_state(ctx).set_branch(self.clone())
+            }
+            None => bail!(
+                "Series {} does not exist on stack {}",
+                series_name,
+                self.name
+            ),
+        }
+    }
+
+    /// Returns `true` if any series of the stack which is not archived is still waiting to be merged by a merge queue.
+    pub fn has_pending_merge_queue_entry(&self) -> bool {
+        self.heads.iter().any(|head| {
+            !head.archived
+                && head
+                    .merge_queue
+                    .as_ref()
+                    .map_or(false, MergeQueueStatus::is_pending)
+        })
+    }
+
     pub fn set_legacy_compatible_stack_reference(&mut self, ctx: &CommandContext) -> Result<()> {
         // self.upstream is only set if this is a branch that was created & manipulated by the legacy flow
         let legacy_refname = match self.upstream.clone().map(|r| r.branch().to_owned()) {
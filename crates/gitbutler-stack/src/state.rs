@@ -18,7 +18,7 @@ use crate::{
 };
 
 /// The state of virtual branches data, as persisted in a TOML file.
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
 pub struct VirtualBranches {
     /// This is the target/base that is set when a repo is added to gb
     default_target: Option<Target>,
@@ -32,11 +32,20 @@ impl VirtualBranches {
     /// Lists all virtual branches that are in the user's workspace.
     ///
     /// Errors if the file cannot be read or written.
-    pub(crate) fn list_all_branches(&self) -> Result<Vec<Stack>> {
+    pub fn list_all_branches(&self) -> Result<Vec<Stack>> {
         let branches: Vec<Stack> = self.branches.values().cloned().collect();
         Ok(branches)
     }
 
+    /// Gets the default target.
+    ///
+    /// Errors if there is none.
+    pub fn get_default_target(&self) -> Result<Target> {
+        self.default_target
+            .clone()
+            .ok_or(anyhow!("there is no default target").context(Code::DefaultTargetNotFound))
+    }
+
     /// Lists all virtual branches that are in the user's workspace.
     ///
     /// Errors if the file cannot be read or written.
@@ -75,6 +84,13 @@ impl VirtualBranchesHandle {
         Self { file_path }
     }
 
+    /// Reads the whole state at once, for reading several parts of it consistently.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn read(&self) -> Result<VirtualBranches> {
+        self.read_file()
+    }
+
     /// Persists the default target for the given repository.
     ///
     /// Errors if the file cannot be read or written.
@@ -89,10 +105,7 @@ impl VirtualBranchesHandle {
     ///
     /// Errors if the file cannot be read or written.
     pub fn get_default_target(&self) -> Result<Target> {
-        let virtual_branches = self.read_file()?;
-        virtual_branches
-            .default_target
-            .ok_or(anyhow!("there is no default target").context(Code::DefaultTargetNotFound))
+        self.read_file()?.get_default_target()
     }
 
     /// Sets the state of the given virtual branch.
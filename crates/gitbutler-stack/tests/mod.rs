@@ -4,6 +4,7 @@ pub mod ownership;
 use anyhow::Result;
 use gitbutler_command_context::CommandContext;
 use gitbutler_commit::commit_ext::CommitExt;
+use gitbutler_forge::forge::MergeQueueStatus;
 use gitbutler_reference::RemoteRefname;
 use gitbutler_repo::{LogUntil, RepositoryExt as _};
 use gitbutler_repo_actions::RepoActionsExt;
@@ -24,6 +25,7 @@ fn add_series_success() -> Result<()> {
         description: Some("my description".into()),
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     let result = test_ctx.branch.add_series(&ctx, reference, None);
     assert!(result.is_ok());
@@ -78,6 +80,7 @@ fn add_series_top_base() -> Result<()> {
         description: Some("my description".into()),
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     let result = test_ctx.branch.add_series(&ctx, reference, None);
     println!("{:?}", result);
@@ -104,6 +107,7 @@ fn add_multiple_series() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     let result = test_ctx
         .branch
@@ -117,6 +121,7 @@ fn add_multiple_series() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     let result = test_ctx.branch.add_series(&ctx, head_2, None);
     assert!(result.is_ok());
@@ -131,6 +136,7 @@ fn add_multiple_series() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
 
     let result = test_ctx.branch.add_series(&ctx, head_1, None);
@@ -157,6 +163,7 @@ fn add_series_commit_id_when_change_id_available() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     let result = test_ctx.branch.add_series(&ctx, reference, None);
     assert_eq!(
@@ -179,6 +186,7 @@ fn add_series_invalid_name_fails() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     let result = test_ctx.branch.add_series(&ctx, reference, None);
     assert_eq!(result.err().unwrap().to_string(), "Invalid branch name");
@@ -195,6 +203,7 @@ fn add_series_duplicate_name_fails() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     let result = test_ctx.branch.add_series(&ctx, reference.clone(), None);
     assert!(result.is_ok());
@@ -216,6 +225,7 @@ fn add_series_matching_git_ref_is_ok() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     let result = test_ctx.branch.add_series(&ctx, reference.clone(), None);
     assert!(result.is_ok()); // allow this
@@ -232,6 +242,7 @@ fn add_series_including_refs_head_fails() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     let result = test_ctx.branch.add_series(&ctx, reference.clone(), None);
     assert_eq!(
@@ -251,6 +262,7 @@ fn add_series_target_commit_doesnt_exist() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     let result = test_ctx.branch.add_series(&ctx, reference.clone(), None);
     assert!(result
@@ -271,6 +283,7 @@ fn add_series_target_change_id_doesnt_exist() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     let result = test_ctx.branch.add_series(&ctx, reference.clone(), None);
     assert_eq!(
@@ -291,6 +304,7 @@ fn add_series_target_commit_not_in_stack() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     let result = test_ctx.branch.add_series(&ctx, reference.clone(), None);
     assert_eq!(
@@ -346,6 +360,7 @@ fn remove_series_with_multiple_last_heads() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     let result = test_ctx.branch.add_series(&ctx, to_stay.clone(), None);
     assert!(result.is_ok());
@@ -378,6 +393,7 @@ fn remove_series_no_orphan_commits() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     }; // references the oldest commit
     let result = test_ctx.branch.add_series(&ctx, to_stay.clone(), None);
     assert!(result.is_ok());
@@ -472,6 +488,43 @@ fn update_series_name_resets_forge_id() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn set_merge_queue_status_success() -> Result<()> {
+    let (ctx, _temp_dir) = command_ctx("multiple-commits")?;
+    let mut test_ctx = test_ctx(&ctx)?;
+    assert!(!test_ctx.branch.has_pending_merge_queue_entry());
+    let status = MergeQueueStatus::Queued { position: Some(2) };
+    test_ctx
+        .branch
+        .set_merge_queue_status(&ctx, "a-branch-2", Some(status.clone()))?;
+    assert_eq!(test_ctx.branch.heads[0].merge_queue, Some(status));
+    assert!(test_ctx.branch.has_pending_merge_queue_entry());
+    // Assert persisted
+    assert_eq!(
+        test_ctx.branch,
+        test_ctx.handle.get_branch(test_ctx.branch.id)?
+    );
+
+    test_ctx
+        .branch
+        .set_merge_queue_status(&ctx, "a-branch-2", Some(MergeQueueStatus::Failed))?;
+    assert!(!test_ctx.branch.has_pending_merge_queue_entry());
+    Ok(())
+}
+
+#[test]
+fn set_merge_queue_status_with_invalid_name_fails() -> Result<()> {
+    let (ctx, _temp_dir) = command_ctx("multiple-commits")?;
+    let mut test_ctx = test_ctx(&ctx)?;
+    let result = test_ctx.branch.set_merge_queue_status(
+        &ctx,
+        "does-not-exist",
+        Some(MergeQueueStatus::Merging),
+    );
+    assert!(result.is_err());
+    Ok(())
+}
+
 #[test]
 fn update_series_set_description() -> Result<()> {
     let (ctx, _temp_dir) = command_ctx("multiple-commits")?;
@@ -560,6 +613,7 @@ fn update_series_target_success() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     let result = test_ctx.branch.add_series(&ctx, series_1, None);
     assert!(result.is_ok());
@@ -659,6 +713,7 @@ fn list_series_two_heads_same_commit() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     // add `head_before` before the initial head
     let result = test_ctx.branch.add_series(&ctx, head_before, None);
@@ -695,6 +750,7 @@ fn list_series_two_heads_different_commit() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     // add `head_before` before the initial head
     let result = test_ctx.branch.add_series(&ctx, head_before, None);
@@ -761,6 +817,7 @@ fn replace_head_single() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     test_ctx.branch.add_series(&ctx, from_head, None)?;
     // replace with previous head
@@ -794,6 +851,7 @@ fn replace_head_single_with_merge_base() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     test_ctx.branch.add_series(&ctx, from_head, None)?;
     // replace with merge base
@@ -831,6 +889,7 @@ fn replace_head_with_invalid_commit_error() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     test_ctx.branch.add_series(&ctx, from_head, None)?;
     let stack = test_ctx.branch.clone();
@@ -859,6 +918,7 @@ fn replace_head_with_same_noop() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     test_ctx.branch.add_series(&ctx, from_head, None)?;
     let stack = test_ctx.branch.clone();
@@ -946,6 +1006,7 @@ fn replace_head_multiple() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     let from_head_2 = Branch {
         name: "from_head_2".into(),
@@ -953,6 +1014,7 @@ fn replace_head_multiple() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     // both references point to the same commit
     test_ctx.branch.add_series(&ctx, from_head_1, None)?;
@@ -994,6 +1056,7 @@ fn replace_head_top_of_stack_multiple() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     // an extra head just beneath the top of the stack
     test_ctx.branch.add_series(&ctx, extra_head, None)?;
@@ -1061,6 +1124,7 @@ fn set_legacy_refname_multiple_heads() -> Result<()> {
         description: None,
         forge_id: Default::default(),
         archived: Default::default(),
+        merge_queue: Default::default(),
     };
     // an extra head just beneath the top of the stack
     test_ctx.branch.add_series(&ctx, extra_head, None)?;
@@ -1130,6 +1194,7 @@ fn archive_heads_success() -> Result<()> {
             description: None,
             forge_id: Default::default(),
             archived: Default::default(),
+            merge_queue: Default::default(),
         },
     );
     assert_eq!(test_ctx.branch.heads.len(), 2);
@@ -1161,6 +1226,7 @@ fn does_not_archive_head_on_merge_base() -> Result<()> {
             description: None,
             forge_id: Default::default(),
             archived: Default::default(),
+            merge_queue: Default::default(),
         },
         None,
     )?;
@@ -1,7 +1,9 @@
 use std::{path::PathBuf, vec};
 
 use gitbutler_diff::Hunk;
-use gitbutler_stack::{reconcile_claims, BranchOwnershipClaims, OwnershipClaim, Stack};
+use gitbutler_stack::{
+    reconcile_claims, BranchOwnershipClaims, OwnershipClaim, OwnershipConflict, Stack,
+};
 
 #[test]
 fn reconcile_ownership_simple() {
@@ -119,6 +121,52 @@ fn reconcile_ownership_simple() {
     );
 }
 
+#[test]
+fn reconcile_ownership_rejects_overlapping_claims() {
+    #[allow(deprecated)] // this is a test
+    let mut branch_a = Stack::new(
+        "a".to_string(),
+        None,
+        None,
+        None,
+        git2::Oid::zero(),
+        git2::Oid::zero(),
+        usize::default(),
+        None,
+        true,
+    );
+    branch_a.ownership = "foo:1-5\nbar:1-5".parse().unwrap();
+
+    #[allow(deprecated)] // this is a test
+    let branch_b = Stack::new(
+        "b".to_string(),
+        None,
+        None,
+        None,
+        git2::Oid::zero(),
+        git2::Oid::zero(),
+        usize::default(),
+        None,
+        true,
+    );
+
+    let claim: Vec<OwnershipClaim> = vec!["foo:3-8".parse().unwrap(), "bar:6-9".parse().unwrap()];
+    let err =
+        reconcile_claims(vec![branch_a.clone(), branch_b.clone()], &branch_b, &claim).unwrap_err();
+    let conflict = err
+        .downcast_ref::<OwnershipConflict>()
+        .expect("overlaps are reported as conflict");
+    assert_eq!(conflict.overlaps.len(), 1, "adjacent hunks don't overlap");
+    let overlap = &conflict.overlaps[0];
+    assert_eq!(overlap.file_path, PathBuf::from("foo"));
+    assert_eq!(overlap.stack_ids, [branch_a.id, branch_b.id]);
+    assert_eq!(
+        overlap.hunks,
+        [Hunk::from(1..=5), Hunk::from(3..=8)],
+        "the hunks are ordered by their start"
+    );
+}
+
 #[test]
 fn ownership() {
     let ownership = "src/main.rs:0-100\nsrc/main2.rs:200-300".parse::<BranchOwnershipClaims>();
@@ -0,0 +1,64 @@
+//! Everything needed to show a project as it's opened, in a single response.
+//!
+//! Instead of one command per part, which each open the repository on their own, the parts are
+//! gathered concurrently and returned together.
+//!
+//! Only what's shown right away belongs here. Remotes, the GitButler config and snapshots are
+//! loaded when they are needed later, so a copy taken when the project was opened would be stale.
+use std::thread::ScopedJoinHandle;
+
+use anyhow::anyhow;
+use gitbutler_branch_actions::{BaseBranch, VirtualBranches};
+use gitbutler_project as projects;
+use gitbutler_project::ProjectId;
+use serde::Serialize;
+use tauri::State;
+use tracing::instrument;
+
+use crate::error::Error;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectBootstrap {
+    /// The branches of the workspace, or `None` if there is no base branch yet.
+    pub virtual_branches: Option<VirtualBranches>,
+    pub base_branch: Option<BaseBranch>,
+}
+
+/// Return what's shown when opening the project with `project_id`, in place of calling
+/// `list_virtual_branches` and `get_base_branch_data`.
+#[tauri::command(async)]
+#[instrument(skip(projects), err(Debug))]
+pub fn project_bootstrap(
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+) -> Result<ProjectBootstrap, Error> {
+    let project = projects.get(project_id)?;
+    let project = &project;
+    std::thread::scope(|scope| {
+        let base_branch =
+            scope.spawn(|| gitbutler_branch_actions::get_base_branch_data(project).ok());
+        let virtual_branches = gitbutler_branch_actions::list_virtual_branches(project);
+
+        let base_branch = join(base_branch)?;
+        let virtual_branches = match virtual_branches {
+            Ok((branches, skipped_files)) => Some(VirtualBranches {
+                branches,
+                skipped_files,
+            }),
+            // Without a base branch there is no workspace to list.
+            Err(_) if base_branch.is_none() => None,
+            Err(err) => return Err(err.into()),
+        };
+        Ok(ProjectBootstrap {
+            virtual_branches,
+            base_branch,
+        })
+    })
+}
+
+fn join<T>(handle: ScopedJoinHandle<'_, T>) -> anyhow::Result<T> {
+    handle
+        .join()
+        .map_err(|_| anyhow!("a part of the project failed to load"))
+}
@@ -0,0 +1,31 @@
+//! Responses that are sent in chunks over a [`Channel`] instead of all at once.
+//!
+//! Large lists serialize to strings of several megabytes, which the frontend then has to parse in
+//! one go on its main thread. Sending them in chunks keeps each message small, and lets the
+//! frontend process one chunk before the next one arrives.
+use anyhow::Result;
+use serde::Serialize;
+use tauri::ipc::Channel;
+
+/// How many items are sent per chunk.
+pub const CHUNK_SIZE: usize = 200;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "subject", rename_all = "camelCase")]
+pub enum Chunk<T> {
+    /// The next items of the response.
+    Items(Vec<T>),
+    /// All items were sent, `total` of them.
+    Done { total: usize },
+}
+
+/// Send `items` over `channel`, [`CHUNK_SIZE`] at a time, followed by [`Chunk::Done`].
+pub fn send<T: Serialize + Clone>(channel: &Channel<Chunk<T>>, items: Vec<T>) -> Result<()> {
+    let total = items.len();
+    let mut items = items.into_iter().peekable();
+    while items.peek().is_some() {
+        channel.send(Chunk::Items(items.by_ref().take(CHUNK_SIZE).collect()))?;
+    }
+    channel.send(Chunk::Done { total })?;
+    Ok(())
+}
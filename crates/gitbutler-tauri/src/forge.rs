@@ -1,8 +1,49 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
+use gitbutler_forge::event_log::EventLog;
+use gitbutler_secret::secret;
+use tauri::{AppHandle, Emitter};
+
+/// The secret handle under which the frontend stores the secret shared with the webhook sender.
+const WEBHOOK_SECRET_HANDLE: &str = "forgeWebhookSecret";
+
+/// The file in the app data directory the received forge events are recorded in.
+pub const FORGE_EVENTS_FILE: &str = "forge-events.json";
+
+/// Receive forge webhooks on `port` of the loopback interface and forward them to the frontend
+/// as `forge://webhook` events, so it can refresh review and CI state without polling.
+/// Each event is also recorded in `event_log`, for the activity of branches.
+///
+/// Webhooks can reach the listener through a relay or a tunnel, which is why deliveries must be
+/// signed with the secret stored under [`WEBHOOK_SECRET_HANDLE`], and nothing listens without one.
+pub fn listen_for_webhooks(
+    app_handle: AppHandle,
+    port: u16,
+    event_log: EventLog,
+) -> anyhow::Result<()> {
+    let Some(secret) =
+        secret::retrieve(WEBHOOK_SECRET_HANDLE, secret::Namespace::Global)?.map(|s| s.0)
+    else {
+        anyhow::bail!("not listening for forge webhooks, as no secret to verify them is set");
+    };
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    gitbutler_forge::webhook::listen(addr, secret, move |event| {
+        if let Err(err) = event_log.record(event.clone()) {
+            tracing::warn!("failed to record forge webhook: {err:#}");
+        }
+        if let Err(err) = app_handle.emit("forge://webhook", &event) {
+            tracing::warn!("failed to forward forge webhook: {err}");
+        }
+    })?;
+    Ok(())
+}
+
 pub mod commands {
     use std::path::Path;
 
     use anyhow::Context;
     use gitbutler_forge::{
+        client::{ForgeClient, ForgeResponse},
         forge::ForgeName,
         review::{
             available_review_templates, get_review_template_functions, ReviewTemplateFunctions,
@@ -53,4 +94,15 @@ pub mod commands {
             .content
             .context("PR template was not valid UTF-8")?)
     }
+
+    /// Perform a `GET` request against a forge API through the shared, rate-limited and caching client.
+    #[tauri::command(async)]
+    #[instrument(skip(client, token), err(Debug))]
+    pub async fn forge_get(
+        client: State<'_, ForgeClient>,
+        url: String,
+        token: Option<String>,
+    ) -> Result<ForgeResponse, Error> {
+        Ok(client.get(&url, token.as_deref()).await?)
+    }
 }
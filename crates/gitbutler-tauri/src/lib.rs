@@ -22,17 +22,21 @@ pub mod window;
 pub use window::state::WindowState;
 
 pub mod askpass;
+pub mod bootstrap;
+pub mod chunked;
 pub mod config;
 pub mod error;
 pub mod forge;
 pub mod github;
 pub mod modes;
 pub mod open;
+pub mod profiles;
 pub mod projects;
 pub mod remotes;
 pub mod repo;
 pub mod secret;
 pub mod undo;
+pub mod updater;
 pub mod users;
 pub mod virtual_branches;
 
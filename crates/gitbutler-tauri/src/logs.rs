@@ -5,6 +5,8 @@ use tracing::{instrument, metadata::LevelFilter, subscriber::set_global_default}
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::{fmt::format::FmtSpan, layer::SubscriberExt, Layer};
 
+use crate::profiles;
+
 pub fn init(app_handle: &AppHandle, performance_logging: bool) {
     let logs_dir = app_handle
         .path()
@@ -61,6 +63,11 @@ pub fn init(app_handle: &AppHandle, performance_logging: bool) {
                 .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE)
                 .with_writer(file_writer)
                 .with_filter(log_level_filter),
+        )
+        .with(
+            // subscriber that profiles slow operations
+            profiles::ProfilesLayer::new(profiles::profiles_dir(&logs_dir))
+                .with_filter(LevelFilter::DEBUG),
         );
     if performance_logging {
         set_global_default(
@@ -13,8 +13,9 @@
 
 use gitbutler_tauri::settings::SettingsStore;
 use gitbutler_tauri::{
-    askpass, commands, config, forge, github, logs, menu, modes, open, projects, remotes, repo,
-    secret, stack, undo, users, virtual_branches, zip, App, WindowState,
+    askpass, bootstrap, commands, config, forge, github, logs, menu, modes, open, profiles,
+    projects, remotes, repo, secret, stack, undo, updater, users, virtual_branches, zip, App,
+    WindowState,
 };
 use tauri::Emitter;
 use tauri::{generate_context, Manager};
@@ -106,8 +107,34 @@ fn main() {
                     };
                     app_handle.manage(app.users());
                     app_handle.manage(app.projects());
-                    let settings_store: SettingsStore = tauri_app.store("settings.json")?.into();
+                    let settings_store: SettingsStore =
+                        tauri_app.store(gitbutler_settings::SETTINGS_FILE)?.into();
+                    if let Some(threshold_ms) =
+                        settings_store.app_settings().slow_operation_threshold_ms
+                    {
+                        profiles::set_threshold_ms(threshold_ms);
+                    }
+                    if settings_store.app_settings().validate_hunk_ranges == Some(true) {
+                        gitbutler_branch_actions::dependencies::set_validation_enabled(true);
+                    }
+                    if let Some(port) = settings_store.app_settings().forge_webhook_port {
+                        if let Err(err) = forge::listen_for_webhooks(
+                            app_handle.clone(),
+                            port,
+                            gitbutler_forge::event_log::EventLog::new(
+                                app_data_dir.join(forge::FORGE_EVENTS_FILE),
+                            ),
+                        ) {
+                            tracing::error!("failed to start forge webhook listener: {err:#}");
+                        }
+                    }
                     app_handle.manage(settings_store);
+                    app_handle.manage(gitbutler_forge::event_log::EventLog::new(
+                        app_data_dir.join(forge::FORGE_EVENTS_FILE),
+                    ));
+                    app_handle.manage(gitbutler_forge::client::ForgeClient::new(Some(
+                        app_cache_dir.join("forge-responses.json"),
+                    )));
 
                     app_handle.manage(gitbutler_feedback::Archival {
                         cache_dir: app_cache_dir,
@@ -145,6 +172,8 @@ fn main() {
                     commands::git_index_size,
                     zip::commands::get_logs_archive_path,
                     zip::commands::get_project_archive_path,
+                    profiles::commands::list_slow_operation_profiles,
+                    profiles::commands::get_profiles_archive_path,
                     users::commands::set_user,
                     users::commands::delete_user,
                     users::commands::get_user,
@@ -155,16 +184,24 @@ fn main() {
                     projects::commands::list_projects,
                     projects::commands::set_project_active,
                     projects::commands::open_project_in_window,
+                    projects::commands::get_repository_ownership,
+                    projects::commands::trust_repository,
+                    projects::commands::list_feature_flags,
+                    projects::commands::set_feature_flag,
+                    projects::commands::set_remote_feature_flags,
+                    bootstrap::project_bootstrap,
                     repo::commands::git_get_local_config,
                     repo::commands::git_set_local_config,
                     repo::commands::check_signing_settings,
                     repo::commands::git_clone_repository,
                     repo::commands::get_uncommited_files,
                     repo::commands::get_blob_info,
+                    repo::commands::expand_hunk_context,
                     virtual_branches::commands::list_virtual_branches,
                     virtual_branches::commands::create_virtual_branch,
                     virtual_branches::commands::delete_local_branch,
                     virtual_branches::commands::commit_virtual_branch,
+                    virtual_branches::commands::commit_hunks,
                     virtual_branches::commands::get_base_branch_data,
                     virtual_branches::commands::set_base_branch,
                     virtual_branches::commands::push_base_branch,
@@ -179,37 +216,79 @@ fn main() {
                     virtual_branches::commands::create_virtual_branch_from_branch,
                     virtual_branches::commands::can_apply_remote_branch,
                     virtual_branches::commands::list_commit_files,
+                    virtual_branches::commands::verify_commit_signatures,
                     virtual_branches::commands::reset_virtual_branch,
                     virtual_branches::commands::amend_virtual_branch,
+                    virtual_branches::commands::list_frequent_co_authors,
+                    virtual_branches::commands::absorb,
+                    virtual_branches::commands::apply_patch,
+                    virtual_branches::commands::format_patch,
+                    virtual_branches::commands::shelve_changes,
+                    virtual_branches::commands::unshelve_changes,
+                    virtual_branches::commands::list_shelves,
+                    virtual_branches::commands::delete_shelf,
                     virtual_branches::commands::move_commit_file,
+                    virtual_branches::commands::move_commit_hunks,
                     virtual_branches::commands::undo_commit,
                     virtual_branches::commands::insert_blank_commit,
+                    virtual_branches::commands::split_commit,
+                    virtual_branches::commands::revert_commit,
                     virtual_branches::commands::reorder_stack,
                     virtual_branches::commands::update_commit_message,
                     virtual_branches::commands::list_local_branches,
                     virtual_branches::commands::list_branches,
+                    virtual_branches::commands::list_branches_chunked,
+                    virtual_branches::commands::hunk_dependency_graph,
+                    virtual_branches::commands::commit_dependencies,
+                    virtual_branches::commands::cross_stack_conflicts,
+                    virtual_branches::commands::hunk_range_errors,
+                    virtual_branches::commands::dependency_timings,
+                    virtual_branches::commands::remove_commit_preview,
+                    virtual_branches::commands::predict_operation,
+                    virtual_branches::commands::line_owners,
                     virtual_branches::commands::get_branch_listing_details,
                     virtual_branches::commands::get_remote_branch_data,
                     virtual_branches::commands::squash_branch_commit,
+                    virtual_branches::commands::squash_commits,
+                    virtual_branches::commands::autosquash,
                     virtual_branches::commands::fetch_from_remotes,
                     virtual_branches::commands::move_commit,
+                    virtual_branches::commands::copy_commits_to_stack,
                     virtual_branches::commands::normalize_branch_name,
                     virtual_branches::commands::upstream_integration_statuses,
                     virtual_branches::commands::integrate_upstream,
                     virtual_branches::commands::resolve_upstream_integration,
+                    virtual_branches::commands::autostash_conflicts,
+                    virtual_branches::commands::dismiss_autostash_conflicts,
                     virtual_branches::commands::find_commit,
+                    virtual_branches::commands::get_branch_summary,
+                    virtual_branches::commands::get_commit_summary,
+                    virtual_branches::commands::get_contributors,
+                    virtual_branches::commands::list_commits_touching,
+                    virtual_branches::commands::list_commit_attachments,
+                    virtual_branches::commands::add_commit_attachment,
+                    virtual_branches::commands::remove_commit_attachment,
+                    virtual_branches::commands::get_policy_report,
+                    virtual_branches::commands::validate_commit_message,
                     stack::create_series,
                     stack::remove_series,
                     stack::update_series_name,
                     stack::update_series_description,
                     stack::update_series_forge_id,
+                    stack::update_series_merge_queue_status,
                     stack::push_stack,
+                    stack::list_stack_templates,
+                    stack::create_stack_from_template,
+                    stack::stack_activity,
                     secret::secret_get_global,
                     secret::secret_set_global,
                     undo::list_snapshots,
                     undo::restore_snapshot,
                     undo::snapshot_diff,
+                    undo::snapshot_storage_stats,
                     undo::take_synced_snapshot,
+                    updater::commands::check_for_update,
+                    updater::commands::install_update,
                     config::get_gb_config,
                     config::set_gb_config,
                     menu::menu_item_set_enabled,
@@ -227,6 +306,7 @@ fn main() {
                     open::open_url,
                     forge::commands::get_available_review_templates,
                     forge::commands::get_review_template_contents,
+                    forge::commands::forge_get,
                 ])
                 .menu(menu::build)
                 .on_window_event(|window, event| match event {
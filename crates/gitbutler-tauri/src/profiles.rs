@@ -0,0 +1,330 @@
+//! Profiles of slow operations, so reports about performance problems come with the data to act on.
+//!
+//! Whenever a top-level span, like the one of a command, takes longer than the configured threshold,
+//! the timings of all spans within it are written as a profile into the `profiles` directory next to
+//! the logs. This way they are also part of the logs archive, and a warning is logged as well.
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{
+    field::{Field, Visit},
+    span, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+/// How long an operation may take before it's profiled, if not configured otherwise.
+pub const DEFAULT_THRESHOLD_MS: u64 = 2000;
+
+/// How many profiles are kept, the oldest ones are removed first.
+const MAX_PROFILES: usize = 50;
+
+/// How many spans are kept within a span, so operations with many small steps don't produce huge profiles,
+/// and spans that live as long as the app don't grow without bounds.
+const MAX_SPANS: usize = 1000;
+
+static THRESHOLD_MS: AtomicU64 = AtomicU64::new(DEFAULT_THRESHOLD_MS);
+
+/// Profile operations which take longer than `threshold_ms` from now on.
+pub fn set_threshold_ms(threshold_ms: u64) {
+    THRESHOLD_MS.store(threshold_ms, Ordering::Relaxed);
+}
+
+/// The directory profiles are written to, given the `logs_dir` of the app.
+pub fn profiles_dir(logs_dir: &Path) -> PathBuf {
+    logs_dir.join(gitbutler_feedback::PROFILES_DIR)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpanTiming {
+    pub name: String,
+    pub target: String,
+    /// The time from creating to closing the span.
+    pub duration_us: u64,
+    /// The recorded fields of the span, like the id of the project.
+    pub fields: BTreeMap<String, String>,
+    /// The spans created within this one, in the order they were closed.
+    pub children: Vec<SpanTiming>,
+    /// How many children were left out as there are more than fit into a profile.
+    pub omitted_children: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    /// When the operation finished, in milliseconds since the Unix epoch.
+    pub recorded_at: u128,
+    /// The threshold the operation exceeded.
+    pub threshold_ms: u64,
+    pub operation: SpanTiming,
+}
+
+/// The state of a span until it's closed.
+struct Timing {
+    started: Instant,
+    fields: BTreeMap<String, String>,
+    children: Vec<SpanTiming>,
+    omitted_children: usize,
+    /// How many spans are kept within this one, in `children` and all of theirs.
+    spans: usize,
+}
+
+struct FieldRecorder<'a>(&'a mut BTreeMap<String, String>);
+
+impl Visit for FieldRecorder<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_owned(), value.to_owned());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.insert(field.name().to_owned(), format!("{value:?}"));
+    }
+}
+
+/// A layer that times all spans and writes a profile of each slow top-level span to `dir`.
+pub struct ProfilesLayer {
+    dir: PathBuf,
+}
+
+impl ProfilesLayer {
+    pub fn new(dir: PathBuf) -> Self {
+        ProfilesLayer { dir }
+    }
+}
+
+impl<S> Layer<S> for ProfilesLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = BTreeMap::new();
+        attrs.record(&mut FieldRecorder(&mut fields));
+        span.extensions_mut().insert(Timing {
+            started: Instant::now(),
+            fields,
+            children: Vec::new(),
+            omitted_children: 0,
+            spans: 0,
+        });
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        if let Some(timing) = span.extensions_mut().get_mut::<Timing>() {
+            values.record(&mut FieldRecorder(&mut timing.fields));
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(timing) = span.extensions_mut().remove::<Timing>() else {
+            return;
+        };
+        let spans = 1 + timing.spans;
+        let span_timing = SpanTiming {
+            name: span.name().to_owned(),
+            target: span.metadata().target().to_owned(),
+            duration_us: u64::try_from(timing.started.elapsed().as_micros()).unwrap_or(u64::MAX),
+            fields: timing.fields,
+            children: timing.children,
+            omitted_children: timing.omitted_children,
+        };
+
+        if let Some(parent) = span.parent() {
+            if let Some(parent_timing) = parent.extensions_mut().get_mut::<Timing>() {
+                if parent_timing.spans + spans <= MAX_SPANS {
+                    parent_timing.children.push(span_timing);
+                    parent_timing.spans += spans;
+                } else {
+                    parent_timing.omitted_children += 1;
+                }
+            }
+            return;
+        }
+
+        let threshold_ms = THRESHOLD_MS.load(Ordering::Relaxed);
+        if span_timing.duration_us < threshold_ms.saturating_mul(1000) {
+            return;
+        }
+        tracing::warn!(
+            operation = %span_timing.name,
+            duration_ms = span_timing.duration_us / 1000,
+            threshold_ms,
+            "slow operation, writing a profile"
+        );
+        let profile = Profile {
+            recorded_at: gitbutler_time::time::now_ms(),
+            threshold_ms,
+            operation: span_timing,
+        };
+        // Errors are dropped, as logging them from within the subscriber is more trouble than it's worth.
+        write_profile(&self.dir, &profile).ok();
+    }
+}
+
+fn write_profile(dir: &Path, profile: &Profile) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let file_name = format!(
+        "{}-{}.json",
+        profile.recorded_at,
+        profile
+            .operation
+            .name
+            .replace(|c: char| !c.is_ascii_alphanumeric() && c != '_', "-")
+    );
+    fs::write(dir.join(file_name), serde_json::to_vec_pretty(profile)?)?;
+
+    let mut paths = profile_paths(dir)?;
+    if paths.len() > MAX_PROFILES {
+        // The names start with the time, so these are the oldest ones.
+        for path in paths.drain(..paths.len() - MAX_PROFILES) {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// The paths of all profiles in `dir`, the oldest first.
+fn profile_paths(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json")
+            })
+            .collect(),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(err.into()),
+    };
+    paths.sort();
+    Ok(paths)
+}
+
+/// Read all profiles in `dir`, the most recent first.
+pub fn list(dir: &Path) -> Result<Vec<Profile>> {
+    let mut profiles = Vec::new();
+    for path in profile_paths(dir)?.into_iter().rev() {
+        let contents = fs::read(&path)?;
+        profiles.push(
+            serde_json::from_slice(&contents)
+                .with_context(|| format!("failed to read profile {}", path.display()))?,
+        );
+    }
+    Ok(profiles)
+}
+
+pub mod commands {
+    use std::path::PathBuf;
+
+    use anyhow::Context;
+    use gitbutler_feedback::Archival;
+    use tauri::{AppHandle, Manager, State};
+    use tracing::instrument;
+
+    use super::Profile;
+    use crate::error::Error;
+
+    /// List the profiles of slow operations, the most recent first.
+    #[tauri::command(async)]
+    #[instrument(skip(handle), err(Debug))]
+    pub fn list_slow_operation_profiles(handle: AppHandle) -> Result<Vec<Profile>, Error> {
+        let logs_dir = handle.path().app_log_dir().context("missing app log dir")?;
+        super::list(&super::profiles_dir(&logs_dir)).map_err(Into::into)
+    }
+
+    /// Zip all profiles of slow operations, to attach them to a bug report.
+    #[tauri::command(async)]
+    #[instrument(skip(archival), err(Debug))]
+    pub fn get_profiles_archive_path(archival: State<'_, Archival>) -> Result<PathBuf, Error> {
+        archival.profiles_archive().map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    /// Run `operation` with all operations being profiled into a new directory, and return its profiles.
+    fn profile(operation: impl FnOnce()) -> Result<Vec<Profile>> {
+        // The threshold is global, so tests changing it must not overlap.
+        static THRESHOLD: Mutex<()> = Mutex::new(());
+        let _threshold = THRESHOLD.lock().unwrap_or_else(|err| err.into_inner());
+
+        let dir = tempfile::tempdir()?;
+        let subscriber =
+            tracing_subscriber::registry().with(ProfilesLayer::new(dir.path().to_owned()));
+        set_threshold_ms(0);
+        tracing::subscriber::with_default(subscriber, operation);
+        set_threshold_ms(DEFAULT_THRESHOLD_MS);
+        list(dir.path())
+    }
+
+    #[test]
+    fn slow_operations_are_profiled() -> Result<()> {
+        let profiles = profile(|| {
+            let operation = tracing::info_span!("list_branches", project_id = "1234").entered();
+            tracing::info_span!("read_refs").in_scope(|| {});
+            drop(operation);
+        })?;
+
+        assert_eq!(profiles.len(), 1);
+        let operation = &profiles[0].operation;
+        assert_eq!(operation.name, "list_branches");
+        assert_eq!(operation.fields["project_id"], "1234");
+        assert_eq!(
+            operation
+                .children
+                .iter()
+                .map(|child| child.name.as_str())
+                .collect::<Vec<_>>(),
+            ["read_refs"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn profiles_keep_a_limited_number_of_spans() -> Result<()> {
+        let profiles = profile(|| {
+            tracing::info_span!("operation").in_scope(|| {
+                for _ in 0..10 {
+                    tracing::info_span!("step").in_scope(|| {
+                        for _ in 0..200 {
+                            tracing::info_span!("small_step").in_scope(|| {});
+                        }
+                    });
+                }
+            });
+        })?;
+
+        // Each step has 201 spans, so only 4 of them fit.
+        let operation = &profiles[0].operation;
+        assert_eq!(operation.children.len(), 4);
+        assert_eq!(operation.omitted_children, 6);
+        assert!(operation
+            .children
+            .iter()
+            .all(|step| step.children.len() == 200 && step.omitted_children == 0));
+        Ok(())
+    }
+}
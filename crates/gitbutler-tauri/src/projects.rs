@@ -1,9 +1,12 @@
 use gitbutler_project::Project;
 
 pub mod commands {
+    use std::collections::BTreeMap;
     use std::path;
 
     use anyhow::Context;
+    use gitbutler_project::feature_flags::{self, FeatureFlag, FlagState};
+    use gitbutler_project::ownership::{self, Ownership};
     use gitbutler_project::{self as projects, Controller, ProjectId};
     use tauri::{State, Window};
     use tracing::instrument;
@@ -19,6 +22,47 @@ pub mod commands {
         Ok(projects.update(&project)?)
     }
 
+    /// Return the state of all feature flags of the project with `project_id`.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn list_feature_flags(
+        projects: State<'_, Controller>,
+        project_id: ProjectId,
+    ) -> Result<Vec<FlagState>, Error> {
+        Ok(projects.get(project_id)?.feature_flag_states())
+    }
+
+    /// Turn `flag` on or off for the project with `project_id`, or go back to the remote
+    /// configuration or default if `enabled` is `None`.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn set_feature_flag(
+        projects: State<'_, Controller>,
+        project_id: ProjectId,
+        flag: FeatureFlag,
+        enabled: Option<bool>,
+    ) -> Result<Vec<FlagState>, Error> {
+        let mut feature_flags = projects.get(project_id)?.feature_flags;
+        match enabled {
+            Some(enabled) => feature_flags.insert(flag, enabled),
+            None => feature_flags.remove(&flag),
+        };
+        let project = projects.update(&projects::UpdateRequest {
+            id: project_id,
+            feature_flags: Some(feature_flags),
+            ..Default::default()
+        })?;
+        Ok(project.feature_flag_states())
+    }
+
+    /// Use `flags` as the remote configuration of the feature flags of all projects.
+    #[tauri::command(async)]
+    #[instrument(err(Debug))]
+    pub fn set_remote_feature_flags(flags: BTreeMap<FeatureFlag, bool>) -> Result<(), Error> {
+        feature_flags::set_remote(flags);
+        Ok(())
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects), err(Debug))]
     pub fn add_project(
@@ -96,6 +140,20 @@ pub mod commands {
     pub fn delete_project(projects: State<'_, Controller>, id: ProjectId) -> Result<(), Error> {
         projects.delete(id).map_err(Into::into)
     }
+
+    /// Tell if the repository at `path` can be opened, or is owned by another user and has to be trusted first.
+    #[tauri::command(async)]
+    #[instrument(err(Debug))]
+    pub fn get_repository_ownership(path: &path::Path) -> Result<Ownership, Error> {
+        Ok(ownership::ownership(path)?)
+    }
+
+    /// Trust the repository at `path` despite being owned by another user, by adding it to `safe.directory`.
+    #[tauri::command(async)]
+    #[instrument(err(Debug))]
+    pub fn trust_repository(path: &path::Path) -> Result<(), Error> {
+        Ok(ownership::trust(path)?)
+    }
 }
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -5,7 +5,7 @@ pub mod commands {
     use gitbutler_branch_actions::RemoteBranchFile;
     use gitbutler_project as projects;
     use gitbutler_project::ProjectId;
-    use gitbutler_repo::{FileInfo, RepoCommands};
+    use gitbutler_repo::{FileInfo, HunkContext, RepoCommands};
     use std::path::Path;
     use std::sync::atomic::AtomicBool;
     use tauri::State;
@@ -83,4 +83,32 @@ pub mod commands {
 
         Ok(project.read_file_from_workspace(commit_oid, relative_path)?)
     }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn expand_hunk_context(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        relative_path: &Path,
+        commit_id: Option<String>,
+        hunk_start: u32,
+        hunk_lines: u32,
+        lines_before: Option<u32>,
+        lines_after: Option<u32>,
+    ) -> Result<HunkContext, Error> {
+        let project = projects.get(project_id)?;
+        let commit_oid = commit_id
+            .map(|id| Oid::from_str(&id).map_err(|e| anyhow::anyhow!(e)))
+            .transpose()?;
+
+        Ok(project.expand_hunk_context(
+            commit_oid,
+            relative_path,
+            hunk_start,
+            hunk_lines,
+            lines_before,
+            lines_after,
+        )?)
+    }
 }
@@ -1,5 +1,5 @@
-use gitbutler_settings::AppSettings;
-use std::sync::Arc;
+use gitbutler_settings::{format::Formatting, AppSettings};
+use std::{hash::BuildHasher, sync::Arc};
 use tauri::Wry;
 use tauri_plugin_store::Store;
 
@@ -21,13 +21,40 @@ impl SettingsStore {
             app_non_anon_metrics_enabled: self.get_bool("appNonAnonMetricsEnabled"),
             app_analytics_confirmed: self.get_bool("appAnalyticsConfirmed"),
             github_oauth_client_id: self.get_string("githubOauthClientId"),
+            update_channel: self.get_string("updateChannel"),
+            forge_webhook_port: self
+                .get_u64("forgeWebhookPort")
+                .and_then(|port| u16::try_from(port).ok()),
+            formatting: Formatting::from_settings(|key| self.get_string(key)),
+            slow_operation_threshold_ms: self.get_u64("slowOperationThresholdMs"),
+            validate_hunk_ranges: self.get_bool("validateHunkRanges"),
         }
     }
 
+    /// The bucket from 0 to 99 this installation falls into for staged rollouts of updates.
+    /// It's drawn on first use and kept in the settings from then on.
+    pub fn rollout_bucket(&self) -> u64 {
+        const KEY: &str = "updateRolloutBucket";
+        if let Some(bucket) = self.get_u64(KEY).filter(|bucket| *bucket < 100) {
+            return bucket;
+        }
+        // Hashing with freshly seeded keys is random enough for spreading installations.
+        let bucket = std::collections::hash_map::RandomState::new().hash_one(()) % 100;
+        self.store.set(KEY, bucket);
+        if let Err(err) = self.store.save() {
+            tracing::warn!("failed to save the rollout bucket: {err}");
+        }
+        bucket
+    }
+
     fn get_bool(&self, value: &str) -> Option<bool> {
         self.store.get(value).and_then(|v| v.as_bool())
     }
 
+    fn get_u64(&self, value: &str) -> Option<u64> {
+        self.store.get(value).and_then(|v| v.as_u64())
+    }
+
     fn get_string(&self, value: &str) -> Option<String> {
         self.store
             .get(value)
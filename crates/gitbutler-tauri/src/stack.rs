@@ -1,4 +1,8 @@
+use gitbutler_branch_actions::activity::ActivityPage;
 use gitbutler_branch_actions::stack::CreateSeriesRequest;
+use gitbutler_branch_actions::stack_templates::StackTemplate;
+use gitbutler_forge::event_log::EventLog;
+use gitbutler_forge::forge::MergeQueueStatus;
 use gitbutler_project as projects;
 use gitbutler_project::ProjectId;
 use gitbutler_stack::{ForgeIdentifier, StackId};
@@ -89,10 +93,33 @@ pub fn update_series_forge_id(
     stack_id: StackId,
     head_name: String,
     forge_id: Option<ForgeIdentifier>,
+    allow_draft: Option<bool>,
 ) -> Result<(), Error> {
     let project = projects.get(project_id)?;
     gitbutler_branch_actions::stack::update_series_forge_id(
-        &project, stack_id, head_name, forge_id,
+        &project,
+        stack_id,
+        head_name,
+        forge_id,
+        allow_draft.unwrap_or_default(),
+    )?;
+    emit_vbranches(&windows, project_id);
+    Ok(())
+}
+
+#[tauri::command(async)]
+#[instrument(skip(projects, windows), err(Debug))]
+pub fn update_series_merge_queue_status(
+    windows: State<'_, WindowState>,
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+    stack_id: StackId,
+    head_name: String,
+    status: Option<MergeQueueStatus>,
+) -> Result<(), Error> {
+    let project = projects.get(project_id)?;
+    gitbutler_branch_actions::stack::update_series_merge_queue_status(
+        &project, stack_id, head_name, status,
     )?;
     emit_vbranches(&windows, project_id);
     Ok(())
@@ -106,9 +133,67 @@ pub fn push_stack(
     project_id: ProjectId,
     branch_id: StackId,
     with_force: bool,
+    allow_draft: Option<bool>,
 ) -> Result<(), Error> {
     let project = projects.get(project_id)?;
-    gitbutler_branch_actions::stack::push_stack(&project, branch_id, with_force)?;
+    gitbutler_branch_actions::stack::push_stack(
+        &project,
+        branch_id,
+        with_force,
+        allow_draft.unwrap_or_default(),
+    )?;
     emit_vbranches(&windows, project_id);
     Ok(())
 }
+
+#[tauri::command(async)]
+#[instrument(skip(projects), err(Debug))]
+pub fn list_stack_templates(
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+) -> Result<Vec<StackTemplate>, Error> {
+    let project = projects.get(project_id)?;
+    Ok(gitbutler_branch_actions::stack_templates::load(
+        &project.worktree_path(),
+    )?)
+}
+
+#[tauri::command(async)]
+#[instrument(skip(projects, windows), err(Debug))]
+pub fn create_stack_from_template(
+    windows: State<'_, WindowState>,
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+    template_name: String,
+    name: String,
+) -> Result<StackId, Error> {
+    let project = projects.get(project_id)?;
+    let stack_id = gitbutler_branch_actions::stack_templates::create_stack_from_template(
+        &project,
+        &template_name,
+        &name,
+    )?;
+    emit_vbranches(&windows, project_id);
+    Ok(stack_id)
+}
+
+/// Return a page of the activity of a stack, the most recent first.
+#[tauri::command(async)]
+#[instrument(skip(projects, event_log), err(Debug))]
+pub fn stack_activity(
+    projects: State<'_, projects::Controller>,
+    event_log: State<'_, EventLog>,
+    project_id: ProjectId,
+    stack_id: StackId,
+    offset: usize,
+    limit: usize,
+) -> Result<ActivityPage, Error> {
+    let project = projects.get(project_id)?;
+    Ok(gitbutler_branch_actions::stack_activity(
+        &project,
+        stack_id,
+        &event_log.list()?,
+        offset,
+        limit,
+    )?)
+}
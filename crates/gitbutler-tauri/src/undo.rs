@@ -2,30 +2,60 @@ use std::{collections::HashMap, path::PathBuf};
 
 use anyhow::Context;
 use gitbutler_diff::FileDiff;
-use gitbutler_oplog::{entry::Snapshot, OplogExt};
+use gitbutler_oplog::{entry::Snapshot, OplogExt, SnapshotStorageStats};
 use gitbutler_project as projects;
 use gitbutler_project::ProjectId;
+use gitbutler_settings::format::{Size, Timestamp};
 use gitbutler_user::User;
+use serde::Serialize;
 use tauri::State;
 use tracing::instrument;
 
-use crate::error::Error;
+use crate::{error::Error, settings::SettingsStore};
+
+/// A [`Snapshot`] along with its creation time formatted according to the settings.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormattedSnapshot {
+    #[serde(flatten)]
+    pub snapshot: Snapshot,
+    pub created: Timestamp,
+}
+
+/// [`SnapshotStorageStats`] with sizes formatted according to the settings.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormattedSnapshotStorageStats {
+    pub snapshots: usize,
+    pub unique_objects: usize,
+    pub unique_size: Size,
+    pub logical_size: Size,
+}
 
 #[tauri::command(async)]
-#[instrument(skip(projects), err(Debug))]
+#[instrument(skip(projects, settings), err(Debug))]
 pub fn list_snapshots(
     projects: State<'_, projects::Controller>,
+    settings: State<'_, SettingsStore>,
     project_id: ProjectId,
     limit: usize,
     sha: Option<String>,
-) -> Result<Vec<Snapshot>, Error> {
+) -> Result<Vec<FormattedSnapshot>, Error> {
     let project = projects.get(project_id).context("failed to get project")?;
     let snapshots = project.list_snapshots(
         limit,
         sha.map(|hex| hex.parse().map_err(anyhow::Error::from))
             .transpose()?,
     )?;
-    Ok(snapshots)
+    let formatting = settings.app_settings().formatting;
+    let now = gitbutler_time::time::now_since_unix_epoch_ms() / 1000;
+    Ok(snapshots
+        .into_iter()
+        .map(|snapshot| FormattedSnapshot {
+            created: formatting.timestamp(snapshot.created_at.seconds(), now),
+            snapshot,
+        })
+        .collect())
 }
 
 #[tauri::command(async)]
@@ -52,6 +82,29 @@ pub fn snapshot_diff(
     Ok(diff)
 }
 
+#[tauri::command(async)]
+#[instrument(skip(projects, settings), err(Debug))]
+pub fn snapshot_storage_stats(
+    projects: State<'_, projects::Controller>,
+    settings: State<'_, SettingsStore>,
+    project_id: ProjectId,
+) -> Result<FormattedSnapshotStorageStats, Error> {
+    let project = projects.get(project_id).context("failed to get project")?;
+    let SnapshotStorageStats {
+        snapshots,
+        unique_objects,
+        unique_bytes,
+        logical_bytes,
+    } = project.snapshot_storage_stats()?;
+    let formatting = settings.app_settings().formatting;
+    Ok(FormattedSnapshotStorageStats {
+        snapshots,
+        unique_objects,
+        unique_size: formatting.size(unique_bytes),
+        logical_size: formatting.size(logical_bytes),
+    })
+}
+
 #[tauri::command(async)]
 #[instrument(skip(projects), err(Debug))]
 pub fn take_synced_snapshot(
@@ -0,0 +1,302 @@
+//! Checking for and installing app updates, on the channel selected by the user.
+//!
+//! The heavy lifting is done by `tauri-plugin-updater`, which verifies the signature of each
+//! downloaded bundle against the public key in `tauri.conf.json` before installing it.
+//!
+//! Release manifests may carry two fields the plugin doesn't know about:
+//! - `rollout`, the percentage of installations a release is offered to by automatic checks.
+//!   Each installation draws its [rollout bucket](crate::settings::SettingsStore::rollout_bucket)
+//!   once, and checks the user asks for always see the release.
+//! - `delta`, with the `url` of a `zstd --patch-from` delta to the bundle of version `from`.
+//!   It's used instead of the full bundle if the bundle of the running version was kept when it
+//!   was installed, and the bundle it results in must match the signature of the full one.
+pub mod commands {
+    use std::{io::Read, path::PathBuf};
+
+    use anyhow::Context;
+    use base64::{prelude::BASE64_STANDARD, Engine};
+    use minisign_verify::{PublicKey, Signature};
+    use serde::{Deserialize, Serialize};
+    use tauri::{AppHandle, Emitter, Manager, State};
+    use tauri_plugin_updater::{Update, UpdaterExt};
+    use tracing::instrument;
+
+    use crate::{error::Error, settings::SettingsStore};
+
+    /// The release channels the app can be updated from.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub enum UpdateChannel {
+        Stable,
+        Nightly,
+    }
+
+    impl UpdateChannel {
+        /// The channel selected by the user, or the one the running build was released on.
+        fn from_settings(app: &AppHandle, store: &SettingsStore) -> Self {
+            match store.app_settings().update_channel.as_deref() {
+                Some("nightly") => Self::Nightly,
+                Some("stable") => Self::Stable,
+                _ => Self::of_build(&app.config().identifier),
+            }
+        }
+
+        /// Nightly builds are identified as such, and everything else follows the releases.
+        fn of_build(identifier: &str) -> Self {
+            if identifier.ends_with(".nightly") {
+                Self::Nightly
+            } else {
+                Self::Stable
+            }
+        }
+
+        fn endpoint(self) -> url::Url {
+            let channel = match self {
+                Self::Stable => "release",
+                Self::Nightly => "nightly",
+            };
+            // The placeholders are filled in by the updater.
+            format!(
+                "https://app.gitbutler.com/releases/{channel}/{}",
+                "{{target}}-{{arch}}/{{current_version}}"
+            )
+            .parse()
+            .expect("endpoint is a valid URL")
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct AvailableUpdate {
+        pub version: String,
+        /// The release date, if the release provides one.
+        pub date: Option<String>,
+        /// The release notes, typically markdown.
+        pub changelog: Option<String>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct UpdateStatus {
+        pub channel: UpdateChannel,
+        pub current_version: String,
+        /// The update that can be installed, or `None` if the app is up to date.
+        pub available: Option<AvailableUpdate>,
+    }
+
+    /// The steps of installing an update, sent as `updater://progress` events.
+    #[derive(Debug, Clone, Copy, Serialize)]
+    enum InstallProgress {
+        Downloading,
+        Downloaded,
+        Installing,
+    }
+
+    /// The delta to the bundle of an update, as listed in its release manifest.
+    #[derive(Debug, Deserialize)]
+    struct Delta {
+        /// The version whose bundle the delta applies to.
+        from: String,
+        url: url::Url,
+    }
+
+    /// Check for an update on the selected channel. Releases that are still rolled out to
+    /// other installations are only returned if the check is `manual`.
+    async fn check(
+        app: &AppHandle,
+        store: &SettingsStore,
+        manual: bool,
+    ) -> anyhow::Result<(UpdateChannel, Option<Update>)> {
+        let channel = UpdateChannel::from_settings(app, store);
+        let update = app
+            .updater_builder()
+            .endpoints(vec![channel.endpoint()])?
+            .build()?
+            .check()
+            .await
+            .with_context(|| format!("Failed to check for updates on the {channel:?} channel"))?;
+        let update = update.filter(|update| manual || store.rollout_bucket() < rollout(update));
+        Ok((channel, update))
+    }
+
+    /// The percentage of installations `update` is offered to, all of them by default.
+    fn rollout(update: &Update) -> u64 {
+        update
+            .raw_json
+            .get("rollout")
+            .and_then(|rollout| rollout.as_u64())
+            .map_or(100, |rollout| rollout.min(100))
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(app, store), err(Debug))]
+    pub async fn check_for_update(
+        app: AppHandle,
+        store: State<'_, SettingsStore>,
+        manual: Option<bool>,
+    ) -> Result<UpdateStatus, Error> {
+        let (channel, update) = check(&app, &store, manual.unwrap_or_default()).await?;
+        let available = update.map(|update| AvailableUpdate {
+            version: update.version,
+            date: update.date.map(|date| date.to_string()),
+            changelog: update.body,
+        });
+        Ok(UpdateStatus {
+            channel,
+            current_version: app.package_info().version.to_string(),
+            available,
+        })
+    }
+
+    /// Download and install the latest update of the selected channel, if there is one.
+    /// Returns the installed version, and the app must be restarted to use it.
+    #[tauri::command(async)]
+    #[instrument(skip(app, store), err(Debug))]
+    pub async fn install_update(
+        app: AppHandle,
+        store: State<'_, SettingsStore>,
+    ) -> Result<Option<String>, Error> {
+        let (_channel, Some(update)) = check(&app, &store, true).await? else {
+            return Ok(None);
+        };
+        report(&app, InstallProgress::Downloading);
+        let bundle = match download_delta(&app, &update).await {
+            Ok(Some(bundle)) => bundle,
+            Ok(None) => download(&update).await?,
+            Err(err) => {
+                tracing::warn!(
+                    "failed to update with a delta, downloading the full bundle: {err:#}"
+                );
+                download(&update).await?
+            }
+        };
+        report(&app, InstallProgress::Downloaded);
+        // Keep the bundle before installing it, as installing may exit the app.
+        if let Err(err) = keep_bundle(&app, &update.version, &bundle) {
+            tracing::warn!("failed to keep the update bundle for deltas: {err:#}");
+        }
+        report(&app, InstallProgress::Installing);
+        update
+            .install(&bundle)
+            .context("Failed to install update")?;
+        Ok(Some(update.version))
+    }
+
+    fn report(app: &AppHandle, progress: InstallProgress) {
+        if let Err(err) = app.emit("updater://progress", progress) {
+            tracing::warn!("failed to report update progress: {err}");
+        }
+    }
+
+    /// Download the full bundle of `update`, which verifies its signature.
+    async fn download(update: &Update) -> anyhow::Result<Vec<u8>> {
+        update
+            .download(|_chunk_len, _content_len| {}, || {})
+            .await
+            .context("Failed to download update")
+    }
+
+    /// Build the bundle of `update` from its delta and the kept bundle of the running version,
+    /// or return `None` if either is missing.
+    async fn download_delta(app: &AppHandle, update: &Update) -> anyhow::Result<Option<Vec<u8>>> {
+        let Some(delta) = update.raw_json.get("delta") else {
+            return Ok(None);
+        };
+        let delta: Delta = serde_json::from_value(delta.clone()).context("Invalid delta")?;
+        if delta.from != update.current_version {
+            return Ok(None);
+        }
+        let base = match std::fs::read(kept_bundles_dir(app)?.join(&delta.from)) {
+            Ok(base) => base,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let patch = reqwest::get(delta.url)
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        let bundle = apply_delta(&base, &patch)?;
+        verify(app, &bundle, &update.signature)?;
+        Ok(Some(bundle))
+    }
+
+    fn apply_delta(base: &[u8], delta: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut decoder = zstd::stream::read::Decoder::with_dictionary(delta, base)?;
+        // Deltas refer back across the whole base bundle, which is larger than the default window.
+        decoder.window_log_max(31)?;
+        let mut bundle = Vec::new();
+        decoder.read_to_end(&mut bundle)?;
+        Ok(bundle)
+    }
+
+    /// Check `bundle` against the `signature` of the full bundle, like the updater does.
+    fn verify(app: &AppHandle, bundle: &[u8], signature: &str) -> anyhow::Result<()> {
+        let pubkey = app
+            .config()
+            .plugins
+            .0
+            .get("updater")
+            .and_then(|updater| updater.get("pubkey"))
+            .and_then(|pubkey| pubkey.as_str())
+            .context("The updater has no public key")?;
+        let public_key = PublicKey::decode(&decode_base64(pubkey)?)?;
+        let signature = Signature::decode(&decode_base64(signature)?)?;
+        public_key
+            .verify(bundle, &signature, true)
+            .context("The bundle built from the delta doesn't match the signature")?;
+        Ok(())
+    }
+
+    fn decode_base64(value: &str) -> anyhow::Result<String> {
+        Ok(String::from_utf8(BASE64_STANDARD.decode(value)?)?)
+    }
+
+    fn kept_bundles_dir(app: &AppHandle) -> anyhow::Result<PathBuf> {
+        Ok(app.path().app_cache_dir()?.join("updates"))
+    }
+
+    /// Keep `bundle` as the only one to apply deltas to, as it's the next running version.
+    fn keep_bundle(app: &AppHandle, version: &str, bundle: &[u8]) -> anyhow::Result<()> {
+        let dir = kept_bundles_dir(app)?;
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join(version), bundle)?;
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn channel_follows_the_build() {
+            assert_eq!(
+                UpdateChannel::of_build("com.gitbutler.app.nightly"),
+                UpdateChannel::Nightly
+            );
+            assert_eq!(
+                UpdateChannel::of_build("com.gitbutler.app"),
+                UpdateChannel::Stable
+            );
+            assert_eq!(
+                UpdateChannel::of_build("com.gitbutler.app.dev"),
+                UpdateChannel::Stable
+            );
+        }
+
+        #[test]
+        fn deltas_rebuild_the_bundle() -> anyhow::Result<()> {
+            let base = b"the bundle of the running version".repeat(100);
+            let mut bundle = base.clone();
+            bundle.extend_from_slice(b"and what the next version adds");
+            let delta = zstd::bulk::Compressor::with_dictionary(19, &base)?.compress(&bundle)?;
+            assert!(delta.len() < bundle.len() / 10);
+
+            assert_eq!(apply_delta(&base, &delta)?, bundle);
+            Ok(())
+        }
+    }
+}
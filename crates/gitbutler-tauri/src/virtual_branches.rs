@@ -1,13 +1,24 @@
 pub mod commands {
     use anyhow::{anyhow, Context};
     use gitbutler_branch::{BranchCreateRequest, BranchUpdateRequest};
+    use gitbutler_branch_actions::attachments::{AttachmentKind, CommitAttachment};
+    use gitbutler_branch_actions::autostash::AutoStashConflicts;
+    use gitbutler_branch_actions::co_authors::{self, CoAuthor};
+    use gitbutler_branch_actions::dependencies::{
+        CrossStackConflict, DependencyTimings, LineOwner, Operation, OperationPrediction,
+        PathError, RemovalPreview, TransitiveDependencies,
+    };
+    use gitbutler_branch_actions::insights::Contributors;
     use gitbutler_branch_actions::internal::PushResult;
+    use gitbutler_branch_actions::policy::{CommitMessageViolation, PolicyReport};
+    use gitbutler_branch_actions::touches::CommitTouch;
     use gitbutler_branch_actions::upstream_integration::{
         BaseBranchResolution, BaseBranchResolutionApproach, BranchStatuses, Resolution,
     };
     use gitbutler_branch_actions::{
-        BaseBranch, BranchListing, BranchListingDetails, BranchListingFilter, RemoteBranch,
-        RemoteBranchData, RemoteBranchFile, RemoteCommit, StackOrder, VirtualBranches,
+        AbsorbReport, ApplyPatchReport, BaseBranch, BranchListing, BranchListingDetails,
+        BranchListingFilter, CommitSignature, RemoteBranch, RemoteBranchData, RemoteBranchFile,
+        RemoteCommit, RevertOutcome, Shelf, SplitAssignment, StackOrder, VirtualBranches,
     };
     use gitbutler_command_context::CommandContext;
     use gitbutler_project as projects;
@@ -15,10 +26,14 @@ pub mod commands {
     use gitbutler_reference::{normalize_branch_name as normalize_name, Refname, RemoteRefname};
     use gitbutler_stack::{BranchOwnershipClaims, ForgeIdentifier, StackId};
     use std::path::PathBuf;
-    use tauri::State;
+    use tauri::{ipc::Channel, State};
     use tracing::instrument;
 
-    use crate::{error::Error, WindowState};
+    use crate::{
+        chunked::{self, Chunk},
+        error::Error,
+        WindowState,
+    };
 
     #[tauri::command(async)]
     #[instrument(err(Debug))]
@@ -36,6 +51,7 @@ pub mod commands {
         message: &str,
         ownership: Option<BranchOwnershipClaims>,
         run_hooks: bool,
+        co_authors: Option<Vec<CoAuthor>>,
     ) -> Result<String, Error> {
         let project = projects.get(project_id)?;
         let oid = gitbutler_branch_actions::create_commit(
@@ -44,6 +60,34 @@ pub mod commands {
             message,
             ownership.as_ref(),
             run_hooks,
+            &co_authors.unwrap_or_default(),
+        )?;
+        emit_vbranches(&windows, project_id);
+        Ok(oid.to_string())
+    }
+
+    /// Commit only the hunks claimed by `claims`, leaving the other changes of the branch in the
+    /// worktree.
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn commit_hunks(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch: StackId,
+        message: &str,
+        claims: BranchOwnershipClaims,
+        run_hooks: bool,
+        co_authors: Option<Vec<CoAuthor>>,
+    ) -> Result<String, Error> {
+        let project = projects.get(project_id)?;
+        let oid = gitbutler_branch_actions::create_commit_from_hunks(
+            &project,
+            branch,
+            message,
+            &claims,
+            run_hooks,
+            &co_authors.unwrap_or_default(),
         )?;
         emit_vbranches(&windows, project_id);
         Ok(oid.to_string())
@@ -271,12 +315,14 @@ pub mod commands {
         project_id: ProjectId,
         branch_id: StackId,
         with_force: bool,
+        allow_draft: Option<bool>,
     ) -> Result<PushResult, Error> {
         let project = projects.get(project_id)?;
         let upstream_refname = gitbutler_branch_actions::push_virtual_branch(
             &project,
             branch_id,
             with_force,
+            allow_draft.unwrap_or_default(),
             Some(Some(branch_id)),
         )?;
         emit_vbranches(&windows, project_id);
@@ -308,6 +354,22 @@ pub mod commands {
         gitbutler_branch_actions::list_commit_files(&project, commit_oid).map_err(Into::into)
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn verify_commit_signatures(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        commit_oids: Vec<String>,
+    ) -> Result<Vec<CommitSignature>, Error> {
+        let project = projects.get(project_id)?;
+        let commit_oids = commit_oids
+            .iter()
+            .map(|oid| git2::Oid::from_str(oid).map_err(|e| anyhow!(e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        gitbutler_branch_actions::verify_commit_signatures(&project, &commit_oids)
+            .map_err(Into::into)
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects, windows), err(Debug))]
     pub fn reset_virtual_branch(
@@ -333,14 +395,131 @@ pub mod commands {
         branch_id: StackId,
         commit_oid: String,
         ownership: BranchOwnershipClaims,
+        co_authors: Option<Vec<CoAuthor>>,
     ) -> Result<String, Error> {
         let project = projects.get(project_id)?;
         let commit_oid = git2::Oid::from_str(&commit_oid).map_err(|e| anyhow!(e))?;
-        let oid = gitbutler_branch_actions::amend(&project, branch_id, commit_oid, &ownership)?;
+        let oid = gitbutler_branch_actions::amend(
+            &project,
+            branch_id,
+            commit_oid,
+            &ownership,
+            &co_authors.unwrap_or_default(),
+        )?;
         emit_vbranches(&windows, project_id);
         Ok(oid.to_string())
     }
 
+    /// The co-authors used most often in the project, to offer when creating a commit.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn list_frequent_co_authors(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<Vec<CoAuthor>, Error> {
+        let project = projects.get(project_id)?;
+        Ok(co_authors::frequent(&project)?)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn absorb(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        stack_id: StackId,
+    ) -> Result<AbsorbReport, Error> {
+        let project = projects.get(project_id)?;
+        let report = gitbutler_branch_actions::absorb(&project, stack_id)?;
+        emit_vbranches(&windows, project_id);
+        Ok(report)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows, patch), err(Debug))]
+    pub fn apply_patch(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        stack_id: StackId,
+        patch: Vec<u8>,
+    ) -> Result<ApplyPatchReport, Error> {
+        let project = projects.get(project_id)?;
+        let report = gitbutler_branch_actions::apply_patch(&project, stack_id, &patch)?;
+        emit_vbranches(&windows, project_id);
+        Ok(report)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn format_patch(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        stack_id: StackId,
+        dir: PathBuf,
+        format: PatchFormat,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let project = projects.get(project_id)?;
+        Ok(gitbutler_branch_actions::format_patch(
+            &project, stack_id, &dir, format,
+        )?)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn shelve_changes(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        stack_id: StackId,
+        name: String,
+    ) -> Result<Shelf, Error> {
+        let project = projects.get(project_id)?;
+        let shelf = gitbutler_branch_actions::shelve_changes(&project, stack_id, &name)?;
+        emit_vbranches(&windows, project_id);
+        Ok(shelf)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn unshelve_changes(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        stack_id: StackId,
+        name: String,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        gitbutler_branch_actions::unshelve_changes(&project, stack_id, &name)?;
+        emit_vbranches(&windows, project_id);
+        Ok(())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn list_shelves(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        stack_id: StackId,
+    ) -> Result<Vec<Shelf>, Error> {
+        let project = projects.get(project_id)?;
+        Ok(gitbutler_branch_actions::list_shelves(&project, stack_id)?)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn delete_shelf(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        stack_id: StackId,
+        name: String,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        Ok(gitbutler_branch_actions::delete_shelf(
+            &project, stack_id, &name,
+        )?)
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects, windows), err(Debug))]
     pub fn move_commit_file(
@@ -366,6 +545,31 @@ pub mod commands {
         Ok(oid.to_string())
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn move_commit_hunks(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        stack_id: StackId,
+        from_commit_oid: String,
+        to_commit_oid: String,
+        ownership: BranchOwnershipClaims,
+    ) -> Result<String, Error> {
+        let project = projects.get(project_id)?;
+        let from_commit_oid = git2::Oid::from_str(&from_commit_oid).map_err(|e| anyhow!(e))?;
+        let to_commit_oid = git2::Oid::from_str(&to_commit_oid).map_err(|e| anyhow!(e))?;
+        let oid = gitbutler_branch_actions::move_commit_hunks(
+            &project,
+            stack_id,
+            from_commit_oid,
+            to_commit_oid,
+            &ownership,
+        )?;
+        emit_vbranches(&windows, project_id);
+        Ok(oid.to_string())
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects, windows), err(Debug))]
     pub fn undo_commit(
@@ -399,6 +603,48 @@ pub mod commands {
         Ok(())
     }
 
+    /// Split the commit with `commit_oid` into one commit per assignment, and return the IDs of
+    /// the commits it was split into.
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn split_commit(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        stack_id: StackId,
+        commit_oid: String,
+        assignments: Vec<SplitAssignment>,
+    ) -> Result<Vec<String>, Error> {
+        let project = projects.get(project_id)?;
+        let commit_oid = git2::Oid::from_str(&commit_oid).map_err(|e| anyhow!(e))?;
+        let commit_ids =
+            gitbutler_branch_actions::split_commit(&project, stack_id, commit_oid, &assignments)?;
+        emit_vbranches(&windows, project_id);
+        Ok(commit_ids.iter().map(ToString::to_string).collect())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn revert_commit(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        stack_id: StackId,
+        commit_oid: String,
+        drop_if_independent: bool,
+    ) -> Result<RevertOutcome, Error> {
+        let project = projects.get(project_id)?;
+        let commit_oid = git2::Oid::from_str(&commit_oid).map_err(|e| anyhow!(e))?;
+        let outcome = gitbutler_branch_actions::revert_commit(
+            &project,
+            stack_id,
+            commit_oid,
+            drop_if_independent,
+        )?;
+        emit_vbranches(&windows, project_id);
+        Ok(outcome)
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects, windows), err(Debug))]
     pub fn reorder_stack(
@@ -437,6 +683,123 @@ pub mod commands {
         Ok(branches)
     }
 
+    /// Return how the commits of the workspace and its uncommitted changes depend on each other, as
+    /// a graph in the DOT language if `dot` is set, or as the serialized graph otherwise.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn hunk_dependency_graph(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        dot: Option<bool>,
+    ) -> Result<serde_json::Value, Error> {
+        let graph = gitbutler_branch_actions::hunk_dependency_graph(&projects.get(project_id)?)?;
+        if dot.unwrap_or(false) {
+            Ok(graph.to_dot().into())
+        } else {
+            Ok(serde_json::to_value(graph).context("failed to serialize dependency graph")?)
+        }
+    }
+
+    /// Return the commits that depend on the commit with `commit_oid`, and those it depends on,
+    /// directly or through other commits.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn commit_dependencies(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        commit_oid: String,
+    ) -> Result<TransitiveDependencies, Error> {
+        let project = projects.get(project_id)?;
+        let commit_oid = git2::Oid::from_str(&commit_oid).map_err(|e| anyhow!(e))?;
+        Ok(gitbutler_branch_actions::commit_dependencies(
+            &project, commit_oid,
+        )?)
+    }
+
+    /// Return what would conflict if the commit with `commit_oid` was removed, before dropping or
+    /// uncommitting it.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn remove_commit_preview(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        commit_oid: String,
+    ) -> Result<RemovalPreview, Error> {
+        let project = projects.get(project_id)?;
+        let commit_oid = git2::Oid::from_str(&commit_oid).map_err(|e| anyhow!(e))?;
+        Ok(gitbutler_branch_actions::remove_commit_preview(
+            &project, commit_oid,
+        )?)
+    }
+
+    /// Predict whether amending, swapping or squashing commits would conflict, and which lines
+    /// would, to disable the action before it's attempted.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn predict_operation(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        operation: Operation,
+    ) -> Result<OperationPrediction, Error> {
+        let project = projects.get(project_id)?;
+        gitbutler_branch_actions::predict_operation(&project, &operation).map_err(Into::into)
+    }
+
+    /// Return the uncommitted hunks that intersect commits of more than one stack, and so can't be
+    /// moved to any single one of them.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn cross_stack_conflicts(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<Vec<CrossStackConflict>, Error> {
+        Ok(gitbutler_branch_actions::cross_stack_conflicts(
+            &projects.get(project_id)?,
+        )?)
+    }
+
+    /// Return the paths whose hunk ranges couldn't be computed, and which are skipped when looking
+    /// up the dependencies and locks of changes.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn hunk_range_errors(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<Vec<PathError>, Error> {
+        Ok(gitbutler_branch_actions::hunk_range_errors(
+            &projects.get(project_id)?,
+        )?)
+    }
+
+    /// Compute the dependencies of the workspace without using cached ranges, and return how long
+    /// each step took.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn dependency_timings(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<DependencyTimings, Error> {
+        Ok(gitbutler_branch_actions::dependency_timings(
+            &projects.get(project_id)?,
+        )?)
+    }
+
+    /// Like [`list_branches`], but sends the listings over `channel` in chunks, as there can be
+    /// thousands of them.
+    #[tauri::command(async)]
+    #[instrument(skip(projects, channel), err(Debug))]
+    pub fn list_branches_chunked(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        filter: Option<BranchListingFilter>,
+        channel: Channel<Chunk<BranchListing>>,
+    ) -> Result<(), Error> {
+        let ctx = CommandContext::open(&projects.get(project_id)?)?;
+        let branches = gitbutler_branch_actions::list_branches(&ctx, filter, None)?;
+        chunked::send(&channel, branches)?;
+        Ok(())
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects), err(Debug))]
     pub fn get_branch_listing_details(
@@ -477,6 +840,50 @@ pub mod commands {
         Ok(())
     }
 
+    /// Fold the `fixup!`, `squash!` and `amend!` commits of a stack into the commits they target,
+    /// and return how many were folded.
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn squash_commits(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        stack_id: StackId,
+        target_commit_oid: String,
+        commit_oids: Vec<String>,
+        message_template: Option<String>,
+    ) -> Result<String, Error> {
+        let project = projects.get(project_id)?;
+        let target_commit_oid = git2::Oid::from_str(&target_commit_oid).map_err(|e| anyhow!(e))?;
+        let commit_oids = commit_oids
+            .iter()
+            .map(|oid| git2::Oid::from_str(oid).map_err(|e| anyhow!(e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let squashed_oid = gitbutler_branch_actions::squash_commits(
+            &project,
+            stack_id,
+            target_commit_oid,
+            &commit_oids,
+            message_template.as_deref(),
+        )?;
+        emit_vbranches(&windows, project_id);
+        Ok(squashed_oid.to_string())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn autosquash(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        stack_id: StackId,
+    ) -> Result<usize, Error> {
+        let project = projects.get(project_id)?;
+        let folded = gitbutler_branch_actions::autosquash(&project, stack_id)?;
+        emit_vbranches(&windows, project_id);
+        Ok(folded)
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects, windows), err(Debug))]
     pub fn fetch_from_remotes(
@@ -534,6 +941,31 @@ pub mod commands {
         Ok(())
     }
 
+    /// Copy the commits with `commit_oids`, in application order, to the top of the stack with
+    /// `target_stack_id`, and return the IDs of the copies.
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn copy_commits_to_stack(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        target_stack_id: StackId,
+        commit_oids: Vec<String>,
+    ) -> Result<Vec<String>, Error> {
+        let project = projects.get(project_id)?;
+        let commit_oids = commit_oids
+            .iter()
+            .map(|oid| git2::Oid::from_str(oid).map_err(|e| anyhow!(e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let copies = gitbutler_branch_actions::copy_commits_to_stack(
+            &project,
+            target_stack_id,
+            &commit_oids,
+        )?;
+        emit_vbranches(&windows, project_id);
+        Ok(copies.iter().map(ToString::to_string).collect())
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects, windows), err(Debug))]
     pub fn update_commit_message(
@@ -551,6 +983,134 @@ pub mod commands {
         Ok(())
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn get_policy_report(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<PolicyReport, Error> {
+        let project = projects.get(project_id)?;
+        gitbutler_branch_actions::policy_report(&project).map_err(Into::into)
+    }
+
+    /// Check a commit message against the commit message rules of the policy while it's written.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn validate_commit_message(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        message: &str,
+    ) -> Result<Vec<CommitMessageViolation>, Error> {
+        let project = projects.get(project_id)?;
+        gitbutler_branch_actions::validate_commit_message(&project, message).map_err(Into::into)
+    }
+
+    /// Describe the branch with `branch_id` in plain language, e.g. for screen readers.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn get_branch_summary(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch_id: StackId,
+    ) -> Result<String, Error> {
+        let project = projects.get(project_id)?;
+        gitbutler_branch_actions::branch_summary(&project, branch_id).map_err(Into::into)
+    }
+
+    /// Describe the commit with `commit_oid` in plain language, e.g. for screen readers.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn get_commit_summary(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        commit_oid: String,
+    ) -> Result<String, Error> {
+        let project = projects.get(project_id)?;
+        let commit_oid = git2::Oid::from_str(&commit_oid).map_err(|e| anyhow!(e))?;
+        gitbutler_branch_actions::commit_summary(&project, commit_oid).map_err(Into::into)
+    }
+
+    /// Compute the statistics of the authors of the workspace and the target, optionally only of commits
+    /// made at or after `since`, in seconds since the Unix epoch.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn get_contributors(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        since: Option<i64>,
+    ) -> Result<Contributors, Error> {
+        let project = projects.get(project_id)?;
+        gitbutler_branch_actions::contributors(&project, since).map_err(Into::into)
+    }
+
+    /// List the commits in the workspace and the recent history of the target which changed `path`.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn list_commits_touching(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        path: PathBuf,
+    ) -> Result<Vec<CommitTouch>, Error> {
+        let project = projects.get(project_id)?;
+        gitbutler_branch_actions::list_commits_touching(&project, &path).map_err(Into::into)
+    }
+
+    /// Return the stack and commit each line of the file at `path` in the worktree comes from, or
+    /// whether it's unchanged or uncommitted, to show it next to the lines.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn line_owners(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        path: PathBuf,
+    ) -> Result<Vec<LineOwner>, Error> {
+        let project = projects.get(project_id)?;
+        gitbutler_branch_actions::line_owners(&project, &path).map_err(Into::into)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn list_commit_attachments(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        commit_oid: String,
+    ) -> Result<Vec<CommitAttachment>, Error> {
+        let project = projects.get(project_id)?;
+        let commit_oid = git2::Oid::from_str(&commit_oid).map_err(|e| anyhow!(e))?;
+        gitbutler_branch_actions::list_commit_attachments(&project, commit_oid).map_err(Into::into)
+    }
+
+    /// Attach `url` to the commit with `commit_oid`, e.g. to link the CI run or the review of it.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn add_commit_attachment(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        commit_oid: String,
+        kind: AttachmentKind,
+        url: &str,
+        title: Option<String>,
+    ) -> Result<CommitAttachment, Error> {
+        let project = projects.get(project_id)?;
+        let commit_oid = git2::Oid::from_str(&commit_oid).map_err(|e| anyhow!(e))?;
+        gitbutler_branch_actions::add_commit_attachment(&project, commit_oid, kind, url, title)
+            .map_err(Into::into)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn remove_commit_attachment(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        commit_oid: String,
+        url: &str,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        let commit_oid = git2::Oid::from_str(&commit_oid).map_err(|e| anyhow!(e))?;
+        gitbutler_branch_actions::remove_commit_attachment(&project, commit_oid, url)
+            .map_err(Into::into)
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects), err(Debug))]
     pub fn find_commit(
@@ -600,6 +1160,28 @@ pub mod commands {
         Ok(())
     }
 
+    /// The paths whose unassigned changes conflicted when they were reapplied after the last operation
+    /// that rewrote the worktree, if they weren't dismissed yet.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn autostash_conflicts(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<Option<AutoStashConflicts>, Error> {
+        let project = projects.get(project_id)?;
+        gitbutler_branch_actions::autostash_conflicts(&project).map_err(Into::into)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn dismiss_autostash_conflicts(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        gitbutler_branch_actions::dismiss_autostash_conflicts(&project).map_err(Into::into)
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects), err(Debug))]
     pub fn resolve_upstream_integration(
@@ -57,6 +57,13 @@ pub(super) mod state {
                         payload: serde_json::json!(files),
                         project_id,
                     },
+                    Change::ConfigFiles { project_id, files } => ChangeForFrontend {
+                        name: format!("project://{}/config", project_id),
+                        payload: serde_json::json!({
+                            "files": files.iter().map(ToString::to_string).collect::<Vec<_>>()
+                        }),
+                        project_id,
+                    },
                 }
             }
         }
@@ -42,6 +42,16 @@ impl TestingRepository {
         self.commit_tree_with_message(parent, &Uuid::new_v4().to_string(), files)
     }
 
+    /// Commit `files` with `message` on top of `parent`.
+    pub fn commit_on<'a>(
+        &'a self,
+        parent: &git2::Commit<'a>,
+        message: &str,
+        files: &[(&str, &str)],
+    ) -> git2::Commit<'a> {
+        self.commit_tree_with_message(Some(parent), message, files)
+    }
+
     pub fn commit_tree_with_message<'a>(
         &'a self,
         parent: Option<&git2::Commit<'a>>,
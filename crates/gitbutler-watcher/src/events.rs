@@ -1,4 +1,7 @@
-use std::{fmt::Display, path::PathBuf};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
 use gitbutler_branch_actions::{RemoteBranchFile, VirtualBranches};
 use gitbutler_operating_modes::OperatingMode;
@@ -105,4 +108,60 @@ pub enum Change {
         project_id: ProjectId,
         files: Vec<RemoteBranchFile>,
     },
+    /// Configuration was changed outside of the application, and what depends on it should be reloaded.
+    ConfigFiles {
+        project_id: ProjectId,
+        files: Vec<ConfigFile>,
+    },
+}
+
+/// A kind of file that configures how the repository is handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConfigFile {
+    /// `.git/config`, which has the remotes, among others.
+    GitConfig,
+    /// A `.gitignore` file or `.git/info/exclude`, which decide which files are ignored.
+    Excludes,
+    /// A `.gitattributes` file or `.git/info/attributes`, which configure filters and diffs.
+    Attributes,
+    /// A file in `.gitbutler/`, the GitButler settings committed with the repository.
+    GitButler,
+}
+
+impl Display for ConfigFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ConfigFile::GitConfig => "gitConfig",
+            ConfigFile::Excludes => "excludes",
+            ConfigFile::Attributes => "attributes",
+            ConfigFile::GitButler => "gitButler",
+        })
+    }
+}
+
+impl ConfigFile {
+    /// Classify `path` relative to the worktree, or return `None` if it doesn't configure anything.
+    pub fn from_worktree_path(path: &Path) -> Option<Self> {
+        if path.starts_with(".gitbutler") {
+            return Some(ConfigFile::GitButler);
+        }
+        match path.file_name()?.to_str()? {
+            ".gitignore" => Some(ConfigFile::Excludes),
+            ".gitattributes" => Some(ConfigFile::Attributes),
+            _ => None,
+        }
+    }
+
+    /// Classify `path` relative to the `.git` directory, or return `None` if it doesn't configure anything.
+    pub fn from_git_path(path: &Path) -> Option<Self> {
+        if path == Path::new("config") {
+            Some(ConfigFile::GitConfig)
+        } else if path == Path::new("info/exclude") {
+            Some(ConfigFile::Excludes)
+        } else if path == Path::new("info/attributes") {
+            Some(ConfigFile::Attributes)
+        } else {
+            None
+        }
+    }
 }
@@ -272,6 +272,9 @@ fn classify_file(git_dir: &Path, file_path: &Path) -> FileKind {
             || check_file_path == Path::new("HEAD")
             || check_file_path == Path::new("GB_FLUSH")
             || check_file_path == Path::new("index")
+            || check_file_path == Path::new("config")
+            || check_file_path == Path::new("info/exclude")
+            || check_file_path == Path::new("info/attributes")
         {
             FileKind::Git
         } else if check_file_path == Path::new("gitbutler").join(OPLOG_FILE_NAME) {
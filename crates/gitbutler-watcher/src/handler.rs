@@ -1,6 +1,6 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::BTreeSet, path::PathBuf, sync::Arc};
 
-use super::{events, Change};
+use super::{events, Change, ConfigFile};
 use anyhow::{Context, Result};
 use gitbutler_branch_actions::VirtualBranches;
 use gitbutler_command_context::CommandContext;
@@ -135,7 +135,24 @@ impl Handler {
             self.calculate_virtual_branches(project_id, worktree_changes)?;
         }
 
-        Ok(())
+        self.emit_config_files(
+            project_id,
+            paths
+                .iter()
+                .filter_map(|path| ConfigFile::from_worktree_path(path))
+                .collect(),
+        )
+    }
+
+    /// Tell about the configuration `files` that changed, if there are any.
+    fn emit_config_files(&self, project_id: ProjectId, files: BTreeSet<ConfigFile>) -> Result<()> {
+        if files.is_empty() {
+            return Ok(());
+        }
+        self.emit_app_event(Change::ConfigFiles {
+            project_id,
+            files: files.into_iter().collect(),
+        })
     }
 
     /// Try to emit uncommited files. Swollow errors if they arrise.
@@ -177,6 +194,18 @@ impl Handler {
             .get(project_id)
             .context("failed to get project")?;
 
+        let config_files: BTreeSet<_> = paths
+            .iter()
+            .filter_map(|path| ConfigFile::from_git_path(path))
+            .collect();
+        if config_files.contains(&ConfigFile::Excludes)
+            || config_files.contains(&ConfigFile::Attributes)
+        {
+            // Which files are ignored, and how they are filtered, decide what the uncommitted changes are.
+            self.recalculate_everything(Vec::new(), project_id)?;
+        }
+        self.emit_config_files(project_id, config_files)?;
+
         for path in paths {
             let Some(file_name) = path.to_str() else {
                 continue;
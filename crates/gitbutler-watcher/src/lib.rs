@@ -7,7 +7,7 @@ use std::path::Path;
 
 use anyhow::{Context, Result};
 use events::InternalEvent;
-pub use events::{Action, Change};
+pub use events::{Action, Change, ConfigFile};
 use gitbutler_project::ProjectId;
 pub use handler::Handler;
 use tokio::{